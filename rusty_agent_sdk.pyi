@@ -44,6 +44,15 @@ Typical usage::
         response_format={"type": "json_object"},
     )
 
+    # Vision (multimodal content parts)
+    response = provider.generate_text(messages=[{
+        "role": "user",
+        "content": [
+            {"type": "text", "text": "What is in this image?"},
+            {"type": "image_url", "image_url": {"url": "https://..."}},
+        ],
+    }])
+
     # Usage tracking
     result = provider.generate_text("Hello!", include_usage=True)
     print(result.text)
@@ -58,9 +67,328 @@ Typical usage::
 
 from __future__ import annotations
 
-from typing import Any, Literal, overload
+import os
+from collections.abc import Awaitable, Callable, Sequence
+from typing import Any, Generic, Literal, TypeVar, overload
+
+_ChunkT = TypeVar("_ChunkT", str, "StreamChunk")
+
+__all__ = [
+    "Provider",
+    "TextStream",
+    "AsyncTextStream",
+    "StreamChunk",
+    "StreamBranch",
+    "ObjectStream",
+    "GenerateResult",
+    "ModelInfo",
+    "Message",
+    "SystemMessage",
+    "UserMessage",
+    "AssistantMessage",
+    "image_part",
+    "file_part",
+    "json_schema",
+    "trim_messages",
+    "Chat",
+    "messages_to_json",
+    "messages_from_json",
+    "ContextLengthExceededError",
+    "ContentFilterError",
+    "CircuitOpenError",
+]
+
+__user_agent__: str
+"""The ``User-Agent`` sent with every request, e.g.
+``"rusty-agent-sdk/0.1.0 (python)"``. Override it with a ``User-Agent``
+entry in ``default_headers``."""
+
+def messages_to_json(messages: list[dict[str, Any] | Message]) -> str:
+    """Serialise messages to a stable JSON format for storage.
+
+    The output is ``{"version": 1, "messages": [...]}``, where each message
+    keeps its ``name``, ``tool_call_id``, and content parts.
+    """
+    ...
+
+def messages_from_json(data: str) -> list[dict[str, Any]]:
+    """Parse messages written by :func:`messages_to_json`.
+
+    A bare JSON list of message dicts is also accepted.
+
+    Raises:
+        ValueError: If ``data`` is not valid chat history JSON.
+    """
+    ...
+
+def trim_messages(
+    messages: list[dict[str, Any] | Message],
+    max_tokens: int,
+    keep_system: bool = True,
+    strategy: Literal["drop_oldest"] = "drop_oldest",
+) -> tuple[list[dict[str, Any]], int]:
+    """Trim a conversation to fit an approximate token budget.
+
+    Whole messages are dropped from the oldest end until the estimated
+    token count fits ``max_tokens``. ``system`` messages (when
+    ``keep_system``) and the latest user message are always kept, so the
+    result may still exceed the budget if they alone do.
+
+    Token counts use a chars/4 heuristic plus a small per-message overhead;
+    leave some headroom below the model's real context window.
+
+    Returns:
+        The trimmed messages as dicts and their estimated token count.
+
+    Raises:
+        ValueError: If a message is malformed or ``strategy`` is unknown.
+    """
+    ...
+
+def normalize(vectors: Sequence[Sequence[float]]) -> list[list[float]]:
+    """Scale vectors to unit length (L2 norm 1.0), e.g. embeddings from
+    another library before comparing them by cosine similarity.
+
+    Zero vectors, and vectors holding ``inf`` or ``nan``, have no direction
+    and are returned unchanged.
+
+    Raises:
+        TypeError: If ``vectors`` is not a sequence of number sequences.
+    """
+    ...
+
+def cosine_similarity(a: Sequence[float], b: Sequence[float]) -> float:
+    """The cosine similarity of two vectors, from ``-1.0`` (opposite) to
+    ``1.0`` (same direction), or ``0.0`` if either is a zero vector.
+
+    Raises:
+        ValueError: If the vectors differ in length.
+    """
+    ...
+
+def similarity_search(
+    query: Sequence[float],
+    matrix: Sequence[Sequence[float]],
+    top_k: int = 5,
+    metric: Literal["cosine", "dot", "euclidean"] = "cosine",
+) -> list[tuple[int, float]]:
+    """Find the rows of ``matrix`` most similar to ``query``.
+
+    Returns ``(row index, score)`` pairs, best match first. ``"cosine"`` and
+    ``"dot"`` rank the highest score first; ``"euclidean"`` ranks the
+    nearest row first and returns the distance.
+
+    Raises:
+        ValueError: If ``metric`` is unknown, or the rows of ``matrix``
+            differ in length from each other or from ``query``.
+    """
+    ...
+
+def similarity_search_many(
+    queries: Sequence[Sequence[float]],
+    matrix: Sequence[Sequence[float]],
+    top_k: int = 5,
+    metric: Literal["cosine", "dot", "euclidean"] = "cosine",
+) -> list[list[tuple[int, float]]]:
+    """:func:`similarity_search` for several queries against one matrix,
+    returning one result list per query."""
+    ...
+
+def json_schema(
+    name: str, schema: dict[str, Any], strict: bool = True
+) -> dict[str, Any]:
+    """Build a ``response_format`` for structured outputs.
+
+    Wraps a JSON Schema in the ``{"type": "json_schema", "json_schema": ...}``
+    envelope expected by OpenAI-compatible APIs.
+
+    Example::
+
+        fmt = json_schema("person", {
+            "type": "object",
+            "properties": {"name": {"type": "string"}},
+            "required": ["name"],
+            "additionalProperties": False,
+        })
+        provider.generate_text("Invent a person.", response_format=fmt)
+
+    Args:
+        name: Schema name (letters, digits, ``_`` or ``-``; max 64).
+        schema: JSON Schema with a ``type`` key.
+        strict: Ask the model to follow the schema exactly.
+
+    Raises:
+        ValueError: If ``name`` or ``schema`` is invalid.
+    """
+    ...
+
+def image_part(
+    path_or_bytes: str | os.PathLike[str] | bytes,
+    detail: Literal["low", "high", "auto"] | None = None,
+    *,
+    max_bytes: int = 20971520,
+) -> dict[str, Any]:
+    """Build an image content part from a local file or raw bytes.
+
+    The format (PNG, JPEG, WebP, or GIF) is detected from the magic bytes
+    and the data is embedded as a base64 data URL.
+
+    Args:
+        path_or_bytes: Image file path or raw image data.
+        detail: Optional detail level for vision models.
+        max_bytes: Maximum accepted size. Defaults to 20 MB.
+
+    Returns:
+        ``{"type": "image_url", "image_url": {"url": "data:...", ...}}``,
+        ready to use as an entry in a message's ``content`` list.
+
+    Raises:
+        ValueError: If the file cannot be read, exceeds ``max_bytes``, or
+            is not a supported image format.
+    """
+    ...
+
+def file_part(
+    path_or_bytes: str | os.PathLike[str] | bytes,
+    filename: str | None = None,
+    *,
+    max_bytes: int = 20971520,
+) -> dict[str, Any]:
+    """Build a file content part (e.g. a PDF) from a local file or raw bytes.
+
+    The data is embedded as a base64 data URL for document-understanding
+    models such as gpt-4o or Gemini.
+
+    Args:
+        path_or_bytes: File path or raw file data.
+        filename: Name reported to the model. Defaults to the file's name,
+            or ``"document.pdf"``/``"file"`` for raw bytes.
+        max_bytes: Maximum accepted size. Defaults to 20 MB.
+
+    Returns:
+        ``{"type": "file", "file": {"filename": ..., "file_data": "data:..."}}``,
+        ready to use as an entry in a message's ``content`` list.
+
+    Raises:
+        ValueError: If the file cannot be read or exceeds ``max_bytes``.
+    """
+    ...
+
+class ContextLengthExceededError(RuntimeError):
+    """Raised when a request does not fit the model's context window.
+
+    Detected from the ``context_length_exceeded`` error code or the
+    provider's wording, including Anthropic's ``prompt is too long``. Catch
+    it to trim the history and retry::
+
+        try:
+            reply = provider.generate_text(messages=history)
+        except ContextLengthExceededError as e:
+            budget = (e.max_context_tokens or 8192) - 1024  # leave room to reply
+            history, _ = trim_messages(history, budget)
+    """
+
+    max_context_tokens: int | None
+    """The model's context window in tokens, if the message states it."""
+    requested_tokens: int | None
+    """Tokens the request needed, if the message states them."""
+    status_code: int
+    code: str | None
+    error_type: str | None
+    body: str
+    request_id: str | None
+    idempotency_key: str | None
+
+class CircuitOpenError(ConnectionError):
+    """Raised without sending a request while the provider's circuit
+    breaker is open (see ``circuit_breaker_threshold``), or while another
+    call is probing it after the cool-down.
+    """
+
+    remaining: float
+    """Seconds until the breaker lets a probe call through; ``0.0`` while
+    a probe is in flight."""
+
+class ContentFilterError(RuntimeError):
+    """Raised with ``raise_on_content_filter=True`` when the provider's
+    content filter stopped the response (``finish_reason`` of
+    ``"content_filter"``, which Anthropic refusals map to) or the model
+    refused.
+    """
+
+    refusal: str | None
+    """The model's refusal message, or ``None`` if it sent none."""
+    idempotency_key: str | None
+
+class Message:
+    """A single chat message that can be passed in the ``messages`` list.
+
+    Instances can be mixed freely with plain ``{"role": ..., "content": ...}``
+    dicts. Messages are immutable and compare equal when their role,
+    content, ``name``, and ``tool_call_id`` match.
+
+    Example::
+
+        provider.generate_text(messages=[
+            SystemMessage("Be concise."),
+            UserMessage("Hi"),
+            {"role": "assistant", "content": "Hello!"},
+            Message("user", "What's new?"),
+        ])
+    """
+
+    def __init__(
+        self,
+        role: str,
+        content: str | list[dict[str, Any]],
+        *,
+        name: str | None = None,
+        tool_call_id: str | None = None,
+    ) -> None: ...
+    @property
+    def role(self) -> str:
+        """The message role, e.g. ``"user"``."""
+        ...
+
+    @property
+    def content(self) -> str | list[dict[str, Any]]:
+        """The message text, or the list of content parts for multimodal
+        messages."""
+        ...
+
+    @property
+    def name(self) -> str | None:
+        """Optional participant name for multi-user conversations."""
+        ...
+
+    @property
+    def tool_call_id(self) -> str | None:
+        """The tool call this message answers, for ``"tool"`` messages."""
+        ...
+
+    def __eq__(self, other: object) -> bool: ...
+    def __repr__(self) -> str: ...
+
+class SystemMessage(Message):
+    """A message with the ``system`` role."""
 
-__all__ = ["Provider", "TextStream", "GenerateResult"]
+    def __init__(
+        self, content: str | list[dict[str, Any]], *, name: str | None = None
+    ) -> None: ...
+
+class UserMessage(Message):
+    """A message with the ``user`` role."""
+
+    def __init__(
+        self, content: str | list[dict[str, Any]], *, name: str | None = None
+    ) -> None: ...
+
+class AssistantMessage(Message):
+    """A message with the ``assistant`` role."""
+
+    def __init__(
+        self, content: str | list[dict[str, Any]], *, name: str | None = None
+    ) -> None: ...
 
 class GenerateResult:
     """Result from a text generation call when ``include_usage=True``.
@@ -78,6 +406,55 @@ class GenerateResult:
         """The model's complete text response."""
         ...
 
+    @property
+    def reasoning(self) -> str | None:
+        """Reasoning text returned by reasoning models (``reasoning_content``
+        or ``reasoning``), or ``None`` if not reported."""
+        ...
+
+    @property
+    def audio(self) -> dict[str, Any] | None:
+        """Audio output as a dict with ``id``, ``data`` (base64),
+        ``transcript``, and ``expires_at``, or ``None`` if the model
+        returned no audio."""
+        ...
+
+    @property
+    def response_id(self) -> str | None:
+        """The response ``id`` sent by the provider, or ``None`` if not
+        reported. Useful for looking the call up in provider dashboards."""
+        ...
+
+    @property
+    def request_id(self) -> str | None:
+        """The provider's request id from the ``x-request-id`` (or
+        ``request-id``, ``openrouter-id``) response header, or ``None`` if
+        the header is absent. Quote it in provider support tickets."""
+        ...
+
+    @property
+    def idempotency_key(self) -> str | None:
+        """The ``Idempotency-Key`` header the request was sent with, or
+        ``None`` if :attr:`Provider.idempotency_keys` is off. After a
+        fallback, the key of the fallback model's request."""
+        ...
+
+    @property
+    def rate_limit(self) -> dict[str, float | None] | None:
+        """The provider's rate-limit response headers as a dict of
+        ``remaining_requests``, ``remaining_tokens``, ``reset_requests``
+        and ``reset_tokens`` (seconds until the limit resets), each
+        ``None`` when not reported. Reads OpenAI's
+        ``x-ratelimit-remaining-*`` and ``x-ratelimit-reset-*`` headers, or
+        OpenRouter's ``x-ratelimit-remaining`` and ``x-ratelimit-reset``."""
+        ...
+
+    @property
+    def created(self) -> int | None:
+        """Unix timestamp the provider reports the response was created at,
+        or ``None`` if not reported."""
+        ...
+
     @property
     def prompt_tokens(self) -> int | None:
         """Number of tokens in the prompt, or ``None`` if not reported."""
@@ -90,7 +467,45 @@ class GenerateResult:
 
     @property
     def total_tokens(self) -> int | None:
-        """Total tokens used (prompt + completion), or ``None`` if not reported."""
+        """Total tokens used (prompt + completion), or ``None`` if not reported.
+
+        Summed from the other two when the provider reports them but leaves
+        this out.
+        """
+        ...
+
+    @property
+    def cached_prompt_tokens(self) -> int | None:
+        """Prompt tokens served from the provider's prompt cache
+        (``usage.prompt_tokens_details.cached_tokens``), or ``None`` if not
+        reported."""
+        ...
+
+    @property
+    def reasoning_tokens(self) -> int | None:
+        """Completion tokens spent on reasoning
+        (``usage.completion_tokens_details.reasoning_tokens``), or ``None``
+        if not reported."""
+        ...
+
+    @property
+    def cost_usd(self) -> float | None:
+        """What the call cost in USD as the provider reports it
+        (``usage.cost``, sent by OpenRouter with ``include_cost=True``), or
+        ``None``. Estimate it from :meth:`Provider.get_pricing` for
+        providers that do not report it."""
+        ...
+
+    @property
+    def accepted_prediction_tokens(self) -> int | None:
+        """Predicted-output tokens that appeared in the completion, or ``None``
+        if not reported."""
+        ...
+
+    @property
+    def rejected_prediction_tokens(self) -> int | None:
+        """Predicted-output tokens that were not used, or ``None`` if not
+        reported."""
         ...
 
     @property
@@ -98,14 +513,204 @@ class GenerateResult:
         """The reason the model stopped generating, e.g. ``"stop"`` or ``"length"``."""
         ...
 
+    @property
+    def refusal(self) -> str | None:
+        """The model's refusal message, sent instead of an answer, or
+        ``None``."""
+        ...
+
+    @property
+    def annotations(self) -> list[dict[str, Any]]:
+        """Citations a web-search model attached to its answer (OpenRouter
+        ``:online`` models, OpenAI search models), as dicts with ``type``,
+        ``url``, ``title``, ``start_index`` and ``end_index``. Empty when
+        there are none."""
+        ...
+
+    @property
+    def was_filtered(self) -> bool:
+        """Whether content filtering stopped the response
+        (``finish_reason == "content_filter"``) or the model refused."""
+        ...
+
     @property
     def model(self) -> str | None:
-        """The model that was used for generation, as reported by the API."""
+        """The model that was used for generation, as reported by the API,
+        or else the model the request was sent to."""
+        ...
+
+    @property
+    def attempted_models(self) -> list[str]:
+        """The models the call was sent to in turn: the provider's
+        ``model``, then any ``fallback_models`` tried after it failed."""
+        ...
+
+    @property
+    def fallback_used(self) -> bool:
+        """Whether a fallback model served the response."""
+        ...
+
+    @property
+    def timings(self) -> dict[str, float | int] | None:
+        """How long the call took, measured around the HTTP requests: a
+        dict of ``total_duration_ms``, ``attempts`` (counting retries and
+        fallback models) and ``retry_wait_ms`` (time slept before retries,
+        included in the total). ``None`` for a result that was not timed."""
+        ...
+
+    @property
+    def raw(self) -> dict[str, Any] | None:
+        """The whole response body as a dict, for fields without a
+        property of their own (citations, cache stats, provider-specific
+        extensions). For Anthropic this is the Messages API response as
+        sent, before translation."""
+        ...
+
+    def to_dict(self) -> dict[str, Any]:
+        """The typed fields as a dict of plain values, e.g. for logging as
+        JSON: ``text``, ``reasoning``, ``refusal``, ``audio``,
+        ``finish_reason``, ``model``, ``response_id``, ``request_id``,
+        ``idempotency_key``, ``attempted_models``, ``created``,
+        ``service_tier``, ``system_fingerprint``, the token counts,
+        ``cost_usd``, ``rate_limit`` and ``timings``. :attr:`raw` is left out."""
+        ...
+
+    @property
+    def service_tier(self) -> str | None:
+        """The service tier that served the request, as reported by the API."""
+        ...
+
+    @property
+    def system_fingerprint(self) -> str | None:
+        """Backend configuration fingerprint, as reported by the API.
+
+        Compare across calls made with the same ``seed``: outputs are only
+        expected to be reproducible when the fingerprint matches.
+        """
+        ...
+
+    def __eq__(self, other: object) -> bool:
+        """Results are equal when all of their fields are. Not hashable."""
         ...
 
     def __str__(self) -> str: ...
     def __repr__(self) -> str: ...
 
+class UsageTracker:
+    """Sums the usage of every call made with it, grouped by model.
+
+    Attach one to a provider with :meth:`Provider.track_usage`, or pass it
+    to a single call as ``usage_tracker``. Each call that completes adds one
+    to :attr:`calls` and its reported usage to the token counts, whether or
+    not it asked for ``include_usage``; streams ask the provider for usage
+    when a tracker is set. Calls that fail are not counted. A tracker may be
+    shared by providers and threads.
+
+    Example::
+
+        tracker = UsageTracker()
+        provider.track_usage(tracker)
+        provider.generate_text("Hello")
+        print(tracker.total_tokens, tracker.by_model())
+    """
+
+    def __init__(self) -> None: ...
+    @property
+    def calls(self) -> int:
+        """Calls counted, across every model."""
+        ...
+
+    @property
+    def prompt_tokens(self) -> int: ...
+    @property
+    def completion_tokens(self) -> int: ...
+    @property
+    def total_tokens(self) -> int: ...
+    @property
+    def cached_prompt_tokens(self) -> int:
+        """Prompt tokens served from the provider's prompt cache."""
+        ...
+
+    @property
+    def reasoning_tokens(self) -> int:
+        """Completion tokens spent on reasoning."""
+        ...
+
+    @property
+    def cost_usd(self) -> float | None:
+        """Cost in USD of the calls that reported one, or ``None`` if none
+        did. See ``include_cost``."""
+        ...
+
+    def by_model(self) -> dict[str, dict[str, Any]]:
+        """The counts for each model, keyed by the model that answered,
+        with the same keys as :meth:`to_dict` except ``models``."""
+        ...
+
+    def reset(self) -> None:
+        """Set every count back to zero."""
+        ...
+
+    def to_dict(self) -> dict[str, Any]:
+        """The totals and per-model counts, for exporting to a metrics
+        system.
+
+        ``calls``, ``prompt_tokens``, ``completion_tokens``,
+        ``total_tokens``, ``cached_prompt_tokens``, ``reasoning_tokens``
+        and ``cost_usd`` summed over every model, and ``models`` with the
+        same counts for each model.
+        """
+        ...
+
+    def __repr__(self) -> str: ...
+
+class ModelInfo:
+    """A model the provider serves, from :meth:`Provider.list_models` or
+    :meth:`Provider.get_model`.
+
+    ``id``, ``created`` and ``owned_by`` are what every OpenAI-compatible
+    server lists; ``name``, ``context_length`` and ``pricing`` are set when
+    the provider lists them, as OpenRouter does.
+    """
+
+    @property
+    def id(self) -> str:
+        """The model id to pass as ``model``."""
+        ...
+
+    @property
+    def created(self) -> int | None:
+        """The Unix timestamp the model was created at, or ``None``."""
+        ...
+
+    @property
+    def owned_by(self) -> str | None:
+        """The organization that owns the model, or ``None``."""
+        ...
+
+    @property
+    def name(self) -> str | None:
+        """The model's display name, or ``None``."""
+        ...
+
+    @property
+    def context_length(self) -> int | None:
+        """The model's context window in tokens, or ``None``."""
+        ...
+
+    @property
+    def pricing(self) -> dict[str, float] | None:
+        """The model's prices in USD, keyed as the provider lists them, or
+        ``None``. See :meth:`Provider.get_pricing`."""
+        ...
+
+    @property
+    def raw(self) -> dict[str, Any]:
+        """The model's entry as the provider sent it."""
+        ...
+
+    def __repr__(self) -> str: ...
+
 class Provider:
     """Configuration for an OpenAI-compatible LLM API provider.
 
@@ -142,27 +747,151 @@ class Provider:
 
     def __init__(
         self,
-        model: str,
+        model: str | None = None,
         *,
+        fallback_models: Sequence[str] | None = None,
         api_key: str | None = None,
         base_url: str | None = None,
+        request_timeout: float | None = None,
+        connect_timeout: float | None = None,
+        max_retries: int | None = None,
+        retry_backoff_ms: int | None = None,
+        max_retry_delay_ms: int | None = None,
+        retry_on_status: Sequence[int] | None = None,
+        on_retry: Callable[[int, int | str, float], object] | None = None,
+        total_timeout: float | None = None,
+        circuit_breaker_threshold: int | None = None,
+        circuit_breaker_cooldown: float | None = None,
+        max_requests_per_minute: int | None = None,
+        max_tokens_per_minute: int | None = None,
+        max_concurrency: int | None = None,
+        idempotency_keys: bool = True,
+        stream_idle_timeout: float | None = None,
+        ca_cert: str | None = None,
+        client_cert: str | None = None,
+        client_key: str | None = None,
+        insecure_skip_tls_verify: bool = False,
+        default_headers: dict[str, str] | None = None,
     ) -> None:
         """Create a new Provider.
 
+        The timeout and retry arguments take precedence over their
+        ``RUSTY_AGENT_*`` environment variables, which take precedence over
+        the defaults.
+
         Args:
-            model: Model identifier, e.g. ``"openai/gpt-4o-mini"``.
+            model: Model identifier, e.g. ``"openai/gpt-4o-mini"``. If
+                ``None``, falls back to the ``RUSTY_AGENT_DEFAULT_MODEL``
+                environment variable.
+            fallback_models: Models to try in turn when a call to ``model``
+                fails with a 404, 429 or 5xx response once its retries are
+                spent. The result's :attr:`~GenerateResult.model` and
+                :attr:`~GenerateResult.attempted_models` report which one
+                served it.
             api_key: API key. If ``None``, falls back to the
-                ``OPENROUTER_API_KEY`` environment variable.
-            base_url: Base URL. Defaults to ``"https://openrouter.ai/api/v1"``.
+                ``OPENROUTER_API_KEY`` environment variable. Pass ``""`` for
+                a server without auth; no ``Authorization`` header is sent
+                then.
+            base_url: Base URL. Defaults to ``RUSTY_AGENT_BASE_URL``, then
+                ``OPENROUTER_BASE_URL``, then
+                ``"https://openrouter.ai/api/v1"``. A pasted
+                ``/chat/completions`` or ``/embeddings`` suffix is removed
+                (see :attr:`base_url_warning`); a query string is kept.
+            request_timeout: Seconds a request may take. Defaults to
+                ``RUSTY_AGENT_REQUEST_TIMEOUT_SECS``, then 60.
+            connect_timeout: Seconds allowed to open a connection. Defaults
+                to ``RUSTY_AGENT_CONNECT_TIMEOUT_SECS``, then 10.
+            max_retries: Retries for retryable failures. Defaults to
+                ``RUSTY_AGENT_MAX_RETRIES``, then 2.
+            retry_backoff_ms: Base delay between retries in milliseconds,
+                doubled per attempt. Defaults to
+                ``RUSTY_AGENT_RETRY_BACKOFF_MS``, then 250.
+            max_retry_delay_ms: Longest wait before a retry in milliseconds,
+                including waits a ``Retry-After`` header asks for. Defaults
+                to ``RUSTY_AGENT_RETRY_MAX_DELAY_MS``, then 30000.
+            retry_on_status: Response statuses to retry. Defaults to 429,
+                500, 502, 503 and 504; ``[]`` retries only connection
+                failures.
+            on_retry: Called before each wait between attempts with the
+                retry number (from 1), the status code or connection error
+                that caused it, and the wait in seconds. Exceptions it
+                raises are counted in :attr:`on_retry_errors`, not raised.
+            total_timeout: Seconds a whole call may take, across all
+                attempts and the waits between them. Each attempt's timeout
+                is shortened to fit, and ``TimeoutError`` is raised once it
+                runs out. Defaults to no overall limit.
+            circuit_breaker_threshold: Consecutive failed calls (connection
+                errors, timeouts, and 429 or 5xx responses once retries are
+                spent) after which the circuit breaker opens and calls raise
+                :class:`CircuitOpenError` without sending a request.
+                ``None`` or ``0`` (the default) turns the breaker off.
+            circuit_breaker_cooldown: Seconds the circuit stays open before
+                one probe call is let through; its success closes the
+                circuit and its failure opens it again. Defaults to 30.
+            max_requests_per_minute: Calls this provider and its copies may
+                start per minute. Calls over the limit wait, with the GIL
+                released, until a slot frees up; ``TimeoutError`` is raised
+                instead if the wait would outlast ``total_timeout``.
+                ``None`` or ``0`` (the default) means no limit.
+            max_tokens_per_minute: Tokens this provider and its copies may
+                use per minute. Each call reserves its estimated prompt
+                tokens plus ``max_tokens``, corrected to the reported usage
+                afterwards. ``None`` or ``0`` (the default) means no limit.
+            max_concurrency: Requests this provider and its copies may have
+                in flight at once, across threads. Others wait, with the GIL
+                released, for one to finish, or raise ``TimeoutError`` once
+                ``total_timeout`` runs out; a stream holds its slot until it
+                ends. ``None`` or ``0`` (the default) means no limit.
+            idempotency_keys: Send a random ``Idempotency-Key`` header with
+                each request, the same on every retry of it, so a provider
+                that honors it does not act on a retried request twice.
+            stream_idle_timeout: Seconds a stream may go without receiving
+                data before it fails. Defaults to
+                ``RUSTY_AGENT_STREAM_IDLE_TIMEOUT_SECS``, or the request
+                timeout when that is unset.
+            ca_cert: Path of a PEM bundle of root certificates to trust in
+                addition to the built-in ones, e.g. a corporate gateway's
+                internal CA.
+            client_cert: Path of a PEM client certificate for mutual TLS.
+                It may also hold the private key.
+            client_key: Path of the PEM private key for ``client_cert``,
+                when it is a separate file.
+            insecure_skip_tls_verify: Accept any server certificate,
+                including expired and self-signed ones. This lets anyone on
+                the network read and alter requests, API key included; do
+                not use it outside local testing, and prefer ``ca_cert``.
+            default_headers: Headers sent with every request, e.g. a
+                gateway's tenant header. The API key header and headers the
+                API requires take precedence.
 
         Raises:
-            ValueError: If no API key is provided and the
-                ``OPENROUTER_API_KEY`` environment variable is not set.
+            ValueError: If no model is provided and the
+                ``RUSTY_AGENT_DEFAULT_MODEL`` environment variable is not set,
+                if no API key is provided and the ``OPENROUTER_API_KEY``
+                environment variable is not set, if ``base_url`` is not an
+                http or https URL with a host, if a timeout,
+                ``retry_backoff_ms`` or ``max_retry_delay_ms`` is not
+                positive, if ``max_retries``,
+                ``circuit_breaker_threshold``, a per-minute limit or
+                ``max_concurrency`` is negative, if ``retry_on_status`` holds
+                a value that is not an HTTP status, if a TLS file cannot be
+                read or parsed, if a default header name or value is
+                invalid, or if a fallback model is empty.
+            TypeError: If ``on_retry`` is not callable.
         """
         ...
 
     @classmethod
-    def openai(cls, model: str, *, api_key: str | None = None) -> Provider:
+    def openai(
+        cls,
+        model: str,
+        *,
+        api_key: str | None = None,
+        request_timeout: float | None = None,
+        connect_timeout: float | None = None,
+        max_retries: int | None = None,
+        retry_backoff_ms: int | None = None,
+    ) -> Provider:
         """Create a Provider configured for the OpenAI API.
 
         Sets the base URL to ``https://api.openai.com/v1``. If ``api_key``
@@ -172,40 +901,69 @@ class Provider:
             model: Model identifier, e.g. ``"gpt-4o-mini"``.
             api_key: API key. If ``None``, falls back to the
                 ``OPENAI_API_KEY`` environment variable.
+            request_timeout, connect_timeout, max_retries, retry_backoff_ms:
+                As for the :class:`Provider` constructor.
 
         Returns:
             A configured :class:`Provider` instance.
 
         Raises:
             ValueError: If no API key is provided and ``OPENAI_API_KEY``
-                is not set.
+                is not set, or if a timeout or retry argument is invalid.
         """
         ...
 
     @classmethod
-    def anthropic(cls, model: str, *, api_key: str | None = None) -> Provider:
+    def anthropic(
+        cls,
+        model: str,
+        *,
+        api_key: str | None = None,
+        request_timeout: float | None = None,
+        connect_timeout: float | None = None,
+        max_retries: int | None = None,
+        retry_backoff_ms: int | None = None,
+    ) -> Provider:
         """Create a Provider configured for the Anthropic API.
 
         Sets the base URL to ``https://api.anthropic.com/v1``. If ``api_key``
         is not provided, the ``ANTHROPIC_API_KEY`` environment variable is
         used.
 
+        Requests use the native Messages API (``/messages`` with the
+        ``x-api-key`` and ``anthropic-version`` headers). ``max_tokens``
+        defaults to 4096, and parameters the Messages API cannot express,
+        such as ``seed`` or ``response_format``, raise ``ValueError``.
+
         Args:
             model: Model identifier, e.g. ``"claude-sonnet-4-20250514"``.
             api_key: API key. If ``None``, falls back to the
                 ``ANTHROPIC_API_KEY`` environment variable.
+            request_timeout, connect_timeout, max_retries, retry_backoff_ms:
+                As for the :class:`Provider` constructor.
 
         Returns:
             A configured :class:`Provider` instance.
 
         Raises:
             ValueError: If no API key is provided and ``ANTHROPIC_API_KEY``
-                is not set.
+                is not set, or if a timeout or retry argument is invalid.
         """
         ...
 
     @classmethod
-    def openrouter(cls, model: str, *, api_key: str | None = None) -> Provider:
+    def openrouter(
+        cls,
+        model: str,
+        *,
+        api_key: str | None = None,
+        site_url: str | None = None,
+        app_name: str | None = None,
+        request_timeout: float | None = None,
+        connect_timeout: float | None = None,
+        max_retries: int | None = None,
+        retry_backoff_ms: int | None = None,
+    ) -> Provider:
         """Create a Provider configured for the OpenRouter API.
 
         Sets the base URL to ``https://openrouter.ai/api/v1``. If ``api_key``
@@ -216,175 +974,1881 @@ class Provider:
             model: Model identifier, e.g. ``"openai/gpt-4o-mini"``.
             api_key: API key. If ``None``, falls back to the
                 ``OPENROUTER_API_KEY`` environment variable.
+            site_url: Your app's URL, sent as the ``HTTP-Referer`` header
+                OpenRouter ranks apps by.
+            app_name: Your app's name, sent as the ``X-Title`` header.
+            request_timeout, connect_timeout, max_retries, retry_backoff_ms:
+                As for the :class:`Provider` constructor.
 
         Returns:
             A configured :class:`Provider` instance.
 
         Raises:
             ValueError: If no API key is provided and ``OPENROUTER_API_KEY``
-                is not set.
+                is not set, if a timeout or retry argument is invalid, or if
+                ``site_url`` or ``app_name`` is not a valid header value.
         """
         ...
 
-    @overload
-    def generate_text(
-        self,
-        prompt: str | None = None,
+    @classmethod
+    def groq(
+        cls,
+        model: str,
         *,
-        system_prompt: str | None = None,
-        messages: list[dict[str, str]] | None = None,
-        temperature: float | None = None,
-        max_tokens: int | None = None,
-        top_p: float | None = None,
-        stop: str | list[str] | None = None,
-        frequency_penalty: float | None = None,
-        presence_penalty: float | None = None,
-        seed: int | None = None,
-        response_format: dict[str, Any] | None = None,
-        include_usage: Literal[False] = ...,
-    ) -> str:
-        """Generate a complete text response (blocking).
+        api_key: str | None = None,
+        request_timeout: float | None = None,
+        connect_timeout: float | None = None,
+        max_retries: int | None = None,
+        retry_backoff_ms: int | None = None,
+    ) -> Provider:
+        """Create a Provider configured for the Groq API.
+
+        Sets the base URL to ``https://api.groq.com/openai/v1``. If ``api_key`` is not
+        provided, the ``GROQ_API_KEY`` environment variable is used.
+
+        Args:
+            model: Model identifier, e.g. ``"llama-3.3-70b-versatile"``.
+            api_key: API key. If ``None``, falls back to the
+                ``GROQ_API_KEY`` environment variable.
+            request_timeout, connect_timeout, max_retries, retry_backoff_ms:
+                As for the :class:`Provider` constructor.
+
+        Returns:
+            A configured :class:`Provider` instance.
 
-        Returns ``str`` when ``include_usage`` is ``False`` (the default).
+        Raises:
+            ValueError: If no API key is provided and ``GROQ_API_KEY``
+                is not set, or if a timeout or retry argument is invalid.
         """
         ...
 
-    @overload
-    def generate_text(
-        self,
-        prompt: str | None = None,
+    @classmethod
+    def mistral(
+        cls,
+        model: str,
         *,
-        system_prompt: str | None = None,
-        messages: list[dict[str, str]] | None = None,
-        temperature: float | None = None,
-        max_tokens: int | None = None,
-        top_p: float | None = None,
-        stop: str | list[str] | None = None,
-        frequency_penalty: float | None = None,
-        presence_penalty: float | None = None,
-        seed: int | None = None,
-        response_format: dict[str, Any] | None = None,
-        include_usage: Literal[True] = ...,
-    ) -> GenerateResult:
-        """Generate a complete text response (blocking).
+        api_key: str | None = None,
+        request_timeout: float | None = None,
+        connect_timeout: float | None = None,
+        max_retries: int | None = None,
+        retry_backoff_ms: int | None = None,
+    ) -> Provider:
+        """Create a Provider configured for the Mistral API.
+
+        Sets the base URL to ``https://api.mistral.ai/v1``. If ``api_key`` is not
+        provided, the ``MISTRAL_API_KEY`` environment variable is used.
+
+        Args:
+            model: Model identifier, e.g. ``"mistral-large-latest"``.
+            api_key: API key. If ``None``, falls back to the
+                ``MISTRAL_API_KEY`` environment variable.
+            request_timeout, connect_timeout, max_retries, retry_backoff_ms:
+                As for the :class:`Provider` constructor.
+
+        Returns:
+            A configured :class:`Provider` instance.
 
-        Returns :class:`GenerateResult` when ``include_usage`` is ``True``.
+        Raises:
+            ValueError: If no API key is provided and ``MISTRAL_API_KEY``
+                is not set, or if a timeout or retry argument is invalid.
         """
         ...
 
-    def generate_text(
-        self,
+    @classmethod
+    def deepseek(
+        cls,
+        model: str,
+        *,
+        api_key: str | None = None,
+        request_timeout: float | None = None,
+        connect_timeout: float | None = None,
+        max_retries: int | None = None,
+        retry_backoff_ms: int | None = None,
+    ) -> Provider:
+        """Create a Provider configured for the DeepSeek API.
+
+        Sets the base URL to ``https://api.deepseek.com/v1``. If ``api_key`` is not
+        provided, the ``DEEPSEEK_API_KEY`` environment variable is used.
+
+        Args:
+            model: Model identifier, e.g. ``"deepseek-chat"``.
+            api_key: API key. If ``None``, falls back to the
+                ``DEEPSEEK_API_KEY`` environment variable.
+            request_timeout, connect_timeout, max_retries, retry_backoff_ms:
+                As for the :class:`Provider` constructor.
+
+        Returns:
+            A configured :class:`Provider` instance.
+
+        Raises:
+            ValueError: If no API key is provided and ``DEEPSEEK_API_KEY``
+                is not set, or if a timeout or retry argument is invalid.
+        """
+        ...
+
+    @classmethod
+    def together(
+        cls,
+        model: str,
+        *,
+        api_key: str | None = None,
+        request_timeout: float | None = None,
+        connect_timeout: float | None = None,
+        max_retries: int | None = None,
+        retry_backoff_ms: int | None = None,
+    ) -> Provider:
+        """Create a Provider configured for the Together AI API.
+
+        Sets the base URL to ``https://api.together.xyz/v1``. If ``api_key`` is not
+        provided, the ``TOGETHER_API_KEY`` environment variable is used.
+
+        Args:
+            model: Model identifier, e.g. ``"meta-llama/Llama-3.3-70B-Instruct-Turbo"``.
+            api_key: API key. If ``None``, falls back to the
+                ``TOGETHER_API_KEY`` environment variable.
+            request_timeout, connect_timeout, max_retries, retry_backoff_ms:
+                As for the :class:`Provider` constructor.
+
+        Returns:
+            A configured :class:`Provider` instance.
+
+        Raises:
+            ValueError: If no API key is provided and ``TOGETHER_API_KEY``
+                is not set, or if a timeout or retry argument is invalid.
+        """
+        ...
+
+    @classmethod
+    def xai(
+        cls,
+        model: str,
+        *,
+        api_key: str | None = None,
+        request_timeout: float | None = None,
+        connect_timeout: float | None = None,
+        max_retries: int | None = None,
+        retry_backoff_ms: int | None = None,
+    ) -> Provider:
+        """Create a Provider configured for the xAI API.
+
+        Sets the base URL to ``https://api.x.ai/v1``. If ``api_key`` is not
+        provided, the ``XAI_API_KEY`` environment variable is used.
+
+        Args:
+            model: Model identifier, e.g. ``"grok-4"``.
+            api_key: API key. If ``None``, falls back to the
+                ``XAI_API_KEY`` environment variable.
+            request_timeout, connect_timeout, max_retries, retry_backoff_ms:
+                As for the :class:`Provider` constructor.
+
+        Returns:
+            A configured :class:`Provider` instance.
+
+        Raises:
+            ValueError: If no API key is provided and ``XAI_API_KEY``
+                is not set, or if a timeout or retry argument is invalid.
+        """
+        ...
+
+    @classmethod
+    def from_named(
+        cls,
+        name: str,
+        model: str,
+        *,
+        api_key: str | None = None,
+        request_timeout: float | None = None,
+        connect_timeout: float | None = None,
+        max_retries: int | None = None,
+        retry_backoff_ms: int | None = None,
+    ) -> Provider:
+        """Create a Provider for a named provider from the built-in registry.
+
+        ``Provider.from_named("groq", model)`` is equivalent to
+        ``Provider.groq(model)``. See :meth:`known_providers` for the
+        available names.
+
+        Args:
+            name: Provider name, e.g. ``"openai"`` or ``"groq"``.
+            model: Model identifier.
+            api_key: API key. If ``None``, falls back to the provider's
+                environment variable, e.g. ``GROQ_API_KEY``.
+            request_timeout, connect_timeout, max_retries, retry_backoff_ms:
+                As for the :class:`Provider` constructor.
+
+        Returns:
+            A configured :class:`Provider` instance.
+
+        Raises:
+            ValueError: If ``name`` is not a known provider (the message
+                lists the supported ones), if no API key is provided and the
+                provider's environment variable is not set, or if a timeout
+                or retry argument is invalid.
+        """
+        ...
+
+    @staticmethod
+    def known_providers() -> list[str]:
+        """Names accepted by :meth:`from_named`, in a fixed order."""
+        ...
+
+    @classmethod
+    def azure(
+        cls,
+        deployment: str,
+        *,
+        resource: str | None = None,
+        api_key: str | None = None,
+        api_version: str = "2024-10-21",
+        base_url: str | None = None,
+        request_timeout: float | None = None,
+        connect_timeout: float | None = None,
+        max_retries: int | None = None,
+        retry_backoff_ms: int | None = None,
+    ) -> Provider:
+        """Create a Provider for an Azure OpenAI deployment.
+
+        Requests go to
+        ``{base_url}/openai/deployments/{deployment}/chat/completions``
+        with the ``api-version`` query parameter, and the API key is sent in
+        the ``api-key`` header. The deployment is the provider's ``model``,
+        so :meth:`with_model` switches deployments.
+
+        Args:
+            deployment: Name of the model deployment.
+            resource: Azure resource name, giving the base URL
+                ``https://{resource}.openai.azure.com``.
+            api_key: API key. If ``None``, falls back to the
+                ``AZURE_OPENAI_API_KEY`` environment variable.
+            api_version: Value of the ``api-version`` query parameter.
+            base_url: Resource endpoint. Takes precedence over ``resource``;
+                if neither is given, falls back to the
+                ``AZURE_OPENAI_ENDPOINT`` environment variable.
+            request_timeout, connect_timeout, max_retries, retry_backoff_ms:
+                As for the :class:`Provider` constructor.
+
+        Returns:
+            A configured :class:`Provider` instance.
+
+        Raises:
+            ValueError: If no endpoint or API key can be resolved, or if a
+                timeout or retry argument is invalid.
+        """
+        ...
+
+    @classmethod
+    def ollama(
+        cls,
+        model: str,
+        *,
+        base_url: str = "http://localhost:11434/v1",
+        api_key: str | None = None,
+        request_timeout: float | None = None,
+        connect_timeout: float | None = None,
+        max_retries: int | None = None,
+        retry_backoff_ms: int | None = None,
+    ) -> Provider:
+        """Create a Provider for a local OpenAI-compatible server.
+
+        Works with Ollama, llama.cpp, vLLM, and similar servers. No API key
+        is required and no environment variable is read for one; without a
+        key, requests are sent without an ``Authorization`` header.
+
+        Args:
+            model: Model name as the server knows it, e.g. ``"llama3.2"``.
+            base_url: Base URL of the server. Defaults to Ollama's
+                ``"http://localhost:11434/v1"``.
+            api_key: API key, for servers started with one.
+            request_timeout, connect_timeout, max_retries, retry_backoff_ms:
+                As for the :class:`Provider` constructor.
+
+        Returns:
+            A configured :class:`Provider` instance.
+
+        Raises:
+            ValueError: If a timeout or retry argument is invalid.
+        """
+        ...
+
+    @property
+    def model(self) -> str:
+        """The model identifier requests are sent with."""
+        ...
+
+    @property
+    def fallback_models(self) -> list[str]:
+        """Models tried in turn when a call to :attr:`model` fails."""
+        ...
+
+    @property
+    def base_url(self) -> str:
+        """The base URL, without a trailing slash."""
+        ...
+
+    @property
+    def base_url_warning(self) -> str | None:
+        """What normalizing the given base URL changed, e.g. a trailing
+        ``/chat/completions`` it removed, or ``None``."""
+        ...
+
+    @property
+    def default_headers(self) -> dict[str, str]:
+        """Headers sent with every request, with lowercase names."""
+        ...
+
+    @property
+    def masked_api_key(self) -> str:
+        """The API key masked to ``"****"`` plus its last four characters.
+
+        Keys of four characters or fewer are masked entirely. The raw key is
+        not exposed.
+        """
+        ...
+
+    @property
+    def request_timeout(self) -> float:
+        """Seconds a request may take."""
+        ...
+
+    @property
+    def connect_timeout(self) -> float:
+        """Seconds allowed to open a connection."""
+        ...
+
+    @property
+    def max_retries(self) -> int:
+        """Retries for retryable failures."""
+        ...
+
+    @property
+    def retry_backoff(self) -> float:
+        """Base delay between retries in seconds, doubled per attempt."""
+        ...
+
+    @property
+    def max_retry_delay(self) -> float:
+        """Longest wait before a retry in seconds."""
+        ...
+
+    @property
+    def retry_on_status(self) -> list[int]:
+        """Response statuses that are retried."""
+        ...
+
+    @property
+    def on_retry(self) -> Callable[[int, int | str, float], object] | None:
+        """Callback invoked before each retry, or ``None``."""
+        ...
+
+    @property
+    def retry_count(self) -> int:
+        """Retries made by requests through this provider and its copies."""
+        ...
+
+    @property
+    def on_retry_errors(self) -> int:
+        """Exceptions raised by :attr:`on_retry`, which are counted instead
+        of breaking the request."""
+        ...
+
+    @property
+    def total_timeout(self) -> float | None:
+        """Seconds a whole call may take across its retries, or ``None``."""
+        ...
+
+    @property
+    def idempotency_keys(self) -> bool:
+        """Whether requests get a generated ``Idempotency-Key`` header."""
+        ...
+
+    @property
+    def circuit_breaker_threshold(self) -> int:
+        """Consecutive failed calls that open the circuit breaker; ``0`` when
+        it is off."""
+        ...
+
+    @property
+    def circuit_breaker_cooldown(self) -> float:
+        """Seconds an open circuit waits before letting a probe call
+        through."""
+        ...
+
+    def circuit_state(self) -> Literal["closed", "open", "half_open"]:
+        """The circuit breaker's state, shared with copies of this provider.
+
+        ``"closed"`` while calls go through, ``"open"`` while they raise
+        :class:`CircuitOpenError`, or ``"half_open"`` once the cool-down has
+        ended and the next call probes the provider. Always ``"closed"``
+        when the breaker is off.
+        """
+        ...
+
+    @property
+    def max_requests_per_minute(self) -> int:
+        """Calls that may start per minute; ``0`` when there is no limit."""
+        ...
+
+    @property
+    def max_tokens_per_minute(self) -> int:
+        """Tokens that may be used per minute; ``0`` when there is no limit."""
+        ...
+
+    @property
+    def max_concurrency(self) -> int:
+        """Requests that may be in flight at once; ``0`` when there is no
+        limit."""
+        ...
+
+    def rate_limiter_levels(self) -> dict[str, float | None]:
+        """What the client-side rate limiter has left, shared with copies
+        of this provider.
+
+        ``requests`` and ``tokens`` available now, or ``None`` for a limit
+        that is off. ``tokens`` is negative while calls that used more than
+        they reserved are repaid.
+        """
+        ...
+
+    def track_usage(self, tracker: UsageTracker | None) -> None:
+        """Count the usage of every call this provider completes from now
+        on in ``tracker``, or stop counting with ``None``.
+
+        Unlike the ``with_*`` methods this changes the provider in place.
+        Copies made afterwards, and chats started from it, count in the
+        same tracker; a pickled provider leaves it behind.
+        """
+        ...
+
+    @property
+    def usage_tracker(self) -> UsageTracker | None:
+        """The tracker set with :meth:`track_usage`, or ``None``."""
+        ...
+
+    def list_models(self) -> list[ModelInfo]:
+        """List the models the provider serves, from its ``/models`` endpoint.
+
+        Retried and timed out like a generation, but not held to the rate
+        limiter, ``max_concurrency`` or the circuit breaker.
+
+        Returns:
+            The models, in the order the provider lists them.
+
+        Raises:
+            ConnectionError: If the HTTP request fails.
+            RuntimeError: If the API returns a non-2xx status code.
+            ValueError: If the response cannot be parsed.
+        """
+        ...
+
+    def get_model(self, model: str | None = None) -> ModelInfo:
+        """Describe one model, from the provider's ``/models/{id}`` endpoint.
+
+        Args:
+            model: Model id. Defaults to the provider's :attr:`model`.
+
+        Raises:
+            ConnectionError: If the HTTP request fails.
+            RuntimeError: If the API returns a non-2xx status code, e.g.
+                404 for an unknown model.
+            ValueError: If the response cannot be parsed.
+        """
+        ...
+
+    def get_pricing(
+        self, model: str | None = None, *, refresh: bool = False
+    ) -> dict[str, float] | None:
+        """Look a model's prices up in the provider's ``/models`` catalog.
+
+        Use it to estimate costs where responses do not report them, e.g.
+        ``result.prompt_tokens * prices["prompt"]``. The catalog is fetched
+        on first use and cached for the process, shared by every provider
+        with the same ``base_url``; pass ``refresh=True`` to fetch it again.
+
+        Args:
+            model: Model id as listed in the catalog. Defaults to the
+                provider's :attr:`model`.
+            refresh: Fetch the catalog again instead of using the cached
+                copy.
+
+        Returns:
+            The model's prices in USD, keyed as the catalog lists them
+            (``prompt`` and ``completion`` per token, ``request`` per call,
+            ...), or ``None`` if the catalog has no prices for it.
+
+        Raises:
+            ConnectionError: If the HTTP request fails.
+            RuntimeError: If the API returns a non-2xx status code.
+            ValueError: If the catalog cannot be parsed.
+        """
+        ...
+
+    def with_model(self, model: str) -> Provider:
+        """Return a copy of this provider that uses ``model``.
+
+        The API key, base URL, timeouts, and retry settings are kept, and
+        the copy shares this provider's connection pool::
+
+            fallback = provider.with_model("openai/gpt-4o-mini")
+
+        Args:
+            model: Model identifier for the copy.
+
+        Returns:
+            A new :class:`Provider`; this one is unchanged.
+        """
+        ...
+
+    def with_options(
+        self,
+        *,
+        fallback_models: Sequence[str] | None = None,
+        api_key: str | None = None,
+        base_url: str | None = None,
+        request_timeout: float | None = None,
+        connect_timeout: float | None = None,
+        max_retries: int | None = None,
+        retry_backoff_ms: int | None = None,
+        max_retry_delay_ms: int | None = None,
+        retry_on_status: Sequence[int] | None = None,
+        on_retry: Callable[[int, int | str, float], object] | None = None,
+        total_timeout: float | None = None,
+        circuit_breaker_threshold: int | None = None,
+        circuit_breaker_cooldown: float | None = None,
+        max_requests_per_minute: int | None = None,
+        max_tokens_per_minute: int | None = None,
+        max_concurrency: int | None = None,
+        idempotency_keys: bool | None = None,
+        stream_idle_timeout: float | None = None,
+        ca_cert: str | None = None,
+        client_cert: str | None = None,
+        client_key: str | None = None,
+        insecure_skip_tls_verify: bool | None = None,
+        default_headers: dict[str, str] | None = None,
+    ) -> Provider:
+        """Return a copy of this provider with the given settings replaced.
+
+        Takes the keyword arguments of the constructor except ``model`` (see
+        :meth:`with_model`). Settings that are not given keep their current
+        values; the environment is not read again.
+
+        Returns:
+            A new :class:`Provider`; this one is unchanged.
+
+        Raises:
+            ValueError: If ``base_url`` is not an http or https URL with a
+                host, if a timeout, ``retry_backoff_ms`` or
+                ``max_retry_delay_ms`` is not positive, if ``max_retries``,
+                ``circuit_breaker_threshold``, a per-minute limit or
+                ``max_concurrency`` is negative, if
+                ``retry_on_status`` holds a value that is not an HTTP status,
+                if a TLS file cannot be read or parsed, if a default header
+                is invalid, or if a fallback model is empty.
+            TypeError: If ``on_retry`` is not callable.
+        """
+        ...
+
+    def __eq__(self, other: object) -> bool:
+        """Providers are equal when their base URL, model, API key, and
+        routing (e.g. Azure deployment URLs) are.
+
+        Timeouts and retry settings are not compared.
+        """
+        ...
+
+    def __hash__(self) -> int: ...
+    def __getnewargs_ex__(self) -> tuple[tuple[str], dict[str, Any]]:
+        """Constructor arguments that rebuild this provider, for ``pickle``.
+
+        They include the API key in plain text, so treat a pickled provider
+        as a secret.
+        """
+        ...
+
+    def __getstate__(self) -> tuple[str, str | None, bool]: ...
+    def __setstate__(self, state: tuple[str, str | None, bool]) -> None: ...
+    def __copy__(self) -> Provider: ...
+    def __deepcopy__(self, memo: dict[int, Any]) -> Provider: ...
+
+    @overload
+    def generate_text(
+        self,
+        prompt: str | None = None,
+        *,
+        system_prompt: str | None = None,
+        messages: list[dict[str, Any] | Message] | None = None,
+        validate_roles: bool = True,
+        auto_trim: int | None = None,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        frequency_penalty: float | None = None,
+        presence_penalty: float | None = None,
+        seed: int | None = None,
+        response_format: dict[str, Any] | type[Any] | None = None,
+        reasoning_effort: Literal["low", "medium", "high"] | None = None,
+        prediction: str | dict[str, Any] | None = None,
+        service_tier: str | None = None,
+        modalities: Sequence[Literal["text", "audio"]] | None = None,
+        audio: dict[str, Any] | None = None,
+        models: Sequence[str] | None = None,
+        include_usage: Literal[False] = ...,
+        include_raw: Literal[False] = ...,
+        include_cost: Literal[False] = ...,
+        total_timeout: float | None = None,
+        retry: bool = True,
+        on_retry: Callable[[int, int | str, float], object] | None = None,
+        usage_tracker: UsageTracker | None = None,
+        idempotency_key: str | None = None,
+        raise_on_content_filter: bool = False,
+    ) -> str:
+        """Generate a complete text response (blocking).
+
+        Returns ``str`` when ``include_usage``, ``include_raw`` and
+        ``include_cost`` are ``False`` (the default).
+        """
+        ...
+
+    @overload
+    def generate_text(
+        self,
+        prompt: str | None = None,
+        *,
+        system_prompt: str | None = None,
+        messages: list[dict[str, Any] | Message] | None = None,
+        validate_roles: bool = True,
+        auto_trim: int | None = None,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        frequency_penalty: float | None = None,
+        presence_penalty: float | None = None,
+        seed: int | None = None,
+        response_format: dict[str, Any] | type[Any] | None = None,
+        reasoning_effort: Literal["low", "medium", "high"] | None = None,
+        prediction: str | dict[str, Any] | None = None,
+        service_tier: str | None = None,
+        modalities: Sequence[Literal["text", "audio"]] | None = None,
+        audio: dict[str, Any] | None = None,
+        models: Sequence[str] | None = None,
+        include_usage: Literal[True] = ...,
+        include_raw: bool = ...,
+        include_cost: bool = ...,
+        total_timeout: float | None = None,
+        retry: bool = True,
+        on_retry: Callable[[int, int | str, float], object] | None = None,
+        usage_tracker: UsageTracker | None = None,
+        idempotency_key: str | None = None,
+        raise_on_content_filter: bool = False,
+    ) -> GenerateResult:
+        """Generate a complete text response (blocking).
+
+        Returns :class:`GenerateResult` when ``include_usage``,
+        ``include_raw`` or ``include_cost`` is ``True``.
+        """
+        ...
+
+    def generate_text(
+        self,
+        prompt: str | None = None,
+        *,
+        system_prompt: str | None = None,
+        messages: list[dict[str, Any] | Message] | None = None,
+        validate_roles: bool = True,
+        auto_trim: int | None = None,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        frequency_penalty: float | None = None,
+        presence_penalty: float | None = None,
+        seed: int | None = None,
+        response_format: dict[str, Any] | type[Any] | None = None,
+        reasoning_effort: Literal["low", "medium", "high"] | None = None,
+        prediction: str | dict[str, Any] | None = None,
+        service_tier: str | None = None,
+        modalities: Sequence[Literal["text", "audio"]] | None = None,
+        audio: dict[str, Any] | None = None,
+        models: Sequence[str] | None = None,
+        include_usage: bool = False,
+        include_raw: bool = False,
+        include_cost: bool = False,
+        total_timeout: float | None = None,
+        retry: bool = True,
+        on_retry: Callable[[int, int | str, float], object] | None = None,
+        usage_tracker: UsageTracker | None = None,
+        idempotency_key: str | None = None,
+        raise_on_content_filter: bool = False,
+    ) -> str | GenerateResult:
+        """Generate a complete text response (blocking).
+
+        Sends a chat completion request and waits for the full response.
+
+        Note: When both ``prompt`` and ``messages`` are provided,
+        ``messages`` takes priority and ``prompt`` is ignored.
+
+        Args:
+            prompt: The user message to send (shorthand for a single user
+                message).
+            system_prompt: Optional system prompt, prepended to the messages.
+            messages: Full conversation history as a list of
+                ``{"role": ..., "content": ...}`` dicts and/or
+                :class:`Message` instances. When provided,
+                ``prompt`` is ignored. Any message may include a ``name``
+                to distinguish participants; messages with the ``"tool"``
+                role may also include a ``tool_call_id``. ``content`` may be
+                a string or a list of content parts, e.g.
+                ``[{"type": "text", "text": ...}, {"type": "image_url",
+                "image_url": {"url": ...}}]``. ``"file"`` and
+                ``"input_audio"`` parts are also accepted.
+            validate_roles: If ``True`` (the default), raise ``ValueError``
+                for roles other than ``system``, ``user``, ``assistant``,
+                ``tool``, and ``developer``. Disable for providers that use
+                custom roles.
+            auto_trim: Token budget for the conversation. When set, the
+                oldest messages are dropped as by :func:`trim_messages`
+                until the estimated count fits.
+            temperature: Sampling temperature (0-2). Default: 1.
+            max_tokens: Maximum tokens to generate.
+            top_p: Nucleus sampling threshold (0-1). Default: 1.
+            stop: Up to 4 non-empty stop sequences (a string, or a list,
+                tuple, or other sequence of strings).
+            frequency_penalty: Frequency penalty (-2 to 2). Default: 0.
+            presence_penalty: Presence penalty (-2 to 2). Default: 0.
+            seed: Random seed for deterministic generation.
+            response_format: Response format, e.g.
+                ``{"type": "json_object"}`` or the result of
+                :func:`json_schema`. The ``type`` must be ``"text"``,
+                ``"json_object"``, or ``"json_schema"``. A Pydantic model
+                class (anything with ``model_json_schema()``) is also
+                accepted and converted to a ``json_schema`` format.
+            reasoning_effort: Reasoning effort for reasoning models
+                (``"low"``, ``"medium"``, or ``"high"``).
+            prediction: Predicted output for faster edits. A plain string is
+                wrapped as ``{"type": "content", "content": ...}``; a dict
+                is sent as-is.
+            service_tier: Service tier to request, e.g. ``"auto"``,
+                ``"default"``, or ``"flex"``.
+            modalities: Output modalities, e.g. ``["text", "audio"]`` for
+                audio-capable models such as ``gpt-4o-audio-preview``.
+            audio: Audio output settings, e.g.
+                ``{"voice": "alloy", "format": "wav"}``. Required when
+                ``modalities`` includes ``"audio"``.
+            models: Models for OpenRouter to try in turn, sent as
+                ``models`` instead of the provider's model so the router
+                falls back server-side. :attr:`GenerateResult.model` names
+                the one that answered. Cannot be combined with the
+                provider's ``fallback_models``.
+            include_usage: If ``True``, return a :class:`GenerateResult` with
+                token usage statistics instead of a plain string.
+            include_raw: If ``True``, return a :class:`GenerateResult` whose
+                :attr:`~GenerateResult.raw` holds the full response JSON.
+                Off by default so large responses are not kept; also set
+                by ``include_usage=True``.
+            include_cost: If ``True``, ask OpenRouter for usage accounting
+                (``usage: {"include": true}``) and return a
+                :class:`GenerateResult` whose
+                :attr:`~GenerateResult.cost_usd` holds the reported cost.
+                Not supported by the Anthropic dialect.
+            total_timeout: Seconds this call may take across all attempts,
+                overriding the provider's ``total_timeout``.
+            retry: Set to ``False`` to make a single attempt, e.g. for a
+                request that is not safe to repeat.
+            on_retry: Called before each retry of this call instead of the
+                provider's :attr:`on_retry`.
+            usage_tracker: Counts this call's usage instead of the tracker
+                set with :meth:`track_usage`.
+            idempotency_key: ``Idempotency-Key`` to send instead of a
+                generated one, e.g. to reuse a key when repeating a call.
+                Only the primary model's request uses it; fallback models
+                get generated keys.
+            raise_on_content_filter: Raise :class:`ContentFilterError`
+                instead of returning when content filtering stopped the
+                response or the model refused.
+
+        Returns:
+            The model's complete text response as a ``str`` when
+            ``include_usage=False`` (default), or a :class:`GenerateResult`
+            when ``include_usage``, ``include_raw`` or ``include_cost`` is
+            ``True``.
+
+        Raises:
+            ConnectionError: If the HTTP request fails. Its ``kind``
+                attribute names the failure (``"dns"``, ``"tls"``,
+                ``"connect"``, ``"timeout"``, ...) and ``__cause__`` holds
+                the underlying error, e.g. ``ConnectionRefusedError``.
+            TimeoutError: If ``total_timeout`` runs out.
+            RuntimeError: If the API returns a non-2xx status code. The
+                exception has ``status_code``, ``code``, ``error_type``,
+                ``body`` and ``request_id`` attributes.
+            ContextLengthExceededError: A ``RuntimeError`` raised when the
+                request does not fit the model's context window.
+            ContentFilterError: With ``raise_on_content_filter=True``, if the
+                response was filtered or refused.
+
+            Exceptions from the request also carry the ``Idempotency-Key``
+            it was sent with as ``idempotency_key``.
+            ValueError: If the response cannot be parsed, with the body
+                as its ``body`` attribute, if neither prompt nor messages is
+                provided, if a parameter value is invalid, or if ``models``
+                is empty or given to a provider with ``fallback_models``.
+        """
+        ...
+
+    @overload
+    def agenerate_text(
+        self,
+        prompt: str | None = None,
+        *,
+        system_prompt: str | None = None,
+        messages: list[dict[str, Any] | Message] | None = None,
+        validate_roles: bool = True,
+        auto_trim: int | None = None,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        frequency_penalty: float | None = None,
+        presence_penalty: float | None = None,
+        seed: int | None = None,
+        response_format: dict[str, Any] | type[Any] | None = None,
+        reasoning_effort: Literal["low", "medium", "high"] | None = None,
+        prediction: str | dict[str, Any] | None = None,
+        service_tier: str | None = None,
+        modalities: Sequence[Literal["text", "audio"]] | None = None,
+        audio: dict[str, Any] | None = None,
+        models: Sequence[str] | None = None,
+        include_usage: Literal[False] = ...,
+        include_raw: Literal[False] = ...,
+        include_cost: Literal[False] = ...,
+        total_timeout: float | None = None,
+        retry: bool = True,
+        on_retry: Callable[[int, int | str, float], object] | None = None,
+        usage_tracker: UsageTracker | None = None,
+        idempotency_key: str | None = None,
+        raise_on_content_filter: bool = False,
+    ) -> Awaitable[str]:
+        """Generate a complete text response without blocking the event loop.
+
+        Resolves to ``str`` when ``include_usage``, ``include_raw`` and
+        ``include_cost`` are ``False`` (the default).
+        """
+        ...
+
+    @overload
+    def agenerate_text(
+        self,
+        prompt: str | None = None,
+        *,
+        system_prompt: str | None = None,
+        messages: list[dict[str, Any] | Message] | None = None,
+        validate_roles: bool = True,
+        auto_trim: int | None = None,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        frequency_penalty: float | None = None,
+        presence_penalty: float | None = None,
+        seed: int | None = None,
+        response_format: dict[str, Any] | type[Any] | None = None,
+        reasoning_effort: Literal["low", "medium", "high"] | None = None,
+        prediction: str | dict[str, Any] | None = None,
+        service_tier: str | None = None,
+        modalities: Sequence[Literal["text", "audio"]] | None = None,
+        audio: dict[str, Any] | None = None,
+        models: Sequence[str] | None = None,
+        include_usage: Literal[True] = ...,
+        include_raw: bool = ...,
+        include_cost: bool = ...,
+        total_timeout: float | None = None,
+        retry: bool = True,
+        on_retry: Callable[[int, int | str, float], object] | None = None,
+        usage_tracker: UsageTracker | None = None,
+        idempotency_key: str | None = None,
+        raise_on_content_filter: bool = False,
+    ) -> Awaitable[GenerateResult]:
+        """Generate a complete text response without blocking the event loop.
+
+        Resolves to :class:`GenerateResult` when ``include_usage``,
+        ``include_raw`` or ``include_cost`` is ``True``.
+        """
+        ...
+
+    def agenerate_text(
+        self,
         prompt: str | None = None,
         *,
         system_prompt: str | None = None,
-        messages: list[dict[str, str]] | None = None,
+        messages: list[dict[str, Any] | Message] | None = None,
+        validate_roles: bool = True,
+        auto_trim: int | None = None,
         temperature: float | None = None,
         max_tokens: int | None = None,
         top_p: float | None = None,
-        stop: str | list[str] | None = None,
+        stop: str | Sequence[str] | None = None,
         frequency_penalty: float | None = None,
         presence_penalty: float | None = None,
         seed: int | None = None,
-        response_format: dict[str, Any] | None = None,
+        response_format: dict[str, Any] | type[Any] | None = None,
+        reasoning_effort: Literal["low", "medium", "high"] | None = None,
+        prediction: str | dict[str, Any] | None = None,
+        service_tier: str | None = None,
+        modalities: Sequence[Literal["text", "audio"]] | None = None,
+        audio: dict[str, Any] | None = None,
+        models: Sequence[str] | None = None,
         include_usage: bool = False,
-    ) -> str | GenerateResult:
-        """Generate a complete text response (blocking).
+        include_raw: bool = False,
+        include_cost: bool = False,
+        total_timeout: float | None = None,
+        retry: bool = True,
+        on_retry: Callable[[int, int | str, float], object] | None = None,
+        usage_tracker: UsageTracker | None = None,
+        idempotency_key: str | None = None,
+        raise_on_content_filter: bool = False,
+    ) -> Awaitable[str | GenerateResult]:
+        """Generate a complete text response without blocking the event loop.
+
+        Accepts the same parameters as :meth:`generate_text` and returns an
+        awaitable (an ``asyncio.Future``) resolving to the same result. It
+        must be called while an event loop is running, e.g. from a
+        coroutine::
+
+            text = await provider.agenerate_text("Hello!")
+
+        The request runs on a background runtime with the same timeouts and
+        retries as :meth:`generate_text`. Cancelling the awaiting task
+        aborts the HTTP request.
 
-        Sends a chat completion request and waits for the full response.
+        Returns:
+            An awaitable resolving to ``str`` when ``include_usage=False``
+            (default), or to a :class:`GenerateResult` when
+            ``include_usage``, ``include_raw`` or ``include_cost`` is
+            ``True``.
+
+        Raises:
+            RuntimeError: If no event loop is running, or, when awaited, if
+                the API returns a non-2xx status code.
+            ConnectionError: When awaited, if the HTTP request fails.
+            ValueError: If neither prompt nor messages is provided or a
+                parameter value is invalid, or, when awaited, if the
+                response cannot be parsed.
+        """
+        ...
+
+    @overload
+    def generate_many(
+        self,
+        prompts: Sequence[str | list[dict[str, Any] | Message]],
+        *,
+        concurrency: int = 8,
+        system_prompt: str | None = None,
+        validate_roles: bool = True,
+        auto_trim: int | None = None,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        frequency_penalty: float | None = None,
+        presence_penalty: float | None = None,
+        seed: int | None = None,
+        response_format: dict[str, Any] | type[Any] | None = None,
+        reasoning_effort: Literal["low", "medium", "high"] | None = None,
+        prediction: str | dict[str, Any] | None = None,
+        service_tier: str | None = None,
+        modalities: Sequence[Literal["text", "audio"]] | None = None,
+        audio: dict[str, Any] | None = None,
+        include_usage: Literal[False] = ...,
+        include_raw: Literal[False] = ...,
+        include_cost: Literal[False] = ...,
+        total_timeout: float | None = None,
+        retry: bool = True,
+        on_retry: Callable[[int, int | str, float], object] | None = None,
+        usage_tracker: UsageTracker | None = None,
+        raise_on_content_filter: bool = False,
+    ) -> list[str | Exception]:
+        """Generate responses for many prompts concurrently.
+
+        Items are ``str`` when ``include_usage``, ``include_raw`` and
+        ``include_cost`` are ``False`` (the default).
+        """
+        ...
+
+    @overload
+    def generate_many(
+        self,
+        prompts: Sequence[str | list[dict[str, Any] | Message]],
+        *,
+        concurrency: int = 8,
+        system_prompt: str | None = None,
+        validate_roles: bool = True,
+        auto_trim: int | None = None,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        frequency_penalty: float | None = None,
+        presence_penalty: float | None = None,
+        seed: int | None = None,
+        response_format: dict[str, Any] | type[Any] | None = None,
+        reasoning_effort: Literal["low", "medium", "high"] | None = None,
+        prediction: str | dict[str, Any] | None = None,
+        service_tier: str | None = None,
+        modalities: Sequence[Literal["text", "audio"]] | None = None,
+        audio: dict[str, Any] | None = None,
+        include_usage: Literal[True] = ...,
+        include_raw: bool = ...,
+        include_cost: bool = ...,
+        total_timeout: float | None = None,
+        retry: bool = True,
+        on_retry: Callable[[int, int | str, float], object] | None = None,
+        usage_tracker: UsageTracker | None = None,
+        raise_on_content_filter: bool = False,
+    ) -> list[GenerateResult | Exception]:
+        """Generate responses for many prompts concurrently.
+
+        Items are :class:`GenerateResult` when ``include_usage``,
+        ``include_raw`` or ``include_cost`` is ``True``.
+        """
+        ...
+
+    def generate_many(
+        self,
+        prompts: Sequence[str | list[dict[str, Any] | Message]],
+        *,
+        concurrency: int = 8,
+        system_prompt: str | None = None,
+        validate_roles: bool = True,
+        auto_trim: int | None = None,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        frequency_penalty: float | None = None,
+        presence_penalty: float | None = None,
+        seed: int | None = None,
+        response_format: dict[str, Any] | type[Any] | None = None,
+        reasoning_effort: Literal["low", "medium", "high"] | None = None,
+        prediction: str | dict[str, Any] | None = None,
+        service_tier: str | None = None,
+        modalities: Sequence[Literal["text", "audio"]] | None = None,
+        audio: dict[str, Any] | None = None,
+        include_usage: bool = False,
+        include_raw: bool = False,
+        include_cost: bool = False,
+        total_timeout: float | None = None,
+        retry: bool = True,
+        on_retry: Callable[[int, int | str, float], object] | None = None,
+        usage_tracker: UsageTracker | None = None,
+        raise_on_content_filter: bool = False,
+    ) -> list[str | GenerateResult | Exception]:
+        """Generate responses for many prompts concurrently (blocking).
+
+        Each item of ``prompts`` is a prompt string or a conversation as a
+        list of messages; the remaining parameters apply to every item, as
+        in :meth:`generate_text`. Up to ``concurrency`` requests are in
+        flight at once, each with the timeouts and retries of
+        :meth:`generate_text`::
+
+            results = provider.generate_many(prompts, concurrency=16)
+
+        A request that fails does not fail the batch: its slot holds the
+        exception it would have raised, returned rather than raised::
+
+            for prompt, result in zip(prompts, results):
+                if isinstance(result, Exception):
+                    print("failed:", prompt, result)
+
+        Returns:
+            One item per prompt, in input order: ``str`` (or
+            :class:`GenerateResult` with ``include_usage=True``), or the
+            exception the request failed with.
+
+        Raises:
+            TypeError: If an item is neither a string nor a list.
+            ValueError: If ``concurrency`` is less than 1, an item is not a
+                valid conversation, or a parameter value is invalid. Raised
+                before any request is sent.
+        """
+        ...
+
+    @overload
+    def stream_text(
+        self,
+        prompt: str | None = None,
+        *,
+        system_prompt: str | None = None,
+        messages: list[dict[str, Any] | Message] | None = None,
+        validate_roles: bool = True,
+        auto_trim: int | None = None,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        frequency_penalty: float | None = None,
+        presence_penalty: float | None = None,
+        seed: int | None = None,
+        response_format: dict[str, Any] | type[Any] | None = None,
+        reasoning_effort: Literal["low", "medium", "high"] | None = None,
+        prediction: str | dict[str, Any] | None = None,
+        service_tier: str | None = None,
+        modalities: Sequence[Literal["text", "audio"]] | None = None,
+        audio: dict[str, Any] | None = None,
+        models: Sequence[str] | None = None,
+        include_usage: bool = False,
+        include_cost: bool = False,
+        n: int | None = None,
+        detailed: Literal[False] = ...,
+        reconnect: bool | int | None = None,
+        min_chunk_chars: int | None = None,
+        split: Literal["none", "line", "sentence"] = "none",
+        client_stop: Sequence[str] | None = None,
+        stop_regex: str | None = None,
+        include_match: bool = False,
+        raw_event_callback: Callable[[str], object] | None = None,
+        total_timeout: float | None = None,
+        retry: bool = True,
+        on_retry: Callable[[int, int | str, float], object] | None = None,
+        usage_tracker: UsageTracker | None = None,
+        idempotency_key: str | None = None,
+    ) -> TextStream[str]:
+        """Stream text from the LLM as an iterator of ``str`` chunks."""
+        ...
+
+    @overload
+    def stream_text(
+        self,
+        prompt: str | None = None,
+        *,
+        system_prompt: str | None = None,
+        messages: list[dict[str, Any] | Message] | None = None,
+        validate_roles: bool = True,
+        auto_trim: int | None = None,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        frequency_penalty: float | None = None,
+        presence_penalty: float | None = None,
+        seed: int | None = None,
+        response_format: dict[str, Any] | type[Any] | None = None,
+        reasoning_effort: Literal["low", "medium", "high"] | None = None,
+        prediction: str | dict[str, Any] | None = None,
+        service_tier: str | None = None,
+        modalities: Sequence[Literal["text", "audio"]] | None = None,
+        audio: dict[str, Any] | None = None,
+        models: Sequence[str] | None = None,
+        include_usage: bool = False,
+        include_cost: bool = False,
+        n: int | None = None,
+        detailed: Literal[True] = ...,
+        reconnect: bool | int | None = None,
+        min_chunk_chars: int | None = None,
+        split: Literal["none", "line", "sentence"] = "none",
+        client_stop: Sequence[str] | None = None,
+        stop_regex: str | None = None,
+        include_match: bool = False,
+        raw_event_callback: Callable[[str], object] | None = None,
+        total_timeout: float | None = None,
+        retry: bool = True,
+        on_retry: Callable[[int, int | str, float], object] | None = None,
+        usage_tracker: UsageTracker | None = None,
+        idempotency_key: str | None = None,
+    ) -> TextStream[StreamChunk]:
+        """Stream text from the LLM as an iterator of :class:`StreamChunk`."""
+        ...
+
+    def stream_text(
+        self,
+        prompt: str | None = None,
+        *,
+        system_prompt: str | None = None,
+        messages: list[dict[str, Any] | Message] | None = None,
+        validate_roles: bool = True,
+        auto_trim: int | None = None,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        frequency_penalty: float | None = None,
+        presence_penalty: float | None = None,
+        seed: int | None = None,
+        response_format: dict[str, Any] | type[Any] | None = None,
+        reasoning_effort: Literal["low", "medium", "high"] | None = None,
+        prediction: str | dict[str, Any] | None = None,
+        service_tier: str | None = None,
+        modalities: Sequence[Literal["text", "audio"]] | None = None,
+        audio: dict[str, Any] | None = None,
+        models: Sequence[str] | None = None,
+        include_usage: bool = False,
+        include_cost: bool = False,
+        n: int | None = None,
+        detailed: bool = False,
+        reconnect: bool | int | None = None,
+        min_chunk_chars: int | None = None,
+        split: Literal["none", "line", "sentence"] = "none",
+        client_stop: Sequence[str] | None = None,
+        stop_regex: str | None = None,
+        include_match: bool = False,
+        raw_event_callback: Callable[[str], object] | None = None,
+        total_timeout: float | None = None,
+        retry: bool = True,
+        on_retry: Callable[[int, int | str, float], object] | None = None,
+        usage_tracker: UsageTracker | None = None,
+        idempotency_key: str | None = None,
+    ) -> TextStream[str] | TextStream[StreamChunk]:
+        """Stream text from the LLM as an iterator of chunks.
+
+        Accepts the same parameters as :meth:`generate_text`.
+
+        With ``detailed=True`` the stream yields :class:`StreamChunk` objects
+        instead of ``str``, one per streamed payload, including the final one
+        that carries ``finish_reason``.
+
+        With ``n > 1`` the stream always yields :class:`StreamChunk` objects;
+        use ``chunk.index`` to separate the choices. Pass ``detailed=True``
+        as well so type checkers see the chunk type. ``text()``,
+        ``partial_text``, and chat history only include choice 0.
+
+        With ``reconnect``, a stream that fails mid-response is reopened
+        with a ``Last-Event-ID`` header so providers that support it can
+        resume; a stream that sent no event ``id:`` is not reopened.
+        ``True`` allows ``max_retries`` attempts (at least one) and an int
+        sets the count. Each attempt is reported to ``on_retry``. Defaults
+        to ``RUSTY_AGENT_STREAM_RECONNECTS`` (off).
+
+        With ``min_chunk_chars``, text is merged into chunks of at least
+        that many characters. Buffered text is also yielded after about
+        100 ms and when the response ends, so no text is lost. Cannot be
+        combined with ``detailed=True`` or ``n > 1``.
+
+        With ``split="line"`` or ``split="sentence"``, chunks are complete
+        lines or sentences (split at ``.``, ``!``, ``?`` or a newline,
+        skipping common abbreviations), e.g. for text-to-speech. An
+        unfinished segment is held until more text arrives and yielded when
+        the response ends. Cannot be combined with ``detailed=True`` or
+        ``n > 1``.
+
+        ``client_stop`` lists stop sequences checked by the SDK, for models
+        or proxies that ignore ``stop``. When the streamed text contains one,
+        the text before it is yielded, the request is cancelled, and
+        ``finish_reason`` reads ``"client_stop"``. Text that could be the
+        start of a sequence is held back until the next chunk. Cannot be
+        combined with ``detailed=True`` or ``n > 1``.
+
+        ``stop_regex`` does the same for a regular expression (``regex``
+        crate syntax), e.g. ``r"\\n```\\s*\\n"`` to stop at a closing code
+        fence. Matches may complete across chunks; text from the first
+        position where a match could still start is held back. With
+        ``include_match=True`` the matched text is yielded too. An invalid
+        pattern, or one that matches the empty string, raises
+        ``ValueError``.
+
+        ``raw_event_callback`` is called with each complete SSE event (e.g.
+        ``'data: {...}'``) before it is parsed, to debug what a provider
+        actually sends. It runs on the stream's worker thread; SSE comments
+        are not passed, and exceptions it raises are counted in
+        :attr:`TextStream.raw_event_callback_errors` instead of ending the
+        stream. Parse errors also quote the offending payload (up to 2 KB).
 
         Note: When both ``prompt`` and ``messages`` are provided,
         ``messages`` takes priority and ``prompt`` is ignored.
 
+        ``finish_reason``, ``model`` and the other metadata properties of the
+        returned :class:`TextStream` are populated once iteration completes.
+        Token counts (``prompt_tokens``, ``completion_tokens``, etc.) also
+        require ``include_usage=True``, which asks the provider to report them.
+        ``include_cost=True`` also asks OpenRouter for the cost, reported as
+        :attr:`TextStream.cost_usd`, and implies ``include_usage``.
+
+        Returns:
+            An iterator yielding ``str`` chunks, or :class:`StreamChunk`
+            objects when ``detailed=True``.
+
+        Raises:
+            ConnectionError: If the initial HTTP connection fails.
+            TimeoutError: If ``total_timeout`` runs out, which for a stream
+                also bounds reading the response.
+            RuntimeError: If the API returns a non-2xx status code.
+            ValueError: If neither prompt nor messages is provided, or if a
+                parameter value is invalid.
+        """
+        ...
+
+    @overload
+    def astream_text(
+        self,
+        prompt: str | None = None,
+        *,
+        system_prompt: str | None = None,
+        messages: list[dict[str, Any] | Message] | None = None,
+        validate_roles: bool = True,
+        auto_trim: int | None = None,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        frequency_penalty: float | None = None,
+        presence_penalty: float | None = None,
+        seed: int | None = None,
+        response_format: dict[str, Any] | type[Any] | None = None,
+        reasoning_effort: Literal["low", "medium", "high"] | None = None,
+        prediction: str | dict[str, Any] | None = None,
+        service_tier: str | None = None,
+        modalities: Sequence[Literal["text", "audio"]] | None = None,
+        audio: dict[str, Any] | None = None,
+        models: Sequence[str] | None = None,
+        include_usage: bool = False,
+        include_cost: bool = False,
+        n: int | None = None,
+        detailed: Literal[False] = ...,
+        reconnect: bool | int | None = None,
+        min_chunk_chars: int | None = None,
+        split: Literal["none", "line", "sentence"] = "none",
+        client_stop: Sequence[str] | None = None,
+        stop_regex: str | None = None,
+        include_match: bool = False,
+        raw_event_callback: Callable[[str], object] | None = None,
+        total_timeout: float | None = None,
+        retry: bool = True,
+        on_retry: Callable[[int, int | str, float], object] | None = None,
+        usage_tracker: UsageTracker | None = None,
+        idempotency_key: str | None = None,
+    ) -> AsyncTextStream[str]:
+        """Stream text from the LLM as an async iterator of ``str`` chunks."""
+        ...
+
+    @overload
+    def astream_text(
+        self,
+        prompt: str | None = None,
+        *,
+        system_prompt: str | None = None,
+        messages: list[dict[str, Any] | Message] | None = None,
+        validate_roles: bool = True,
+        auto_trim: int | None = None,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        frequency_penalty: float | None = None,
+        presence_penalty: float | None = None,
+        seed: int | None = None,
+        response_format: dict[str, Any] | type[Any] | None = None,
+        reasoning_effort: Literal["low", "medium", "high"] | None = None,
+        prediction: str | dict[str, Any] | None = None,
+        service_tier: str | None = None,
+        modalities: Sequence[Literal["text", "audio"]] | None = None,
+        audio: dict[str, Any] | None = None,
+        models: Sequence[str] | None = None,
+        include_usage: bool = False,
+        include_cost: bool = False,
+        n: int | None = None,
+        detailed: Literal[True] = ...,
+        reconnect: bool | int | None = None,
+        min_chunk_chars: int | None = None,
+        split: Literal["none", "line", "sentence"] = "none",
+        client_stop: Sequence[str] | None = None,
+        stop_regex: str | None = None,
+        include_match: bool = False,
+        raw_event_callback: Callable[[str], object] | None = None,
+        total_timeout: float | None = None,
+        retry: bool = True,
+        on_retry: Callable[[int, int | str, float], object] | None = None,
+        usage_tracker: UsageTracker | None = None,
+        idempotency_key: str | None = None,
+    ) -> AsyncTextStream[StreamChunk]:
+        """Stream text from the LLM as an async iterator of :class:`StreamChunk`."""
+        ...
+
+    def astream_text(
+        self,
+        prompt: str | None = None,
+        *,
+        system_prompt: str | None = None,
+        messages: list[dict[str, Any] | Message] | None = None,
+        validate_roles: bool = True,
+        auto_trim: int | None = None,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        frequency_penalty: float | None = None,
+        presence_penalty: float | None = None,
+        seed: int | None = None,
+        response_format: dict[str, Any] | type[Any] | None = None,
+        reasoning_effort: Literal["low", "medium", "high"] | None = None,
+        prediction: str | dict[str, Any] | None = None,
+        service_tier: str | None = None,
+        modalities: Sequence[Literal["text", "audio"]] | None = None,
+        audio: dict[str, Any] | None = None,
+        models: Sequence[str] | None = None,
+        include_usage: bool = False,
+        include_cost: bool = False,
+        n: int | None = None,
+        detailed: bool = False,
+        reconnect: bool | int | None = None,
+        min_chunk_chars: int | None = None,
+        split: Literal["none", "line", "sentence"] = "none",
+        client_stop: Sequence[str] | None = None,
+        stop_regex: str | None = None,
+        include_match: bool = False,
+        raw_event_callback: Callable[[str], object] | None = None,
+        total_timeout: float | None = None,
+        retry: bool = True,
+        on_retry: Callable[[int, int | str, float], object] | None = None,
+        usage_tracker: UsageTracker | None = None,
+        idempotency_key: str | None = None,
+    ) -> AsyncTextStream[str] | AsyncTextStream[StreamChunk]:
+        """Stream text from the LLM as an async iterator of chunks.
+
+        Accepts the same parameters as :meth:`stream_text` and yields the
+        same chunks, for use with ``async for``::
+
+            async for chunk in provider.astream_text("Hello!"):
+                print(chunk, end="", flush=True)
+
+        The response is read on a runtime shared by all async streams rather
+        than a thread per stream. Cancelling the task that awaits the next
+        chunk, calling :meth:`AsyncTextStream.cancel`, or dropping the
+        iterator aborts the HTTP request.
+
+        Returns:
+            An async iterator yielding ``str`` chunks, or
+            :class:`StreamChunk` objects when ``detailed=True``.
+
+        Raises:
+            ConnectionError: When iterated, if the HTTP connection fails.
+            RuntimeError: When iterated, if the API returns a non-2xx status
+                code.
+            ValueError: If neither prompt nor messages is provided, or if a
+                parameter value is invalid.
+        """
+        ...
+
+    def chat(
+        self,
+        system_prompt: str | None = None,
+        *,
+        messages: list[dict[str, Any] | Message] | None = None,
+    ) -> Chat:
+        """Start a conversation that keeps its own message history.
+
         Args:
-            prompt: The user message to send (shorthand for a single user
-                message).
-            system_prompt: Optional system prompt, prepended to the messages.
-            messages: Full conversation history as a list of
-                ``{"role": ..., "content": ...}`` dicts. When provided,
-                ``prompt`` is ignored.
-            temperature: Sampling temperature (0-2). Default: 1.
-            max_tokens: Maximum tokens to generate.
-            top_p: Nucleus sampling threshold (0-1). Default: 1.
-            stop: Up to 4 stop sequences (string or list of strings).
-            frequency_penalty: Frequency penalty (-2 to 2). Default: 0.
-            presence_penalty: Presence penalty (-2 to 2). Default: 0.
-            seed: Random seed for deterministic generation.
-            response_format: Response format, e.g.
-                ``{"type": "json_object"}`` or
-                ``{"type": "json_schema", "json_schema": {...}}``.
-            include_usage: If ``True``, return a :class:`GenerateResult` with
-                token usage statistics instead of a plain string.
+            system_prompt: System prompt for the conversation.
+            messages: Initial history, placed after the system prompt.
+        """
+        ...
+
+    def generate_object(
+        self,
+        prompt: str | None = None,
+        *,
+        schema: dict[str, Any] | type[Any] | None = None,
+        repair: bool = False,
+        system_prompt: str | None = None,
+        messages: list[dict[str, Any] | Message] | None = None,
+        validate_roles: bool = True,
+        auto_trim: int | None = None,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        frequency_penalty: float | None = None,
+        presence_penalty: float | None = None,
+        seed: int | None = None,
+        reasoning_effort: Literal["low", "medium", "high"] | None = None,
+        service_tier: str | None = None,
+    ) -> Any:
+        """Generate structured output and return it parsed as a Python object.
+
+        Sets ``response_format`` to a strict ``json_schema`` built from
+        ``schema`` (or ``{"type": "json_object"}`` when no schema is given)
+        and parses the reply as JSON.
+
+        Example::
+
+            person = provider.generate_object(
+                "Invent a person.",
+                schema={
+                    "type": "object",
+                    "properties": {"name": {"type": "string"}},
+                    "required": ["name"],
+                    "additionalProperties": False,
+                },
+            )
+            print(person["name"])
+
+        Args:
+            prompt: The user message to send.
+            schema: JSON Schema the output must follow, or a Pydantic model
+                class. When ``None``, JSON mode is used and the prompt should
+                mention JSON.
+            repair: If the reply is not valid JSON, send it back once with a
+                corrective system message before giving up.
+
+        Other parameters are as for :meth:`generate_text`.
 
         Returns:
-            The model's complete text response as a ``str`` when
-            ``include_usage=False`` (default), or a :class:`GenerateResult`
-            when ``include_usage=True``.
+            The parsed JSON value (usually a ``dict``), or an instance of
+            ``schema`` validated with ``model_validate_json`` when a Pydantic
+            model class is given.
 
         Raises:
             ConnectionError: If the HTTP request fails.
             RuntimeError: If the API returns a non-2xx status code.
-            ValueError: If the response cannot be parsed, or if neither
-                prompt nor messages is provided.
+            TypeError: If ``schema`` is a class without
+                ``model_json_schema()``.
+            ValueError: If the reply is not valid JSON (the message includes
+                the raw text), fails Pydantic validation, or if a parameter
+                value is invalid.
+        """
+        ...
+
+    def stream_object(
+        self,
+        prompt: str | None = None,
+        *,
+        schema: dict[str, Any] | type[Any] | None = None,
+        system_prompt: str | None = None,
+        messages: list[dict[str, Any] | Message] | None = None,
+        validate_roles: bool = True,
+        auto_trim: int | None = None,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        frequency_penalty: float | None = None,
+        presence_penalty: float | None = None,
+        seed: int | None = None,
+        reasoning_effort: Literal["low", "medium", "high"] | None = None,
+        service_tier: str | None = None,
+    ) -> ObjectStream:
+        """Stream structured output as progressively completed objects.
+
+        Each item is the JSON value parsed from the reply so far, with
+        unterminated strings, arrays, and objects closed. An item is only
+        yielded when the value changed. When the stream ends the full reply
+        is parsed exactly and yielded if it differs from the last item.
+
+        Example::
+
+            for person in provider.stream_object("Invent a person as JSON."):
+                render(person)
+
+        Args:
+            prompt: The user message to send.
+            schema: JSON Schema the output must follow, or a Pydantic model
+                class. Items are plain ``dict``/``list`` values either way.
+
+        Other parameters are as for :meth:`generate_object`, except
+        ``repair``.
+
+        Returns:
+            An iterator yielding ``dict`` or ``list`` snapshots.
+
+        Raises:
+            ConnectionError: If the initial HTTP connection fails.
+            RuntimeError: If the API returns a non-2xx status code.
+            ValueError: If a parameter value is invalid. Also raised from the
+                iterator if the complete reply is not valid JSON (the message
+                includes the raw text).
+        """
+        ...
+
+    def __repr__(self) -> str: ...
+
+class Chat:
+    """A conversation that keeps its own message history.
+
+    Created with :meth:`Provider.chat`. Each :meth:`send` or :meth:`stream`
+    call sends the full history plus the new user message, then records both
+    the user message and the assistant reply. Failed calls leave the history
+    unchanged.
+
+    Example::
+
+        chat = provider.chat(system_prompt="Be concise.")
+        chat.send("What is Rust?")
+        for chunk in chat.stream("And PyO3?"):
+            print(chunk, end="")
+        print(chat.messages)
+    """
+
+    @overload
+    def send(
+        self,
+        content: str | list[dict[str, Any]],
+        *,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        seed: int | None = None,
+        include_usage: Literal[False] = ...,
+    ) -> str: ...
+    @overload
+    def send(
+        self,
+        content: str | list[dict[str, Any]],
+        *,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        seed: int | None = None,
+        include_usage: Literal[True] = ...,
+    ) -> GenerateResult: ...
+    def send(
+        self,
+        content: str | list[dict[str, Any]],
+        *,
+        temperature: float | None = None,
+        max_tokens: int | None = None,
+        top_p: float | None = None,
+        stop: str | Sequence[str] | None = None,
+        seed: int | None = None,
+        include_usage: bool = False,
+    ) -> str | GenerateResult:
+        """Send a user message and return the assistant's reply (blocking).
+
+        Raises:
+            RuntimeError: If another ``send`` or ``stream`` on this chat is
+                still in progress, or the API returns a non-2xx status code.
         """
         ...
 
-    def stream_text(
+    def stream(
         self,
-        prompt: str | None = None,
+        content: str | list[dict[str, Any]],
         *,
-        system_prompt: str | None = None,
-        messages: list[dict[str, str]] | None = None,
         temperature: float | None = None,
         max_tokens: int | None = None,
         top_p: float | None = None,
-        stop: str | list[str] | None = None,
-        frequency_penalty: float | None = None,
-        presence_penalty: float | None = None,
+        stop: str | Sequence[str] | None = None,
         seed: int | None = None,
-        response_format: dict[str, Any] | None = None,
         include_usage: bool = False,
-    ) -> TextStream:
-        """Stream text from the LLM as an iterator of chunks.
+    ) -> TextStream[str]:
+        """Send a user message and stream the assistant's reply.
 
-        Accepts the same parameters as :meth:`generate_text`.
+        The reply is added to the history once the stream has been fully
+        consumed. The chat stays busy until then; an abandoned or failed
+        stream leaves the history unchanged.
+        """
+        ...
 
-        Note: When both ``prompt`` and ``messages`` are provided,
-        ``messages`` takes priority and ``prompt`` is ignored.
+    @property
+    def messages(self) -> list[dict[str, Any]]:
+        """The conversation so far as a list of message dicts."""
+        ...
+
+    def to_json(self) -> str:
+        """Serialise the history as JSON (see :func:`messages_to_json`)."""
+        ...
 
-        When ``include_usage=True``, token usage statistics and metadata
-        will be available on the returned :class:`TextStream` after iteration
-        completes (via properties like ``prompt_tokens``, ``completion_tokens``,
-        etc.).
+    @staticmethod
+    def from_json(provider: Provider, data: str) -> Chat:
+        """Resume a chat from JSON written by :meth:`to_json`.
 
-        Returns:
-            An iterator yielding ``str`` chunks.
+        The loaded messages become the chat's starting point, so
+        :meth:`reset` returns to them.
 
         Raises:
-            ConnectionError: If the initial HTTP connection fails.
-            RuntimeError: If the API returns a non-2xx status code.
-            ValueError: If neither prompt nor messages is provided.
+            ValueError: If ``data`` is not valid chat history JSON.
+        """
+        ...
+
+    def reset(self) -> None:
+        """Clear the history back to the messages the chat was created with.
+
+        Raises:
+            RuntimeError: If a message is still in progress.
         """
         ...
 
+    def __len__(self) -> int: ...
+    def __repr__(self) -> str: ...
+
+class ObjectStream:
+    """An iterator over progressively completed JSON values.
+
+    Returned by :meth:`Provider.stream_object`. If the complete reply is not
+    valid JSON, every later ``next()`` raises the same ``ValueError``.
+    """
+
+    def __enter__(self) -> ObjectStream: ...
+    def __exit__(
+        self,
+        exc_type: type[BaseException] | None,
+        exc_value: BaseException | None,
+        traceback: Any,
+    ) -> bool:
+        """Cancel the stream and wait for the worker thread to exit."""
+        ...
+
+    def __iter__(self) -> ObjectStream: ...
+    def __next__(self) -> Any: ...
+    def cancel(self) -> None:
+        """Stop the stream. Later iteration raises ``StopIteration``."""
+        ...
+
+    @property
+    def text(self) -> str:
+        """The raw text received so far."""
+        ...
+
+class StreamChunk:
+    """A chunk yielded by ``stream_text(detailed=True)``.
+
+    ``str(chunk)`` returns its text.
+    """
+
+    @property
+    def text(self) -> str:
+        """The text delta, or ``""`` if the payload carried none."""
+        ...
+
+    @property
+    def index(self) -> int:
+        """The choice index the payload belongs to."""
+        ...
+
+    @property
+    def finish_reason(self) -> str | None:
+        """The reason generation stopped; usually only set on the last chunk."""
+        ...
+
+    @property
+    def raw(self) -> dict[str, Any]:
+        """The full parsed SSE payload."""
+        ...
+
+    def __str__(self) -> str: ...
     def __repr__(self) -> str: ...
 
-class TextStream:
+class StreamBranch(Generic[_ChunkT]):
+    """One of the iterators returned by :meth:`TextStream.tee`.
+
+    Yields the same chunk type as the stream it was split from. If the
+    stream fails, every later ``next()`` raises the same exception.
+    """
+
+    def __enter__(self) -> StreamBranch[_ChunkT]: ...
+    def __exit__(
+        self,
+        exc_type: type[BaseException] | None,
+        exc_value: BaseException | None,
+        traceback: Any,
+    ) -> bool:
+        """Cancel this branch."""
+        ...
+
+    def __iter__(self) -> StreamBranch[_ChunkT]: ...
+    def __next__(self) -> _ChunkT: ...
+    def text(self) -> str:
+        """Drain this branch and return its remaining chunks as one string."""
+        ...
+
+    def cancel(self) -> None:
+        """Stop this branch so the others no longer wait for it.
+
+        The response is cancelled once every branch has been.
+        """
+        ...
+
+    @property
+    def error(self) -> BaseException | None:
+        """The exception the stream failed with, or ``None``."""
+        ...
+
+class TextStream(Generic[_ChunkT]):
     """An iterator that yields text chunks from a streaming LLM response.
 
     You do not construct this directly -- it is returned by
     :meth:`Provider.stream_text`.
 
-    When ``include_usage=True`` was passed to :meth:`Provider.stream_text`,
-    token usage statistics and metadata are available as properties after
-    the stream has been fully consumed. Before the stream is fully consumed,
-    all metadata properties return ``None``.
+    Metadata such as ``finish_reason`` and ``model`` is available as
+    properties after the stream has been fully consumed; token counts also
+    require ``include_usage=True`` to be passed to
+    :meth:`Provider.stream_text`. Before the stream is fully consumed, all
+    metadata properties return ``None``.
+
+    If the stream fails, every later ``next()`` raises the same exception
+    rather than ``StopIteration``.
     """
 
+    @property
+    def partial_text(self) -> str:
+        """All text parsed from the response so far.
+
+        Includes chunks received but not yet yielded, and stays available
+        after the stream fails, e.g. for logging or resuming.
+        """
+        ...
+
+    @property
+    def last_event_id(self) -> str | None:
+        """The ``id:`` of the last SSE event received, or ``None``.
+
+        Sent as ``Last-Event-ID`` when a stream created with ``reconnect``
+        reopens after a mid-response failure.
+        """
+        ...
+
+    @property
+    def error(self) -> BaseException | None:
+        """The exception the stream failed with, or ``None``.
+
+        ``None`` while the stream is running and after it ends cleanly.
+        """
+        ...
+
+    @property
+    def response_id(self) -> str | None:
+        """The response ``id`` sent by the provider, or ``None``.
+
+        Taken from the first chunk that carries one, so it is available as
+        soon as that chunk has been received.
+        """
+        ...
+
+    @property
+    def request_id(self) -> str | None:
+        """The provider's request id from the ``x-request-id`` (or
+        ``request-id``, ``openrouter-id``) response header, or ``None``.
+
+        Available as soon as the response headers have been received.
+        """
+
+    @property
+    def idempotency_key(self) -> str | None:
+        """The ``Idempotency-Key`` header the request was sent with, or
+        ``None`` if :attr:`Provider.idempotency_keys` is off. Once a
+        fallback model has started the stream, the key of its request."""
+        ...
+
+    @property
+    def rate_limit(self) -> dict[str, float | None] | None:
+        """The provider's rate-limit response headers, as in
+        :attr:`GenerateResult.rate_limit`, or ``None`` until the stream
+        starts."""
+        ...
+
+    @property
+    def created(self) -> int | None:
+        """Unix timestamp the provider reports the response was created at.
+
+        Taken from the first chunk that carries one; ``None`` before then.
+        """
+        ...
+
     @property
     def prompt_tokens(self) -> int | None:
         """Number of tokens in the prompt, or ``None`` if not available.
 
-        Returns ``None`` until the stream is fully consumed.
+        Returns ``None`` until the stream is fully consumed, and unless
+        ``include_usage=True`` was passed.
         """
         ...
 
@@ -392,7 +2856,8 @@ class TextStream:
     def completion_tokens(self) -> int | None:
         """Number of tokens in the completion, or ``None`` if not available.
 
-        Returns ``None`` until the stream is fully consumed.
+        Returns ``None`` until the stream is fully consumed, and unless
+        ``include_usage=True`` was passed.
         """
         ...
 
@@ -400,7 +2865,38 @@ class TextStream:
     def total_tokens(self) -> int | None:
         """Total tokens used (prompt + completion), or ``None`` if not available.
 
-        Returns ``None`` until the stream is fully consumed.
+        Returns ``None`` until the stream is fully consumed, and unless
+        ``include_usage=True`` was passed.
+        """
+        ...
+
+    @property
+    def cached_prompt_tokens(self) -> int | None:
+        """Prompt tokens served from the provider's prompt cache, or
+        ``None`` if not available.
+
+        Returns ``None`` until the stream is fully consumed, and unless
+        ``include_usage=True`` was passed and the provider reported it.
+        """
+        ...
+
+    @property
+    def reasoning_tokens(self) -> int | None:
+        """Completion tokens spent on reasoning, or ``None`` if not
+        available.
+
+        Returns ``None`` until the stream is fully consumed, and unless
+        ``include_usage=True`` was passed and the provider reported it.
+        """
+        ...
+
+    @property
+    def cost_usd(self) -> float | None:
+        """What the stream cost in USD, as the provider reports it.
+
+        Returns ``None`` until the stream is fully consumed, and unless
+        ``include_cost=True`` was passed to a provider that reports cost
+        (OpenRouter).
         """
         ...
 
@@ -412,13 +2908,275 @@ class TextStream:
         """
         ...
 
+    @property
+    def refusal(self) -> str | None:
+        """The model's refusal message, joined from the chunks received so
+        far, or ``None``."""
+        ...
+
+    @property
+    def annotations(self) -> list[dict[str, Any]]:
+        """Citations a web-search model attached to its answer, collected
+        from the chunks received so far."""
+        ...
+
+    @property
+    def was_filtered(self) -> bool:
+        """Whether content filtering stopped the stream
+        (``finish_reason == "content_filter"``) or the model refused."""
+        ...
+
     @property
     def model(self) -> str | None:
-        """The model that was used, as reported by the API.
+        """The model that was used, as reported by the API, or else the
+        model the request was sent to.
+
+        Returns ``None`` until the stream starts.
+        """
+        ...
+
+    @property
+    def attempted_models(self) -> list[str]:
+        """The models the stream was sent to in turn: the provider's
+        ``model``, then any ``fallback_models`` tried after it failed.
+        Empty until the stream starts."""
+        ...
+
+    @property
+    def fallback_used(self) -> bool:
+        """Whether a fallback model served the stream."""
+        ...
+
+    @property
+    def service_tier(self) -> str | None:
+        """The service tier that served the request, as reported by the API.
+
+        Returns ``None`` until the stream is fully consumed.
+        """
+        ...
+
+    @property
+    def system_fingerprint(self) -> str | None:
+        """Backend configuration fingerprint, as reported by the API.
 
         Returns ``None`` until the stream is fully consumed.
         """
         ...
 
-    def __iter__(self) -> TextStream: ...
-    def __next__(self) -> str: ...
+    @property
+    def time_to_first_chunk_ms(self) -> float | None:
+        """Milliseconds from sending the request to the first text chunk.
+
+        Includes connecting and any retries. Returns ``None`` until the
+        stream finishes, or if no text was received.
+        """
+        ...
+
+    @property
+    def total_duration_ms(self) -> float | None:
+        """Milliseconds from sending the request to the end of the response.
+
+        Returns ``None`` until the stream finishes.
+        """
+        ...
+
+    @property
+    def chunk_count(self) -> int | None:
+        """Number of streamed payloads that carried text.
+
+        Counted before ``split`` and ``min_chunk_chars`` regroup the text.
+        Returns ``None`` until the stream finishes.
+        """
+        ...
+
+    @property
+    def completion_tokens_per_second(self) -> float | None:
+        """Completion tokens per second, from the first chunk to the end.
+
+        Returns ``None`` until the stream finishes, and unless
+        ``include_usage=True`` was passed and the provider reported usage.
+        """
+        ...
+
+    @property
+    def raw_event_callback_errors(self) -> int:
+        """How many times ``raw_event_callback`` raised.
+
+        Its exceptions are swallowed so the stream keeps going.
+        """
+        ...
+
+    @property
+    def reasoning(self) -> str | None:
+        """Reasoning text streamed by reasoning models, or ``None`` if none
+        was received.
+
+        Reasoning deltas are not yielded by the iterator; they are
+        accumulated here and are complete once the stream is fully consumed.
+        """
+        ...
+
+    def text(self) -> str:
+        """Drain the remaining chunks and return them as one string.
+
+        Chunks already consumed by iteration are not included. Metadata
+        properties are populated afterwards. Calling it again returns the
+        same string.
+
+        Raises:
+            RuntimeError: If the stream fails while draining.
+        """
+        ...
+
+    def write_to(self, fileobj: Any, flush_every: int = 1) -> int:
+        """Drain the remaining chunks into ``fileobj`` without holding them.
+
+        Each chunk is passed to ``fileobj.write()``. ``fileobj.flush()`` is
+        called, if present, every ``flush_every`` chunks and once at the
+        end; ``flush_every=0`` flushes only at the end. Metadata properties
+        are populated afterwards.
+
+        Returns:
+            The number of characters written.
+
+        Raises:
+            RuntimeError: If the stream fails, or was already drained,
+                failed, or cancelled before the call.
+            Exception: Anything raised by ``write`` or ``flush``; the stream
+                is cancelled first.
+        """
+        ...
+
+    def tee(self, n: int = 2, *, max_buffer: int = 1024) -> list[StreamBranch[_ChunkT]]:
+        """Split the stream into ``n`` independent iterators.
+
+        Every branch yields every remaining chunk of the one response, so no
+        second request is made. Once a branch holds ``max_buffer``
+        unconsumed chunks, the other branches wait for it, and the worker
+        stops reading; consume branches concurrently when they drift further
+        apart than that. Stream errors are raised in every branch.
+
+        This stream cannot be iterated afterwards, but its metadata
+        properties keep working.
+
+        Raises:
+            ValueError: If ``n`` or ``max_buffer`` is zero.
+            RuntimeError: If the stream already ended, failed, was
+                cancelled, or was drained.
+        """
+        ...
+
+    def cancel(self) -> None:
+        """Stop the stream. Later iteration raises ``StopIteration``.
+
+        The background worker exits within about 100 ms, even if it is
+        blocked on a full buffer. An unfinished :meth:`Chat.stream` reply is
+        not recorded. A stream that already failed keeps raising its error.
+        Safe to call more than once.
+        """
+        ...
+
+    def __enter__(self) -> TextStream[_ChunkT]: ...
+    def __exit__(
+        self,
+        exc_type: type[BaseException] | None,
+        exc_value: BaseException | None,
+        traceback: Any,
+    ) -> bool:
+        """Cancel the stream and wait for the worker thread to exit.
+
+        Exceptions raised in the ``with`` body are not suppressed.
+        """
+        ...
+
+    def __iter__(self) -> TextStream[_ChunkT]: ...
+    def __next__(self) -> _ChunkT: ...
+
+class AsyncTextStream(Generic[_ChunkT]):
+    """An async iterator that yields text chunks from a streaming LLM
+    response.
+
+    You do not construct this directly -- it is returned by
+    :meth:`Provider.astream_text`. Its metadata properties behave like
+    those of :class:`TextStream`. If the stream fails, every later
+    ``__anext__()`` raises the same exception rather than
+    ``StopAsyncIteration``.
+    """
+
+    @property
+    def partial_text(self) -> str:
+        """All text parsed from the response so far."""
+        ...
+
+    @property
+    def last_event_id(self) -> str | None:
+        """The ``id:`` of the last SSE event received, or ``None``."""
+        ...
+
+    @property
+    def error(self) -> BaseException | None:
+        """The exception the stream failed with, or ``None``."""
+        ...
+
+    @property
+    def response_id(self) -> str | None: ...
+    @property
+    def request_id(self) -> str | None: ...
+    @property
+    def idempotency_key(self) -> str | None: ...
+    @property
+    def rate_limit(self) -> dict[str, float | None] | None: ...
+    @property
+    def created(self) -> int | None: ...
+    @property
+    def prompt_tokens(self) -> int | None: ...
+    @property
+    def completion_tokens(self) -> int | None: ...
+    @property
+    def total_tokens(self) -> int | None: ...
+    @property
+    def cached_prompt_tokens(self) -> int | None: ...
+    @property
+    def reasoning_tokens(self) -> int | None: ...
+    @property
+    def cost_usd(self) -> float | None: ...
+    @property
+    def finish_reason(self) -> str | None: ...
+    @property
+    def refusal(self) -> str | None: ...
+    @property
+    def annotations(self) -> list[dict[str, Any]]: ...
+    @property
+    def was_filtered(self) -> bool: ...
+    @property
+    def model(self) -> str | None: ...
+    @property
+    def attempted_models(self) -> list[str]: ...
+    @property
+    def fallback_used(self) -> bool: ...
+    @property
+    def service_tier(self) -> str | None: ...
+    @property
+    def system_fingerprint(self) -> str | None: ...
+    @property
+    def time_to_first_chunk_ms(self) -> float | None: ...
+    @property
+    def total_duration_ms(self) -> float | None: ...
+    @property
+    def chunk_count(self) -> int | None: ...
+    @property
+    def completion_tokens_per_second(self) -> float | None: ...
+    @property
+    def raw_event_callback_errors(self) -> int: ...
+    @property
+    def reasoning(self) -> str | None: ...
+    def cancel(self) -> None:
+        """Stop the stream and abort the HTTP request.
+
+        Later iteration raises ``StopAsyncIteration``. Safe to call more
+        than once.
+        """
+        ...
+
+    def __aiter__(self) -> AsyncTextStream[_ChunkT]: ...
+    async def __anext__(self) -> _ChunkT: ...
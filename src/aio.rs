@@ -0,0 +1,81 @@
+use crate::errors::SdkError;
+use crate::runtime;
+use pyo3::IntoPyObjectExt;
+use pyo3::prelude::*;
+use pyo3::types::{PyCFunction, PyString};
+use std::future::Future;
+use tokio::task::AbortHandle;
+
+/// Run `future` on the shared runtime in the background.
+pub fn spawn<F>(future: F) -> PyResult<AbortHandle>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let runtime = runtime::get().map_err(SdkError::into_pyerr)?;
+    Ok(runtime.spawn(future).abort_handle())
+}
+
+/// Abort `task` if the `asyncio.Future` `future` is cancelled.
+pub fn abort_on_cancel(future: &Bound<'_, PyAny>, task: AbortHandle) -> PyResult<()> {
+    let on_done = PyCFunction::new_closure(
+        future.py(),
+        None,
+        None,
+        move |args, _kwargs| -> PyResult<()> {
+            if args.get_item(0)?.call_method0("cancelled")?.is_truthy()? {
+                task.abort();
+            }
+            Ok(())
+        },
+    )?;
+    future.call_method1("add_done_callback", (on_done,))?;
+    Ok(())
+}
+
+/// Run `future` on the shared runtime and return an `asyncio.Future` on the
+/// running event loop that resolves to its output.
+///
+/// The result is handed to the loop with `call_soon_threadsafe`, so the
+/// future is only completed on the loop's own thread. Cancelling the
+/// `asyncio.Future` aborts the task, dropping any in-flight request.
+pub fn future_into_py<'py, F, T>(py: Python<'py>, future: F) -> PyResult<Bound<'py, PyAny>>
+where
+    F: Future<Output = PyResult<T>> + Send + 'static,
+    T: for<'a> IntoPyObject<'a> + Send + 'static,
+{
+    let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+    let py_future = event_loop.call_method0("create_future")?;
+
+    // Called on the loop as `complete(future, method, value)`; skips futures
+    // that were cancelled while the result was on its way.
+    let complete = PyCFunction::new_closure(py, None, None, |args, _kwargs| -> PyResult<()> {
+        let future = args.get_item(0)?;
+        if !future.call_method0("done")?.is_truthy()? {
+            let method = args.get_item(1)?.cast_into::<PyString>()?;
+            future.getattr(method)?.call1((args.get_item(2)?,))?;
+        }
+        Ok(())
+    })?
+    .unbind();
+
+    let loop_ref = event_loop.clone().unbind();
+    let future_ref = py_future.clone().unbind();
+    let task = spawn(async move {
+        let output = future.await;
+        Python::try_attach(|py| {
+            let (method, value) = match output.and_then(|value| value.into_py_any(py)) {
+                Ok(value) => ("set_result", value),
+                Err(err) => ("set_exception", err.into_value(py).into_any()),
+            };
+            // Fails only if the loop has been closed, when nobody is
+            // waiting for the result any more.
+            let _ = loop_ref.bind(py).call_method1(
+                "call_soon_threadsafe",
+                (complete, future_ref, method, value),
+            );
+        });
+    })?;
+    abort_on_cancel(&py_future, task)?;
+
+    Ok(py_future)
+}
@@ -0,0 +1,307 @@
+use crate::errors::SdkError;
+use crate::models::sse_data;
+use serde_json::{Map, Value, json};
+
+/// Value of the `anthropic-version` header sent with every request.
+pub const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// `max_tokens` sent when the caller gives none, since the Messages API
+/// requires it.
+pub const DEFAULT_ANTHROPIC_MAX_TOKENS: u64 = 4096;
+
+/// Chat completions request fields the Messages API has no counterpart for.
+const UNSUPPORTED_FIELDS: [&str; 11] = [
+    "frequency_penalty",
+    "presence_penalty",
+    "seed",
+    "response_format",
+    "reasoning_effort",
+    "prediction",
+    "service_tier",
+    "modalities",
+    "audio",
+    "models",
+    "usage",
+];
+
+/// Translate a serialized `ChatRequest` into a Messages API request.
+///
+/// System and developer messages are joined into the top-level `system`
+/// string, `stop` becomes `stop_sequences`, and `max_tokens` defaults to
+/// `DEFAULT_ANTHROPIC_MAX_TOKENS`. Fields the Messages API cannot express
+/// are rejected rather than dropped.
+pub fn to_messages_request(body: Value) -> Result<Value, SdkError> {
+    let Value::Object(mut body) = body else {
+        return Err(SdkError::runtime("Request body must be a JSON object."));
+    };
+    for field in UNSUPPORTED_FIELDS {
+        if body.contains_key(field) {
+            return Err(unsupported(&format!("'{}'", field)));
+        }
+    }
+    if body.get("n").and_then(Value::as_u64).is_some_and(|n| n > 1) {
+        return Err(unsupported("'n' greater than 1"));
+    }
+
+    let mut system = Vec::new();
+    let mut messages = Vec::new();
+    let history = match body.remove("messages") {
+        Some(Value::Array(history)) => history,
+        _ => Vec::new(),
+    };
+    for (index, message) in history.iter().enumerate() {
+        let role = message.get("role").and_then(Value::as_str).unwrap_or("");
+        let content = message.get("content").unwrap_or(&Value::Null);
+        match role {
+            "system" | "developer" => system.push(content_text(content)),
+            "user" | "assistant" => messages.push(json!({
+                "role": role,
+                "content": content_blocks(index, content)?,
+            })),
+            other => {
+                return Err(SdkError::value(format!(
+                    "messages[{}] has role '{}', which the Anthropic Messages API does not support.",
+                    index, other
+                )));
+            }
+        }
+    }
+
+    let mut request = Map::new();
+    if let Some(model) = body.remove("model") {
+        request.insert("model".to_string(), model);
+    }
+    if !system.is_empty() {
+        request.insert("system".to_string(), Value::String(system.join("\n\n")));
+    }
+    request.insert("messages".to_string(), Value::Array(messages));
+    request.insert(
+        "max_tokens".to_string(),
+        body.remove("max_tokens")
+            .unwrap_or_else(|| json!(DEFAULT_ANTHROPIC_MAX_TOKENS)),
+    );
+    for field in ["temperature", "top_p", "stream"] {
+        if let Some(value) = body.remove(field) {
+            request.insert(field.to_string(), value);
+        }
+    }
+    if let Some(stop) = body.remove("stop") {
+        let sequences = match stop {
+            Value::String(sequence) => json!([sequence]),
+            other => other,
+        };
+        request.insert("stop_sequences".to_string(), sequences);
+    }
+
+    Ok(Value::Object(request))
+}
+
+/// Translate a Messages API response into a chat completions response, so
+/// it can be parsed like any other.
+pub fn to_chat_response(response_text: &str) -> Result<String, SdkError> {
+    let response: Value = serde_json::from_str(response_text).map_err(|e| {
+        SdkError::value(format!("Failed to parse response: {}", e)).with_body(response_text)
+    })?;
+    let blocks = response
+        .get("content")
+        .and_then(Value::as_array)
+        .ok_or_else(|| {
+            SdkError::value("No content returned in API response").with_body(response_text)
+        })?;
+
+    let mut message = json!({
+        "role": "assistant",
+        "content": block_text(blocks, "text"),
+    });
+    let thinking = block_text(blocks, "thinking");
+    if !thinking.is_empty() {
+        message["reasoning"] = Value::String(thinking);
+    }
+
+    let mut chat = json!({
+        "id": response.get("id"),
+        "model": response.get("model"),
+        "choices": [{
+            "index": 0,
+            "message": message,
+            "finish_reason": finish_reason(response.get("stop_reason")),
+        }],
+    });
+    if let Some(usage) = response.get("usage") {
+        chat["usage"] = chat_usage(usage, usage);
+    }
+    Ok(chat.to_string())
+}
+
+/// Translates Messages API stream events into chat completions SSE events,
+/// keeping the message id, model, and input token counts of
+/// `message_start` for the chunks that follow it.
+#[derive(Debug, Default)]
+pub struct StreamTranslator {
+    id: Value,
+    model: Value,
+    start_usage: Value,
+}
+
+impl StreamTranslator {
+    /// The chat completions event for `event`, or `None` for events that
+    /// carry nothing the SDK reads, such as `ping`. Errors and payloads
+    /// that are not JSON are passed through for the usual error handling.
+    pub fn translate(&mut self, event: &str) -> Option<String> {
+        let data = sse_data(event)?;
+        let Ok(data) = serde_json::from_str::<Value>(&data) else {
+            return Some(event.to_string());
+        };
+
+        let chunk = match data.get("type").and_then(Value::as_str) {
+            Some("message_start") => {
+                let message = data.get("message").unwrap_or(&Value::Null);
+                self.id = message.get("id").cloned().unwrap_or_default();
+                self.model = message.get("model").cloned().unwrap_or_default();
+                self.start_usage = message.get("usage").cloned().unwrap_or_default();
+                self.chunk(json!({"role": "assistant"}), Value::Null)
+            }
+            Some("content_block_delta") => {
+                let delta = data.get("delta").unwrap_or(&Value::Null);
+                match delta.get("type").and_then(Value::as_str) {
+                    Some("text_delta") => {
+                        self.chunk(json!({"content": delta.get("text")}), Value::Null)
+                    }
+                    Some("thinking_delta") => {
+                        self.chunk(json!({"reasoning": delta.get("thinking")}), Value::Null)
+                    }
+                    _ => return None,
+                }
+            }
+            Some("message_delta") => {
+                let stop_reason = data.get("delta").and_then(|delta| delta.get("stop_reason"));
+                let mut chunk = self.chunk(json!({}), finish_reason(stop_reason));
+                if let Some(usage) = data.get("usage") {
+                    chunk["usage"] = chat_usage(&self.start_usage, usage);
+                }
+                chunk
+            }
+            Some("message_stop") => return Some("data: [DONE]".to_string()),
+            Some("error") => return Some(event.to_string()),
+            _ => return None,
+        };
+        Some(format!("data: {}", chunk))
+    }
+
+    fn chunk(&self, delta: Value, finish_reason: Value) -> Value {
+        json!({
+            "id": self.id,
+            "model": self.model,
+            "choices": [{"index": 0, "delta": delta, "finish_reason": finish_reason}],
+        })
+    }
+}
+
+fn unsupported(what: &str) -> SdkError {
+    SdkError::value(format!(
+        "{} is not supported by the Anthropic Messages API.",
+        what
+    ))
+}
+
+/// Map a Messages API `stop_reason` to a chat completions `finish_reason`.
+fn finish_reason(stop_reason: Option<&Value>) -> Value {
+    match stop_reason.and_then(Value::as_str) {
+        Some("end_turn" | "stop_sequence") => json!("stop"),
+        Some("max_tokens") => json!("length"),
+        Some("tool_use") => json!("tool_calls"),
+        Some("refusal") => json!("content_filter"),
+        Some(other) => json!(other),
+        None => Value::Null,
+    }
+}
+
+/// Chat completions usage from the input counts in `input` and the output
+/// count in `output`. Cached input tokens count towards `prompt_tokens`, as
+/// they do in chat completions.
+fn chat_usage(input: &Value, output: &Value) -> Value {
+    let count = |usage: &Value, field: &str| usage.get(field).and_then(Value::as_u64);
+    let input_count = |field: &str| count(output, field).or(count(input, field)).unwrap_or(0);
+    let prompt = input_count("input_tokens")
+        + input_count("cache_read_input_tokens")
+        + input_count("cache_creation_input_tokens");
+    let completion = count(output, "output_tokens").unwrap_or(0);
+    json!({
+        "prompt_tokens": prompt,
+        "completion_tokens": completion,
+        "total_tokens": prompt + completion,
+    })
+}
+
+/// The concatenated text of the content blocks of `block_type`, read from
+/// the field of the same name.
+fn block_text(blocks: &[Value], block_type: &str) -> String {
+    blocks
+        .iter()
+        .filter(|block| block.get("type").and_then(Value::as_str) == Some(block_type))
+        .filter_map(|block| block.get(block_type).and_then(Value::as_str))
+        .collect()
+}
+
+/// The text of message content, skipping parts that are not text.
+fn content_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => block_text(parts, "text"),
+        _ => String::new(),
+    }
+}
+
+/// Message content as Messages API content: a string stays a string and
+/// content parts become content blocks.
+fn content_blocks(index: usize, content: &Value) -> Result<Value, SdkError> {
+    let Value::Array(parts) = content else {
+        return Ok(Value::String(content_text(content)));
+    };
+
+    parts
+        .iter()
+        .map(|part| {
+            let part_type = part.get("type").and_then(Value::as_str).unwrap_or("");
+            match part_type {
+                "text" => Ok(json!({"type": "text", "text": part.get("text")})),
+                "image_url" => {
+                    let url = part
+                        .get("image_url")
+                        .and_then(|image| image.get("url"))
+                        .and_then(Value::as_str)
+                        .unwrap_or("");
+                    Ok(json!({"type": "image", "source": media_source(url)}))
+                }
+                "file" => {
+                    let data = part
+                        .get("file")
+                        .and_then(|file| file.get("file_data"))
+                        .and_then(Value::as_str)
+                        .unwrap_or("");
+                    Ok(json!({"type": "document", "source": media_source(data)}))
+                }
+                other => Err(unsupported(&format!(
+                    "messages[{}] content of type '{}'",
+                    index, other
+                ))),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Value::Array)
+}
+
+/// A Messages API media source: base64 for a data URL, else a URL.
+fn media_source(url: &str) -> Value {
+    match url
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(";base64,"))
+    {
+        Some((media_type, data)) => json!({
+            "type": "base64",
+            "media_type": media_type,
+            "data": data,
+        }),
+        None => json!({"type": "url", "url": url}),
+    }
+}
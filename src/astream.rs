@@ -0,0 +1,313 @@
+use crate::aio;
+use crate::errors::SdkError;
+use crate::models::{ChatRequest, StreamMetadata, content_filtered};
+use crate::provider::{Provider, annotations_to_py, rate_limit_to_py};
+use crate::stream::{self, StreamItem, StreamSettings};
+use pyo3::exceptions::PyStopAsyncIteration;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc::Receiver;
+use tokio::task::AbortHandle;
+
+/// An async iterator that yields text chunks from a streaming LLM response.
+///
+/// The response is read on a runtime shared by all async streams, so no
+/// thread is started per stream.
+#[pyclass]
+pub struct AsyncTextStream {
+    receiver: Arc<tokio::sync::Mutex<Receiver<Result<StreamItem, SdkError>>>>,
+    worker: AbortHandle,
+    metadata: Arc<Mutex<Option<StreamMetadata>>>,
+    reasoning: Arc<Mutex<String>>,
+    partial: Arc<Mutex<String>>,
+    last_event_id: Arc<Mutex<Option<String>>>,
+    callback_errors: Arc<AtomicUsize>,
+    error: Arc<Mutex<Option<SdkError>>>,
+    idempotency_key: Option<String>,
+}
+
+impl Drop for AsyncTextStream {
+    fn drop(&mut self) {
+        self.worker.abort();
+    }
+}
+
+#[pymethods]
+impl AsyncTextStream {
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Await the next chunk. Cancelling the awaiting task cancels the
+    /// stream, aborting the HTTP request.
+    fn __anext__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let receiver = Arc::clone(&self.receiver);
+        let error = Arc::clone(&self.error);
+        let next = aio::future_into_py(py, async move {
+            // A failed stream keeps raising its error instead of looking
+            // finished.
+            if let Some(err) = error.lock().ok().and_then(|error| error.clone()) {
+                return Err(err.into_pyerr());
+            }
+            match receiver.lock().await.recv().await {
+                Some(Ok(item)) => Ok(item),
+                Some(Err(err)) => {
+                    if let Ok(mut error) = error.lock() {
+                        *error = Some(err.clone());
+                    }
+                    Err(err.into_pyerr())
+                }
+                None => Err(PyStopAsyncIteration::new_err(())),
+            }
+        })?;
+        aio::abort_on_cancel(&next, self.worker.clone())?;
+        Ok(next)
+    }
+
+    /// Stop the stream and abort the HTTP request. Later iteration raises
+    /// ``StopAsyncIteration``. Safe to call more than once.
+    fn cancel(&self) {
+        self.worker.abort();
+    }
+
+    /// All text parsed from the response so far.
+    #[getter]
+    fn partial_text(&self) -> String {
+        self.partial
+            .lock()
+            .map(|text| text.clone())
+            .unwrap_or_default()
+    }
+
+    /// The ``id`` of the last SSE event received, or ``None``.
+    #[getter]
+    fn last_event_id(&self) -> Option<String> {
+        self.last_event_id.lock().ok()?.clone()
+    }
+
+    /// The exception the stream failed with, or ``None``.
+    #[getter]
+    fn error(&self) -> Option<PyErr> {
+        let error = self.error.lock().ok()?;
+        error.clone().map(SdkError::into_pyerr)
+    }
+
+    #[getter]
+    fn response_id(&self) -> Option<String> {
+        self.flat_metadata(|m| m.id.clone())
+    }
+
+    /// The provider's request id from the ``x-request-id`` (or
+    /// ``request-id``, ``openrouter-id``) response header, or ``None``.
+    #[getter]
+    fn request_id(&self) -> Option<String> {
+        self.flat_metadata(|m| m.request_id.clone())
+    }
+
+    /// The ``Idempotency-Key`` header the request was sent with, or
+    /// ``None`` if keys are disabled. Once a fallback model has started
+    /// the stream, the key of its request.
+    #[getter]
+    fn idempotency_key(&self) -> Option<String> {
+        self.flat_metadata(|m| m.idempotency_key.clone())
+            .or_else(|| self.idempotency_key.clone())
+    }
+
+    #[getter]
+    fn rate_limit<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        self.flat_metadata(|m| m.rate_limit)
+            .map(|limit| rate_limit_to_py(py, &limit))
+            .transpose()
+    }
+
+    #[getter]
+    fn created(&self) -> Option<u64> {
+        self.flat_metadata(|m| m.created)
+    }
+
+    #[getter]
+    fn prompt_tokens(&self) -> Option<u64> {
+        self.flat_metadata(|m| m.usage.as_ref().and_then(|u| u.prompt_tokens))
+    }
+
+    #[getter]
+    fn completion_tokens(&self) -> Option<u64> {
+        self.flat_metadata(|m| m.usage.as_ref().and_then(|u| u.completion_tokens))
+    }
+
+    #[getter]
+    fn total_tokens(&self) -> Option<u64> {
+        self.flat_metadata(|m| m.usage.as_ref().and_then(|u| u.total_tokens))
+    }
+
+    /// Prompt tokens served from the provider's prompt cache, or ``None``
+    /// until the stream finishes or if not reported.
+    #[getter]
+    fn cached_prompt_tokens(&self) -> Option<u64> {
+        self.flat_metadata(|m| {
+            m.usage
+                .as_ref()?
+                .prompt_tokens_details
+                .as_ref()?
+                .cached_tokens
+        })
+    }
+
+    /// Completion tokens spent on reasoning, or ``None`` until the stream
+    /// finishes or if not reported.
+    #[getter]
+    fn reasoning_tokens(&self) -> Option<u64> {
+        self.flat_metadata(|m| {
+            m.usage
+                .as_ref()?
+                .completion_tokens_details
+                .as_ref()?
+                .reasoning_tokens
+        })
+    }
+
+    /// What the stream cost in USD as the provider reports it, or
+    /// ``None``. Requires ``include_cost=True`` on OpenRouter.
+    #[getter]
+    fn cost_usd(&self) -> Option<f64> {
+        self.flat_metadata(|m| m.usage.as_ref().and_then(|u| u.cost))
+    }
+
+    #[getter]
+    fn finish_reason(&self) -> Option<String> {
+        self.flat_metadata(|m| m.finish_reason.clone())
+    }
+
+    /// The model's refusal message, joined from the chunks received so
+    /// far, or ``None``.
+    #[getter]
+    fn refusal(&self) -> Option<String> {
+        self.flat_metadata(|m| m.refusal.clone())
+    }
+
+    /// Citations a web-search model attached to its answer, collected from
+    /// the chunks received so far, as dicts with ``type``, ``url``,
+    /// ``title``, ``start_index`` and ``end_index``.
+    #[getter]
+    fn annotations(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let annotations = self
+            .flat_metadata(|m| Some(m.annotations.clone()))
+            .unwrap_or_default();
+        annotations_to_py(py, &annotations)
+    }
+
+    /// Whether content filtering stopped the stream (``finish_reason``
+    /// ``"content_filter"``) or the model refused.
+    #[getter]
+    fn was_filtered(&self) -> bool {
+        self.flat_metadata(|m| {
+            Some(content_filtered(
+                m.finish_reason.as_deref(),
+                m.refusal.as_deref(),
+            ))
+        })
+        .unwrap_or(false)
+    }
+
+    /// The model that served the stream, as the provider reports it, or
+    /// else the model the request was sent to.
+    #[getter]
+    fn model(&self) -> Option<String> {
+        self.flat_metadata(|m| {
+            m.model
+                .clone()
+                .or_else(|| m.attempted_models.last().cloned())
+        })
+    }
+
+    /// The models the stream was sent to in turn: the provider's
+    /// ``model``, then any ``fallback_models`` tried after it failed.
+    /// Empty until the response starts.
+    #[getter]
+    fn attempted_models(&self) -> Vec<String> {
+        self.flat_metadata(|m| Some(m.attempted_models.clone()))
+            .unwrap_or_default()
+    }
+
+    /// Whether a fallback model served the stream.
+    #[getter]
+    fn fallback_used(&self) -> bool {
+        self.attempted_models().len() > 1
+    }
+
+    #[getter]
+    fn service_tier(&self) -> Option<String> {
+        self.flat_metadata(|m| m.service_tier.clone())
+    }
+
+    #[getter]
+    fn system_fingerprint(&self) -> Option<String> {
+        self.flat_metadata(|m| m.system_fingerprint.clone())
+    }
+
+    #[getter]
+    fn time_to_first_chunk_ms(&self) -> Option<f64> {
+        self.flat_metadata(|m| m.timings?.time_to_first_chunk)
+            .map(|duration| duration.as_secs_f64() * 1000.0)
+    }
+
+    #[getter]
+    fn total_duration_ms(&self) -> Option<f64> {
+        self.flat_metadata(|m| m.timings)
+            .map(|timings| timings.total_duration.as_secs_f64() * 1000.0)
+    }
+
+    #[getter]
+    fn chunk_count(&self) -> Option<u64> {
+        self.flat_metadata(|m| m.timings.map(|timings| timings.chunk_count))
+    }
+
+    #[getter]
+    fn completion_tokens_per_second(&self) -> Option<f64> {
+        self.flat_metadata(|m| {
+            m.timings?
+                .tokens_per_second(m.usage.as_ref()?.completion_tokens?)
+        })
+    }
+
+    #[getter]
+    fn raw_event_callback_errors(&self) -> usize {
+        self.callback_errors.load(Ordering::Relaxed)
+    }
+
+    #[getter]
+    fn reasoning(&self) -> Option<String> {
+        let guard = self.reasoning.lock().ok()?;
+        (!guard.is_empty()).then(|| guard.clone())
+    }
+}
+
+impl AsyncTextStream {
+    fn flat_metadata<T>(&self, f: impl FnOnce(&StreamMetadata) -> Option<T>) -> Option<T> {
+        let guard = self.metadata.lock().ok()?;
+        let meta = guard.as_ref()?;
+        f(meta)
+    }
+}
+
+/// Core async streaming logic, called by `Provider.astream_text()`.
+pub fn run(
+    provider: &Provider,
+    body: ChatRequest,
+    settings: StreamSettings,
+) -> PyResult<AsyncTextStream> {
+    let (worker, handles) = stream::start(provider, body, settings)?;
+    Ok(AsyncTextStream {
+        receiver: Arc::new(tokio::sync::Mutex::new(handles.receiver)),
+        worker: aio::spawn(worker)?,
+        metadata: handles.metadata,
+        reasoning: handles.reasoning,
+        partial: handles.partial,
+        last_event_id: handles.last_event_id,
+        callback_errors: handles.callback_errors,
+        error: Arc::new(Mutex::new(None)),
+        idempotency_key: handles.idempotency_key,
+    })
+}
@@ -0,0 +1,315 @@
+use crate::errors::SdkError;
+use crate::generate;
+use crate::models::{self, ChatMessage, GenerationParams};
+use crate::provider::{
+    GenerateResult, Provider, extract_content, extract_messages, extract_stop, messages_to_py,
+};
+use crate::stream::{self, StreamSettings, TextStream};
+use pyo3::prelude::*;
+use pyo3::types::PyList;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+// ---------------------------------------------------------------------------
+// Chat pyclass
+// ---------------------------------------------------------------------------
+
+/// A conversation that keeps its own message history.
+///
+/// Created with ``Provider.chat()``. Each ``send`` or ``stream`` call sends
+/// the full history plus the new user message, then records both the user
+/// message and the assistant reply. Failed calls leave the history unchanged.
+///
+/// Examples (Python):
+///
+/// ```text
+/// chat = provider.chat(system_prompt="Be concise.")
+/// chat.send("What is Rust?")
+/// for chunk in chat.stream("And PyO3?"):
+///     print(chunk, end="")
+/// ```
+#[pyclass(frozen)]
+pub struct Chat {
+    provider: Provider,
+    initial: Vec<ChatMessage>,
+    history: Arc<Mutex<Vec<ChatMessage>>>,
+    busy: Arc<AtomicBool>,
+}
+
+impl Chat {
+    pub(crate) fn new(provider: Provider, initial: Vec<ChatMessage>) -> Self {
+        Self {
+            provider,
+            history: Arc::new(Mutex::new(initial.clone())),
+            initial,
+            busy: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn history(&self) -> PyResult<std::sync::MutexGuard<'_, Vec<ChatMessage>>> {
+        self.history
+            .lock()
+            .map_err(|_| SdkError::runtime("Chat history is unavailable.").into_pyerr())
+    }
+
+    /// Claim the chat for one turn and build the request parameters.
+    fn begin_turn(
+        &self,
+        content: &Bound<'_, PyAny>,
+        temperature: Option<f64>,
+        max_tokens: Option<u64>,
+        top_p: Option<f64>,
+        stop: Option<&Bound<'_, PyAny>>,
+        seed: Option<i64>,
+    ) -> PyResult<(ChatTurn, GenerationParams)> {
+        let user = ChatMessage {
+            role: "user".to_string(),
+            content: extract_content(content, "'content'")?,
+            ..Default::default()
+        };
+        let mut messages = self.history()?.clone();
+        messages.push(user.clone());
+        let params = GenerationParams {
+            messages,
+            temperature,
+            max_tokens,
+            top_p,
+            stop: stop.map(extract_stop).transpose()?,
+            seed,
+            ..Default::default()
+        };
+        params.validate().map_err(SdkError::into_pyerr)?;
+
+        if self
+            .busy
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            return Err(SdkError::runtime(
+                "Chat is already processing a message; wait for it to finish \
+                 (or finish iterating its stream) before sending another.",
+            )
+            .into_pyerr());
+        }
+        let turn = ChatTurn {
+            history: Arc::clone(&self.history),
+            busy: Arc::clone(&self.busy),
+            user,
+            reply: String::new(),
+        };
+
+        Ok((turn, params))
+    }
+}
+
+#[pymethods]
+impl Chat {
+    /// Send a user message and return the assistant's reply (blocking).
+    ///
+    /// Args:
+    ///     content (str | list[dict]): The user message text or content parts.
+    ///     include_usage (bool): Return a ``GenerateResult`` instead of ``str``.
+    ///
+    /// Also accepts ``temperature``, ``max_tokens``, ``top_p``, ``stop``, and
+    /// ``seed`` as in ``generate_text``.
+    ///
+    /// Raises:
+    ///     RuntimeError: If another ``send`` or ``stream`` on this chat is
+    ///         still in progress, or the API returns a non-2xx status code.
+    #[expect(clippy::too_many_arguments)] // PyO3 requires flat params for Python kwargs
+    #[pyo3(signature = (content, *, temperature=None, max_tokens=None, top_p=None, stop=None, seed=None, include_usage=false))]
+    #[pyo3(
+        text_signature = "(self, content, *, temperature=None, max_tokens=None, top_p=None, stop=None, seed=None, include_usage=False)"
+    )]
+    fn send(
+        &self,
+        py: Python<'_>,
+        content: &Bound<'_, PyAny>,
+        temperature: Option<f64>,
+        max_tokens: Option<u64>,
+        top_p: Option<f64>,
+        stop: Option<&Bound<'_, PyAny>>,
+        seed: Option<i64>,
+        include_usage: bool,
+    ) -> PyResult<Py<PyAny>> {
+        let (mut turn, params) =
+            self.begin_turn(content, temperature, max_tokens, top_p, stop, seed)?;
+
+        if include_usage {
+            let result = generate::run_full(&self.provider, params)?;
+            turn.push(&result.text);
+            turn.commit();
+            Ok(GenerateResult::from_parsed(result)
+                .into_pyobject(py)?
+                .into_any()
+                .unbind())
+        } else {
+            let text = generate::run(&self.provider, params)?;
+            turn.push(&text);
+            turn.commit();
+            Ok(text.into_pyobject(py)?.into_any().unbind())
+        }
+    }
+
+    /// Send a user message and stream the assistant's reply.
+    ///
+    /// The reply is added to the history once the stream has been fully
+    /// consumed. The chat stays busy until then; an abandoned or failed
+    /// stream leaves the history unchanged.
+    ///
+    /// Accepts the same parameters as ``send``.
+    #[expect(clippy::too_many_arguments)] // PyO3 requires flat params for Python kwargs
+    #[pyo3(signature = (content, *, temperature=None, max_tokens=None, top_p=None, stop=None, seed=None, include_usage=false))]
+    #[pyo3(
+        text_signature = "(self, content, *, temperature=None, max_tokens=None, top_p=None, stop=None, seed=None, include_usage=False)"
+    )]
+    fn stream(
+        &self,
+        content: &Bound<'_, PyAny>,
+        temperature: Option<f64>,
+        max_tokens: Option<u64>,
+        top_p: Option<f64>,
+        stop: Option<&Bound<'_, PyAny>>,
+        seed: Option<i64>,
+        include_usage: bool,
+    ) -> PyResult<TextStream> {
+        let (turn, params) =
+            self.begin_turn(content, temperature, max_tokens, top_p, stop, seed)?;
+
+        let settings = StreamSettings {
+            reconnects: self.provider.stream_reconnects,
+            ..Default::default()
+        };
+        let stream = if include_usage {
+            stream::run_with_metadata(&self.provider, params, settings)?
+        } else {
+            stream::run(&self.provider, params, settings)?
+        };
+        Ok(stream.with_turn(turn))
+    }
+
+    /// The conversation so far as a list of message dicts.
+    #[getter]
+    fn messages<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyList>> {
+        messages_to_py(py, &self.history()?)
+    }
+
+    /// Serialise the history as JSON (see ``messages_to_json``).
+    fn to_json(&self) -> PyResult<String> {
+        models::messages_to_json(&self.history()?).map_err(SdkError::into_pyerr)
+    }
+
+    /// Resume a chat from JSON written by ``to_json``.
+    ///
+    /// The loaded messages become the chat's starting point, so ``reset()``
+    /// returns to them.
+    ///
+    /// Args:
+    ///     provider (Provider): Provider to send new messages with.
+    ///     data (str): JSON from ``Chat.to_json()`` or ``messages_to_json()``.
+    ///
+    /// Raises:
+    ///     ValueError: If ``data`` is not valid chat history JSON.
+    #[staticmethod]
+    #[pyo3(text_signature = "(provider, data)")]
+    fn from_json(provider: Provider, data: &str) -> PyResult<Self> {
+        let messages = models::messages_from_json(data).map_err(SdkError::into_pyerr)?;
+        Ok(Self::new(provider, messages))
+    }
+
+    /// Clear the history back to the messages the chat was created with.
+    fn reset(&self) -> PyResult<()> {
+        if self.busy.load(Ordering::Acquire) {
+            return Err(
+                SdkError::runtime("Cannot reset a chat while a message is in progress.")
+                    .into_pyerr(),
+            );
+        }
+        *self.history()? = self.initial.clone();
+        Ok(())
+    }
+
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(self.history()?.len())
+    }
+
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!(
+            "Chat(model='{}', messages={})",
+            self.provider.model,
+            self.history()?.len()
+        ))
+    }
+}
+
+/// One in-flight exchange on a `Chat`.
+///
+/// Holds the chat's busy flag until dropped. `commit` records the user
+/// message and the accumulated reply; dropping without committing (on error
+/// or an abandoned stream) leaves the history untouched.
+pub(crate) struct ChatTurn {
+    history: Arc<Mutex<Vec<ChatMessage>>>,
+    busy: Arc<AtomicBool>,
+    user: ChatMessage,
+    reply: String,
+}
+
+impl ChatTurn {
+    pub(crate) fn push(&mut self, chunk: &str) {
+        self.reply.push_str(chunk);
+    }
+
+    pub(crate) fn commit(mut self) {
+        if let Ok(mut history) = self.history.lock() {
+            history.push(std::mem::take(&mut self.user));
+            history.push(ChatMessage {
+                role: "assistant".to_string(),
+                content: std::mem::take(&mut self.reply).into(),
+                ..Default::default()
+            });
+        }
+    }
+}
+
+impl Drop for ChatTurn {
+    fn drop(&mut self) {
+        self.busy.store(false, Ordering::Release);
+    }
+}
+
+/// Serialise messages to a stable JSON format for storage.
+///
+/// The output is ``{"version": 1, "messages": [...]}``, where each message
+/// keeps its ``name``, ``tool_call_id``, and content parts.
+///
+/// Args:
+///     messages (list[dict | Message]): The messages to serialise.
+///
+/// Returns:
+///     str: The JSON document.
+#[pyfunction]
+#[pyo3(name = "messages_to_json")]
+#[pyo3(text_signature = "(messages)")]
+pub fn py_messages_to_json(messages: &Bound<'_, PyList>) -> PyResult<String> {
+    models::messages_to_json(&extract_messages(messages)?).map_err(SdkError::into_pyerr)
+}
+
+/// Parse messages written by ``messages_to_json``.
+///
+/// A bare JSON list of message dicts is also accepted.
+///
+/// Args:
+///     data (str): The JSON document.
+///
+/// Returns:
+///     list[dict]: The messages as dicts, ready for ``messages=``.
+///
+/// Raises:
+///     ValueError: If ``data`` is not valid chat history JSON.
+#[pyfunction]
+#[pyo3(name = "messages_from_json")]
+#[pyo3(text_signature = "(data)")]
+pub fn py_messages_from_json<'py>(py: Python<'py>, data: &str) -> PyResult<Bound<'py, PyList>> {
+    let messages = models::messages_from_json(data).map_err(SdkError::into_pyerr)?;
+    messages_to_py(py, &messages)
+}
@@ -0,0 +1,227 @@
+use crate::errors::SdkError;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Source of the current time, replaced in tests to step through the
+/// cool-down without sleeping.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+pub(crate) struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A circuit's state, as reported by `Provider.circuit_state()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls go through, counting consecutive failures.
+    Closed,
+    /// Calls fail without a request until the cool-down ends.
+    Open,
+    /// The cool-down has ended; the next call is a probe that closes the
+    /// circuit on success and opens it again on failure.
+    HalfOpen,
+}
+
+impl CircuitState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Closed => "closed",
+            Self::Open => "open",
+            Self::HalfOpen => "half_open",
+        }
+    }
+}
+
+enum Phase {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen { probing: bool },
+}
+
+/// Default cool-down of an open circuit.
+pub const DEFAULT_CIRCUIT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Opens after `threshold` consecutive failed calls and refuses calls for
+/// `cooldown`, then lets one probe through. Copies of a provider share the
+/// state, whatever their settings.
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    /// Consecutive failures that open the circuit; `0` turns it off.
+    threshold: u32,
+    cooldown: Duration,
+    phase: Arc<Mutex<Phase>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self {
+            threshold: 0,
+            cooldown: DEFAULT_CIRCUIT_COOLDOWN,
+            phase: Arc::new(Mutex::new(Phase::Closed { failures: 0 })),
+            clock: Arc::new(SystemClock),
+        }
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self::default().with_settings(threshold, cooldown)
+    }
+
+    /// This breaker opening after `threshold` failures (`0` turns it off)
+    /// for `cooldown`, keeping the shared state.
+    pub fn with_settings(&self, threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self { clock, ..self }
+    }
+
+    pub fn threshold(&self) -> u32 {
+        self.threshold
+    }
+
+    pub fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+
+    pub fn state(&self) -> CircuitState {
+        let Ok(phase) = self.phase.lock() else {
+            return CircuitState::Closed;
+        };
+        match *phase {
+            Phase::Closed { .. } => CircuitState::Closed,
+            Phase::Open { until } if self.clock.now() < until => CircuitState::Open,
+            Phase::Open { .. } | Phase::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Admit a call, or fail with `CircuitOpen` while the circuit is open
+    /// or another call is probing it. The call reports its outcome through
+    /// the permit.
+    pub fn permit(&self) -> Result<CircuitPermit, SdkError> {
+        if self.threshold == 0 {
+            return Ok(CircuitPermit::default());
+        }
+        let Ok(mut phase) = self.phase.lock() else {
+            return Ok(CircuitPermit::default());
+        };
+        let now = self.clock.now();
+        let probe = match *phase {
+            Phase::Closed { .. } => false,
+            Phase::Open { until } if now < until => {
+                let remaining = until - now;
+                return Err(SdkError::circuit_open(
+                    format!(
+                        "Circuit breaker is open after {} consecutive failures; \
+                         calls fail for another {:.1}s.",
+                        self.threshold,
+                        remaining.as_secs_f64()
+                    ),
+                    remaining,
+                ));
+            }
+            Phase::HalfOpen { probing: true } => {
+                return Err(SdkError::circuit_open(
+                    "Circuit breaker is half-open and a probe request is in flight.",
+                    Duration::ZERO,
+                ));
+            }
+            Phase::Open { .. } | Phase::HalfOpen { probing: false } => {
+                *phase = Phase::HalfOpen { probing: true };
+                true
+            }
+        };
+        Ok(CircuitPermit {
+            breaker: Some(self.clone()),
+            probe,
+        })
+    }
+
+    fn record(&self, failed: bool) {
+        if self.threshold == 0 {
+            return;
+        }
+        let Ok(mut phase) = self.phase.lock() else {
+            return;
+        };
+        let failures = match *phase {
+            Phase::Closed { failures } if failed => failures + 1,
+            Phase::Closed { .. } => 0,
+            // A call admitted before the circuit opened does not change it.
+            Phase::Open { .. } => return,
+            Phase::HalfOpen { .. } if failed => self.threshold,
+            Phase::HalfOpen { .. } => 0,
+        };
+        *phase = if failures >= self.threshold {
+            Phase::Open {
+                until: self.clock.now() + self.cooldown,
+            }
+        } else {
+            Phase::Closed { failures }
+        };
+    }
+
+    /// Let another call probe, after the probing one ended without an
+    /// outcome, e.g. when it was cancelled.
+    fn release_probe(&self) {
+        if let Ok(mut phase) = self.phase.lock()
+            && let Phase::HalfOpen { probing: true } = *phase
+        {
+            *phase = Phase::HalfOpen { probing: false };
+        }
+    }
+}
+
+/// A call admitted by `CircuitBreaker::permit`. Dropping it without an
+/// outcome leaves the counts alone.
+#[derive(Default)]
+pub struct CircuitPermit {
+    breaker: Option<CircuitBreaker>,
+    probe: bool,
+}
+
+impl CircuitPermit {
+    pub fn success(mut self) {
+        if let Some(breaker) = self.breaker.take() {
+            breaker.record(false);
+        }
+    }
+
+    pub fn failure(mut self) {
+        if let Some(breaker) = self.breaker.take() {
+            breaker.record(true);
+        }
+    }
+
+    /// Report how the call ended: errors that are the provider's failures
+    /// count against it, and anything else shows it is up.
+    pub fn finish(self, error: Option<&SdkError>) {
+        match error {
+            Some(error) if error.is_provider_failure() => self.failure(),
+            _ => self.success(),
+        }
+    }
+}
+
+impl Drop for CircuitPermit {
+    fn drop(&mut self) {
+        if let Some(breaker) = self.breaker.take()
+            && self.probe
+        {
+            breaker.release_probe();
+        }
+    }
+}
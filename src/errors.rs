@@ -1,11 +1,213 @@
-use pyo3::PyErr;
-use pyo3::exceptions::{PyConnectionError, PyRuntimeError, PyValueError};
+use pyo3::exceptions::{
+    PyConnectionError, PyRuntimeError, PyTimeoutError, PyTypeError, PyValueError,
+};
+use pyo3::prelude::*;
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+pyo3::create_exception!(
+    rusty_agent_sdk,
+    ContextLengthExceededError,
+    PyRuntimeError,
+    "Raised when a request does not fit the model's context window.\n\n\
+     A ``RuntimeError`` subclass with the usual API error attributes, plus\n\
+     ``max_context_tokens`` and ``requested_tokens`` when the provider's\n\
+     message states them."
+);
+
+pyo3::create_exception!(
+    rusty_agent_sdk,
+    ContentFilterError,
+    PyRuntimeError,
+    "Raised with ``raise_on_content_filter=True`` when the provider's\n\
+     content filter stopped the response or the model refused. ``refusal``\n\
+     holds the model's refusal message, or ``None``."
+);
+
+pyo3::create_exception!(
+    rusty_agent_sdk,
+    CircuitOpenError,
+    PyConnectionError,
+    "Raised without sending a request while the provider's circuit breaker\n\
+     is open. A ``ConnectionError`` subclass; ``remaining`` holds the\n\
+     seconds until a probe request is let through."
+);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum SdkError {
     Connection(String),
     Runtime(String),
     Value(String),
+    Type(String),
+    Timeout(String),
+    Api(Box<ApiError>),
+    /// A response stopped by content filtering or refused by the model,
+    /// with the refusal message if there was one.
+    ContentFilter {
+        message: String,
+        refusal: Option<String>,
+    },
+    /// A call refused by an open circuit breaker, `remaining` before it
+    /// lets a probe through.
+    CircuitOpen {
+        message: String,
+        remaining: Duration,
+    },
+    /// An error made from `Cause`, which becomes the exception's
+    /// `__cause__`.
+    Caused(Box<SdkError>, Cause),
+    /// The error of the last model tried, with every model tried in turn,
+    /// set as the exception's `attempted_models` attribute.
+    Attempted(Box<SdkError>, Vec<String>),
+    /// An error from a request sent with this `Idempotency-Key`, which is
+    /// set as the exception's `idempotency_key` attribute.
+    Keyed(Box<SdkError>, String),
+    /// An error reading this response body, which is set as the
+    /// exception's `body` attribute.
+    Unparsed(Box<SdkError>, String),
+}
+
+/// Longest response body kept on a parse error.
+pub const MAX_ERROR_BODY_BYTES: usize = 4096;
+
+/// `text` with likely credentials replaced by `[REDACTED]`: the token after
+/// `Bearer`, and long tokens with a known key prefix such as `sk-`, which
+/// some gateways echo back in their responses.
+pub fn redact_secrets(text: &str) -> Cow<'_, str> {
+    const KEY_PREFIXES: [&str; 3] = ["sk-", "sk_", "AIza"];
+    const MIN_KEY_LEN: usize = 20;
+    let is_token_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.');
+
+    let mut redacted = String::new();
+    let mut copied = 0;
+    let mut after_bearer = false;
+    let mut rest = text;
+    while let Some(start) = rest.find(is_token_char) {
+        let offset = text.len() - rest.len() + start;
+        let token_len = rest[start..]
+            .find(|c| !is_token_char(c))
+            .unwrap_or(rest.len() - start);
+        let token = &rest[start..start + token_len];
+        let secret = after_bearer
+            || (token.len() >= MIN_KEY_LEN
+                && KEY_PREFIXES.iter().any(|prefix| token.starts_with(prefix)));
+        if secret {
+            redacted.push_str(&text[copied..offset]);
+            redacted.push_str("[REDACTED]");
+            copied = offset + token_len;
+        }
+        after_bearer = token.eq_ignore_ascii_case("bearer");
+        rest = &rest[start + token_len..];
+    }
+    if copied == 0 {
+        return Cow::Borrowed(text);
+    }
+    redacted.push_str(&text[copied..]);
+    Cow::Owned(redacted)
+}
+
+/// `body` as kept on an error: secrets redacted, then cut to
+/// `MAX_ERROR_BODY_BYTES` with a note of the full length.
+pub fn quoted_body(body: &str) -> String {
+    let body = redact_secrets(body);
+    let mut end = body.len().min(MAX_ERROR_BODY_BYTES);
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    if end == body.len() {
+        return body.into_owned();
+    }
+    format!("{} ... ({} bytes total)", &body[..end], body.len())
+}
+
+/// An error response from the provider. Raised as `RuntimeError`, with the
+/// fields other than `message` set as attributes on the exception.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiError {
+    pub message: String,
+    pub status_code: u16,
+    pub code: Option<String>,
+    pub error_type: Option<String>,
+    pub body: String,
+    pub request_id: Option<String>,
+    /// Set when the error says the request overflowed the context window,
+    /// which raises `ContextLengthExceededError` instead.
+    pub context_length: Option<ContextLength>,
+}
+
+/// The Rust error an `SdkError` was made from. Raised exceptions chain it,
+/// and its sources, as `__cause__`, and transport errors also set `kind`.
+#[derive(Clone)]
+pub struct Cause {
+    error: Arc<dyn Error + Send + Sync>,
+    kind: Option<&'static str>,
+}
+
+impl Cause {
+    pub fn new(error: impl Error + Send + Sync + 'static) -> Self {
+        Self {
+            error: Arc::new(error),
+            kind: None,
+        }
+    }
+
+    /// Set as the exception's `kind` attribute, e.g. `"dns"` or `"tls"`.
+    pub fn with_kind(self, kind: &'static str) -> Self {
+        Self {
+            kind: Some(kind),
+            ..self
+        }
+    }
+
+    pub fn kind(&self) -> Option<&'static str> {
+        self.kind
+    }
+
+    fn into_pyerr(self) -> PyErr {
+        chained_pyerr(self.error.as_ref())
+    }
+}
+
+impl fmt::Debug for Cause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cause")
+            .field("error", &self.error.to_string())
+            .field("kind", &self.kind)
+            .finish()
+    }
+}
+
+/// Causes compare by message, as the errors themselves are not comparable.
+impl PartialEq for Cause {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.error.to_string() == other.error.to_string()
+    }
+}
+
+impl Eq for Cause {}
+
+/// `error` as a Python exception, with its sources chained as `__cause__`.
+/// I/O errors become the matching `OSError` subclass, such as
+/// `ConnectionRefusedError`; others become `RuntimeError`.
+fn chained_pyerr(error: &(dyn Error + 'static)) -> PyErr {
+    let err = match error.downcast_ref::<std::io::Error>() {
+        Some(io) => PyErr::from(std::io::Error::new(io.kind(), io.to_string())),
+        None => PyRuntimeError::new_err(error.to_string()),
+    };
+    if let Some(source) = error.source() {
+        Python::attach(|py| err.set_cause(py, Some(chained_pyerr(source))));
+    }
+    err
+}
+
+/// Token counts from a context-length error, where the message gives them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ContextLength {
+    pub max_context_tokens: Option<u64>,
+    pub requested_tokens: Option<u64>,
 }
 
 impl SdkError {
@@ -21,11 +223,208 @@ impl SdkError {
         Self::Value(message.into())
     }
 
+    pub fn type_error(message: impl Into<String>) -> Self {
+        Self::Type(message.into())
+    }
+
+    pub fn timeout(message: impl Into<String>) -> Self {
+        Self::Timeout(message.into())
+    }
+
+    pub fn api(error: ApiError) -> Self {
+        Self::Api(Box::new(error))
+    }
+
+    pub fn content_filter(refusal: Option<String>) -> Self {
+        let message = match &refusal {
+            Some(refusal) => format!("The model refused the request: {}", refusal),
+            None => "The response was stopped by the provider's content filter.".to_string(),
+        };
+        Self::ContentFilter { message, refusal }
+    }
+
+    pub fn circuit_open(message: impl Into<String>, remaining: Duration) -> Self {
+        Self::CircuitOpen {
+            message: message.into(),
+            remaining,
+        }
+    }
+
+    /// Whether the error points at the provider rather than the request:
+    /// transport errors, timeouts, and 429 or 5xx responses. These count
+    /// towards opening the circuit breaker.
+    pub fn is_provider_failure(&self) -> bool {
+        match self {
+            Self::Connection(_) | Self::Timeout(_) | Self::Caused(..) => true,
+            Self::Api(error) => error.status_code == 429 || error.status_code >= 500,
+            Self::Attempted(error, _) | Self::Keyed(error, _) | Self::Unparsed(error, _) => {
+                error.is_provider_failure()
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether another model may not hit the error: 404, 429 or 5xx
+    /// responses, which move a call on to its fallback models.
+    pub fn warrants_fallback(&self) -> bool {
+        match self {
+            Self::Api(error) => matches!(error.status_code, 404 | 429 | 500..),
+            Self::Caused(error, _) | Self::Keyed(error, _) | Self::Unparsed(error, _) => {
+                error.warrants_fallback()
+            }
+            _ => false,
+        }
+    }
+
+    /// Record the models a call tried, naming them in the message when
+    /// there was more than one.
+    pub fn with_attempted_models(self, models: Vec<String>) -> Self {
+        let error = match models.len() {
+            0 | 1 => self,
+            _ => self.map_message(|message| {
+                format!("{} (tried models: {})", message, models.join(", "))
+            }),
+        };
+        Self::Attempted(Box::new(error), models)
+    }
+
+    /// Keep `cause`, the error this one was made from.
+    pub fn with_cause(self, cause: Cause) -> Self {
+        Self::Caused(Box::new(self), cause)
+    }
+
+    /// Keep the response `body` that could not be read, redacted and
+    /// truncated by `quoted_body`.
+    pub fn with_body(self, body: &str) -> Self {
+        Self::Unparsed(Box::new(self), quoted_body(body))
+    }
+
+    /// Tag the error with the `Idempotency-Key` its request was sent with.
+    pub fn with_idempotency_key(self, key: Option<&str>) -> Self {
+        match key {
+            Some(key) => Self::Keyed(Box::new(self), key.to_string()),
+            None => self,
+        }
+    }
+
+    /// Rewrite the message while keeping the error kind.
+    pub fn map_message(self, f: impl FnOnce(String) -> String) -> Self {
+        match self {
+            Self::Connection(message) => Self::Connection(f(message)),
+            Self::Runtime(message) => Self::Runtime(f(message)),
+            Self::Value(message) => Self::Value(f(message)),
+            Self::Type(message) => Self::Type(f(message)),
+            Self::Timeout(message) => Self::Timeout(f(message)),
+            Self::Api(mut error) => {
+                error.message = f(error.message);
+                Self::Api(error)
+            }
+            Self::ContentFilter { message, refusal } => Self::ContentFilter {
+                message: f(message),
+                refusal,
+            },
+            Self::CircuitOpen { message, remaining } => Self::CircuitOpen {
+                message: f(message),
+                remaining,
+            },
+            Self::Caused(error, cause) => Self::Caused(Box::new(error.map_message(f)), cause),
+            Self::Attempted(error, models) => {
+                Self::Attempted(Box::new(error.map_message(f)), models)
+            }
+            Self::Keyed(error, key) => Self::Keyed(Box::new(error.map_message(f)), key),
+            Self::Unparsed(error, body) => Self::Unparsed(Box::new(error.map_message(f)), body),
+        }
+    }
+
     pub fn into_pyerr(self) -> PyErr {
         match self {
             Self::Connection(message) => PyConnectionError::new_err(message),
             Self::Runtime(message) => PyRuntimeError::new_err(message),
             Self::Value(message) => PyValueError::new_err(message),
+            Self::Type(message) => PyTypeError::new_err(message),
+            Self::Timeout(message) => PyTimeoutError::new_err(message),
+            Self::Api(error) => Python::attach(|py| {
+                let ApiError {
+                    message,
+                    status_code,
+                    code,
+                    error_type,
+                    body,
+                    request_id,
+                    context_length,
+                } = *error;
+                let err = match context_length {
+                    Some(_) => ContextLengthExceededError::new_err(message),
+                    None => PyRuntimeError::new_err(message),
+                };
+                let value = err.value(py);
+                let attributes = value
+                    .setattr("status_code", status_code)
+                    .and_then(|()| value.setattr("code", code))
+                    .and_then(|()| value.setattr("error_type", error_type))
+                    .and_then(|()| value.setattr("body", body))
+                    .and_then(|()| value.setattr("request_id", request_id))
+                    .and_then(|()| match context_length {
+                        Some(tokens) => value
+                            .setattr("max_context_tokens", tokens.max_context_tokens)
+                            .and_then(|()| {
+                                value.setattr("requested_tokens", tokens.requested_tokens)
+                            }),
+                        None => Ok(()),
+                    });
+                match attributes {
+                    Ok(()) => err,
+                    Err(setattr_error) => setattr_error,
+                }
+            }),
+            Self::ContentFilter { message, refusal } => Python::attach(|py| {
+                let err = ContentFilterError::new_err(message);
+                match err.value(py).setattr("refusal", refusal) {
+                    Ok(()) => err,
+                    Err(setattr_error) => setattr_error,
+                }
+            }),
+            Self::CircuitOpen { message, remaining } => Python::attach(|py| {
+                let err = CircuitOpenError::new_err(message);
+                match err.value(py).setattr("remaining", remaining.as_secs_f64()) {
+                    Ok(()) => err,
+                    Err(setattr_error) => setattr_error,
+                }
+            }),
+            Self::Caused(error, cause) => {
+                let err = error.into_pyerr();
+                let kind = cause.kind();
+                Python::attach(|py| {
+                    err.set_cause(py, Some(cause.into_pyerr()));
+                    match kind.map(|kind| err.value(py).setattr("kind", kind)) {
+                        Some(Err(setattr_error)) => setattr_error,
+                        _ => err,
+                    }
+                })
+            }
+            Self::Attempted(error, models) => {
+                let err = error.into_pyerr();
+                Python::attach(
+                    |py| match err.value(py).setattr("attempted_models", models) {
+                        Ok(()) => err,
+                        Err(setattr_error) => setattr_error,
+                    },
+                )
+            }
+            Self::Keyed(error, key) => {
+                let err = error.into_pyerr();
+                Python::attach(|py| match err.value(py).setattr("idempotency_key", key) {
+                    Ok(()) => err,
+                    Err(setattr_error) => setattr_error,
+                })
+            }
+            Self::Unparsed(error, body) => {
+                let err = error.into_pyerr();
+                Python::attach(|py| match err.value(py).setattr("body", body) {
+                    Ok(()) => err,
+                    Err(setattr_error) => setattr_error,
+                })
+            }
         }
     }
 }
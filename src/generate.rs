@@ -1,92 +1,374 @@
+use crate::aio;
+use crate::anthropic;
+use crate::circuit::CircuitBreaker;
 use crate::errors::SdkError;
-use crate::http::{is_retryable_error, is_retryable_status, retry_delay};
+use crate::http::{Deadline, RetrySettings, RetryTally, idempotency_key, send_with_retries};
+use crate::limiter::{ConcurrencyLimit, RateLimiter, estimate_request_tokens};
 use crate::models::{
-    GenerationParams, ParsedChatResult, api_error_message, parse_chat_response,
-    parse_chat_response_full,
+    CallTimings, GenerationParams, ParsedChatResult, RateLimit, Usage, content_filter_error,
+    parse_chat_response, parse_chat_response_full,
 };
-use crate::provider::{Provider, build_chat_completions_url};
+use crate::provider::{ApiDialect, Fallback, GenerateResult, Provider};
+use crate::runtime;
+use crate::usage::UsageTracker;
+use futures_util::StreamExt;
+use futures_util::stream;
+use pyo3::IntoPyObjectExt;
 use pyo3::prelude::*;
-use tokio::time::sleep;
+use reqwest::header::HeaderMap;
+use serde::Deserialize;
+use serde_json::Value;
+use std::future::Future;
+use std::time::Instant;
 
 /// Core generation logic, called by `Provider.generate_text()`.
 pub fn run(provider: &Provider, params: GenerationParams) -> PyResult<String> {
-    let body = params.into_chat_request(provider.model.clone(), None, None);
-    run_request(provider, &body, parse_chat_response)
+    block_on(Request::new(provider, params)?.send(parse_text))
 }
 
 /// Generation with full metadata, called by `Provider.generate_text(include_usage=True)`.
 pub fn run_full(provider: &Provider, params: GenerationParams) -> PyResult<ParsedChatResult> {
-    let body = params.into_chat_request(provider.model.clone(), None, None);
-    run_request(provider, &body, parse_chat_response_full)
+    block_on(Request::new(provider, params)?.send(parse_full))
 }
 
-fn run_request<T>(
+fn parse_text(response_text: &str, _ids: RequestIds) -> Result<String, SdkError> {
+    parse_chat_response(response_text)
+}
+
+fn parse_full(response_text: &str, ids: RequestIds) -> Result<ParsedChatResult, SdkError> {
+    let mut result = parse_chat_response_full(response_text)?;
+    result.request_id = ids.request_id;
+    result.idempotency_key = ids.idempotency_key;
+    result.model = result
+        .model
+        .or_else(|| ids.attempted_models.last().cloned());
+    result.attempted_models = ids.attempted_models;
+    result.rate_limit = Some(ids.rate_limit);
+    result.timings = Some(ids.timings);
+    if let Some(body) = ids.dialect_body {
+        result.raw = serde_json::from_str(&body).ok();
+    }
+    Ok(result)
+}
+
+/// The ids a successful response is passed to `parse` with.
+struct RequestIds {
+    /// The provider's id, from the response headers.
+    request_id: Option<String>,
+    /// The `Idempotency-Key` the request was sent with.
+    idempotency_key: Option<String>,
+    /// The models sent to in turn, the last of which answered.
+    attempted_models: Vec<String>,
+    /// From the response headers.
+    rate_limit: RateLimit,
+    /// The provider's own response body, when it was translated into the
+    /// OpenAI shape before parsing.
+    dialect_body: Option<String>,
+    /// The call's timings up to the successful response.
+    timings: CallTimings,
+}
+
+/// Counts a call's attempts and retry waits across the models it tries.
+struct CallClock {
+    started: Instant,
+    tally: RetryTally,
+}
+
+impl CallClock {
+    fn start() -> Self {
+        Self {
+            started: Instant::now(),
+            tally: RetryTally::default(),
+        }
+    }
+
+    fn timings(&self) -> CallTimings {
+        CallTimings {
+            total_duration: self.started.elapsed(),
+            attempts: self.tally.attempts,
+            retry_wait: self.tally.retry_wait,
+        }
+    }
+}
+
+/// Start a generation on the shared runtime, called by
+/// `Provider.agenerate_text()`. Returns an `asyncio.Future` resolving to
+/// `str`, or to `GenerateResult` with `include_usage`.
+pub fn spawn<'py>(
+    py: Python<'py>,
     provider: &Provider,
-    body: &crate::models::ChatRequest,
-    parse: impl FnOnce(&str) -> Result<T, SdkError>,
-) -> PyResult<T> {
-    let url = build_chat_completions_url(&provider.base_url);
-    let api_key = provider.api_key.clone();
-    let request_timeout = provider.request_timeout;
-    let connect_timeout = provider.connect_timeout;
-    let max_retries = provider.max_retries;
-    let retry_backoff = provider.retry_backoff;
-    let body_json =
-        serde_json::to_value(body).map_err(|e| SdkError::runtime(e.to_string()).into_pyerr())?;
-
-    let runtime = tokio::runtime::Runtime::new()
-        .map_err(|e| SdkError::runtime(e.to_string()).into_pyerr())?;
-
-    runtime
-        .block_on(async move {
-            let client = reqwest::Client::builder()
-                .connect_timeout(connect_timeout)
-                .build()
-                .map_err(|e| SdkError::runtime(e.to_string()))?;
-
-            for attempt in 0..=max_retries {
-                let response_result = client
-                    .post(&url)
-                    .header("Authorization", format!("Bearer {}", api_key))
-                    .header("Content-Type", "application/json")
-                    .timeout(request_timeout)
-                    .json(&body_json)
-                    .send()
-                    .await;
-
-                match response_result {
-                    Ok(response) => {
-                        let status = response.status();
-                        let response_text = response
-                            .text()
-                            .await
-                            .map_err(|e| SdkError::runtime(e.to_string()))?;
-
-                        if status.is_success() {
-                            return parse(&response_text);
-                        }
-
-                        if is_retryable_status(status) && attempt < max_retries {
-                            sleep(retry_delay(retry_backoff, attempt)).await;
-                            continue;
-                        }
-
-                        return Err(SdkError::runtime(api_error_message(status, &response_text)));
-                    }
-                    Err(error) => {
-                        if is_retryable_error(&error) && attempt < max_retries {
-                            sleep(retry_delay(retry_backoff, attempt)).await;
-                            continue;
-                        }
-
-                        return Err(SdkError::connection(error.to_string()));
-                    }
+    params: GenerationParams,
+    include_usage: bool,
+) -> PyResult<Bound<'py, PyAny>> {
+    let request = Request::new(provider, params)?;
+    if include_usage {
+        aio::future_into_py(py, async move {
+            request
+                .send(parse_full)
+                .await
+                .map(GenerateResult::from_parsed)
+                .map_err(SdkError::into_pyerr)
+        })
+    } else {
+        aio::future_into_py(py, async move {
+            request.send(parse_text).await.map_err(SdkError::into_pyerr)
+        })
+    }
+}
+
+/// Batch generation, called by `Provider.generate_many()`.
+///
+/// Sends up to `concurrency` requests at a time on the shared runtime, with
+/// the GIL released, and returns one item per request in input order: the
+/// text (or `GenerateResult` with `include_usage`), or the exception that
+/// request failed with.
+pub fn run_many(
+    py: Python<'_>,
+    provider: &Provider,
+    params: Vec<GenerationParams>,
+    concurrency: usize,
+    include_usage: bool,
+) -> PyResult<Vec<Py<PyAny>>> {
+    let requests = params
+        .into_iter()
+        .map(|params| Request::new(provider, params))
+        .collect::<PyResult<Vec<_>>>()?;
+    if include_usage {
+        let results = py
+            .detach(|| runtime::block_on(send_all(requests, concurrency, parse_full)))
+            .map_err(SdkError::into_pyerr)?;
+        results
+            .into_iter()
+            .map(|result| batch_item(py, result.map(GenerateResult::from_parsed)))
+            .collect()
+    } else {
+        let results = py
+            .detach(|| runtime::block_on(send_all(requests, concurrency, parse_text)))
+            .map_err(SdkError::into_pyerr)?;
+        results
+            .into_iter()
+            .map(|result| batch_item(py, result))
+            .collect()
+    }
+}
+
+/// Send `requests` with at most `concurrency` in flight, keeping their order.
+async fn send_all<T>(
+    requests: Vec<Request>,
+    concurrency: usize,
+    parse: impl Fn(&str, RequestIds) -> Result<T, SdkError> + Copy,
+) -> Vec<Result<T, SdkError>> {
+    stream::iter(requests)
+        .map(|request| request.send(parse))
+        .buffered(concurrency)
+        .collect()
+        .await
+}
+
+/// A batch result as a Python object, with a failure as its exception
+/// instance rather than raised.
+fn batch_item<T>(py: Python<'_>, result: Result<T, SdkError>) -> PyResult<Py<PyAny>>
+where
+    T: for<'a> IntoPyObject<'a>,
+{
+    match result {
+        Ok(value) => value.into_py_any(py),
+        Err(err) => Ok(err.into_pyerr().into_value(py).into_any()),
+    }
+}
+
+/// The `model` and `usage` a chat completions response reported.
+#[derive(Deserialize, Default)]
+struct Reported {
+    model: Option<String>,
+    usage: Option<Usage>,
+}
+
+fn reported_usage(response_text: &str) -> Reported {
+    serde_json::from_str(response_text).unwrap_or_default()
+}
+
+/// Run `future` with the GIL released, so other Python threads run while
+/// the call waits on the network, the rate limiter, or a concurrency slot.
+fn block_on<T: Send>(future: impl Future<Output = Result<T, SdkError>> + Send) -> PyResult<T> {
+    Python::attach(|py| py.detach(|| runtime::block_on(future)))
+        .and_then(|result| result)
+        .map_err(SdkError::into_pyerr)
+}
+
+/// A chat completions request with the provider settings it needs, owned so
+/// it can outlive the Python call that built it.
+struct Request {
+    model: String,
+    url: String,
+    headers: HeaderMap,
+    dialect: ApiDialect,
+    client: reqwest::Client,
+    retries: RetrySettings,
+    circuit: CircuitBreaker,
+    limiter: RateLimiter,
+    /// Reserved from the limiter's token bucket before the call.
+    estimated_tokens: u64,
+    concurrency: ConcurrencyLimit,
+    usage_tracker: Option<UsageTracker>,
+    idempotency_key: Option<String>,
+    raise_on_content_filter: bool,
+    body: Value,
+    /// Tried in turn after an error another model may not hit.
+    fallbacks: Vec<Fallback>,
+}
+
+impl Request {
+    fn new(provider: &Provider, params: GenerationParams) -> PyResult<Self> {
+        let body = params.into_chat_request(provider.model.clone(), None, None);
+        let model = body.primary_model().to_string();
+        let estimated_tokens = estimate_request_tokens(&body);
+        let headers = provider.headers().map_err(SdkError::into_pyerr)?;
+        let body = provider.request_body(&body).map_err(SdkError::into_pyerr)?;
+        Ok(Self {
+            model,
+            url: provider.request_url(),
+            idempotency_key: idempotency_key(&headers),
+            fallbacks: provider.fallbacks(&body, &headers),
+            headers,
+            dialect: provider.routing.dialect,
+            client: provider.client().map_err(SdkError::into_pyerr)?,
+            retries: provider.retry_settings(),
+            circuit: provider.circuit.clone(),
+            limiter: provider.limiter.clone(),
+            estimated_tokens,
+            concurrency: provider.concurrency.clone(),
+            usage_tracker: provider.usage_tracker.clone(),
+            raise_on_content_filter: provider.raise_on_content_filter,
+            body,
+        })
+    }
+
+    /// POST the request, retrying on retryable statuses and errors within
+    /// the `total_timeout` budget and then moving on to each fallback model
+    /// while the error warrants it, and parse the successful response with
+    /// `parse`, unless the circuit breaker is open. Waits first for the
+    /// client-side rate limiter, then holds a `max_concurrency` slot for
+    /// the rest of the call. A successful call is counted in the usage
+    /// tracker, if set. Errors carry the request's `Idempotency-Key`, and
+    /// the models tried if there were fallbacks.
+    async fn send<T>(
+        mut self,
+        parse: impl Fn(&str, RequestIds) -> Result<T, SdkError>,
+    ) -> Result<T, SdkError> {
+        let permit = self.circuit.permit()?;
+        let deadline = Deadline::start(self.retries.total_timeout);
+        let admitted = async {
+            let reservation = self
+                .limiter
+                .acquire(self.estimated_tokens, deadline.as_ref())
+                .await?;
+            let slot = self.concurrency.acquire(deadline.as_ref()).await?;
+            Ok::<_, SdkError>((reservation, slot))
+        };
+        let (reservation, _slot) = match admitted.await {
+            Ok(admitted) => admitted,
+            Err(error) => return Err(error.with_idempotency_key(self.idempotency_key.as_deref())),
+        };
+        let has_fallbacks = !self.fallbacks.is_empty();
+        let mut fallbacks = std::mem::take(&mut self.fallbacks).into_iter();
+        let mut attempted = vec![self.model.clone()];
+        let mut clock = CallClock::start();
+        let result = loop {
+            let result = self.attempt(&parse, deadline, &attempted, &mut clock).await;
+            match (result, fallbacks.next()) {
+                (Err(error), Some(fallback)) if error.warrants_fallback() => {
+                    attempted.push(fallback.model.clone());
+                    self.retarget(fallback);
                 }
+                (result, _) => break result,
+            }
+        };
+        let result = match result {
+            Ok((parsed, reported)) => {
+                let usage = reported.usage.as_ref();
+                reservation.settle(usage.and_then(|usage| usage.total_tokens));
+                if let Some(tracker) = &self.usage_tracker {
+                    tracker.record(reported.model.as_deref().unwrap_or(&self.model), usage);
+                }
+                Ok(parsed)
+            }
+            Err(error) => {
+                reservation.settle(None);
+                Err(error)
+            }
+        };
+        permit.finish(result.as_ref().err());
+        result.map_err(|error| {
+            let error = error.with_idempotency_key(self.idempotency_key.as_deref());
+            if has_fallbacks {
+                error.with_attempted_models(attempted)
+            } else {
+                error
             }
-
-            Err(SdkError::runtime(
-                "Request failed after retries were exhausted.",
-            ))
         })
-        .map_err(SdkError::into_pyerr)
+    }
+
+    /// Send the rest of the call to `fallback`'s model, under its own
+    /// `Idempotency-Key`.
+    fn retarget(&mut self, fallback: Fallback) {
+        self.model = fallback.model;
+        self.url = fallback.url;
+        self.body = fallback.body;
+        self.idempotency_key = idempotency_key(&fallback.headers);
+        self.headers = fallback.headers;
+    }
+
+    /// One model's attempts at the call, returning the parsed response and,
+    /// when the limiter counts tokens or a usage tracker is set, the model
+    /// and usage it reported. Attempts and retry waits are added to `clock`.
+    async fn attempt<T>(
+        &self,
+        parse: &impl Fn(&str, RequestIds) -> Result<T, SdkError>,
+        deadline: Option<Deadline>,
+        attempted: &[String],
+        clock: &mut CallClock,
+    ) -> Result<(T, Reported), SdkError> {
+        let response = send_with_retries(
+            |timeout| {
+                self.client
+                    .post(&self.url)
+                    .header("Content-Type", "application/json")
+                    .headers(self.headers.clone())
+                    .timeout(timeout)
+                    .json(&self.body)
+            },
+            &self.retries,
+            deadline,
+            &mut clock.tally,
+        )
+        .await?;
+
+        let (response_text, dialect_body) = match self.dialect {
+            ApiDialect::OpenAi => (response.text, None),
+            ApiDialect::Anthropic => (
+                anthropic::to_chat_response(&response.text)?,
+                Some(response.text),
+            ),
+        };
+        let ids = RequestIds {
+            request_id: response.request_id,
+            idempotency_key: self.idempotency_key.clone(),
+            attempted_models: attempted.to_vec(),
+            rate_limit: response.rate_limit,
+            dialect_body,
+            timings: clock.timings(),
+        };
+        if self.raise_on_content_filter
+            && let Some(error) = content_filter_error(&response_text)
+        {
+            return Err(error);
+        }
+        let reported = if self.limiter.tokens_per_minute() > 0 || self.usage_tracker.is_some() {
+            reported_usage(&response_text)
+        } else {
+            Reported::default()
+        };
+        parse(&response_text, ids).map(|parsed| (parsed, reported))
+    }
 }
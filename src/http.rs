@@ -1,22 +1,517 @@
-use reqwest::StatusCode;
-use std::time::Duration;
-
-pub fn is_retryable_status(status: StatusCode) -> bool {
-    matches!(
-        status,
-        StatusCode::TOO_MANY_REQUESTS
-            | StatusCode::INTERNAL_SERVER_ERROR
-            | StatusCode::BAD_GATEWAY
-            | StatusCode::SERVICE_UNAVAILABLE
-            | StatusCode::GATEWAY_TIMEOUT
-    )
+use crate::errors::{ApiError, Cause, SdkError};
+use crate::models::{RateLimit, api_error};
+use crate::retry_hook::{RetryCause, RetryHook};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::hash::{BuildHasher, RandomState};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::time::sleep;
+
+/// Statuses retried unless `retry_on_status` says otherwise.
+pub const DEFAULT_RETRY_STATUSES: [u16; 5] = [429, 500, 502, 503, 504];
+
+/// Whether a response with `status` is retried, given the configured
+/// `retry_on` set.
+pub fn is_retryable_status(status: StatusCode, retry_on: &[u16]) -> bool {
+    retry_on.contains(&status.as_u16())
 }
 
 pub fn is_retryable_error(error: &reqwest::Error) -> bool {
     error.is_timeout() || error.is_connect() || error.is_request()
 }
 
+/// A short name for what went wrong with a transport error, raised as the
+/// exception's `kind`. DNS and TLS failures surface as connect errors, so
+/// they are told apart by the messages of the underlying errors.
+pub fn error_kind(error: &reqwest::Error) -> &'static str {
+    if error.is_timeout() {
+        return "timeout";
+    }
+    if error.is_connect() {
+        // The top-level message holds the URL, so only its sources are read.
+        let mut source = std::error::Error::source(error);
+        while let Some(inner) = source {
+            let message = inner.to_string().to_ascii_lowercase();
+            if message.contains("dns error") || message.contains("failed to lookup address") {
+                return "dns";
+            }
+            if message.contains("certificate")
+                || message.contains("tls")
+                || message.contains("handshake")
+            {
+                return "tls";
+            }
+            source = inner.source();
+        }
+        return "connect";
+    }
+    if error.is_body() {
+        "body"
+    } else if error.is_decode() {
+        "decode"
+    } else if error.is_redirect() {
+        "redirect"
+    } else if error.is_request() {
+        "request"
+    } else {
+        "other"
+    }
+}
+
+/// `error` as the cause of an `SdkError`, with its `kind`.
+pub fn transport_cause(error: reqwest::Error) -> Cause {
+    let kind = error_kind(&error);
+    Cause::new(error).with_kind(kind)
+}
+
+/// Header carrying a request's idempotency key, sent unchanged on every
+/// retry so the provider can tell a retry from a new request.
+pub const IDEMPOTENCY_KEY: &str = "idempotency-key";
+
+/// A random UUID (version 4) for the `Idempotency-Key` header.
+pub fn new_idempotency_key() -> String {
+    let state = RandomState::new();
+    let random = u128::from(state.hash_one(0_u8)) << 64 | u128::from(state.hash_one(1_u8));
+    let mut bytes = random.to_be_bytes();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// The `Idempotency-Key` a request is sent with, from its headers.
+pub fn idempotency_key(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(IDEMPOTENCY_KEY)?
+        .to_str()
+        .ok()
+        .map(str::to_string)
+}
+
+/// Response headers providers put their request id in, in order of
+/// preference.
+const REQUEST_ID_HEADERS: [&str; 3] = ["x-request-id", "request-id", "openrouter-id"];
+
+/// The provider's id for a request, from the response headers.
+pub fn request_id(headers: &HeaderMap) -> Option<String> {
+    REQUEST_ID_HEADERS
+        .iter()
+        .find_map(|name| headers.get(*name)?.to_str().ok())
+        .map(str::to_string)
+}
+
+/// OpenAI's headers for when its request and token rate limits reset.
+const RATE_LIMIT_RESET_HEADERS: [&str; 2] =
+    ["x-ratelimit-reset-requests", "x-ratelimit-reset-tokens"];
+
+/// The rate-limit headers of a response: OpenAI's `x-ratelimit-remaining-*`
+/// and `x-ratelimit-reset-*` pairs, or else OpenRouter's
+/// `x-ratelimit-remaining` and `x-ratelimit-reset` (a Unix time in
+/// milliseconds), which count requests.
+pub fn rate_limit(headers: &HeaderMap, now: SystemTime) -> RateLimit {
+    let header = |name: &str| headers.get(name)?.to_str().ok().map(str::trim);
+    let count = |name: &str| header(name)?.parse::<u64>().ok();
+    let reset_at = |name: &str| {
+        let millis = header(name)?.parse::<u64>().ok()?;
+        let at = UNIX_EPOCH.checked_add(Duration::from_millis(millis))?;
+        Some(at.duration_since(now).unwrap_or_default())
+    };
+    RateLimit {
+        remaining_requests: count("x-ratelimit-remaining-requests")
+            .or_else(|| count("x-ratelimit-remaining")),
+        remaining_tokens: count("x-ratelimit-remaining-tokens"),
+        reset_requests: header(RATE_LIMIT_RESET_HEADERS[0])
+            .and_then(parse_reset_duration)
+            .or_else(|| reset_at("x-ratelimit-reset")),
+        reset_tokens: header(RATE_LIMIT_RESET_HEADERS[1]).and_then(parse_reset_duration),
+    }
+}
+
 pub fn retry_delay(base: Duration, attempt: u32) -> Duration {
     let multiplier = 1_u32 << attempt.min(8);
     base.saturating_mul(multiplier)
 }
+
+/// How long to wait before retry `attempt`: a random share of the
+/// exponential backoff ("full jitter"), or the server's `hint` when that is
+/// longer, capped at `max_delay`. `unit` is the random sample in `[0, 1)`,
+/// from `jitter()` outside tests.
+pub fn retry_wait(
+    base: Duration,
+    attempt: u32,
+    hint: Option<Duration>,
+    max_delay: Duration,
+    unit: f64,
+) -> Duration {
+    retry_delay(base, attempt)
+        .min(max_delay)
+        .mul_f64(unit.clamp(0.0, 1.0))
+        .max(hint.unwrap_or_default())
+        .min(max_delay)
+}
+
+/// A random sample in `[0, 1)` for `retry_wait`, so clients retrying the
+/// same failure spread out instead of waking together. Each `RandomState`
+/// is seeded differently, which is random enough for spreading retries.
+pub fn jitter() -> f64 {
+    let bits = RandomState::new().hash_one(0_u8);
+    (bits >> 11) as f64 / (1_u64 << 53) as f64
+}
+
+/// How long the server asked us to wait, from `Retry-After` or, failing
+/// that, the later of OpenAI's `x-ratelimit-reset-*` headers.
+pub fn retry_after(headers: &HeaderMap, now: SystemTime) -> Option<Duration> {
+    if let Some(delay) = headers
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| parse_retry_after(value, now))
+    {
+        return Some(delay);
+    }
+
+    RATE_LIMIT_RESET_HEADERS
+        .iter()
+        .filter_map(|name| headers.get(*name)?.to_str().ok())
+        .filter_map(parse_reset_duration)
+        .max()
+}
+
+/// Parse a `Retry-After` value: a number of seconds or an HTTP-date. A
+/// date in the past means no wait.
+pub fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let date = parse_http_date(value)?;
+    Some(date.duration_since(now).unwrap_or_default())
+}
+
+/// Parse an `x-ratelimit-reset-*` duration such as `"1s"`, `"6m0s"`,
+/// `"20ms"` or `"7.66s"`.
+pub fn parse_reset_duration(value: &str) -> Option<Duration> {
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return None;
+    }
+
+    let mut secs = 0.0;
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let (number, tail) = rest.split_at(number_end);
+        let unit_end = tail
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_end);
+        let number: f64 = number.parse().ok()?;
+        let scale = match unit {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 0.001,
+            _ => return None,
+        };
+        secs += number * scale;
+        rest = tail;
+    }
+
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Parse an HTTP-date in its preferred form, e.g.
+/// `"Wed, 21 Oct 2015 07:28:00 GMT"`.
+fn parse_http_date(value: &str) -> Option<SystemTime> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let [_weekday, day, month, year, time, "GMT"] = parts.as_slice() else {
+        return None;
+    };
+    let day: i64 = day.parse().ok().filter(|day| (1..=31).contains(day))?;
+    let month = MONTHS.iter().position(|name| name == month)? as i64 + 1;
+    let year: i64 = year.parse().ok()?;
+
+    let clock: Vec<i64> = time
+        .split(':')
+        .map(|part| part.parse().ok())
+        .collect::<Option<_>>()?;
+    let [hours @ 0..=23, minutes @ 0..=59, seconds @ 0..=60] = clock.as_slice() else {
+        return None;
+    };
+
+    let secs = days_from_civil(year, month, day) * 86_400 + hours * 3600 + minutes * 60 + seconds;
+    SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(u64::try_from(secs).ok()?))
+}
+
+/// Days from 1970-01-01 to the given date in the proleptic Gregorian
+/// calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Note on a retryable failure that retries ran out, and how long was
+/// spent waiting between them. Left unchanged when there were no retries.
+pub fn retries_exhausted(error: SdkError, retries: u32, waited: Duration) -> SdkError {
+    if retries == 0 {
+        return error;
+    }
+    error.map_message(|message| {
+        format!(
+            "{}; gave up after {} retry attempt(s), {:.1}s spent waiting",
+            message,
+            retries,
+            waited.as_secs_f64()
+        )
+    })
+}
+
+/// The end of a call's `total_timeout`, which spans all of its attempts and
+/// the waits between them.
+#[derive(Clone, Copy, Debug)]
+pub struct Deadline {
+    total: Duration,
+    at: Instant,
+}
+
+impl Deadline {
+    /// Start the clock for `total`; `None` means no deadline.
+    pub fn start(total: Option<Duration>) -> Option<Self> {
+        total.map(|total| Self {
+            total,
+            at: Instant::now() + total,
+        })
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.at.saturating_duration_since(Instant::now())
+    }
+
+    pub fn is_exceeded(&self) -> bool {
+        self.remaining().is_zero()
+    }
+
+    /// The error for a call that ran out of time after `attempts` attempts.
+    pub fn exceeded(&self, attempts: u32) -> SdkError {
+        SdkError::timeout(format!(
+            "Request exceeded total_timeout of {:.1}s after {} attempt(s).",
+            self.total.as_secs_f64(),
+            attempts
+        ))
+    }
+}
+
+/// The timeout for the next attempt: `request_timeout`, shrunk to what is
+/// left of the deadline. Fails once the deadline has passed.
+pub fn attempt_timeout(
+    request_timeout: Duration,
+    deadline: Option<&Deadline>,
+    attempts: u32,
+) -> Result<Duration, SdkError> {
+    let Some(deadline) = deadline else {
+        return Ok(request_timeout);
+    };
+    let remaining = deadline.remaining();
+    if remaining.is_zero() {
+        return Err(deadline.exceeded(attempts));
+    }
+    Ok(request_timeout.min(remaining))
+}
+
+/// A provider's timeout and retry settings, as a call sends with them.
+#[derive(Clone)]
+pub struct RetrySettings {
+    pub request_timeout: Duration,
+    pub max_retries: u32,
+    pub retry_backoff: Duration,
+    pub max_retry_delay: Duration,
+    pub retry_on_status: Vec<u16>,
+    pub total_timeout: Option<Duration>,
+    pub retry_hook: RetryHook,
+}
+
+/// Attempts made and time spent waiting between them, summed over the
+/// calls to `send_with_retries` a caller makes, e.g. one per fallback model.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RetryTally {
+    pub attempts: u32,
+    pub retry_wait: Duration,
+}
+
+/// A successful response's body, with what its headers reported.
+pub struct Success {
+    pub text: String,
+    /// The provider's id, from the response headers.
+    pub request_id: Option<String>,
+    pub rate_limit: RateLimit,
+}
+
+/// How often a cancellable wait checks its cancel flag.
+pub const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Send the request `build` makes for each attempt's timeout, retrying on
+/// retryable statuses and errors as `settings` allow within `deadline`,
+/// and return the first successful response's body. Each retry is reported
+/// to the retry hook; attempts and waits are added to `tally`.
+pub async fn send_with_retries(
+    build: impl Fn(Duration) -> RequestBuilder,
+    settings: &RetrySettings,
+    deadline: Option<Deadline>,
+    tally: &mut RetryTally,
+) -> Result<Success, SdkError> {
+    let before = tally.attempts;
+    // Without a cancel flag there is always a response.
+    let response = connect_with_retries(build, settings, deadline, tally, None)
+        .await?
+        .ok_or_else(|| SdkError::runtime("Request was cancelled."))?;
+    let request_id = request_id(response.headers());
+    let rate_limit = rate_limit(response.headers(), SystemTime::now());
+    let text = read_body(response, deadline, tally.attempts - before).await?;
+    Ok(Success {
+        text,
+        request_id,
+        rate_limit,
+    })
+}
+
+/// Like `send_with_retries`, but return the successful response with its
+/// body unread, e.g. to stream it, or `None` once `cancel` is set: it is
+/// checked before each attempt and while waiting between them.
+pub async fn connect_with_retries(
+    build: impl Fn(Duration) -> RequestBuilder,
+    settings: &RetrySettings,
+    deadline: Option<Deadline>,
+    tally: &mut RetryTally,
+    cancel: Option<&AtomicBool>,
+) -> Result<Option<Response>, SdkError> {
+    let mut waited = Duration::ZERO;
+    for attempt in 0..=settings.max_retries {
+        if cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+            return Ok(None);
+        }
+        tally.attempts += 1;
+        let timeout = attempt_timeout(settings.request_timeout, deadline.as_ref(), attempt)?;
+
+        let delay = match build(timeout).send().await {
+            Ok(response) if response.status().is_success() => return Ok(Some(response)),
+            Ok(response) => {
+                let status = response.status();
+                let request_id = request_id(response.headers());
+                let hint = retry_after(response.headers(), SystemTime::now());
+                let text = read_body(response, deadline, attempt + 1).await?;
+                let error = SdkError::api(ApiError {
+                    request_id,
+                    ..api_error(status, &text)
+                });
+                if !is_retryable_status(status, &settings.retry_on_status) {
+                    return Err(error);
+                }
+                if attempt == settings.max_retries {
+                    return Err(retries_exhausted(error, attempt, waited));
+                }
+                let cause = RetryCause::Status(status.as_u16());
+                wait_to_retry(settings, attempt, hint, deadline.as_ref(), cause, cancel).await
+            }
+            Err(error) => {
+                if let Some(deadline) = deadline
+                    && deadline.is_exceeded()
+                {
+                    return Err(deadline.exceeded(attempt + 1));
+                }
+                let retryable = is_retryable_error(&error);
+                let message = error.to_string();
+                if !retryable {
+                    let cause = transport_cause(error);
+                    return Err(SdkError::connection(message).with_cause(cause));
+                }
+                if attempt == settings.max_retries {
+                    let error = SdkError::connection(message).with_cause(transport_cause(error));
+                    return Err(retries_exhausted(error, attempt, waited));
+                }
+                let cause = RetryCause::Error(&message);
+                wait_to_retry(settings, attempt, None, deadline.as_ref(), cause, cancel).await
+            }
+        };
+        let Some(delay) = delay else {
+            return Ok(None);
+        };
+        waited += delay;
+        tally.retry_wait += delay;
+    }
+
+    Err(SdkError::runtime(
+        "Request failed after retries were exhausted.",
+    ))
+}
+
+/// The body of `response`, the last of `attempts`. Running out of time
+/// while reading it is a timeout.
+async fn read_body(
+    response: Response,
+    deadline: Option<Deadline>,
+    attempts: u32,
+) -> Result<String, SdkError> {
+    response.text().await.map_err(|e| match deadline {
+        Some(deadline) if deadline.is_exceeded() => deadline.exceeded(attempts),
+        _ => SdkError::runtime(e.to_string()).with_cause(transport_cause(e)),
+    })
+}
+
+/// Report the retry to the hook, then sleep before retry `attempt`,
+/// honoring the server's `hint` but not past the `deadline`, and return how
+/// long that was; `None` if `cancel` was set during the wait.
+pub async fn wait_to_retry(
+    settings: &RetrySettings,
+    attempt: u32,
+    hint: Option<Duration>,
+    deadline: Option<&Deadline>,
+    cause: RetryCause<'_>,
+    cancel: Option<&AtomicBool>,
+) -> Option<Duration> {
+    let delay = retry_wait(
+        settings.retry_backoff,
+        attempt,
+        hint,
+        settings.max_retry_delay,
+        jitter(),
+    );
+    let delay = deadline.map_or(delay, |deadline| delay.min(deadline.remaining()));
+    settings.retry_hook.notify(attempt + 1, cause, delay);
+    match cancel {
+        Some(cancel) => (!sleep_with_cancellation(cancel, delay).await).then_some(delay),
+        None => {
+            sleep(delay).await;
+            Some(delay)
+        }
+    }
+}
+
+/// Sleep for `delay`, checking `cancel_flag` every `CANCEL_POLL_INTERVAL`.
+/// Returns whether the sleep was cut short by cancellation.
+pub async fn sleep_with_cancellation(cancel_flag: &AtomicBool, delay: Duration) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < delay {
+        if cancel_flag.load(Ordering::Relaxed) {
+            return true;
+        }
+        sleep(CANCEL_POLL_INTERVAL.min(delay.saturating_sub(start.elapsed()))).await;
+    }
+    false
+}
@@ -4,36 +4,178 @@
 
 use pyo3::prelude::*;
 
+mod aio;
+mod anthropic;
+mod astream;
+mod chat;
+mod circuit;
 mod errors;
 mod generate;
 mod http;
+mod limiter;
+mod list_models;
+mod messages;
 mod models;
+mod parts;
+mod pricing;
 mod provider;
+mod retry_hook;
+mod runtime;
+mod segment;
 mod stream;
+mod structured;
+mod trim;
+mod usage;
+mod vectors;
 
+pub use astream::AsyncTextStream;
+pub use chat::{Chat, py_messages_from_json, py_messages_to_json};
+pub use errors::{CircuitOpenError, ContentFilterError, ContextLengthExceededError};
+pub use list_models::ModelInfo;
+pub use messages::{AssistantMessage, Message, SystemMessage, UserMessage};
+pub use parts::{file_part, image_part};
 pub use provider::{GenerateResult, Provider};
-pub use stream::TextStream;
+pub use stream::{StreamBranch, StreamChunk, TextStream};
+pub use structured::{ObjectStream, json_schema};
+pub use trim::trim_messages;
+pub use usage::UsageTracker;
+pub use vectors::{cosine_similarity, normalize, similarity_search, similarity_search_many};
 
 #[doc(hidden)]
 pub mod internal {
+    pub use crate::anthropic::{
+        ANTHROPIC_VERSION, DEFAULT_ANTHROPIC_MAX_TOKENS, StreamTranslator, to_chat_response,
+        to_messages_request,
+    };
+    pub use crate::circuit::{CircuitBreaker, CircuitPermit, CircuitState, Clock};
+    pub use crate::errors::{
+        ApiError, ContextLength, MAX_ERROR_BODY_BYTES, quoted_body, redact_secrets,
+    };
+    pub use crate::http::{
+        DEFAULT_RETRY_STATUSES, Deadline, attempt_timeout, is_retryable_status, jitter,
+        new_idempotency_key, parse_reset_duration, parse_retry_after, rate_limit,
+        retries_exhausted, retry_after, retry_delay, retry_wait,
+    };
+    pub use crate::limiter::{
+        ConcurrencyLimit, RateLimiter, Reservation, Slot, estimate_request_tokens, wait_within,
+    };
     pub use crate::models::{
-        ChatMessage, ChatRequest, GenerationParams, ParsedChatResult, StreamEvent, StreamMetadata,
-        Usage, api_error_message, parse_chat_response, parse_chat_response_full, parse_sse_event,
-        parse_sse_line,
+        Annotation, AudioOutput, ChatMessage, ChatRequest, CompletionTokensDetails,
+        GenerationParams, HISTORY_FORMAT_VERSION, MessageContent, ModelObject, ParsedChatResult,
+        RateLimit, SseLineBuffer, StreamChunkData, StreamEvent, StreamMetadata, StreamTimings,
+        Usage, api_error, api_error_message, content_filter_error, content_filtered,
+        context_length_exceeded, is_sse_comment, messages_from_json, messages_to_json,
+        parse_chat_response, parse_chat_response_full, parse_model, parse_model_list,
+        parse_sse_chunks, parse_sse_event, parse_sse_line, sse_event_id, validate_content_parts,
+        validate_message_roles,
     };
+    pub use crate::parts::{
+        DEFAULT_MAX_PART_BYTES, build_file_part, build_image_part, detect_file_mime,
+        detect_image_mime,
+    };
+    pub use crate::pricing::{Catalog, Pricing, parse_catalog};
     pub use crate::provider::{
-        build_chat_completions_url, resolve_provider_values, resolve_runtime_config,
+        ApiDialect, AuthHeader, PRESETS, Preset, Routing, RuntimeOverrides, TlsConfig, USER_AGENT,
+        build_chat_completions_url, build_endpoint_url, build_models_url, find_preset,
+        mask_api_key, normalize_base_url, parse_default_headers, resolve_azure_base_url,
+        resolve_preset_values, resolve_provider_values, resolve_runtime_config,
+    };
+    pub use crate::runtime::runtimes_built;
+    pub use crate::segment::{Segmenter, SplitMode, StopPattern, StopScanner};
+    pub use crate::structured::{
+        build_json_schema_format, parse_json_output, parse_partial_json, schema_name,
+        validate_response_format,
     };
+    pub use crate::trim::{estimate_message_tokens, estimate_tokens, trim_history};
+    pub use crate::usage::UsageTotals;
+    pub use crate::vectors::{METRICS, Matrix, Metric, cosine, l2_normalize};
 }
 
 #[pymodule]
 mod rusty_agent_sdk {
+    #[pymodule_export]
+    use super::AssistantMessage;
+
+    #[pymodule_export]
+    use super::AsyncTextStream;
+
     #[pymodule_export]
     use super::GenerateResult;
 
+    #[pymodule_export]
+    use super::Message;
+
+    #[pymodule_export]
+    use super::image_part;
+
+    #[pymodule_export]
+    use super::file_part;
+
+    #[pymodule_export]
+    use super::json_schema;
+
+    #[pymodule_export]
+    use super::Chat;
+
+    #[pymodule_export]
+    use super::CircuitOpenError;
+
+    #[pymodule_export]
+    use super::ContentFilterError;
+
+    #[pymodule_export]
+    use super::ContextLengthExceededError;
+
+    #[pymodule_export]
+    use super::py_messages_to_json;
+
+    #[pymodule_export]
+    use super::py_messages_from_json;
+
+    #[pymodule_export]
+    use super::trim_messages;
+
+    #[pymodule_export]
+    use super::normalize;
+
+    #[pymodule_export]
+    use super::cosine_similarity;
+
+    #[pymodule_export]
+    use super::similarity_search;
+
+    #[pymodule_export]
+    use super::similarity_search_many;
+
     #[pymodule_export]
     use super::Provider;
 
+    #[pymodule_export]
+    use super::SystemMessage;
+
     #[pymodule_export]
     use super::TextStream;
+
+    #[pymodule_export]
+    use super::StreamChunk;
+
+    #[pymodule_export]
+    use super::StreamBranch;
+
+    #[pymodule_export]
+    use super::ObjectStream;
+
+    #[pymodule_export]
+    use super::UserMessage;
+
+    #[pymodule_export]
+    use super::UsageTracker;
+
+    #[pymodule_export]
+    use super::ModelInfo;
+
+    /// The `User-Agent` the SDK sends, for debugging.
+    #[expect(non_upper_case_globals)] // Python dunder attribute name
+    #[pymodule_export]
+    const __user_agent__: &str = super::provider::USER_AGENT;
 }
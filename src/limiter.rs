@@ -0,0 +1,302 @@
+use crate::circuit::{Clock, SystemClock};
+use crate::errors::SdkError;
+use crate::http::Deadline;
+use crate::models::ChatRequest;
+use crate::trim::estimate_tokens;
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tokio::time::{sleep, timeout};
+
+/// Token buckets for `max_requests_per_minute` and `max_tokens_per_minute`.
+/// Each bucket holds a minute's allowance and refills continuously, so a
+/// full bucket allows a burst. Copies of a provider share the levels,
+/// whatever their settings.
+#[derive(Clone)]
+pub struct RateLimiter {
+    /// `0` turns the request bucket off.
+    requests_per_minute: u32,
+    /// `0` turns the token bucket off.
+    tokens_per_minute: u64,
+    used: Arc<Mutex<Used>>,
+    clock: Arc<dyn Clock>,
+}
+
+/// How far each bucket is below full. Counting what was taken rather than
+/// what is left keeps the levels meaningful when copies change the limits.
+struct Used {
+    requests: f64,
+    /// May exceed the bucket after a call reports more tokens than were
+    /// reserved for it; later calls wait for the debt to refill.
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+        Self {
+            requests_per_minute: 0,
+            tokens_per_minute: 0,
+            used: Arc::new(Mutex::new(Used {
+                requests: 0.0,
+                tokens: 0.0,
+                updated: clock.now(),
+            })),
+            clock,
+        }
+    }
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_minute: u32, tokens_per_minute: u64) -> Self {
+        Self::default().with_settings(requests_per_minute, tokens_per_minute)
+    }
+
+    /// This limiter with new per-minute limits (`0` turns one off), keeping
+    /// the shared levels.
+    pub fn with_settings(&self, requests_per_minute: u32, tokens_per_minute: u64) -> Self {
+        Self {
+            requests_per_minute,
+            tokens_per_minute,
+            ..self.clone()
+        }
+    }
+
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        if let Ok(mut used) = self.used.lock() {
+            used.updated = clock.now();
+        }
+        Self { clock, ..self }
+    }
+
+    pub fn requests_per_minute(&self) -> u32 {
+        self.requests_per_minute
+    }
+
+    pub fn tokens_per_minute(&self) -> u64 {
+        self.tokens_per_minute
+    }
+
+    /// The requests and tokens available now, `None` for a bucket that is
+    /// off. Tokens go negative while a call's overrun is being repaid.
+    pub fn levels(&self) -> (Option<f64>, Option<f64>) {
+        let Ok(mut used) = self.used.lock() else {
+            return (None, None);
+        };
+        self.refill(&mut used);
+        (
+            (self.requests_per_minute > 0)
+                .then(|| f64::from(self.requests_per_minute) - used.requests),
+            (self.tokens_per_minute > 0).then(|| self.tokens_per_minute as f64 - used.tokens),
+        )
+    }
+
+    /// Take a request and `tokens` from the buckets if both have room, or
+    /// say how long until they will. A call needing more tokens than the
+    /// bucket holds waits for a full bucket.
+    pub fn try_acquire(&self, tokens: u64) -> Result<Reservation, Duration> {
+        if self.requests_per_minute == 0 && self.tokens_per_minute == 0 {
+            return Ok(Reservation::default());
+        }
+        let Ok(mut used) = self.used.lock() else {
+            return Ok(Reservation::default());
+        };
+        self.refill(&mut used);
+
+        let mut wait: f64 = 0.0;
+        if self.requests_per_minute > 0 {
+            let over = used.requests + 1.0 - f64::from(self.requests_per_minute);
+            wait = wait.max(over * 60.0 / f64::from(self.requests_per_minute));
+        }
+        if self.tokens_per_minute > 0 {
+            let capacity = self.tokens_per_minute as f64;
+            let over = used.tokens + (tokens as f64).min(capacity) - capacity;
+            wait = wait.max(over * 60.0 / capacity);
+        }
+        if wait > 0.0 {
+            return Err(Duration::from_secs_f64(wait));
+        }
+
+        if self.requests_per_minute > 0 {
+            used.requests += 1.0;
+        }
+        if self.tokens_per_minute > 0 {
+            used.tokens += tokens as f64;
+        }
+        Ok(Reservation {
+            limiter: (self.tokens_per_minute > 0).then(|| self.clone()),
+            tokens,
+        })
+    }
+
+    /// Wait until the buckets have room for a request and `tokens`, failing
+    /// with `Timeout` instead if that would outlast `deadline`.
+    pub async fn acquire(
+        &self,
+        tokens: u64,
+        deadline: Option<&Deadline>,
+    ) -> Result<Reservation, SdkError> {
+        loop {
+            match self.try_acquire(tokens) {
+                Ok(reservation) => return Ok(reservation),
+                Err(wait) => {
+                    wait_within(wait, deadline)?;
+                    sleep(wait).await;
+                }
+            }
+        }
+    }
+
+    /// Refill the buckets for the time since the last refill.
+    fn refill(&self, used: &mut Used) {
+        let now = self.clock.now();
+        let minutes = now.saturating_duration_since(used.updated).as_secs_f64() / 60.0;
+        used.updated = now;
+        used.requests = (used.requests - minutes * f64::from(self.requests_per_minute)).max(0.0);
+        used.tokens = (used.tokens - minutes * self.tokens_per_minute as f64).max(0.0);
+    }
+
+    /// Correct a reservation of `reserved` tokens to the `actual` count.
+    fn correct(&self, reserved: u64, actual: u64) {
+        if let Ok(mut used) = self.used.lock() {
+            self.refill(&mut used);
+            used.tokens = (used.tokens + actual as f64 - reserved as f64).max(0.0);
+        }
+    }
+}
+
+/// The error for a rate-limit wait of `wait` that would outlast `deadline`.
+pub fn wait_within(wait: Duration, deadline: Option<&Deadline>) -> Result<(), SdkError> {
+    match deadline {
+        Some(deadline) if wait >= deadline.remaining() => Err(SdkError::timeout(format!(
+            "Client-side rate limit needs a {:.1}s wait, longer than is left of total_timeout.",
+            wait.as_secs_f64()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// The tokens reserved for `body` before it is sent: the estimated prompt
+/// plus `max_tokens` for each of its `n` completions.
+pub fn estimate_request_tokens(body: &ChatRequest) -> u64 {
+    estimate_tokens(&body.messages) + body.max_tokens.unwrap_or(0) * u64::from(body.n.unwrap_or(1))
+}
+
+/// Tokens taken from the bucket by `RateLimiter::try_acquire`, corrected
+/// once the call reports its usage.
+#[derive(Default)]
+pub struct Reservation {
+    limiter: Option<RateLimiter>,
+    tokens: u64,
+}
+
+impl Reservation {
+    /// Replace the estimate with the `total_tokens` the response reported;
+    /// without a count, the estimate stands.
+    pub fn settle(self, total_tokens: Option<u64>) {
+        if let (Some(limiter), Some(actual)) = (self.limiter, total_tokens) {
+            limiter.correct(self.tokens, actual);
+        }
+    }
+}
+
+/// Caps the requests in flight at once for `max_concurrency`. Copies of a
+/// provider share the count, whatever their settings.
+#[derive(Clone, Default)]
+pub struct ConcurrencyLimit {
+    /// `0` turns the limit off.
+    max: usize,
+    shared: Arc<InFlight>,
+}
+
+#[derive(Default)]
+struct InFlight {
+    count: Mutex<usize>,
+    released: Notify,
+}
+
+impl ConcurrencyLimit {
+    pub fn new(max: usize) -> Self {
+        Self::default().with_max(max)
+    }
+
+    /// This limit allowing `max` requests at once (`0` turns it off),
+    /// keeping the shared count.
+    pub fn with_max(&self, max: usize) -> Self {
+        Self {
+            max,
+            ..self.clone()
+        }
+    }
+
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Requests in flight now through this limit and its copies.
+    pub fn in_flight(&self) -> usize {
+        self.shared.count.lock().map_or(0, |count| *count)
+    }
+
+    /// Take a slot if one is free.
+    pub fn try_acquire(&self) -> Option<Slot> {
+        if self.max == 0 {
+            return Some(Slot::default());
+        }
+        let mut count = self.shared.count.lock().ok()?;
+        if *count >= self.max {
+            return None;
+        }
+        *count += 1;
+        Some(Slot {
+            shared: Some(self.shared.clone()),
+        })
+    }
+
+    /// Wait for a free slot, failing with `Timeout` instead if none frees
+    /// up before `deadline`.
+    pub async fn acquire(&self, deadline: Option<&Deadline>) -> Result<Slot, SdkError> {
+        let wait = self.wait();
+        match deadline {
+            Some(deadline) => timeout(deadline.remaining(), wait).await.map_err(|_| {
+                SdkError::timeout(format!(
+                    "No max_concurrency slot (limit {}) freed up within total_timeout.",
+                    self.max
+                ))
+            }),
+            None => Ok(wait.await),
+        }
+    }
+
+    async fn wait(&self) -> Slot {
+        loop {
+            // Registered before checking, so a slot released in between
+            // still wakes this call.
+            let mut released = pin!(self.shared.released.notified());
+            released.as_mut().enable();
+            if let Some(slot) = self.try_acquire() {
+                return slot;
+            }
+            released.await;
+        }
+    }
+}
+
+/// A request's place under `ConcurrencyLimit`, given back when dropped.
+#[derive(Default)]
+pub struct Slot {
+    shared: Option<Arc<InFlight>>,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        if let Some(shared) = self.shared.take() {
+            if let Ok(mut count) = shared.count.lock() {
+                *count = count.saturating_sub(1);
+            }
+            shared.released.notify_waiters();
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::errors::SdkError;
+use crate::http::{Deadline, IDEMPOTENCY_KEY, RetryTally, send_with_retries};
+use crate::models::{ModelObject, parse_model, parse_model_list};
+use crate::provider::{Provider, build_models_url, json_to_py};
+use crate::runtime;
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyDict};
+
+/// A model the provider serves, from :meth:`Provider.list_models` or
+/// :meth:`Provider.get_model`.
+///
+/// ``id``, ``created`` and ``owned_by`` are what every OpenAI-compatible
+/// server lists; ``name``, ``context_length`` and ``pricing`` are set when
+/// the provider lists them, as OpenRouter does. ``raw`` holds the whole
+/// entry.
+#[pyclass(frozen, skip_from_py_object, module = "rusty_agent_sdk")]
+pub struct ModelInfo {
+    model: ModelObject,
+}
+
+#[pymethods]
+impl ModelInfo {
+    /// The model id to pass as ``model``.
+    #[getter]
+    fn id(&self) -> &str {
+        &self.model.id
+    }
+
+    /// The Unix timestamp the model was created at, or ``None``.
+    #[getter]
+    fn created(&self) -> Option<u64> {
+        self.model.created
+    }
+
+    /// The organization that owns the model, or ``None``.
+    #[getter]
+    fn owned_by(&self) -> Option<&str> {
+        self.model.owned_by.as_deref()
+    }
+
+    /// The model's display name, or ``None``.
+    #[getter]
+    fn name(&self) -> Option<&str> {
+        self.model.name.as_deref()
+    }
+
+    /// The model's context window in tokens, or ``None``.
+    #[getter]
+    fn context_length(&self) -> Option<u64> {
+        self.model.context_length
+    }
+
+    /// The model's prices in USD, keyed as the provider lists them
+    /// (``prompt`` and ``completion`` per token, ...), or ``None``. See
+    /// :meth:`Provider.get_pricing`.
+    #[getter]
+    fn pricing<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        self.model
+            .pricing
+            .as_ref()
+            .map(|prices| prices.into_py_dict(py))
+            .transpose()
+    }
+
+    /// The model's entry as the provider sent it.
+    #[getter]
+    fn raw(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        json_to_py(py, &self.model.raw)
+    }
+
+    fn __repr__(&self) -> String {
+        let owned_by = self
+            .model
+            .owned_by
+            .as_deref()
+            .map_or_else(|| "None".to_string(), |owner| format!("'{}'", owner));
+        format!("ModelInfo(id='{}', owned_by={})", self.model.id, owned_by)
+    }
+}
+
+/// The provider's models, called by `Provider.list_models()`.
+pub fn list(py: Python<'_>, provider: &Provider) -> PyResult<Vec<ModelInfo>> {
+    let models = fetch(py, provider, &build_models_url(&provider.base_url, None))
+        .and_then(|response_text| parse_model_list(&response_text))
+        .map_err(SdkError::into_pyerr)?;
+    Ok(models
+        .into_iter()
+        .map(|model| ModelInfo { model })
+        .collect())
+}
+
+/// One model, called by `Provider.get_model()`.
+pub fn get(py: Python<'_>, provider: &Provider, model: &str) -> PyResult<ModelInfo> {
+    let model = fetch(
+        py,
+        provider,
+        &build_models_url(&provider.base_url, Some(model)),
+    )
+    .and_then(|response_text| parse_model(&response_text))
+    .map_err(SdkError::into_pyerr)?;
+    Ok(ModelInfo { model })
+}
+
+/// GET `url` with the provider's headers, timeouts and retry settings, and
+/// the GIL released. Unlike a generation it is not held to the rate
+/// limiter, `max_concurrency` or the circuit breaker, and sends no
+/// `Idempotency-Key`.
+fn fetch(py: Python<'_>, provider: &Provider, url: &str) -> Result<String, SdkError> {
+    let mut headers = provider.headers()?;
+    headers.remove(IDEMPOTENCY_KEY);
+    let client = provider.client()?;
+    let retries = provider.retry_settings();
+    py.detach(|| {
+        runtime::block_on(send_with_retries(
+            |timeout| client.get(url).headers(headers.clone()).timeout(timeout),
+            &retries,
+            Deadline::start(retries.total_timeout),
+            &mut RetryTally::default(),
+        ))
+    })
+    .and_then(|result| result)
+    .map(|response| response.text)
+}
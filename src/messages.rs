@@ -0,0 +1,150 @@
+use crate::errors::SdkError;
+use crate::models::{ChatMessage, MessageContent};
+use crate::provider::{extract_content, json_to_py};
+use pyo3::prelude::*;
+use pyo3::types::PyString;
+
+// ---------------------------------------------------------------------------
+// Message pyclasses
+// ---------------------------------------------------------------------------
+
+/// A single chat message that can be passed in the ``messages`` list.
+///
+/// Instances can be mixed freely with plain ``{"role": ..., "content": ...}``
+/// dicts. The role-specific subclasses (``SystemMessage``, ``UserMessage``,
+/// ``AssistantMessage``) fix the role so only the content is needed.
+///
+/// Examples (Python):
+///
+/// ```text
+/// provider.generate_text(messages=[
+///     SystemMessage("Be concise."),
+///     UserMessage("Hi"),
+///     {"role": "assistant", "content": "Hello!"},
+///     Message("user", "What's new?"),
+/// ])
+/// ```
+#[pyclass(subclass, frozen, skip_from_py_object)]
+#[derive(Clone)]
+pub struct Message {
+    pub(crate) inner: ChatMessage,
+}
+
+#[pymethods]
+impl Message {
+    #[new]
+    #[pyo3(signature = (role, content, *, name=None, tool_call_id=None))]
+    #[pyo3(text_signature = "(role, content, *, name=None, tool_call_id=None)")]
+    fn new(
+        role: String,
+        content: &Bound<'_, PyAny>,
+        name: Option<String>,
+        tool_call_id: Option<String>,
+    ) -> PyResult<Self> {
+        Ok(Self {
+            inner: ChatMessage {
+                role,
+                content: extract_content(content, "'content'")?,
+                name,
+                tool_call_id,
+            },
+        })
+    }
+
+    #[getter]
+    fn role(&self) -> &str {
+        &self.inner.role
+    }
+
+    /// The message text, or the list of content parts for multimodal messages.
+    #[getter]
+    fn content(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        match &self.inner.content {
+            MessageContent::Text(text) => Ok(PyString::new(py, text).into_any().unbind()),
+            MessageContent::Parts(parts) => json_to_py(py, &serde_json::Value::from(parts.clone())),
+        }
+    }
+
+    #[getter]
+    fn name(&self) -> Option<&str> {
+        self.inner.name.as_deref()
+    }
+
+    #[getter]
+    fn tool_call_id(&self) -> Option<&str> {
+        self.inner.tool_call_id.as_deref()
+    }
+
+    fn __eq__(&self, other: PyRef<'_, Message>) -> bool {
+        self.inner == other.inner
+    }
+
+    fn __repr__(slf: &Bound<'_, Self>) -> PyResult<String> {
+        let class_name = slf.get_type().name()?;
+        let message = &slf.get().inner;
+        let content = match &message.content {
+            MessageContent::Text(text) => format!("'{}'", text),
+            MessageContent::Parts(parts) => serde_json::to_string(parts)
+                .map_err(|e| SdkError::runtime(e.to_string()).into_pyerr())?,
+        };
+        Ok(format!(
+            "{}(role='{}', content={})",
+            class_name, message.role, content
+        ))
+    }
+}
+
+impl Message {
+    fn with_role(role: &str, content: &Bound<'_, PyAny>, name: Option<String>) -> PyResult<Self> {
+        Ok(Self {
+            inner: ChatMessage {
+                role: role.to_string(),
+                content: extract_content(content, "'content'")?,
+                name,
+                ..Default::default()
+            },
+        })
+    }
+}
+
+/// A message with the ``system`` role.
+#[pyclass(extends = Message, frozen)]
+pub struct SystemMessage;
+
+#[pymethods]
+impl SystemMessage {
+    #[new]
+    #[pyo3(signature = (content, *, name=None))]
+    #[pyo3(text_signature = "(content, *, name=None)")]
+    fn new(content: &Bound<'_, PyAny>, name: Option<String>) -> PyResult<(Self, Message)> {
+        Ok((Self, Message::with_role("system", content, name)?))
+    }
+}
+
+/// A message with the ``user`` role.
+#[pyclass(extends = Message, frozen)]
+pub struct UserMessage;
+
+#[pymethods]
+impl UserMessage {
+    #[new]
+    #[pyo3(signature = (content, *, name=None))]
+    #[pyo3(text_signature = "(content, *, name=None)")]
+    fn new(content: &Bound<'_, PyAny>, name: Option<String>) -> PyResult<(Self, Message)> {
+        Ok((Self, Message::with_role("user", content, name)?))
+    }
+}
+
+/// A message with the ``assistant`` role.
+#[pyclass(extends = Message, frozen)]
+pub struct AssistantMessage;
+
+#[pymethods]
+impl AssistantMessage {
+    #[new]
+    #[pyo3(signature = (content, *, name=None))]
+    #[pyo3(text_signature = "(content, *, name=None)")]
+    fn new(content: &Bound<'_, PyAny>, name: Option<String>) -> PyResult<(Self, Message)> {
+        Ok((Self, Message::with_role("assistant", content, name)?))
+    }
+}
@@ -1,42 +1,459 @@
-use crate::errors::SdkError;
+use crate::errors::{ApiError, ContextLength, SdkError, redact_secrets};
+use crate::pricing::{Pricing, price};
+use crate::structured::validate_response_format;
 use reqwest::StatusCode;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Value;
+use std::borrow::Cow;
+use std::time::Duration;
 
 // ---------------------------------------------------------------------------
 // Usage / metadata types
 // ---------------------------------------------------------------------------
 
-#[derive(Deserialize, Clone, Debug, PartialEq)]
+/// Token counts from a response's `usage`. Some servers (llama.cpp, some
+/// vLLM builds, Azure) leave counts out or send `null`, so each is optional;
+/// a missing `total_tokens` is the sum of the other two when both are known.
+#[derive(Deserialize, Clone, Debug, PartialEq, Default)]
+#[serde(from = "UsageFields")]
 pub struct Usage {
-    pub prompt_tokens: u64,
-    pub completion_tokens: u64,
-    pub total_tokens: u64,
+    pub prompt_tokens: Option<u64>,
+    pub completion_tokens: Option<u64>,
+    pub total_tokens: Option<u64>,
+    pub prompt_tokens_details: Option<PromptTokensDetails>,
+    pub completion_tokens_details: Option<CompletionTokensDetails>,
+    /// What the request cost in USD, sent by OpenRouter with usage
+    /// accounting on.
+    pub cost: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct UsageFields {
+    prompt_tokens: Option<u64>,
+    completion_tokens: Option<u64>,
+    total_tokens: Option<u64>,
+    prompt_tokens_details: Option<PromptTokensDetails>,
+    completion_tokens_details: Option<CompletionTokensDetails>,
+    cost: Option<f64>,
+}
+
+impl From<UsageFields> for Usage {
+    fn from(fields: UsageFields) -> Self {
+        let derived_total = fields
+            .prompt_tokens
+            .zip(fields.completion_tokens)
+            .map(|(prompt, completion)| prompt + completion);
+        Self {
+            prompt_tokens: fields.prompt_tokens,
+            completion_tokens: fields.completion_tokens,
+            total_tokens: fields.total_tokens.or(derived_total),
+            prompt_tokens_details: fields.prompt_tokens_details,
+            completion_tokens_details: fields.completion_tokens_details,
+            cost: fields.cost,
+        }
+    }
+}
+
+/// Read `usage` so that a shape that does not parse counts as no usage
+/// rather than failing a response whose text is fine.
+fn lenient_usage<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Usage>, D::Error> {
+    let value = Option::<Value>::deserialize(deserializer)?;
+    Ok(value.and_then(|value| Usage::deserialize(value).ok()))
+}
+
+/// Breakdown of `prompt_tokens`, reported for prompts served from cache.
+#[derive(Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct PromptTokensDetails {
+    pub cached_tokens: Option<u64>,
+}
+
+/// Breakdown of `completion_tokens`, reported by OpenAI for predicted
+/// outputs and by reasoning models.
+#[derive(Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct CompletionTokensDetails {
+    pub accepted_prediction_tokens: Option<u64>,
+    pub rejected_prediction_tokens: Option<u64>,
+    pub reasoning_tokens: Option<u64>,
+}
+
+/// Audio returned by models that generate speech (`modalities=["text", "audio"]`).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AudioOutput {
+    pub id: String,
+    /// Base64-encoded audio in the requested format.
+    pub data: String,
+    pub transcript: Option<String>,
+    pub expires_at: Option<u64>,
+}
+
+/// A citation a web-search model attached to its answer, from a message's
+/// `annotations`. OpenAI and OpenRouter nest the URL fields under
+/// `url_citation`; they are flattened here, and left `None` for annotation
+/// types without them.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(from = "AnnotationFields")]
+pub struct Annotation {
+    #[serde(rename = "type")]
+    pub annotation_type: String,
+    pub url: Option<String>,
+    pub title: Option<String>,
+    /// Where the cited span starts in the message text.
+    pub start_index: Option<u64>,
+    /// Where the cited span ends in the message text.
+    pub end_index: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct AnnotationFields {
+    #[serde(rename = "type")]
+    annotation_type: String,
+    url_citation: Option<UrlCitation>,
+}
+
+#[derive(Deserialize, Default)]
+struct UrlCitation {
+    url: Option<String>,
+    title: Option<String>,
+    start_index: Option<u64>,
+    end_index: Option<u64>,
+}
+
+impl From<AnnotationFields> for Annotation {
+    fn from(fields: AnnotationFields) -> Self {
+        let citation = fields.url_citation.unwrap_or_default();
+        Self {
+            annotation_type: fields.annotation_type,
+            url: citation.url,
+            title: citation.title,
+            start_index: citation.start_index,
+            end_index: citation.end_index,
+        }
+    }
+}
+
+/// Read `annotations` so that entries which do not parse are skipped
+/// rather than failing a response whose text is fine.
+fn lenient_annotations<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Annotation>, D::Error> {
+    let Some(Value::Array(annotations)) = Option::<Value>::deserialize(deserializer)? else {
+        return Ok(Vec::new());
+    };
+    Ok(annotations
+        .into_iter()
+        .filter_map(|annotation| Annotation::deserialize(annotation).ok())
+        .collect())
 }
 
 #[derive(Debug)]
 pub struct ParsedChatResult {
+    pub id: Option<String>,
+    pub created: Option<u64>,
     pub text: String,
+    pub reasoning: Option<String>,
+    pub audio: Option<AudioOutput>,
+    /// The model's refusal message, sent in place of content.
+    pub refusal: Option<String>,
+    /// Citations from web-search models; empty when there are none.
+    pub annotations: Vec<Annotation>,
     pub usage: Option<Usage>,
     pub finish_reason: Option<String>,
     pub model: Option<String>,
+    pub service_tier: Option<String>,
+    pub system_fingerprint: Option<String>,
+    /// From the response headers, set by the caller that sent the request.
+    pub request_id: Option<String>,
+    /// The `Idempotency-Key` the request was sent with, set by the caller.
+    pub idempotency_key: Option<String>,
+    /// The models the call was sent to in turn, set by the caller.
+    pub attempted_models: Vec<String>,
+    /// From the response headers, set by the caller.
+    pub rate_limit: Option<RateLimit>,
+    /// The whole response as parsed JSON, for fields without a typed field.
+    pub raw: Option<Value>,
+    /// Measured around the HTTP call, set by the caller that sent it.
+    pub timings: Option<CallTimings>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Default, PartialEq)]
 pub struct StreamMetadata {
+    pub id: Option<String>,
+    pub created: Option<u64>,
     pub usage: Option<Usage>,
     pub finish_reason: Option<String>,
     pub model: Option<String>,
+    pub service_tier: Option<String>,
+    pub system_fingerprint: Option<String>,
+    /// The model's refusal message, joined from the chunks that carry it.
+    pub refusal: Option<String>,
+    /// Citations from web-search models, collected from the chunks that
+    /// carry them.
+    pub annotations: Vec<Annotation>,
+    /// From the response headers, recorded by the stream worker.
+    pub request_id: Option<String>,
+    /// The `Idempotency-Key` of the request that started the stream,
+    /// recorded by the stream worker.
+    pub idempotency_key: Option<String>,
+    /// Recorded by the stream worker once the response ends.
+    pub timings: Option<StreamTimings>,
+    /// The models the stream was sent to in turn, recorded by the stream
+    /// worker once the response starts.
+    pub attempted_models: Vec<String>,
+    /// From the response headers, recorded by the stream worker.
+    pub rate_limit: Option<RateLimit>,
+}
+
+impl StreamMetadata {
+    /// Fold a later metadata event into this one. Fields the later event
+    /// lacks keep their value, so a final usage-only chunk does not clear
+    /// the `finish_reason` sent before it. `id`, `created`, `request_id`,
+    /// `idempotency_key`, `attempted_models` and `rate_limit` keep the first
+    /// value seen, `refusal` pieces are joined, and `annotations` are
+    /// collected.
+    pub fn merge(&mut self, later: StreamMetadata) {
+        self.id = self.id.take().or(later.id);
+        self.created = self.created.or(later.created);
+        self.usage = later.usage.or(self.usage.take());
+        self.finish_reason = later.finish_reason.or(self.finish_reason.take());
+        self.model = later.model.or(self.model.take());
+        self.service_tier = later.service_tier.or(self.service_tier.take());
+        self.system_fingerprint = later.system_fingerprint.or(self.system_fingerprint.take());
+        self.refusal = match (self.refusal.take(), later.refusal) {
+            (Some(mut refusal), Some(more)) => {
+                refusal.push_str(&more);
+                Some(refusal)
+            }
+            (refusal, more) => refusal.or(more),
+        };
+        self.annotations.extend(later.annotations);
+        self.request_id = self.request_id.take().or(later.request_id);
+        self.idempotency_key = self.idempotency_key.take().or(later.idempotency_key);
+        self.timings = later.timings.or(self.timings);
+        if self.attempted_models.is_empty() {
+            self.attempted_models = later.attempted_models;
+        }
+        self.rate_limit = self.rate_limit.or(later.rate_limit);
+    }
+}
+
+/// How long a stream took, measured from before the request is sent.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StreamTimings {
+    /// Until the first text chunk was parsed; `None` if none arrived.
+    pub time_to_first_chunk: Option<Duration>,
+    pub total_duration: Duration,
+    /// Number of payloads that carried text.
+    pub chunk_count: u64,
 }
 
-#[derive(Serialize, Clone, Debug)]
+impl StreamTimings {
+    /// Completion tokens per second from the first chunk to the end, or
+    /// `None` if no chunk arrived or no time passed.
+    pub fn tokens_per_second(&self, completion_tokens: u64) -> Option<f64> {
+        let generating = self
+            .total_duration
+            .checked_sub(self.time_to_first_chunk?)?
+            .as_secs_f64();
+        (generating > 0.0).then(|| completion_tokens as f64 / generating)
+    }
+}
+
+/// How long a non-streaming call took, measured from its first attempt
+/// across retries and fallback models.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CallTimings {
+    /// Until the successful response was read.
+    pub total_duration: Duration,
+    /// HTTP attempts sent, counting every model tried.
+    pub attempts: u32,
+    /// Spent sleeping before retries, part of `total_duration`.
+    pub retry_wait: Duration,
+}
+
+/// Rate-limit state reported in a response's `x-ratelimit-*` headers.
+/// Fields whose header is missing or unreadable are `None`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RateLimit {
+    pub remaining_requests: Option<u64>,
+    pub remaining_tokens: Option<u64>,
+    /// Until the request limit resets.
+    pub reset_requests: Option<Duration>,
+    /// Until the token limit resets.
+    pub reset_tokens: Option<Duration>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
 pub struct ChatMessage {
     pub role: String,
-    pub content: String,
+    pub content: MessageContent,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+/// Version written by `messages_to_json` and accepted by `messages_from_json`.
+pub const HISTORY_FORMAT_VERSION: u64 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct HistoryDocument {
+    version: u64,
+    messages: Vec<ChatMessage>,
+}
+
+/// Serialise messages as `{"version": 1, "messages": [...]}`.
+pub fn messages_to_json(messages: &[ChatMessage]) -> Result<String, SdkError> {
+    serde_json::to_string(&HistoryDocument {
+        version: HISTORY_FORMAT_VERSION,
+        messages: messages.to_vec(),
+    })
+    .map_err(|e| SdkError::runtime(e.to_string()))
+}
+
+/// Parse messages written by `messages_to_json`.
+///
+/// A bare JSON array of message objects is accepted too. Content parts are
+/// validated as they would be in a request.
+pub fn messages_from_json(data: &str) -> Result<Vec<ChatMessage>, SdkError> {
+    let invalid =
+        |e: serde_json::Error| SdkError::value(format!("Invalid chat history JSON: {}", e));
+    let value: Value = serde_json::from_str(data).map_err(invalid)?;
+    let messages = if value.is_array() {
+        serde_json::from_value(value).map_err(invalid)?
+    } else {
+        let doc: HistoryDocument = serde_json::from_value(value).map_err(invalid)?;
+        if doc.version != HISTORY_FORMAT_VERSION {
+            return Err(SdkError::value(format!(
+                "Unsupported chat history version {}; expected {}.",
+                doc.version, HISTORY_FORMAT_VERSION
+            )));
+        }
+        doc.messages
+    };
+    for (index, message) in messages.iter().enumerate() {
+        if let MessageContent::Parts(parts) = &message.content {
+            validate_content_parts(&format!("messages[{}]['content']", index), parts)?;
+        }
+    }
+
+    Ok(messages)
+}
+
+/// Message content: either plain text or a list of multimodal content parts
+/// such as `{"type": "image_url", "image_url": {"url": ...}}`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<Value>),
+}
+
+impl MessageContent {
+    /// The text of this content. For parts, the `text` fields of all
+    /// `{"type": "text"}` parts are concatenated and other parts are skipped.
+    pub fn to_text(&self) -> String {
+        match self {
+            Self::Text(text) => text.clone(),
+            Self::Parts(parts) => parts
+                .iter()
+                .filter(|part| part.get("type").and_then(Value::as_str) == Some("text"))
+                .filter_map(|part| part.get("text").and_then(Value::as_str))
+                .collect(),
+        }
+    }
+}
+
+impl Default for MessageContent {
+    fn default() -> Self {
+        Self::Text(String::new())
+    }
+}
+
+impl From<&str> for MessageContent {
+    fn from(text: &str) -> Self {
+        Self::Text(text.to_string())
+    }
+}
+
+impl From<String> for MessageContent {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
+impl PartialEq<&str> for MessageContent {
+    fn eq(&self, other: &&str) -> bool {
+        matches!(self, Self::Text(text) if text == other)
+    }
+}
+
+/// Content part types accepted in a message's `content` list.
+pub const CONTENT_PART_TYPES: [&str; 4] = ["text", "image_url", "file", "input_audio"];
+
+/// Check the shape of a list of content parts.
+///
+/// Each part must be an object whose `type` is one of `CONTENT_PART_TYPES`
+/// and which carries a payload under the key of the same name, e.g.
+/// `{"type": "text", "text": "..."}`.
+///
+/// `label` names the content in error messages, e.g. `messages[0]['content']`.
+pub fn validate_content_parts(label: &str, parts: &[Value]) -> Result<(), SdkError> {
+    for (index, part) in parts.iter().enumerate() {
+        let Some(part_type) = part.get("type").and_then(Value::as_str) else {
+            return Err(SdkError::value(format!(
+                "{}[{}] must be a dict with a string 'type'.",
+                label, index
+            )));
+        };
+        if !CONTENT_PART_TYPES.contains(&part_type) {
+            return Err(SdkError::value(format!(
+                "{}[{}] has unsupported type '{}'; expected one of {}.",
+                label,
+                index,
+                part_type,
+                CONTENT_PART_TYPES.join(", ")
+            )));
+        }
+        if part.get(part_type).is_none() {
+            return Err(SdkError::value(format!(
+                "{}[{}] of type '{}' is missing the '{}' key.",
+                label, index, part_type, part_type
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Roles accepted in `messages` unless role validation is disabled.
+pub const MESSAGE_ROLES: [&str; 5] = ["system", "user", "assistant", "tool", "developer"];
+
+/// Check that every message uses a known role.
+///
+/// Errors name the index of the offending message so callers can find it in
+/// long conversation histories.
+pub fn validate_message_roles(messages: &[ChatMessage]) -> Result<(), SdkError> {
+    for (index, message) in messages.iter().enumerate() {
+        if !MESSAGE_ROLES.contains(&message.role.as_str()) {
+            return Err(SdkError::value(format!(
+                "messages[{}] has unsupported role '{}'; expected one of {}. \
+                 Pass validate_roles=False to allow other roles.",
+                index,
+                message.role,
+                MESSAGE_ROLES.join(", ")
+            )));
+        }
+    }
+
+    Ok(())
 }
 
 #[derive(Serialize)]
 pub struct ChatRequest {
+    /// Left out when `models` asks the router to choose.
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub model: String,
     pub messages: Vec<ChatMessage>,
 
@@ -64,17 +481,64 @@ pub struct ChatRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub seed: Option<i64>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<u32>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub response_format: Option<Value>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prediction: Option<Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_tier: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modalities: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio: Option<Value>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream_options: Option<Value>,
+
+    /// OpenRouter's server-side fallback list, tried in order.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub models: Option<Vec<String>>,
+
+    /// OpenRouter usage accounting, which adds `cost` to `usage`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Value>,
+}
+
+impl ChatRequest {
+    /// The model the request is for: the first of `models` when the
+    /// router chooses, else `model`.
+    pub fn primary_model(&self) -> &str {
+        self.models
+            .as_ref()
+            .and_then(|models| models.first())
+            .unwrap_or(&self.model)
+    }
 }
 
+/// Maximum number of stop sequences accepted by the chat completions API.
+pub const MAX_STOP_SEQUENCES: usize = 4;
+
+/// Accepted values for the `reasoning_effort` request field.
+pub const REASONING_EFFORT_VALUES: [&str; 3] = ["low", "medium", "high"];
+
+/// Accepted values for the `modalities` request field.
+pub const MODALITY_VALUES: [&str; 2] = ["text", "audio"];
+
 /// Internal parameters extracted from Python keyword arguments.
 ///
 /// This is not a pyclass — it exists to pass generation options from
 /// `Provider` methods to `generate::run` and `stream::run`.
+#[derive(Clone, Default)]
 pub struct GenerationParams {
     pub messages: Vec<ChatMessage>,
     pub temperature: Option<f64>,
@@ -84,7 +548,16 @@ pub struct GenerationParams {
     pub frequency_penalty: Option<f64>,
     pub presence_penalty: Option<f64>,
     pub seed: Option<i64>,
+    pub n: Option<u32>,
     pub response_format: Option<Value>,
+    pub reasoning_effort: Option<String>,
+    pub prediction: Option<Value>,
+    pub service_tier: Option<String>,
+    pub modalities: Option<Vec<String>>,
+    pub audio: Option<Value>,
+    pub models: Option<Vec<String>>,
+    /// Ask for usage accounting, so the response reports its cost.
+    pub include_cost: bool,
 }
 
 impl GenerationParams {
@@ -106,7 +579,8 @@ impl GenerationParams {
         if let Some(sys) = system_prompt {
             messages.push(ChatMessage {
                 role: "system".to_string(),
-                content: sys.to_string(),
+                content: sys.into(),
+                ..Default::default()
             });
         }
 
@@ -117,7 +591,8 @@ impl GenerationParams {
             (_, Some(p)) => {
                 messages.push(ChatMessage {
                     role: "user".to_string(),
-                    content: p.to_string(),
+                    content: p.into(),
+                    ..Default::default()
                 });
             }
             _ => {
@@ -130,7 +605,77 @@ impl GenerationParams {
         Ok(messages)
     }
 
-    /// Convert into a serialisable `ChatRequest`.
+    /// Check parameter values before any request is sent.
+    pub fn validate(&self) -> Result<(), SdkError> {
+        check_range("temperature", self.temperature, 0.0..=2.0, "[0, 2]")?;
+        if let Some(top_p) = self.top_p
+            && !(top_p > 0.0 && top_p <= 1.0)
+        {
+            return Err(SdkError::value(format!(
+                "'top_p' must be in (0, 1], got {}.",
+                top_p
+            )));
+        }
+        check_range(
+            "frequency_penalty",
+            self.frequency_penalty,
+            -2.0..=2.0,
+            "[-2, 2]",
+        )?;
+        check_range(
+            "presence_penalty",
+            self.presence_penalty,
+            -2.0..=2.0,
+            "[-2, 2]",
+        )?;
+        if self.max_tokens == Some(0) {
+            return Err(SdkError::value("'max_tokens' must be greater than zero."));
+        }
+        if self.n == Some(0) {
+            return Err(SdkError::value("'n' must be greater than zero."));
+        }
+
+        if let Some(stop) = &self.stop {
+            validate_stop(stop)?;
+        }
+
+        if let Some(effort) = &self.reasoning_effort
+            && !REASONING_EFFORT_VALUES.contains(&effort.as_str())
+        {
+            return Err(SdkError::value(format!(
+                "'reasoning_effort' must be one of {}, got '{}'.",
+                REASONING_EFFORT_VALUES.join(", "),
+                effort
+            )));
+        }
+
+        if let Some(format) = &self.response_format {
+            validate_response_format(format)?;
+        }
+
+        if let Some(modalities) = &self.modalities {
+            validate_modalities(modalities, self.audio.as_ref())?;
+        }
+        if let Some(audio) = &self.audio {
+            validate_audio(audio)?;
+        }
+        if let Some(models) = &self.models {
+            if models.is_empty() {
+                return Err(SdkError::value("'models' must not be empty."));
+            }
+            if let Some(index) = models.iter().position(String::is_empty) {
+                return Err(SdkError::value(format!(
+                    "'models' entry {} must be a non-empty model name.",
+                    index
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Convert into a serialisable `ChatRequest`, for `model` unless
+    /// `models` is set.
     pub fn into_chat_request(
         self,
         model: String,
@@ -138,7 +683,11 @@ impl GenerationParams {
         stream_options: Option<Value>,
     ) -> ChatRequest {
         ChatRequest {
-            model,
+            model: if self.models.is_some() {
+                String::new()
+            } else {
+                model
+            },
             messages: self.messages,
             stream,
             temperature: self.temperature,
@@ -148,12 +697,110 @@ impl GenerationParams {
             frequency_penalty: self.frequency_penalty,
             presence_penalty: self.presence_penalty,
             seed: self.seed,
+            n: self.n,
             response_format: self.response_format,
+            reasoning_effort: self.reasoning_effort,
+            prediction: self.prediction,
+            service_tier: self.service_tier,
+            modalities: self.modalities,
+            audio: self.audio,
             stream_options,
+            models: self.models,
+            usage: self
+                .include_cost
+                .then(|| serde_json::json!({"include": true})),
         }
     }
 }
 
+fn validate_modalities(modalities: &[String], audio: Option<&Value>) -> Result<(), SdkError> {
+    for (index, modality) in modalities.iter().enumerate() {
+        if !MODALITY_VALUES.contains(&modality.as_str()) {
+            return Err(SdkError::value(format!(
+                "'modalities' entry {} must be one of {}, got '{}'.",
+                index,
+                MODALITY_VALUES.join(", "),
+                modality
+            )));
+        }
+    }
+    if modalities.iter().any(|m| m == "audio") && audio.is_none() {
+        return Err(SdkError::value(
+            "'audio' (voice and format) is required when 'modalities' includes 'audio'.",
+        ));
+    }
+
+    Ok(())
+}
+
+fn validate_audio(audio: &Value) -> Result<(), SdkError> {
+    for key in ["voice", "format"] {
+        if !audio.get(key).is_some_and(Value::is_string) {
+            return Err(SdkError::value(format!(
+                "'audio' must be a dict with a string '{}'.",
+                key
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn check_range(
+    name: &str,
+    value: Option<f64>,
+    range: std::ops::RangeInclusive<f64>,
+    display: &str,
+) -> Result<(), SdkError> {
+    match value {
+        Some(v) if !range.contains(&v) => Err(SdkError::value(format!(
+            "'{}' must be in {}, got {}.",
+            name, display, v
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn validate_stop(stop: &Value) -> Result<(), SdkError> {
+    let entries = match stop {
+        Value::String(_) => std::slice::from_ref(stop),
+        Value::Array(entries) => entries.as_slice(),
+        _ => {
+            return Err(SdkError::value(
+                "'stop' must be a string or list of strings.",
+            ));
+        }
+    };
+
+    if entries.len() > MAX_STOP_SEQUENCES {
+        return Err(SdkError::value(format!(
+            "'stop' accepts at most {} sequences, got {}.",
+            MAX_STOP_SEQUENCES,
+            entries.len()
+        )));
+    }
+
+    for (index, entry) in entries.iter().enumerate() {
+        match entry.as_str() {
+            Some(s) if !s.is_empty() => {}
+            Some(_) => {
+                return Err(SdkError::value(format!(
+                    "'stop' sequences must be non-empty strings, got '' at index {}.",
+                    index
+                )));
+            }
+            None => {
+                return Err(SdkError::value(format!(
+                    "'stop' sequences must be strings, got {} at index {}.",
+                    entry, index
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
 // ---------------------------------------------------------------------------
 // Response parsing (unchanged)
 // ---------------------------------------------------------------------------
@@ -164,21 +811,48 @@ struct ChatChoice {
     finish_reason: Option<String>,
 }
 
+/// Reasoning models report their chain of thought under either
+/// `reasoning_content` (DeepSeek) or `reasoning` (OpenRouter).
+///
+/// `content` is `null` when a model replies with audio only.
 #[derive(Deserialize)]
 struct ChatResponseMessage {
-    content: String,
+    content: Option<MessageContent>,
+    refusal: Option<String>,
+    reasoning_content: Option<String>,
+    reasoning: Option<String>,
+    audio: Option<AudioOutput>,
+    #[serde(default, deserialize_with = "lenient_annotations")]
+    annotations: Vec<Annotation>,
+}
+
+impl ChatResponseMessage {
+    fn text(&self) -> String {
+        self.content
+            .as_ref()
+            .map(MessageContent::to_text)
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Deserialize)]
 struct ChatResponse {
+    id: Option<String>,
+    created: Option<u64>,
     choices: Vec<ChatChoice>,
+    #[serde(default, deserialize_with = "lenient_usage")]
     usage: Option<Usage>,
     model: Option<String>,
+    service_tier: Option<String>,
+    system_fingerprint: Option<String>,
 }
 
 #[derive(Deserialize)]
 struct ErrorDetail {
     message: String,
+    code: Option<Value>,
+    #[serde(rename = "type")]
+    error_type: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -188,64 +862,267 @@ struct ErrorResponse {
 
 #[derive(Deserialize)]
 struct DeltaMessage {
-    content: Option<String>,
+    content: Option<MessageContent>,
+    refusal: Option<String>,
+    reasoning_content: Option<String>,
+    reasoning: Option<String>,
+    #[serde(default, deserialize_with = "lenient_annotations")]
+    annotations: Vec<Annotation>,
 }
 
 #[derive(Deserialize)]
 struct StreamChoice {
+    #[serde(default)]
+    index: u32,
     delta: DeltaMessage,
     finish_reason: Option<String>,
 }
 
 #[derive(Deserialize)]
 struct StreamChunk {
+    id: Option<String>,
+    created: Option<u64>,
     choices: Vec<StreamChoice>,
+    #[serde(default, deserialize_with = "lenient_usage")]
     usage: Option<Usage>,
     model: Option<String>,
+    service_tier: Option<String>,
+    system_fingerprint: Option<String>,
 }
 
 pub fn parse_chat_response(response_text: &str) -> Result<String, SdkError> {
-    let chat_response: ChatResponse = serde_json::from_str(response_text)
-        .map_err(|e| SdkError::value(format!("Failed to parse response: {}", e)))?;
+    let chat_response: ChatResponse = serde_json::from_str(response_text).map_err(|e| {
+        SdkError::value(format!("Failed to parse response: {}", e)).with_body(response_text)
+    })?;
 
     chat_response
         .choices
         .first()
-        .map(|choice| choice.message.content.clone())
-        .ok_or_else(|| SdkError::value("No choices returned in API response"))
+        .map(|choice| choice.message.text())
+        .ok_or_else(|| no_choices(response_text))
+}
+
+fn no_choices(response_text: &str) -> SdkError {
+    SdkError::value("No choices returned in API response").with_body(response_text)
 }
 
 pub fn parse_chat_response_full(response_text: &str) -> Result<ParsedChatResult, SdkError> {
-    let chat_response: ChatResponse = serde_json::from_str(response_text)
-        .map_err(|e| SdkError::value(format!("Failed to parse response: {}", e)))?;
+    let chat_response: ChatResponse = serde_json::from_str(response_text).map_err(|e| {
+        SdkError::value(format!("Failed to parse response: {}", e)).with_body(response_text)
+    })?;
 
     let choice = chat_response
         .choices
         .first()
-        .ok_or_else(|| SdkError::value("No choices returned in API response"))?;
+        .ok_or_else(|| no_choices(response_text))?;
 
     Ok(ParsedChatResult {
-        text: choice.message.content.clone(),
+        id: chat_response.id,
+        created: chat_response.created,
+        text: choice.message.text(),
+        reasoning: choice
+            .message
+            .reasoning_content
+            .clone()
+            .or_else(|| choice.message.reasoning.clone()),
+        audio: choice.message.audio.clone(),
+        refusal: choice.message.refusal.clone(),
+        annotations: choice.message.annotations.clone(),
         usage: chat_response.usage,
         finish_reason: choice.finish_reason.clone(),
         model: chat_response.model,
+        service_tier: chat_response.service_tier,
+        system_fingerprint: chat_response.system_fingerprint,
+        request_id: None,
+        idempotency_key: None,
+        attempted_models: Vec::new(),
+        rate_limit: None,
+        raw: serde_json::from_str(response_text).ok(),
+        timings: None,
     })
 }
 
+/// A model as listed by `/models` or described by `/models/{id}`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelObject {
+    pub id: String,
+    /// Unix timestamp the model was created at.
+    pub created: Option<u64>,
+    pub owned_by: Option<String>,
+    /// Display name: OpenRouter's `name`, or Anthropic's `display_name`.
+    pub name: Option<String>,
+    /// Context window in tokens, as OpenRouter lists it.
+    pub context_length: Option<u64>,
+    /// Prices in USD, as OpenRouter lists them; see `pricing::parse_catalog`.
+    pub pricing: Option<Pricing>,
+    /// The model's entry as the provider sent it.
+    pub raw: Value,
+}
+
+impl ModelObject {
+    /// Read one model entry, or `None` if it has no string `id`. Fields of
+    /// the wrong type are left unset rather than failing the entry.
+    fn from_value(raw: Value) -> Option<Self> {
+        let text = |key: &str| raw.get(key)?.as_str().map(str::to_string);
+        let number = |key: &str| raw.get(key)?.as_u64();
+        Some(Self {
+            id: text("id")?,
+            created: number("created"),
+            owned_by: text("owned_by"),
+            name: text("name").or_else(|| text("display_name")),
+            context_length: number("context_length"),
+            pricing: raw.get("pricing").and_then(Value::as_object).map(|prices| {
+                prices
+                    .iter()
+                    .filter_map(|(key, value)| Some((key.clone(), price(value)?)))
+                    .collect()
+            }),
+            raw,
+        })
+    }
+}
+
+fn invalid_models_response(e: impl std::fmt::Display, response_text: &str) -> SdkError {
+    SdkError::value(format!("Failed to parse the models response: {}", e)).with_body(response_text)
+}
+
+/// Parse a `/models` response. Entries without an `id` are skipped, and a
+/// response without `data` lists no models.
+pub fn parse_model_list(response_text: &str) -> Result<Vec<ModelObject>, SdkError> {
+    let value: Value = serde_json::from_str(response_text)
+        .map_err(|e| invalid_models_response(e, response_text))?;
+    let data = match value.get("data") {
+        None | Some(Value::Null) => return Ok(Vec::new()),
+        Some(Value::Array(data)) => data,
+        Some(_) => {
+            return Err(invalid_models_response(
+                "'data' is not a list",
+                response_text,
+            ));
+        }
+    };
+    Ok(data
+        .iter()
+        .cloned()
+        .filter_map(ModelObject::from_value)
+        .collect())
+}
+
+/// Parse a `/models/{id}` response. Some servers wrap the model in `data`,
+/// as OpenRouter does, which is unwrapped.
+pub fn parse_model(response_text: &str) -> Result<ModelObject, SdkError> {
+    let mut value: Value = serde_json::from_str(response_text)
+        .map_err(|e| invalid_models_response(e, response_text))?;
+    if value.get("id").is_none()
+        && let Some(data) = value.get_mut("data").filter(|data| data.is_object())
+    {
+        value = data.take();
+    }
+    ModelObject::from_value(value)
+        .ok_or_else(|| invalid_models_response("the model has no 'id'", response_text))
+}
+
+/// Whether content filtering stopped a response or the model refused it.
+/// Anthropic refusals arrive as a `content_filter` finish reason.
+pub fn content_filtered(finish_reason: Option<&str>, refusal: Option<&str>) -> bool {
+    finish_reason == Some("content_filter") || refusal.is_some()
+}
+
+/// The `ContentFilterError` for a response that is `content_filtered`, or `None`.
+pub fn content_filter_error(response_text: &str) -> Option<SdkError> {
+    let result = parse_chat_response_full(response_text).ok()?;
+    content_filtered(result.finish_reason.as_deref(), result.refusal.as_deref())
+        .then(|| SdkError::content_filter(result.refusal))
+}
+
 pub fn api_error_message(status: StatusCode, response_text: &str) -> String {
-    if let Ok(err) = serde_json::from_str::<ErrorResponse>(response_text) {
-        return format!("API error ({}): {}", status, err.error.message);
+    api_error(status, response_text).message
+}
+
+/// The error for an unsuccessful response, with the `code` and `type` of a
+/// structured error body when the provider sends one. Numeric codes, as
+/// OpenRouter sends, are kept as their decimal text.
+pub fn api_error(status: StatusCode, response_text: &str) -> ApiError {
+    let (message, code, error_type) = match serde_json::from_str::<ErrorResponse>(response_text) {
+        Ok(err) => {
+            let code = err.error.code.and_then(|code| match code {
+                Value::String(code) => Some(code),
+                Value::Null => None,
+                other => Some(other.to_string()),
+            });
+            (err.error.message, code, err.error.error_type)
+        }
+        Err(_) => (response_text.to_string(), None, None),
+    };
+
+    ApiError {
+        context_length: context_length_exceeded(code.as_deref(), &message),
+        message: format!("API error ({}): {}", status, message),
+        status_code: status.as_u16(),
+        code,
+        error_type,
+        body: response_text.to_string(),
+        request_id: None,
     }
+}
+
+/// Phrases providers use when a request overflows the context window.
+const CONTEXT_LENGTH_PHRASES: [&str; 3] = [
+    "context_length_exceeded",
+    "maximum context length",
+    "context window",
+];
+
+/// Whether an error `code` and `message` say the request did not fit the
+/// context window, with the token counts the message gives. Reads OpenAI's
+/// wording ("maximum context length is 8192 tokens. However, your messages
+/// resulted in 9000 tokens"), which OpenRouter and vLLM share, and
+/// Anthropic's ("prompt is too long: 208310 tokens > 200000 maximum").
+pub fn context_length_exceeded(code: Option<&str>, message: &str) -> Option<ContextLength> {
+    let message = message.to_ascii_lowercase();
+    if let Some(rest) = text_after(&message, "prompt is too long:") {
+        return Some(ContextLength {
+            requested_tokens: leading_number(rest),
+            max_context_tokens: text_after(rest, ">").and_then(leading_number),
+        });
+    }
+    let matched = code == Some("context_length_exceeded")
+        || CONTEXT_LENGTH_PHRASES
+            .iter()
+            .any(|phrase| message.contains(phrase));
+    matched.then(|| ContextLength {
+        max_context_tokens: text_after(&message, "maximum context length is")
+            .and_then(leading_number),
+        requested_tokens: ["resulted in", "requested"]
+            .iter()
+            .find_map(|marker| text_after(&message, marker).and_then(leading_number)),
+    })
+}
+
+fn text_after<'a>(text: &'a str, marker: &str) -> Option<&'a str> {
+    text.find(marker).map(|start| &text[start + marker.len()..])
+}
 
-    format!("API error ({}): {}", status, response_text)
+/// The number at the start of `text`, allowing "about" and thousands
+/// separators before it ends, as in "about 130,000 tokens".
+fn leading_number(text: &str) -> Option<u64> {
+    let text = text.trim_start();
+    let text = text.strip_prefix("about ").unwrap_or(text);
+    let digits: String = text
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == ',')
+        .filter(char::is_ascii_digit)
+        .collect();
+    digits.parse().ok()
 }
 
 #[derive(Debug, PartialEq)]
 pub enum StreamEvent {
     Done,
     Content(String),
+    Reasoning(String),
     Ignore,
-    Metadata(StreamMetadata),
+    Metadata(Box<StreamMetadata>),
 }
 
 pub fn parse_sse_line(line: &str) -> Result<Vec<StreamEvent>, SdkError> {
@@ -258,19 +1135,238 @@ pub fn parse_sse_line(line: &str) -> Result<Vec<StreamEvent>, SdkError> {
 }
 
 pub fn parse_sse_event(event: &str) -> Result<Vec<StreamEvent>, SdkError> {
-    let mut data_lines = Vec::new();
-    for line in event.lines() {
-        let trimmed = line.trim_end_matches('\r');
-        if let Some(data) = trimmed.strip_prefix("data:") {
-            data_lines.push(data.trim_start());
+    let data = sse_data(event);
+    if sse_event_type(event) == Some("error") {
+        return Err(sse_error_event(data.as_deref().unwrap_or_default()));
+    }
+
+    match data {
+        Some(data) => parse_sse_data(&data),
+        None => Ok(vec![StreamEvent::Ignore]),
+    }
+}
+
+/// The `event:` field of an SSE event, if present.
+fn sse_event_type(event: &str) -> Option<&str> {
+    sse_lines(event)
+        .find_map(|line| line.strip_prefix("event:"))
+        .map(str::trim)
+}
+
+/// Build the error for an `event: error` SSE event.
+///
+/// The data may be a JSON error object (with or without an `error`
+/// wrapper) or plain text.
+fn sse_error_event(data: &str) -> SdkError {
+    match serde_json::from_str::<Value>(data) {
+        Ok(value) => stream_error(value.get("error").unwrap_or(&value)),
+        Err(_) if data.trim().is_empty() => {
+            SdkError::runtime("Stream error from provider: no details given.")
         }
+        Err(_) => SdkError::runtime(format!("Stream error from provider: {}", data.trim())),
     }
+}
 
-    if data_lines.is_empty() {
-        return Ok(vec![StreamEvent::Ignore]);
+/// Build the error for an `error` object sent in place of a stream chunk.
+fn stream_error(error: &Value) -> SdkError {
+    let as_text = |value: &Value| match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    let message = as_text(error.get("message").unwrap_or(error));
+
+    match error.get("code").filter(|code| !code.is_null()) {
+        Some(code) => SdkError::runtime(format!(
+            "Stream error from provider (code {}): {}",
+            as_text(code),
+            message
+        )),
+        None => SdkError::runtime(format!("Stream error from provider: {}", message)),
     }
+}
 
-    parse_sse_data(&data_lines.join("\n"))
+/// One SSE payload as yielded by `stream_text(detailed=True)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamChunkData {
+    pub text: String,
+    pub index: u32,
+    pub finish_reason: Option<String>,
+    pub raw: Value,
+}
+
+/// Parse an SSE event into `StreamChunkData` for detailed streaming.
+///
+/// Unlike `parse_sse_event`, every data payload produces output, including
+/// role-only and usage-only ones, so unusual provider output stays visible.
+/// A payload yields one chunk per choice, so `n > 1` streams can be told
+/// apart by `index`; a payload without choices yields a single chunk for
+/// index 0. Returns no chunks for events without data and for `[DONE]`.
+pub fn parse_sse_chunks(event: &str) -> Result<Vec<StreamChunkData>, SdkError> {
+    let Some(data) = sse_data(event) else {
+        return Ok(Vec::new());
+    };
+    if data == "[DONE]" {
+        return Ok(Vec::new());
+    }
+
+    let raw: Value = serde_json::from_str(&data).map_err(|e| invalid_stream_chunk(e, &data))?;
+    let chunk = StreamChunk::deserialize(&raw).map_err(|e| invalid_stream_chunk(e, &data))?;
+
+    if chunk.choices.is_empty() {
+        return Ok(vec![StreamChunkData {
+            text: String::new(),
+            index: 0,
+            finish_reason: None,
+            raw,
+        }]);
+    }
+
+    Ok(chunk
+        .choices
+        .into_iter()
+        .map(|choice| StreamChunkData {
+            text: choice
+                .delta
+                .content
+                .as_ref()
+                .map(MessageContent::to_text)
+                .unwrap_or_default(),
+            index: choice.index,
+            finish_reason: choice.finish_reason,
+            raw: raw.clone(),
+        })
+        .collect())
+}
+
+/// Join the `data:` lines of an SSE event, or `None` if it has none.
+pub(crate) fn sse_data(event: &str) -> Option<String> {
+    let data_lines: Vec<&str> = sse_lines(event)
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(str::trim_start)
+        .collect();
+
+    (!data_lines.is_empty()).then(|| data_lines.join("\n"))
+}
+
+/// The `id:` field of an SSE event, if present.
+///
+/// Per the SSE spec, ids containing a NUL character are ignored.
+pub fn sse_event_id(event: &str) -> Option<&str> {
+    sse_lines(event)
+        .filter_map(|line| line.strip_prefix("id:"))
+        .map(|id| id.strip_prefix(' ').unwrap_or(id))
+        .filter(|id| !id.contains('\0'))
+        .last()
+}
+
+/// The field lines of an SSE event, without comment lines.
+fn sse_lines(event: &str) -> impl Iterator<Item = &str> {
+    event
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !is_sse_comment(line))
+}
+
+/// Whether `line` is an SSE comment such as `: keep-alive`.
+///
+/// Per the SSE spec, lines starting with a colon carry no data and are only
+/// used to hold the connection open.
+pub fn is_sse_comment(line: &str) -> bool {
+    line.starts_with(':')
+}
+
+/// Splits a byte stream into SSE lines.
+///
+/// Bytes are buffered until a full line arrives and only then decoded, so a
+/// multibyte character split across network reads is kept intact. A `\n`
+/// byte never occurs inside a multibyte UTF-8 sequence.
+///
+/// Lines are read in place behind a cursor and only copied when they hold
+/// invalid UTF-8. The consumed prefix is dropped on the next `extend`, which
+/// moves just the unfinished tail and keeps the allocation.
+#[derive(Debug, Default)]
+pub struct SseLineBuffer {
+    buffer: Vec<u8>,
+    /// Start of the first line not yet returned.
+    start: usize,
+    /// Bytes from `start` already searched for a newline.
+    searched: usize,
+}
+
+impl SseLineBuffer {
+    /// Append `bytes` to the buffer.
+    pub fn extend(&mut self, bytes: &[u8]) {
+        if self.start > 0 {
+            self.buffer.drain(..self.start);
+            self.start = 0;
+        }
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// The next complete line, without its terminator.
+    pub fn next_line(&mut self) -> Option<Cow<'_, str>> {
+        let line_start = self.start;
+        let unsearched = &self.buffer[line_start + self.searched..];
+        let Some(newline) = unsearched.iter().position(|&b| b == b'\n') else {
+            self.searched += unsearched.len();
+            return None;
+        };
+
+        let line_end = line_start + self.searched + newline;
+        self.start = line_end + 1;
+        self.searched = 0;
+        Some(decode_sse_line(&self.buffer[line_start..line_end]))
+    }
+
+    /// Append `bytes` and return the lines they complete, without line
+    /// terminators.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.extend(bytes);
+        std::iter::from_fn(|| self.next_line().map(Cow::into_owned)).collect()
+    }
+
+    /// The unterminated line left at the end of the stream, if any.
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = &self.buffer[self.start..];
+        let line = (!rest.is_empty()).then(|| decode_sse_line(rest).into_owned());
+        self.clear();
+        line
+    }
+
+    /// Drop any buffered partial line.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.start = 0;
+        self.searched = 0;
+    }
+}
+
+fn decode_sse_line(line: &[u8]) -> Cow<'_, str> {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    String::from_utf8_lossy(line)
+}
+
+/// Longest part of a raw payload quoted in a parse error.
+const MAX_QUOTED_PAYLOAD_BYTES: usize = 2048;
+
+fn invalid_stream_chunk(e: serde_json::Error, data: &str) -> SdkError {
+    let payload = redact_secrets(data);
+    let mut end = payload.len().min(MAX_QUOTED_PAYLOAD_BYTES);
+    while !payload.is_char_boundary(end) {
+        end -= 1;
+    }
+    let omitted = if end < payload.len() {
+        format!(" ... ({} bytes total)", payload.len())
+    } else {
+        String::new()
+    };
+    SdkError::runtime(format!(
+        "Failed to parse streaming response chunk: {}. Payload: {}{}",
+        e,
+        &payload[..end],
+        omitted
+    ))
+    .with_body(data)
 }
 
 fn parse_sse_data(data: &str) -> Result<Vec<StreamEvent>, SdkError> {
@@ -278,28 +1374,71 @@ fn parse_sse_data(data: &str) -> Result<Vec<StreamEvent>, SdkError> {
         return Ok(vec![StreamEvent::Done]);
     }
 
-    let chunk: StreamChunk = serde_json::from_str(data).map_err(|e| {
-        SdkError::runtime(format!("Failed to parse streaming response chunk: {}", e))
-    })?;
+    let value: Value = serde_json::from_str(data).map_err(|e| invalid_stream_chunk(e, data))?;
+    // Some gateways report failures as an `error` object mid-stream instead
+    // of an HTTP error status. Others send `"error": null` on every chunk.
+    if let Some(error) = value.get("error").filter(|error| !error.is_null()) {
+        return Err(stream_error(error));
+    }
+    let chunk = StreamChunk::deserialize(&value).map_err(|e| invalid_stream_chunk(e, data))?;
 
     let mut events = Vec::new();
 
     let first_choice = chunk.choices.first();
-    let content = first_choice.and_then(|choice| choice.delta.content.as_ref());
+    let reasoning = first_choice.and_then(|choice| {
+        choice
+            .delta
+            .reasoning_content
+            .as_ref()
+            .or(choice.delta.reasoning.as_ref())
+    });
+
+    if let Some(reasoning) = reasoning
+        && !reasoning.is_empty()
+    {
+        events.push(StreamEvent::Reasoning(reasoning.clone()));
+    }
+
+    let content = first_choice
+        .and_then(|choice| choice.delta.content.as_ref())
+        .map(MessageContent::to_text);
 
     if let Some(content) = content
         && !content.is_empty()
     {
-        events.push(StreamEvent::Content(content.clone()));
+        events.push(StreamEvent::Content(content));
     }
 
     let finish_reason = first_choice.and_then(|c| c.finish_reason.clone());
-    if chunk.usage.is_some() || finish_reason.is_some() {
-        events.push(StreamEvent::Metadata(StreamMetadata {
+    let refusal = first_choice
+        .and_then(|choice| choice.delta.refusal.clone())
+        .filter(|refusal| !refusal.is_empty());
+    let annotations = first_choice
+        .map(|choice| choice.delta.annotations.clone())
+        .unwrap_or_default();
+    let has_identity = chunk.id.is_some() || chunk.created.is_some();
+    if chunk.usage.is_some()
+        || finish_reason.is_some()
+        || refusal.is_some()
+        || !annotations.is_empty()
+        || has_identity
+    {
+        events.push(StreamEvent::Metadata(Box::new(StreamMetadata {
+            id: chunk.id,
+            created: chunk.created,
             usage: chunk.usage,
             finish_reason,
             model: chunk.model,
-        }));
+            service_tier: chunk.service_tier,
+            system_fingerprint: chunk.system_fingerprint,
+            refusal,
+            annotations,
+            request_id: None,
+            idempotency_key: None,
+            timings: None,
+            attempted_models: Vec::new(),
+            rate_limit: None,
+        })));
     }
 
     if events.is_empty() {
@@ -0,0 +1,198 @@
+use crate::errors::SdkError;
+use crate::provider::json_to_py;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use serde_json::Value;
+use std::path::PathBuf;
+
+/// Default upper bound for attached files (20 MB).
+pub const DEFAULT_MAX_PART_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Accepted values for the `detail` field of an image part.
+pub const IMAGE_DETAIL_VALUES: [&str; 3] = ["low", "high", "auto"];
+
+/// Detect an image MIME type from its magic bytes.
+///
+/// Only the formats accepted by OpenAI-compatible vision endpoints are
+/// recognised; anything else returns `None` rather than guessing.
+pub fn detect_image_mime(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.starts_with(b"\xff\xd8\xff") {
+        Some("image/jpeg")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+/// Detect the MIME type of a document attached with `file_part`.
+///
+/// PDFs and the supported image formats are recognised; anything else is sent
+/// as `application/octet-stream` and left for the provider to accept or reject.
+pub fn detect_file_mime(data: &[u8]) -> &'static str {
+    if data.starts_with(b"%PDF-") {
+        "application/pdf"
+    } else {
+        detect_image_mime(data).unwrap_or("application/octet-stream")
+    }
+}
+
+/// Build an `image_url` content part with a base64 data URL.
+pub fn build_image_part(
+    data: &[u8],
+    detail: Option<&str>,
+    max_bytes: u64,
+) -> Result<Value, SdkError> {
+    check_size(data.len() as u64, max_bytes)?;
+
+    if let Some(detail) = detail
+        && !IMAGE_DETAIL_VALUES.contains(&detail)
+    {
+        return Err(SdkError::value(format!(
+            "'detail' must be one of {}, got '{}'.",
+            IMAGE_DETAIL_VALUES.join(", "),
+            detail
+        )));
+    }
+
+    let mime = detect_image_mime(data).ok_or_else(|| {
+        SdkError::value("Unsupported image format; expected PNG, JPEG, WebP, or GIF.")
+    })?;
+
+    let mut image_url = serde_json::Map::new();
+    image_url.insert("url".to_string(), Value::String(data_url(mime, data)));
+    if let Some(detail) = detail {
+        image_url.insert("detail".to_string(), Value::String(detail.to_string()));
+    }
+
+    Ok(serde_json::json!({"type": "image_url", "image_url": image_url}))
+}
+
+/// Build a `file` content part with a base64 data URL.
+pub fn build_file_part(data: &[u8], filename: &str, max_bytes: u64) -> Result<Value, SdkError> {
+    check_size(data.len() as u64, max_bytes)?;
+
+    Ok(serde_json::json!({
+        "type": "file",
+        "file": {
+            "filename": filename,
+            "file_data": data_url(detect_file_mime(data), data),
+        },
+    }))
+}
+
+fn data_url(mime: &str, data: &[u8]) -> String {
+    format!("data:{};base64,{}", mime, STANDARD.encode(data))
+}
+
+fn check_size(len: u64, max_bytes: u64) -> Result<(), SdkError> {
+    if len > max_bytes {
+        return Err(SdkError::value(format!(
+            "File is {} bytes, which exceeds the {} byte limit.",
+            len, max_bytes
+        )));
+    }
+    Ok(())
+}
+
+/// Read `bytes` directly, or a file from a `str`/`os.PathLike` path.
+///
+/// The file size is checked before reading so oversized files are rejected
+/// without loading them into memory. Returns the path alongside the data
+/// when one was given.
+fn read_source(source: &Bound<'_, PyAny>, max_bytes: u64) -> PyResult<(Vec<u8>, Option<PathBuf>)> {
+    if let Ok(bytes) = source.cast::<PyBytes>() {
+        return Ok((bytes.as_bytes().to_vec(), None));
+    }
+
+    let path: PathBuf = source.extract().map_err(|_| {
+        SdkError::value("Expected a file path (str or os.PathLike) or bytes.").into_pyerr()
+    })?;
+    let metadata = std::fs::metadata(&path).map_err(|e| {
+        SdkError::value(format!("Cannot read '{}': {}", path.display(), e)).into_pyerr()
+    })?;
+    check_size(metadata.len(), max_bytes).map_err(SdkError::into_pyerr)?;
+
+    let data = std::fs::read(&path).map_err(|e| {
+        SdkError::value(format!("Cannot read '{}': {}", path.display(), e)).into_pyerr()
+    })?;
+    Ok((data, Some(path)))
+}
+
+/// Build an image content part from a local file or raw bytes.
+///
+/// The image format (PNG, JPEG, WebP, or GIF) is detected from its magic
+/// bytes and the data is embedded as a base64 data URL, ready to drop into
+/// a message's ``content`` list.
+///
+/// Args:
+///     path_or_bytes (str | os.PathLike | bytes): Image file path or data.
+///     detail (str | None): Optional detail level: ``"low"``, ``"high"``,
+///         or ``"auto"``.
+///     max_bytes (int): Maximum accepted size. Defaults to 20 MB.
+///
+/// Returns:
+///     dict: ``{"type": "image_url", "image_url": {"url": "data:...", ...}}``
+///
+/// Raises:
+///     ValueError: If the file cannot be read, exceeds ``max_bytes``, or is
+///         not a supported image format.
+#[pyfunction]
+#[pyo3(signature = (path_or_bytes, detail=None, *, max_bytes=DEFAULT_MAX_PART_BYTES))]
+#[pyo3(text_signature = "(path_or_bytes, detail=None, *, max_bytes=20971520)")]
+pub fn image_part(
+    py: Python<'_>,
+    path_or_bytes: &Bound<'_, PyAny>,
+    detail: Option<&str>,
+    max_bytes: u64,
+) -> PyResult<Py<PyAny>> {
+    let (data, _) = read_source(path_or_bytes, max_bytes)?;
+    let part = build_image_part(&data, detail, max_bytes).map_err(SdkError::into_pyerr)?;
+    json_to_py(py, &part)
+}
+
+/// Build a file content part (e.g. a PDF) from a local file or raw bytes.
+///
+/// The data is embedded as a base64 data URL for document-understanding
+/// models. PDFs are detected from their magic bytes.
+///
+/// Args:
+///     path_or_bytes (str | os.PathLike | bytes): File path or data.
+///     filename (str | None): Name reported to the model. Defaults to the
+///         file's name, or ``"document.pdf"``/``"file"`` for raw bytes.
+///     max_bytes (int): Maximum accepted size. Defaults to 20 MB.
+///
+/// Returns:
+///     dict: ``{"type": "file", "file": {"filename": ..., "file_data": "data:..."}}``
+///
+/// Raises:
+///     ValueError: If the file cannot be read or exceeds ``max_bytes``.
+#[pyfunction]
+#[pyo3(signature = (path_or_bytes, filename=None, *, max_bytes=DEFAULT_MAX_PART_BYTES))]
+#[pyo3(text_signature = "(path_or_bytes, filename=None, *, max_bytes=20971520)")]
+pub fn file_part(
+    py: Python<'_>,
+    path_or_bytes: &Bound<'_, PyAny>,
+    filename: Option<String>,
+    max_bytes: u64,
+) -> PyResult<Py<PyAny>> {
+    let (data, path) = read_source(path_or_bytes, max_bytes)?;
+    let filename = filename
+        .or_else(|| {
+            path.as_deref()
+                .and_then(|p| p.file_name())
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| match detect_file_mime(&data) {
+            "application/pdf" => "document.pdf".to_string(),
+            _ => "file".to_string(),
+        });
+    let part = build_file_part(&data, &filename, max_bytes).map_err(SdkError::into_pyerr)?;
+    json_to_py(py, &part)
+}
@@ -0,0 +1,96 @@
+use crate::errors::SdkError;
+use crate::http::{Deadline, RetrySettings, RetryTally, send_with_retries};
+use reqwest::header::HeaderMap;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, LazyLock, Mutex};
+
+/// A model's prices in USD from the catalog's `pricing`, keyed by what
+/// they are for: `prompt` and `completion` per token, `request` per call,
+/// `image` per image, and whatever else the provider lists.
+pub type Pricing = BTreeMap<String, f64>;
+
+/// Model ids and their prices, as read from one `/models` catalog.
+pub type Catalog = HashMap<String, Pricing>;
+
+/// Catalogs fetched so far, keyed by URL and shared by every provider, so
+/// the catalog is downloaded once per process rather than once per call.
+static CATALOGS: LazyLock<Mutex<HashMap<String, Arc<Catalog>>>> = LazyLock::new(Default::default);
+
+#[derive(Deserialize)]
+struct ModelList {
+    #[serde(default)]
+    data: Vec<ModelEntry>,
+}
+
+#[derive(Deserialize)]
+struct ModelEntry {
+    id: String,
+    #[serde(default)]
+    pricing: Option<HashMap<String, Value>>,
+}
+
+/// Read a `/models` response into a catalog. Prices may be numbers or, as
+/// OpenRouter sends them, decimal strings; anything else is skipped, as
+/// are models without `pricing`.
+pub fn parse_catalog(response_text: &str) -> Result<Catalog, SdkError> {
+    let list: ModelList = serde_json::from_str(response_text).map_err(|e| {
+        SdkError::value(format!("Failed to parse the models catalog: {}", e))
+            .with_body(response_text)
+    })?;
+    Ok(list
+        .data
+        .into_iter()
+        .filter_map(|model| {
+            let pricing = model
+                .pricing?
+                .iter()
+                .filter_map(|(key, value)| Some((key.clone(), price(value)?)))
+                .collect();
+            Some((model.id, pricing))
+        })
+        .collect())
+}
+
+pub(crate) fn price(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// The catalog at `url`, fetched on first use with the provider's timeout
+/// and retry settings and then served from the cache, unless `refresh`
+/// asks for a new copy.
+pub async fn catalog(
+    client: &reqwest::Client,
+    url: &str,
+    headers: HeaderMap,
+    retries: &RetrySettings,
+    refresh: bool,
+) -> Result<Arc<Catalog>, SdkError> {
+    if !refresh
+        && let Some(catalog) = CATALOGS
+            .lock()
+            .ok()
+            .and_then(|catalogs| catalogs.get(url).cloned())
+    {
+        return Ok(catalog);
+    }
+
+    let response = send_with_retries(
+        |timeout| client.get(url).headers(headers.clone()).timeout(timeout),
+        retries,
+        Deadline::start(retries.total_timeout),
+        &mut RetryTally::default(),
+    )
+    .await?;
+
+    let catalog = Arc::new(parse_catalog(&response.text)?);
+    if let Ok(mut catalogs) = CATALOGS.lock() {
+        catalogs.insert(url.to_string(), catalog.clone());
+    }
+    Ok(catalog)
+}
@@ -1,10 +1,37 @@
+use crate::anthropic::{self, ANTHROPIC_VERSION};
+use crate::astream::{self, AsyncTextStream};
+use crate::chat::Chat;
+use crate::circuit::CircuitBreaker;
 use crate::errors::SdkError;
 use crate::generate;
-use crate::models::{ChatMessage, GenerationParams, ParsedChatResult, Usage};
-use crate::stream::{self, TextStream};
+use crate::http::{DEFAULT_RETRY_STATUSES, IDEMPOTENCY_KEY, RetrySettings, new_idempotency_key};
+use crate::limiter::{ConcurrencyLimit, RateLimiter};
+use crate::list_models::{self, ModelInfo};
+use crate::messages::Message;
+use crate::models::{
+    Annotation, AudioOutput, CallTimings, ChatMessage, ChatRequest, CompletionTokensDetails,
+    GenerationParams, MessageContent, ParsedChatResult, RateLimit, Usage, content_filtered,
+    validate_content_parts, validate_message_roles,
+};
+use crate::pricing;
+use crate::retry_hook::RetryHook;
+use crate::runtime;
+use crate::segment::{SplitMode, StopPattern};
+use crate::stream::{self, StreamSettings, TextStream};
+use crate::structured::{
+    self, ObjectStream, ResolvedFormat, build_json_schema_format, resolve_format,
+};
+use crate::trim::{extract_trim_budget, trim_history};
+use crate::usage::UsageTracker;
 use pyo3::prelude::*;
-use pyo3::types::{PyBool, PyDict, PyFloat, PyList, PyString};
+use pyo3::types::{IntoPyDict, PyBool, PyDict, PyFloat, PyList, PySequence, PyString};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::process;
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::Duration;
 
 // ---------------------------------------------------------------------------
@@ -14,10 +41,24 @@ use std::time::Duration;
 #[pyclass(skip_from_py_object)]
 #[derive(Clone)]
 pub struct GenerateResult {
+    id: Option<String>,
+    created: Option<u64>,
     text: String,
+    reasoning: Option<String>,
+    audio: Option<AudioOutput>,
+    refusal: Option<String>,
+    annotations: Vec<Annotation>,
     usage: Option<Usage>,
     finish_reason: Option<String>,
     model: Option<String>,
+    service_tier: Option<String>,
+    system_fingerprint: Option<String>,
+    request_id: Option<String>,
+    idempotency_key: Option<String>,
+    attempted_models: Vec<String>,
+    rate_limit: Option<RateLimit>,
+    raw: Option<Value>,
+    timings: Option<CallTimings>,
 }
 
 #[pymethods]
@@ -27,19 +68,138 @@ impl GenerateResult {
         &self.text
     }
 
+    #[getter]
+    fn reasoning(&self) -> Option<&str> {
+        self.reasoning.as_deref()
+    }
+
+    /// The model's refusal message, sent in place of an answer, or
+    /// ``None``.
+    #[getter]
+    fn refusal(&self) -> Option<&str> {
+        self.refusal.as_deref()
+    }
+
+    /// Citations a web-search model attached to its answer (OpenRouter
+    /// ``:online`` models, OpenAI search models), as dicts with ``type``,
+    /// ``url``, ``title``, ``start_index`` and ``end_index``. Empty when
+    /// there are none.
+    #[getter]
+    fn annotations(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        annotations_to_py(py, &self.annotations)
+    }
+
+    /// Whether content filtering stopped the response (``finish_reason``
+    /// ``"content_filter"``) or the model refused.
+    #[getter]
+    fn was_filtered(&self) -> bool {
+        content_filtered(self.finish_reason.as_deref(), self.refusal.as_deref())
+    }
+
+    /// The response ``id`` sent by the provider, or ``None``.
+    #[getter]
+    fn response_id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// The provider's request id from the ``x-request-id`` (or
+    /// ``request-id``, ``openrouter-id``) response header, or ``None``.
+    #[getter]
+    fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    /// The ``Idempotency-Key`` header the request was sent with, or
+    /// ``None`` if keys are disabled. After a fallback, the key of the
+    /// fallback model's request.
+    #[getter]
+    fn idempotency_key(&self) -> Option<&str> {
+        self.idempotency_key.as_deref()
+    }
+
+    /// The models the call was sent to in turn: the provider's ``model``,
+    /// then any ``fallback_models`` tried after it failed.
+    #[getter]
+    fn attempted_models(&self) -> Vec<String> {
+        self.attempted_models.clone()
+    }
+
+    /// Whether a fallback model served the response.
+    #[getter]
+    fn fallback_used(&self) -> bool {
+        self.attempted_models.len() > 1
+    }
+
+    /// The Unix timestamp the provider reports the response was created
+    /// at, or ``None``.
+    #[getter]
+    fn created(&self) -> Option<u64> {
+        self.created
+    }
+
+    /// Audio output as a dict with ``id``, ``data`` (base64), ``transcript``,
+    /// and ``expires_at``, or ``None`` if the model returned no audio.
+    #[getter]
+    fn audio(&self, py: Python<'_>) -> PyResult<Option<Py<PyAny>>> {
+        self.audio
+            .as_ref()
+            .map(|audio| {
+                let value = serde_json::to_value(audio)
+                    .map_err(|e| SdkError::runtime(e.to_string()).into_pyerr())?;
+                json_to_py(py, &value)
+            })
+            .transpose()
+    }
+
     #[getter]
     fn prompt_tokens(&self) -> Option<u64> {
-        self.usage.as_ref().map(|u| u.prompt_tokens)
+        self.usage.as_ref().and_then(|u| u.prompt_tokens)
     }
 
     #[getter]
     fn completion_tokens(&self) -> Option<u64> {
-        self.usage.as_ref().map(|u| u.completion_tokens)
+        self.usage.as_ref().and_then(|u| u.completion_tokens)
     }
 
     #[getter]
     fn total_tokens(&self) -> Option<u64> {
-        self.usage.as_ref().map(|u| u.total_tokens)
+        self.usage.as_ref().and_then(|u| u.total_tokens)
+    }
+
+    /// Prompt tokens served from the provider's prompt cache, or ``None``
+    /// if not reported.
+    #[getter]
+    fn cached_prompt_tokens(&self) -> Option<u64> {
+        self.usage
+            .as_ref()?
+            .prompt_tokens_details
+            .as_ref()?
+            .cached_tokens
+    }
+
+    /// Completion tokens spent on reasoning, or ``None`` if not reported.
+    #[getter]
+    fn reasoning_tokens(&self) -> Option<u64> {
+        self.completion_details().and_then(|d| d.reasoning_tokens)
+    }
+
+    /// What the call cost in USD as the provider reports it (OpenRouter
+    /// with ``include_cost=True``), or ``None``.
+    #[getter]
+    fn cost_usd(&self) -> Option<f64> {
+        self.usage.as_ref().and_then(|u| u.cost)
+    }
+
+    #[getter]
+    fn accepted_prediction_tokens(&self) -> Option<u64> {
+        self.completion_details()
+            .and_then(|d| d.accepted_prediction_tokens)
+    }
+
+    #[getter]
+    fn rejected_prediction_tokens(&self) -> Option<u64> {
+        self.completion_details()
+            .and_then(|d| d.rejected_prediction_tokens)
     }
 
     #[getter]
@@ -47,11 +207,105 @@ impl GenerateResult {
         self.finish_reason.as_deref()
     }
 
+    /// The model that served the response, as the provider reports it,
+    /// or else the model the request was sent to.
     #[getter]
     fn model(&self) -> Option<&str> {
         self.model.as_deref()
     }
 
+    #[getter]
+    fn service_tier(&self) -> Option<&str> {
+        self.service_tier.as_deref()
+    }
+
+    #[getter]
+    fn system_fingerprint(&self) -> Option<&str> {
+        self.system_fingerprint.as_deref()
+    }
+
+    /// The provider's rate-limit headers as a dict of
+    /// ``remaining_requests``, ``remaining_tokens``, ``reset_requests`` and
+    /// ``reset_tokens`` (seconds), each ``None`` when not reported.
+    #[getter]
+    fn rate_limit<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        self.rate_limit
+            .as_ref()
+            .map(|limit| rate_limit_to_py(py, limit))
+            .transpose()
+    }
+
+    /// How long the call took, as a dict of ``total_duration_ms``,
+    /// ``attempts`` (across retries and fallback models) and
+    /// ``retry_wait_ms`` (time slept before retries), or ``None`` if the
+    /// result was not timed.
+    #[getter]
+    fn timings<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        self.timings
+            .as_ref()
+            .map(|timings| {
+                let dict = PyDict::new(py);
+                dict.set_item(
+                    "total_duration_ms",
+                    timings.total_duration.as_secs_f64() * 1000.0,
+                )?;
+                dict.set_item("attempts", timings.attempts)?;
+                dict.set_item("retry_wait_ms", timings.retry_wait.as_secs_f64() * 1000.0)?;
+                Ok(dict)
+            })
+            .transpose()
+    }
+
+    /// The whole response body as a dict, for fields the SDK has no
+    /// getter for (citations, cache stats, provider extensions). For
+    /// Anthropic this is the Messages API response as sent.
+    #[getter]
+    fn raw(&self, py: Python<'_>) -> PyResult<Option<Py<PyAny>>> {
+        self.raw.as_ref().map(|raw| json_to_py(py, raw)).transpose()
+    }
+
+    /// The typed fields, including ``timings``, as a dict of plain values,
+    /// e.g. for logging as JSON. ``raw`` is left out.
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("text", &self.text)?;
+        dict.set_item("reasoning", &self.reasoning)?;
+        dict.set_item("refusal", &self.refusal)?;
+        dict.set_item("annotations", self.annotations(py)?)?;
+        dict.set_item("audio", self.audio(py)?)?;
+        dict.set_item("finish_reason", &self.finish_reason)?;
+        dict.set_item("model", &self.model)?;
+        dict.set_item("response_id", &self.id)?;
+        dict.set_item("request_id", &self.request_id)?;
+        dict.set_item("idempotency_key", &self.idempotency_key)?;
+        dict.set_item("attempted_models", &self.attempted_models)?;
+        dict.set_item("created", self.created)?;
+        dict.set_item("service_tier", &self.service_tier)?;
+        dict.set_item("system_fingerprint", &self.system_fingerprint)?;
+        dict.set_item("prompt_tokens", self.prompt_tokens())?;
+        dict.set_item("completion_tokens", self.completion_tokens())?;
+        dict.set_item("total_tokens", self.total_tokens())?;
+        dict.set_item("cached_prompt_tokens", self.cached_prompt_tokens())?;
+        dict.set_item("reasoning_tokens", self.reasoning_tokens())?;
+        dict.set_item("cost_usd", self.cost_usd())?;
+        dict.set_item(
+            "accepted_prediction_tokens",
+            self.accepted_prediction_tokens(),
+        )?;
+        dict.set_item(
+            "rejected_prediction_tokens",
+            self.rejected_prediction_tokens(),
+        )?;
+        dict.set_item("rate_limit", self.rate_limit(py)?)?;
+        dict.set_item("timings", self.timings(py)?)?;
+        Ok(dict)
+    }
+
+    /// Results are equal when all of their fields are.
+    fn __eq__(&self, other: PyRef<'_, GenerateResult>) -> bool {
+        *self == *other
+    }
+
     fn __str__(&self) -> &str {
         &self.text
     }
@@ -61,48 +315,535 @@ impl GenerateResult {
             "GenerateResult(text='{}...', finish_reason={:?}, prompt_tokens={:?}, completion_tokens={:?})",
             &self.text.chars().take(50).collect::<String>(),
             self.finish_reason,
-            self.usage.as_ref().map(|u| u.prompt_tokens),
-            self.usage.as_ref().map(|u| u.completion_tokens),
+            self.usage.as_ref().and_then(|u| u.prompt_tokens),
+            self.usage.as_ref().and_then(|u| u.completion_tokens),
         )
     }
 }
 
+/// Results compare by response. The `Idempotency-Key` and timings are
+/// left out since they are new for every request, even one that gets the
+/// same reply.
+impl PartialEq for GenerateResult {
+    fn eq(&self, other: &Self) -> bool {
+        let Self {
+            id,
+            created,
+            text,
+            reasoning,
+            audio,
+            refusal,
+            annotations,
+            usage,
+            finish_reason,
+            model,
+            service_tier,
+            system_fingerprint,
+            request_id,
+            idempotency_key: _,
+            attempted_models,
+            rate_limit,
+            raw,
+            timings: _,
+        } = self;
+        (id, created, text, reasoning, audio, refusal, usage)
+            == (
+                &other.id,
+                &other.created,
+                &other.text,
+                &other.reasoning,
+                &other.audio,
+                &other.refusal,
+                &other.usage,
+            )
+            && (
+                finish_reason,
+                model,
+                service_tier,
+                system_fingerprint,
+                request_id,
+                attempted_models,
+                rate_limit,
+                raw,
+                annotations,
+            ) == (
+                &other.finish_reason,
+                &other.model,
+                &other.service_tier,
+                &other.system_fingerprint,
+                &other.request_id,
+                &other.attempted_models,
+                &other.rate_limit,
+                &other.raw,
+                &other.annotations,
+            )
+    }
+}
+
 impl GenerateResult {
+    fn completion_details(&self) -> Option<&CompletionTokensDetails> {
+        self.usage.as_ref()?.completion_tokens_details.as_ref()
+    }
+
     pub fn from_parsed(result: ParsedChatResult) -> Self {
         Self {
+            id: result.id,
+            created: result.created,
             text: result.text,
+            reasoning: result.reasoning,
+            audio: result.audio,
+            refusal: result.refusal,
+            annotations: result.annotations,
             usage: result.usage,
             finish_reason: result.finish_reason,
             model: result.model,
+            service_tier: result.service_tier,
+            system_fingerprint: result.system_fingerprint,
+            request_id: result.request_id,
+            idempotency_key: result.idempotency_key,
+            attempted_models: result.attempted_models,
+            rate_limit: result.rate_limit,
+            raw: result.raw,
+            timings: result.timings,
         }
     }
 }
 
+/// `annotations` as the list of dicts `annotations` getters return.
+pub(crate) fn annotations_to_py(py: Python<'_>, annotations: &[Annotation]) -> PyResult<Py<PyAny>> {
+    let value = serde_json::to_value(annotations)
+        .map_err(|e| SdkError::runtime(e.to_string()).into_pyerr())?;
+    json_to_py(py, &value)
+}
+
+/// `limit` as the dict `rate_limit` getters return, with reset times in
+/// seconds.
+pub(crate) fn rate_limit_to_py<'py>(
+    py: Python<'py>,
+    limit: &RateLimit,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("remaining_requests", limit.remaining_requests)?;
+    dict.set_item("remaining_tokens", limit.remaining_tokens)?;
+    dict.set_item(
+        "reset_requests",
+        limit.reset_requests.map(|reset| reset.as_secs_f64()),
+    )?;
+    dict.set_item(
+        "reset_tokens",
+        limit.reset_tokens.map(|reset| reset.as_secs_f64()),
+    )?;
+    Ok(dict)
+}
+
 pub const DEFAULT_BASE_URL: &str = "https://openrouter.ai/api/v1";
 pub const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 60;
 pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
 pub const DEFAULT_MAX_RETRIES: u32 = 2;
 pub const DEFAULT_RETRY_BACKOFF_MS: u64 = 250;
+pub const DEFAULT_RETRY_MAX_DELAY_MS: u64 = 30_000;
+pub const DEFAULT_STREAM_RECONNECTS: u32 = 0;
 
+const DEFAULT_MODEL_ENV: &str = "RUSTY_AGENT_DEFAULT_MODEL";
+const BASE_URL_ENV: &str = "RUSTY_AGENT_BASE_URL";
+const OPENROUTER_BASE_URL_ENV: &str = "OPENROUTER_BASE_URL";
 const REQUEST_TIMEOUT_ENV: &str = "RUSTY_AGENT_REQUEST_TIMEOUT_SECS";
 const CONNECT_TIMEOUT_ENV: &str = "RUSTY_AGENT_CONNECT_TIMEOUT_SECS";
 const MAX_RETRIES_ENV: &str = "RUSTY_AGENT_MAX_RETRIES";
 const RETRY_BACKOFF_ENV: &str = "RUSTY_AGENT_RETRY_BACKOFF_MS";
+const RETRY_MAX_DELAY_ENV: &str = "RUSTY_AGENT_RETRY_MAX_DELAY_MS";
+const STREAM_RECONNECTS_ENV: &str = "RUSTY_AGENT_STREAM_RECONNECTS";
+const STREAM_IDLE_TIMEOUT_ENV: &str = "RUSTY_AGENT_STREAM_IDLE_TIMEOUT_SECS";
+
+pub const DEFAULT_AZURE_API_VERSION: &str = "2024-10-21";
+
+/// A named provider: the base URL it targets, the environment variable
+/// its API key is read from, the header the key is sent in, and the API
+/// dialect it speaks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Preset {
+    /// Name accepted by `Provider.from_named`, and of its classmethod.
+    pub name: &'static str,
+    pub base_url: &'static str,
+    pub env_var: &'static str,
+    pub auth: AuthHeader,
+    pub dialect: ApiDialect,
+}
+
+const OPENAI: Preset = Preset {
+    name: "openai",
+    base_url: "https://api.openai.com/v1",
+    env_var: "OPENAI_API_KEY",
+    auth: AuthHeader::Bearer,
+    dialect: ApiDialect::OpenAi,
+};
+const ANTHROPIC: Preset = Preset {
+    name: "anthropic",
+    base_url: "https://api.anthropic.com/v1",
+    env_var: "ANTHROPIC_API_KEY",
+    auth: AuthHeader::XApiKey,
+    dialect: ApiDialect::Anthropic,
+};
+const OPENROUTER: Preset = Preset {
+    name: "openrouter",
+    base_url: "https://openrouter.ai/api/v1",
+    env_var: "OPENROUTER_API_KEY",
+    auth: AuthHeader::Bearer,
+    dialect: ApiDialect::OpenAi,
+};
+const GROQ: Preset = Preset {
+    name: "groq",
+    base_url: "https://api.groq.com/openai/v1",
+    env_var: "GROQ_API_KEY",
+    auth: AuthHeader::Bearer,
+    dialect: ApiDialect::OpenAi,
+};
+const MISTRAL: Preset = Preset {
+    name: "mistral",
+    base_url: "https://api.mistral.ai/v1",
+    env_var: "MISTRAL_API_KEY",
+    auth: AuthHeader::Bearer,
+    dialect: ApiDialect::OpenAi,
+};
+const DEEPSEEK: Preset = Preset {
+    name: "deepseek",
+    base_url: "https://api.deepseek.com/v1",
+    env_var: "DEEPSEEK_API_KEY",
+    auth: AuthHeader::Bearer,
+    dialect: ApiDialect::OpenAi,
+};
+const TOGETHER: Preset = Preset {
+    name: "together",
+    base_url: "https://api.together.xyz/v1",
+    env_var: "TOGETHER_API_KEY",
+    auth: AuthHeader::Bearer,
+    dialect: ApiDialect::OpenAi,
+};
+const XAI: Preset = Preset {
+    name: "xai",
+    base_url: "https://api.x.ai/v1",
+    env_var: "XAI_API_KEY",
+    auth: AuthHeader::Bearer,
+    dialect: ApiDialect::OpenAi,
+};
+
+/// The registry behind `Provider.from_named` and the preset classmethods,
+/// in the order `Provider.known_providers()` lists it.
+pub const PRESETS: [Preset; 8] = [
+    OPENAI, ANTHROPIC, OPENROUTER, GROQ, MISTRAL, DEEPSEEK, TOGETHER, XAI,
+];
+
+/// Look up the preset called `name`.
+pub fn find_preset(name: &str) -> Result<&'static Preset, SdkError> {
+    PRESETS
+        .iter()
+        .find(|preset| preset.name == name)
+        .ok_or_else(|| {
+            let names: Vec<&str> = PRESETS.iter().map(|preset| preset.name).collect();
+            SdkError::value(format!(
+                "Unknown provider '{}'. Supported providers: {}.",
+                name,
+                names.join(", ")
+            ))
+        })
+}
+pub const DEFAULT_OLLAMA_BASE_URL: &str = "http://localhost:11434/v1";
 
 /// Build a normalized chat completions URL from the configured provider base URL.
 pub fn build_chat_completions_url(base_url: &str) -> String {
-    format!("{}/chat/completions", base_url.trim_end_matches('/'))
+    build_endpoint_url(base_url, "", "chat/completions", None)
+}
+
+/// Build the URL of the provider's model list, or of the model `model_id`
+/// when given.
+pub fn build_models_url(base_url: &str, model_id: Option<&str>) -> String {
+    match model_id {
+        Some(model_id) => build_endpoint_url(base_url, "", &format!("models/{}", model_id), None),
+        None => build_endpoint_url(base_url, "", "models", None),
+    }
+}
+
+/// Build the URL of `endpoint` (e.g. `"chat/completions"`) under
+/// `base_url` and the provider-specific `path`, with an optional query
+/// string given without its `?`. A query string already on `base_url` is
+/// kept, ahead of `query`.
+pub fn build_endpoint_url(
+    base_url: &str,
+    path: &str,
+    endpoint: &str,
+    query: Option<&str>,
+) -> String {
+    let (base_url, base_query) = match base_url.split_once('?') {
+        Some((base_url, base_query)) => (base_url, Some(base_query)),
+        None => (base_url, None),
+    };
+    let mut url = format!(
+        "{}{}/{}",
+        base_url.trim_end_matches('/'),
+        path.trim_end_matches('/'),
+        endpoint
+    );
+    let queries: Vec<&str> = [base_query, query]
+        .into_iter()
+        .flatten()
+        .filter(|query| !query.is_empty())
+        .collect();
+    if !queries.is_empty() {
+        url.push('?');
+        url.push_str(&queries.join("&"));
+    }
+    url
+}
+
+/// Endpoint paths sometimes pasted onto a base URL, which the SDK appends
+/// itself.
+const ENDPOINT_SUFFIXES: [&str; 2] = ["/chat/completions", "/embeddings"];
+
+/// Validate `base_url` and return it without a trailing slash, along with
+/// a warning when a pasted endpoint path such as `/chat/completions` was
+/// removed from its end. The query string is kept.
+///
+/// The URL must be absolute, use http or https, and name a host.
+pub fn normalize_base_url(base_url: &str) -> Result<(String, Option<String>), SdkError> {
+    let invalid =
+        |reason: &str| SdkError::value(format!("Invalid base_url '{}': {}.", base_url, reason));
+    let trimmed = base_url.trim();
+    if trimmed.is_empty() {
+        return Err(invalid("it is empty"));
+    }
+    if !trimmed.contains("://") {
+        return Err(invalid(&format!(
+            "it has no scheme; did you mean 'https://{}'?",
+            trimmed
+        )));
+    }
+    let mut url = url::Url::parse(trimmed).map_err(|e| invalid(&e.to_string()))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(invalid(&format!(
+            "scheme '{}' is not supported; use http or https",
+            url.scheme()
+        )));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(invalid("it has no host"));
+    }
+
+    let path = url.path().trim_end_matches('/').to_string();
+    let suffix = ENDPOINT_SUFFIXES
+        .into_iter()
+        .find(|suffix| path.ends_with(suffix));
+    let warning = suffix.map(|suffix| {
+        format!(
+            "Removed '{}' from the end of base_url; endpoint paths are added per request.",
+            suffix
+        )
+    });
+    url.set_path(path.strip_suffix(suffix.unwrap_or("")).unwrap_or(&path));
+
+    let mut normalized = url[..url::Position::AfterPath]
+        .trim_end_matches('/')
+        .to_string();
+    if let Some(query) = url.query() {
+        normalized.push('?');
+        normalized.push_str(query);
+    }
+    Ok((normalized, warning))
+}
+
+/// The header a provider sends its API key in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AuthHeader {
+    /// `Authorization: Bearer <key>`, used by OpenAI-compatible APIs.
+    #[default]
+    Bearer,
+    /// `api-key: <key>`, used by Azure OpenAI.
+    ApiKey,
+    /// `x-api-key: <key>`, used by Anthropic.
+    XApiKey,
+}
+
+impl AuthHeader {
+    /// The header name and value carrying `api_key`, or `None` for an
+    /// empty key, which local servers that ignore auth are given.
+    pub fn header(self, api_key: &str) -> Option<(&'static str, String)> {
+        if api_key.is_empty() {
+            return None;
+        }
+        Some(match self {
+            Self::Bearer => ("Authorization", format!("Bearer {}", api_key)),
+            Self::ApiKey => ("api-key", api_key.to_string()),
+            Self::XApiKey => ("x-api-key", api_key.to_string()),
+        })
+    }
+
+    /// The header's name, lowercase, as pickled.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Bearer => "authorization",
+            Self::ApiKey => "api-key",
+            Self::XApiKey => "x-api-key",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [Self::Bearer, Self::ApiKey, Self::XApiKey]
+            .into_iter()
+            .find(|auth| auth.name() == name)
+    }
+}
+
+/// The request and response format a provider speaks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ApiDialect {
+    /// OpenAI chat completions, spoken by most providers.
+    #[default]
+    OpenAi,
+    /// Anthropic's Messages API, translated to and from chat completions
+    /// by `anthropic.rs`.
+    Anthropic,
+}
+
+impl ApiDialect {
+    /// The endpoint generation requests are sent to.
+    pub fn endpoint(self) -> &'static str {
+        match self {
+            Self::OpenAi => "chat/completions",
+            Self::Anthropic => "messages",
+        }
+    }
+
+    /// Headers sent with every request besides the API key.
+    fn extra_headers(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::OpenAi => &[],
+            Self::Anthropic => &[("anthropic-version", ANTHROPIC_VERSION)],
+        }
+    }
+
+    /// The dialect's name, as pickled.
+    fn name(self) -> &'static str {
+        match self {
+            Self::OpenAi => "openai",
+            Self::Anthropic => "anthropic",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        [Self::OpenAi, Self::Anthropic]
+            .into_iter()
+            .find(|dialect| dialect.name() == name)
+    }
+}
+
+/// How a provider lays out its endpoint URLs and authenticates. The
+/// default is the OpenAI-compatible layout, with endpoints directly under
+/// the base URL.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Routing {
+    /// Path between the base URL and the endpoint, with `{model}` replaced
+    /// by the provider's model.
+    pub path_template: String,
+    /// Query string added to every endpoint URL, without its `?`.
+    pub query: Option<String>,
+    pub auth: AuthHeader,
+    pub dialect: ApiDialect,
+}
+
+impl Routing {
+    /// Azure OpenAI routing: endpoints under the model's deployment, the
+    /// `api-version` query parameter, and the `api-key` header.
+    pub fn azure(api_version: &str) -> Self {
+        Self {
+            path_template: "/openai/deployments/{model}".to_string(),
+            query: Some(format!("api-version={}", api_version)),
+            auth: AuthHeader::ApiKey,
+            dialect: ApiDialect::OpenAi,
+        }
+    }
+
+    /// The URL of `endpoint` for a provider at `base_url` using `model`.
+    pub fn endpoint_url(&self, base_url: &str, model: &str, endpoint: &str) -> String {
+        build_endpoint_url(
+            base_url,
+            &self.path_template.replace("{model}", model),
+            endpoint,
+            self.query.as_deref(),
+        )
+    }
+}
+
+/// Resolve the model, API key and base URL of a preset provider, with
+/// `env_api_key` read from `env_var`. A missing key is reported against
+/// `env_var` rather than `OPENROUTER_API_KEY`.
+pub fn resolve_preset_values(
+    model: String,
+    api_key: Option<String>,
+    base_url: &str,
+    env_var: &str,
+    env_api_key: Option<String>,
+) -> Result<(String, String, String), SdkError> {
+    let api_key = api_key.or(env_api_key).ok_or_else(|| {
+        SdkError::value(format!(
+            "No api_key provided and {} environment variable is not set.",
+            env_var
+        ))
+    })?;
+    let (model, api_key, base_url, _) = resolve_provider_values(
+        Some(model),
+        Some(api_key),
+        Some(base_url.to_string()),
+        None,
+        None,
+        None,
+    )?;
+    Ok((model, api_key, base_url))
 }
 
+/// Resolve the base URL of an Azure OpenAI resource: an explicit
+/// `base_url` wins, then `https://{resource}.openai.azure.com`, then
+/// `env_endpoint`, which the caller reads from `AZURE_OPENAI_ENDPOINT`.
+pub fn resolve_azure_base_url(
+    resource: Option<String>,
+    base_url: Option<String>,
+    env_endpoint: Option<String>,
+) -> Result<String, SdkError> {
+    let base_url = base_url
+        .or_else(|| resource.map(|resource| format!("https://{}.openai.azure.com", resource)))
+        .or_else(|| env_endpoint.filter(|value| !value.is_empty()))
+        .ok_or_else(|| {
+            SdkError::value(
+                "No resource or base_url provided and AZURE_OPENAI_ENDPOINT environment variable is not set.",
+            )
+        })?;
+    Ok(base_url.trim_end_matches('/').to_string())
+}
+
+/// Resolve the model, API key and base URL of a provider, returned in that
+/// order with the warning from `normalize_base_url`, if any.
+///
+/// Explicit values win over the `env_*` ones, which the caller reads from
+/// the environment; empty environment values count as unset.
 pub fn resolve_provider_values(
+    model: Option<String>,
     api_key: Option<String>,
     base_url: Option<String>,
+    env_model: Option<String>,
     env_api_key: Option<String>,
-) -> Result<(String, String), SdkError> {
-    let base_url = base_url
-        .unwrap_or_else(|| DEFAULT_BASE_URL.to_string())
-        .trim_end_matches('/')
-        .to_string();
+    env_base_url: Option<String>,
+) -> Result<(String, String, String, Option<String>), SdkError> {
+    let model = model
+        .or_else(|| env_model.filter(|value| !value.is_empty()))
+        .ok_or_else(|| {
+            SdkError::value(
+                "No model provided and RUSTY_AGENT_DEFAULT_MODEL environment variable is not set.",
+            )
+        })?;
+
+    let (base_url, base_url_warning) = normalize_base_url(
+        &base_url
+            .or_else(|| env_base_url.filter(|value| !value.is_empty()))
+            .unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+    )?;
 
     let api_key = match api_key {
         Some(key) => key,
@@ -113,7 +854,7 @@ pub fn resolve_provider_values(
         })?,
     };
 
-    Ok((api_key, base_url))
+    Ok((model, api_key, base_url, base_url_warning))
 }
 
 #[derive(Clone, Debug)]
@@ -122,6 +863,11 @@ pub struct RuntimeConfig {
     pub connect_timeout: Duration,
     pub max_retries: u32,
     pub retry_backoff: Duration,
+    /// Longest wait before a retry, whatever the backoff or the server asks.
+    pub max_retry_delay: Duration,
+    pub stream_reconnects: u32,
+    /// How long a stream may go without data; `None` uses `request_timeout`.
+    pub stream_idle_timeout: Option<Duration>,
 }
 
 pub fn resolve_runtime_config(
@@ -129,6 +875,9 @@ pub fn resolve_runtime_config(
     connect_timeout_env: Option<String>,
     max_retries_env: Option<String>,
     retry_backoff_env: Option<String>,
+    retry_max_delay_env: Option<String>,
+    stream_reconnects_env: Option<String>,
+    stream_idle_timeout_env: Option<String>,
 ) -> Result<RuntimeConfig, SdkError> {
     let request_timeout_secs = parse_positive_u64_env(
         request_timeout_env,
@@ -145,16 +894,294 @@ pub fn resolve_runtime_config(
         RETRY_BACKOFF_ENV,
         DEFAULT_RETRY_BACKOFF_MS,
     )?;
+    let max_retry_delay_ms = parse_positive_u64_env(
+        retry_max_delay_env,
+        RETRY_MAX_DELAY_ENV,
+        DEFAULT_RETRY_MAX_DELAY_MS,
+    )?;
     let max_retries = parse_u32_env(max_retries_env, MAX_RETRIES_ENV, DEFAULT_MAX_RETRIES)?;
+    let stream_reconnects = parse_u32_env(
+        stream_reconnects_env,
+        STREAM_RECONNECTS_ENV,
+        DEFAULT_STREAM_RECONNECTS,
+    )?;
+    let stream_idle_timeout = match stream_idle_timeout_env {
+        Some(raw) => Some(Duration::from_secs(parse_positive_u64_env(
+            Some(raw),
+            STREAM_IDLE_TIMEOUT_ENV,
+            DEFAULT_REQUEST_TIMEOUT_SECS,
+        )?)),
+        None => None,
+    };
 
     Ok(RuntimeConfig {
         request_timeout: Duration::from_secs(request_timeout_secs),
         connect_timeout: Duration::from_secs(connect_timeout_secs),
         max_retries,
         retry_backoff: Duration::from_millis(retry_backoff_ms),
+        max_retry_delay: Duration::from_millis(max_retry_delay_ms),
+        stream_reconnects,
+        stream_idle_timeout,
     })
 }
 
+/// TLS settings for a provider's client. The PEM files are read when the
+/// config is loaded, so a missing or malformed file fails construction
+/// rather than the first request.
+#[derive(Clone, Debug, Default)]
+pub struct TlsConfig {
+    /// Path of a PEM bundle of extra root certificates.
+    pub ca_cert: Option<String>,
+    /// Path of the PEM client certificate for mutual TLS.
+    pub client_cert: Option<String>,
+    /// Path of the PEM private key for `client_cert`, when it is not in
+    /// the same file.
+    pub client_key: Option<String>,
+    pub insecure_skip_tls_verify: bool,
+    roots: Vec<reqwest::Certificate>,
+    identity: Option<reqwest::Identity>,
+}
+
+impl TlsConfig {
+    /// Read and parse the PEM files named by the paths.
+    pub fn load(
+        ca_cert: Option<String>,
+        client_cert: Option<String>,
+        client_key: Option<String>,
+        insecure_skip_tls_verify: bool,
+    ) -> Result<Self, SdkError> {
+        let roots = match &ca_cert {
+            Some(path) => {
+                let roots = reqwest::Certificate::from_pem_bundle(&read_pem("ca_cert", path)?)
+                    .map_err(|e| invalid_pem("ca_cert", path, &error_chain(&e)))?;
+                if roots.is_empty() {
+                    return Err(invalid_pem("ca_cert", path, "no certificates found"));
+                }
+                roots
+            }
+            None => Vec::new(),
+        };
+
+        let identity = match (&client_cert, &client_key) {
+            (Some(cert_path), key_path) => {
+                let mut pem = read_pem("client_cert", cert_path)?;
+                if let Some(key_path) = key_path {
+                    pem.push(b'\n');
+                    pem.extend(read_pem("client_key", key_path)?);
+                }
+                let identity = reqwest::Identity::from_pem(&pem).map_err(|e| {
+                    invalid_pem(
+                        "client_cert",
+                        cert_path,
+                        &format!(
+                            "{} (a certificate and its private key are required)",
+                            error_chain(&e)
+                        ),
+                    )
+                })?;
+                Some(identity)
+            }
+            (None, Some(_)) => {
+                return Err(SdkError::value("'client_key' requires 'client_cert'."));
+            }
+            (None, None) => None,
+        };
+
+        Ok(Self {
+            ca_cert,
+            client_cert,
+            client_key,
+            insecure_skip_tls_verify,
+            roots,
+            identity,
+        })
+    }
+
+    fn apply(&self, builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        let mut builder = builder
+            .tls_certs_merge(self.roots.iter().cloned())
+            .tls_danger_accept_invalid_certs(self.insecure_skip_tls_verify);
+        if let Some(identity) = &self.identity {
+            builder = builder.identity(identity.clone());
+        }
+        builder
+    }
+}
+
+fn read_pem(name: &str, path: &str) -> Result<Vec<u8>, SdkError> {
+    std::fs::read(path)
+        .map_err(|e| SdkError::value(format!("Could not read {} '{}': {}.", name, path, e)))
+}
+
+fn invalid_pem(name: &str, path: &str, reason: &str) -> SdkError {
+    SdkError::value(format!("Invalid {} '{}': {}.", name, path, reason))
+}
+
+/// Runtime settings passed to the `Provider` constructor or a preset. Each
+/// one that is set takes precedence over its environment variable.
+#[derive(Clone, Debug, Default)]
+pub struct RuntimeOverrides {
+    pub request_timeout: Option<f64>,
+    pub connect_timeout: Option<f64>,
+    pub max_retries: Option<i64>,
+    pub retry_backoff_ms: Option<i64>,
+    pub max_retry_delay_ms: Option<i64>,
+    pub stream_idle_timeout: Option<f64>,
+}
+
+impl RuntimeOverrides {
+    /// Resolve the runtime config from the explicit values, then the
+    /// environment variables returned by `env`, then the defaults. The
+    /// variable behind an explicit value is not read, so an invalid one
+    /// cannot fail construction.
+    pub fn resolve(&self, env: impl Fn(&str) -> Option<String>) -> Result<RuntimeConfig, SdkError> {
+        let env_unless = |explicit: bool, name: &str| if explicit { None } else { env(name) };
+        let mut config = resolve_runtime_config(
+            env_unless(self.request_timeout.is_some(), REQUEST_TIMEOUT_ENV),
+            env_unless(self.connect_timeout.is_some(), CONNECT_TIMEOUT_ENV),
+            env_unless(self.max_retries.is_some(), MAX_RETRIES_ENV),
+            env_unless(self.retry_backoff_ms.is_some(), RETRY_BACKOFF_ENV),
+            env_unless(self.max_retry_delay_ms.is_some(), RETRY_MAX_DELAY_ENV),
+            env(STREAM_RECONNECTS_ENV),
+            env_unless(self.stream_idle_timeout.is_some(), STREAM_IDLE_TIMEOUT_ENV),
+        )?;
+
+        if let Some(secs) = self.request_timeout {
+            config.request_timeout = positive_secs("request_timeout", secs)?;
+        }
+        if let Some(secs) = self.connect_timeout {
+            config.connect_timeout = positive_secs("connect_timeout", secs)?;
+        }
+        if let Some(retries) = self.max_retries {
+            config.max_retries = retry_count(retries)?;
+        }
+        if let Some(ms) = self.retry_backoff_ms {
+            config.retry_backoff = retry_backoff(ms)?;
+        }
+        if let Some(ms) = self.max_retry_delay_ms {
+            config.max_retry_delay = positive_ms("max_retry_delay_ms", ms)?;
+        }
+        if let Some(secs) = self.stream_idle_timeout {
+            config.stream_idle_timeout = Some(positive_secs("stream_idle_timeout", secs)?);
+        }
+        Ok(config)
+    }
+}
+
+/// Read a kwarg given in seconds as a non-zero `Duration`.
+fn positive_secs(name: &str, secs: f64) -> Result<Duration, SdkError> {
+    Duration::try_from_secs_f64(secs)
+        .ok()
+        .filter(|duration| !duration.is_zero())
+        .ok_or_else(|| SdkError::value(format!("'{}' must be a positive number of seconds.", name)))
+}
+
+/// Read the `max_retries` kwarg.
+fn retry_count(retries: i64) -> Result<u32, SdkError> {
+    u32::try_from(retries)
+        .map_err(|_| SdkError::value("'max_retries' must be a non-negative integer."))
+}
+
+/// Read the `retry_backoff_ms` kwarg.
+fn retry_backoff(ms: i64) -> Result<Duration, SdkError> {
+    positive_ms("retry_backoff_ms", ms)
+}
+
+/// Read the `retry_on_status` kwarg.
+fn retry_statuses(codes: Vec<i64>) -> Result<Vec<u16>, SdkError> {
+    codes
+        .into_iter()
+        .map(|code| {
+            u16::try_from(code)
+                .ok()
+                .filter(|code| (100..=599).contains(code))
+                .ok_or_else(|| {
+                    SdkError::value(format!(
+                        "'retry_on_status' must contain HTTP status codes (100-599), got {}.",
+                        code
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// Read the `on_retry` kwarg.
+fn retry_callback(callback: Bound<'_, PyAny>) -> Result<Py<PyAny>, SdkError> {
+    if callback.is_callable() {
+        Ok(callback.unbind())
+    } else {
+        Err(SdkError::type_error("'on_retry' must be callable."))
+    }
+}
+
+/// Read the `fallback_models` kwarg.
+fn fallback_model_list(models: Vec<String>) -> Result<Vec<String>, SdkError> {
+    if models.iter().any(|model| model.trim().is_empty()) {
+        return Err(SdkError::value(
+            "'fallback_models' must not contain empty model names.",
+        ));
+    }
+    Ok(models)
+}
+
+/// Apply the `circuit_breaker_threshold` and `circuit_breaker_cooldown`
+/// kwargs to `current`, keeping its state and any setting not given.
+fn circuit_breaker(
+    current: &CircuitBreaker,
+    threshold: Option<i64>,
+    cooldown: Option<f64>,
+) -> Result<CircuitBreaker, SdkError> {
+    let threshold = match threshold {
+        Some(threshold) => u32::try_from(threshold).map_err(|_| {
+            SdkError::value("'circuit_breaker_threshold' must be a non-negative integer.")
+        })?,
+        None => current.threshold(),
+    };
+    let cooldown = match cooldown {
+        Some(secs) => positive_secs("circuit_breaker_cooldown", secs)?,
+        None => current.cooldown(),
+    };
+    Ok(current.with_settings(threshold, cooldown))
+}
+
+/// Apply the `max_requests_per_minute` and `max_tokens_per_minute` kwargs
+/// to `current`, keeping its levels and any limit not given.
+fn rate_limiter(
+    current: &RateLimiter,
+    requests: Option<i64>,
+    tokens: Option<i64>,
+) -> Result<RateLimiter, SdkError> {
+    let requests = match requests {
+        Some(requests) => u32::try_from(requests).map_err(|_| {
+            SdkError::value("'max_requests_per_minute' must be a non-negative integer.")
+        })?,
+        None => current.requests_per_minute(),
+    };
+    let tokens = match tokens {
+        Some(tokens) => u64::try_from(tokens).map_err(|_| {
+            SdkError::value("'max_tokens_per_minute' must be a non-negative integer.")
+        })?,
+        None => current.tokens_per_minute(),
+    };
+    Ok(current.with_settings(requests, tokens))
+}
+
+/// Apply the `max_concurrency` kwarg to `current`, keeping its count.
+fn concurrency_limit(current: &ConcurrencyLimit, max: i64) -> Result<ConcurrencyLimit, SdkError> {
+    let max = usize::try_from(max)
+        .map_err(|_| SdkError::value("'max_concurrency' must be a non-negative integer."))?;
+    Ok(current.with_max(max))
+}
+
+/// Read a kwarg given in milliseconds as a non-zero `Duration`.
+fn positive_ms(name: &str, ms: i64) -> Result<Duration, SdkError> {
+    u64::try_from(ms)
+        .ok()
+        .filter(|&ms| ms > 0)
+        .map(Duration::from_millis)
+        .ok_or_else(|| SdkError::value(format!("'{}' must be a positive integer.", name)))
+}
+
 fn parse_positive_u64_env(
     value: Option<String>,
     name: &str,
@@ -202,7 +1229,7 @@ fn parse_u32_env(value: Option<String>, name: &str, default: u32) -> Result<u32,
 ///
 /// PyBool is checked before integer extraction because in Python
 /// `bool` is a subclass of `int`.
-fn py_to_json(obj: &Bound<'_, PyAny>) -> PyResult<Value> {
+pub(crate) fn py_to_json(obj: &Bound<'_, PyAny>) -> PyResult<Value> {
     if obj.is_none() {
         Ok(Value::Null)
     } else if let Ok(b) = obj.cast::<PyBool>() {
@@ -233,35 +1260,207 @@ fn py_to_json(obj: &Bound<'_, PyAny>) -> PyResult<Value> {
     }
 }
 
-/// Extract a Python list of `{"role": ..., "content": ...}` dicts into `Vec<ChatMessage>`.
-fn extract_messages(py_messages: &Bound<'_, PyList>) -> PyResult<Vec<ChatMessage>> {
-    let mut messages = Vec::with_capacity(py_messages.len());
-    for item in py_messages.iter() {
-        let role: String = item.get_item("role")?.extract()?;
-        let content: String = item.get_item("content")?.extract()?;
-        messages.push(ChatMessage { role, content });
-    }
-    Ok(messages)
+/// Convert messages to a Python list of `{"role": ..., "content": ...}` dicts.
+pub(crate) fn messages_to_py<'py>(
+    py: Python<'py>,
+    messages: &[ChatMessage],
+) -> PyResult<Bound<'py, PyList>> {
+    let items = messages
+        .iter()
+        .map(|message| {
+            let value = serde_json::to_value(message)
+                .map_err(|e| SdkError::runtime(e.to_string()).into_pyerr())?;
+            json_to_py(py, &value)
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    PyList::new(py, items)
+}
+
+/// Recursively convert a `serde_json::Value` to a Python object.
+///
+/// The inverse of `py_to_json`: objects become dicts, arrays become lists,
+/// and integers that fit in `i64`/`u64` stay integers.
+pub(crate) fn json_to_py(py: Python<'_>, value: &Value) -> PyResult<Py<PyAny>> {
+    Ok(match value {
+        Value::Null => py.None(),
+        Value::Bool(b) => PyBool::new(py, *b).to_owned().into_any().unbind(),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                i.into_pyobject(py)?.into_any().unbind()
+            } else if let Some(u) = n.as_u64() {
+                u.into_pyobject(py)?.into_any().unbind()
+            } else {
+                n.as_f64()
+                    .unwrap_or(f64::NAN)
+                    .into_pyobject(py)?
+                    .into_any()
+                    .unbind()
+            }
+        }
+        Value::String(s) => PyString::new(py, s).into_any().unbind(),
+        Value::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(json_to_py(py, item)?)?;
+            }
+            list.into_any().unbind()
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (k, v) in map {
+                dict.set_item(k, json_to_py(py, v)?)?;
+            }
+            dict.into_any().unbind()
+        }
+    })
 }
 
-/// Convert a Python `str | list[str]` to `serde_json::Value`.
-fn extract_stop(obj: &Bound<'_, PyAny>) -> PyResult<Value> {
-    if let Ok(s) = obj.extract::<String>() {
-        return Ok(Value::String(s));
+/// Convert message content (`str` or a list of content-part dicts) to
+/// `MessageContent`. `label` names the value in error messages.
+pub(crate) fn extract_content(obj: &Bound<'_, PyAny>, label: &str) -> PyResult<MessageContent> {
+    if let Ok(s) = obj.cast::<PyString>() {
+        return Ok(MessageContent::Text(s.to_string()));
     }
     if let Ok(list) = obj.cast::<PyList>() {
-        let strings: Vec<String> = list.extract()?;
-        return Ok(serde_json::json!(strings));
+        let mut parts = Vec::with_capacity(list.len());
+        for item in list.iter() {
+            if item.cast::<PyDict>().is_err() {
+                return Err(SdkError::value(format!("{} parts must be dicts.", label)).into_pyerr());
+            }
+            parts.push(py_to_json(&item)?);
+        }
+        validate_content_parts(label, &parts).map_err(SdkError::into_pyerr)?;
+        return Ok(MessageContent::Parts(parts));
     }
-    Err(SdkError::value("'stop' must be a string or list of strings.").into_pyerr())
+    Err(SdkError::value(format!(
+        "{} must be a string or a list of content parts.",
+        label
+    ))
+    .into_pyerr())
 }
 
-/// Build `GenerationParams` from Python keyword arguments.
-#[expect(clippy::too_many_arguments)] // mirrors the Python-facing API surface
-fn build_generation_params(
+/// Extract a Python list of `{"role": ..., "content": ...}` dicts or
+/// `Message` instances into `Vec<ChatMessage>`.
+///
+/// Shape errors are raised as `ValueError` naming the message index and key.
+/// An optional `name` is read from any message; messages with the `tool`
+/// role may also carry a `tool_call_id`.
+pub(crate) fn extract_messages(py_messages: &Bound<'_, PyList>) -> PyResult<Vec<ChatMessage>> {
+    let mut messages = Vec::with_capacity(py_messages.len());
+    for (index, item) in py_messages.iter().enumerate() {
+        if let Ok(message) = item.cast::<Message>() {
+            messages.push(message.get().inner.clone());
+            continue;
+        }
+
+        let dict = item.cast::<PyDict>().map_err(|_| {
+            SdkError::value(format!(
+                "messages[{}] must be a dict or Message, got '{}'.",
+                index,
+                item.get_type()
+                    .name()
+                    .map(|n| n.to_string())
+                    .unwrap_or_default()
+            ))
+            .into_pyerr()
+        })?;
+
+        let role = extract_message_str(dict, index, "role")?.ok_or_else(|| {
+            SdkError::value(format!(
+                "messages[{}] is missing required key 'role'.",
+                index
+            ))
+            .into_pyerr()
+        })?;
+        let content = match dict.get_item("content")? {
+            Some(value) => extract_content(&value, &format!("messages[{}]['content']", index))?,
+            None => {
+                return Err(SdkError::value(format!(
+                    "messages[{}] is missing required key 'content'.",
+                    index
+                ))
+                .into_pyerr());
+            }
+        };
+        let name = extract_message_str(dict, index, "name")?;
+        let tool_call_id = if role == "tool" {
+            extract_message_str(dict, index, "tool_call_id")?
+        } else {
+            None
+        };
+
+        messages.push(ChatMessage {
+            role,
+            content,
+            name,
+            tool_call_id,
+        });
+    }
+    Ok(messages)
+}
+
+/// Read an optional string key from a message dict.
+fn extract_message_str(
+    dict: &Bound<'_, PyDict>,
+    index: usize,
+    key: &str,
+) -> PyResult<Option<String>> {
+    let Some(value) = dict.get_item(key)? else {
+        return Ok(None);
+    };
+    value.extract::<String>().map(Some).map_err(|_| {
+        SdkError::value(format!("messages[{}]['{}'] must be a string.", index, key)).into_pyerr()
+    })
+}
+
+/// Convert a Python `str | Sequence[str]` to `serde_json::Value`.
+///
+/// Lists, tuples, and any other sequence are accepted. Count and emptiness
+/// limits are enforced later by `GenerationParams::validate`.
+pub(crate) fn extract_stop(obj: &Bound<'_, PyAny>) -> PyResult<Value> {
+    if let Ok(s) = obj.cast::<PyString>() {
+        return Ok(Value::String(s.to_string()));
+    }
+    if let Ok(seq) = obj.cast::<PySequence>() {
+        let mut strings = Vec::with_capacity(seq.len()?);
+        for (index, item) in seq.try_iter()?.enumerate() {
+            let item = item?;
+            let s = item.extract::<String>().map_err(|_| {
+                SdkError::value(format!(
+                    "'stop' sequences must be strings, got {} at index {}.",
+                    item.repr().map(|r| r.to_string()).unwrap_or_default(),
+                    index
+                ))
+                .into_pyerr()
+            })?;
+            strings.push(s);
+        }
+        return Ok(serde_json::json!(strings));
+    }
+    Err(SdkError::value("'stop' must be a string or sequence of strings.").into_pyerr())
+}
+
+/// Convert a Python `str | dict` to a `prediction` value.
+///
+/// A plain string is wrapped in the `{"type": "content", ...}` envelope.
+fn extract_prediction(obj: &Bound<'_, PyAny>) -> PyResult<Value> {
+    if let Ok(s) = obj.cast::<PyString>() {
+        return Ok(serde_json::json!({"type": "content", "content": s.to_string()}));
+    }
+    if obj.cast::<PyDict>().is_ok() {
+        return py_to_json(obj);
+    }
+    Err(SdkError::value("'prediction' must be a string or dict.").into_pyerr())
+}
+
+/// Build `GenerationParams` from Python keyword arguments.
+#[expect(clippy::too_many_arguments)] // mirrors the Python-facing API surface
+fn build_generation_params(
     prompt: Option<&str>,
     system_prompt: Option<&str>,
     messages: Option<&Bound<'_, PyList>>,
+    validate_roles: bool,
+    auto_trim: Option<&Bound<'_, PyAny>>,
     temperature: Option<f64>,
     max_tokens: Option<u64>,
     top_p: Option<f64>,
@@ -270,15 +1469,31 @@ fn build_generation_params(
     presence_penalty: Option<f64>,
     seed: Option<i64>,
     response_format: Option<&Bound<'_, PyAny>>,
+    reasoning_effort: Option<String>,
+    prediction: Option<&Bound<'_, PyAny>>,
+    service_tier: Option<String>,
+    modalities: Option<Vec<String>>,
+    audio: Option<&Bound<'_, PyAny>>,
+    models: Option<Vec<String>>,
 ) -> PyResult<GenerationParams> {
     let raw_messages = messages.map(extract_messages).transpose()?;
+    if validate_roles && let Some(raw) = &raw_messages {
+        validate_message_roles(raw).map_err(SdkError::into_pyerr)?;
+    }
     let stop_val = stop.map(extract_stop).transpose()?;
-    let rf_val = response_format.map(py_to_json).transpose()?;
+    let rf_val = response_format
+        .map(|rf| resolve_format(rf, Ok).map(|resolved| resolved.format))
+        .transpose()?;
+    let prediction_val = prediction.map(extract_prediction).transpose()?;
+    let audio_val = audio.map(py_to_json).transpose()?;
 
-    let msgs = GenerationParams::build_messages(prompt, system_prompt, raw_messages)
+    let mut msgs = GenerationParams::build_messages(prompt, system_prompt, raw_messages)
         .map_err(SdkError::into_pyerr)?;
+    if let Some(budget) = auto_trim.map(extract_trim_budget).transpose()? {
+        msgs = trim_history(msgs, budget, true).0;
+    }
 
-    Ok(GenerationParams {
+    let params = GenerationParams {
         messages: msgs,
         temperature,
         max_tokens,
@@ -288,7 +1503,17 @@ fn build_generation_params(
         presence_penalty,
         seed,
         response_format: rf_val,
-    })
+        reasoning_effort,
+        prediction: prediction_val,
+        service_tier,
+        modalities,
+        audio: audio_val,
+        models,
+        ..Default::default()
+    };
+    params.validate().map_err(SdkError::into_pyerr)?;
+
+    Ok(params)
 }
 
 // ---------------------------------------------------------------------------
@@ -321,16 +1546,62 @@ fn build_generation_params(
 /// )
 /// response = provider.generate_text("Hello!")
 /// ```
-#[pyclass(from_py_object)]
+#[pyclass(from_py_object, module = "rusty_agent_sdk")]
 #[derive(Clone)]
 pub struct Provider {
     pub(crate) api_key: String,
     pub(crate) base_url: String,
+    /// Set when `normalize_base_url` removed an endpoint path.
+    pub(crate) base_url_warning: Option<String>,
     pub(crate) model: String,
+    /// Models tried in turn when `model` fails with an error another model
+    /// may not hit.
+    pub(crate) fallback_models: Vec<String>,
     pub(crate) request_timeout: Duration,
     pub(crate) connect_timeout: Duration,
+    /// Built with `connect_timeout` applied and shared by every request,
+    /// so connections are pooled and reused, with the id of the process
+    /// that built it. Read it through `client()`.
+    pub(crate) client: Arc<Mutex<(u32, reqwest::Client)>>,
     pub(crate) max_retries: u32,
     pub(crate) retry_backoff: Duration,
+    pub(crate) max_retry_delay: Duration,
+    /// Response statuses that are retried.
+    pub(crate) retry_on_status: Vec<u16>,
+    /// Budget for a whole call, retries and waits included.
+    pub(crate) total_timeout: Option<Duration>,
+    /// Calls `on_retry` and counts retries.
+    pub(crate) retry_hook: RetryHook,
+    /// Fails calls early while the provider keeps failing.
+    pub(crate) circuit: CircuitBreaker,
+    /// Holds calls back to stay under per-minute request and token limits.
+    pub(crate) limiter: RateLimiter,
+    /// Caps the requests in flight at once.
+    pub(crate) concurrency: ConcurrencyLimit,
+    /// Counts the usage of every completed call.
+    pub(crate) usage_tracker: Option<UsageTracker>,
+    /// Whether requests get a generated `Idempotency-Key` header.
+    pub(crate) idempotency_keys: bool,
+    /// Set per call by `for_call`; never on a provider the caller holds.
+    pub(crate) raise_on_content_filter: bool,
+    pub(crate) stream_reconnects: u32,
+    pub(crate) stream_idle_timeout: Duration,
+    /// Endpoint layout and auth header; only Azure differs from the default.
+    pub(crate) routing: Routing,
+    /// Kept so the client can be rebuilt with a new connect timeout.
+    pub(crate) tls: TlsConfig,
+    /// Sent with every request, under the auth and dialect headers.
+    pub(crate) default_headers: HeaderMap,
+}
+
+/// A request retargeted to one of `fallback_models`.
+pub(crate) struct Fallback {
+    pub(crate) model: String,
+    pub(crate) url: String,
+    pub(crate) body: Value,
+    /// The primary request's headers, with an `Idempotency-Key` of its own
+    /// when the primary sends one.
+    pub(crate) headers: HeaderMap,
 }
 
 #[pymethods]
@@ -338,42 +1609,244 @@ impl Provider {
     /// Create a new Provider.
     ///
     /// Args:
-    ///     model (str): Model identifier, e.g. ``"openai/gpt-4o-mini"``
-    ///         or ``"anthropic/claude-sonnet-4-5-20250514"``.
+    ///     model (str | None): Model identifier, e.g.
+    ///         ``"openai/gpt-4o-mini"`` or
+    ///         ``"anthropic/claude-sonnet-4-5-20250514"``. If ``None``, the
+    ///         ``RUSTY_AGENT_DEFAULT_MODEL`` environment variable is used.
+    ///     fallback_models (list[str] | None): Models to try in turn when a
+    ///         call to ``model`` fails with a 404, 429 or 5xx response once
+    ///         its retries are spent. The result's ``model`` and
+    ///         ``attempted_models`` report which served it.
     ///     api_key (str | None): API key for the LLM service. If ``None``,
-    ///         the ``OPENROUTER_API_KEY`` environment variable is used.
+    ///         the ``OPENROUTER_API_KEY`` environment variable is used. Pass
+    ///         ``""`` for a server without auth; no ``Authorization``
+    ///         header is sent then.
     ///     base_url (str | None): Base URL of the OpenAI-compatible API.
-    ///         Defaults to ``"https://openrouter.ai/api/v1"``.
+    ///         Defaults to ``RUSTY_AGENT_BASE_URL``, then
+    ///         ``OPENROUTER_BASE_URL``, then
+    ///         ``"https://openrouter.ai/api/v1"``. A pasted
+    ///         ``/chat/completions`` or ``/embeddings`` suffix is removed
+    ///         (see ``base_url_warning``); a query string is kept.
+    ///     request_timeout (float | None): Seconds a request may take.
+    ///         Defaults to ``RUSTY_AGENT_REQUEST_TIMEOUT_SECS``, then 60.
+    ///     connect_timeout (float | None): Seconds allowed to open a
+    ///         connection. Defaults to ``RUSTY_AGENT_CONNECT_TIMEOUT_SECS``,
+    ///         then 10.
+    ///     max_retries (int | None): Retries for retryable failures.
+    ///         Defaults to ``RUSTY_AGENT_MAX_RETRIES``, then 2.
+    ///     retry_backoff_ms (int | None): Base delay between retries in
+    ///         milliseconds, doubled per attempt. Defaults to
+    ///         ``RUSTY_AGENT_RETRY_BACKOFF_MS``, then 250.
+    ///     max_retry_delay_ms (int | None): Longest wait before a retry in
+    ///         milliseconds, including waits a ``Retry-After`` header asks
+    ///         for. Defaults to ``RUSTY_AGENT_RETRY_MAX_DELAY_MS``, then
+    ///         30000.
+    ///     retry_on_status (list[int] | None): Response statuses to retry.
+    ///         Defaults to 429, 500, 502, 503 and 504; ``[]`` retries only
+    ///         connection failures.
+    ///     on_retry (Callable[[int, int | str, float], object] | None):
+    ///         Called before each wait between attempts with the retry
+    ///         number (from 1), the status code or connection error that
+    ///         caused it, and the wait in seconds. Exceptions it raises are
+    ///         counted in ``on_retry_errors``, not raised.
+    ///     total_timeout (float | None): Seconds a whole call may take,
+    ///         across all attempts and the waits between them. Each attempt's
+    ///         timeout is shortened to fit, and ``TimeoutError`` is raised
+    ///         once it runs out. Defaults to no limit beyond
+    ///         ``request_timeout`` per attempt.
+    ///     circuit_breaker_threshold (int | None): Consecutive failed calls
+    ///         (connection errors, timeouts, and 429 or 5xx responses once
+    ///         retries are spent) after which the circuit breaker opens and
+    ///         calls raise ``CircuitOpenError`` without a request. ``None``
+    ///         or ``0`` (the default) turns the breaker off.
+    ///     circuit_breaker_cooldown (float | None): Seconds the circuit
+    ///         stays open before one probe call is let through; its success
+    ///         closes the circuit and its failure opens it again. Defaults
+    ///         to 30.
+    ///     max_requests_per_minute (int | None): Calls this provider and
+    ///         its copies may start per minute. Calls over the limit wait,
+    ///         with the GIL released, until a slot frees up. ``None`` or
+    ///         ``0`` (the default) means no limit.
+    ///     max_tokens_per_minute (int | None): Tokens this provider and its
+    ///         copies may use per minute. Each call reserves its estimated
+    ///         prompt tokens plus ``max_tokens`` and is corrected to the
+    ///         reported usage afterwards. ``None`` or ``0`` (the default)
+    ///         means no limit.
+    ///     max_concurrency (int | None): Requests this provider and its
+    ///         copies may have in flight at once, across threads. Others
+    ///         wait, with the GIL released, for one to finish; a stream
+    ///         holds its slot until it ends. ``None`` or ``0`` (the
+    ///         default) means no limit.
+    ///     idempotency_keys (bool): Send a random ``Idempotency-Key``
+    ///         header with each request, unchanged on its retries, so
+    ///         providers that honor it do not run a retried request twice.
+    ///         Defaults to ``True``.
+    ///     stream_idle_timeout (float | None): Seconds a stream may go
+    ///         without receiving data before it fails. Defaults to the
+    ///         ``RUSTY_AGENT_STREAM_IDLE_TIMEOUT_SECS`` environment variable,
+    ///         or the request timeout when that is unset.
+    ///     ca_cert (str | None): Path of a PEM bundle of root certificates
+    ///         to trust in addition to the built-in ones, e.g. a corporate
+    ///         gateway's internal CA.
+    ///     client_cert (str | None): Path of a PEM client certificate for
+    ///         mutual TLS. It may also hold the private key.
+    ///     client_key (str | None): Path of the PEM private key for
+    ///         ``client_cert``, when it is a separate file.
+    ///     insecure_skip_tls_verify (bool): Accept any server certificate,
+    ///         including expired and self-signed ones. This lets anyone on
+    ///         the network read and alter requests, API key included; do
+    ///         not use it outside local testing, and prefer ``ca_cert``.
+    ///         Defaults to ``False``.
+    ///     default_headers (dict[str, str] | None): Headers sent with every
+    ///         request, e.g. a gateway's tenant header. The API key header
+    ///         and headers the API requires take precedence.
     ///
     /// Returns:
     ///     Provider: A configured provider instance.
     ///
     /// Raises:
-    ///     ValueError: If no ``api_key`` is provided and the
-    ///         ``OPENROUTER_API_KEY`` environment variable is not set.
+    ///     ValueError: If no ``model`` is provided and the
+    ///         ``RUSTY_AGENT_DEFAULT_MODEL`` environment variable is not set,
+    ///         if no ``api_key`` is provided and the ``OPENROUTER_API_KEY``
+    ///         environment variable is not set, if ``base_url`` is not an
+    ///         http or https URL with a host, if a timeout,
+    ///         ``retry_backoff_ms`` or ``max_retry_delay_ms`` is not
+    ///         positive, if ``max_retries``, ``circuit_breaker_threshold``,
+    ///         a per-minute limit or ``max_concurrency`` is negative, if
+    ///         ``retry_on_status`` holds a value that is not an HTTP status,
+    ///         if a TLS file cannot be read or parsed, or if a default
+    ///         header name or value is invalid, or if a fallback model is
+    ///         empty.
+    ///     TypeError: If ``on_retry`` is not callable.
+    #[expect(clippy::too_many_arguments)] // PyO3 requires flat params for Python kwargs
     #[new]
-    #[pyo3(signature = (model, *, api_key=None, base_url=None))]
-    #[pyo3(text_signature = "(model, *, api_key=None, base_url=None)")]
-    fn new(model: String, api_key: Option<String>, base_url: Option<String>) -> PyResult<Self> {
-        let env_api_key = std::env::var("OPENROUTER_API_KEY").ok();
-        let (api_key, base_url) = resolve_provider_values(api_key, base_url, env_api_key)
+    #[pyo3(signature = (
+        model = None,
+        *,
+        fallback_models = None,
+        api_key = None,
+        base_url = None,
+        request_timeout = None,
+        connect_timeout = None,
+        max_retries = None,
+        retry_backoff_ms = None,
+        max_retry_delay_ms = None,
+        retry_on_status = None,
+        on_retry = None,
+        total_timeout = None,
+        circuit_breaker_threshold = None,
+        circuit_breaker_cooldown = None,
+        max_requests_per_minute = None,
+        max_tokens_per_minute = None,
+        max_concurrency = None,
+        idempotency_keys = true,
+        stream_idle_timeout = None,
+        ca_cert = None,
+        client_cert = None,
+        client_key = None,
+        insecure_skip_tls_verify = false,
+        default_headers = None,
+    ))]
+    #[pyo3(
+        text_signature = "(model=None, *, fallback_models=None, api_key=None, base_url=None, request_timeout=None, connect_timeout=None, max_retries=None, retry_backoff_ms=None, max_retry_delay_ms=None, retry_on_status=None, on_retry=None, total_timeout=None, circuit_breaker_threshold=None, circuit_breaker_cooldown=None, max_requests_per_minute=None, max_tokens_per_minute=None, max_concurrency=None, idempotency_keys=True, stream_idle_timeout=None, ca_cert=None, client_cert=None, client_key=None, insecure_skip_tls_verify=False, default_headers=None)"
+    )]
+    fn new(
+        model: Option<String>,
+        fallback_models: Option<Vec<String>>,
+        api_key: Option<String>,
+        base_url: Option<String>,
+        request_timeout: Option<f64>,
+        connect_timeout: Option<f64>,
+        max_retries: Option<i64>,
+        retry_backoff_ms: Option<i64>,
+        max_retry_delay_ms: Option<i64>,
+        retry_on_status: Option<Vec<i64>>,
+        on_retry: Option<Bound<'_, PyAny>>,
+        total_timeout: Option<f64>,
+        circuit_breaker_threshold: Option<i64>,
+        circuit_breaker_cooldown: Option<f64>,
+        max_requests_per_minute: Option<i64>,
+        max_tokens_per_minute: Option<i64>,
+        max_concurrency: Option<i64>,
+        idempotency_keys: bool,
+        stream_idle_timeout: Option<f64>,
+        ca_cert: Option<String>,
+        client_cert: Option<String>,
+        client_key: Option<String>,
+        insecure_skip_tls_verify: bool,
+        default_headers: Option<HashMap<String, String>>,
+    ) -> PyResult<Self> {
+        let env_base_url = std::env::var(BASE_URL_ENV)
+            .ok()
+            .filter(|value| !value.is_empty())
+            .or_else(|| std::env::var(OPENROUTER_BASE_URL_ENV).ok());
+        let (model, api_key, base_url, base_url_warning) = resolve_provider_values(
+            model,
+            api_key,
+            base_url,
+            std::env::var(DEFAULT_MODEL_ENV).ok(),
+            std::env::var("OPENROUTER_API_KEY").ok(),
+            env_base_url,
+        )
+        .map_err(SdkError::into_pyerr)?;
+        let overrides = RuntimeOverrides {
+            request_timeout,
+            connect_timeout,
+            max_retries,
+            retry_backoff_ms,
+            max_retry_delay_ms,
+            stream_idle_timeout,
+        };
+        let tls = TlsConfig::load(ca_cert, client_cert, client_key, insecure_skip_tls_verify)
+            .map_err(SdkError::into_pyerr)?;
+        let default_headers = parse_default_headers(default_headers.unwrap_or_default())
             .map_err(SdkError::into_pyerr)?;
-        let runtime_config = resolve_runtime_config(
-            std::env::var(REQUEST_TIMEOUT_ENV).ok(),
-            std::env::var(CONNECT_TIMEOUT_ENV).ok(),
-            std::env::var(MAX_RETRIES_ENV).ok(),
-            std::env::var(RETRY_BACKOFF_ENV).ok(),
+        let retry_on_status = retry_on_status
+            .map(retry_statuses)
+            .transpose()
+            .map_err(SdkError::into_pyerr)?;
+        let total_timeout = total_timeout
+            .map(|secs| positive_secs("total_timeout", secs))
+            .transpose()
+            .map_err(SdkError::into_pyerr)?;
+        let retry_hook = match on_retry {
+            Some(callback) => RetryHook::default()
+                .with_callback(retry_callback(callback).map_err(SdkError::into_pyerr)?),
+            None => RetryHook::default(),
+        };
+        let fallback_models = fallback_models
+            .map(fallback_model_list)
+            .transpose()
+            .map_err(SdkError::into_pyerr)?
+            .unwrap_or_default();
+        let circuit = circuit_breaker(
+            &CircuitBreaker::default(),
+            circuit_breaker_threshold,
+            circuit_breaker_cooldown,
         )
         .map_err(SdkError::into_pyerr)?;
-
+        let limiter = rate_limiter(
+            &RateLimiter::default(),
+            max_requests_per_minute,
+            max_tokens_per_minute,
+        )
+        .map_err(SdkError::into_pyerr)?;
+        let concurrency =
+            concurrency_limit(&ConcurrencyLimit::default(), max_concurrency.unwrap_or(0))
+                .map_err(SdkError::into_pyerr)?;
+        let provider = Self::from_parts(model, api_key, base_url, &overrides, tls)?;
         Ok(Self {
-            api_key,
-            base_url,
-            model,
-            request_timeout: runtime_config.request_timeout,
-            connect_timeout: runtime_config.connect_timeout,
-            max_retries: runtime_config.max_retries,
-            retry_backoff: runtime_config.retry_backoff,
+            fallback_models,
+            retry_hook,
+            circuit,
+            limiter,
+            concurrency,
+            idempotency_keys,
+            base_url_warning,
+            retry_on_status: retry_on_status.unwrap_or_else(|| DEFAULT_RETRY_STATUSES.to_vec()),
+            total_timeout,
+            default_headers,
+            ..provider
         })
     }
 
@@ -385,6 +1858,12 @@ impl Provider {
     ///     system_prompt (str | None): System prompt, prepended to messages.
     ///     messages (list[dict] | None): Full conversation history as a
     ///         list of ``{"role": ..., "content": ...}`` dicts.
+    ///     validate_roles (bool): Reject message roles outside ``system``,
+    ///         ``user``, ``assistant``, ``tool``, and ``developer``. Disable
+    ///         for providers with custom roles. Defaults to ``True``.
+    ///     auto_trim (int | None): Token budget for the conversation. When
+    ///         set, the oldest messages are dropped (see ``trim_messages``)
+    ///         until the estimated count fits.
     ///     temperature (float | None): Sampling temperature (0-2).
     ///     max_tokens (int | None): Maximum tokens to generate.
     ///     top_p (float | None): Nucleus sampling threshold (0-1).
@@ -393,21 +1872,60 @@ impl Provider {
     ///     presence_penalty (float | None): Presence penalty (-2 to 2).
     ///     seed (int | None): Random seed for deterministic generation.
     ///     response_format (dict | None): Response format configuration.
+    ///     reasoning_effort (str | None): Reasoning effort for reasoning
+    ///         models: ``"low"``, ``"medium"``, or ``"high"``.
+    ///     prediction (str | dict | None): Predicted output. A string is
+    ///         wrapped as ``{"type": "content", "content": ...}``.
+    ///     service_tier (str | None): Service tier to request, e.g.
+    ///         ``"auto"``, ``"default"``, or ``"flex"``.
+    ///     modalities (list[str] | None): Output modalities, e.g.
+    ///         ``["text", "audio"]`` for audio-capable models.
+    ///     audio (dict | None): Audio output settings, e.g.
+    ///         ``{"voice": "alloy", "format": "wav"}``. Required when
+    ///         ``modalities`` includes ``"audio"``.
+    ///     models (list[str] | None): Models for OpenRouter to try in turn,
+    ///         sent as ``models`` instead of the provider's model. The
+    ///         result's ``model`` names the one that answered.
+    ///     include_raw (bool): Return a ``GenerateResult`` whose ``raw``
+    ///         holds the full response JSON, as ``include_usage=True``
+    ///         does. Defaults to ``False``, so large responses are not kept.
+    ///     total_timeout (float | None): Seconds this call may take across
+    ///         all attempts, overriding the provider's ``total_timeout``.
+    ///     retry (bool): Set to ``False`` to make a single attempt, e.g. for
+    ///         a request that is not safe to repeat. Defaults to ``True``.
+    ///     on_retry (Callable | None): Called before each retry of this call
+    ///         instead of the provider's ``on_retry``.
+    ///     usage_tracker (UsageTracker | None): Counts this call's usage
+    ///         instead of the tracker set with :meth:`track_usage`.
+    ///     idempotency_key (str | None): ``Idempotency-Key`` to send instead
+    ///         of a generated one, for callers who track their own. Only
+    ///         the primary model's request uses it; fallback models get
+    ///         generated keys.
+    ///     raise_on_content_filter (bool): Raise ``ContentFilterError``
+    ///         instead of returning when content filtering stopped the
+    ///         response or the model refused. Defaults to ``False``.
     ///
     /// Returns:
     ///     str: The model's complete text response.
     ///
     /// Raises:
     ///     ConnectionError: If the HTTP request fails.
+    ///     TimeoutError: If ``total_timeout`` runs out.
     ///     RuntimeError: If the API returns a non-2xx status code.
-    ///     ValueError: If the response cannot be parsed, or if neither
-    ///         prompt nor messages is provided.
+    ///     ContentFilterError: With ``raise_on_content_filter=True``, if
+    ///         the response was filtered or refused.
+    ///     ValueError: If the response cannot be parsed, if neither
+    ///         prompt nor messages is provided, if a parameter value is
+    ///         invalid, or if ``models`` is given to a provider with
+    ///         ``fallback_models``.
     #[expect(clippy::too_many_arguments)] // PyO3 requires flat params for Python kwargs
     #[pyo3(signature = (
         prompt = None,
         *,
         system_prompt = None,
         messages = None,
+        validate_roles = true,
+        auto_trim = None,
         temperature = None,
         max_tokens = None,
         top_p = None,
@@ -416,10 +1934,24 @@ impl Provider {
         presence_penalty = None,
         seed = None,
         response_format = None,
+        reasoning_effort = None,
+        prediction = None,
+        service_tier = None,
+        modalities = None,
+        audio = None,
+        models = None,
         include_usage = false,
+        include_raw = false,
+        include_cost = false,
+        total_timeout = None,
+        retry = true,
+        on_retry = None,
+        usage_tracker = None,
+        idempotency_key = None,
+        raise_on_content_filter = false,
     ))]
     #[pyo3(
-        text_signature = "(self, prompt=None, *, system_prompt=None, messages=None, temperature=None, max_tokens=None, top_p=None, stop=None, frequency_penalty=None, presence_penalty=None, seed=None, response_format=None, include_usage=False)"
+        text_signature = "(self, prompt=None, *, system_prompt=None, messages=None, validate_roles=True, auto_trim=None, temperature=None, max_tokens=None, top_p=None, stop=None, frequency_penalty=None, presence_penalty=None, seed=None, response_format=None, reasoning_effort=None, prediction=None, service_tier=None, modalities=None, audio=None, models=None, include_usage=False, include_raw=False, include_cost=False, total_timeout=None, retry=True, on_retry=None, usage_tracker=None, idempotency_key=None, raise_on_content_filter=False)"
     )]
     fn generate_text(
         &self,
@@ -427,6 +1959,8 @@ impl Provider {
         prompt: Option<&str>,
         system_prompt: Option<&str>,
         messages: Option<&Bound<'_, PyList>>,
+        validate_roles: bool,
+        auto_trim: Option<&Bound<'_, PyAny>>,
         temperature: Option<f64>,
         max_tokens: Option<u64>,
         top_p: Option<f64>,
@@ -435,12 +1969,28 @@ impl Provider {
         presence_penalty: Option<f64>,
         seed: Option<i64>,
         response_format: Option<&Bound<'_, PyAny>>,
+        reasoning_effort: Option<String>,
+        prediction: Option<&Bound<'_, PyAny>>,
+        service_tier: Option<String>,
+        modalities: Option<Vec<String>>,
+        audio: Option<&Bound<'_, PyAny>>,
+        models: Option<Vec<String>>,
         include_usage: bool,
+        include_raw: bool,
+        include_cost: bool,
+        total_timeout: Option<f64>,
+        retry: bool,
+        on_retry: Option<Bound<'_, PyAny>>,
+        usage_tracker: Option<UsageTracker>,
+        idempotency_key: Option<String>,
+        raise_on_content_filter: bool,
     ) -> PyResult<Py<PyAny>> {
-        let params = build_generation_params(
+        let mut params = build_generation_params(
             prompt,
             system_prompt,
             messages,
+            validate_roles,
+            auto_trim,
             temperature,
             max_tokens,
             top_p,
@@ -449,37 +1999,367 @@ impl Provider {
             presence_penalty,
             seed,
             response_format,
+            reasoning_effort,
+            prediction,
+            service_tier,
+            modalities,
+            audio,
+            models,
         )?;
+        params.include_cost = include_cost;
 
-        if include_usage {
-            let result = generate::run_full(self, params)?;
+        let provider = self.for_call(
+            total_timeout,
+            retry,
+            on_retry,
+            usage_tracker,
+            idempotency_key,
+            raise_on_content_filter,
+        )?;
+        if include_usage || include_raw || include_cost {
+            let result = generate::run_full(&provider, params)?;
             Ok(GenerateResult::from_parsed(result)
                 .into_pyobject(py)?
                 .into_any()
                 .unbind())
         } else {
-            let text = generate::run(self, params)?;
+            let text = generate::run(&provider, params)?;
             Ok(text.into_pyobject(py)?.into_any().unbind())
         }
     }
 
+    /// Generate a complete text response from the LLM without blocking the
+    /// event loop.
+    ///
+    /// Accepts the same parameters as ``generate_text`` and must be called
+    /// while an asyncio event loop is running, e.g. from a coroutine. The
+    /// request runs on a background runtime, with the same timeouts and
+    /// retries as ``generate_text``. Cancelling the awaiting task aborts
+    /// the HTTP request.
+    ///
+    /// Returns:
+    ///     Awaitable[str | GenerateResult]: Resolves to the model's
+    ///     complete text response, or a ``GenerateResult`` when
+    ///     ``include_usage=True`` or ``include_raw=True``.
+    ///
+    /// Raises:
+    ///     RuntimeError: If no event loop is running, or (when awaited) if
+    ///         the API returns a non-2xx status code.
+    ///     ConnectionError: When awaited, if the HTTP request fails.
+    ///     ValueError: If neither prompt nor messages is provided or a
+    ///         parameter value is invalid, or (when awaited) if the response
+    ///         cannot be parsed.
+    #[expect(clippy::too_many_arguments)] // PyO3 requires flat params for Python kwargs
+    #[pyo3(signature = (
+        prompt = None,
+        *,
+        system_prompt = None,
+        messages = None,
+        validate_roles = true,
+        auto_trim = None,
+        temperature = None,
+        max_tokens = None,
+        top_p = None,
+        stop = None,
+        frequency_penalty = None,
+        presence_penalty = None,
+        seed = None,
+        response_format = None,
+        reasoning_effort = None,
+        prediction = None,
+        service_tier = None,
+        modalities = None,
+        audio = None,
+        models = None,
+        include_usage = false,
+        include_raw = false,
+        include_cost = false,
+        total_timeout = None,
+        retry = true,
+        on_retry = None,
+        usage_tracker = None,
+        idempotency_key = None,
+        raise_on_content_filter = false,
+    ))]
+    #[pyo3(
+        text_signature = "(self, prompt=None, *, system_prompt=None, messages=None, validate_roles=True, auto_trim=None, temperature=None, max_tokens=None, top_p=None, stop=None, frequency_penalty=None, presence_penalty=None, seed=None, response_format=None, reasoning_effort=None, prediction=None, service_tier=None, modalities=None, audio=None, models=None, include_usage=False, include_raw=False, include_cost=False, total_timeout=None, retry=True, on_retry=None, usage_tracker=None, idempotency_key=None, raise_on_content_filter=False)"
+    )]
+    fn agenerate_text<'py>(
+        &self,
+        py: Python<'py>,
+        prompt: Option<&str>,
+        system_prompt: Option<&str>,
+        messages: Option<&Bound<'_, PyList>>,
+        validate_roles: bool,
+        auto_trim: Option<&Bound<'_, PyAny>>,
+        temperature: Option<f64>,
+        max_tokens: Option<u64>,
+        top_p: Option<f64>,
+        stop: Option<&Bound<'_, PyAny>>,
+        frequency_penalty: Option<f64>,
+        presence_penalty: Option<f64>,
+        seed: Option<i64>,
+        response_format: Option<&Bound<'_, PyAny>>,
+        reasoning_effort: Option<String>,
+        prediction: Option<&Bound<'_, PyAny>>,
+        service_tier: Option<String>,
+        modalities: Option<Vec<String>>,
+        audio: Option<&Bound<'_, PyAny>>,
+        models: Option<Vec<String>>,
+        include_usage: bool,
+        include_raw: bool,
+        include_cost: bool,
+        total_timeout: Option<f64>,
+        retry: bool,
+        on_retry: Option<Bound<'_, PyAny>>,
+        usage_tracker: Option<UsageTracker>,
+        idempotency_key: Option<String>,
+        raise_on_content_filter: bool,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let mut params = build_generation_params(
+            prompt,
+            system_prompt,
+            messages,
+            validate_roles,
+            auto_trim,
+            temperature,
+            max_tokens,
+            top_p,
+            stop,
+            frequency_penalty,
+            presence_penalty,
+            seed,
+            response_format,
+            reasoning_effort,
+            prediction,
+            service_tier,
+            modalities,
+            audio,
+            models,
+        )?;
+        params.include_cost = include_cost;
+
+        let provider = self.for_call(
+            total_timeout,
+            retry,
+            on_retry,
+            usage_tracker,
+            idempotency_key,
+            raise_on_content_filter,
+        )?;
+        generate::spawn(
+            py,
+            &provider,
+            params,
+            include_usage || include_raw || include_cost,
+        )
+    }
+
+    /// Generate responses for many prompts concurrently (blocking).
+    ///
+    /// Each request gets the same timeouts and retries as
+    /// ``generate_text``. Accepts the generation parameters of
+    /// ``generate_text`` except ``prompt`` and ``messages``, applied to
+    /// every item.
+    ///
+    /// Args:
+    ///     prompts (list[str] | list[list[dict]]): One item per request:
+    ///         a prompt string, or a conversation as a list of message
+    ///         dicts.
+    ///     concurrency (int): Maximum number of requests in flight at once.
+    ///         Defaults to 8.
+    ///
+    /// Returns:
+    ///     list[str | GenerateResult | Exception]: One item per prompt, in
+    ///     input order. A request that fails does not fail the batch; its
+    ///     slot holds the exception it would have raised (e.g.
+    ///     ``RuntimeError`` for a non-2xx status), which is returned, not
+    ///     raised.
+    ///
+    /// Raises:
+    ///     TypeError: If an item is neither a string nor a list.
+    ///     ValueError: If ``concurrency`` is less than 1, an item is not a
+    ///         valid conversation, or a parameter value is invalid. Raised
+    ///         before any request is sent.
+    #[expect(clippy::too_many_arguments)] // PyO3 requires flat params for Python kwargs
+    #[pyo3(signature = (
+        prompts,
+        *,
+        concurrency = 8,
+        system_prompt = None,
+        validate_roles = true,
+        auto_trim = None,
+        temperature = None,
+        max_tokens = None,
+        top_p = None,
+        stop = None,
+        frequency_penalty = None,
+        presence_penalty = None,
+        seed = None,
+        response_format = None,
+        reasoning_effort = None,
+        prediction = None,
+        service_tier = None,
+        modalities = None,
+        audio = None,
+        include_usage = false,
+        include_raw = false,
+        include_cost = false,
+        total_timeout = None,
+        retry = true,
+        on_retry = None,
+        usage_tracker = None,
+        raise_on_content_filter = false,
+    ))]
+    #[pyo3(
+        text_signature = "(self, prompts, *, concurrency=8, system_prompt=None, validate_roles=True, auto_trim=None, temperature=None, max_tokens=None, top_p=None, stop=None, frequency_penalty=None, presence_penalty=None, seed=None, response_format=None, reasoning_effort=None, prediction=None, service_tier=None, modalities=None, audio=None, include_usage=False, include_raw=False, include_cost=False, total_timeout=None, retry=True, on_retry=None, usage_tracker=None, raise_on_content_filter=False)"
+    )]
+    fn generate_many(
+        &self,
+        py: Python<'_>,
+        prompts: &Bound<'_, PyList>,
+        concurrency: usize,
+        system_prompt: Option<&str>,
+        validate_roles: bool,
+        auto_trim: Option<&Bound<'_, PyAny>>,
+        temperature: Option<f64>,
+        max_tokens: Option<u64>,
+        top_p: Option<f64>,
+        stop: Option<&Bound<'_, PyAny>>,
+        frequency_penalty: Option<f64>,
+        presence_penalty: Option<f64>,
+        seed: Option<i64>,
+        response_format: Option<&Bound<'_, PyAny>>,
+        reasoning_effort: Option<String>,
+        prediction: Option<&Bound<'_, PyAny>>,
+        service_tier: Option<String>,
+        modalities: Option<Vec<String>>,
+        audio: Option<&Bound<'_, PyAny>>,
+        include_usage: bool,
+        include_raw: bool,
+        include_cost: bool,
+        total_timeout: Option<f64>,
+        retry: bool,
+        on_retry: Option<Bound<'_, PyAny>>,
+        usage_tracker: Option<UsageTracker>,
+        raise_on_content_filter: bool,
+    ) -> PyResult<Vec<Py<PyAny>>> {
+        if concurrency == 0 {
+            return Err(SdkError::value("concurrency must be at least 1").into_pyerr());
+        }
+
+        let mut batch = Vec::with_capacity(prompts.len());
+        for (index, item) in prompts.iter().enumerate() {
+            let (prompt, messages) = if let Ok(prompt) = item.extract::<String>() {
+                (Some(prompt), None)
+            } else if let Ok(messages) = item.cast_into::<PyList>() {
+                (None, Some(messages))
+            } else {
+                return Err(SdkError::type_error(format!(
+                    "prompts[{index}] must be a str or a list of messages"
+                ))
+                .into_pyerr());
+            };
+            let mut params = build_generation_params(
+                prompt.as_deref(),
+                system_prompt,
+                messages.as_ref(),
+                validate_roles,
+                auto_trim,
+                temperature,
+                max_tokens,
+                top_p,
+                stop,
+                frequency_penalty,
+                presence_penalty,
+                seed,
+                response_format,
+                reasoning_effort.clone(),
+                prediction,
+                service_tier.clone(),
+                modalities.clone(),
+                audio,
+                None,
+            )?;
+            params.include_cost = include_cost;
+            batch.push(params);
+        }
+
+        let provider = self.for_call(
+            total_timeout,
+            retry,
+            on_retry,
+            usage_tracker,
+            None,
+            raise_on_content_filter,
+        )?;
+        generate::run_many(
+            py,
+            &provider,
+            batch,
+            concurrency,
+            include_usage || include_raw || include_cost,
+        )
+    }
+
     /// Stream text from the LLM, returning an iterator of chunks.
     ///
-    /// Accepts the same parameters as ``generate_text``.
+    /// Accepts the same parameters as ``generate_text``, plus:
+    ///
+    /// Args:
+    ///     detailed (bool): Yield ``StreamChunk`` objects carrying ``text``,
+    ///         ``index``, ``finish_reason``, and the ``raw`` payload instead
+    ///         of ``str``. One is yielded per streamed payload.
+    ///     n (int | None): Number of completions to generate. With ``n > 1``
+    ///         the stream always yields ``StreamChunk`` objects; use their
+    ///         ``index`` to separate the choices.
+    ///     reconnect (bool | int | None): Reopen the stream if it fails
+    ///         mid-response, sending ``Last-Event-ID`` so providers that
+    ///         support it can resume; a stream that sent no event ``id:``
+    ///         is not reopened. ``True`` allows ``max_retries``
+    ///         attempts (at least one) and an int sets the count. Defaults to the
+    ///         ``RUSTY_AGENT_STREAM_RECONNECTS`` environment variable (off).
+    ///     min_chunk_chars (int | None): Merge text into chunks of at least
+    ///         this many characters. Buffered text is also yielded after about
+    ///         100 ms and when the response ends. Defaults to one chunk per
+    ///         streamed event.
+    ///     split (str): ``"line"`` or ``"sentence"`` to yield complete lines
+    ///         or sentences, holding back an unfinished one until more text
+    ///         arrives or the response ends. Defaults to ``"none"``.
+    ///     client_stop (list[str] | None): Stop sequences checked by the SDK
+    ///         rather than the provider. When the streamed text contains one,
+    ///         the text before it is yielded, the request is cancelled, and
+    ///         ``finish_reason`` reads ``"client_stop"``.
+    ///     stop_regex (str | None): A regular expression checked like
+    ///         ``client_stop``, e.g. to end the stream at a closing code
+    ///         fence. Matches may span chunks.
+    ///     include_match (bool): Also yield the text that matched
+    ///         ``client_stop`` or ``stop_regex``. Defaults to ``False``.
+    ///     raw_event_callback (Callable[[str], object] | None): Called from
+    ///         the stream's worker thread with each complete SSE event before
+    ///         it is parsed, for debugging provider output. SSE comments
+    ///         are not passed. Exceptions it raises are counted in
+    ///         ``raw_event_callback_errors``.
     ///
     /// Returns:
-    ///     TextStream: An iterator yielding ``str`` chunks.
+    ///     TextStream: An iterator yielding ``str`` chunks, or
+    ///     ``StreamChunk`` objects when ``detailed=True``.
     ///
     /// Raises:
     ///     ConnectionError: If the initial HTTP connection fails.
+    ///     TimeoutError: If ``total_timeout`` runs out, which for a stream
+    ///         also bounds reading the response.
     ///     RuntimeError: If the API returns a non-2xx status code.
-    ///     ValueError: If neither prompt nor messages is provided.
+    ///     ValueError: If neither prompt nor messages is provided, or if a
+    ///         parameter value is invalid.
     #[expect(clippy::too_many_arguments)] // PyO3 requires flat params for Python kwargs
     #[pyo3(signature = (
         prompt = None,
         *,
         system_prompt = None,
         messages = None,
+        validate_roles = true,
+        auto_trim = None,
         temperature = None,
         max_tokens = None,
         top_p = None,
@@ -488,16 +2368,39 @@ impl Provider {
         presence_penalty = None,
         seed = None,
         response_format = None,
+        reasoning_effort = None,
+        prediction = None,
+        service_tier = None,
+        modalities = None,
+        audio = None,
+        models = None,
         include_usage = false,
+        include_cost = false,
+        detailed = false,
+        n = None,
+        reconnect = None,
+        min_chunk_chars = None,
+        split = "none",
+        client_stop = None,
+        stop_regex = None,
+        include_match = false,
+        raw_event_callback = None,
+        total_timeout = None,
+        retry = true,
+        on_retry = None,
+        usage_tracker = None,
+        idempotency_key = None,
     ))]
     #[pyo3(
-        text_signature = "(self, prompt=None, *, system_prompt=None, messages=None, temperature=None, max_tokens=None, top_p=None, stop=None, frequency_penalty=None, presence_penalty=None, seed=None, response_format=None, include_usage=False)"
+        text_signature = "(self, prompt=None, *, system_prompt=None, messages=None, validate_roles=True, auto_trim=None, temperature=None, max_tokens=None, top_p=None, stop=None, frequency_penalty=None, presence_penalty=None, seed=None, response_format=None, reasoning_effort=None, prediction=None, service_tier=None, modalities=None, audio=None, models=None, include_usage=False, include_cost=False, detailed=False, n=None, reconnect=None, min_chunk_chars=None, split=\"none\", client_stop=None, stop_regex=None, include_match=False, raw_event_callback=None, total_timeout=None, retry=True, on_retry=None, usage_tracker=None, idempotency_key=None)"
     )]
     fn stream_text(
         &self,
         prompt: Option<&str>,
         system_prompt: Option<&str>,
         messages: Option<&Bound<'_, PyList>>,
+        validate_roles: bool,
+        auto_trim: Option<&Bound<'_, PyAny>>,
         temperature: Option<f64>,
         max_tokens: Option<u64>,
         top_p: Option<f64>,
@@ -506,12 +2409,35 @@ impl Provider {
         presence_penalty: Option<f64>,
         seed: Option<i64>,
         response_format: Option<&Bound<'_, PyAny>>,
+        reasoning_effort: Option<String>,
+        prediction: Option<&Bound<'_, PyAny>>,
+        service_tier: Option<String>,
+        modalities: Option<Vec<String>>,
+        audio: Option<&Bound<'_, PyAny>>,
+        models: Option<Vec<String>>,
         include_usage: bool,
+        include_cost: bool,
+        detailed: bool,
+        n: Option<u32>,
+        reconnect: Option<&Bound<'_, PyAny>>,
+        min_chunk_chars: Option<usize>,
+        split: &str,
+        client_stop: Option<Vec<String>>,
+        stop_regex: Option<&str>,
+        include_match: bool,
+        raw_event_callback: Option<Bound<'_, PyAny>>,
+        total_timeout: Option<f64>,
+        retry: bool,
+        on_retry: Option<Bound<'_, PyAny>>,
+        usage_tracker: Option<UsageTracker>,
+        idempotency_key: Option<String>,
     ) -> PyResult<TextStream> {
-        let params = build_generation_params(
+        let mut params = build_generation_params(
             prompt,
             system_prompt,
             messages,
+            validate_roles,
+            auto_trim,
             temperature,
             max_tokens,
             top_p,
@@ -520,78 +2446,1602 @@ impl Provider {
             presence_penalty,
             seed,
             response_format,
+            reasoning_effort,
+            prediction,
+            service_tier,
+            modalities,
+            audio,
+            models,
         )?;
 
-        if include_usage {
-            stream::run_with_metadata(self, params)
+        let settings = self.build_stream_settings(
+            &mut params,
+            detailed,
+            n,
+            reconnect,
+            min_chunk_chars,
+            split,
+            client_stop,
+            stop_regex,
+            include_match,
+            raw_event_callback,
+        )?;
+
+        params.include_cost = include_cost;
+        let provider = self.for_call(
+            total_timeout,
+            retry,
+            on_retry,
+            usage_tracker,
+            idempotency_key,
+            false,
+        )?;
+        if include_usage || include_cost {
+            stream::run_with_metadata(&provider, params, settings)
         } else {
-            stream::run(self, params)
+            stream::run(&provider, params, settings)
         }
     }
 
-    /// Create a Provider pre-configured for OpenAI's API.
+    /// Stream text from the LLM as an async iterator of chunks.
     ///
-    /// Args:
-    ///     model (str): Model identifier, e.g. ``"gpt-4o-mini"``.
-    ///     api_key (str | None): API key. Defaults to ``OPENAI_API_KEY`` env var.
-    #[classmethod]
-    #[pyo3(signature = (model, *, api_key=None))]
-    #[pyo3(text_signature = "(model, *, api_key=None)")]
-    fn openai(
-        _cls: &Bound<'_, pyo3::types::PyType>,
-        model: String,
-        api_key: Option<String>,
-    ) -> PyResult<Self> {
-        Self::from_preset(
-            model,
-            api_key,
-            "https://api.openai.com/v1",
-            "OPENAI_API_KEY",
-        )
+    /// Accepts the same parameters as ``stream_text`` and yields the same
+    /// chunks, for use with ``async for``. The response is read on a
+    /// runtime shared by all async streams instead of a thread per stream.
+    /// Cancelling the task that awaits the next chunk, calling ``cancel()``,
+    /// or dropping the iterator aborts the HTTP request.
+    ///
+    /// Returns:
+    ///     AsyncTextStream: An async iterator yielding ``str`` chunks, or
+    ///     ``StreamChunk`` objects when ``detailed=True``.
+    ///
+    /// Raises:
+    ///     ConnectionError: When iterated, if the HTTP connection fails.
+    ///     RuntimeError: When iterated, if the API returns a non-2xx status
+    ///         code, or if no event loop is running when a chunk is awaited.
+    ///     ValueError: If neither prompt nor messages is provided, or if a
+    ///         parameter value is invalid.
+    #[expect(clippy::too_many_arguments)] // PyO3 requires flat params for Python kwargs
+    #[pyo3(signature = (
+        prompt = None,
+        *,
+        system_prompt = None,
+        messages = None,
+        validate_roles = true,
+        auto_trim = None,
+        temperature = None,
+        max_tokens = None,
+        top_p = None,
+        stop = None,
+        frequency_penalty = None,
+        presence_penalty = None,
+        seed = None,
+        response_format = None,
+        reasoning_effort = None,
+        prediction = None,
+        service_tier = None,
+        modalities = None,
+        audio = None,
+        models = None,
+        include_usage = false,
+        include_cost = false,
+        detailed = false,
+        n = None,
+        reconnect = None,
+        min_chunk_chars = None,
+        split = "none",
+        client_stop = None,
+        stop_regex = None,
+        include_match = false,
+        raw_event_callback = None,
+        total_timeout = None,
+        retry = true,
+        on_retry = None,
+        usage_tracker = None,
+        idempotency_key = None,
+    ))]
+    #[pyo3(
+        text_signature = "(self, prompt=None, *, system_prompt=None, messages=None, validate_roles=True, auto_trim=None, temperature=None, max_tokens=None, top_p=None, stop=None, frequency_penalty=None, presence_penalty=None, seed=None, response_format=None, reasoning_effort=None, prediction=None, service_tier=None, modalities=None, audio=None, models=None, include_usage=False, include_cost=False, detailed=False, n=None, reconnect=None, min_chunk_chars=None, split=\"none\", client_stop=None, stop_regex=None, include_match=False, raw_event_callback=None, total_timeout=None, retry=True, on_retry=None, usage_tracker=None, idempotency_key=None)"
+    )]
+    fn astream_text(
+        &self,
+        prompt: Option<&str>,
+        system_prompt: Option<&str>,
+        messages: Option<&Bound<'_, PyList>>,
+        validate_roles: bool,
+        auto_trim: Option<&Bound<'_, PyAny>>,
+        temperature: Option<f64>,
+        max_tokens: Option<u64>,
+        top_p: Option<f64>,
+        stop: Option<&Bound<'_, PyAny>>,
+        frequency_penalty: Option<f64>,
+        presence_penalty: Option<f64>,
+        seed: Option<i64>,
+        response_format: Option<&Bound<'_, PyAny>>,
+        reasoning_effort: Option<String>,
+        prediction: Option<&Bound<'_, PyAny>>,
+        service_tier: Option<String>,
+        modalities: Option<Vec<String>>,
+        audio: Option<&Bound<'_, PyAny>>,
+        models: Option<Vec<String>>,
+        include_usage: bool,
+        include_cost: bool,
+        detailed: bool,
+        n: Option<u32>,
+        reconnect: Option<&Bound<'_, PyAny>>,
+        min_chunk_chars: Option<usize>,
+        split: &str,
+        client_stop: Option<Vec<String>>,
+        stop_regex: Option<&str>,
+        include_match: bool,
+        raw_event_callback: Option<Bound<'_, PyAny>>,
+        total_timeout: Option<f64>,
+        retry: bool,
+        on_retry: Option<Bound<'_, PyAny>>,
+        usage_tracker: Option<UsageTracker>,
+        idempotency_key: Option<String>,
+    ) -> PyResult<AsyncTextStream> {
+        let mut params = build_generation_params(
+            prompt,
+            system_prompt,
+            messages,
+            validate_roles,
+            auto_trim,
+            temperature,
+            max_tokens,
+            top_p,
+            stop,
+            frequency_penalty,
+            presence_penalty,
+            seed,
+            response_format,
+            reasoning_effort,
+            prediction,
+            service_tier,
+            modalities,
+            audio,
+            models,
+        )?;
+
+        let settings = self.build_stream_settings(
+            &mut params,
+            detailed,
+            n,
+            reconnect,
+            min_chunk_chars,
+            split,
+            client_stop,
+            stop_regex,
+            include_match,
+            raw_event_callback,
+        )?;
+
+        params.include_cost = include_cost;
+        let provider = self.for_call(
+            total_timeout,
+            retry,
+            on_retry,
+            usage_tracker,
+            idempotency_key,
+            false,
+        )?;
+        let body = stream::stream_request(&provider, params, include_usage || include_cost);
+        astream::run(&provider, body, settings)
     }
 
-    /// Create a Provider pre-configured for Anthropic's API.
+    /// Start a conversation that keeps its own message history.
     ///
     /// Args:
-    ///     model (str): Model identifier, e.g. ``"claude-sonnet-4-5-20250514"``.
-    ///     api_key (str | None): API key. Defaults to ``ANTHROPIC_API_KEY`` env var.
-    #[classmethod]
-    #[pyo3(signature = (model, *, api_key=None))]
-    #[pyo3(text_signature = "(model, *, api_key=None)")]
-    fn anthropic(
-        _cls: &Bound<'_, pyo3::types::PyType>,
-        model: String,
-        api_key: Option<String>,
-    ) -> PyResult<Self> {
-        Self::from_preset(
-            model,
-            api_key,
-            "https://api.anthropic.com/v1",
-            "ANTHROPIC_API_KEY",
-        )
+    ///     system_prompt (str | None): System prompt for the conversation.
+    ///     messages (list[dict] | None): Initial history, e.g. from a
+    ///         previous session. Placed after the system prompt.
+    ///
+    /// Returns:
+    ///     Chat: A chat session bound to this provider.
+    #[pyo3(signature = (system_prompt=None, *, messages=None))]
+    #[pyo3(text_signature = "(self, system_prompt=None, *, messages=None)")]
+    fn chat(
+        &self,
+        system_prompt: Option<&str>,
+        messages: Option<&Bound<'_, PyList>>,
+    ) -> PyResult<Chat> {
+        let mut initial = Vec::new();
+        if let Some(system_prompt) = system_prompt {
+            initial.push(ChatMessage {
+                role: "system".to_string(),
+                content: system_prompt.into(),
+                ..Default::default()
+            });
+        }
+        if let Some(messages) = messages {
+            initial.extend(extract_messages(messages)?);
+        }
+        Ok(Chat::new(self.clone(), initial))
     }
 
-    /// Create a Provider pre-configured for OpenRouter's API.
+    /// Generate structured output and return it parsed as a Python object.
+    ///
+    /// Sets ``response_format`` to a strict ``json_schema`` built from
+    /// ``schema`` (or ``json_object`` when no schema is given), then parses
+    /// the reply as JSON.
     ///
     /// Args:
-    ///     model (str): Model identifier, e.g. ``"openai/gpt-4o-mini"``.
-    ///     api_key (str | None): API key. Defaults to ``OPENROUTER_API_KEY`` env var.
-    #[classmethod]
-    #[pyo3(signature = (model, *, api_key=None))]
-    #[pyo3(text_signature = "(model, *, api_key=None)")]
-    fn openrouter(
+    ///     prompt (str | None): The user message to send.
+    ///     schema (dict | None): JSON Schema the output must follow. When
+    ///         ``None``, JSON mode (``{"type": "json_object"}``) is used;
+    ///         the prompt should then mention JSON.
+    ///     repair (bool): If the reply is not valid JSON, send it back once
+    ///         with a corrective system message. Defaults to ``False``.
+    ///
+    /// Also accepts the generation parameters of ``generate_text`` except
+    /// ``response_format``, ``prediction``, ``modalities``, ``audio``, and
+    /// ``include_usage``.
+    ///
+    /// Returns:
+    ///     dict | list: The parsed JSON value.
+    ///
+    /// Raises:
+    ///     ConnectionError: If the HTTP request fails.
+    ///     RuntimeError: If the API returns a non-2xx status code.
+    ///     ValueError: If the model's reply is not valid JSON (the message
+    ///         includes the raw text), or if a parameter value is invalid.
+    #[expect(clippy::too_many_arguments)] // PyO3 requires flat params for Python kwargs
+    #[pyo3(signature = (
+        prompt = None,
+        *,
+        schema = None,
+        repair = false,
+        system_prompt = None,
+        messages = None,
+        validate_roles = true,
+        auto_trim = None,
+        temperature = None,
+        max_tokens = None,
+        top_p = None,
+        stop = None,
+        frequency_penalty = None,
+        presence_penalty = None,
+        seed = None,
+        reasoning_effort = None,
+        service_tier = None,
+    ))]
+    #[pyo3(
+        text_signature = "(self, prompt=None, *, schema=None, repair=False, system_prompt=None, messages=None, validate_roles=True, auto_trim=None, temperature=None, max_tokens=None, top_p=None, stop=None, frequency_penalty=None, presence_penalty=None, seed=None, reasoning_effort=None, service_tier=None)"
+    )]
+    fn generate_object(
+        &self,
+        py: Python<'_>,
+        prompt: Option<&str>,
+        schema: Option<&Bound<'_, PyAny>>,
+        repair: bool,
+        system_prompt: Option<&str>,
+        messages: Option<&Bound<'_, PyList>>,
+        validate_roles: bool,
+        auto_trim: Option<&Bound<'_, PyAny>>,
+        temperature: Option<f64>,
+        max_tokens: Option<u64>,
+        top_p: Option<f64>,
+        stop: Option<&Bound<'_, PyAny>>,
+        frequency_penalty: Option<f64>,
+        presence_penalty: Option<f64>,
+        seed: Option<i64>,
+        reasoning_effort: Option<String>,
+        service_tier: Option<String>,
+    ) -> PyResult<Py<PyAny>> {
+        let mut params = build_generation_params(
+            prompt,
+            system_prompt,
+            messages,
+            validate_roles,
+            auto_trim,
+            temperature,
+            max_tokens,
+            top_p,
+            stop,
+            frequency_penalty,
+            presence_penalty,
+            seed,
+            None,
+            reasoning_effort,
+            None,
+            service_tier,
+            None,
+            None,
+            None,
+        )?;
+        let ResolvedFormat { format, model } = match schema {
+            Some(schema) => resolve_format(schema, |schema| {
+                build_json_schema_format("response", schema, true)
+            })?,
+            None => ResolvedFormat {
+                format: serde_json::json!({"type": "json_object"}),
+                model: None,
+            },
+        };
+        params.response_format = Some(format);
+
+        let value = structured::run_object(self, params, repair)?;
+        match model {
+            Some(model) => {
+                let text = serde_json::to_string(&value)
+                    .map_err(|e| SdkError::runtime(e.to_string()).into_pyerr())?;
+                Ok(model.call_method1("model_validate_json", (text,))?.unbind())
+            }
+            None => json_to_py(py, &value),
+        }
+    }
+
+    /// Stream structured output as progressively completed Python objects.
+    ///
+    /// Like :meth:`generate_object`, but returns an iterator. Each item is
+    /// the JSON value parsed from the reply so far, with unterminated
+    /// strings, arrays, and objects closed. A new item is only yielded when
+    /// the value changed. When the stream ends the full reply is parsed
+    /// exactly, and the final value is yielded if it differs from the last.
+    ///
+    /// Args:
+    ///     prompt (str | None): The user message to send.
+    ///     schema (dict | type | None): JSON Schema or Pydantic model class
+    ///         the output must follow. When ``None``, JSON mode
+    ///         (``{"type": "json_object"}``) is used. Items are plain
+    ///         ``dict``/``list`` values even for a Pydantic model.
+    ///
+    /// Also accepts the generation parameters of ``generate_object``
+    /// except ``repair``.
+    ///
+    /// Returns:
+    ///     ObjectStream: An iterator yielding ``dict`` or ``list`` snapshots.
+    ///
+    /// Raises:
+    ///     ConnectionError: If the initial HTTP connection fails.
+    ///     RuntimeError: If the API returns a non-2xx status code.
+    ///     ValueError: If a parameter value is invalid. Raised from the
+    ///         iterator if the complete reply is not valid JSON (the message
+    ///         includes the raw text).
+    #[expect(clippy::too_many_arguments)] // PyO3 requires flat params for Python kwargs
+    #[pyo3(signature = (
+        prompt = None,
+        *,
+        schema = None,
+        system_prompt = None,
+        messages = None,
+        validate_roles = true,
+        auto_trim = None,
+        temperature = None,
+        max_tokens = None,
+        top_p = None,
+        stop = None,
+        frequency_penalty = None,
+        presence_penalty = None,
+        seed = None,
+        reasoning_effort = None,
+        service_tier = None,
+    ))]
+    #[pyo3(
+        text_signature = "(self, prompt=None, *, schema=None, system_prompt=None, messages=None, validate_roles=True, auto_trim=None, temperature=None, max_tokens=None, top_p=None, stop=None, frequency_penalty=None, presence_penalty=None, seed=None, reasoning_effort=None, service_tier=None)"
+    )]
+    fn stream_object(
+        &self,
+        prompt: Option<&str>,
+        schema: Option<&Bound<'_, PyAny>>,
+        system_prompt: Option<&str>,
+        messages: Option<&Bound<'_, PyList>>,
+        validate_roles: bool,
+        auto_trim: Option<&Bound<'_, PyAny>>,
+        temperature: Option<f64>,
+        max_tokens: Option<u64>,
+        top_p: Option<f64>,
+        stop: Option<&Bound<'_, PyAny>>,
+        frequency_penalty: Option<f64>,
+        presence_penalty: Option<f64>,
+        seed: Option<i64>,
+        reasoning_effort: Option<String>,
+        service_tier: Option<String>,
+    ) -> PyResult<ObjectStream> {
+        let mut params = build_generation_params(
+            prompt,
+            system_prompt,
+            messages,
+            validate_roles,
+            auto_trim,
+            temperature,
+            max_tokens,
+            top_p,
+            stop,
+            frequency_penalty,
+            presence_penalty,
+            seed,
+            None,
+            reasoning_effort,
+            None,
+            service_tier,
+            None,
+            None,
+            None,
+        )?;
+        params.response_format = Some(match schema {
+            Some(schema) => {
+                resolve_format(schema, |schema| {
+                    build_json_schema_format("response", schema, true)
+                })?
+                .format
+            }
+            None => serde_json::json!({"type": "json_object"}),
+        });
+
+        structured::run_object_stream(self, params)
+    }
+
+    /// Create a Provider pre-configured for OpenAI's API.
+    ///
+    /// Args:
+    ///     model (str): Model identifier, e.g. ``"gpt-4o-mini"``.
+    ///     api_key (str | None): API key. Defaults to ``OPENAI_API_KEY`` env var.
+    ///
+    /// Also accepts the ``request_timeout``, ``connect_timeout``,
+    /// ``max_retries``, and ``retry_backoff_ms`` arguments of the
+    /// constructor.
+    #[classmethod]
+    #[pyo3(signature = (
+        model,
+        *,
+        api_key = None,
+        request_timeout = None,
+        connect_timeout = None,
+        max_retries = None,
+        retry_backoff_ms = None,
+    ))]
+    #[pyo3(
+        text_signature = "(model, *, api_key=None, request_timeout=None, connect_timeout=None, max_retries=None, retry_backoff_ms=None)"
+    )]
+    fn openai(
+        _cls: &Bound<'_, pyo3::types::PyType>,
+        model: String,
+        api_key: Option<String>,
+        request_timeout: Option<f64>,
+        connect_timeout: Option<f64>,
+        max_retries: Option<i64>,
+        retry_backoff_ms: Option<i64>,
+    ) -> PyResult<Self> {
+        Self::from_registry(
+            &OPENAI,
+            model,
+            api_key,
+            &RuntimeOverrides {
+                request_timeout,
+                connect_timeout,
+                max_retries,
+                retry_backoff_ms,
+                max_retry_delay_ms: None,
+                stream_idle_timeout: None,
+            },
+        )
+    }
+
+    /// Create a Provider pre-configured for Anthropic's API.
+    ///
+    /// Args:
+    ///     model (str): Model identifier, e.g. ``"claude-sonnet-4-5-20250514"``.
+    ///     api_key (str | None): API key. Defaults to ``ANTHROPIC_API_KEY`` env var.
+    ///
+    /// Also accepts the ``request_timeout``, ``connect_timeout``,
+    /// ``max_retries``, and ``retry_backoff_ms`` arguments of the
+    /// constructor.
+    #[classmethod]
+    #[pyo3(signature = (
+        model,
+        *,
+        api_key = None,
+        request_timeout = None,
+        connect_timeout = None,
+        max_retries = None,
+        retry_backoff_ms = None,
+    ))]
+    #[pyo3(
+        text_signature = "(model, *, api_key=None, request_timeout=None, connect_timeout=None, max_retries=None, retry_backoff_ms=None)"
+    )]
+    fn anthropic(
+        _cls: &Bound<'_, pyo3::types::PyType>,
+        model: String,
+        api_key: Option<String>,
+        request_timeout: Option<f64>,
+        connect_timeout: Option<f64>,
+        max_retries: Option<i64>,
+        retry_backoff_ms: Option<i64>,
+    ) -> PyResult<Self> {
+        Self::from_registry(
+            &ANTHROPIC,
+            model,
+            api_key,
+            &RuntimeOverrides {
+                request_timeout,
+                connect_timeout,
+                max_retries,
+                retry_backoff_ms,
+                max_retry_delay_ms: None,
+                stream_idle_timeout: None,
+            },
+        )
+    }
+
+    /// Create a Provider pre-configured for OpenRouter's API.
+    ///
+    /// Args:
+    ///     model (str): Model identifier, e.g. ``"openai/gpt-4o-mini"``.
+    ///     api_key (str | None): API key. Defaults to ``OPENROUTER_API_KEY`` env var.
+    ///     site_url (str | None): Your app's URL, sent as ``HTTP-Referer``
+    ///         for OpenRouter's app rankings.
+    ///     app_name (str | None): Your app's name, sent as ``X-Title``.
+    ///
+    /// Also accepts the ``request_timeout``, ``connect_timeout``,
+    /// ``max_retries``, and ``retry_backoff_ms`` arguments of the
+    /// constructor.
+    #[expect(clippy::too_many_arguments)] // PyO3 requires flat params for Python kwargs
+    #[classmethod]
+    #[pyo3(signature = (
+        model,
+        *,
+        api_key = None,
+        site_url = None,
+        app_name = None,
+        request_timeout = None,
+        connect_timeout = None,
+        max_retries = None,
+        retry_backoff_ms = None,
+    ))]
+    #[pyo3(
+        text_signature = "(model, *, api_key=None, site_url=None, app_name=None, request_timeout=None, connect_timeout=None, max_retries=None, retry_backoff_ms=None)"
+    )]
+    fn openrouter(
+        _cls: &Bound<'_, pyo3::types::PyType>,
+        model: String,
+        api_key: Option<String>,
+        site_url: Option<String>,
+        app_name: Option<String>,
+        request_timeout: Option<f64>,
+        connect_timeout: Option<f64>,
+        max_retries: Option<i64>,
+        retry_backoff_ms: Option<i64>,
+    ) -> PyResult<Self> {
+        let attribution = [("HTTP-Referer", site_url), ("X-Title", app_name)]
+            .into_iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value?)));
+        let default_headers = parse_default_headers(attribution).map_err(SdkError::into_pyerr)?;
+        let provider = Self::from_registry(
+            &OPENROUTER,
+            model,
+            api_key,
+            &RuntimeOverrides {
+                request_timeout,
+                connect_timeout,
+                max_retries,
+                retry_backoff_ms,
+                max_retry_delay_ms: None,
+                stream_idle_timeout: None,
+            },
+        )?;
+        Ok(Self {
+            default_headers,
+            ..provider
+        })
+    }
+
+    /// Create a Provider pre-configured for Groq's API.
+    ///
+    /// Args:
+    ///     model (str): Model identifier, e.g. ``"llama-3.3-70b-versatile"``.
+    ///     api_key (str | None): API key. Defaults to ``GROQ_API_KEY`` env var.
+    ///
+    /// Also accepts the ``request_timeout``, ``connect_timeout``,
+    /// ``max_retries``, and ``retry_backoff_ms`` arguments of the
+    /// constructor.
+    #[classmethod]
+    #[pyo3(signature = (
+        model,
+        *,
+        api_key = None,
+        request_timeout = None,
+        connect_timeout = None,
+        max_retries = None,
+        retry_backoff_ms = None,
+    ))]
+    #[pyo3(
+        text_signature = "(model, *, api_key=None, request_timeout=None, connect_timeout=None, max_retries=None, retry_backoff_ms=None)"
+    )]
+    fn groq(
+        _cls: &Bound<'_, pyo3::types::PyType>,
+        model: String,
+        api_key: Option<String>,
+        request_timeout: Option<f64>,
+        connect_timeout: Option<f64>,
+        max_retries: Option<i64>,
+        retry_backoff_ms: Option<i64>,
+    ) -> PyResult<Self> {
+        Self::from_registry(
+            &GROQ,
+            model,
+            api_key,
+            &RuntimeOverrides {
+                request_timeout,
+                connect_timeout,
+                max_retries,
+                retry_backoff_ms,
+                max_retry_delay_ms: None,
+                stream_idle_timeout: None,
+            },
+        )
+    }
+
+    /// Create a Provider pre-configured for Mistral's API.
+    ///
+    /// Args:
+    ///     model (str): Model identifier, e.g. ``"mistral-large-latest"``.
+    ///     api_key (str | None): API key. Defaults to ``MISTRAL_API_KEY`` env var.
+    ///
+    /// Also accepts the ``request_timeout``, ``connect_timeout``,
+    /// ``max_retries``, and ``retry_backoff_ms`` arguments of the
+    /// constructor.
+    #[classmethod]
+    #[pyo3(signature = (
+        model,
+        *,
+        api_key = None,
+        request_timeout = None,
+        connect_timeout = None,
+        max_retries = None,
+        retry_backoff_ms = None,
+    ))]
+    #[pyo3(
+        text_signature = "(model, *, api_key=None, request_timeout=None, connect_timeout=None, max_retries=None, retry_backoff_ms=None)"
+    )]
+    fn mistral(
+        _cls: &Bound<'_, pyo3::types::PyType>,
+        model: String,
+        api_key: Option<String>,
+        request_timeout: Option<f64>,
+        connect_timeout: Option<f64>,
+        max_retries: Option<i64>,
+        retry_backoff_ms: Option<i64>,
+    ) -> PyResult<Self> {
+        Self::from_registry(
+            &MISTRAL,
+            model,
+            api_key,
+            &RuntimeOverrides {
+                request_timeout,
+                connect_timeout,
+                max_retries,
+                retry_backoff_ms,
+                max_retry_delay_ms: None,
+                stream_idle_timeout: None,
+            },
+        )
+    }
+
+    /// Create a Provider pre-configured for DeepSeek's API.
+    ///
+    /// Args:
+    ///     model (str): Model identifier, e.g. ``"deepseek-chat"``.
+    ///     api_key (str | None): API key. Defaults to ``DEEPSEEK_API_KEY`` env var.
+    ///
+    /// Also accepts the ``request_timeout``, ``connect_timeout``,
+    /// ``max_retries``, and ``retry_backoff_ms`` arguments of the
+    /// constructor.
+    #[classmethod]
+    #[pyo3(signature = (
+        model,
+        *,
+        api_key = None,
+        request_timeout = None,
+        connect_timeout = None,
+        max_retries = None,
+        retry_backoff_ms = None,
+    ))]
+    #[pyo3(
+        text_signature = "(model, *, api_key=None, request_timeout=None, connect_timeout=None, max_retries=None, retry_backoff_ms=None)"
+    )]
+    fn deepseek(
+        _cls: &Bound<'_, pyo3::types::PyType>,
+        model: String,
+        api_key: Option<String>,
+        request_timeout: Option<f64>,
+        connect_timeout: Option<f64>,
+        max_retries: Option<i64>,
+        retry_backoff_ms: Option<i64>,
+    ) -> PyResult<Self> {
+        Self::from_registry(
+            &DEEPSEEK,
+            model,
+            api_key,
+            &RuntimeOverrides {
+                request_timeout,
+                connect_timeout,
+                max_retries,
+                retry_backoff_ms,
+                max_retry_delay_ms: None,
+                stream_idle_timeout: None,
+            },
+        )
+    }
+
+    /// Create a Provider pre-configured for Together AI's API.
+    ///
+    /// Args:
+    ///     model (str): Model identifier, e.g. ``"meta-llama/Llama-3.3-70B-Instruct-Turbo"``.
+    ///     api_key (str | None): API key. Defaults to ``TOGETHER_API_KEY`` env var.
+    ///
+    /// Also accepts the ``request_timeout``, ``connect_timeout``,
+    /// ``max_retries``, and ``retry_backoff_ms`` arguments of the
+    /// constructor.
+    #[classmethod]
+    #[pyo3(signature = (
+        model,
+        *,
+        api_key = None,
+        request_timeout = None,
+        connect_timeout = None,
+        max_retries = None,
+        retry_backoff_ms = None,
+    ))]
+    #[pyo3(
+        text_signature = "(model, *, api_key=None, request_timeout=None, connect_timeout=None, max_retries=None, retry_backoff_ms=None)"
+    )]
+    fn together(
         _cls: &Bound<'_, pyo3::types::PyType>,
         model: String,
         api_key: Option<String>,
+        request_timeout: Option<f64>,
+        connect_timeout: Option<f64>,
+        max_retries: Option<i64>,
+        retry_backoff_ms: Option<i64>,
+    ) -> PyResult<Self> {
+        Self::from_registry(
+            &TOGETHER,
+            model,
+            api_key,
+            &RuntimeOverrides {
+                request_timeout,
+                connect_timeout,
+                max_retries,
+                retry_backoff_ms,
+                max_retry_delay_ms: None,
+                stream_idle_timeout: None,
+            },
+        )
+    }
+
+    /// Create a Provider pre-configured for xAI's API.
+    ///
+    /// Args:
+    ///     model (str): Model identifier, e.g. ``"grok-4"``.
+    ///     api_key (str | None): API key. Defaults to ``XAI_API_KEY`` env var.
+    ///
+    /// Also accepts the ``request_timeout``, ``connect_timeout``,
+    /// ``max_retries``, and ``retry_backoff_ms`` arguments of the
+    /// constructor.
+    #[classmethod]
+    #[pyo3(signature = (
+        model,
+        *,
+        api_key = None,
+        request_timeout = None,
+        connect_timeout = None,
+        max_retries = None,
+        retry_backoff_ms = None,
+    ))]
+    #[pyo3(
+        text_signature = "(model, *, api_key=None, request_timeout=None, connect_timeout=None, max_retries=None, retry_backoff_ms=None)"
+    )]
+    fn xai(
+        _cls: &Bound<'_, pyo3::types::PyType>,
+        model: String,
+        api_key: Option<String>,
+        request_timeout: Option<f64>,
+        connect_timeout: Option<f64>,
+        max_retries: Option<i64>,
+        retry_backoff_ms: Option<i64>,
+    ) -> PyResult<Self> {
+        Self::from_registry(
+            &XAI,
+            model,
+            api_key,
+            &RuntimeOverrides {
+                request_timeout,
+                connect_timeout,
+                max_retries,
+                retry_backoff_ms,
+                max_retry_delay_ms: None,
+                stream_idle_timeout: None,
+            },
+        )
+    }
+
+    /// Create a Provider for a named provider from the built-in registry.
+    ///
+    /// ``Provider.from_named("groq", model)`` is equivalent to
+    /// ``Provider.groq(model)``; see :meth:`known_providers` for the
+    /// available names.
+    ///
+    /// Args:
+    ///     name (str): Provider name, e.g. ``"openai"`` or ``"groq"``.
+    ///     model (str): Model identifier.
+    ///     api_key (str | None): API key. Defaults to the provider's
+    ///         environment variable, e.g. ``GROQ_API_KEY``.
+    ///
+    /// Also accepts the ``request_timeout``, ``connect_timeout``,
+    /// ``max_retries``, and ``retry_backoff_ms`` arguments of the
+    /// constructor.
+    ///
+    /// Raises:
+    ///     ValueError: If ``name`` is not a known provider, listing the
+    ///         supported ones, or if no API key is provided and the
+    ///         provider's environment variable is not set.
+    #[expect(clippy::too_many_arguments)] // PyO3 requires flat params for Python kwargs
+    #[classmethod]
+    #[pyo3(signature = (
+        name,
+        model,
+        *,
+        api_key = None,
+        request_timeout = None,
+        connect_timeout = None,
+        max_retries = None,
+        retry_backoff_ms = None,
+    ))]
+    #[pyo3(
+        text_signature = "(name, model, *, api_key=None, request_timeout=None, connect_timeout=None, max_retries=None, retry_backoff_ms=None)"
+    )]
+    fn from_named(
+        _cls: &Bound<'_, pyo3::types::PyType>,
+        name: &str,
+        model: String,
+        api_key: Option<String>,
+        request_timeout: Option<f64>,
+        connect_timeout: Option<f64>,
+        max_retries: Option<i64>,
+        retry_backoff_ms: Option<i64>,
+    ) -> PyResult<Self> {
+        let preset = find_preset(name).map_err(SdkError::into_pyerr)?;
+        Self::from_registry(
+            preset,
+            model,
+            api_key,
+            &RuntimeOverrides {
+                request_timeout,
+                connect_timeout,
+                max_retries,
+                retry_backoff_ms,
+                max_retry_delay_ms: None,
+                stream_idle_timeout: None,
+            },
+        )
+    }
+
+    /// Names accepted by :meth:`from_named`.
+    ///
+    /// Returns:
+    ///     list[str]: The registry's provider names, in a fixed order.
+    #[staticmethod]
+    fn known_providers() -> Vec<&'static str> {
+        PRESETS.iter().map(|preset| preset.name).collect()
+    }
+
+    /// Create a Provider for an Azure OpenAI deployment.
+    ///
+    /// Requests go to
+    /// ``{base_url}/openai/deployments/{deployment}/chat/completions``
+    /// with the ``api-version`` query parameter, and the key is sent in
+    /// the ``api-key`` header. The deployment is the provider's model, so
+    /// :meth:`with_model` switches deployments.
+    ///
+    /// Args:
+    ///     deployment (str): Name of the model deployment.
+    ///     resource (str | None): Azure resource name, giving the base URL
+    ///         ``https://{resource}.openai.azure.com``.
+    ///     api_key (str | None): API key. Defaults to
+    ///         ``AZURE_OPENAI_API_KEY`` env var.
+    ///     api_version (str): Value of the ``api-version`` query parameter.
+    ///         Defaults to ``"2024-10-21"``.
+    ///     base_url (str | None): Resource endpoint, e.g.
+    ///         ``"https://my-resource.openai.azure.com"``. Takes precedence
+    ///         over ``resource``; if neither is given, defaults to
+    ///         ``AZURE_OPENAI_ENDPOINT`` env var.
+    ///
+    /// Also accepts the ``request_timeout``, ``connect_timeout``,
+    /// ``max_retries``, and ``retry_backoff_ms`` arguments of the
+    /// constructor.
+    ///
+    /// Raises:
+    ///     ValueError: If no endpoint or API key can be resolved, or if a
+    ///         timeout or retry argument is invalid.
+    #[expect(clippy::too_many_arguments)] // PyO3 requires flat params for Python kwargs
+    #[classmethod]
+    #[pyo3(signature = (
+        deployment,
+        *,
+        resource = None,
+        api_key = None,
+        api_version = DEFAULT_AZURE_API_VERSION.to_string(),
+        base_url = None,
+        request_timeout = None,
+        connect_timeout = None,
+        max_retries = None,
+        retry_backoff_ms = None,
+    ))]
+    #[pyo3(
+        text_signature = "(deployment, *, resource=None, api_key=None, api_version='2024-10-21', base_url=None, request_timeout=None, connect_timeout=None, max_retries=None, retry_backoff_ms=None)"
+    )]
+    fn azure(
+        _cls: &Bound<'_, pyo3::types::PyType>,
+        deployment: String,
+        resource: Option<String>,
+        api_key: Option<String>,
+        api_version: String,
+        base_url: Option<String>,
+        request_timeout: Option<f64>,
+        connect_timeout: Option<f64>,
+        max_retries: Option<i64>,
+        retry_backoff_ms: Option<i64>,
+    ) -> PyResult<Self> {
+        let base_url = resolve_azure_base_url(
+            resource,
+            base_url,
+            std::env::var("AZURE_OPENAI_ENDPOINT").ok(),
+        )
+        .map_err(SdkError::into_pyerr)?;
+        let provider = Self::from_preset(
+            deployment,
+            api_key,
+            &base_url,
+            "AZURE_OPENAI_API_KEY",
+            &RuntimeOverrides {
+                request_timeout,
+                connect_timeout,
+                max_retries,
+                retry_backoff_ms,
+                max_retry_delay_ms: None,
+                stream_idle_timeout: None,
+            },
+        )?;
+        Ok(Self {
+            routing: Routing::azure(&api_version),
+            ..provider
+        })
+    }
+
+    /// Create a Provider for a local OpenAI-compatible server such as
+    /// Ollama, llama.cpp, or vLLM.
+    ///
+    /// No API key is required and no environment variable is read for
+    /// one; without a key, requests are sent without an ``Authorization``
+    /// header.
+    ///
+    /// Args:
+    ///     model (str): Model name as the server knows it, e.g.
+    ///         ``"llama3.2"``.
+    ///     base_url (str): Base URL of the server. Defaults to
+    ///         ``"http://localhost:11434/v1"``, Ollama's default.
+    ///     api_key (str | None): API key, for servers started with one.
+    ///
+    /// Also accepts the ``request_timeout``, ``connect_timeout``,
+    /// ``max_retries``, and ``retry_backoff_ms`` arguments of the
+    /// constructor.
+    #[expect(clippy::too_many_arguments)] // PyO3 requires flat params for Python kwargs
+    #[classmethod]
+    #[pyo3(signature = (
+        model,
+        *,
+        base_url = DEFAULT_OLLAMA_BASE_URL.to_string(),
+        api_key = None,
+        request_timeout = None,
+        connect_timeout = None,
+        max_retries = None,
+        retry_backoff_ms = None,
+    ))]
+    #[pyo3(
+        text_signature = "(model, *, base_url='http://localhost:11434/v1', api_key=None, request_timeout=None, connect_timeout=None, max_retries=None, retry_backoff_ms=None)"
+    )]
+    fn ollama(
+        _cls: &Bound<'_, pyo3::types::PyType>,
+        model: String,
+        base_url: String,
+        api_key: Option<String>,
+        request_timeout: Option<f64>,
+        connect_timeout: Option<f64>,
+        max_retries: Option<i64>,
+        retry_backoff_ms: Option<i64>,
+    ) -> PyResult<Self> {
+        let (base_url, base_url_warning) =
+            normalize_base_url(&base_url).map_err(SdkError::into_pyerr)?;
+        let provider = Self::from_parts(
+            model,
+            api_key.unwrap_or_default(),
+            base_url,
+            &RuntimeOverrides {
+                request_timeout,
+                connect_timeout,
+                max_retries,
+                retry_backoff_ms,
+                max_retry_delay_ms: None,
+                stream_idle_timeout: None,
+            },
+            TlsConfig::default(),
+        )?;
+        Ok(Self {
+            base_url_warning,
+            ..provider
+        })
+    }
+
+    /// The model identifier requests are sent with.
+    // Named so its generated wrapper does not clash with `get_model`'s.
+    #[getter(model)]
+    fn model_id(&self) -> &str {
+        &self.model
+    }
+
+    /// Models tried in turn when a call to ``model`` fails.
+    #[getter]
+    fn fallback_models(&self) -> Vec<String> {
+        self.fallback_models.clone()
+    }
+
+    /// The base URL, without a trailing slash.
+    #[getter]
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// What normalizing the given base URL changed, e.g. a trailing
+    /// ``/chat/completions`` it removed, or ``None``.
+    #[getter]
+    fn base_url_warning(&self) -> Option<&str> {
+        self.base_url_warning.as_deref()
+    }
+
+    /// Headers sent with every request, with lowercase names.
+    #[getter]
+    fn default_headers(&self) -> HashMap<String, String> {
+        header_dict(&self.default_headers)
+    }
+
+    /// The API key masked to ``"****"`` plus its last four characters, for
+    /// logging. Keys of four characters or fewer are masked entirely.
+    #[getter]
+    fn masked_api_key(&self) -> String {
+        mask_api_key(&self.api_key)
+    }
+
+    /// Seconds a request may take.
+    #[getter]
+    fn request_timeout(&self) -> f64 {
+        self.request_timeout.as_secs_f64()
+    }
+
+    /// Seconds allowed to open a connection.
+    #[getter]
+    fn connect_timeout(&self) -> f64 {
+        self.connect_timeout.as_secs_f64()
+    }
+
+    /// Retries for retryable failures.
+    #[getter]
+    fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Base delay between retries in seconds, doubled per attempt.
+    #[getter]
+    fn retry_backoff(&self) -> f64 {
+        self.retry_backoff.as_secs_f64()
+    }
+
+    /// Longest wait before a retry in seconds.
+    #[getter]
+    fn max_retry_delay(&self) -> f64 {
+        self.max_retry_delay.as_secs_f64()
+    }
+
+    /// Response statuses that are retried.
+    #[getter]
+    fn retry_on_status(&self) -> Vec<u16> {
+        self.retry_on_status.clone()
+    }
+
+    /// Callback invoked before each retry, or ``None``.
+    #[getter]
+    fn on_retry(&self, py: Python<'_>) -> Option<Py<PyAny>> {
+        self.retry_hook
+            .callback()
+            .map(|callback| callback.clone_ref(py))
+    }
+
+    /// Retries made by requests through this provider and its copies.
+    #[getter]
+    fn retry_count(&self) -> u64 {
+        self.retry_hook.retries()
+    }
+
+    /// Exceptions raised by ``on_retry``, which are counted instead of
+    /// breaking the request.
+    #[getter]
+    fn on_retry_errors(&self) -> u64 {
+        self.retry_hook.callback_errors()
+    }
+
+    /// Consecutive failed calls that open the circuit breaker; ``0`` when
+    /// it is off.
+    #[getter]
+    fn circuit_breaker_threshold(&self) -> u32 {
+        self.circuit.threshold()
+    }
+
+    /// Seconds an open circuit waits before letting a probe call through.
+    #[getter]
+    fn circuit_breaker_cooldown(&self) -> f64 {
+        self.circuit.cooldown().as_secs_f64()
+    }
+
+    /// The circuit breaker's state, shared with copies of this provider.
+    ///
+    /// Returns:
+    ///     str: ``"closed"`` while calls go through, ``"open"`` while they
+    ///     raise ``CircuitOpenError``, or ``"half_open"`` once the
+    ///     cool-down has ended and the next call probes the provider.
+    ///     Always ``"closed"`` when the breaker is off.
+    #[pyo3(text_signature = "(self)")]
+    fn circuit_state(&self) -> &'static str {
+        self.circuit.state().as_str()
+    }
+
+    /// Calls that may start per minute; ``0`` when there is no limit.
+    #[getter]
+    fn max_requests_per_minute(&self) -> u32 {
+        self.limiter.requests_per_minute()
+    }
+
+    /// Tokens that may be used per minute; ``0`` when there is no limit.
+    #[getter]
+    fn max_tokens_per_minute(&self) -> u64 {
+        self.limiter.tokens_per_minute()
+    }
+
+    /// What the client-side rate limiter has left, shared with copies of
+    /// this provider.
+    ///
+    /// Returns:
+    ///     dict[str, float | None]: ``requests`` and ``tokens`` available
+    ///     now, or ``None`` for a limit that is off. ``tokens`` is negative
+    ///     while calls that used more than they reserved are repaid.
+    #[pyo3(text_signature = "(self)")]
+    fn rate_limiter_levels<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let (requests, tokens) = self.limiter.levels();
+        let dict = PyDict::new(py);
+        dict.set_item("requests", requests)?;
+        dict.set_item("tokens", tokens)?;
+        Ok(dict)
+    }
+
+    /// Count the usage of every call this provider completes from now on
+    /// in ``tracker``, or stop counting with ``None``.
+    ///
+    /// Unlike the ``with_*`` methods this changes the provider in place.
+    /// Copies made afterwards, and chats started from it, count in the
+    /// same tracker; a pickled provider leaves it behind.
+    ///
+    /// Args:
+    ///     tracker (UsageTracker | None): The tracker to add to.
+    ///
+    /// Example:
+    ///     >>> tracker = UsageTracker()
+    ///     >>> provider.track_usage(tracker)
+    ///     >>> provider.generate_text("Hello")
+    ///     >>> tracker.to_dict()["models"]
+    ///     {'gpt-4o-mini': {'calls': 1, 'prompt_tokens': 9, ...}}
+    #[pyo3(text_signature = "(self, tracker)")]
+    fn track_usage(&mut self, tracker: Option<UsageTracker>) {
+        self.usage_tracker = tracker;
+    }
+
+    /// The tracker set with :meth:`track_usage`, or ``None``.
+    #[getter]
+    fn usage_tracker(&self) -> Option<UsageTracker> {
+        self.usage_tracker.clone()
+    }
+
+    /// List the models the provider serves, from its ``/models`` endpoint.
+    ///
+    /// The request is retried and timed out like a generation, but is not
+    /// held to ``rate_limit``, ``max_concurrency`` or the circuit breaker.
+    ///
+    /// Returns:
+    ///     list[ModelInfo]: The models, in the order the provider lists
+    ///     them. Entries without an ``id`` are skipped.
+    ///
+    /// Raises:
+    ///     ConnectionError: If the HTTP request fails.
+    ///     RuntimeError: If the API returns a non-2xx status code.
+    ///     ValueError: If the response cannot be parsed.
+    ///
+    /// Example:
+    ///     >>> [model.id for model in provider.list_models()][:2]
+    ///     ['gpt-4o', 'gpt-4o-mini']
+    #[pyo3(text_signature = "(self)")]
+    fn list_models(&self, py: Python<'_>) -> PyResult<Vec<ModelInfo>> {
+        list_models::list(py, self)
+    }
+
+    /// Describe one model, from the provider's ``/models/{id}`` endpoint.
+    ///
+    /// Args:
+    ///     model (str | None): Model id. Defaults to the provider's
+    ///         ``model``.
+    ///
+    /// Returns:
+    ///     ModelInfo: The model.
+    ///
+    /// Raises:
+    ///     ConnectionError: If the HTTP request fails.
+    ///     RuntimeError: If the API returns a non-2xx status code, e.g.
+    ///         404 for an unknown model.
+    ///     ValueError: If the response cannot be parsed.
+    #[pyo3(signature = (model=None))]
+    #[pyo3(text_signature = "(self, model=None)")]
+    fn get_model(&self, py: Python<'_>, model: Option<&str>) -> PyResult<ModelInfo> {
+        list_models::get(py, self, model.unwrap_or(&self.model))
+    }
+
+    /// Look a model's prices up in the provider's ``/models`` catalog, e.g.
+    /// to estimate costs where responses do not report them.
+    ///
+    /// The catalog is fetched on first use, retried and timed out like
+    /// :meth:`list_models`, and cached for the process, shared by every
+    /// provider with the same ``base_url``.
+    ///
+    /// Args:
+    ///     model (str | None): Model id as listed in the catalog. Defaults
+    ///         to the provider's ``model``.
+    ///     refresh (bool): Fetch the catalog again instead of using the
+    ///         cached copy. Defaults to ``False``.
+    ///
+    /// Returns:
+    ///     dict[str, float] | None: The model's prices in USD, keyed as
+    ///     the catalog lists them (``prompt`` and ``completion`` per
+    ///     token, ``request`` per call, ...), or ``None`` if the catalog
+    ///     has no prices for it.
+    ///
+    /// Raises:
+    ///     ConnectionError: If the HTTP request fails.
+    ///     RuntimeError: If the API returns a non-2xx status code.
+    ///     ValueError: If the catalog cannot be parsed.
+    #[pyo3(signature = (model=None, *, refresh=false))]
+    #[pyo3(text_signature = "(self, model=None, *, refresh=False)")]
+    fn get_pricing<'py>(
+        &self,
+        py: Python<'py>,
+        model: Option<&str>,
+        refresh: bool,
+    ) -> PyResult<Option<Bound<'py, PyDict>>> {
+        let url = build_models_url(&self.base_url, None);
+        let mut headers = self.headers().map_err(SdkError::into_pyerr)?;
+        headers.remove(IDEMPOTENCY_KEY);
+        let catalog = py
+            .detach(|| {
+                runtime::block_on(pricing::catalog(
+                    &self.client()?,
+                    &url,
+                    headers,
+                    &self.retry_settings(),
+                    refresh,
+                ))
+            })
+            .and_then(|result| result)
+            .map_err(SdkError::into_pyerr)?;
+        catalog
+            .get(model.unwrap_or(&self.model))
+            .map(|prices| prices.into_py_dict(py))
+            .transpose()
+    }
+
+    /// Requests that may be in flight at once; ``0`` when there is no
+    /// limit.
+    #[getter]
+    fn max_concurrency(&self) -> usize {
+        self.concurrency.max()
+    }
+
+    /// Whether requests get a generated ``Idempotency-Key`` header.
+    #[getter]
+    fn idempotency_keys(&self) -> bool {
+        self.idempotency_keys
+    }
+
+    /// Seconds a whole call may take across its retries, or ``None``.
+    #[getter]
+    fn total_timeout(&self) -> Option<f64> {
+        self.total_timeout.map(|total| total.as_secs_f64())
+    }
+
+    /// Return a copy of this provider that uses ``model``.
+    ///
+    /// The API key, base URL, timeouts, and retry settings are kept, and the
+    /// copy shares this provider's connection pool.
+    ///
+    /// Args:
+    ///     model (str): Model identifier for the copy.
+    ///
+    /// Returns:
+    ///     Provider: A new provider; this one is unchanged.
+    #[pyo3(text_signature = "(self, model)")]
+    fn with_model(&self, model: String) -> Self {
+        Self {
+            model,
+            ..self.clone()
+        }
+    }
+
+    /// Return a copy of this provider with the given settings replaced.
+    ///
+    /// Settings that are not given keep their current values; the
+    /// environment is not read again. Takes the keyword arguments of the
+    /// constructor except ``model`` (see :meth:`with_model`).
+    ///
+    /// Returns:
+    ///     Provider: A new provider; this one is unchanged.
+    ///
+    /// Raises:
+    ///     ValueError: If ``base_url`` is not an http or https URL with a
+    ///         host, if a timeout, ``retry_backoff_ms`` or
+    ///         ``max_retry_delay_ms`` is not positive, if ``max_retries``,
+    ///         ``circuit_breaker_threshold``, a per-minute limit or
+    ///         ``max_concurrency`` is negative, if ``retry_on_status`` holds
+    ///         a value that is not an HTTP status, if a TLS file cannot be
+    ///         read or parsed, if a default header is invalid, or if a
+    ///         fallback model is empty.
+    ///     TypeError: If ``on_retry`` is not callable.
+    #[expect(clippy::too_many_arguments)] // PyO3 requires flat params for Python kwargs
+    #[pyo3(signature = (
+        *,
+        fallback_models = None,
+        api_key = None,
+        base_url = None,
+        request_timeout = None,
+        connect_timeout = None,
+        max_retries = None,
+        retry_backoff_ms = None,
+        max_retry_delay_ms = None,
+        retry_on_status = None,
+        on_retry = None,
+        total_timeout = None,
+        circuit_breaker_threshold = None,
+        circuit_breaker_cooldown = None,
+        max_requests_per_minute = None,
+        max_tokens_per_minute = None,
+        max_concurrency = None,
+        idempotency_keys = None,
+        stream_idle_timeout = None,
+        ca_cert = None,
+        client_cert = None,
+        client_key = None,
+        insecure_skip_tls_verify = None,
+        default_headers = None,
+    ))]
+    #[pyo3(
+        text_signature = "(self, *, fallback_models=None, api_key=None, base_url=None, request_timeout=None, connect_timeout=None, max_retries=None, retry_backoff_ms=None, max_retry_delay_ms=None, retry_on_status=None, on_retry=None, total_timeout=None, circuit_breaker_threshold=None, circuit_breaker_cooldown=None, max_requests_per_minute=None, max_tokens_per_minute=None, max_concurrency=None, idempotency_keys=None, stream_idle_timeout=None, ca_cert=None, client_cert=None, client_key=None, insecure_skip_tls_verify=None, default_headers=None)"
+    )]
+    fn with_options(
+        &self,
+        fallback_models: Option<Vec<String>>,
+        api_key: Option<String>,
+        base_url: Option<String>,
+        request_timeout: Option<f64>,
+        connect_timeout: Option<f64>,
+        max_retries: Option<i64>,
+        retry_backoff_ms: Option<i64>,
+        max_retry_delay_ms: Option<i64>,
+        retry_on_status: Option<Vec<i64>>,
+        on_retry: Option<Bound<'_, PyAny>>,
+        total_timeout: Option<f64>,
+        circuit_breaker_threshold: Option<i64>,
+        circuit_breaker_cooldown: Option<f64>,
+        max_requests_per_minute: Option<i64>,
+        max_tokens_per_minute: Option<i64>,
+        max_concurrency: Option<i64>,
+        idempotency_keys: Option<bool>,
+        stream_idle_timeout: Option<f64>,
+        ca_cert: Option<String>,
+        client_cert: Option<String>,
+        client_key: Option<String>,
+        insecure_skip_tls_verify: Option<bool>,
+        default_headers: Option<HashMap<String, String>>,
     ) -> PyResult<Self> {
-        Self::from_preset(
-            model,
-            api_key,
-            "https://openrouter.ai/api/v1",
-            "OPENROUTER_API_KEY",
+        let mut provider = self.clone();
+        if let Some(models) = fallback_models {
+            provider.fallback_models = fallback_model_list(models).map_err(SdkError::into_pyerr)?;
+        }
+        if let Some(api_key) = api_key {
+            provider.api_key = api_key;
+        }
+        if let Some(base_url) = base_url {
+            let (base_url, warning) =
+                normalize_base_url(&base_url).map_err(SdkError::into_pyerr)?;
+            provider.base_url = base_url;
+            provider.base_url_warning = warning;
+        }
+        if let Some(secs) = request_timeout {
+            provider.request_timeout =
+                positive_secs("request_timeout", secs).map_err(SdkError::into_pyerr)?;
+        }
+        if let Some(secs) = connect_timeout {
+            provider.connect_timeout =
+                positive_secs("connect_timeout", secs).map_err(SdkError::into_pyerr)?;
+        }
+        if let Some(retries) = max_retries {
+            provider.max_retries = retry_count(retries).map_err(SdkError::into_pyerr)?;
+        }
+        if let Some(ms) = retry_backoff_ms {
+            provider.retry_backoff = retry_backoff(ms).map_err(SdkError::into_pyerr)?;
+        }
+        if let Some(ms) = max_retry_delay_ms {
+            provider.max_retry_delay =
+                positive_ms("max_retry_delay_ms", ms).map_err(SdkError::into_pyerr)?;
+        }
+        if let Some(codes) = retry_on_status {
+            provider.retry_on_status = retry_statuses(codes).map_err(SdkError::into_pyerr)?;
+        }
+        if let Some(callback) = on_retry {
+            provider.retry_hook = provider
+                .retry_hook
+                .with_callback(retry_callback(callback).map_err(SdkError::into_pyerr)?);
+        }
+        if let Some(secs) = total_timeout {
+            provider.total_timeout =
+                Some(positive_secs("total_timeout", secs).map_err(SdkError::into_pyerr)?);
+        }
+        if circuit_breaker_threshold.is_some() || circuit_breaker_cooldown.is_some() {
+            provider.circuit = circuit_breaker(
+                &provider.circuit,
+                circuit_breaker_threshold,
+                circuit_breaker_cooldown,
+            )
+            .map_err(SdkError::into_pyerr)?;
+        }
+        if max_requests_per_minute.is_some() || max_tokens_per_minute.is_some() {
+            provider.limiter = rate_limiter(
+                &provider.limiter,
+                max_requests_per_minute,
+                max_tokens_per_minute,
+            )
+            .map_err(SdkError::into_pyerr)?;
+        }
+        if let Some(max) = max_concurrency {
+            provider.concurrency =
+                concurrency_limit(&provider.concurrency, max).map_err(SdkError::into_pyerr)?;
+        }
+        if let Some(enabled) = idempotency_keys {
+            provider.idempotency_keys = enabled;
+        }
+        if let Some(secs) = stream_idle_timeout {
+            provider.stream_idle_timeout =
+                positive_secs("stream_idle_timeout", secs).map_err(SdkError::into_pyerr)?;
+        }
+        let tls_given = ca_cert.is_some()
+            || client_cert.is_some()
+            || client_key.is_some()
+            || insecure_skip_tls_verify.is_some();
+        if tls_given {
+            let tls = &self.tls;
+            provider.tls = TlsConfig::load(
+                ca_cert.or_else(|| tls.ca_cert.clone()),
+                client_cert.or_else(|| tls.client_cert.clone()),
+                client_key.or_else(|| tls.client_key.clone()),
+                insecure_skip_tls_verify.unwrap_or(tls.insecure_skip_tls_verify),
+            )
+            .map_err(SdkError::into_pyerr)?;
+        }
+        if tls_given || connect_timeout.is_some() {
+            provider.client = shared_client(provider.connect_timeout, &provider.tls)?;
+        }
+        if let Some(headers) = default_headers {
+            provider.default_headers =
+                parse_default_headers(headers).map_err(SdkError::into_pyerr)?;
+        }
+        Ok(provider)
+    }
+
+    /// Providers are equal when they share a base URL, model, API key, and
+    /// routing, whatever their timeouts and retry settings.
+    fn __eq__(&self, other: PyRef<'_, Provider>) -> bool {
+        self.identity() == other.identity()
+    }
+
+    fn __hash__(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.identity().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Constructor arguments that rebuild this provider, used by ``pickle``.
+    ///
+    /// They include the API key, so a pickled provider carries it in plain
+    /// text. ``stream_reconnects`` has no argument and is read from the
+    /// environment again when unpickling, TLS files are read again from
+    /// their paths, and ``on_retry`` and the usage tracker are dropped.
+    fn __getnewargs_ex__<'py>(&self, py: Python<'py>) -> PyResult<((String,), Bound<'py, PyDict>)> {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("fallback_models", &self.fallback_models)?;
+        kwargs.set_item("api_key", &self.api_key)?;
+        kwargs.set_item("base_url", &self.base_url)?;
+        kwargs.set_item("request_timeout", self.request_timeout.as_secs_f64())?;
+        kwargs.set_item("connect_timeout", self.connect_timeout.as_secs_f64())?;
+        kwargs.set_item("max_retries", self.max_retries)?;
+        kwargs.set_item("retry_backoff_ms", self.retry_backoff.as_millis())?;
+        kwargs.set_item("max_retry_delay_ms", self.max_retry_delay.as_millis())?;
+        kwargs.set_item("retry_on_status", &self.retry_on_status)?;
+        kwargs.set_item("idempotency_keys", self.idempotency_keys)?;
+        kwargs.set_item("circuit_breaker_threshold", self.circuit.threshold())?;
+        kwargs.set_item(
+            "circuit_breaker_cooldown",
+            self.circuit.cooldown().as_secs_f64(),
+        )?;
+        kwargs.set_item(
+            "max_requests_per_minute",
+            self.limiter.requests_per_minute(),
+        )?;
+        kwargs.set_item("max_tokens_per_minute", self.limiter.tokens_per_minute())?;
+        kwargs.set_item("max_concurrency", self.concurrency.max())?;
+        kwargs.set_item(
+            "total_timeout",
+            self.total_timeout.map(|total| total.as_secs_f64()),
+        )?;
+        kwargs.set_item(
+            "stream_idle_timeout",
+            self.stream_idle_timeout.as_secs_f64(),
+        )?;
+        kwargs.set_item("ca_cert", &self.tls.ca_cert)?;
+        kwargs.set_item("client_cert", &self.tls.client_cert)?;
+        kwargs.set_item("client_key", &self.tls.client_key)?;
+        kwargs.set_item(
+            "insecure_skip_tls_verify",
+            self.tls.insecure_skip_tls_verify,
+        )?;
+        kwargs.set_item("default_headers", header_dict(&self.default_headers))?;
+        Ok(((self.model.clone(),), kwargs))
+    }
+
+    /// Routing the constructor arguments cannot express, restored by
+    /// :meth:`__setstate__` when unpickling an Azure or Anthropic provider.
+    fn __getstate__(&self) -> (String, Option<String>, &'static str, &'static str) {
+        (
+            self.routing.path_template.clone(),
+            self.routing.query.clone(),
+            self.routing.auth.name(),
+            self.routing.dialect.name(),
         )
     }
 
+    fn __setstate__(&mut self, state: (String, Option<String>, String, String)) -> PyResult<()> {
+        let (path_template, query, auth, dialect) = state;
+        let invalid = || SdkError::value("Invalid Provider state.").into_pyerr();
+        self.routing = Routing {
+            path_template,
+            query,
+            auth: AuthHeader::from_name(&auth).ok_or_else(invalid)?,
+            dialect: ApiDialect::from_name(&dialect).ok_or_else(invalid)?,
+        };
+        Ok(())
+    }
+
+    fn __copy__(&self) -> Self {
+        self.clone()
+    }
+
+    /// Settings are immutable, so a deep copy is a plain clone; it shares
+    /// this provider's connection pool.
+    fn __deepcopy__(&self, _memo: &Bound<'_, PyAny>) -> Self {
+        self.clone()
+    }
+
     fn __repr__(&self) -> String {
         format!(
             "Provider(model='{}', base_url='{}')",
@@ -601,39 +4051,417 @@ impl Provider {
 }
 
 impl Provider {
+    /// The fields `__eq__` and `__hash__` compare.
+    fn identity(&self) -> (&str, &str, &str, &Routing) {
+        (&self.base_url, &self.model, &self.api_key, &self.routing)
+    }
+
+    /// The URL generation requests are sent to.
+    pub(crate) fn request_url(&self) -> String {
+        self.routing
+            .endpoint_url(&self.base_url, &self.model, self.routing.dialect.endpoint())
+    }
+
+    /// `body`, a request body for `model` sent with `headers`, retargeted to
+    /// each of `fallback_models`. Each fallback gets a new `Idempotency-Key`,
+    /// even when the caller gave the primary's: it is a different request,
+    /// which a server must not answer from the primary's cached response.
+    pub(crate) fn fallbacks(&self, body: &Value, headers: &HeaderMap) -> Vec<Fallback> {
+        self.fallback_models
+            .iter()
+            .map(|model| {
+                let mut body = body.clone();
+                body["model"] = Value::String(model.clone());
+                let mut headers = headers.clone();
+                if headers.contains_key(IDEMPOTENCY_KEY)
+                    && let Ok(key) = HeaderValue::from_str(&new_idempotency_key())
+                {
+                    headers.insert(IDEMPOTENCY_KEY, key);
+                }
+                Fallback {
+                    model: model.clone(),
+                    url: self.routing.endpoint_url(
+                        &self.base_url,
+                        model,
+                        self.routing.dialect.endpoint(),
+                    ),
+                    body,
+                    headers,
+                }
+            })
+            .collect()
+    }
+
+    /// Headers sent with every request besides `Content-Type`: the
+    /// default headers, then the API key, if there is one, and any the
+    /// dialect requires, which replace default headers of the same name.
+    pub(crate) fn headers(&self) -> Result<HeaderMap, SdkError> {
+        let mut headers = self.default_headers.clone();
+        if let Some((_, key)) = self.routing.auth.header(&self.api_key) {
+            let mut value = HeaderValue::from_str(&key).map_err(|_| {
+                SdkError::value("The API key contains characters not allowed in a header.")
+            })?;
+            value.set_sensitive(true);
+            headers.insert(HeaderName::from_static(self.routing.auth.name()), value);
+        }
+        for &(name, value) in self.routing.dialect.extra_headers() {
+            headers.insert(
+                HeaderName::from_static(name),
+                HeaderValue::from_static(value),
+            );
+        }
+        // A key given for the call, in `default_headers`, is kept.
+        if self.idempotency_keys
+            && !headers.contains_key(IDEMPOTENCY_KEY)
+            && let Ok(key) = HeaderValue::from_str(&new_idempotency_key())
+        {
+            headers.insert(IDEMPOTENCY_KEY, key);
+        }
+        Ok(headers)
+    }
+
+    /// The HTTP client, rebuilt the first time it is used in a process
+    /// forked after it was built: the child must not share the parent's
+    /// pooled connections, whose I/O is driven by the parent's runtime.
+    /// Copies of the provider share the rebuilt client.
+    pub(crate) fn client(&self) -> Result<reqwest::Client, SdkError> {
+        let pid = process::id();
+        let mut client = self.client.lock().unwrap_or_else(PoisonError::into_inner);
+        if client.0 != pid {
+            *client = (pid, build_client(self.connect_timeout, &self.tls)?);
+        }
+        Ok(client.1.clone())
+    }
+
+    /// The timeout and retry settings calls are sent with.
+    pub(crate) fn retry_settings(&self) -> RetrySettings {
+        RetrySettings {
+            request_timeout: self.request_timeout,
+            max_retries: self.max_retries,
+            retry_backoff: self.retry_backoff,
+            max_retry_delay: self.max_retry_delay,
+            retry_on_status: self.retry_on_status.clone(),
+            total_timeout: self.total_timeout,
+            retry_hook: self.retry_hook.clone(),
+        }
+    }
+
+    /// `body` as sent in the provider's dialect. Asking OpenRouter to fall
+    /// back with `models` conflicts with client-side `fallback_models`.
+    pub(crate) fn request_body(&self, body: &ChatRequest) -> Result<Value, SdkError> {
+        if body.models.is_some() && !self.fallback_models.is_empty() {
+            return Err(SdkError::value(
+                "'models' cannot be combined with the provider's fallback_models; \
+                 pass one or the other.",
+            ));
+        }
+        let body = serde_json::to_value(body).map_err(|e| SdkError::runtime(e.to_string()))?;
+        match self.routing.dialect {
+            ApiDialect::OpenAi => Ok(body),
+            ApiDialect::Anthropic => anthropic::to_messages_request(body),
+        }
+    }
+
+    /// Read the `reconnect` kwarg as a number of reconnect attempts.
+    fn extract_reconnects(&self, reconnect: &Bound<'_, PyAny>) -> PyResult<u32> {
+        if let Ok(flag) = reconnect.cast::<PyBool>() {
+            return Ok(if flag.is_true() {
+                self.max_retries.max(1)
+            } else {
+                0
+            });
+        }
+        reconnect.extract().map_err(|_| {
+            SdkError::value("'reconnect' must be a bool or a non-negative integer.").into_pyerr()
+        })
+    }
+
+    /// This provider with a call's `total_timeout`, `retry`, `on_retry`,
+    /// `usage_tracker`, `idempotency_key` and `raise_on_content_filter`
+    /// kwargs applied, cloned only when they change something.
+    fn for_call(
+        &self,
+        total_timeout: Option<f64>,
+        retry: bool,
+        on_retry: Option<Bound<'_, PyAny>>,
+        usage_tracker: Option<UsageTracker>,
+        idempotency_key: Option<String>,
+        raise_on_content_filter: bool,
+    ) -> PyResult<Cow<'_, Self>> {
+        if total_timeout.is_none()
+            && retry
+            && on_retry.is_none()
+            && usage_tracker.is_none()
+            && idempotency_key.is_none()
+            && !raise_on_content_filter
+        {
+            return Ok(Cow::Borrowed(self));
+        }
+        let mut provider = self.clone();
+        provider.raise_on_content_filter = raise_on_content_filter;
+        if let Some(key) = idempotency_key {
+            let value = HeaderValue::from_str(&key)
+                .ok()
+                .filter(|_| !key.is_empty())
+                .ok_or_else(|| {
+                    SdkError::value(
+                        "'idempotency_key' must be a non-empty string of visible ASCII characters.",
+                    )
+                    .into_pyerr()
+                })?;
+            provider.default_headers.insert(IDEMPOTENCY_KEY, value);
+        }
+        if let Some(callback) = on_retry {
+            provider.retry_hook = self
+                .retry_hook
+                .with_callback(retry_callback(callback).map_err(SdkError::into_pyerr)?);
+        }
+        if let Some(tracker) = usage_tracker {
+            provider.usage_tracker = Some(tracker);
+        }
+        if let Some(secs) = total_timeout {
+            provider.total_timeout =
+                Some(positive_secs("total_timeout", secs).map_err(SdkError::into_pyerr)?);
+        }
+        if !retry {
+            provider.max_retries = 0;
+        }
+        Ok(Cow::Owned(provider))
+    }
+
+    /// Read the streaming-only kwargs of `stream_text` and `astream_text`
+    /// into `StreamSettings`, setting `n` on `params`.
+    #[expect(clippy::too_many_arguments)] // mirrors the Python-facing API surface
+    fn build_stream_settings(
+        &self,
+        params: &mut GenerationParams,
+        detailed: bool,
+        n: Option<u32>,
+        reconnect: Option<&Bound<'_, PyAny>>,
+        min_chunk_chars: Option<usize>,
+        split: &str,
+        client_stop: Option<Vec<String>>,
+        stop_regex: Option<&str>,
+        include_match: bool,
+        raw_event_callback: Option<Bound<'_, PyAny>>,
+    ) -> PyResult<StreamSettings> {
+        params.n = n;
+        params.validate().map_err(SdkError::into_pyerr)?;
+        let settings = StreamSettings {
+            // Choices arrive interleaved, so they can only be told apart by index.
+            detailed: detailed || n.is_some_and(|n| n > 1),
+            reconnects: match reconnect {
+                Some(reconnect) => self.extract_reconnects(reconnect)?,
+                None => self.stream_reconnects,
+            },
+            min_chunk_chars: min_chunk_chars.unwrap_or(0),
+            split: SplitMode::parse(split).map_err(SdkError::into_pyerr)?,
+            client_stop: client_stop.unwrap_or_default(),
+            stop_regex: stop_regex
+                .map(StopPattern::new)
+                .transpose()
+                .map_err(SdkError::into_pyerr)?,
+            include_match,
+            raw_event_callback: raw_event_callback
+                .map(|callback| {
+                    if callback.is_callable() {
+                        Ok(callback.unbind())
+                    } else {
+                        Err(
+                            SdkError::type_error("'raw_event_callback' must be callable.")
+                                .into_pyerr(),
+                        )
+                    }
+                })
+                .transpose()?,
+        };
+        if settings.client_stop.iter().any(String::is_empty) {
+            return Err(SdkError::value("'client_stop' sequences must not be empty.").into_pyerr());
+        }
+        let stops = !settings.client_stop.is_empty() || settings.stop_regex.is_some();
+        if settings.detailed && stops {
+            return Err(SdkError::value(
+                "'client_stop' and 'stop_regex' cannot be combined with detailed chunks or n > 1.",
+            )
+            .into_pyerr());
+        }
+        if include_match && !stops {
+            return Err(
+                SdkError::value("'include_match' requires 'client_stop' or 'stop_regex'.")
+                    .into_pyerr(),
+            );
+        }
+        if settings.detailed && settings.min_chunk_chars > 1 {
+            return Err(SdkError::value(
+                "'min_chunk_chars' cannot be combined with detailed chunks or n > 1.",
+            )
+            .into_pyerr());
+        }
+        if settings.detailed && settings.split != SplitMode::None {
+            return Err(SdkError::value(
+                "'split' cannot be combined with detailed chunks or n > 1.",
+            )
+            .into_pyerr());
+        }
+
+        Ok(settings)
+    }
+
     fn from_preset(
         model: String,
         api_key: Option<String>,
         base_url: &str,
         env_var: &str,
+        overrides: &RuntimeOverrides,
     ) -> PyResult<Self> {
         let env_api_key = std::env::var(env_var).ok();
-        let (api_key, base_url) =
-            resolve_provider_values(api_key, Some(base_url.to_string()), env_api_key).map_err(
-                |_| {
-                    SdkError::value(format!(
-                        "No api_key provided and {} environment variable is not set.",
-                        env_var
-                    ))
-                    .into_pyerr()
-                },
-            )?;
-        let runtime_config = resolve_runtime_config(
-            std::env::var(REQUEST_TIMEOUT_ENV).ok(),
-            std::env::var(CONNECT_TIMEOUT_ENV).ok(),
-            std::env::var(MAX_RETRIES_ENV).ok(),
-            std::env::var(RETRY_BACKOFF_ENV).ok(),
-        )
-        .map_err(SdkError::into_pyerr)?;
+        let (model, api_key, base_url) =
+            resolve_preset_values(model, api_key, base_url, env_var, env_api_key)
+                .map_err(SdkError::into_pyerr)?;
+        Self::from_parts(model, api_key, base_url, overrides, TlsConfig::default())
+    }
+
+    /// Build a provider from a registry entry.
+    fn from_registry(
+        preset: &Preset,
+        model: String,
+        api_key: Option<String>,
+        overrides: &RuntimeOverrides,
+    ) -> PyResult<Self> {
+        let provider =
+            Self::from_preset(model, api_key, preset.base_url, preset.env_var, overrides)?;
+        Ok(Self {
+            routing: Routing {
+                auth: preset.auth,
+                dialect: preset.dialect,
+                ..Routing::default()
+            },
+            ..provider
+        })
+    }
+
+    /// Build a provider, resolving its runtime settings from `overrides`
+    /// and the environment.
+    fn from_parts(
+        model: String,
+        api_key: String,
+        base_url: String,
+        overrides: &RuntimeOverrides,
+        tls: TlsConfig,
+    ) -> PyResult<Self> {
+        let runtime_config = overrides
+            .resolve(|name| std::env::var(name).ok())
+            .map_err(SdkError::into_pyerr)?;
 
         Ok(Self {
             api_key,
             base_url,
+            base_url_warning: None,
             model,
+            fallback_models: Vec::new(),
             request_timeout: runtime_config.request_timeout,
             connect_timeout: runtime_config.connect_timeout,
+            client: shared_client(runtime_config.connect_timeout, &tls)?,
             max_retries: runtime_config.max_retries,
             retry_backoff: runtime_config.retry_backoff,
+            max_retry_delay: runtime_config.max_retry_delay,
+            retry_on_status: DEFAULT_RETRY_STATUSES.to_vec(),
+            total_timeout: None,
+            retry_hook: RetryHook::default(),
+            circuit: CircuitBreaker::default(),
+            limiter: RateLimiter::default(),
+            concurrency: ConcurrencyLimit::default(),
+            usage_tracker: None,
+            idempotency_keys: true,
+            raise_on_content_filter: false,
+            stream_reconnects: runtime_config.stream_reconnects,
+            stream_idle_timeout: runtime_config
+                .stream_idle_timeout
+                .unwrap_or(runtime_config.request_timeout),
+            routing: Routing::default(),
+            tls,
+            default_headers: HeaderMap::new(),
+        })
+    }
+}
+
+/// Validate `headers` as HTTP header names and values, for the
+/// `default_headers` kwarg.
+pub fn parse_default_headers(
+    headers: impl IntoIterator<Item = (String, String)>,
+) -> Result<HeaderMap, SdkError> {
+    let mut parsed = HeaderMap::new();
+    for (name, value) in headers {
+        let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| {
+            SdkError::value(format!(
+                "Invalid header name '{}' in default_headers.",
+                name
+            ))
+        })?;
+        let header_value = HeaderValue::from_str(&value).map_err(|_| {
+            SdkError::value(format!(
+                "Invalid value for header '{}' in default_headers: only visible ASCII characters are allowed.",
+                name
+            ))
+        })?;
+        parsed.insert(header_name, header_value);
+    }
+    Ok(parsed)
+}
+
+/// `headers` as a `{name: value}` dict, for the getter and pickling.
+fn header_dict(headers: &HeaderMap) -> HashMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = String::from_utf8_lossy(value.as_bytes()).into_owned();
+            (name.as_str().to_string(), value)
         })
+        .collect()
+}
+
+/// Mask `api_key` down to its last four characters. The mask has a fixed
+/// width so the key's length is not revealed either.
+pub fn mask_api_key(api_key: &str) -> String {
+    let chars = api_key.chars().count();
+    if chars <= 4 {
+        return "****".to_string();
+    }
+    let visible: String = api_key.chars().skip(chars - 4).collect();
+    format!("****{}", visible)
+}
+
+/// `User-Agent` sent with every request unless `default_headers` sets one.
+pub const USER_AGENT: &str = concat!("rusty-agent-sdk/", env!("CARGO_PKG_VERSION"), " (python)");
+
+/// Build the HTTP client a `Provider` reuses for all of its requests.
+fn build_client(connect_timeout: Duration, tls: &TlsConfig) -> Result<reqwest::Client, SdkError> {
+    let builder = reqwest::Client::builder()
+        .connect_timeout(connect_timeout)
+        .user_agent(USER_AGENT);
+    tls.apply(builder)
+        .build()
+        .map_err(|e| SdkError::value(error_chain(&e)))
+}
+
+/// A client for `Provider.client`, marked as built by this process.
+fn shared_client(
+    connect_timeout: Duration,
+    tls: &TlsConfig,
+) -> PyResult<Arc<Mutex<(u32, reqwest::Client)>>> {
+    let client = build_client(connect_timeout, tls).map_err(SdkError::into_pyerr)?;
+    Ok(Arc::new(Mutex::new((process::id(), client))))
+}
+
+/// `error` followed by each of its sources, which hold the detail reqwest's
+/// TLS errors leave out of their own message.
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
     }
+    message
 }
@@ -0,0 +1,69 @@
+use pyo3::prelude::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Why a request is being retried, as passed to `on_retry`.
+pub enum RetryCause<'a> {
+    /// A response with a retryable status.
+    Status(u16),
+    /// A transport error, by its message.
+    Error(&'a str),
+}
+
+/// Counts a provider's retries and calls its `on_retry` callback before
+/// each wait. Copies of a provider share the counts.
+#[derive(Clone, Default)]
+pub struct RetryHook {
+    callback: Option<Arc<Py<PyAny>>>,
+    counts: Arc<RetryCounts>,
+}
+
+#[derive(Default)]
+struct RetryCounts {
+    retries: AtomicU64,
+    callback_errors: AtomicU64,
+}
+
+impl RetryHook {
+    /// This hook with `callback` in place of the current one, keeping the
+    /// counts.
+    pub fn with_callback(&self, callback: Py<PyAny>) -> Self {
+        Self {
+            callback: Some(Arc::new(callback)),
+            counts: Arc::clone(&self.counts),
+        }
+    }
+
+    pub fn callback(&self) -> Option<&Py<PyAny>> {
+        self.callback.as_deref()
+    }
+
+    /// Record retry number `attempt` (from 1), made after waiting `delay`
+    /// because of `cause`. Exceptions the callback raises are counted, not
+    /// raised, so it cannot break the request.
+    pub fn notify(&self, attempt: u32, cause: RetryCause<'_>, delay: Duration) {
+        self.counts.retries.fetch_add(1, Ordering::Relaxed);
+        let Some(callback) = &self.callback else {
+            return;
+        };
+        let delay = delay.as_secs_f64();
+        Python::try_attach(|py| {
+            let result = match cause {
+                RetryCause::Status(status) => callback.call1(py, (attempt, status, delay)),
+                RetryCause::Error(message) => callback.call1(py, (attempt, message, delay)),
+            };
+            if result.is_err() {
+                self.counts.callback_errors.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+    }
+
+    pub fn retries(&self) -> u64 {
+        self.counts.retries.load(Ordering::Relaxed)
+    }
+
+    pub fn callback_errors(&self) -> u64 {
+        self.counts.callback_errors.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,48 @@
+use crate::errors::SdkError;
+use std::future::Future;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use tokio::runtime::Runtime;
+
+/// The Tokio runtime every request runs on, built on first use, and the id
+/// of the process that built it. Building a multi-threaded runtime per call
+/// costs worker threads and an I/O driver each time; sharing one keeps that
+/// to once per process.
+///
+/// A child forked from a process that made requests inherits the runtime
+/// without its worker threads, and its I/O driver's epoll instance is
+/// shared with the parent, so the child builds its own. The inherited one
+/// is leaked: dropping it would wait on threads the child does not have.
+static RUNTIME: Mutex<Option<(u32, &'static Runtime)>> = Mutex::new(None);
+
+/// How many times a runtime has been built.
+static BUILT: AtomicUsize = AtomicUsize::new(0);
+
+/// The shared runtime, building it on the first call in each process.
+pub fn get() -> Result<&'static Runtime, SdkError> {
+    let pid = process::id();
+    let mut shared = RUNTIME.lock().unwrap_or_else(PoisonError::into_inner);
+    match *shared {
+        Some((built_in, runtime)) if built_in == pid => Ok(runtime),
+        _ => {
+            let runtime = Runtime::new().map_err(|e| SdkError::runtime(e.to_string()))?;
+            let runtime: &'static Runtime = Box::leak(Box::new(runtime));
+            BUILT.fetch_add(1, Ordering::Relaxed);
+            *shared = Some((pid, runtime));
+            Ok(runtime)
+        }
+    }
+}
+
+/// Run `future` to completion on the calling thread, using the shared
+/// runtime's drivers. Any number of threads may block at once, but not a
+/// runtime thread.
+pub fn block_on<F: Future>(future: F) -> Result<F::Output, SdkError> {
+    Ok(get()?.block_on(future))
+}
+
+/// How many runtimes have been built: one per process that made requests.
+pub fn runtimes_built() -> usize {
+    BUILT.load(Ordering::Relaxed)
+}
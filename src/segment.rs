@@ -0,0 +1,315 @@
+use crate::errors::SdkError;
+use regex::Regex;
+use regex_automata::Anchored;
+use regex_automata::dfa::{Automaton, StartKind, dense};
+use regex_automata::util::start;
+
+/// Accepted values for the `split` argument of `stream_text`.
+pub const SPLIT_MODES: [&str; 3] = ["none", "line", "sentence"];
+
+/// Words that end with a period without ending a sentence. Compared
+/// lowercased, without the period.
+const ABBREVIATIONS: [&str; 18] = [
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "etc", "inc", "ltd", "co", "no",
+    "fig", "approx", "dept", "est",
+];
+
+/// Characters that may follow a sentence terminator before the whitespace,
+/// e.g. the quote in `"Stop." She left.`
+const SENTENCE_CLOSERS: [char; 5] = ['"', '\'', ')', ']', '\u{201d}'];
+
+/// How `stream_text` re-segments streamed text.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SplitMode {
+    /// Yield text as the provider sends it.
+    #[default]
+    None,
+    /// Yield complete lines, including their `\n`.
+    Line,
+    /// Yield complete sentences, including the whitespace after them.
+    Sentence,
+}
+
+impl SplitMode {
+    pub fn parse(value: &str) -> Result<Self, SdkError> {
+        match value {
+            "none" => Ok(Self::None),
+            "line" => Ok(Self::Line),
+            "sentence" => Ok(Self::Sentence),
+            _ => Err(SdkError::value(format!(
+                "'split' must be one of {}, got '{}'.",
+                SPLIT_MODES.join(", "),
+                value
+            ))),
+        }
+    }
+}
+
+/// Buffers streamed text and cuts it into lines or sentences.
+///
+/// Segments concatenate back to the input: nothing is trimmed. Incomplete
+/// tails are held until more text arrives or `finish` is called.
+#[derive(Debug)]
+pub struct Segmenter {
+    mode: SplitMode,
+    buffer: String,
+}
+
+impl Segmenter {
+    pub fn new(mode: SplitMode) -> Self {
+        Self {
+            mode,
+            buffer: String::new(),
+        }
+    }
+
+    /// Append `text` and return the segments it completes.
+    pub fn push(&mut self, text: &str) -> Vec<String> {
+        if self.mode == SplitMode::None {
+            return vec![text.to_string()];
+        }
+
+        self.buffer.push_str(text);
+        let mut segments = Vec::new();
+        while let Some(end) = self.next_boundary() {
+            let rest = self.buffer.split_off(end);
+            segments.push(std::mem::replace(&mut self.buffer, rest));
+        }
+        segments
+    }
+
+    /// The held-back tail, if any.
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.buffer);
+        (!rest.is_empty()).then_some(rest)
+    }
+
+    /// Byte offset just past the first complete segment in the buffer.
+    fn next_boundary(&self) -> Option<usize> {
+        match self.mode {
+            SplitMode::None => (!self.buffer.is_empty()).then_some(self.buffer.len()),
+            SplitMode::Line => self.buffer.find('\n').map(|pos| pos + 1),
+            SplitMode::Sentence => sentence_boundary(&self.buffer),
+        }
+    }
+}
+
+/// End of the first sentence in `text`, after the whitespace following it.
+///
+/// A sentence ends at a newline, or at `.`, `!` or `?` (plus any closing
+/// quotes or brackets) followed by whitespace. A period after a known
+/// abbreviation, an initial, a dotted abbreviation such as `e.g` or a list
+/// number such as `1` does not end a sentence.
+fn sentence_boundary(text: &str) -> Option<usize> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let (pos, c) = chars[i];
+        let ends_sentence = if c == '\n' {
+            i += 1;
+            true
+        } else if matches!(c, '.' | '!' | '?') {
+            i += 1;
+            while i < chars.len()
+                && (matches!(chars[i].1, '.' | '!' | '?') || SENTENCE_CLOSERS.contains(&chars[i].1))
+            {
+                i += 1;
+            }
+            // Whether the sentence ended depends on what comes next.
+            if i == chars.len() {
+                return None;
+            }
+            chars[i].1.is_whitespace() && !(c == '.' && is_abbreviation(&text[..pos]))
+        } else {
+            i += 1;
+            false
+        };
+
+        if ends_sentence {
+            while i < chars.len() && chars[i].1.is_whitespace() {
+                i += 1;
+            }
+            return Some(chars.get(i).map_or(text.len(), |&(pos, _)| pos));
+        }
+    }
+    None
+}
+
+/// Whether the word before a period is an abbreviation rather than the end
+/// of a sentence.
+fn is_abbreviation(before: &str) -> bool {
+    let word = before
+        .rsplit(char::is_whitespace)
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase();
+    let mut chars = word.chars();
+    let is_initial = matches!((chars.next(), chars.next()), (Some(c), None) if c.is_alphabetic());
+    let is_list_number = !word.is_empty() && word.chars().all(|c| c.is_ascii_digit());
+    is_initial || is_list_number || word.contains('.') || ABBREVIATIONS.contains(&word.as_str())
+}
+
+/// A compiled `stop_regex` pattern.
+///
+/// `regex` finds matches; an anchored DFA of the same pattern tells whether
+/// a match could still start at a position once more text arrives.
+#[derive(Clone, Debug)]
+pub struct StopPattern {
+    regex: Regex,
+    dfa: dense::DFA<Vec<u32>>,
+}
+
+impl StopPattern {
+    pub fn new(pattern: &str) -> Result<Self, SdkError> {
+        let invalid = |e: &dyn std::fmt::Display| {
+            SdkError::value(format!("'stop_regex' is not a valid pattern: {}", e))
+        };
+        let regex = Regex::new(pattern).map_err(|e| invalid(&e))?;
+        if regex.is_match("") {
+            return Err(SdkError::value(
+                "'stop_regex' must not match an empty string.",
+            ));
+        }
+        let dfa = dense::Builder::new()
+            .configure(
+                dense::Config::new()
+                    .start_kind(StartKind::Anchored)
+                    .unicode_word_boundary(true),
+            )
+            .build(pattern)
+            .map_err(|e| invalid(&e))?;
+        Ok(Self { regex, dfa })
+    }
+
+    /// Whether a match starting at byte `start` of `text` could still
+    /// complete. Positions where the DFA gives up count as possible.
+    fn may_start_at(&self, text: &str, start: usize) -> bool {
+        let config = start::Config::new()
+            .anchored(Anchored::Yes)
+            .look_behind(start.checked_sub(1).map(|i| text.as_bytes()[i]));
+        let Ok(mut state) = self.dfa.start_state(&config) else {
+            return true;
+        };
+        for &byte in &text.as_bytes()[start..] {
+            state = self.dfa.next_state(state, byte);
+            if self.dfa.is_dead_state(state) {
+                return false;
+            }
+            if self.dfa.is_quit_state(state) {
+                return true;
+            }
+        }
+        true
+    }
+}
+
+/// Watches streamed text for client-side stop sequences and a stop pattern.
+///
+/// Text is held back while it could be the start of a match split across
+/// chunks: up to the longest sequence minus one character, and from the
+/// first position where the pattern could still match.
+#[derive(Debug)]
+pub struct StopScanner {
+    stops: Vec<String>,
+    longest: usize,
+    pattern: Option<StopPattern>,
+    include_match: bool,
+    /// Received text, from just before the first byte not yet emitted, so
+    /// look-behind assertions still see the preceding character.
+    text: String,
+    emitted: usize,
+    stopped: bool,
+}
+
+impl StopScanner {
+    pub fn new(stops: Vec<String>, pattern: Option<StopPattern>, include_match: bool) -> Self {
+        let longest = stops.iter().map(|stop| stop.chars().count()).max();
+        Self {
+            stops,
+            longest: longest.unwrap_or_default(),
+            pattern,
+            include_match,
+            text: String::new(),
+            emitted: 0,
+            stopped: false,
+        }
+    }
+
+    /// Append `text` and return what can be emitted. Once a match is found,
+    /// the text before it (and the match itself with `include_match`) is
+    /// returned and everything after it, including later pushes, is dropped.
+    pub fn push(&mut self, text: &str) -> String {
+        if self.stopped {
+            return String::new();
+        }
+        self.text.push_str(text);
+
+        let literal = self.stops.iter().filter_map(|stop| {
+            let start = self.emitted + self.text[self.emitted..].find(stop.as_str())?;
+            Some((start, start + stop.len()))
+        });
+        let pattern = self.pattern.as_ref().and_then(|pattern| {
+            let found = pattern.regex.find_at(&self.text, self.emitted)?;
+            Some((found.start(), found.end()))
+        });
+        if let Some((start, end)) = literal.chain(pattern).min() {
+            self.stopped = true;
+            let cut = if self.include_match { end } else { start };
+            let out = self.emit(cut);
+            self.text.truncate(self.emitted);
+            return out;
+        }
+
+        let safe = self.literal_safe_end().min(self.pattern_safe_end());
+        self.emit(safe)
+    }
+
+    /// Whether a stop sequence or the stop pattern has matched.
+    pub fn stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// The held-back tail, if any.
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = self.emit(self.text.len());
+        (!rest.is_empty()).then_some(rest)
+    }
+
+    /// Return the text up to byte `end` and drop what is no longer needed.
+    fn emit(&mut self, end: usize) -> String {
+        let out = self.text[self.emitted..end].to_string();
+        // Keep one character before the unemitted text for look-behind.
+        let keep_from = self.text[..end]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(pos, _)| pos);
+        self.text.drain(..keep_from);
+        self.emitted = end - keep_from;
+        out
+    }
+
+    /// Start of the last `longest - 1` characters.
+    fn literal_safe_end(&self) -> usize {
+        match self.longest.saturating_sub(1) {
+            0 => self.text.len(),
+            keep => self.text[self.emitted..]
+                .char_indices()
+                .rev()
+                .nth(keep - 1)
+                .map_or(self.emitted, |(pos, _)| self.emitted + pos),
+        }
+    }
+
+    /// First position where the pattern could still start a match.
+    fn pattern_safe_end(&self) -> usize {
+        let Some(pattern) = &self.pattern else {
+            return self.text.len();
+        };
+        self.text[self.emitted..]
+            .char_indices()
+            .map(|(pos, _)| self.emitted + pos)
+            .find(|&start| pattern.may_start_at(&self.text, start))
+            .unwrap_or(self.text.len())
+    }
+}
@@ -1,48 +1,224 @@
-use crate::errors::SdkError;
-use crate::http::{is_retryable_error, is_retryable_status, retry_delay};
+use crate::anthropic::StreamTranslator;
+use crate::chat::ChatTurn;
+use crate::circuit::CircuitPermit;
+use crate::errors::{ApiError, SdkError};
+use crate::http::{
+    CANCEL_POLL_INTERVAL, Deadline, RetrySettings, RetryTally, attempt_timeout,
+    connect_with_retries, idempotency_key, is_retryable_status, rate_limit, request_id,
+    retry_after, sleep_with_cancellation, transport_cause, wait_to_retry,
+};
+use crate::limiter::{
+    ConcurrencyLimit, RateLimiter, Reservation, estimate_request_tokens, wait_within,
+};
 use crate::models::{
-    ChatRequest, GenerationParams, StreamEvent, StreamMetadata, api_error_message, parse_sse_event,
+    ChatRequest, GenerationParams, SseLineBuffer, StreamChunkData, StreamEvent, StreamMetadata,
+    StreamTimings, api_error, content_filtered, is_sse_comment, parse_sse_chunks, parse_sse_event,
+    sse_event_id,
 };
-use crate::provider::{Provider, build_chat_completions_url};
+use crate::provider::{
+    ApiDialect, Fallback, Provider, annotations_to_py, json_to_py, rate_limit_to_py,
+};
+use crate::retry_hook::RetryCause;
+use crate::runtime;
+use crate::segment::{Segmenter, SplitMode, StopPattern, StopScanner};
+use crate::usage::UsageTracker;
 use futures_util::StreamExt;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::VecDeque;
 use std::sync::Arc;
-use std::sync::Mutex;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
 use std::thread::JoinHandle;
-use std::time::Duration;
-use tokio::time::{Instant, sleep, timeout};
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc::{Receiver, Sender, channel};
+use tokio::time::{Instant, timeout};
 
 const STREAM_CHANNEL_CAPACITY: usize = 128;
-const STREAM_CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Longest time coalesced text is held back before it is sent anyway.
+const COALESCE_MAX_DELAY: Duration = Duration::from_millis(100);
 
 struct StreamWorkerConfig {
+    model: String,
     url: String,
-    api_key: String,
-    body: ChatRequest,
-    request_timeout: Duration,
-    connect_timeout: Duration,
-    max_retries: u32,
-    retry_backoff: Duration,
+    headers: HeaderMap,
+    body: Value,
+    /// Tried in turn when connecting fails with an error another model may
+    /// not hit.
+    fallbacks: Vec<Fallback>,
+    /// Set for dialects whose events are translated before parsing.
+    translator: Option<StreamTranslator>,
+    idle_timeout: Duration,
+    client: reqwest::Client,
+    retries: RetrySettings,
+    /// Settled once the response starts or the request fails.
+    circuit: Option<CircuitPermit>,
+    limiter: RateLimiter,
+    /// Reserved from the limiter's token bucket before the request.
+    estimated_tokens: u64,
+    concurrency: ConcurrencyLimit,
+    usage_tracker: Option<UsageTracker>,
+    idempotency_key: Option<String>,
     cancel_flag: Arc<AtomicBool>,
-    metadata: Option<Arc<Mutex<Option<StreamMetadata>>>>,
+    metadata: Arc<Mutex<Option<StreamMetadata>>>,
+    reasoning: Arc<Mutex<String>>,
+    partial: Arc<Mutex<String>>,
+    last_event_id: Arc<Mutex<Option<String>>>,
+    detailed: bool,
+    max_reconnects: u32,
+    min_chunk_chars: usize,
+    split: SplitMode,
+    stop: Option<StopScanner>,
+    raw_event_hook: Option<RawEventHook>,
+}
+
+/// Calls `raw_event_callback` from the worker.
+struct RawEventHook {
+    callback: Py<PyAny>,
+    errors: Arc<AtomicUsize>,
+}
+
+impl RawEventHook {
+    /// Pass `event` to the callback. Exceptions are counted, not raised, so
+    /// the hook cannot break the stream.
+    fn call(&self, event: &str) {
+        Python::try_attach(|py| {
+            if self.callback.call1(py, (event,)).is_err() {
+                self.errors.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+    }
+}
+
+/// Wait for a worker thread. The GIL is released first, as a worker
+/// calling `raw_event_callback` needs it to finish.
+fn join_worker(handle: JoinHandle<()>) {
+    Python::try_attach(|py| {
+        py.detach(|| {
+            let _ = handle.join();
+        })
+    });
+}
+
+/// What the worker sends for each chunk.
+#[derive(Clone)]
+pub(crate) enum StreamItem {
+    Text(String),
+    Chunk(StreamChunkData),
+}
+
+impl StreamItem {
+    /// Text counted towards `text()` and `Chat` history: only the first
+    /// choice's, so `n > 1` streams are not merged.
+    fn text(&self) -> &str {
+        match self {
+            Self::Text(text) => text,
+            Self::Chunk(chunk) if chunk.index == 0 => &chunk.text,
+            Self::Chunk(_) => "",
+        }
+    }
+
+    fn into_py(self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        match self {
+            Self::Text(text) => Ok(text.into_pyobject(py)?.into_any().unbind()),
+            Self::Chunk(data) => Ok(Py::new(py, StreamChunk { data })?.into_any()),
+        }
+    }
+}
+
+impl<'py> IntoPyObject<'py> for StreamItem {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> PyResult<Self::Output> {
+        self.into_py(py).map(|item| item.into_bound(py))
+    }
+}
+
+/// A chunk yielded by ``stream_text(detailed=True)``.
+///
+/// One is yielded per streamed payload, including payloads without text
+/// such as the final one carrying ``finish_reason``.
+#[pyclass(frozen)]
+pub struct StreamChunk {
+    data: StreamChunkData,
+}
+
+#[pymethods]
+impl StreamChunk {
+    /// The text delta, or ``""`` if the payload carried none.
+    #[getter]
+    fn text(&self) -> &str {
+        &self.data.text
+    }
+
+    /// The choice index the payload belongs to.
+    #[getter]
+    fn index(&self) -> u32 {
+        self.data.index
+    }
+
+    /// The reason generation stopped; usually only set on the last chunk.
+    #[getter]
+    fn finish_reason(&self) -> Option<&str> {
+        self.data.finish_reason.as_deref()
+    }
+
+    /// The full parsed payload as a dict.
+    #[getter]
+    fn raw(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        json_to_py(py, &self.data.raw)
+    }
+
+    fn __str__(&self) -> &str {
+        &self.data.text
+    }
+
+    fn __repr__(&self) -> String {
+        let finish_reason = self
+            .data
+            .finish_reason
+            .as_deref()
+            .map_or_else(|| "None".to_string(), |reason| format!("'{}'", reason));
+        format!(
+            "StreamChunk(text='{}', index={}, finish_reason={})",
+            self.data.text, self.data.index, finish_reason
+        )
+    }
 }
 
 /// An iterator that yields text chunks from a streaming LLM response.
 #[pyclass]
 pub struct TextStream {
-    receiver: Mutex<Receiver<Result<String, SdkError>>>,
+    receiver: Mutex<Option<Receiver<Result<StreamItem, SdkError>>>>,
     cancel_flag: Arc<AtomicBool>,
     handle: Option<JoinHandle<()>>,
-    metadata: Option<Arc<Mutex<Option<StreamMetadata>>>>,
+    metadata: Arc<Mutex<Option<StreamMetadata>>>,
+    reasoning: Arc<Mutex<String>>,
+    partial: Arc<Mutex<String>>,
+    last_event_id: Arc<Mutex<Option<String>>>,
+    turn: Mutex<Option<ChatTurn>>,
+    collected: Mutex<Option<String>>,
+    error: Mutex<Option<PyErr>>,
+    exhausted: AtomicBool,
+    teed: AtomicBool,
+    callback_errors: Arc<AtomicUsize>,
+    idempotency_key: Option<String>,
 }
 
 impl Drop for TextStream {
     fn drop(&mut self) {
+        // After `tee()` the branches own the worker.
+        if self.teed.load(Ordering::Relaxed) {
+            return;
+        }
         self.cancel_flag.store(true, Ordering::Relaxed);
         if let Some(handle) = self.handle.take() {
-            let _ = handle.join();
+            join_worker(handle);
         }
     }
 }
@@ -53,37 +229,287 @@ impl TextStream {
         slf
     }
 
-    fn __next__(&self) -> Option<PyResult<String>> {
-        let receiver = match self.receiver.lock() {
-            Ok(receiver) => receiver,
-            Err(_) => {
-                return Some(Err(SdkError::runtime(
-                    "Internal stream state is unavailable.",
-                )
-                .into_pyerr()));
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Cancel the stream and wait for the worker thread to exit.
+    ///
+    /// Exceptions raised in the ``with`` body are never suppressed.
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<&Bound<'_, PyAny>>,
+        _exc_value: Option<&Bound<'_, PyAny>>,
+        _traceback: Option<&Bound<'_, PyAny>>,
+    ) -> bool {
+        self.close();
+        false
+    }
+
+    fn __next__(&self, py: Python<'_>) -> Option<PyResult<Py<PyAny>>> {
+        Some(self.next_item(py)?.and_then(|item| item.into_py(py)))
+    }
+
+    /// Drain the remaining chunks and return them as one string.
+    ///
+    /// Chunks already consumed by iteration are not included. Once the stream
+    /// is drained the metadata getters are populated, and an error from the
+    /// stream is raised here. Later calls return the same text.
+    fn text(&self, py: Python<'_>) -> PyResult<String> {
+        if let Ok(collected) = self.collected.lock()
+            && let Some(text) = collected.as_ref()
+        {
+            return Ok(text.clone());
+        }
+
+        let mut text = String::new();
+        while let Some(item) = self.next_item(py) {
+            text.push_str(item?.text());
+        }
+        if let Ok(mut collected) = self.collected.lock() {
+            *collected = Some(text.clone());
+        }
+        Ok(text)
+    }
+
+    /// Drain the remaining chunks into a writable object.
+    ///
+    /// Each chunk is passed to ``fileobj.write()`` as it arrives, so long
+    /// replies are never held in memory. ``fileobj.flush()`` is called, if
+    /// present, every ``flush_every`` chunks and once at the end; with
+    /// ``flush_every=0`` only at the end. Chunks already consumed by
+    /// iteration are not written. Once the stream is drained the metadata
+    /// getters are populated.
+    ///
+    /// Returns the number of characters written. An error from the writer
+    /// cancels the stream and is raised here, as is a stream error. Raises
+    /// ``RuntimeError`` if the stream already ended, failed, was cancelled,
+    /// or was drained by ``text()``.
+    #[pyo3(signature = (fileobj, flush_every=1))]
+    #[pyo3(text_signature = "(self, fileobj, flush_every=1)")]
+    fn write_to(
+        &self,
+        py: Python<'_>,
+        fileobj: &Bound<'_, PyAny>,
+        flush_every: usize,
+    ) -> PyResult<usize> {
+        self.check_unconsumed()?;
+        let flush = fileobj.getattr_opt("flush")?;
+
+        let mut written = 0;
+        let mut chunks = 0;
+        while let Some(item) = self.next_item(py) {
+            let item = item?;
+            let text = item.text();
+            if text.is_empty() {
+                continue;
             }
-        };
+            let result = fileobj.call_method1("write", (text,)).and_then(|_| {
+                chunks += 1;
+                match &flush {
+                    Some(flush) if flush_every > 0 && chunks % flush_every == 0 => {
+                        flush.call0().map(drop)
+                    }
+                    _ => Ok(()),
+                }
+            });
+            if let Err(err) = result {
+                self.cancel();
+                return Err(err);
+            }
+            written += text.chars().count();
+        }
+        if let Some(flush) = flush {
+            flush.call0()?;
+        }
+        Ok(written)
+    }
+
+    /// Split the stream into ``n`` independent iterators.
+    ///
+    /// Every branch yields every remaining chunk of the single underlying
+    /// response, so no second request is made. Each branch buffers the
+    /// chunks it has not consumed yet; once any branch holds ``max_buffer``
+    /// of them, the others wait for it to catch up and the worker stops
+    /// reading, so branches must be consumed concurrently (e.g. in threads)
+    /// when they drift further apart than that. Stream errors are raised in
+    /// every branch.
+    ///
+    /// This stream can no longer be iterated afterwards; its metadata
+    /// getters keep working. Raises ``RuntimeError`` if the stream already
+    /// ended, failed, was cancelled, or was drained.
+    #[pyo3(signature = (n=2, *, max_buffer=1024))]
+    #[pyo3(text_signature = "(self, n=2, *, max_buffer=1024)")]
+    fn tee(&mut self, n: usize, max_buffer: usize) -> PyResult<Vec<StreamBranch>> {
+        if n == 0 {
+            return Err(SdkError::value("'n' must be greater than zero.").into_pyerr());
+        }
+        if max_buffer == 0 {
+            return Err(SdkError::value("'max_buffer' must be greater than zero.").into_pyerr());
+        }
+        self.check_unconsumed()?;
+        let receiver = self
+            .receiver
+            .lock()
+            .ok()
+            .and_then(|mut receiver| receiver.take());
+        let turn = self.turn.lock().ok().and_then(|mut turn| turn.take());
+        self.teed.store(true, Ordering::Relaxed);
 
-        match receiver.recv() {
-            Ok(Ok(chunk)) => Some(Ok(chunk)),
-            Ok(Err(err)) => Some(Err(err.into_pyerr())),
-            Err(_) => None,
+        let shared = Arc::new(TeeShared {
+            state: Mutex::new(TeeState {
+                receiver,
+                queues: vec![Some(VecDeque::new()); n],
+                end: None,
+                reading: false,
+                turn,
+            }),
+            changed: Condvar::new(),
+            cancel_flag: Arc::clone(&self.cancel_flag),
+            handle: self.handle.take(),
+            max_buffer,
+        });
+        Ok((0..n)
+            .map(|index| StreamBranch {
+                shared: Arc::clone(&shared),
+                index,
+                error: Mutex::new(None),
+            })
+            .collect())
+    }
+
+    /// Stop the stream. Later iteration raises ``StopIteration``.
+    ///
+    /// Closes the channel so the background worker exits within one poll
+    /// interval even if it is blocked on a full buffer. An unfinished
+    /// ``Chat.stream`` reply is not recorded. A stream that already failed
+    /// keeps raising its error. Safe to call more than once.
+    pub(crate) fn cancel(&self) {
+        self.cancel_flag.store(true, Ordering::Relaxed);
+        if let Ok(mut receiver) = self.receiver.lock() {
+            receiver.take();
+        }
+        if let Ok(mut turn) = self.turn.lock() {
+            turn.take();
         }
     }
 
+    /// All text parsed from the response so far.
+    ///
+    /// Includes chunks the worker has parsed but not yet yielded, and stays
+    /// available after the stream fails, e.g. for logging or resuming.
+    #[getter]
+    fn partial_text(&self) -> String {
+        self.partial
+            .lock()
+            .map(|text| text.clone())
+            .unwrap_or_default()
+    }
+
+    /// The ``id`` of the last SSE event received, or ``None``.
+    ///
+    /// Sent as ``Last-Event-ID`` when a stream created with ``reconnect``
+    /// reopens after a mid-response failure.
+    #[getter]
+    fn last_event_id(&self) -> Option<String> {
+        self.last_event_id.lock().ok()?.clone()
+    }
+
+    /// The exception the stream failed with, or ``None``.
+    ///
+    /// ``None`` while the stream is running and after it ends cleanly.
+    #[getter]
+    fn error(&self, py: Python<'_>) -> Option<PyErr> {
+        let error = self.error.lock().ok()?;
+        error.as_ref().map(|error| error.clone_ref(py))
+    }
+
+    /// The response ``id`` sent by the provider, or ``None``.
+    #[getter]
+    fn response_id(&self) -> Option<String> {
+        self.flat_metadata(|m| m.id.clone())
+    }
+
+    /// The provider's request id from the ``x-request-id`` (or
+    /// ``request-id``, ``openrouter-id``) response header, or ``None``.
+    #[getter]
+    fn request_id(&self) -> Option<String> {
+        self.flat_metadata(|m| m.request_id.clone())
+    }
+
+    /// The ``Idempotency-Key`` header the request was sent with, or
+    /// ``None`` if keys are disabled. Once a fallback model has started
+    /// the stream, the key of its request.
+    #[getter]
+    fn idempotency_key(&self) -> Option<String> {
+        self.flat_metadata(|m| m.idempotency_key.clone())
+            .or_else(|| self.idempotency_key.clone())
+    }
+
+    /// The provider's rate-limit headers as a dict of
+    /// ``remaining_requests``, ``remaining_tokens``, ``reset_requests`` and
+    /// ``reset_tokens`` (seconds), or ``None`` until the stream starts.
+    #[getter]
+    fn rate_limit<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        self.flat_metadata(|m| m.rate_limit)
+            .map(|limit| rate_limit_to_py(py, &limit))
+            .transpose()
+    }
+
+    /// The Unix timestamp the provider reports the response was created
+    /// at, or ``None``.
+    #[getter]
+    fn created(&self) -> Option<u64> {
+        self.flat_metadata(|m| m.created)
+    }
+
     #[getter]
     fn prompt_tokens(&self) -> Option<u64> {
-        self.flat_metadata(|m| m.usage.as_ref().map(|u| u.prompt_tokens))
+        self.flat_metadata(|m| m.usage.as_ref().and_then(|u| u.prompt_tokens))
     }
 
     #[getter]
     fn completion_tokens(&self) -> Option<u64> {
-        self.flat_metadata(|m| m.usage.as_ref().map(|u| u.completion_tokens))
+        self.flat_metadata(|m| m.usage.as_ref().and_then(|u| u.completion_tokens))
     }
 
     #[getter]
     fn total_tokens(&self) -> Option<u64> {
-        self.flat_metadata(|m| m.usage.as_ref().map(|u| u.total_tokens))
+        self.flat_metadata(|m| m.usage.as_ref().and_then(|u| u.total_tokens))
+    }
+
+    /// Prompt tokens served from the provider's prompt cache, or ``None``
+    /// until the stream finishes or if not reported.
+    #[getter]
+    fn cached_prompt_tokens(&self) -> Option<u64> {
+        self.flat_metadata(|m| {
+            m.usage
+                .as_ref()?
+                .prompt_tokens_details
+                .as_ref()?
+                .cached_tokens
+        })
+    }
+
+    /// Completion tokens spent on reasoning, or ``None`` until the stream
+    /// finishes or if not reported.
+    #[getter]
+    fn reasoning_tokens(&self) -> Option<u64> {
+        self.flat_metadata(|m| {
+            m.usage
+                .as_ref()?
+                .completion_tokens_details
+                .as_ref()?
+                .reasoning_tokens
+        })
+    }
+
+    /// What the stream cost in USD as the provider reports it, or
+    /// ``None``. Requires ``include_cost=True`` on OpenRouter.
+    #[getter]
+    fn cost_usd(&self) -> Option<f64> {
+        self.flat_metadata(|m| m.usage.as_ref().and_then(|u| u.cost))
     }
 
     #[getter]
@@ -91,261 +517,975 @@ impl TextStream {
         self.flat_metadata(|m| m.finish_reason.clone())
     }
 
+    /// The model's refusal message, joined from the chunks received so
+    /// far, or ``None``.
+    #[getter]
+    fn refusal(&self) -> Option<String> {
+        self.flat_metadata(|m| m.refusal.clone())
+    }
+
+    /// Citations a web-search model attached to its answer, collected from
+    /// the chunks received so far, as dicts with ``type``, ``url``,
+    /// ``title``, ``start_index`` and ``end_index``.
+    #[getter]
+    fn annotations(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        let annotations = self
+            .flat_metadata(|m| Some(m.annotations.clone()))
+            .unwrap_or_default();
+        annotations_to_py(py, &annotations)
+    }
+
+    /// Whether content filtering stopped the stream (``finish_reason``
+    /// ``"content_filter"``) or the model refused.
+    #[getter]
+    fn was_filtered(&self) -> bool {
+        self.flat_metadata(|m| {
+            Some(content_filtered(
+                m.finish_reason.as_deref(),
+                m.refusal.as_deref(),
+            ))
+        })
+        .unwrap_or(false)
+    }
+
+    /// The model that served the stream, as the provider reports it, or
+    /// else the model the request was sent to.
     #[getter]
     fn model(&self) -> Option<String> {
-        self.flat_metadata(|m| m.model.clone())
+        self.flat_metadata(|m| {
+            m.model
+                .clone()
+                .or_else(|| m.attempted_models.last().cloned())
+        })
+    }
+
+    /// The models the stream was sent to in turn: the provider's
+    /// ``model``, then any ``fallback_models`` tried after it failed.
+    /// Empty until the response starts.
+    #[getter]
+    fn attempted_models(&self) -> Vec<String> {
+        self.flat_metadata(|m| Some(m.attempted_models.clone()))
+            .unwrap_or_default()
+    }
+
+    /// Whether a fallback model served the stream.
+    #[getter]
+    fn fallback_used(&self) -> bool {
+        self.attempted_models().len() > 1
+    }
+
+    #[getter]
+    fn service_tier(&self) -> Option<String> {
+        self.flat_metadata(|m| m.service_tier.clone())
+    }
+
+    #[getter]
+    fn system_fingerprint(&self) -> Option<String> {
+        self.flat_metadata(|m| m.system_fingerprint.clone())
+    }
+
+    /// Milliseconds from sending the request to the first text chunk, or
+    /// ``None`` until the stream finishes or if no text arrived.
+    #[getter]
+    fn time_to_first_chunk_ms(&self) -> Option<f64> {
+        self.flat_metadata(|m| m.timings?.time_to_first_chunk)
+            .map(|duration| duration.as_secs_f64() * 1000.0)
+    }
+
+    /// Milliseconds from sending the request to the end of the response,
+    /// or ``None`` until the stream finishes.
+    #[getter]
+    fn total_duration_ms(&self) -> Option<f64> {
+        self.flat_metadata(|m| m.timings)
+            .map(|timings| timings.total_duration.as_secs_f64() * 1000.0)
+    }
+
+    /// Number of streamed payloads that carried text, or ``None`` until the
+    /// stream finishes.
+    #[getter]
+    fn chunk_count(&self) -> Option<u64> {
+        self.flat_metadata(|m| m.timings.map(|timings| timings.chunk_count))
+    }
+
+    /// Completion tokens per second from the first chunk to the end.
+    ///
+    /// ``None`` until the stream finishes, and unless it was created with
+    /// ``include_usage=True`` and the provider reported usage.
+    #[getter]
+    fn completion_tokens_per_second(&self) -> Option<f64> {
+        self.flat_metadata(|m| {
+            m.timings?
+                .tokens_per_second(m.usage.as_ref()?.completion_tokens?)
+        })
+    }
+
+    /// How many times ``raw_event_callback`` raised. Its exceptions are
+    /// swallowed so the stream keeps going.
+    #[getter]
+    fn raw_event_callback_errors(&self) -> usize {
+        self.callback_errors.load(Ordering::Relaxed)
+    }
+
+    /// Reasoning text accumulated from the stream so far, or `None` if the
+    /// model has not sent any.
+    #[getter]
+    fn reasoning(&self) -> Option<String> {
+        let guard = self.reasoning.lock().ok()?;
+        if guard.is_empty() {
+            None
+        } else {
+            Some(guard.clone())
+        }
     }
 }
 
 impl TextStream {
+    /// The text of the next chunk, as counted by `text()`.
+    pub(crate) fn next_text(&self, py: Python<'_>) -> Option<PyResult<String>> {
+        Some(self.next_item(py)?.map(|item| item.text().to_string()))
+    }
+
+    /// Cancel the stream and wait for the worker thread to exit.
+    pub(crate) fn close(&mut self) {
+        self.cancel();
+        if let Some(handle) = self.handle.take() {
+            join_worker(handle);
+        }
+    }
+
+    /// Fail if the stream already ended, failed, was cancelled, or drained.
+    fn check_unconsumed(&self) -> PyResult<()> {
+        let consumed = self.exhausted.load(Ordering::Relaxed)
+            || self.cancel_flag.load(Ordering::Relaxed)
+            || self.teed.load(Ordering::Relaxed)
+            || self
+                .collected
+                .lock()
+                .is_ok_and(|collected| collected.is_some())
+            || self.error.lock().is_ok_and(|error| error.is_some());
+        if consumed {
+            return Err(SdkError::runtime("The stream has already been consumed.").into_pyerr());
+        }
+        Ok(())
+    }
+
+    fn next_item(&self, py: Python<'_>) -> Option<PyResult<StreamItem>> {
+        // A failed stream keeps raising its error instead of looking finished.
+        if let Ok(error) = self.error.lock()
+            && let Some(error) = error.as_ref()
+        {
+            return Some(Err(error.clone_ref(py)));
+        }
+        if self.teed.load(Ordering::Relaxed) {
+            return Some(Err(SdkError::runtime(
+                "This stream was split with tee(); iterate its branches instead.",
+            )
+            .into_pyerr()));
+        }
+
+        // Wait for the next chunk without holding the GIL so other Python
+        // threads keep running while the stream is stalled. `cancel()` sets
+        // the flag before taking this lock, so the worker disconnects and the
+        // wait ends within one poll interval.
+        let received = py.detach(|| {
+            let mut receiver = self.receiver.lock().map_err(|_| ())?;
+            // `cancel()` drops the receiver; iteration then ends immediately.
+            Ok(receiver.as_mut().map(Receiver::blocking_recv))
+        });
+        let received = match received {
+            Ok(received) => received?,
+            Err(()) => {
+                return Some(Err(SdkError::runtime(
+                    "Internal stream state is unavailable.",
+                )
+                .into_pyerr()));
+            }
+        };
+
+        match received {
+            Some(Ok(item)) => {
+                if let Ok(mut turn) = self.turn.lock()
+                    && let Some(turn) = turn.as_mut()
+                {
+                    turn.push(item.text());
+                }
+                Some(Ok(item))
+            }
+            Some(Err(err)) => {
+                // Drop the chat turn so a failed reply is never recorded.
+                if let Ok(mut turn) = self.turn.lock() {
+                    turn.take();
+                }
+                let err = err.into_pyerr();
+                if let Ok(mut error) = self.error.lock() {
+                    *error = Some(err.clone_ref(py));
+                }
+                Some(Err(err))
+            }
+            None => {
+                self.exhausted.store(true, Ordering::Relaxed);
+                if let Ok(mut turn) = self.turn.lock()
+                    && let Some(turn) = turn.take()
+                {
+                    turn.commit();
+                }
+                None
+            }
+        }
+    }
+
+    /// Record the streamed reply in a `Chat` history once the stream ends.
+    pub(crate) fn with_turn(self, turn: ChatTurn) -> Self {
+        if let Ok(mut slot) = self.turn.lock() {
+            *slot = Some(turn);
+        }
+        self
+    }
+
     fn flat_metadata<T>(&self, f: impl FnOnce(&StreamMetadata) -> Option<T>) -> Option<T> {
-        let meta_arc = self.metadata.as_ref()?;
-        let guard = meta_arc.lock().ok()?;
+        let guard = self.metadata.lock().ok()?;
         let meta = guard.as_ref()?;
         f(meta)
     }
 }
 
+/// One of the iterators returned by `TextStream.tee()`.
+#[pyclass]
+pub struct StreamBranch {
+    shared: Arc<TeeShared>,
+    index: usize,
+    error: Mutex<Option<PyErr>>,
+}
+
+/// State shared by the branches of a `tee()`.
+struct TeeShared {
+    state: Mutex<TeeState>,
+    /// Signalled when a queue shrinks, items arrive, or the stream ends.
+    changed: Condvar,
+    cancel_flag: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+    max_buffer: usize,
+}
+
+struct TeeState {
+    receiver: Option<Receiver<Result<StreamItem, SdkError>>>,
+    /// Unconsumed items per branch; `None` once a branch is cancelled.
+    queues: Vec<Option<VecDeque<StreamItem>>>,
+    /// `Some` once the stream ended, holding its error if it failed.
+    end: Option<Result<(), SdkError>>,
+    /// Whether a branch is waiting on the receiver.
+    reading: bool,
+    turn: Option<ChatTurn>,
+}
+
+impl Drop for TeeShared {
+    fn drop(&mut self) {
+        self.cancel_flag.store(true, Ordering::Relaxed);
+        // Disconnect first so a worker blocked on a full channel exits.
+        if let Ok(mut state) = self.state.lock() {
+            state.receiver.take();
+        }
+        if let Some(handle) = self.handle.take() {
+            join_worker(handle);
+        }
+    }
+}
+
+impl TeeShared {
+    /// The next item for branch `index`, reading from the worker when its
+    /// queue is empty. Blocks while another branch is reading or is
+    /// `max_buffer` items behind.
+    fn next(&self, index: usize) -> Option<Result<StreamItem, SdkError>> {
+        let mut state = self.state.lock().ok()?;
+        loop {
+            let queue = state.queues[index].as_mut()?;
+            if let Some(item) = queue.pop_front() {
+                self.changed.notify_all();
+                return Some(Ok(item));
+            }
+            match &state.end {
+                Some(Ok(())) => return None,
+                Some(Err(err)) => return Some(Err(err.clone())),
+                None => {}
+            }
+
+            let lagging = state
+                .queues
+                .iter()
+                .flatten()
+                .any(|queue| queue.len() >= self.max_buffer);
+            if state.reading || lagging {
+                state = self.changed.wait(state).ok()?;
+                continue;
+            }
+            let Some(mut receiver) = state.receiver.take() else {
+                state.end = Some(Ok(()));
+                continue;
+            };
+
+            // Read without the lock so other branches can drain their queues.
+            state.reading = true;
+            drop(state);
+            let received = receiver.blocking_recv();
+            state = self.state.lock().ok()?;
+            state.reading = false;
+            state.receiver = Some(receiver);
+
+            match received {
+                Some(Ok(item)) => {
+                    if let Some(turn) = state.turn.as_mut() {
+                        turn.push(item.text());
+                    }
+                    for queue in state.queues.iter_mut().flatten() {
+                        queue.push_back(item.clone());
+                    }
+                }
+                Some(Err(err)) => {
+                    // Drop the chat turn so a failed reply is never recorded.
+                    state.turn.take();
+                    state.end = Some(Err(err));
+                }
+                None => {
+                    if let Some(turn) = state.turn.take() {
+                        turn.commit();
+                    }
+                    state.end = Some(Ok(()));
+                }
+            }
+            self.changed.notify_all();
+        }
+    }
+
+    /// Stop feeding branch `index`. The worker is cancelled once every
+    /// branch has been.
+    fn close(&self, index: usize) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        state.queues[index] = None;
+        if state.queues.iter().all(Option::is_none) {
+            self.cancel_flag.store(true, Ordering::Relaxed);
+            state.receiver.take();
+            state.turn.take();
+        }
+        self.changed.notify_all();
+    }
+}
+
+#[pymethods]
+impl StreamBranch {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Cancel this branch. Exceptions raised in the ``with`` body are never
+    /// suppressed.
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    fn __exit__(
+        &self,
+        _exc_type: Option<&Bound<'_, PyAny>>,
+        _exc_value: Option<&Bound<'_, PyAny>>,
+        _traceback: Option<&Bound<'_, PyAny>>,
+    ) -> bool {
+        self.cancel();
+        false
+    }
+
+    fn __next__(&self, py: Python<'_>) -> Option<PyResult<Py<PyAny>>> {
+        Some(self.next_item(py)?.and_then(|item| item.into_py(py)))
+    }
+
+    /// Drain this branch and return its remaining chunks as one string.
+    fn text(&self, py: Python<'_>) -> PyResult<String> {
+        let mut text = String::new();
+        while let Some(item) = self.next_item(py) {
+            text.push_str(item?.text());
+        }
+        Ok(text)
+    }
+
+    /// Stop this branch. Later iteration raises ``StopIteration``, and the
+    /// other branches no longer wait for it. The response is cancelled once
+    /// every branch has been.
+    fn cancel(&self) {
+        self.shared.close(self.index);
+    }
+
+    /// The exception the stream failed with, or ``None``.
+    #[getter]
+    fn error(&self, py: Python<'_>) -> Option<PyErr> {
+        let error = self.error.lock().ok()?;
+        error.as_ref().map(|error| error.clone_ref(py))
+    }
+}
+
+impl StreamBranch {
+    fn next_item(&self, py: Python<'_>) -> Option<PyResult<StreamItem>> {
+        if let Ok(error) = self.error.lock()
+            && let Some(error) = error.as_ref()
+        {
+            return Some(Err(error.clone_ref(py)));
+        }
+
+        // Waiting for the worker or for other branches releases the GIL.
+        match py.detach(|| self.shared.next(self.index))? {
+            Ok(item) => Some(Ok(item)),
+            Err(err) => {
+                let err = err.into_pyerr();
+                if let Ok(mut error) = self.error.lock() {
+                    *error = Some(err.clone_ref(py));
+                }
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl Drop for StreamBranch {
+    fn drop(&mut self) {
+        self.shared.close(self.index);
+    }
+}
+
+/// Per-call streaming options that are not part of the request body.
+#[derive(Default)]
+pub struct StreamSettings {
+    /// Yield `StreamChunk` objects instead of `str`.
+    pub detailed: bool,
+    /// How many times to reopen a stream that fails mid-response.
+    pub reconnects: u32,
+    /// Merge text into chunks of at least this many characters.
+    pub min_chunk_chars: usize,
+    /// Re-segment text into lines or sentences.
+    pub split: SplitMode,
+    /// End the stream when the text contains one of these.
+    pub client_stop: Vec<String>,
+    /// End the stream when the text matches this pattern.
+    pub stop_regex: Option<StopPattern>,
+    /// Yield the text that matched `client_stop` or `stop_regex`.
+    pub include_match: bool,
+    /// Called with each raw SSE event before it is parsed.
+    pub raw_event_callback: Option<Py<PyAny>>,
+}
+
 /// Core streaming logic, called by `Provider.stream_text()`.
-pub fn run(provider: &Provider, params: GenerationParams) -> PyResult<TextStream> {
-    let body = params.into_chat_request(provider.model.clone(), Some(true), None);
-    run_internal(provider, body, None)
+pub fn run(
+    provider: &Provider,
+    params: GenerationParams,
+    settings: StreamSettings,
+) -> PyResult<TextStream> {
+    run_internal(provider, stream_request(provider, params, false), settings)
 }
 
-/// Streaming with metadata tracking, called by `Provider.stream_text(include_usage=True)`.
-pub fn run_with_metadata(provider: &Provider, params: GenerationParams) -> PyResult<TextStream> {
-    let stream_options = Some(serde_json::json!({"include_usage": true}));
-    let body = params.into_chat_request(provider.model.clone(), Some(true), stream_options);
-    let metadata = Arc::new(Mutex::new(None));
-    run_internal(provider, body, Some(metadata))
+/// Streaming with token usage reported, called by `Provider.stream_text(include_usage=True)`.
+pub fn run_with_metadata(
+    provider: &Provider,
+    params: GenerationParams,
+    settings: StreamSettings,
+) -> PyResult<TextStream> {
+    run_internal(provider, stream_request(provider, params, true), settings)
+}
+
+/// The request body for a stream, asking for usage with `include_usage`
+/// or when the provider has a usage tracker.
+pub(crate) fn stream_request(
+    provider: &Provider,
+    params: GenerationParams,
+    include_usage: bool,
+) -> ChatRequest {
+    let include_usage = include_usage || provider.usage_tracker.is_some();
+    let stream_options = include_usage.then(|| serde_json::json!({"include_usage": true}));
+    params.into_chat_request(provider.model.clone(), Some(true), stream_options)
 }
 
 fn run_internal(
     provider: &Provider,
     body: ChatRequest,
-    metadata: Option<Arc<Mutex<Option<StreamMetadata>>>>,
+    settings: StreamSettings,
 ) -> PyResult<TextStream> {
-    let (sender, receiver) = sync_channel::<Result<String, SdkError>>(STREAM_CHANNEL_CAPACITY);
-    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let runtime = runtime::get().map_err(SdkError::into_pyerr)?;
+    let (worker, handles) = start(provider, body, settings)?;
+    let handle = std::thread::spawn(move || runtime.block_on(worker));
 
-    let url = build_chat_completions_url(&provider.base_url);
+    Ok(TextStream {
+        receiver: Mutex::new(Some(handles.receiver)),
+        cancel_flag: handles.cancel_flag,
+        handle: Some(handle),
+        metadata: handles.metadata,
+        reasoning: handles.reasoning,
+        partial: handles.partial,
+        last_event_id: handles.last_event_id,
+        turn: Mutex::new(None),
+        collected: Mutex::new(None),
+        error: Mutex::new(None),
+        exhausted: AtomicBool::new(false),
+        teed: AtomicBool::new(false),
+        callback_errors: handles.callback_errors,
+        idempotency_key: handles.idempotency_key,
+    })
+}
 
-    let thread_cancel_flag = Arc::clone(&cancel_flag);
-    let thread_metadata = metadata.clone();
+/// What a Python-side stream object keeps of a worker started by `start`.
+pub(crate) struct StreamHandles {
+    pub(crate) receiver: Receiver<Result<StreamItem, SdkError>>,
+    pub(crate) cancel_flag: Arc<AtomicBool>,
+    pub(crate) metadata: Arc<Mutex<Option<StreamMetadata>>>,
+    pub(crate) reasoning: Arc<Mutex<String>>,
+    pub(crate) partial: Arc<Mutex<String>>,
+    pub(crate) last_event_id: Arc<Mutex<Option<String>>>,
+    pub(crate) callback_errors: Arc<AtomicUsize>,
+    pub(crate) idempotency_key: Option<String>,
+}
+
+/// Build the worker for a stream without running it. `TextStream` drives
+/// it on a thread of its own and `AsyncTextStream` on the shared runtime.
+pub(crate) fn start(
+    provider: &Provider,
+    body: ChatRequest,
+    settings: StreamSettings,
+) -> PyResult<(impl Future<Output = ()> + Send + 'static, StreamHandles)> {
+    let model = body.primary_model().to_string();
+    let estimated_tokens = estimate_request_tokens(&body);
+    let body = provider.request_body(&body).map_err(SdkError::into_pyerr)?;
+    let (sender, receiver) = channel::<Result<StreamItem, SdkError>>(STREAM_CHANNEL_CAPACITY);
+    let cancel_flag = Arc::new(AtomicBool::new(false));
+    let metadata = Arc::new(Mutex::new(None));
+    let reasoning = Arc::new(Mutex::new(String::new()));
+    let partial = Arc::new(Mutex::new(String::new()));
+    let last_event_id = Arc::new(Mutex::new(None));
+    let callback_errors = Arc::new(AtomicUsize::new(0));
+    let headers = provider.headers().map_err(SdkError::into_pyerr)?;
+    let key = idempotency_key(&headers);
+    let permit = provider.circuit.permit().map_err(SdkError::into_pyerr)?;
     let config = StreamWorkerConfig {
-        url,
-        api_key: provider.api_key.clone(),
+        model,
+        url: provider.request_url(),
+        fallbacks: provider.fallbacks(&body, &headers),
+        headers,
         body,
-        request_timeout: provider.request_timeout,
-        connect_timeout: provider.connect_timeout,
-        max_retries: provider.max_retries,
-        retry_backoff: provider.retry_backoff,
-        cancel_flag: thread_cancel_flag,
-        metadata: thread_metadata,
+        translator: (provider.routing.dialect == ApiDialect::Anthropic)
+            .then(StreamTranslator::default),
+        idle_timeout: provider.stream_idle_timeout,
+        client: provider.client().map_err(SdkError::into_pyerr)?,
+        retries: provider.retry_settings(),
+        circuit: Some(permit),
+        limiter: provider.limiter.clone(),
+        estimated_tokens,
+        concurrency: provider.concurrency.clone(),
+        usage_tracker: provider.usage_tracker.clone(),
+        idempotency_key: key.clone(),
+        cancel_flag: Arc::clone(&cancel_flag),
+        metadata: Arc::clone(&metadata),
+        reasoning: Arc::clone(&reasoning),
+        partial: Arc::clone(&partial),
+        last_event_id: Arc::clone(&last_event_id),
+        detailed: settings.detailed,
+        max_reconnects: settings.reconnects,
+        min_chunk_chars: settings.min_chunk_chars,
+        split: settings.split,
+        stop: (!settings.client_stop.is_empty() || settings.stop_regex.is_some()).then(|| {
+            StopScanner::new(
+                settings.client_stop,
+                settings.stop_regex,
+                settings.include_match,
+            )
+        }),
+        raw_event_hook: settings.raw_event_callback.map(|callback| RawEventHook {
+            callback,
+            errors: Arc::clone(&callback_errors),
+        }),
     };
 
-    let handle = std::thread::spawn(move || {
-        run_stream_thread(sender, config);
-    });
-
-    Ok(TextStream {
-        receiver: Mutex::new(receiver),
+    let handles = StreamHandles {
+        receiver,
         cancel_flag,
-        handle: Some(handle),
         metadata,
-    })
+        reasoning,
+        partial,
+        last_event_id,
+        callback_errors,
+        idempotency_key: key,
+    };
+    Ok((run_worker(sender, config), handles))
 }
 
-fn run_stream_thread(sender: SyncSender<Result<String, SdkError>>, config: StreamWorkerConfig) {
-    let runtime = match tokio::runtime::Runtime::new() {
-        Ok(runtime) => runtime,
-        Err(e) => {
-            let _ = sender.send(Err(SdkError::runtime(e.to_string())));
-            return;
+async fn run_worker(sender: Sender<Result<StreamItem, SdkError>>, mut config: StreamWorkerConfig) {
+    let deadline = Deadline::start(config.retries.total_timeout);
+    // A rate-limit or concurrency wait that outlasts the deadline is no
+    // fault of the provider's, so the error skips `ItemSender` and its
+    // circuit permit.
+    let reservation = loop {
+        match config.limiter.try_acquire(config.estimated_tokens) {
+            Ok(reservation) => break reservation,
+            Err(wait) => {
+                if let Err(error) = wait_within(wait, deadline.as_ref()) {
+                    let error = error.with_idempotency_key(config.idempotency_key.as_deref());
+                    let _ = sender.send(Err(error)).await;
+                    return;
+                }
+                if sleep_with_cancellation(&config.cancel_flag, wait).await {
+                    return;
+                }
+            }
+        }
+    };
+    // Held until the worker returns, so the stream keeps its slot for as
+    // long as it runs.
+    let _slot = loop {
+        let acquire = config.concurrency.acquire(deadline.as_ref());
+        match timeout(CANCEL_POLL_INTERVAL, acquire).await {
+            Ok(Ok(slot)) => break slot,
+            Ok(Err(error)) => {
+                let error = error.with_idempotency_key(config.idempotency_key.as_deref());
+                let _ = sender.send(Err(error)).await;
+                return;
+            }
+            Err(_) if config.cancel_flag.load(Ordering::Relaxed) => return,
+            Err(_) => {}
         }
     };
 
-    runtime.block_on(async move {
-        let StreamWorkerConfig {
-            url,
-            api_key,
-            body,
-            request_timeout,
-            connect_timeout,
-            max_retries,
-            retry_backoff,
-            cancel_flag,
-            metadata,
-        } = config;
+    let mut sender = ItemSender::new(
+        sender,
+        config.min_chunk_chars,
+        config.split,
+        config.stop.take(),
+        config.idempotency_key.take(),
+        config.circuit.take(),
+    );
 
-        let client = match reqwest::Client::builder()
-            .connect_timeout(connect_timeout)
-            .build()
-        {
-            Ok(client) => client,
-            Err(e) => {
-                let _ = sender.send(Err(SdkError::runtime(e.to_string())));
-                return;
+    let StreamWorkerConfig {
+        model,
+        url,
+        headers,
+        body,
+        fallbacks,
+        mut translator,
+        idle_timeout,
+        client,
+        retries,
+        cancel_flag,
+        metadata,
+        reasoning,
+        partial,
+        last_event_id,
+        detailed,
+        max_reconnects,
+        raw_event_hook,
+        usage_tracker,
+        ..
+    } = config;
+    let mut clock = StreamClock::new(metadata, reservation, usage_tracker);
+
+    let mut target = Fallback {
+        model,
+        url,
+        body,
+        headers,
+    };
+    let build_request = |target: &Fallback, last_event_id: Option<String>, timeout: Duration| {
+        let request = client
+            .post(&target.url)
+            .header("Content-Type", "application/json")
+            .headers(target.headers.clone())
+            .timeout(timeout)
+            .json(&target.body);
+        match last_event_id {
+            Some(id) => request.header("Last-Event-ID", id),
+            None => request,
+        }
+    };
+
+    let mut fallbacks = fallbacks.into_iter();
+    let has_fallbacks = fallbacks.len() > 0;
+    let mut attempted = vec![target.model.clone()];
+    let response = loop {
+        let connected = connect_with_retries(
+            |timeout| build_request(&target, None, timeout),
+            &retries,
+            deadline,
+            &mut RetryTally::default(),
+            Some(&cancel_flag),
+        )
+        .await;
+        let error = match connected {
+            Ok(Some(response)) => {
+                sender.connected();
+                clock.metadata(StreamMetadata {
+                    request_id: request_id(response.headers()),
+                    idempotency_key: idempotency_key(&target.headers),
+                    attempted_models: attempted.clone(),
+                    rate_limit: Some(rate_limit(response.headers(), SystemTime::now())),
+                    ..Default::default()
+                });
+                break response;
             }
+            // Cancelled before the response started.
+            Ok(None) => return,
+            Err(error) => error,
         };
-
-        let mut response = None;
-        for attempt in 0..=max_retries {
-            if cancel_flag.load(Ordering::Relaxed) {
+        match fallbacks.next() {
+            Some(fallback) if error.warrants_fallback() => {
+                attempted.push(fallback.model.clone());
+                sender.idempotency_key = idempotency_key(&fallback.headers);
+                target = fallback;
+            }
+            _ => {
+                let error = if has_fallbacks {
+                    error.with_attempted_models(attempted)
+                } else {
+                    error
+                };
+                sender.error(error).await;
                 return;
             }
+        }
+    };
 
-            let response_result = client
-                .post(&url)
-                .header("Authorization", format!("Bearer {}", api_key))
-                .header("Content-Type", "application/json")
-                .timeout(request_timeout)
-                .json(&body)
-                .send()
-                .await;
-
-            match response_result {
-                Ok(resp) => {
-                    if resp.status().is_success() {
-                        response = Some(resp);
-                        break;
-                    }
+    let mut stream = response.bytes_stream();
+    let mut line_buffer = SseLineBuffer::default();
+    let mut event_buffer = String::new();
+    let mut last_activity = Instant::now();
+    let mut reconnect_attempts = 0;
 
-                    let status = resp.status();
-                    let text = resp.text().await.unwrap_or_default();
-                    if is_retryable_status(status) && attempt < max_retries {
-                        if sleep_with_cancellation(
-                            &cancel_flag,
-                            retry_delay(retry_backoff, attempt),
-                        )
-                        .await
-                        {
-                            return;
-                        }
-                        continue;
-                    }
+    loop {
+        if cancel_flag.load(Ordering::Relaxed) {
+            return;
+        }
 
-                    let _ = sender.send(Err(SdkError::runtime(api_error_message(status, &text))));
+        let chunk_result = match timeout(CANCEL_POLL_INTERVAL, stream.next()).await {
+            Ok(chunk) => chunk,
+            Err(_) => {
+                if last_activity.elapsed() >= idle_timeout {
+                    let error = SdkError::runtime(format!(
+                        "Streaming response timed out after {:?} of inactivity \
+                         (stream_idle_timeout, RUSTY_AGENT_STREAM_IDLE_TIMEOUT_SECS).",
+                        idle_timeout
+                    ));
+                    sender.error(mid_stream_error(error, &partial)).await;
                     return;
                 }
-                Err(error) => {
-                    if is_retryable_error(&error) && attempt < max_retries {
-                        if sleep_with_cancellation(
-                            &cancel_flag,
-                            retry_delay(retry_backoff, attempt),
-                        )
-                        .await
-                        {
-                            return;
-                        }
-                        continue;
-                    }
-
-                    let _ = sender.send(Err(SdkError::connection(error.to_string())));
+                if !sender.flush_if_stale().await {
                     return;
                 }
+                continue;
             }
-        }
-
-        let Some(response) = response else {
-            let _ = sender.send(Err(SdkError::runtime(
-                "Stream request failed after retries were exhausted.",
-            )));
-            return;
         };
 
-        let mut stream = response.bytes_stream();
-        let mut line_buffer = String::new();
-        let mut event_buffer = String::new();
-        let mut last_activity = Instant::now();
-
-        loop {
-            if cancel_flag.load(Ordering::Relaxed) {
-                return;
-            }
+        let Some(chunk_result) = chunk_result else {
+            break;
+        };
 
-            let chunk_result = match timeout(STREAM_CANCEL_POLL_INTERVAL, stream.next()).await {
-                Ok(chunk) => chunk,
-                Err(_) => {
-                    if last_activity.elapsed() >= request_timeout {
-                        let _ = sender.send(Err(SdkError::runtime(format!(
-                            "Streaming response timed out after {}s of inactivity.",
-                            request_timeout.as_secs()
-                        ))));
+        let bytes = match chunk_result {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                // Reopen the stream, letting providers that support
+                // `Last-Event-ID` resume after the last event received.
+                // Without an event id the server cannot resume, and a new
+                // request would start a new completion whose text is
+                // appended to what was already yielded, so the stream fails.
+                let id = last_event_id.lock().ok().and_then(|id| id.clone());
+                let mut reopened = None;
+                let mut refused = None;
+                // Why the next attempt is made, for the retry hook and the
+                // final error: the stream's own failure, then that of the
+                // last reconnect, with its status and `Retry-After` hint.
+                let mut failure = e.to_string();
+                let mut failed_status = None;
+                let mut hint = None;
+                while let Some(id) = &id
+                    && reopened.is_none()
+                    && refused.is_none()
+                    && reconnect_attempts < max_reconnects
+                {
+                    let cause = match failed_status {
+                        Some(status) => RetryCause::Status(status),
+                        None => RetryCause::Error(&failure),
+                    };
+                    let waited = wait_to_retry(
+                        &retries,
+                        reconnect_attempts,
+                        hint,
+                        deadline.as_ref(),
+                        cause,
+                        Some(&cancel_flag),
+                    );
+                    if waited.await.is_none() {
                         return;
                     }
-                    continue;
+                    // The deadline also bounds reconnects, which count as
+                    // further attempts of the same call.
+                    let Ok(timeout) = attempt_timeout(
+                        retries.request_timeout,
+                        deadline.as_ref(),
+                        reconnect_attempts,
+                    ) else {
+                        break;
+                    };
+                    reconnect_attempts += 1;
+                    let request = build_request(&target, Some(id.clone()), timeout);
+                    match request.send().await {
+                        Ok(resp) if resp.status().is_success() => reopened = Some(resp),
+                        Ok(resp) => {
+                            let status = resp.status();
+                            let request_id = request_id(resp.headers());
+                            hint = retry_after(resp.headers(), SystemTime::now());
+                            let text = resp.text().await.unwrap_or_default();
+                            let error = api_error(status, &text);
+                            // A status that is not worth retrying, such as
+                            // 401 or 404, ends the reconnects.
+                            if !is_retryable_status(status, &retries.retry_on_status) {
+                                refused = Some(SdkError::api(ApiError {
+                                    request_id,
+                                    ..error
+                                }));
+                            } else {
+                                failure = error.message;
+                                failed_status = Some(status.as_u16());
+                            }
+                        }
+                        Err(error) => {
+                            failure = error.to_string();
+                            failed_status = None;
+                            hint = None;
+                        }
+                    }
                 }
-            };
-
-            let Some(chunk_result) = chunk_result else {
-                break;
-            };
 
-            let bytes = match chunk_result {
-                Ok(bytes) => bytes,
-                Err(e) => {
-                    let _ = sender.send(Err(SdkError::runtime(e.to_string())));
+                if let Some(error) = refused {
+                    let error = error
+                        .map_message(|message| {
+                            format!(
+                                "{} (reconnect attempt {} after the stream failed: {})",
+                                message.trim_end_matches('.'),
+                                reconnect_attempts,
+                                e
+                            )
+                        })
+                        .with_cause(transport_cause(e));
+                    sender.error(mid_stream_error(error, &partial)).await;
                     return;
                 }
-            };
-            last_activity = Instant::now();
-
-            line_buffer.push_str(&String::from_utf8_lossy(&bytes));
-
-            while let Some(newline_pos) = line_buffer.find('\n') {
-                let mut line = line_buffer[..newline_pos].to_string();
-                line_buffer = line_buffer[newline_pos + 1..].to_string();
-                if line.ends_with('\r') {
-                    line.pop();
-                }
-
-                if line.is_empty() {
-                    if !event_buffer.is_empty() {
-                        if handle_sse_event(&sender, &event_buffer, &metadata) {
-                            return;
-                        }
-                        event_buffer.clear();
+                let Some(resp) = reopened else {
+                    if let Some(deadline) = deadline
+                        && deadline.is_exceeded()
+                    {
+                        let error = deadline.exceeded(reconnect_attempts + 1);
+                        sender.error(mid_stream_error(error, &partial)).await;
+                        return;
                     }
-                    continue;
-                }
+                    let mut message = e.to_string();
+                    if reconnect_attempts > 0 {
+                        message = format!(
+                            "{}; gave up after {} reconnect attempt(s), the last failing with: {}",
+                            message, reconnect_attempts, failure
+                        );
+                    }
+                    let error = SdkError::runtime(message).with_cause(transport_cause(e));
+                    sender.error(mid_stream_error(error, &partial)).await;
+                    return;
+                };
+                stream = resp.bytes_stream();
+                line_buffer.clear();
+                event_buffer.clear();
+                last_activity = Instant::now();
+                continue;
+            }
+        };
+        last_activity = Instant::now();
 
+        line_buffer.extend(&bytes);
+        while let Some(line) = line_buffer.next_line() {
+            if line.is_empty() {
                 if !event_buffer.is_empty() {
-                    event_buffer.push('\n');
+                    if let Some(hook) = &raw_event_hook {
+                        hook.call(&event_buffer);
+                    }
+                    record_event_id(&event_buffer, &last_event_id);
+                    if let Some(event) = dialect_event(&mut translator, &event_buffer)
+                        && handle_sse_event(
+                            &mut sender,
+                            &event,
+                            &mut clock,
+                            &reasoning,
+                            &partial,
+                            detailed,
+                        )
+                        .await
+                    {
+                        return;
+                    }
+                    event_buffer.clear();
                 }
-                event_buffer.push_str(&line);
+                continue;
+            }
+            if is_sse_comment(&line) {
+                continue;
             }
-        }
 
-        let trailing_line = line_buffer.trim_end_matches('\r');
-        if !trailing_line.is_empty() {
             if !event_buffer.is_empty() {
                 event_buffer.push('\n');
             }
-            event_buffer.push_str(trailing_line);
+            event_buffer.push_str(&line);
+        }
+        if !sender.flush_if_stale().await {
+            return;
         }
+    }
 
-        if !event_buffer.trim().is_empty() {
-            let _ = handle_sse_event(&sender, &event_buffer, &metadata);
+    if let Some(trailing_line) = line_buffer.finish()
+        && !is_sse_comment(&trailing_line)
+    {
+        if !event_buffer.is_empty() {
+            event_buffer.push('\n');
         }
-    });
-}
+        event_buffer.push_str(&trailing_line);
+    }
 
-async fn sleep_with_cancellation(cancel_flag: &AtomicBool, delay: Duration) -> bool {
-    let start = Instant::now();
-    while start.elapsed() < delay {
-        if cancel_flag.load(Ordering::Relaxed) {
-            return true;
+    if !event_buffer.trim().is_empty() {
+        if let Some(hook) = &raw_event_hook {
+            hook.call(&event_buffer);
+        }
+        record_event_id(&event_buffer, &last_event_id);
+        if let Some(event) = dialect_event(&mut translator, &event_buffer) {
+            let _ = handle_sse_event(
+                &mut sender,
+                &event,
+                &mut clock,
+                &reasoning,
+                &partial,
+                detailed,
+            )
+            .await;
         }
-        sleep(STREAM_CANCEL_POLL_INTERVAL).await;
     }
-    false
+    sender.flush().await;
+    clock.finish();
 }
 
-fn handle_sse_event(
-    sender: &SyncSender<Result<String, SdkError>>,
+/// `event` in chat completions format, translated if the provider speaks
+/// another dialect; `None` if it carries nothing to parse.
+fn dialect_event<'a>(
+    translator: &mut Option<StreamTranslator>,
+    event: &'a str,
+) -> Option<Cow<'a, str>> {
+    match translator {
+        Some(translator) => translator.translate(event).map(Cow::Owned),
+        None => Some(Cow::Borrowed(event)),
+    }
+}
+
+async fn handle_sse_event(
+    sender: &mut ItemSender,
     event: &str,
-    metadata: &Option<Arc<Mutex<Option<StreamMetadata>>>>,
+    clock: &mut StreamClock,
+    reasoning: &Mutex<String>,
+    partial: &Mutex<String>,
+    detailed: bool,
 ) -> bool {
     match parse_sse_event(event) {
         Ok(events) => {
@@ -353,28 +1493,301 @@ fn handle_sse_event(
             for ev in events {
                 match ev {
                     StreamEvent::Done => {
+                        sender.flush().await;
+                        clock.finish();
                         should_stop = true;
                     }
+                    // Detailed streams send every choice below instead.
+                    StreamEvent::Content(_) if detailed => clock.chunk(),
                     StreamEvent::Content(content) => {
-                        if sender.send(Ok(content)).is_err() {
+                        clock.chunk();
+                        if let Ok(mut guard) = partial.lock() {
+                            guard.push_str(&content);
+                        }
+                        if !sender.text(content).await {
+                            if sender.stopped() {
+                                // Dropping the response on return cancels it.
+                                sender.flush().await;
+                                clock.client_stop();
+                                return true;
+                            }
                             should_stop = true;
                         }
                     }
+                    StreamEvent::Reasoning(text) => {
+                        if let Ok(mut guard) = reasoning.lock() {
+                            guard.push_str(&text);
+                        }
+                    }
                     StreamEvent::Metadata(meta) => {
-                        if let Some(meta_arc) = metadata
-                            && let Ok(mut guard) = meta_arc.lock()
-                        {
-                            *guard = Some(meta);
+                        // Usage and `finish_reason` arrive with the final
+                        // chunks; `id` and `created` come with every chunk.
+                        let is_final = meta.usage.is_some() || meta.finish_reason.is_some();
+                        if is_final && !sender.flush().await {
+                            should_stop = true;
                         }
+                        clock.metadata(*meta);
                     }
                     StreamEvent::Ignore => {}
                 }
             }
+            if detailed && let Ok(chunks) = parse_sse_chunks(event) {
+                for chunk in chunks {
+                    if chunk.index == 0
+                        && let Ok(mut guard) = partial.lock()
+                    {
+                        guard.push_str(&chunk.text);
+                    }
+                    if !sender.item(StreamItem::Chunk(chunk)).await {
+                        should_stop = true;
+                        break;
+                    }
+                }
+            }
             should_stop
         }
         Err(err) => {
-            let _ = sender.send(Err(err));
+            sender.error(mid_stream_error(err, partial)).await;
             true
         }
     }
 }
+
+/// Records metadata and timings for the `TextStream` getters.
+struct StreamClock {
+    metadata: Arc<Mutex<Option<StreamMetadata>>>,
+    started: Instant,
+    first_chunk: Option<Duration>,
+    chunk_count: u64,
+    /// Corrected to the reported usage once the response ends.
+    reservation: Option<Reservation>,
+    /// Given the reported usage once the response ends.
+    usage_tracker: Option<UsageTracker>,
+}
+
+impl StreamClock {
+    fn new(
+        metadata: Arc<Mutex<Option<StreamMetadata>>>,
+        reservation: Reservation,
+        usage_tracker: Option<UsageTracker>,
+    ) -> Self {
+        Self {
+            metadata,
+            reservation: Some(reservation),
+            usage_tracker,
+            started: Instant::now(),
+            first_chunk: None,
+            chunk_count: 0,
+        }
+    }
+
+    /// Count a payload that carried text.
+    fn chunk(&mut self) {
+        self.first_chunk
+            .get_or_insert_with(|| self.started.elapsed());
+        self.chunk_count += 1;
+    }
+
+    /// Keep the metadata sent with the final chunks.
+    fn metadata(&self, meta: StreamMetadata) {
+        if let Ok(mut guard) = self.metadata.lock() {
+            guard
+                .get_or_insert_with(StreamMetadata::default)
+                .merge(meta);
+        }
+    }
+
+    /// Record that a `client_stop` sequence or `stop_regex` ended the stream.
+    fn client_stop(&mut self) {
+        self.metadata(StreamMetadata {
+            finish_reason: Some("client_stop".to_string()),
+            ..Default::default()
+        });
+        self.finish();
+    }
+
+    /// Record the timings once the response has ended, settle the rate
+    /// limiter's reservation with the usage, if reported, and count the
+    /// call in the usage tracker, if set.
+    fn finish(&mut self) {
+        if let Ok(mut guard) = self.metadata.lock() {
+            let metadata = guard.get_or_insert_with(StreamMetadata::default);
+            metadata.timings = Some(StreamTimings {
+                time_to_first_chunk: self.first_chunk,
+                total_duration: self.started.elapsed(),
+                chunk_count: self.chunk_count,
+            });
+            if let Some(reservation) = self.reservation.take() {
+                reservation.settle(metadata.usage.as_ref().and_then(|usage| usage.total_tokens));
+            }
+            if let Some(tracker) = self.usage_tracker.take() {
+                let model = metadata
+                    .model
+                    .as_ref()
+                    .or(metadata.attempted_models.last())
+                    .map_or("", String::as_str);
+                tracker.record(model, metadata.usage.as_ref());
+            }
+        }
+    }
+}
+
+/// Sends items to the `TextStream`, cutting text at a client-side stop
+/// sequence or pattern, into lines or sentences, and merging it into chunks of at least
+/// `min_chars` characters when those options are on.
+struct ItemSender {
+    sender: Sender<Result<StreamItem, SdkError>>,
+    stop: Option<StopScanner>,
+    segmenter: Option<Segmenter>,
+    min_chars: usize,
+    pending: String,
+    pending_chars: usize,
+    pending_since: Instant,
+    /// Set on errors, as their `idempotency_key` attribute.
+    idempotency_key: Option<String>,
+    /// Told whether the stream started, by `connected` or the first error.
+    circuit: Option<CircuitPermit>,
+}
+
+impl ItemSender {
+    fn new(
+        sender: Sender<Result<StreamItem, SdkError>>,
+        min_chars: usize,
+        split: SplitMode,
+        stop: Option<StopScanner>,
+        idempotency_key: Option<String>,
+        circuit: Option<CircuitPermit>,
+    ) -> Self {
+        Self {
+            sender,
+            stop,
+            idempotency_key,
+            circuit,
+            segmenter: (split != SplitMode::None).then(|| Segmenter::new(split)),
+            min_chars,
+            pending: String::new(),
+            pending_chars: 0,
+            pending_since: Instant::now(),
+        }
+    }
+
+    /// Send or buffer `text`. Returns `false` once the stream was dropped
+    /// or a stop sequence matched.
+    async fn text(&mut self, text: String) -> bool {
+        let text = match &mut self.stop {
+            Some(stop) => stop.push(&text),
+            None => text,
+        };
+        self.segment(text).await && !self.stopped()
+    }
+
+    async fn segment(&mut self, text: String) -> bool {
+        if text.is_empty() {
+            return true;
+        }
+        let segments = match &mut self.segmenter {
+            Some(segmenter) => segmenter.push(&text),
+            None => vec![text],
+        };
+        for segment in segments {
+            if !self.coalesce(segment).await {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Whether a `client_stop` sequence or `stop_regex` has matched.
+    fn stopped(&self) -> bool {
+        self.stop.as_ref().is_some_and(StopScanner::stopped)
+    }
+
+    async fn coalesce(&mut self, text: String) -> bool {
+        if self.min_chars <= 1 {
+            return self.item(StreamItem::Text(text)).await;
+        }
+        if self.pending.is_empty() {
+            self.pending_since = Instant::now();
+        }
+        self.pending_chars += text.chars().count();
+        self.pending.push_str(&text);
+        self.pending_chars < self.min_chars || self.send_pending().await
+    }
+
+    async fn item(&mut self, item: StreamItem) -> bool {
+        self.send_pending().await && self.sender.send(Ok(item)).await.is_ok()
+    }
+
+    /// Send any buffered text, including an unfinished line or sentence.
+    async fn flush(&mut self) -> bool {
+        if let Some(tail) = self.stop.as_mut().and_then(StopScanner::finish)
+            && !self.segment(tail).await
+        {
+            return false;
+        }
+        if let Some(tail) = self.segmenter.as_mut().and_then(Segmenter::finish)
+            && !self.coalesce(tail).await
+        {
+            return false;
+        }
+        self.send_pending().await
+    }
+
+    /// Send coalesced text that has been held for `COALESCE_MAX_DELAY`.
+    /// An unfinished line or sentence is kept.
+    async fn flush_if_stale(&mut self) -> bool {
+        if self.pending_since.elapsed() < COALESCE_MAX_DELAY {
+            return true;
+        }
+        self.send_pending().await
+    }
+
+    /// Send the coalesced text, leaving held-back tails in place.
+    async fn send_pending(&mut self) -> bool {
+        if self.pending.is_empty() {
+            return true;
+        }
+        self.pending_chars = 0;
+        let text = std::mem::take(&mut self.pending);
+        self.sender.send(Ok(StreamItem::Text(text))).await.is_ok()
+    }
+
+    /// Send an error after any buffered text, which came before it.
+    /// Report the response as started to the circuit breaker.
+    fn connected(&mut self) {
+        if let Some(permit) = self.circuit.take() {
+            permit.success();
+        }
+    }
+
+    async fn error(&mut self, error: SdkError) {
+        if let Some(permit) = self.circuit.take() {
+            permit.finish(Some(&error));
+        }
+        let _ = self.flush().await;
+        let error = error.with_idempotency_key(self.idempotency_key.as_deref());
+        let _ = self.sender.send(Err(error)).await;
+    }
+}
+
+fn record_event_id(event: &str, last_event_id: &Mutex<Option<String>>) {
+    // An empty `id:` resets the id, so no header is sent on reconnect.
+    if let Some(id) = sse_event_id(event)
+        && let Ok(mut last) = last_event_id.lock()
+    {
+        *last = (!id.is_empty()).then(|| id.to_string());
+    }
+}
+
+/// Note how much text had been received on an error raised after the
+/// response started.
+fn mid_stream_error(error: SdkError, partial: &Mutex<String>) -> SdkError {
+    let received = partial.lock().map_or(0, |text| text.chars().count());
+    error.map_message(|message| {
+        format!(
+            "{} ({} characters received before the failure.)",
+            message.trim_end_matches('.'),
+            received
+        )
+    })
+}
@@ -0,0 +1,441 @@
+use crate::errors::SdkError;
+use crate::generate;
+use crate::models::{ChatMessage, GenerationParams};
+use crate::provider::{Provider, json_to_py, py_to_json};
+use crate::stream::{self, StreamSettings, TextStream};
+use pyo3::prelude::*;
+use pyo3::types::PyType;
+use serde_json::Value;
+use std::sync::Mutex;
+
+/// Accepted values for the `type` field of `response_format`.
+pub const RESPONSE_FORMAT_TYPES: [&str; 3] = ["text", "json_object", "json_schema"];
+
+/// Build a `json_schema` response format envelope.
+///
+/// `name` must match `^[a-zA-Z0-9_-]{1,64}$` as required by the API, and
+/// `schema` must be a JSON object with a `type` key.
+pub fn build_json_schema_format(
+    name: &str,
+    schema: Value,
+    strict: bool,
+) -> Result<Value, SdkError> {
+    let valid_name = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !valid_name {
+        return Err(SdkError::value(format!(
+            "'name' must be 1-64 characters of letters, digits, '_' or '-', got '{}'.",
+            name
+        )));
+    }
+    if !schema.is_object() || schema.get("type").is_none() {
+        return Err(SdkError::value(
+            "'schema' must be a dict with a 'type' key.",
+        ));
+    }
+
+    Ok(serde_json::json!({
+        "type": "json_schema",
+        "json_schema": {
+            "name": name,
+            "schema": schema,
+            "strict": strict,
+        },
+    }))
+}
+
+/// Check that `response_format` is an object with a known `type`.
+///
+/// `json_schema` formats must also carry the `json_schema` envelope.
+pub fn validate_response_format(format: &Value) -> Result<(), SdkError> {
+    let Some(format_type) = format.get("type").and_then(Value::as_str) else {
+        return Err(SdkError::value(
+            "'response_format' must be a dict with a string 'type'.",
+        ));
+    };
+    if !RESPONSE_FORMAT_TYPES.contains(&format_type) {
+        return Err(SdkError::value(format!(
+            "'response_format' type must be one of {}, got '{}'.",
+            RESPONSE_FORMAT_TYPES.join(", "),
+            format_type
+        )));
+    }
+    if format_type == "json_schema" && !format.get("json_schema").is_some_and(Value::is_object) {
+        return Err(SdkError::value(
+            "'response_format' of type 'json_schema' is missing the 'json_schema' dict; \
+             use json_schema(name, schema) to build it.",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Parse model output as JSON.
+///
+/// Surrounding whitespace and a Markdown code fence (```` ```json ... ``` ````)
+/// are stripped first, since models often wrap JSON in one even when asked not
+/// to. The error includes the raw text so callers can see what went wrong.
+pub fn parse_json_output(text: &str) -> Result<Value, SdkError> {
+    parse_unfenced(text).map_err(|e| invalid_json(&e, text))
+}
+
+fn invalid_json(error: &serde_json::Error, text: &str) -> SdkError {
+    SdkError::value(format!(
+        "Model returned invalid JSON ({}). Raw output: {}",
+        error, text
+    ))
+}
+
+/// Parse the JSON value in an incomplete model reply.
+///
+/// Unterminated strings, arrays, and objects are closed, and a trailing
+/// member that cannot be completed yet (a key without a value, or a partial
+/// `true`) is dropped. Text before the first `{` or `[`, such as an opening
+/// code fence, is skipped. Returns `None` until an object or array has been
+/// opened.
+pub fn parse_partial_json(text: &str) -> Option<Value> {
+    let start = text.find(['{', '['])?;
+    let text = &text[start..];
+
+    let mut closers = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut string_is_key = false;
+    let mut expecting_key = false;
+    // The longest prefix that ends after a complete value, and what closes it.
+    let mut safe = (0, String::new());
+
+    for (i, c) in text.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+                if !string_is_key {
+                    safe = (i + 1, closing(&closers));
+                }
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                string_is_key = expecting_key;
+            }
+            '{' | '[' => {
+                closers.push(if c == '{' { '}' } else { ']' });
+                expecting_key = c == '{';
+                safe = (i + 1, closing(&closers));
+            }
+            '}' | ']' => {
+                closers.pop();
+                if closers.is_empty() {
+                    return serde_json::from_str(&text[..=i]).ok();
+                }
+                safe = (i + 1, closing(&closers));
+            }
+            ':' => expecting_key = false,
+            ',' => {
+                expecting_key = closers.last() == Some(&'}');
+                safe = (i, closing(&closers));
+            }
+            _ => {}
+        }
+    }
+
+    let mut candidates = Vec::with_capacity(2);
+    if !in_string {
+        let body = text.trim_end().trim_end_matches(',');
+        candidates.push(format!("{}{}", body, closing(&closers)));
+    } else if !string_is_key {
+        let body = without_partial_escape(text, escaped);
+        candidates.push(format!("{}\"{}", body, closing(&closers)));
+    }
+    let (end, safe_closers) = safe;
+    candidates.push(format!("{}{}", &text[..end], safe_closers));
+
+    candidates
+        .iter()
+        .find_map(|candidate| serde_json::from_str(candidate).ok())
+}
+
+/// The characters that close the open containers, innermost first.
+fn closing(closers: &[char]) -> String {
+    closers.iter().rev().collect()
+}
+
+/// Drop an unfinished escape sequence from the end of a string.
+fn without_partial_escape(text: &str, escaped: bool) -> &str {
+    if escaped {
+        return &text[..text.len() - 1];
+    }
+    if let Some(pos) = text.rfind("\\u") {
+        let digits = &text[pos + 2..];
+        if digits.len() < 4 && digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return &text[..pos];
+        }
+    }
+    text
+}
+
+fn parse_unfenced(text: &str) -> Result<Value, serde_json::Error> {
+    let trimmed = text.trim();
+    let unfenced = trimmed
+        .strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+        .map(|inner| inner.strip_prefix("json").unwrap_or(inner).trim())
+        .unwrap_or(trimmed);
+
+    serde_json::from_str(unfenced)
+}
+
+/// Run a structured-output request and parse the reply as JSON.
+///
+/// With `repair`, an invalid reply is sent back once together with the parse
+/// error and a request for valid JSON only.
+pub(crate) fn run_object(
+    provider: &Provider,
+    params: GenerationParams,
+    repair: bool,
+) -> PyResult<Value> {
+    let retry_params = repair.then(|| params.clone());
+    let text = generate::run(provider, params)?;
+    let error = match parse_unfenced(&text) {
+        Ok(value) => return Ok(value),
+        Err(error) => error,
+    };
+    let Some(mut params) = retry_params else {
+        return Err(invalid_json(&error, &text).into_pyerr());
+    };
+
+    params.messages.push(ChatMessage {
+        role: "assistant".to_string(),
+        content: text.into(),
+        ..Default::default()
+    });
+    params.messages.push(ChatMessage {
+        role: "system".to_string(),
+        content: format!(
+            "Your previous reply was not valid JSON ({}). \
+             Reply again with only the corrected JSON and no other text.",
+            error
+        )
+        .into(),
+        ..Default::default()
+    });
+    let text = generate::run(provider, params)?;
+    parse_json_output(&text).map_err(SdkError::into_pyerr)
+}
+
+/// An iterator that yields progressively completed JSON values from a
+/// streaming structured-output response.
+#[pyclass]
+pub struct ObjectStream {
+    stream: TextStream,
+    state: Mutex<ObjectStreamState>,
+}
+
+#[derive(Default)]
+struct ObjectStreamState {
+    text: String,
+    last: Option<Value>,
+    finished: bool,
+}
+
+#[pymethods]
+impl ObjectStream {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Cancel the stream and wait for the worker thread to exit.
+    ///
+    /// Exceptions raised in the ``with`` body are never suppressed.
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<&Bound<'_, PyAny>>,
+        _exc_value: Option<&Bound<'_, PyAny>>,
+        _traceback: Option<&Bound<'_, PyAny>>,
+    ) -> bool {
+        self.stream.close();
+        false
+    }
+
+    fn __next__(&self, py: Python<'_>) -> Option<PyResult<Py<PyAny>>> {
+        loop {
+            if self.state.lock().is_ok_and(|state| state.finished) {
+                return None;
+            }
+            // The state lock is not held here: waiting releases the GIL.
+            let chunk = match self.stream.next_text(py) {
+                Some(Ok(chunk)) => Some(chunk),
+                Some(Err(err)) => return Some(Err(err)),
+                None => None,
+            };
+
+            let Ok(mut state) = self.state.lock() else {
+                return Some(Err(SdkError::runtime(
+                    "Internal stream state is unavailable.",
+                )
+                .into_pyerr()));
+            };
+            let value = match chunk {
+                Some(chunk) => {
+                    state.text.push_str(&chunk);
+                    match parse_partial_json(&state.text) {
+                        Some(value) => value,
+                        None => continue,
+                    }
+                }
+                // An invalid reply keeps raising, like a failed `TextStream`.
+                None => match parse_json_output(&state.text) {
+                    Ok(value) => {
+                        state.finished = true;
+                        value
+                    }
+                    Err(err) => return Some(Err(err.into_pyerr())),
+                },
+            };
+            if state.last.as_ref() != Some(&value) {
+                let object = json_to_py(py, &value);
+                state.last = Some(value);
+                return Some(object);
+            }
+        }
+    }
+
+    /// Stop the stream. Later iteration raises ``StopIteration``.
+    fn cancel(&self) {
+        self.stream.cancel();
+        if let Ok(mut state) = self.state.lock() {
+            state.finished = true;
+        }
+    }
+
+    /// The raw text received so far.
+    #[getter]
+    fn text(&self) -> String {
+        self.state
+            .lock()
+            .map(|state| state.text.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Start a structured-output stream, backing `Provider.stream_object()`.
+pub(crate) fn run_object_stream(
+    provider: &Provider,
+    params: GenerationParams,
+) -> PyResult<ObjectStream> {
+    Ok(ObjectStream {
+        stream: stream::run(provider, params, StreamSettings::default())?,
+        state: Mutex::new(ObjectStreamState::default()),
+    })
+}
+
+/// A `schema`/`response_format` argument resolved to a response format.
+///
+/// `model` is set when the format came from a Pydantic model class, so the
+/// reply can be validated into an instance of it.
+pub(crate) struct ResolvedFormat<'py> {
+    pub format: Value,
+    pub model: Option<Bound<'py, PyAny>>,
+}
+
+/// Resolve a Pydantic model class (anything with `model_json_schema()`) or a
+/// JSON value into a response format.
+///
+/// Pydantic schemas are sent with `strict: false`: they neither set
+/// `additionalProperties: false` nor list optional fields as required, both of
+/// which strict mode demands. The reply is validated client-side instead.
+///
+/// Classes without `model_json_schema` raise `TypeError`; any other object
+/// goes through `wrap` after conversion to JSON.
+pub(crate) fn resolve_format<'py>(
+    obj: &Bound<'py, PyAny>,
+    wrap: impl FnOnce(Value) -> Result<Value, SdkError>,
+) -> PyResult<ResolvedFormat<'py>> {
+    if obj.hasattr("model_json_schema")? {
+        let schema = py_to_json(&obj.call_method0("model_json_schema")?)?;
+        let name: String = obj.getattr("__name__")?.extract()?;
+        let format = build_json_schema_format(&schema_name(&name), schema, false)
+            .map_err(SdkError::into_pyerr)?;
+        return Ok(ResolvedFormat {
+            format,
+            model: Some(obj.clone()),
+        });
+    }
+    if obj.is_instance_of::<PyType>() {
+        return Err(SdkError::type_error(format!(
+            "{} has no model_json_schema(); pass a Pydantic model class or a JSON Schema dict.",
+            obj.getattr("__name__")?
+        ))
+        .into_pyerr());
+    }
+
+    let format = wrap(py_to_json(obj)?).map_err(SdkError::into_pyerr)?;
+    Ok(ResolvedFormat {
+        format,
+        model: None,
+    })
+}
+
+/// Turn a class name into a valid schema name, e.g. `Page[Item]` -> `Page_Item_`.
+pub fn schema_name(raw: &str) -> String {
+    let name: String = raw
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(64)
+        .collect();
+    if name.is_empty() {
+        "response".to_string()
+    } else {
+        name
+    }
+}
+
+/// Build a ``response_format`` for structured outputs.
+///
+/// Wraps a JSON Schema in the ``{"type": "json_schema", "json_schema": ...}``
+/// envelope expected by OpenAI-compatible APIs.
+///
+/// Args:
+///     name (str): Schema name (letters, digits, ``_`` or ``-``; max 64).
+///     schema (dict): JSON Schema with a ``type`` key.
+///     strict (bool): Ask the model to follow the schema exactly.
+///         Defaults to ``True``.
+///
+/// Returns:
+///     dict: A value for the ``response_format`` kwarg.
+///
+/// Raises:
+///     ValueError: If ``name`` or ``schema`` is invalid.
+#[pyfunction]
+#[pyo3(signature = (name, schema, strict=true))]
+#[pyo3(text_signature = "(name, schema, strict=True)")]
+pub fn json_schema(
+    py: Python<'_>,
+    name: &str,
+    schema: &Bound<'_, PyAny>,
+    strict: bool,
+) -> PyResult<Py<PyAny>> {
+    let schema = py_to_json(schema)?;
+    let format = build_json_schema_format(name, schema, strict).map_err(SdkError::into_pyerr)?;
+    json_to_py(py, &format)
+}
@@ -0,0 +1,134 @@
+use crate::errors::SdkError;
+use crate::models::{ChatMessage, MessageContent};
+use crate::provider::{extract_messages, messages_to_py};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyList, PyTuple};
+use serde_json::Value;
+
+/// Accepted values for the `strategy` argument of `trim_messages`.
+pub const TRIM_STRATEGIES: [&str; 1] = ["drop_oldest"];
+
+/// Approximate per-message overhead for role and formatting tokens.
+const MESSAGE_OVERHEAD_TOKENS: u64 = 4;
+
+/// Flat estimate for a non-text content part such as an image or file.
+const NON_TEXT_PART_TOKENS: u64 = 85;
+
+/// Estimate the token count of a single message.
+///
+/// Uses the common chars/4 heuristic for text plus a small per-message
+/// overhead. Non-text parts count as a flat amount rather than by the size of
+/// their base64 payload.
+pub fn estimate_message_tokens(message: &ChatMessage) -> u64 {
+    let non_text_parts = match &message.content {
+        MessageContent::Text(_) => 0,
+        MessageContent::Parts(parts) => parts
+            .iter()
+            .filter(|part| part.get("type").and_then(Value::as_str) != Some("text"))
+            .count() as u64,
+    };
+    let chars = message.content.to_text().chars().count()
+        + message
+            .name
+            .as_deref()
+            .map_or(0, |name| name.chars().count());
+
+    (chars as u64).div_ceil(4) + non_text_parts * NON_TEXT_PART_TOKENS + MESSAGE_OVERHEAD_TOKENS
+}
+
+/// Estimate the total token count of a conversation.
+pub fn estimate_tokens(messages: &[ChatMessage]) -> u64 {
+    messages.iter().map(estimate_message_tokens).sum()
+}
+
+/// Drop whole messages from the oldest end until the estimate fits `max_tokens`.
+///
+/// System messages (when `keep_system`) and the latest user message are never
+/// dropped, so the result can still exceed the budget if those alone do.
+/// Returns the kept messages and their estimated token count.
+pub fn trim_history(
+    messages: Vec<ChatMessage>,
+    max_tokens: u64,
+    keep_system: bool,
+) -> (Vec<ChatMessage>, u64) {
+    let last_user = messages.iter().rposition(|m| m.role == "user");
+    let mut total = estimate_tokens(&messages);
+    let mut keep = vec![true; messages.len()];
+
+    for (index, message) in messages.iter().enumerate() {
+        if total <= max_tokens {
+            break;
+        }
+        let protected = Some(index) == last_user || (keep_system && message.role == "system");
+        if !protected {
+            keep[index] = false;
+            total -= estimate_message_tokens(message);
+        }
+    }
+
+    let trimmed = messages
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(message, keep)| keep.then_some(message))
+        .collect();
+    (trimmed, total)
+}
+
+/// Trim a conversation to fit an approximate token budget.
+///
+/// Token counts are estimated with a chars/4 heuristic, so leave some
+/// headroom below the model's real context window.
+///
+/// Args:
+///     messages (list[dict | Message]): The conversation history.
+///     max_tokens (int): Token budget for the returned messages.
+///     keep_system (bool): Never drop ``system`` messages. Defaults to
+///         ``True``.
+///     strategy (str): Trimming strategy. Only ``"drop_oldest"`` is
+///         supported: whole messages are dropped from the oldest end. The
+///         latest user message is always kept.
+///
+/// Returns:
+///     tuple[list[dict], int]: The trimmed messages as dicts and their
+///     estimated token count.
+///
+/// Raises:
+///     ValueError: If a message is malformed or ``strategy`` is unknown.
+#[pyfunction]
+#[pyo3(signature = (messages, max_tokens, keep_system=true, strategy="drop_oldest"))]
+#[pyo3(text_signature = "(messages, max_tokens, keep_system=True, strategy=\"drop_oldest\")")]
+pub fn trim_messages<'py>(
+    py: Python<'py>,
+    messages: &Bound<'py, PyList>,
+    max_tokens: u64,
+    keep_system: bool,
+    strategy: &str,
+) -> PyResult<Bound<'py, PyTuple>> {
+    if !TRIM_STRATEGIES.contains(&strategy) {
+        return Err(SdkError::value(format!(
+            "'strategy' must be one of {}, got '{}'.",
+            TRIM_STRATEGIES.join(", "),
+            strategy
+        ))
+        .into_pyerr());
+    }
+
+    let (trimmed, total) = trim_history(extract_messages(messages)?, max_tokens, keep_system);
+    (messages_to_py(py, &trimmed)?, total).into_pyobject(py)
+}
+
+/// Read the `auto_trim` token budget.
+///
+/// `bool` is rejected explicitly: it is an `int` subclass in Python, so
+/// `auto_trim=True` would otherwise become a budget of one token.
+pub(crate) fn extract_trim_budget(obj: &Bound<'_, PyAny>) -> PyResult<u64> {
+    if obj.is_instance_of::<PyBool>() {
+        return Err(SdkError::value(
+            "'auto_trim' takes a token budget, e.g. auto_trim=8000, not a bool.",
+        )
+        .into_pyerr());
+    }
+    obj.extract().map_err(|_| {
+        SdkError::value("'auto_trim' must be a non-negative integer token budget.").into_pyerr()
+    })
+}
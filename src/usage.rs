@@ -0,0 +1,205 @@
+use crate::models::Usage;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// Token and cost counts summed over calls.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UsageTotals {
+    pub calls: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
+    pub cached_prompt_tokens: u64,
+    pub reasoning_tokens: u64,
+    /// `None` until a call reports its cost.
+    pub cost_usd: Option<f64>,
+}
+
+impl UsageTotals {
+    fn add(&mut self, usage: Option<&Usage>) {
+        self.calls += 1;
+        let Some(usage) = usage else {
+            return;
+        };
+        self.prompt_tokens += usage.prompt_tokens.unwrap_or(0);
+        self.completion_tokens += usage.completion_tokens.unwrap_or(0);
+        self.total_tokens += usage.total_tokens.unwrap_or(0);
+        self.cached_prompt_tokens += usage
+            .prompt_tokens_details
+            .as_ref()
+            .and_then(|details| details.cached_tokens)
+            .unwrap_or(0);
+        self.reasoning_tokens += usage
+            .completion_tokens_details
+            .as_ref()
+            .and_then(|details| details.reasoning_tokens)
+            .unwrap_or(0);
+        if let Some(cost) = usage.cost {
+            *self.cost_usd.get_or_insert(0.0) += cost;
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.calls += other.calls;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+        self.cached_prompt_tokens += other.cached_prompt_tokens;
+        self.reasoning_tokens += other.reasoning_tokens;
+        if let Some(cost) = other.cost_usd {
+            *self.cost_usd.get_or_insert(0.0) += cost;
+        }
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("calls", self.calls)?;
+        dict.set_item("prompt_tokens", self.prompt_tokens)?;
+        dict.set_item("completion_tokens", self.completion_tokens)?;
+        dict.set_item("total_tokens", self.total_tokens)?;
+        dict.set_item("cached_prompt_tokens", self.cached_prompt_tokens)?;
+        dict.set_item("reasoning_tokens", self.reasoning_tokens)?;
+        dict.set_item("cost_usd", self.cost_usd)?;
+        Ok(dict)
+    }
+}
+
+/// Sums the usage of every call made with it, grouped by model.
+///
+/// Attach one to a provider with ``Provider.track_usage()``, or pass it to
+/// a single call as ``usage_tracker``. Each call that completes adds one to
+/// ``calls`` and its reported usage to the token counts, whether or not it
+/// asked for ``include_usage``; streams ask the provider for usage when a
+/// tracker is set. Calls that fail are not counted. A tracker may be shared
+/// by providers and threads.
+///
+/// Example:
+///     >>> tracker = UsageTracker()
+///     >>> provider.track_usage(tracker)
+///     >>> provider.generate_text("Hello")
+///     >>> tracker.total_tokens
+///     21
+#[pyclass(frozen, from_py_object, module = "rusty_agent_sdk")]
+#[derive(Clone, Default)]
+pub struct UsageTracker {
+    models: Arc<Mutex<BTreeMap<String, UsageTotals>>>,
+}
+
+impl UsageTracker {
+    /// Count a completed call to `model`, with its `usage` if reported.
+    pub fn record(&self, model: &str, usage: Option<&Usage>) {
+        if let Ok(mut models) = self.models.lock() {
+            models.entry(model.to_string()).or_default().add(usage);
+        }
+    }
+
+    /// The counts for each model called so far.
+    pub fn model_totals(&self) -> BTreeMap<String, UsageTotals> {
+        self.models
+            .lock()
+            .map(|models| models.clone())
+            .unwrap_or_default()
+    }
+
+    /// The counts summed over every model.
+    pub fn totals(&self) -> UsageTotals {
+        self.model_totals()
+            .values()
+            .fold(UsageTotals::default(), |mut totals, model| {
+                totals.merge(model);
+                totals
+            })
+    }
+}
+
+#[pymethods]
+impl UsageTracker {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Calls counted, across every model.
+    #[getter]
+    fn calls(&self) -> u64 {
+        self.totals().calls
+    }
+
+    #[getter]
+    fn prompt_tokens(&self) -> u64 {
+        self.totals().prompt_tokens
+    }
+
+    #[getter]
+    fn completion_tokens(&self) -> u64 {
+        self.totals().completion_tokens
+    }
+
+    #[getter]
+    fn total_tokens(&self) -> u64 {
+        self.totals().total_tokens
+    }
+
+    /// Prompt tokens served from the provider's prompt cache.
+    #[getter]
+    fn cached_prompt_tokens(&self) -> u64 {
+        self.totals().cached_prompt_tokens
+    }
+
+    /// Completion tokens spent on reasoning.
+    #[getter]
+    fn reasoning_tokens(&self) -> u64 {
+        self.totals().reasoning_tokens
+    }
+
+    /// Cost in USD of the calls that reported one, or ``None`` if none
+    /// did. See ``include_cost``.
+    #[getter]
+    fn cost_usd(&self) -> Option<f64> {
+        self.totals().cost_usd
+    }
+
+    /// The counts for each model, keyed by the model that answered.
+    ///
+    /// Returns:
+    ///     dict[str, dict]: The same keys as :meth:`to_dict`, without
+    ///     ``models``.
+    fn by_model<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for (model, totals) in self.model_totals() {
+            dict.set_item(model, totals.to_dict(py)?)?;
+        }
+        Ok(dict)
+    }
+
+    /// Set every count back to zero.
+    fn reset(&self) {
+        if let Ok(mut models) = self.models.lock() {
+            models.clear();
+        }
+    }
+
+    /// The totals and per-model counts as a dict, for exporting to a
+    /// metrics system.
+    ///
+    /// Returns:
+    ///     dict: ``calls``, ``prompt_tokens``, ``completion_tokens``,
+    ///     ``total_tokens``, ``cached_prompt_tokens``, ``reasoning_tokens``
+    ///     and ``cost_usd`` summed over every model, and ``models`` with
+    ///     the same counts for each model.
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = self.totals().to_dict(py)?;
+        dict.set_item("models", self.by_model(py)?)?;
+        Ok(dict)
+    }
+
+    fn __repr__(&self) -> String {
+        let totals = self.totals();
+        format!(
+            "UsageTracker(calls={}, total_tokens={}, cost_usd={:?})",
+            totals.calls, totals.total_tokens, totals.cost_usd
+        )
+    }
+}
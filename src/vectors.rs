@@ -0,0 +1,265 @@
+use crate::errors::SdkError;
+use pyo3::prelude::*;
+use std::cmp::Ordering;
+
+/// Accepted values for the `metric` argument of `similarity_search`.
+pub const METRICS: [&str; 3] = ["cosine", "dot", "euclidean"];
+
+/// How `similarity_search` scores a row against the query.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Metric {
+    /// Cosine similarity, highest first.
+    Cosine,
+    /// Dot product, highest first; the same ranking as cosine for
+    /// normalized vectors, without dividing by the norms.
+    Dot,
+    /// Euclidean distance, nearest first.
+    Euclidean,
+}
+
+impl Metric {
+    pub fn parse(value: &str) -> Result<Self, SdkError> {
+        match value {
+            "cosine" => Ok(Self::Cosine),
+            "dot" => Ok(Self::Dot),
+            "euclidean" => Ok(Self::Euclidean),
+            _ => Err(SdkError::value(format!(
+                "'metric' must be one of {}, got '{}'.",
+                METRICS.join(", "),
+                value
+            ))),
+        }
+    }
+
+    fn score(self, a: &[f64], b: &[f64]) -> f64 {
+        match self {
+            Self::Cosine => cosine(a, b),
+            Self::Dot => dot(a, b),
+            Self::Euclidean => a
+                .iter()
+                .zip(b)
+                .map(|(x, y)| (x - y) * (x - y))
+                .sum::<f64>()
+                .sqrt(),
+        }
+    }
+
+    /// Order `a` before `b` when it is the better match, breaking ties by
+    /// index so results are stable.
+    fn rank(self, a: &(usize, f64), b: &(usize, f64)) -> Ordering {
+        let by_score = match self {
+            Self::Cosine | Self::Dot => b.1.total_cmp(&a.1),
+            Self::Euclidean => a.1.total_cmp(&b.1),
+        };
+        by_score.then(a.0.cmp(&b.0))
+    }
+}
+
+/// Rows of equal length in one contiguous buffer, so a search walks memory
+/// in order instead of chasing a pointer per row.
+#[derive(Debug)]
+pub struct Matrix {
+    data: Vec<f64>,
+    dim: usize,
+}
+
+impl Matrix {
+    /// Copy `rows` into one buffer, failing if their lengths differ.
+    pub fn from_rows(rows: &[Vec<f64>]) -> Result<Self, SdkError> {
+        let dim = rows.first().map_or(0, Vec::len);
+        let mut data = Vec::with_capacity(dim * rows.len());
+        for (index, row) in rows.iter().enumerate() {
+            if row.len() != dim {
+                return Err(SdkError::value(format!(
+                    "Every row of 'matrix' must have the same length: row 0 has {} dimensions but row {} has {}.",
+                    dim,
+                    index,
+                    row.len()
+                )));
+            }
+            data.extend_from_slice(row);
+        }
+        Ok(Self { data, dim })
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len().checked_div(self.dim).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The `top_k` rows that best match `query` by `metric`, as
+    /// `(row index, score)` pairs from best to worst.
+    pub fn search(
+        &self,
+        query: &[f64],
+        top_k: usize,
+        metric: Metric,
+    ) -> Result<Vec<(usize, f64)>, SdkError> {
+        if self.is_empty() {
+            return Ok(Vec::new());
+        }
+        check_dims("query", query.len(), self.dim)?;
+        if top_k == 0 {
+            return Ok(Vec::new());
+        }
+        let mut scores: Vec<(usize, f64)> = self
+            .data
+            .chunks_exact(self.dim)
+            .map(|row| metric.score(query, row))
+            .enumerate()
+            .collect();
+        if top_k < scores.len() {
+            scores.select_nth_unstable_by(top_k - 1, |a, b| metric.rank(a, b));
+            scores.truncate(top_k);
+        }
+        scores.sort_unstable_by(|a, b| metric.rank(a, b));
+        Ok(scores)
+    }
+}
+
+fn check_dims(name: &str, len: usize, dim: usize) -> Result<(), SdkError> {
+    if len == dim {
+        return Ok(());
+    }
+    Err(SdkError::value(format!(
+        "'{}' has {} dimensions but the vectors it is compared with have {}.",
+        name, len, dim
+    )))
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// The cosine of the angle between `a` and `b`, or `0.0` if either is a
+/// zero vector.
+pub fn cosine(a: &[f64], b: &[f64]) -> f64 {
+    let norms = (dot(a, a) * dot(b, b)).sqrt();
+    if norms == 0.0 {
+        return 0.0;
+    }
+    dot(a, b) / norms
+}
+
+/// Scale `vector` to unit L2 norm in place. A zero vector has no direction
+/// and is left as it is; returns whether the vector was scaled.
+pub fn l2_normalize(vector: &mut [f64]) -> bool {
+    let norm = vector.iter().map(|x| x * x).sum::<f64>().sqrt();
+    if norm == 0.0 || !norm.is_finite() {
+        return false;
+    }
+    for x in vector.iter_mut() {
+        *x /= norm;
+    }
+    true
+}
+
+/// Scale vectors to unit length (L2 norm 1.0), e.g. embeddings from
+/// another library before comparing them by cosine similarity.
+///
+/// Zero vectors, and vectors holding ``inf`` or ``nan``, have no direction
+/// and are returned unchanged.
+///
+/// Args:
+///     vectors (Sequence[Sequence[float]]): The vectors to normalize.
+///
+/// Returns:
+///     list[list[float]]: The normalized vectors, in input order.
+///
+/// Raises:
+///     TypeError: If ``vectors`` is not a sequence of number sequences.
+#[pyfunction]
+#[pyo3(text_signature = "(vectors)")]
+pub fn normalize(py: Python<'_>, mut vectors: Vec<Vec<f64>>) -> Vec<Vec<f64>> {
+    py.detach(|| {
+        for vector in &mut vectors {
+            l2_normalize(vector);
+        }
+    });
+    vectors
+}
+
+/// The cosine similarity of two vectors, from ``-1.0`` (opposite) to
+/// ``1.0`` (same direction).
+///
+/// Args:
+///     a (Sequence[float]): The first vector.
+///     b (Sequence[float]): The second vector, of the same length.
+///
+/// Returns:
+///     float: The similarity, or ``0.0`` if either is a zero vector.
+///
+/// Raises:
+///     ValueError: If the vectors differ in length.
+#[pyfunction]
+#[pyo3(text_signature = "(a, b)")]
+pub fn cosine_similarity(a: Vec<f64>, b: Vec<f64>) -> PyResult<f64> {
+    check_dims("b", b.len(), a.len()).map_err(SdkError::into_pyerr)?;
+    Ok(cosine(&a, &b))
+}
+
+/// Find the rows of ``matrix`` most similar to ``query``, e.g. the
+/// embedded documents closest to an embedded question, without numpy.
+///
+/// Args:
+///     query (Sequence[float]): The vector to search for.
+///     matrix (Sequence[Sequence[float]]): The vectors to search, all of
+///         the query's length.
+///     top_k (int): How many matches to return. Defaults to ``5``.
+///     metric (str): ``"cosine"`` (the default) or ``"dot"``, highest
+///         score first, or ``"euclidean"`` distance, nearest first.
+///
+/// Returns:
+///     list[tuple[int, float]]: ``(row index, score)`` pairs, best match
+///     first; fewer than ``top_k`` if ``matrix`` has fewer rows.
+///
+/// Raises:
+///     ValueError: If ``metric`` is unknown, or the rows of ``matrix``
+///         differ in length from each other or from ``query``.
+#[pyfunction]
+#[pyo3(signature = (query, matrix, top_k=5, metric="cosine"))]
+#[pyo3(text_signature = "(query, matrix, top_k=5, metric=\"cosine\")")]
+pub fn similarity_search(
+    py: Python<'_>,
+    query: Vec<f64>,
+    matrix: Vec<Vec<f64>>,
+    top_k: usize,
+    metric: &str,
+) -> PyResult<Vec<(usize, f64)>> {
+    let metric = Metric::parse(metric).map_err(SdkError::into_pyerr)?;
+    py.detach(|| Matrix::from_rows(&matrix)?.search(&query, top_k, metric))
+        .map_err(SdkError::into_pyerr)
+}
+
+/// :func:`similarity_search` for several queries against one matrix,
+/// which is converted once and searched with the GIL released.
+///
+/// Returns:
+///     list[list[tuple[int, float]]]: One result list per query, in
+///     query order.
+///
+/// Raises:
+///     ValueError: As for :func:`similarity_search`.
+#[pyfunction]
+#[pyo3(signature = (queries, matrix, top_k=5, metric="cosine"))]
+#[pyo3(text_signature = "(queries, matrix, top_k=5, metric=\"cosine\")")]
+pub fn similarity_search_many(
+    py: Python<'_>,
+    queries: Vec<Vec<f64>>,
+    matrix: Vec<Vec<f64>>,
+    top_k: usize,
+    metric: &str,
+) -> PyResult<Vec<Vec<(usize, f64)>>> {
+    let metric = Metric::parse(metric).map_err(SdkError::into_pyerr)?;
+    py.detach(|| {
+        let matrix = Matrix::from_rows(&matrix)?;
+        queries
+            .iter()
+            .map(|query| matrix.search(query, top_k, metric))
+            .collect::<Result<Vec<_>, _>>()
+    })
+    .map_err(SdkError::into_pyerr)
+}
@@ -0,0 +1,254 @@
+mod common;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyDict};
+use rusty_agent_sdk::Provider;
+use rusty_agent_sdk::internal::{
+    StreamEvent, StreamTranslator, parse_chat_response_full, parse_sse_event, to_chat_response,
+    to_messages_request,
+};
+use serde_json::json;
+
+const GENERATED: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"id\":\"msg_1\",\"type\":\"message\",\"role\":\"assistant\",\"model\":\"claude-test\",\
+\"content\":[{\"type\":\"thinking\",\"thinking\":\"Hmm.\"},{\"type\":\"text\",\"text\":\"Hi\"},{\"type\":\"text\",\"text\":\" there\"}],\
+\"stop_reason\":\"end_turn\",\"usage\":{\"input_tokens\":5,\"output_tokens\":2}}";
+
+const STREAMED: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n\
+event: message_start\n\
+data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_2\",\"model\":\"claude-test\",\"usage\":{\"input_tokens\":7,\"output_tokens\":1}}}\n\n\
+event: content_block_start\n\
+data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n\
+event: ping\n\
+data: {\"type\":\"ping\"}\n\n\
+event: content_block_delta\n\
+data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hel\"}}\n\n\
+event: content_block_delta\n\
+data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"lo\"}}\n\n\
+event: content_block_stop\n\
+data: {\"type\":\"content_block_stop\",\"index\":0}\n\n\
+event: message_delta\n\
+data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"max_tokens\"},\"usage\":{\"output_tokens\":3}}\n\n\
+event: message_stop\n\
+data: {\"type\":\"message_stop\"}\n\n";
+
+/// `Provider.anthropic` pointed at a local server.
+fn anthropic<'py>(py: Python<'py>, base_url: &str) -> Bound<'py, PyAny> {
+    let kwargs = [("api_key", "test-key")].into_py_dict(py).unwrap();
+    let provider = py
+        .get_type::<Provider>()
+        .call_method("anthropic", ("claude-test",), Some(&kwargs))
+        .unwrap();
+    let kwargs = [("base_url", base_url)].into_py_dict(py).unwrap();
+    provider
+        .call_method("with_options", (), Some(&kwargs))
+        .unwrap()
+}
+
+#[test]
+fn request_moves_system_prompt_and_stop_and_defaults_max_tokens() {
+    let body = json!({
+        "model": "claude-test",
+        "messages": [
+            {"role": "system", "content": "Be brief."},
+            {"role": "developer", "content": [{"type": "text", "text": "No lists."}]},
+            {"role": "user", "content": [
+                {"type": "text", "text": "What is this?"},
+                {"type": "image_url", "image_url": {"url": "data:image/png;base64,AAAA"}},
+            ]},
+        ],
+        "stream": true,
+        "stream_options": {"include_usage": true},
+        "temperature": 0.5,
+        "stop": "END",
+    });
+
+    let request = to_messages_request(body).unwrap();
+
+    assert_eq!(
+        request,
+        json!({
+            "model": "claude-test",
+            "system": "Be brief.\n\nNo lists.",
+            "messages": [{"role": "user", "content": [
+                {"type": "text", "text": "What is this?"},
+                {"type": "image", "source": {"type": "base64", "media_type": "image/png", "data": "AAAA"}},
+            ]}],
+            "max_tokens": 4096,
+            "stream": true,
+            "temperature": 0.5,
+            "stop_sequences": ["END"],
+        })
+    );
+}
+
+#[test]
+fn request_rejects_what_the_messages_api_cannot_express() {
+    let message = |body: serde_json::Value| format!("{:?}", to_messages_request(body).unwrap_err());
+
+    let error = message(json!({"model": "m", "messages": [], "seed": 1}));
+    assert!(error.contains("'seed' is not supported"), "{}", error);
+    let error = message(json!({"model": "m", "messages": [], "n": 2}));
+    assert!(error.contains("'n' greater than 1"), "{}", error);
+    let error = message(json!({
+        "model": "m",
+        "messages": [{"role": "tool", "content": "42", "tool_call_id": "call_1"}],
+    }));
+    assert!(error.contains("messages[0] has role 'tool'"), "{}", error);
+}
+
+#[test]
+fn response_translates_content_blocks_usage_and_stop_reason() {
+    let body = GENERATED.split(|&b| b == b'\n').next_back().unwrap();
+    let chat = to_chat_response(std::str::from_utf8(body).unwrap()).unwrap();
+    let result = parse_chat_response_full(&chat).unwrap();
+
+    assert_eq!(result.text, "Hi there");
+    assert_eq!(result.reasoning.as_deref(), Some("Hmm."));
+    assert_eq!(result.finish_reason.as_deref(), Some("stop"));
+    assert_eq!(result.id.as_deref(), Some("msg_1"));
+    let usage = result.usage.unwrap();
+    assert_eq!(
+        (
+            usage.prompt_tokens,
+            usage.completion_tokens,
+            usage.total_tokens
+        ),
+        (Some(5), Some(2), Some(7))
+    );
+}
+
+#[test]
+fn stream_events_translate_to_chat_completion_chunks() {
+    let mut translator = StreamTranslator::default();
+    let events: Vec<_> = std::str::from_utf8(STREAMED)
+        .unwrap()
+        .split_once("\r\n\r\n")
+        .unwrap()
+        .1
+        .split("\n\n")
+        .filter(|event| !event.is_empty())
+        .filter_map(|event| translator.translate(event))
+        .flat_map(|event| parse_sse_event(&event).unwrap())
+        .collect();
+
+    let texts: Vec<_> = events
+        .iter()
+        .filter_map(|event| match event {
+            StreamEvent::Content(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(texts, ["Hel", "lo"]);
+    assert_eq!(events.last(), Some(&StreamEvent::Done));
+
+    let finish = events.iter().find_map(|event| match event {
+        StreamEvent::Metadata(meta) if meta.finish_reason.is_some() => Some(meta),
+        _ => None,
+    });
+    let finish = finish.expect("message_delta should carry metadata");
+    assert_eq!(finish.finish_reason.as_deref(), Some("length"));
+    let usage = finish.usage.as_ref().unwrap();
+    assert_eq!(
+        (usage.prompt_tokens, usage.completion_tokens),
+        (Some(7), Some(3))
+    );
+}
+
+#[test]
+fn generate_text_speaks_the_messages_api() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let (base_url, heads) = common::serve_sequence(vec![GENERATED, GENERATED]);
+        let provider = anthropic(py, &base_url);
+
+        let kwargs = [("system_prompt", "Be brief.")].into_py_dict(py).unwrap();
+        let text: String = provider
+            .call_method("generate_text", ("Hello",), Some(&kwargs))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(text, "Hi there");
+
+        let request = heads.recv().unwrap();
+        assert!(request.starts_with("POST /v1/messages "), "{}", request);
+        assert!(request.contains("x-api-key: test-key"), "{}", request);
+        assert!(
+            request.contains("anthropic-version: 2023-06-01"),
+            "{}",
+            request
+        );
+        assert!(!request.contains("Bearer"), "{}", request);
+        assert!(request.contains("\"system\":\"Be brief.\""), "{}", request);
+        assert!(request.contains("\"max_tokens\":4096"), "{}", request);
+
+        let kwargs = [("include_usage", true)].into_py_dict(py).unwrap();
+        let result = provider
+            .call_method("generate_text", ("Hello",), Some(&kwargs))
+            .unwrap();
+        let finish: String = result.getattr("finish_reason").unwrap().extract().unwrap();
+        assert_eq!(finish, "stop");
+        let total: u64 = result.getattr("total_tokens").unwrap().extract().unwrap();
+        assert_eq!(total, 7);
+        let kind: String = result
+            .getattr("raw")
+            .unwrap()
+            .get_item("type")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(kind, "message", "raw is the Messages API response");
+    });
+}
+
+#[test]
+fn stream_text_speaks_the_messages_api() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let (base_url, heads) = common::serve_sequence(vec![STREAMED]);
+        let provider = anthropic(py, &base_url);
+
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("include_usage", true).unwrap();
+        let stream = provider
+            .call_method("stream_text", ("Hello",), Some(&kwargs))
+            .unwrap();
+        let chunks: Vec<String> = stream
+            .try_iter()
+            .unwrap()
+            .map(|chunk| chunk.unwrap().extract().unwrap())
+            .collect();
+        assert_eq!(chunks.concat(), "Hello");
+
+        let finish: String = stream.getattr("finish_reason").unwrap().extract().unwrap();
+        assert_eq!(finish, "length");
+        let total: u64 = stream.getattr("total_tokens").unwrap().extract().unwrap();
+        assert_eq!(total, 10);
+
+        let request = heads.recv().unwrap();
+        assert!(request.starts_with("POST /v1/messages "), "{}", request);
+        assert!(!request.contains("stream_options"), "{}", request);
+    });
+}
+
+#[test]
+fn unsupported_options_raise_before_sending() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = anthropic(py, "http://127.0.0.1:9/v1");
+        let kwargs = [("seed", 1)].into_py_dict(py).unwrap();
+
+        let err = provider
+            .call_method("generate_text", ("Hello",), Some(&kwargs))
+            .unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py));
+        let err = provider
+            .call_method("stream_text", ("Hello",), Some(&kwargs))
+            .unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py));
+    });
+}
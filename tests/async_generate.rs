@@ -0,0 +1,149 @@
+mod common;
+
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::ffi::CString;
+use std::time::{Duration, Instant};
+
+const HEAD: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n";
+
+const BODY: &[u8] = b"{\"id\":\"gen-1\",\"model\":\"test-model\",\"choices\":[{\"message\":\
+{\"role\":\"assistant\",\"content\":\"Hi there\"},\"finish_reason\":\"stop\"}],\
+\"usage\":{\"prompt_tokens\":3,\"completion_tokens\":2,\"total_tokens\":5}}";
+
+/// Coroutines driven with `asyncio.run` from the tests.
+const HELPERS: &str = r#"
+import asyncio
+
+async def generate(provider, **kwargs):
+    return await provider.agenerate_text("Hello", **kwargs)
+
+async def generate_and_count_ticks(provider):
+    ticks = 0
+    future = provider.agenerate_text("Hello")
+    while not future.done():
+        ticks += 1
+        await asyncio.sleep(0.01)
+    return await future, ticks
+
+async def cancel(provider):
+    task = asyncio.ensure_future(provider.agenerate_text("Hello"))
+    await asyncio.sleep(0.1)
+    task.cancel()
+    try:
+        await task
+    except asyncio.CancelledError:
+        return True
+    return False
+"#;
+
+fn run<'py>(
+    py: Python<'py>,
+    helper: &str,
+    provider: &Bound<'py, PyAny>,
+    kwargs: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let code = CString::new(HELPERS).unwrap();
+    let globals = PyDict::new(py);
+    py.run(&code, Some(&globals), None).unwrap();
+    let coroutine = globals
+        .get_item(helper)
+        .unwrap()
+        .unwrap()
+        .call((provider,), kwargs)?;
+    py.import("asyncio")?.call_method1("run", (coroutine,))
+}
+
+#[test]
+fn agenerate_text_resolves_to_text_without_blocking_the_loop() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let base_url = common::serve_once(HEAD, Duration::from_millis(200), BODY);
+        let provider = common::provider(py, &base_url);
+
+        let (text, ticks): (String, usize) = run(py, "generate_and_count_ticks", &provider, None)
+            .expect("generation should succeed")
+            .extract()
+            .unwrap();
+        assert_eq!(text, "Hi there");
+        assert!(ticks >= 5, "the loop only ran {} times", ticks);
+    });
+}
+
+#[test]
+fn agenerate_text_with_include_usage_resolves_to_generate_result() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let base_url = common::serve_once(HEAD, Duration::ZERO, BODY);
+        let provider = common::provider(py, &base_url);
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("include_usage", true).unwrap();
+
+        let result = run(py, "generate", &provider, Some(&kwargs)).unwrap();
+        let text: String = result.getattr("text").unwrap().extract().unwrap();
+        let tokens: u64 = result.getattr("total_tokens").unwrap().extract().unwrap();
+        assert_eq!(text, "Hi there");
+        assert_eq!(tokens, 5);
+    });
+}
+
+#[test]
+fn agenerate_text_raises_api_errors_when_awaited() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let base_url = common::serve_once(
+            b"HTTP/1.1 401 Unauthorized\r\nContent-Type: application/json\r\nContent-Length: 36\r\nConnection: close\r\n\r\n",
+            Duration::ZERO,
+            b"{\"error\":{\"message\":\"Bad API key\"}}\n",
+        );
+        let provider = common::provider(py, &base_url);
+
+        let err = run(py, "generate", &provider, None).expect_err("401 should fail");
+        assert!(err.is_instance_of::<PyRuntimeError>(py));
+        assert!(err.to_string().contains("Bad API key"));
+        let status_code: u16 = err
+            .value(py)
+            .getattr("status_code")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(status_code, 401);
+    });
+}
+
+#[test]
+fn cancelling_agenerate_text_aborts_the_request() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let base_url = common::serve_once(HEAD, Duration::from_secs(5), BODY);
+        let provider = common::provider(py, &base_url);
+
+        let started = Instant::now();
+        let cancelled: bool = run(py, "cancel", &provider, None)
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert!(cancelled);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    });
+}
+
+#[test]
+fn agenerate_text_requires_a_running_loop() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider(py, "http://127.0.0.1:9/v1");
+
+        let err = provider
+            .call_method1("agenerate_text", ("Hello",))
+            .expect_err("no loop is running");
+        assert!(err.is_instance_of::<PyRuntimeError>(py));
+    });
+}
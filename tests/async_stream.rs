@@ -0,0 +1,162 @@
+mod common;
+
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::ffi::CString;
+use std::time::{Duration, Instant};
+
+const HEAD: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n";
+
+const BODY: &[u8] = b"data: {\"id\":\"gen-1\",\"choices\":[{\"delta\":{\"content\":\"Hello\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\" there\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n\
+data: {\"choices\":[],\"usage\":{\"prompt_tokens\":3,\"completion_tokens\":2,\"total_tokens\":5}}\n\n\
+data: [DONE]\n\n";
+
+/// Coroutines driven with `asyncio.run` from the tests.
+const HELPERS: &str = r#"
+import asyncio
+
+async def collect(stream):
+    return [chunk async for chunk in stream]
+
+async def errors(stream):
+    messages = []
+    for _ in range(2):
+        try:
+            await stream.__anext__()
+        except RuntimeError as e:
+            messages.append(str(e))
+    return messages
+
+async def cancel_consumer(stream):
+    task = asyncio.ensure_future(collect(stream))
+    await asyncio.sleep(0.1)
+    task.cancel()
+    try:
+        await task
+    except asyncio.CancelledError:
+        return True
+    return False
+"#;
+
+fn run<'py>(
+    py: Python<'py>,
+    helper: &str,
+    stream: &Bound<'py, PyAny>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let code = CString::new(HELPERS).unwrap();
+    let globals = PyDict::new(py);
+    py.run(&code, Some(&globals), None).unwrap();
+    let coroutine = globals
+        .get_item(helper)
+        .unwrap()
+        .unwrap()
+        .call1((stream,))?;
+    py.import("asyncio")?.call_method1("run", (coroutine,))
+}
+
+fn stream<'py>(
+    py: Python<'py>,
+    head: &'static [u8],
+    stall: Duration,
+    body: &'static [u8],
+) -> Bound<'py, PyAny> {
+    let base_url = common::serve_once(head, stall, body);
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("include_usage", true).unwrap();
+    common::provider(py, &base_url)
+        .call_method("astream_text", ("Hello",), Some(&kwargs))
+        .expect("stream should start")
+}
+
+fn getter<T: for<'a, 'py> FromPyObject<'a, 'py>>(stream: &Bound<'_, PyAny>, name: &str) -> T {
+    stream
+        .getattr(name)
+        .unwrap()
+        .extract()
+        .unwrap_or_else(|_| panic!("{} should extract", name))
+}
+
+#[test]
+fn async_for_yields_chunks_and_populates_metadata() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = stream(py, HEAD, Duration::ZERO, BODY);
+        assert!(stream.getattr("finish_reason").unwrap().is_none());
+
+        let chunks: Vec<String> = run(py, "collect", &stream).unwrap().extract().unwrap();
+
+        assert_eq!(chunks, vec!["Hello", " there"]);
+        assert_eq!(getter::<String>(&stream, "finish_reason"), "stop");
+        assert_eq!(getter::<String>(&stream, "response_id"), "gen-1");
+        assert_eq!(getter::<u64>(&stream, "completion_tokens"), 2);
+        assert_eq!(getter::<u64>(&stream, "chunk_count"), 2);
+        assert_eq!(getter::<String>(&stream, "partial_text"), "Hello there");
+    });
+}
+
+#[test]
+fn failed_async_stream_keeps_raising_its_error() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = stream(
+            py,
+            b"HTTP/1.1 401 Unauthorized\r\nContent-Type: application/json\r\nContent-Length: 36\r\nConnection: close\r\n\r\n",
+            Duration::ZERO,
+            b"{\"error\":{\"message\":\"bad api key\"}}\n",
+        );
+
+        let messages: Vec<String> = run(py, "errors", &stream).unwrap().extract().unwrap();
+
+        assert_eq!(messages.len(), 2);
+        assert!(messages.iter().all(|m| m.contains("bad api key")));
+        assert!(
+            stream
+                .getattr("error")
+                .unwrap()
+                .is_instance_of::<PyRuntimeError>()
+        );
+    });
+}
+
+#[test]
+fn cancelling_the_consumer_aborts_the_stream() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = stream(py, HEAD, Duration::from_secs(5), BODY);
+
+        let started = Instant::now();
+        let cancelled: bool = run(py, "cancel_consumer", &stream)
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert!(cancelled);
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // The worker is gone, so the stream now ends straight away.
+        let chunks: Vec<String> = run(py, "collect", &stream).unwrap().extract().unwrap();
+        assert!(chunks.is_empty());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    });
+}
+
+#[test]
+fn cancel_ends_the_async_stream() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = stream(py, HEAD, Duration::from_secs(5), BODY);
+        stream.call_method0("cancel").unwrap();
+
+        let started = Instant::now();
+        let chunks: Vec<String> = run(py, "collect", &stream).unwrap().extract().unwrap();
+        assert!(chunks.is_empty());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    });
+}
@@ -0,0 +1,84 @@
+mod common;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rusty_agent_sdk::internal::parse_chat_response_full;
+
+const UNAVAILABLE: &[u8] =
+    b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+const OK: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"choices\":[{\"message\":{\"role\":\"assistant\",\"content\":\"ok\"}}]}";
+
+fn timings<'py>(result: &Bound<'py, PyAny>) -> (f64, u32, f64) {
+    let timings = result.getattr("timings").unwrap();
+    (
+        timings
+            .get_item("total_duration_ms")
+            .unwrap()
+            .extract()
+            .unwrap(),
+        timings.get_item("attempts").unwrap().extract().unwrap(),
+        timings
+            .get_item("retry_wait_ms")
+            .unwrap()
+            .extract()
+            .unwrap(),
+    )
+}
+
+fn generate<'py>(provider: &Bound<'py, PyAny>) -> Bound<'py, PyAny> {
+    let kwargs = PyDict::new(provider.py());
+    kwargs.set_item("include_usage", true).unwrap();
+    provider
+        .call_method("generate_text", ("Hello",), Some(&kwargs))
+        .unwrap()
+}
+
+#[test]
+fn a_single_attempt_has_no_retry_wait() {
+    let (base_url, _heads) = common::serve_sequence(vec![OK, OK]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider(py, &base_url);
+        let first = generate(&provider);
+        let (total, attempts, retry_wait) = timings(&first);
+        assert!(total > 0.0);
+        assert_eq!((attempts, retry_wait), (1, 0.0));
+
+        let second = generate(&provider);
+        assert!(first.eq(&second).unwrap(), "timings do not affect equality");
+        let logged = first
+            .call_method0("to_dict")
+            .unwrap()
+            .get_item("timings")
+            .unwrap();
+        assert!(!logged.is_none());
+    });
+}
+
+#[test]
+fn retries_are_counted_and_their_waits_split_out() {
+    let (base_url, _heads) = common::serve_sequence(vec![UNAVAILABLE, UNAVAILABLE, OK]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("retry_backoff_ms", 20)
+        });
+        let (total, attempts, retry_wait) = timings(&generate(&provider));
+        assert_eq!(attempts, 3);
+        assert!(retry_wait > 0.0);
+        assert!(total >= retry_wait, "{} < {}", total, retry_wait);
+    });
+}
+
+#[test]
+fn results_without_timing_report_none() {
+    let body = r#"{"choices":[{"message":{"content":"Hi"}}]}"#;
+
+    let result = parse_chat_response_full(body).expect("response should parse");
+
+    assert_eq!(result.timings, None);
+}
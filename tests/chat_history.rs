@@ -0,0 +1,81 @@
+use rusty_agent_sdk::internal::{
+    ChatMessage, HISTORY_FORMAT_VERSION, MessageContent, messages_from_json, messages_to_json,
+};
+
+fn sample_history() -> Vec<ChatMessage> {
+    vec![
+        ChatMessage {
+            role: "system".into(),
+            content: "Be brief.".into(),
+            ..Default::default()
+        },
+        ChatMessage {
+            role: "user".into(),
+            content: MessageContent::Parts(vec![
+                serde_json::json!({"type": "text", "text": "What is this?"}),
+                serde_json::json!({
+                    "type": "image_url",
+                    "image_url": {"url": "data:image/png;base64,iVBORw0KGgo=", "detail": "low"}
+                }),
+            ]),
+            name: Some("alice".into()),
+            ..Default::default()
+        },
+        ChatMessage {
+            role: "tool".into(),
+            content: "{\"temperature\": 21}".into(),
+            tool_call_id: Some("call_123".into()),
+            ..Default::default()
+        },
+        ChatMessage {
+            role: "assistant".into(),
+            content: "A cat.".into(),
+            ..Default::default()
+        },
+    ]
+}
+
+#[test]
+fn messages_json_round_trips_tool_messages_and_image_parts() {
+    let history = sample_history();
+
+    let json = messages_to_json(&history).expect("should serialise");
+    let restored = messages_from_json(&json).expect("should deserialise");
+
+    assert_eq!(restored, history);
+}
+
+#[test]
+fn messages_to_json_writes_versioned_document() {
+    let json = messages_to_json(&sample_history()).expect("should serialise");
+    let value: serde_json::Value = serde_json::from_str(&json).expect("should be valid JSON");
+
+    assert_eq!(value["version"], HISTORY_FORMAT_VERSION);
+    assert_eq!(value["messages"][2]["tool_call_id"], "call_123");
+    assert_eq!(value["messages"][1]["name"], "alice");
+    assert_eq!(value["messages"][1]["content"][1]["type"], "image_url");
+    assert!(value["messages"][0].get("name").is_none());
+}
+
+#[test]
+fn messages_from_json_accepts_bare_list() {
+    let restored = messages_from_json(r#"[{"role": "user", "content": "Hi"}]"#)
+        .expect("bare list should parse");
+
+    assert_eq!(restored.len(), 1);
+    assert_eq!(restored[0].content, "Hi");
+}
+
+#[test]
+fn messages_from_json_rejects_bad_input() {
+    let err = messages_from_json("not json").expect_err("invalid JSON should fail");
+    assert!(format!("{:?}", err).contains("Invalid chat history JSON"));
+
+    let err = messages_from_json(r#"{"version": 99, "messages": []}"#)
+        .expect_err("unknown version should fail");
+    assert!(format!("{:?}", err).contains("version 99"));
+
+    let err = messages_from_json(r#"[{"role": "user", "content": [{"type": "video"}]}]"#)
+        .expect_err("bad content parts should fail");
+    assert!(format!("{:?}", err).contains("messages[0]['content'][0]"));
+}
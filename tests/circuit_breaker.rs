@@ -0,0 +1,224 @@
+mod common;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rusty_agent_sdk::CircuitOpenError;
+use rusty_agent_sdk::internal::{CircuitBreaker, CircuitState, Clock};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const UNAVAILABLE: &[u8] =
+    b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+const BAD_REQUEST: &[u8] =
+    b"HTTP/1.1 400 Bad Request\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"error\":{\"message\":\"bad request\"}}";
+
+const OK: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"choices\":[{\"message\":{\"role\":\"assistant\",\"content\":\"ok\"}}]}";
+
+/// A clock that only moves when told to.
+struct ManualClock(Mutex<Instant>);
+
+impl ManualClock {
+    fn advance(&self, by: Duration) {
+        *self.0.lock().unwrap() += by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.0.lock().unwrap()
+    }
+}
+
+fn breaker(threshold: u32) -> (CircuitBreaker, Arc<ManualClock>) {
+    let clock = Arc::new(ManualClock(Mutex::new(Instant::now())));
+    let breaker = CircuitBreaker::new(threshold, Duration::from_secs(30)).with_clock(clock.clone());
+    (breaker, clock)
+}
+
+fn fail(breaker: &CircuitBreaker, times: u32) {
+    for _ in 0..times {
+        breaker
+            .permit()
+            .expect("closed circuit admits calls")
+            .failure();
+    }
+}
+
+#[test]
+fn opens_after_consecutive_failures() {
+    let (breaker, _clock) = breaker(3);
+    fail(&breaker, 2);
+    assert_eq!(breaker.state(), CircuitState::Closed);
+
+    fail(&breaker, 1);
+    assert_eq!(breaker.state(), CircuitState::Open);
+    let err = breaker.permit().err().expect("open circuit refuses calls");
+    assert!(format!("{:?}", err).contains("remaining: 30s"), "{:?}", err);
+}
+
+#[test]
+fn a_success_resets_the_failure_count() {
+    let (breaker, _clock) = breaker(2);
+    fail(&breaker, 1);
+    breaker.permit().unwrap().success();
+    fail(&breaker, 1);
+    assert_eq!(breaker.state(), CircuitState::Closed);
+}
+
+#[test]
+fn refusals_report_the_time_remaining() {
+    let (breaker, clock) = breaker(1);
+    fail(&breaker, 1);
+    clock.advance(Duration::from_secs(20));
+    let err = breaker.permit().err().expect("circuit is still open");
+    assert!(format!("{:?}", err).contains("remaining: 10s"), "{:?}", err);
+    assert!(format!("{:?}", err).contains("10.0s"), "{:?}", err);
+}
+
+#[test]
+fn a_successful_probe_closes_the_circuit() {
+    let (breaker, clock) = breaker(1);
+    fail(&breaker, 1);
+    clock.advance(Duration::from_secs(30));
+    assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+    let probe = breaker
+        .permit()
+        .expect("the first call after the cool-down probes");
+    assert!(breaker.permit().is_err(), "only one probe at a time");
+    probe.success();
+    assert_eq!(breaker.state(), CircuitState::Closed);
+    assert!(breaker.permit().is_ok());
+}
+
+#[test]
+fn a_failed_probe_opens_the_circuit_again() {
+    let (breaker, clock) = breaker(3);
+    fail(&breaker, 3);
+    clock.advance(Duration::from_secs(31));
+
+    breaker.permit().expect("probe").failure();
+    assert_eq!(breaker.state(), CircuitState::Open);
+    clock.advance(Duration::from_secs(29));
+    assert!(breaker.permit().is_err());
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(breaker.state(), CircuitState::HalfOpen);
+}
+
+#[test]
+fn a_dropped_probe_lets_another_call_probe() {
+    let (breaker, clock) = breaker(1);
+    fail(&breaker, 1);
+    clock.advance(Duration::from_secs(30));
+
+    drop(breaker.permit().expect("probe"));
+    assert_eq!(breaker.state(), CircuitState::HalfOpen);
+    breaker.permit().expect("a new probe").success();
+    assert_eq!(breaker.state(), CircuitState::Closed);
+}
+
+#[test]
+fn a_zero_threshold_never_opens() {
+    let (breaker, _clock) = breaker(0);
+    fail(&breaker, 10);
+    assert_eq!(breaker.state(), CircuitState::Closed);
+}
+
+#[test]
+fn copies_share_the_state() {
+    let (breaker, _clock) = breaker(2);
+    let copy = breaker.with_settings(2, Duration::from_secs(5));
+    fail(&breaker, 1);
+    fail(&copy, 1);
+    assert_eq!(breaker.state(), CircuitState::Open);
+}
+
+fn state(provider: &Bound<'_, PyAny>) -> String {
+    provider
+        .call_method0("circuit_state")
+        .unwrap()
+        .extract()
+        .unwrap()
+}
+
+#[test]
+fn provider_raises_circuit_open_error_without_a_request() {
+    let (base_url, heads) = common::serve_sequence(vec![UNAVAILABLE, UNAVAILABLE, OK]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("max_retries", 0)?;
+            kwargs.set_item("circuit_breaker_threshold", 2)?;
+            kwargs.set_item("circuit_breaker_cooldown", 60)
+        });
+        let copy = provider
+            .call_method1("with_model", ("other-model",))
+            .unwrap();
+        for _ in 0..2 {
+            let err = provider
+                .call_method1("generate_text", ("Hello",))
+                .expect_err("503 should fail");
+            assert!(!err.is_instance_of::<CircuitOpenError>(py));
+        }
+        assert_eq!(state(&provider), "open");
+        assert_eq!(state(&copy), "open");
+
+        let err = copy
+            .call_method1("generate_text", ("Hello",))
+            .expect_err("the circuit is open");
+        assert!(err.is_instance_of::<CircuitOpenError>(py), "{}", err);
+        assert!(err.is_instance_of::<pyo3::exceptions::PyConnectionError>(py));
+        let remaining: f64 = err
+            .value(py)
+            .getattr("remaining")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert!(remaining > 50.0 && remaining <= 60.0, "{}", remaining);
+
+        let err = provider
+            .call_method1("stream_text", ("Hello",))
+            .expect_err("streams fail at the call too");
+        assert!(err.is_instance_of::<CircuitOpenError>(py));
+    });
+    assert_eq!(heads.try_iter().count(), 2);
+}
+
+#[test]
+fn client_errors_do_not_count() {
+    let (base_url, _heads) = common::serve_sequence(vec![BAD_REQUEST, BAD_REQUEST, BAD_REQUEST]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("max_retries", 0)?;
+            kwargs.set_item("circuit_breaker_threshold", 2)?;
+            kwargs.set_item("circuit_breaker_cooldown", 60)
+        });
+        for _ in 0..3 {
+            let err = provider
+                .call_method1("generate_text", ("Hello",))
+                .expect_err("400 should fail");
+            assert!(!err.is_instance_of::<CircuitOpenError>(py));
+        }
+        assert_eq!(state(&provider), "closed");
+    });
+}
+
+#[test]
+fn negative_threshold_is_rejected() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("circuit_breaker_threshold", -1).unwrap();
+        let err = common::provider(py, "http://127.0.0.1:9/v1")
+            .call_method("with_options", (), Some(&kwargs))
+            .expect_err("negative threshold");
+        assert!(err.to_string().contains("circuit_breaker_threshold"));
+    });
+}
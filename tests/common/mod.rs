@@ -0,0 +1,114 @@
+// Each test binary uses only some of these helpers.
+#![allow(dead_code)]
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rusty_agent_sdk::Provider;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+/// Serve a single raw HTTP response from a local port, pausing for `stall`
+/// after `head` and before `body`. Returns the base URL to pass to `Provider`.
+pub fn serve_once(head: &'static [u8], stall: Duration, body: &'static [u8]) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("should bind a local port");
+    let port = listener
+        .local_addr()
+        .expect("should have an address")
+        .port();
+
+    std::thread::spawn(move || {
+        let (mut socket, _) = listener.accept().expect("should accept a connection");
+        let mut buf = [0u8; 8192];
+        let _ = socket.read(&mut buf);
+        let _ = socket.write_all(head);
+        std::thread::sleep(stall);
+        let _ = socket.write_all(body);
+    });
+
+    format!("http://127.0.0.1:{}/v1", port)
+}
+
+/// Create a Python `Provider` for `test-model` pointed at `base_url`.
+pub fn provider<'py>(py: Python<'py>, base_url: &str) -> Bound<'py, PyAny> {
+    provider_with(py, base_url, |_| Ok(()))
+}
+
+/// Create a Python `Provider` pointed at `base_url`, with the keyword
+/// arguments `configure` sets on top. Setting `model` replaces `test-model`.
+pub fn provider_with<'py>(
+    py: Python<'py>,
+    base_url: &str,
+    configure: impl FnOnce(&Bound<'py, PyDict>) -> PyResult<()>,
+) -> Bound<'py, PyAny> {
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("model", "test-model").unwrap();
+    kwargs.set_item("api_key", "test-key").unwrap();
+    kwargs.set_item("base_url", base_url).unwrap();
+    configure(&kwargs).unwrap();
+    py.get_type::<Provider>()
+        .call((), Some(&kwargs))
+        .expect("provider should be created")
+}
+
+/// Serve one raw HTTP response per connection, in order, then stop.
+///
+/// Each connection is closed straight after its response is written, so a
+/// chunked response without its terminating chunk ends mid-body. Returns the
+/// base URL and a receiver yielding each request's head.
+pub fn serve_sequence(responses: Vec<&'static [u8]>) -> (String, Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("should bind a local port");
+    let port = listener
+        .local_addr()
+        .expect("should have an address")
+        .port();
+    let (heads, received) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        for response in responses {
+            let Ok((mut socket, _)) = listener.accept() else {
+                return;
+            };
+            let mut buf = [0u8; 8192];
+            let read = socket.read(&mut buf).unwrap_or(0);
+            let _ = heads.send(String::from_utf8_lossy(&buf[..read]).into_owned());
+            let _ = socket.write_all(response);
+        }
+    });
+
+    (format!("http://127.0.0.1:{}/v1", port), received)
+}
+
+/// Serve raw HTTP responses in order, keeping each connection open for the
+/// next request. Returns the base URL and a receiver that yields once per
+/// accepted connection.
+pub fn serve_keep_alive(responses: Vec<&'static [u8]>) -> (String, Receiver<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("should bind a local port");
+    let port = listener
+        .local_addr()
+        .expect("should have an address")
+        .port();
+    let (connections, accepted) = mpsc::channel();
+
+    std::thread::spawn(move || {
+        let mut responses = responses.into_iter().peekable();
+        while responses.peek().is_some() {
+            let Ok((mut socket, _)) = listener.accept() else {
+                return;
+            };
+            let _ = connections.send(());
+            let mut buf = [0u8; 8192];
+            while let Some(response) = responses.peek() {
+                match socket.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => {}
+                }
+                let _ = socket.write_all(response);
+                responses.next();
+            }
+        }
+    });
+
+    (format!("http://127.0.0.1:{}/v1", port), accepted)
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use pyo3::prelude::*;
+
+const GENERATED: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 84\r\n\r\n\
+{\"choices\":[{\"message\":{\"role\":\"assistant\",\"content\":\"Hi\"},\"finish_reason\":\"stop\"}]}";
+
+const STREAMED: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nContent-Length: 62\r\n\r\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n\
+data: [DONE]\n\n";
+
+#[test]
+fn requests_from_one_provider_share_a_connection() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let (base_url, connections) =
+            common::serve_keep_alive(vec![GENERATED, GENERATED, STREAMED]);
+        let provider = common::provider(py, &base_url);
+
+        for _ in 0..2 {
+            let text: String = provider
+                .call_method1("generate_text", ("Hello",))
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(text, "Hi");
+        }
+        let stream = provider.call_method1("stream_text", ("Hello",)).unwrap();
+        let text: String = stream.call_method0("text").unwrap().extract().unwrap();
+        assert_eq!(text, "Hi");
+
+        assert_eq!(connections.try_iter().count(), 1);
+    });
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rusty_agent_sdk::ContentFilterError;
+use std::time::Duration;
+
+const HEAD: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n";
+
+const REFUSED: &[u8] = br#"{"choices":[{"message":{"role":"assistant","content":null,"refusal":"I can't help with that."},"finish_reason":"stop"}]}"#;
+
+const FILTERED: &[u8] =
+    br#"{"choices":[{"message":{"role":"assistant","content":""},"finish_reason":"content_filter"}]}"#;
+
+fn generate<'py>(
+    py: Python<'py>,
+    body: &'static [u8],
+    options: &[(&str, bool)],
+) -> PyResult<Bound<'py, PyAny>> {
+    let base_url = common::serve_once(HEAD, Duration::ZERO, body);
+    let kwargs = PyDict::new(py);
+    for (key, value) in options {
+        kwargs.set_item(key, value).unwrap();
+    }
+    common::provider(py, &base_url).call_method("generate_text", ("Hello",), Some(&kwargs))
+}
+
+#[test]
+fn results_expose_the_refusal() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let result = generate(py, REFUSED, &[("include_usage", true)]).unwrap();
+        let refusal: Option<String> = result.getattr("refusal").unwrap().extract().unwrap();
+        let filtered: bool = result.getattr("was_filtered").unwrap().extract().unwrap();
+        assert_eq!(refusal.as_deref(), Some("I can't help with that."));
+        assert!(filtered);
+
+        let result = generate(py, FILTERED, &[("include_usage", true)]).unwrap();
+        let refusal: Option<String> = result.getattr("refusal").unwrap().extract().unwrap();
+        let filtered: bool = result.getattr("was_filtered").unwrap().extract().unwrap();
+        assert_eq!(refusal, None);
+        assert!(filtered);
+    });
+}
+
+#[test]
+fn filtered_responses_are_returned_by_default() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let text: String = generate(py, FILTERED, &[]).unwrap().extract().unwrap();
+        assert_eq!(text, "");
+    });
+}
+
+#[test]
+fn raise_on_content_filter_raises_with_the_refusal() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let err = generate(py, REFUSED, &[("raise_on_content_filter", true)])
+            .expect_err("the refusal should raise");
+        assert!(err.is_instance_of::<ContentFilterError>(py), "{}", err);
+        assert!(err.is_instance_of::<pyo3::exceptions::PyRuntimeError>(py));
+        assert!(
+            err.to_string().contains("I can't help with that."),
+            "{}",
+            err
+        );
+        let refusal: Option<String> = err.value(py).getattr("refusal").unwrap().extract().unwrap();
+        assert_eq!(refusal.as_deref(), Some("I can't help with that."));
+
+        let err = generate(
+            py,
+            FILTERED,
+            &[("raise_on_content_filter", true), ("include_usage", true)],
+        )
+        .expect_err("the filtered response should raise");
+        assert!(err.is_instance_of::<ContentFilterError>(py), "{}", err);
+        let refusal: Option<String> = err.value(py).getattr("refusal").unwrap().extract().unwrap();
+        assert_eq!(refusal, None);
+    });
+}
+
+#[test]
+fn streams_record_the_refusal() {
+    let base_url = common::serve_once(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n",
+        Duration::ZERO,
+        b"data: {\"choices\":[{\"delta\":{\"refusal\":\"I can't \"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"refusal\":\"help with that.\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n\
+data: [DONE]\n\n",
+    );
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = common::provider(py, &base_url)
+            .call_method1("stream_text", ("Hello",))
+            .expect("stream should start");
+        let text: String = stream.call_method0("text").unwrap().extract().unwrap();
+        assert_eq!(text, "");
+        let refusal: Option<String> = stream.getattr("refusal").unwrap().extract().unwrap();
+        let filtered: bool = stream.getattr("was_filtered").unwrap().extract().unwrap();
+        assert_eq!(refusal.as_deref(), Some("I can't help with that."));
+        assert!(filtered);
+    });
+}
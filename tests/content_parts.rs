@@ -0,0 +1,103 @@
+use rusty_agent_sdk::internal::{
+    ChatMessage, DEFAULT_MAX_PART_BYTES, MessageContent, build_file_part, build_image_part,
+    detect_file_mime, detect_image_mime, validate_content_parts,
+};
+
+const PNG_HEADER: &[u8] = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR";
+
+#[test]
+fn detect_image_mime_recognises_supported_formats() {
+    assert_eq!(detect_image_mime(PNG_HEADER), Some("image/png"));
+    assert_eq!(
+        detect_image_mime(b"\xff\xd8\xff\xe0JFIF"),
+        Some("image/jpeg")
+    );
+    assert_eq!(detect_image_mime(b"GIF89a\x01\x00"), Some("image/gif"));
+    assert_eq!(
+        detect_image_mime(b"RIFF\x24\x00\x00\x00WEBPVP8 "),
+        Some("image/webp")
+    );
+}
+
+#[test]
+fn detect_image_mime_rejects_unknown_formats() {
+    assert_eq!(detect_image_mime(b"%PDF-1.7"), None);
+    assert_eq!(detect_image_mime(b""), None);
+    assert_eq!(detect_image_mime(b"RIFF\x24\x00\x00\x00WAVE"), None);
+}
+
+#[test]
+fn build_image_part_produces_data_url_part() {
+    let part = build_image_part(PNG_HEADER, Some("low"), DEFAULT_MAX_PART_BYTES)
+        .expect("png should be accepted");
+
+    assert_eq!(part["type"], "image_url");
+    assert_eq!(part["image_url"]["detail"], "low");
+    let url = part["image_url"]["url"]
+        .as_str()
+        .expect("url should be a string");
+    assert_eq!(url, "data:image/png;base64,iVBORw0KGgoAAAANSUhEUg==");
+}
+
+#[test]
+fn build_image_part_omits_detail_when_none() {
+    let part =
+        build_image_part(PNG_HEADER, None, DEFAULT_MAX_PART_BYTES).expect("png should be accepted");
+
+    assert!(part["image_url"].get("detail").is_none());
+}
+
+#[test]
+fn build_image_part_rejects_oversized_and_unknown_data() {
+    let err = build_image_part(PNG_HEADER, None, 4).expect_err("oversized data should fail");
+    assert!(format!("{:?}", err).contains("exceeds the 4 byte limit"));
+
+    let err = build_image_part(b"plain text", None, DEFAULT_MAX_PART_BYTES)
+        .expect_err("unknown format should fail");
+    assert!(format!("{:?}", err).contains("Unsupported image format"));
+
+    let err = build_image_part(PNG_HEADER, Some("ultra"), DEFAULT_MAX_PART_BYTES)
+        .expect_err("unknown detail should fail");
+    assert!(format!("{:?}", err).contains("'detail'"));
+}
+
+#[test]
+fn detect_file_mime_recognises_pdf_and_falls_back() {
+    assert_eq!(detect_file_mime(b"%PDF-1.7\n"), "application/pdf");
+    assert_eq!(detect_file_mime(PNG_HEADER), "image/png");
+    assert_eq!(detect_file_mime(b"hello"), "application/octet-stream");
+}
+
+#[test]
+fn build_file_part_matches_expected_json_shape() {
+    let part = build_file_part(b"%PDF-1.7", "report.pdf", DEFAULT_MAX_PART_BYTES)
+        .expect("pdf should be accepted");
+
+    assert_eq!(
+        part,
+        serde_json::json!({
+            "type": "file",
+            "file": {
+                "filename": "report.pdf",
+                "file_data": "data:application/pdf;base64,JVBERi0xLjc=",
+            },
+        })
+    );
+    validate_content_parts("content", std::slice::from_ref(&part))
+        .expect("file parts should be accepted in message content");
+
+    let msg = ChatMessage {
+        role: "user".into(),
+        content: MessageContent::Parts(vec![part]),
+        ..Default::default()
+    };
+    let json = serde_json::to_value(&msg).expect("should serialise");
+    assert_eq!(json["content"][0]["file"]["filename"], "report.pdf");
+}
+
+#[test]
+fn build_file_part_rejects_oversized_data() {
+    let err =
+        build_file_part(b"%PDF-1.7", "report.pdf", 4).expect_err("oversized data should fail");
+    assert!(format!("{:?}", err).contains("exceeds the 4 byte limit"));
+}
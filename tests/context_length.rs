@@ -0,0 +1,116 @@
+mod common;
+
+use pyo3::prelude::*;
+use reqwest::StatusCode;
+use rusty_agent_sdk::ContextLengthExceededError;
+use rusty_agent_sdk::internal::{ContextLength, api_error, context_length_exceeded};
+use std::time::Duration;
+
+fn tokens(max_context_tokens: Option<u64>, requested_tokens: Option<u64>) -> Option<ContextLength> {
+    Some(ContextLength {
+        max_context_tokens,
+        requested_tokens,
+    })
+}
+
+#[test]
+fn openai_errors_give_both_token_counts() {
+    let body = r#"{"error":{"message":"This model's maximum context length is 8192 tokens. However, your messages resulted in 9000 tokens. Please reduce the length of the messages.","type":"invalid_request_error","code":"context_length_exceeded"}}"#;
+
+    let error = api_error(StatusCode::BAD_REQUEST, body);
+
+    assert_eq!(error.context_length, tokens(Some(8192), Some(9000)));
+}
+
+#[test]
+fn requested_counts_may_be_approximate_or_grouped() {
+    assert_eq!(
+        context_length_exceeded(
+            None,
+            "This endpoint's maximum context length is 128,000 tokens. However, you requested about 130512 tokens (129000 of text input, 1512 in the output).",
+        ),
+        tokens(Some(128_000), Some(130_512))
+    );
+}
+
+#[test]
+fn anthropic_prompt_too_long_errors_are_detected() {
+    let body = r#"{"type":"error","error":{"type":"invalid_request_error","message":"prompt is too long: 208310 tokens > 200000 maximum"}}"#;
+
+    let error = api_error(StatusCode::BAD_REQUEST, body);
+
+    assert_eq!(error.context_length, tokens(Some(200_000), Some(208_310)));
+}
+
+#[test]
+fn the_code_alone_is_enough_without_counts() {
+    assert_eq!(
+        context_length_exceeded(Some("context_length_exceeded"), "Input too long."),
+        tokens(None, None)
+    );
+    assert_eq!(
+        context_length_exceeded(None, "Your input exceeds the context window of this model."),
+        tokens(None, None)
+    );
+}
+
+#[test]
+fn other_errors_are_not_context_length_errors() {
+    let body = r#"{"error":{"message":"max_tokens is too large: 9000","code":"invalid_value"}}"#;
+
+    assert_eq!(
+        api_error(StatusCode::BAD_REQUEST, body).context_length,
+        None
+    );
+    assert_eq!(
+        api_error(StatusCode::TOO_MANY_REQUESTS, "slow down").context_length,
+        None
+    );
+}
+
+#[test]
+fn generate_text_raises_context_length_exceeded_error() {
+    let base_url = common::serve_once(
+        b"HTTP/1.1 400 Bad Request\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n",
+        Duration::ZERO,
+        br#"{"error":{"message":"This model's maximum context length is 4096 tokens. However, you requested 5000 tokens (4000 in the messages, 1000 in the completion).","code":"context_length_exceeded"}}"#,
+    );
+    Python::initialize();
+
+    Python::attach(|py| {
+        let err = common::provider(py, &base_url)
+            .call_method1("generate_text", ("Hello",))
+            .expect_err("an oversized request should fail");
+        assert!(
+            err.is_instance_of::<ContextLengthExceededError>(py),
+            "{}",
+            err
+        );
+        assert!(err.is_instance_of::<pyo3::exceptions::PyRuntimeError>(py));
+
+        let value = err.value(py);
+        let attribute =
+            |name: &str| -> Option<u64> { value.getattr(name).unwrap().extract().unwrap() };
+        assert_eq!(attribute("max_context_tokens"), Some(4096));
+        assert_eq!(attribute("requested_tokens"), Some(5000));
+        assert_eq!(attribute("status_code"), Some(400));
+    });
+}
+
+#[test]
+fn other_api_errors_stay_plain_runtime_errors() {
+    let base_url = common::serve_once(
+        b"HTTP/1.1 400 Bad Request\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n",
+        Duration::ZERO,
+        br#"{"error":{"message":"bad request"}}"#,
+    );
+    Python::initialize();
+
+    Python::attach(|py| {
+        let err = common::provider(py, &base_url)
+            .call_method1("generate_text", ("Hello",))
+            .expect_err("400 should fail");
+        assert!(!err.is_instance_of::<ContextLengthExceededError>(py));
+        assert!(!err.value(py).hasattr("max_context_tokens").unwrap());
+    });
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rusty_agent_sdk::internal::{parse_catalog, parse_chat_response_full};
+
+const PRICED: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"choices\":[{\"message\":{\"content\":\"ok\"}}],\"usage\":{\"prompt_tokens\":10,\"completion_tokens\":5,\
+\"cost\":0.00042,\"prompt_tokens_details\":{\"cached_tokens\":8},\"completion_tokens_details\":{\"reasoning_tokens\":3}}}";
+
+const STREAM_PRICED: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"ok\"},\"finish_reason\":\"stop\"}]}\n\n\
+data: {\"choices\":[],\"usage\":{\"prompt_tokens\":10,\"completion_tokens\":1,\"cost\":0.0001}}\n\n\
+data: [DONE]\n\n";
+
+const UNAVAILABLE: &[u8] =
+    b"HTTP/1.1 503 Service Unavailable\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"error\":{\"message\":\"busy\"}}";
+
+const CATALOG: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"data\":[{\"id\":\"test-model\",\"pricing\":{\"prompt\":\"0.000001\",\"completion\":\"0.000002\",\"request\":0}},\
+{\"id\":\"free-model\"}]}";
+
+#[test]
+fn usage_reads_cost_and_token_details() {
+    let body = r#"{"choices":[{"message":{"content":"ok"}}],"usage":{"prompt_tokens":10,"completion_tokens":5,"cost":0.5,"prompt_tokens_details":{"cached_tokens":8},"completion_tokens_details":{"reasoning_tokens":3}}}"#;
+
+    let usage = parse_chat_response_full(body).unwrap().usage.unwrap();
+
+    assert_eq!(usage.cost, Some(0.5));
+    assert_eq!(usage.prompt_tokens_details.unwrap().cached_tokens, Some(8));
+    assert_eq!(
+        usage.completion_tokens_details.unwrap().reasoning_tokens,
+        Some(3)
+    );
+}
+
+#[test]
+fn include_cost_asks_for_usage_accounting() {
+    let (base_url, heads) = common::serve_sequence(vec![PRICED, PRICED]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider(py, &base_url);
+        provider.call_method1("generate_text", ("Hello",)).unwrap();
+        let request = heads.recv().unwrap();
+        assert!(!request.contains("\"usage\""), "{}", request);
+
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("include_cost", true).unwrap();
+        let result = provider
+            .call_method("generate_text", ("Hello",), Some(&kwargs))
+            .unwrap();
+        let request = heads.recv().unwrap();
+        assert!(
+            request.contains("\"usage\":{\"include\":true}"),
+            "{}",
+            request
+        );
+
+        let cost: f64 = result.getattr("cost_usd").unwrap().extract().unwrap();
+        assert_eq!(cost, 0.00042);
+        let cached: u64 = result
+            .getattr("cached_prompt_tokens")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(cached, 8);
+        let reasoning: u64 = result
+            .getattr("reasoning_tokens")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(reasoning, 3);
+    });
+}
+
+#[test]
+fn cost_is_none_when_not_reported() {
+    let body = r#"{"choices":[{"message":{"content":"ok"}}],"usage":{"prompt_tokens":10}}"#;
+
+    let usage = parse_chat_response_full(body).unwrap().usage.unwrap();
+
+    assert_eq!(usage.cost, None);
+    assert_eq!(usage.prompt_tokens, Some(10));
+}
+
+#[test]
+fn streams_report_the_cost() {
+    let (base_url, heads) = common::serve_sequence(vec![STREAM_PRICED]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("include_cost", true).unwrap();
+        let stream = common::provider(py, &base_url)
+            .call_method("stream_text", ("Hello",), Some(&kwargs))
+            .unwrap();
+        let text: String = stream.call_method0("text").unwrap().extract().unwrap();
+        assert_eq!(text, "ok");
+        let cost: f64 = stream.getattr("cost_usd").unwrap().extract().unwrap();
+        assert_eq!(cost, 0.0001);
+
+        let request = heads.recv().unwrap();
+        assert!(
+            request.contains("\"usage\":{\"include\":true}"),
+            "{}",
+            request
+        );
+        assert!(request.contains("\"include_usage\":true"), "{}", request);
+    });
+}
+
+#[test]
+fn catalogs_read_string_and_number_prices() {
+    let catalog = parse_catalog(
+        r#"{"data":[{"id":"a","pricing":{"prompt":"0.000001","completion":2e-6,"image":"n/a"}},{"id":"b"}]}"#,
+    )
+    .unwrap();
+
+    let prices = &catalog["a"];
+    assert_eq!(prices["prompt"], 0.000001);
+    assert_eq!(prices["completion"], 0.000002);
+    assert!(!prices.contains_key("image"));
+    assert!(
+        !catalog.contains_key("b"),
+        "models without pricing are left out"
+    );
+    assert!(parse_catalog("not json").is_err());
+}
+
+#[test]
+fn get_pricing_retries_like_other_calls() {
+    let (base_url, heads) = common::serve_sequence(vec![UNAVAILABLE, CATALOG]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("retry_backoff_ms", 1)
+        });
+
+        let prices = provider
+            .call_method0("get_pricing")
+            .expect("the 503 is retried");
+        assert!(!prices.is_none());
+    });
+    assert_eq!(heads.try_iter().count(), 2);
+}
+
+#[test]
+fn get_pricing_fetches_the_catalog_once() {
+    let (base_url, heads) = common::serve_sequence(vec![CATALOG, CATALOG]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider(py, &base_url);
+        let prices = provider
+            .call_method0("get_pricing")
+            .unwrap()
+            .cast_into::<PyDict>()
+            .unwrap();
+        let prompt: f64 = prices
+            .get_item("prompt")
+            .unwrap()
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(prompt, 0.000001);
+        let request = heads.recv().unwrap();
+        assert!(request.starts_with("GET /v1/models "), "{}", request);
+
+        let other = provider
+            .call_method1("get_pricing", ("free-model",))
+            .unwrap();
+        assert!(other.is_none());
+        assert!(heads.try_recv().is_err(), "the catalog is cached");
+
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("refresh", true).unwrap();
+        provider
+            .call_method("get_pricing", (), Some(&kwargs))
+            .unwrap();
+        assert!(heads.recv().is_ok(), "refresh fetches it again");
+    });
+}
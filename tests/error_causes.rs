@@ -0,0 +1,100 @@
+mod common;
+
+use pyo3::exceptions::{PyConnectionRefusedError, PyOSError};
+use pyo3::prelude::*;
+use std::time::Duration;
+
+fn kind(err: &PyErr, py: Python<'_>) -> String {
+    err.value(py).getattr("kind").unwrap().extract().unwrap()
+}
+
+/// The exceptions chained below `err` through `__cause__`.
+fn causes(err: &PyErr, py: Python<'_>) -> Vec<PyErr> {
+    let mut causes = Vec::new();
+    let mut cause = err.cause(py);
+    while let Some(err) = cause {
+        cause = err.cause(py);
+        causes.push(err);
+    }
+    causes
+}
+
+#[test]
+fn refused_connections_chain_the_os_error() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let err = common::provider_with(py, "http://127.0.0.1:9/v1", |kwargs| {
+            kwargs.set_item("max_retries", 0)
+        })
+        .call_method1("generate_text", ("Hello",))
+        .expect_err("nothing listens on the discard port");
+        assert_eq!(kind(&err, py), "connect");
+        assert!(err.to_string().contains("error sending request"), "{}", err);
+
+        let causes = causes(&err, py);
+        assert!(!causes.is_empty());
+        assert!(
+            causes
+                .iter()
+                .any(|cause| cause.is_instance_of::<PyConnectionRefusedError>(py)),
+            "{:?}",
+            causes
+        );
+    });
+}
+
+#[test]
+fn stalled_responses_have_the_timeout_kind() {
+    let base_url = common::serve_once(b"", Duration::from_secs(2), b"");
+    Python::initialize();
+
+    Python::attach(|py| {
+        let err = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("max_retries", 0)?;
+            kwargs.set_item("request_timeout", 0.2)
+        })
+        .call_method1("generate_text", ("Hello",))
+        .expect_err("the request should time out");
+        assert_eq!(kind(&err, py), "timeout");
+        assert!(err.cause(py).is_some());
+    });
+}
+
+#[test]
+fn stream_connection_errors_chain_their_cause() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let err = common::provider_with(py, "http://127.0.0.1:9/v1", |kwargs| {
+            kwargs.set_item("max_retries", 0)
+        })
+        .call_method1("stream_text", ("Hello",))
+        .and_then(|stream| stream.call_method0("text"))
+        .expect_err("nothing listens on the discard port");
+        assert_eq!(kind(&err, py), "connect");
+        assert!(
+            causes(&err, py)
+                .iter()
+                .any(|cause| cause.is_instance_of::<PyOSError>(py))
+        );
+    });
+}
+
+#[test]
+fn api_errors_have_no_cause() {
+    let base_url = common::serve_once(
+        b"HTTP/1.1 400 Bad Request\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n",
+        Duration::ZERO,
+        b"{\"error\":{\"message\":\"bad request\"}}",
+    );
+    Python::initialize();
+
+    Python::attach(|py| {
+        let err = common::provider_with(py, &base_url, |kwargs| kwargs.set_item("max_retries", 0))
+            .call_method1("generate_text", ("Hello",))
+            .expect_err("400 should fail");
+        assert!(err.cause(py).is_none());
+        assert!(!err.value(py).hasattr("kind").unwrap());
+    });
+}
@@ -0,0 +1,220 @@
+mod common;
+
+use pyo3::exceptions::{PyRuntimeError, PyTypeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyDict, PyList};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Serve every connection on its own thread, replying after `delay` with the
+/// last message's content echoed back, or a 400 when that content is "fail".
+/// Returns the base URL and the highest number of requests seen in flight.
+fn serve_echo(delay: Duration) -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("should bind a local port");
+    let port = listener
+        .local_addr()
+        .expect("should have an address")
+        .port();
+    let in_flight = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+
+    let peak_seen = Arc::clone(&peak);
+    std::thread::spawn(move || {
+        for socket in listener.incoming() {
+            let Ok(socket) = socket else {
+                return;
+            };
+            let in_flight = Arc::clone(&in_flight);
+            let peak = Arc::clone(&peak_seen);
+            std::thread::spawn(move || {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                std::thread::sleep(delay);
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                reply(socket);
+            });
+        }
+    });
+
+    (format!("http://127.0.0.1:{}/v1", port), peak)
+}
+
+fn reply(mut socket: TcpStream) {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 8192];
+    let body = loop {
+        let Ok(read) = socket.read(&mut buf) else {
+            return;
+        };
+        if read == 0 {
+            return;
+        }
+        request.extend_from_slice(&buf[..read]);
+        let text = String::from_utf8_lossy(&request);
+        let Some((head, body)) = text.split_once("\r\n\r\n") else {
+            continue;
+        };
+        let length = head
+            .lines()
+            .find_map(|line| {
+                line.to_ascii_lowercase()
+                    .strip_prefix("content-length: ")
+                    .map(str::to_owned)
+            })
+            .and_then(|length| length.trim().parse::<usize>().ok())
+            .unwrap_or(0);
+        if body.len() >= length {
+            break body.to_owned();
+        }
+    };
+
+    let request: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let content = request["messages"].as_array().unwrap().last().unwrap()["content"]
+        .as_str()
+        .unwrap()
+        .to_owned();
+    let (status, body) = if content == "fail" {
+        (
+            "400 Bad Request",
+            r#"{"error":{"message":"bad prompt"}}"#.to_owned(),
+        )
+    } else {
+        (
+            "200 OK",
+            serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": content}, "finish_reason": "stop"}],
+                "usage": {"prompt_tokens": 1, "completion_tokens": 1, "total_tokens": 2},
+            })
+            .to_string(),
+        )
+    };
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    let _ = socket.write_all(response.as_bytes());
+}
+
+fn generate_many<'py>(
+    provider: &Bound<'py, PyAny>,
+    prompts: &Bound<'py, PyList>,
+    kwargs: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyList>> {
+    provider
+        .call_method("generate_many", (prompts,), kwargs)?
+        .cast_into::<PyList>()
+        .map_err(Into::into)
+}
+
+#[test]
+fn results_keep_input_order_and_respect_concurrency() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let (base_url, peak) = serve_echo(Duration::from_millis(100));
+        let provider = common::provider(py, &base_url);
+        let conversation = PyList::new(py, [PyDict::new(py)]).unwrap();
+        conversation
+            .get_item(0)
+            .unwrap()
+            .set_item("role", "user")
+            .unwrap();
+        conversation
+            .get_item(0)
+            .unwrap()
+            .set_item("content", "from messages")
+            .unwrap();
+        let prompts = PyList::new(py, ["one", "two", "three", "four", "five"]).unwrap();
+        prompts.append(conversation).unwrap();
+
+        let results = generate_many(
+            &provider,
+            &prompts,
+            Some(&[("concurrency", 2)].into_py_dict(py).unwrap()),
+        )
+        .unwrap();
+
+        let texts: Vec<String> = results.extract().unwrap();
+        assert_eq!(
+            texts,
+            ["one", "two", "three", "four", "five", "from messages"]
+        );
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    });
+}
+
+#[test]
+fn failed_items_are_returned_as_exceptions() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let (base_url, _peak) = serve_echo(Duration::ZERO);
+        let provider = common::provider(py, &base_url);
+        let prompts = PyList::new(py, ["ok", "fail", "also ok"]).unwrap();
+
+        let results = generate_many(&provider, &prompts, None).unwrap();
+
+        assert_eq!(
+            results.get_item(0).unwrap().extract::<String>().unwrap(),
+            "ok"
+        );
+        let error = results.get_item(1).unwrap();
+        assert!(error.is_instance_of::<PyRuntimeError>());
+        assert!(error.str().unwrap().to_string().contains("bad prompt"));
+        assert_eq!(
+            results.get_item(2).unwrap().extract::<String>().unwrap(),
+            "also ok"
+        );
+    });
+}
+
+#[test]
+fn include_usage_returns_generate_results() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let (base_url, _peak) = serve_echo(Duration::ZERO);
+        let provider = common::provider(py, &base_url);
+        let prompts = PyList::new(py, ["one", "two"]).unwrap();
+
+        let results = generate_many(
+            &provider,
+            &prompts,
+            Some(&[("include_usage", true)].into_py_dict(py).unwrap()),
+        )
+        .unwrap();
+
+        for (result, expected) in results.iter().zip(["one", "two"]) {
+            let text: String = result.getattr("text").unwrap().extract().unwrap();
+            assert_eq!(text, expected);
+            let total: u64 = result.getattr("total_tokens").unwrap().extract().unwrap();
+            assert_eq!(total, 2);
+        }
+    });
+}
+
+#[test]
+fn invalid_arguments_raise_before_sending() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider(py, "http://127.0.0.1:9/v1");
+        let prompts = PyList::new(py, ["one"]).unwrap();
+
+        let err = generate_many(
+            &provider,
+            &prompts,
+            Some(&[("concurrency", 0)].into_py_dict(py).unwrap()),
+        )
+        .unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py));
+
+        prompts.append(3).unwrap();
+        let err = generate_many(&provider, &prompts, None).unwrap_err();
+        assert!(err.is_instance_of::<PyTypeError>(py));
+        assert!(err.to_string().contains("prompts[1]"));
+    });
+}
@@ -1,6 +1,12 @@
+mod common;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use reqwest::StatusCode;
 use rusty_agent_sdk::internal::{
-    Usage, api_error_message, parse_chat_response, parse_chat_response_full,
+    AudioOutput, CompletionTokensDetails, MAX_ERROR_BODY_BYTES, Usage, api_error,
+    api_error_message, content_filter_error, content_filtered, parse_chat_response,
+    parse_chat_response_full, parse_sse_line, quoted_body, redact_secrets,
 };
 
 #[test]
@@ -48,6 +54,39 @@ fn api_error_message_falls_back_to_raw_body() {
     assert_eq!(message, "API error (502 Bad Gateway): upstream unavailable");
 }
 
+#[test]
+fn api_error_reads_code_and_type_from_structured_error() {
+    let body = r#"{"error":{"message":"Invalid key","type":"invalid_request_error","code":"invalid_api_key"}}"#;
+
+    let error = api_error(StatusCode::UNAUTHORIZED, body);
+
+    assert_eq!(error.message, "API error (401 Unauthorized): Invalid key");
+    assert_eq!(error.status_code, 401);
+    assert_eq!(error.code.as_deref(), Some("invalid_api_key"));
+    assert_eq!(error.error_type.as_deref(), Some("invalid_request_error"));
+    assert_eq!(error.body, body);
+}
+
+#[test]
+fn api_error_keeps_numeric_codes_as_text() {
+    let body = r#"{"error":{"message":"Rate limited","code":429}}"#;
+
+    let error = api_error(StatusCode::TOO_MANY_REQUESTS, body);
+
+    assert_eq!(error.code.as_deref(), Some("429"));
+    assert_eq!(error.error_type, None);
+}
+
+#[test]
+fn api_error_without_structured_body_has_no_code() {
+    let error = api_error(StatusCode::BAD_GATEWAY, "upstream unavailable");
+
+    assert_eq!(error.status_code, 502);
+    assert_eq!(error.code, None);
+    assert_eq!(error.error_type, None);
+    assert_eq!(error.body, "upstream unavailable");
+}
+
 // ---------------------------------------------------------------------------
 // parse_chat_response_full tests
 // ---------------------------------------------------------------------------
@@ -55,6 +94,8 @@ fn api_error_message_falls_back_to_raw_body() {
 #[test]
 fn parse_chat_response_full_extracts_all_fields() {
     let body = r#"{
+        "id": "chatcmpl-123",
+        "created": 1700000000,
         "choices": [{"message": {"content": "Hello!"}, "finish_reason": "stop"}],
         "usage": {"prompt_tokens": 10, "completion_tokens": 5, "total_tokens": 15},
         "model": "gpt-4"
@@ -62,6 +103,8 @@ fn parse_chat_response_full_extracts_all_fields() {
 
     let result = parse_chat_response_full(body).expect("should parse full response");
 
+    assert_eq!(result.id.as_deref(), Some("chatcmpl-123"));
+    assert_eq!(result.created, Some(1700000000));
     assert_eq!(result.text, "Hello!");
     assert_eq!(result.finish_reason, Some("stop".to_string()));
     assert_eq!(result.model, Some("gpt-4".to_string()));
@@ -70,13 +113,198 @@ fn parse_chat_response_full_extracts_all_fields() {
     assert_eq!(
         usage,
         Usage {
-            prompt_tokens: 10,
-            completion_tokens: 5,
-            total_tokens: 15,
+            prompt_tokens: Some(10),
+            completion_tokens: Some(5),
+            total_tokens: Some(15),
+            prompt_tokens_details: None,
+            completion_tokens_details: None,
+            cost: None,
+        }
+    );
+}
+
+#[test]
+fn parse_chat_response_full_extracts_cached_and_reasoning_tokens() {
+    let body = r#"{
+        "choices": [{"message": {"content": "ok"}}],
+        "usage": {
+            "prompt_tokens": 1200,
+            "completion_tokens": 90,
+            "prompt_tokens_details": {"cached_tokens": 1024, "audio_tokens": 0},
+            "completion_tokens_details": {"reasoning_tokens": 64}
         }
+    }"#;
+
+    let usage = parse_chat_response_full(body).unwrap().usage.unwrap();
+
+    assert_eq!(
+        usage.prompt_tokens_details.unwrap().cached_tokens,
+        Some(1024)
+    );
+    assert_eq!(
+        usage.completion_tokens_details.unwrap().reasoning_tokens,
+        Some(64)
     );
 }
 
+#[test]
+fn absent_token_details_are_none() {
+    let body = r#"{"choices":[{"message":{"content":"ok"}}],"usage":{"prompt_tokens":3,"completion_tokens_details":{}}}"#;
+
+    let usage = parse_chat_response_full(body).unwrap().usage.unwrap();
+
+    assert_eq!(usage.prompt_tokens_details, None);
+    assert_eq!(
+        usage.completion_tokens_details,
+        Some(CompletionTokensDetails::default())
+    );
+}
+
+#[test]
+fn parse_chat_response_full_tolerates_missing_usage_counts() {
+    let body = r#"{"choices":[{"message":{"content":"Hi"}}],"usage":{"prompt_tokens":7,"total_tokens":9}}"#;
+
+    let usage = parse_chat_response_full(body)
+        .expect("partial usage should parse")
+        .usage
+        .expect("usage should be present");
+
+    assert_eq!(usage.prompt_tokens, Some(7));
+    assert_eq!(usage.completion_tokens, None);
+    assert_eq!(usage.total_tokens, Some(9));
+}
+
+#[test]
+fn parse_chat_response_full_derives_a_missing_total() {
+    let body = r#"{"choices":[{"message":{"content":"Hi"}}],"usage":{"prompt_tokens":7,"completion_tokens":2}}"#;
+
+    let usage = parse_chat_response_full(body)
+        .expect("usage without a total should parse")
+        .usage
+        .expect("usage should be present");
+
+    assert_eq!(usage.total_tokens, Some(9));
+}
+
+#[test]
+fn parse_chat_response_full_treats_null_or_malformed_usage_as_none() {
+    for usage in [
+        "null",
+        r#""n/a""#,
+        r#"{"prompt_tokens":"7"}"#,
+        r#"{"completion_tokens":-1}"#,
+    ] {
+        let body = format!(
+            r#"{{"choices":[{{"message":{{"content":"Hi"}}}}],"usage":{}}}"#,
+            usage
+        );
+
+        let result = parse_chat_response_full(&body).expect("the text should still parse");
+
+        assert_eq!(result.text, "Hi");
+        assert_eq!(result.usage, None, "{}", usage);
+    }
+}
+
+#[test]
+fn parse_chat_response_full_reads_the_refusal() {
+    let body = r#"{"choices":[{"message":{"role":"assistant","content":null,"refusal":"I can't help with that."},"finish_reason":"stop"}]}"#;
+
+    let result = parse_chat_response_full(body).expect("response should parse");
+
+    assert_eq!(result.text, "");
+    assert_eq!(result.refusal.as_deref(), Some("I can't help with that."));
+    assert!(content_filtered(None, result.refusal.as_deref()));
+    assert!(content_filter_error(body).is_some());
+}
+
+#[test]
+fn parse_chat_response_full_reads_url_citations() {
+    let body = r#"{"choices":[{"message":{"content":"Rust 1.0 shipped in 2015.","annotations":[{"type":"url_citation","url_citation":{"url":"https://blog.rust-lang.org/","title":"Rust Blog","start_index":0,"end_index":24}},{"type":"file_citation"},"not an annotation"]}}]}"#;
+
+    let result = parse_chat_response_full(body).expect("response should parse");
+
+    assert_eq!(result.annotations.len(), 2, "{:?}", result.annotations);
+    let citation = &result.annotations[0];
+    assert_eq!(citation.annotation_type, "url_citation");
+    assert_eq!(citation.url.as_deref(), Some("https://blog.rust-lang.org/"));
+    assert_eq!(citation.title.as_deref(), Some("Rust Blog"));
+    assert_eq!(
+        (citation.start_index, citation.end_index),
+        (Some(0), Some(24))
+    );
+    assert_eq!(result.annotations[1].annotation_type, "file_citation");
+    assert_eq!(result.annotations[1].url, None);
+}
+
+#[test]
+fn missing_or_malformed_annotations_are_empty() {
+    for message in [
+        r#"{"content":"Hi"}"#,
+        r#"{"content":"Hi","annotations":null}"#,
+        r#"{"content":"Hi","annotations":"none"}"#,
+    ] {
+        let body = format!(r#"{{"choices":[{{"message":{}}}]}}"#, message);
+
+        let result = parse_chat_response_full(&body).expect("response should parse");
+
+        assert!(result.annotations.is_empty(), "{}", message);
+    }
+}
+
+#[test]
+fn annotations_are_a_list_of_dicts() {
+    const CITED: &[u8] =
+        b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"choices\":[{\"message\":{\"content\":\"Hi\",\"annotations\":[{\"type\":\"url_citation\",\
+\"url_citation\":{\"url\":\"https://example.com\",\"title\":\"Example\",\"start_index\":0,\"end_index\":2}}]}}]}";
+    const PLAIN: &[u8] =
+        b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"choices\":[{\"message\":{\"content\":\"Hi\"}}]}";
+    let (base_url, _heads) = common::serve_sequence(vec![CITED, PLAIN]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider(py, &base_url);
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("include_usage", true).unwrap();
+        let result = provider
+            .call_method("generate_text", ("Hello",), Some(&kwargs))
+            .unwrap();
+        let citation = result.getattr("annotations").unwrap().get_item(0).unwrap();
+        let url: String = citation.get_item("url").unwrap().extract().unwrap();
+        assert_eq!(url, "https://example.com");
+        let end: u64 = citation.get_item("end_index").unwrap().extract().unwrap();
+        assert_eq!(end, 2);
+        let kind: String = citation.get_item("type").unwrap().extract().unwrap();
+        assert_eq!(kind, "url_citation");
+        let exported = result
+            .call_method0("to_dict")
+            .unwrap()
+            .get_item("annotations")
+            .unwrap();
+        assert!(exported.eq(result.getattr("annotations").unwrap()).unwrap());
+
+        let result = provider
+            .call_method("generate_text", ("Hello",), Some(&kwargs))
+            .unwrap();
+        let annotations = result.getattr("annotations").unwrap();
+        assert_eq!(annotations.len().unwrap(), 0);
+    });
+}
+
+#[test]
+fn content_filter_finish_reason_counts_as_filtered() {
+    let filtered = r#"{"choices":[{"message":{"content":""},"finish_reason":"content_filter"}]}"#;
+    let normal = r#"{"choices":[{"message":{"content":"Hi"},"finish_reason":"stop"}]}"#;
+
+    let error = content_filter_error(filtered).expect("the response was filtered");
+
+    assert!(format!("{:?}", error).contains("content filter"));
+    assert!(content_filter_error(normal).is_none());
+    assert!(!content_filtered(Some("stop"), None));
+}
+
 #[test]
 fn parse_chat_response_full_with_missing_optional_fields() {
     let body = r#"{"choices": [{"message": {"content": "Hi"}}]}"#;
@@ -84,9 +312,13 @@ fn parse_chat_response_full_with_missing_optional_fields() {
     let result = parse_chat_response_full(body).expect("should parse without optionals");
 
     assert_eq!(result.text, "Hi");
+    assert!(result.id.is_none());
+    assert!(result.created.is_none());
     assert!(result.usage.is_none());
     assert!(result.finish_reason.is_none());
     assert!(result.model.is_none());
+    assert!(result.service_tier.is_none());
+    assert!(result.system_fingerprint.is_none());
 }
 
 #[test]
@@ -106,3 +338,261 @@ fn parse_chat_response_full_fails_on_invalid_json() {
 
     assert!(msg.contains("Failed to parse response"));
 }
+
+#[test]
+fn parse_chat_response_full_extracts_reasoning_content() {
+    let body =
+        r#"{"choices": [{"message": {"content": "42", "reasoning_content": "Let me think..."}}]}"#;
+
+    let result = parse_chat_response_full(body).expect("should parse reasoning_content");
+
+    assert_eq!(result.text, "42");
+    assert_eq!(result.reasoning, Some("Let me think...".to_string()));
+}
+
+#[test]
+fn parse_chat_response_full_extracts_reasoning_alias() {
+    let body = r#"{"choices": [{"message": {"content": "42", "reasoning": "Thinking"}}]}"#;
+
+    let result = parse_chat_response_full(body).expect("should parse reasoning");
+
+    assert_eq!(result.reasoning, Some("Thinking".to_string()));
+}
+
+#[test]
+fn parse_chat_response_full_reasoning_defaults_to_none() {
+    let body = r#"{"choices": [{"message": {"content": "Hi"}}]}"#;
+
+    let result = parse_chat_response_full(body).expect("should parse");
+
+    assert!(result.reasoning.is_none());
+}
+
+#[test]
+fn parse_chat_response_full_extracts_prediction_token_details() {
+    let body = r#"{
+        "choices": [{"message": {"content": "edited"}}],
+        "usage": {
+            "prompt_tokens": 10,
+            "completion_tokens": 20,
+            "total_tokens": 30,
+            "completion_tokens_details": {
+                "accepted_prediction_tokens": 18,
+                "rejected_prediction_tokens": 2
+            }
+        }
+    }"#;
+
+    let result = parse_chat_response_full(body).expect("should parse token details");
+    let usage = result.usage.expect("usage should be present");
+
+    assert_eq!(
+        usage.completion_tokens_details,
+        Some(CompletionTokensDetails {
+            accepted_prediction_tokens: Some(18),
+            rejected_prediction_tokens: Some(2),
+            reasoning_tokens: None,
+        })
+    );
+}
+
+#[test]
+fn parse_chat_response_full_extracts_service_tier() {
+    let body = r#"{"choices": [{"message": {"content": "Hi"}}], "service_tier": "scale"}"#;
+
+    let result = parse_chat_response_full(body).expect("should parse service_tier");
+
+    assert_eq!(result.service_tier, Some("scale".to_string()));
+}
+
+#[test]
+fn parse_chat_response_full_extracts_system_fingerprint() {
+    let body =
+        r#"{"choices": [{"message": {"content": "Hi"}}], "system_fingerprint": "fp_44709d6fcb"}"#;
+
+    let result = parse_chat_response_full(body).expect("should parse system_fingerprint");
+
+    assert_eq!(result.system_fingerprint, Some("fp_44709d6fcb".to_string()));
+}
+
+#[test]
+fn parse_chat_response_accepts_content_parts_array() {
+    let body = r#"{"choices": [{"message": {"content": [
+        {"type": "text", "text": "Hello, "},
+        {"type": "text", "text": "world"}
+    ]}}]}"#;
+
+    let content = parse_chat_response(body).expect("parts content should parse");
+
+    assert_eq!(content, "Hello, world");
+}
+
+#[test]
+fn parse_chat_response_full_extracts_audio_with_null_content() {
+    let body = r#"{"choices": [{"message": {
+        "content": null,
+        "audio": {"id": "audio_1", "data": "UklGRg==", "transcript": "Hi there", "expires_at": 1700000000}
+    }}]}"#;
+
+    let result = parse_chat_response_full(body).expect("audio response should parse");
+
+    assert_eq!(result.text, "");
+    assert_eq!(
+        result.audio,
+        Some(AudioOutput {
+            id: "audio_1".to_string(),
+            data: "UklGRg==".to_string(),
+            transcript: Some("Hi there".to_string()),
+            expires_at: Some(1_700_000_000),
+        })
+    );
+}
+
+#[test]
+fn parse_chat_response_full_audio_defaults_to_none() {
+    let body = r#"{"choices": [{"message": {"content": "Hi"}}]}"#;
+
+    let result = parse_chat_response_full(body).expect("should parse");
+
+    assert!(result.audio.is_none());
+}
+
+#[test]
+fn stream_deltas_accept_part_list_content() {
+    let line = r#"data: {"choices":[{"delta":{"content":[{"type":"text","text":"Hi"}]}}]}"#;
+
+    let events = parse_sse_line(line).expect("part-list deltas should parse");
+
+    assert!(
+        format!("{:?}", events).contains(r#"Content("Hi")"#),
+        "{:?}",
+        events
+    );
+}
+
+#[test]
+fn parse_failures_keep_the_body_with_secrets_redacted() {
+    let body = r#"{"choices":"oops","echo":"Authorization: Bearer abc123","key":"sk-or-v1-0123456789abcdef"}"#;
+
+    for err in [
+        parse_chat_response(body).expect_err("choices is not a list"),
+        parse_chat_response_full(r#"{"choices":[]}"#).expect_err("no choices"),
+        parse_sse_line(&format!("data: {}", body)).expect_err("bad chunk"),
+    ] {
+        let message = format!("{:?}", err);
+        assert!(message.contains("Unparsed"), "{}", message);
+        assert!(!message.contains("abc123"), "{}", message);
+        assert!(!message.contains("0123456789abcdef"), "{}", message);
+    }
+}
+
+#[test]
+fn redact_secrets_masks_bearer_tokens_and_prefixed_keys() {
+    assert_eq!(
+        redact_secrets("Bearer abc.def key=sk-proj-aaaaaaaaaaaaaaaaaaaa scikit sk-learn"),
+        "Bearer [REDACTED] key=[REDACTED] scikit sk-learn"
+    );
+    assert_eq!(redact_secrets("nothing to hide"), "nothing to hide");
+}
+
+#[test]
+fn quoted_body_truncates_long_bodies() {
+    let body = "é".repeat(MAX_ERROR_BODY_BYTES);
+
+    let quoted = quoted_body(&body);
+
+    assert!(quoted.len() < MAX_ERROR_BODY_BYTES + 40, "{}", quoted.len());
+    assert!(quoted.ends_with(&format!(" ... ({} bytes total)", body.len())));
+    assert_eq!(quoted_body("short"), "short");
+}
+
+#[test]
+fn unparseable_responses_raise_with_a_body_attribute() {
+    const GARBLED: &[u8] =
+        b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"choices\":[{\"message\":{\"content\":7}}]}";
+    let (base_url, _heads) = common::serve_sequence(vec![GARBLED]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let err = common::provider(py, &base_url)
+            .call_method1("generate_text", ("Hello",))
+            .expect_err("content 7 does not parse");
+        assert!(err.is_instance_of::<pyo3::exceptions::PyValueError>(py));
+        let body: String = err.value(py).getattr("body").unwrap().extract().unwrap();
+        assert_eq!(body, r#"{"choices":[{"message":{"content":7}}]}"#);
+    });
+}
+
+#[test]
+fn parse_chat_response_full_keeps_the_whole_body() {
+    let body = r#"{"choices":[{"message":{"content":"Hi"}}],"citations":["https://example.com"]}"#;
+
+    let result = parse_chat_response_full(body).expect("response should parse");
+
+    let raw = result.raw.expect("the body is JSON");
+    assert_eq!(raw["citations"][0], "https://example.com");
+}
+
+#[test]
+fn include_raw_returns_a_result_with_the_body() {
+    const CITED: &[u8] =
+        b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"id\":\"gen-1\",\"choices\":[{\"message\":{\"content\":\"Hi\"},\"finish_reason\":\"stop\"}],\
+\"usage\":{\"prompt_tokens\":3,\"completion_tokens\":1},\"citations\":[\"https://example.com\"]}";
+    let (base_url, _heads) = common::serve_sequence(vec![CITED, CITED]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider(py, &base_url);
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("include_raw", true).unwrap();
+        let result = provider
+            .call_method("generate_text", ("Hello",), Some(&kwargs))
+            .unwrap();
+        let citation: String = result
+            .getattr("raw")
+            .unwrap()
+            .get_item("citations")
+            .unwrap()
+            .get_item(0)
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(citation, "https://example.com");
+
+        let fields = result
+            .call_method0("to_dict")
+            .unwrap()
+            .cast_into::<PyDict>()
+            .unwrap();
+        let text: String = fields.get_item("text").unwrap().unwrap().extract().unwrap();
+        assert_eq!(text, "Hi");
+        let id: String = fields
+            .get_item("response_id")
+            .unwrap()
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(id, "gen-1");
+        let total: u64 = fields
+            .get_item("total_tokens")
+            .unwrap()
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(total, 4);
+        assert!(!fields.contains("raw").unwrap());
+        py.import("json")
+            .unwrap()
+            .call_method1("dumps", (fields,))
+            .expect("to_dict is JSON-serialisable");
+
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("include_usage", true).unwrap();
+        let result = provider
+            .call_method("generate_text", ("Hello",), Some(&kwargs))
+            .unwrap();
+        assert!(!result.getattr("raw").unwrap().is_none());
+    });
+}
@@ -0,0 +1,81 @@
+use rusty_agent_sdk::internal::{
+    ChatMessage, MessageContent, estimate_message_tokens, estimate_tokens, trim_history,
+};
+
+fn msg(role: &str, content: &str) -> ChatMessage {
+    ChatMessage {
+        role: role.into(),
+        content: content.into(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn estimate_message_tokens_uses_chars_over_four_plus_overhead() {
+    assert_eq!(estimate_message_tokens(&msg("user", "")), 4);
+    assert_eq!(estimate_message_tokens(&msg("user", "abcd")), 5);
+    assert_eq!(estimate_message_tokens(&msg("user", "abcde")), 6);
+}
+
+#[test]
+fn estimate_message_tokens_counts_non_text_parts_flat() {
+    let message = ChatMessage {
+        role: "user".into(),
+        content: MessageContent::Parts(vec![
+            serde_json::json!({"type": "text", "text": "abcd"}),
+            serde_json::json!({"type": "image_url", "image_url": {"url": "data:image/png;base64,AAAA"}}),
+        ]),
+        ..Default::default()
+    };
+
+    assert_eq!(estimate_message_tokens(&message), 1 + 85 + 4);
+}
+
+#[test]
+fn trim_history_returns_everything_when_under_budget() {
+    let messages = vec![msg("system", "Be brief."), msg("user", "Hi")];
+    let expected = estimate_tokens(&messages);
+
+    let (trimmed, total) = trim_history(messages.clone(), 1_000, true);
+
+    assert_eq!(trimmed, messages);
+    assert_eq!(total, expected);
+}
+
+#[test]
+fn trim_history_drops_oldest_but_keeps_system_and_latest_user() {
+    let long = "x".repeat(400);
+    let messages = vec![
+        msg("system", "Be brief."),
+        msg("user", &long),
+        msg("assistant", &long),
+        msg("user", "Latest question"),
+    ];
+
+    let (trimmed, total) = trim_history(messages, 50, true);
+
+    let roles: Vec<&str> = trimmed.iter().map(|m| m.role.as_str()).collect();
+    assert_eq!(roles, ["system", "user"]);
+    assert_eq!(trimmed[1].content, "Latest question");
+    assert_eq!(total, estimate_tokens(&trimmed));
+    assert!(total <= 50);
+}
+
+#[test]
+fn trim_history_can_drop_system_when_not_kept() {
+    let messages = vec![msg("system", &"s".repeat(400)), msg("user", "Hi")];
+
+    let (trimmed, _) = trim_history(messages, 20, false);
+
+    assert_eq!(trimmed, vec![msg("user", "Hi")]);
+}
+
+#[test]
+fn trim_history_may_exceed_budget_when_only_protected_messages_remain() {
+    let messages = vec![msg("user", &"x".repeat(400))];
+
+    let (trimmed, total) = trim_history(messages, 10, true);
+
+    assert_eq!(trimmed.len(), 1);
+    assert!(total > 10);
+}
@@ -0,0 +1,166 @@
+mod common;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rusty_agent_sdk::internal::new_idempotency_key;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+
+const OK: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"choices\":[{\"message\":{\"role\":\"assistant\",\"content\":\"ok\"}}]}";
+
+const UNAVAILABLE: &[u8] =
+    b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+const BAD_REQUEST: &[u8] =
+    b"HTTP/1.1 400 Bad Request\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"error\":{\"message\":\"bad request\"}}";
+
+/// The `Idempotency-Key` header of the next request the server received.
+fn sent_key(heads: &Receiver<String>) -> Option<String> {
+    let head = heads
+        .recv_timeout(Duration::from_secs(5))
+        .expect("a request should arrive");
+    head.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.eq_ignore_ascii_case("idempotency-key")
+            .then(|| value.trim().to_string())
+    })
+}
+
+fn generate_full<'py>(
+    provider: &Bound<'py, PyAny>,
+    kwargs: Bound<'py, PyDict>,
+) -> PyResult<Bound<'py, PyAny>> {
+    kwargs.set_item("include_usage", true).unwrap();
+    provider.call_method("generate_text", ("Hello",), Some(&kwargs))
+}
+
+fn idempotency_key(object: &Bound<'_, PyAny>) -> Option<String> {
+    object
+        .getattr("idempotency_key")
+        .unwrap()
+        .extract()
+        .unwrap()
+}
+
+#[test]
+fn generated_keys_are_random_uuid_v4() {
+    let key = new_idempotency_key();
+    let groups: Vec<&str> = key.split('-').collect();
+
+    assert_eq!(
+        groups.iter().map(|group| group.len()).collect::<Vec<_>>(),
+        [8, 4, 4, 4, 12]
+    );
+    assert!(key.chars().all(|c| c == '-' || c.is_ascii_hexdigit()));
+    assert!(groups[2].starts_with('4'), "{}", key);
+    assert!(groups[3].starts_with(['8', '9', 'a', 'b']), "{}", key);
+    assert_ne!(key, new_idempotency_key());
+}
+
+#[test]
+fn retries_resend_the_same_key() {
+    let (base_url, heads) = common::serve_sequence(vec![UNAVAILABLE, OK, OK]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("retry_backoff_ms", 1)
+        });
+
+        let result = generate_full(&provider, PyDict::new(py)).expect("the retry should succeed");
+        let first = sent_key(&heads).expect("the key should be sent");
+        assert_eq!(sent_key(&heads).as_ref(), Some(&first));
+        assert_eq!(idempotency_key(&result).as_ref(), Some(&first));
+
+        generate_full(&provider, PyDict::new(py)).expect("generation should succeed");
+        let next = sent_key(&heads).expect("the key should be sent");
+        assert_ne!(next, first, "each call should get its own key");
+    });
+}
+
+#[test]
+fn keys_can_be_turned_off() {
+    let (base_url, heads) = common::serve_sequence(vec![OK]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let options = PyDict::new(py);
+        options.set_item("idempotency_keys", false).unwrap();
+        let provider = common::provider(py, &base_url)
+            .call_method("with_options", (), Some(&options))
+            .unwrap();
+        let enabled: bool = provider
+            .getattr("idempotency_keys")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert!(!enabled);
+
+        let result = generate_full(&provider, PyDict::new(py)).expect("generation should succeed");
+        assert_eq!(sent_key(&heads), None);
+        assert_eq!(idempotency_key(&result), None);
+    });
+}
+
+#[test]
+fn a_per_call_key_is_sent_and_set_on_errors() {
+    let (base_url, heads) = common::serve_sequence(vec![BAD_REQUEST]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("idempotency_key", "order-42").unwrap();
+        let err =
+            generate_full(&common::provider(py, &base_url), kwargs).expect_err("400 should fail");
+        assert_eq!(sent_key(&heads).as_deref(), Some("order-42"));
+        assert_eq!(idempotency_key(err.value(py)).as_deref(), Some("order-42"));
+        let status: u16 = err
+            .value(py)
+            .getattr("status_code")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(status, 400);
+    });
+}
+
+#[test]
+fn invalid_per_call_keys_are_rejected() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider(py, "http://127.0.0.1:9/v1");
+        for key in ["", "line\nbreak"] {
+            let kwargs = PyDict::new(py);
+            kwargs.set_item("idempotency_key", key).unwrap();
+            let err = generate_full(&provider, kwargs).expect_err("the key should be rejected");
+            assert!(
+                err.is_instance_of::<pyo3::exceptions::PyValueError>(py),
+                "{}",
+                err
+            );
+        }
+    });
+}
+
+#[test]
+fn streams_expose_their_key() {
+    let (base_url, heads) = common::serve_sequence(vec![
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n\
+data: [DONE]\n\n",
+    ]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = common::provider(py, &base_url)
+            .call_method1("stream_text", ("Hello",))
+            .expect("stream should start");
+        let text: String = stream.call_method0("text").unwrap().extract().unwrap();
+        assert_eq!(text, "Hi");
+        assert_eq!(idempotency_key(&stream), sent_key(&heads));
+        assert!(idempotency_key(&stream).is_some());
+    });
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use pyo3::prelude::*;
+use rusty_agent_sdk::internal::{build_models_url, parse_model, parse_model_list};
+
+const MODELS: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"object\":\"list\",\"data\":[{\"id\":\"gpt-4o\",\"object\":\"model\",\"created\":1715367049,\"owned_by\":\"system\"},\
+{\"id\":\"openai/gpt-4o-mini\",\"name\":\"GPT-4o-mini\",\"context_length\":128000,\
+\"pricing\":{\"prompt\":\"0.00000015\",\"completion\":\"0.0000006\"}}]}";
+
+const MODEL: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"id\":\"test-model\",\"object\":\"model\",\"created\":1,\"owned_by\":\"acme\"}";
+
+const UNAVAILABLE: &[u8] =
+    b"HTTP/1.1 503 Service Unavailable\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"error\":{\"message\":\"busy\"}}";
+
+const NOT_FOUND: &[u8] =
+    b"HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"error\":{\"message\":\"The model 'missing' does not exist\"}}";
+
+#[test]
+fn models_urls_sit_next_to_chat_completions() {
+    assert_eq!(
+        build_models_url("https://api.openai.com/v1/", None),
+        "https://api.openai.com/v1/models"
+    );
+    assert_eq!(
+        build_models_url("https://api.openai.com/v1", Some("gpt-4o")),
+        "https://api.openai.com/v1/models/gpt-4o"
+    );
+    assert_eq!(
+        build_models_url("https://example.com/v1?key=1", None),
+        "https://example.com/v1/models?key=1"
+    );
+}
+
+#[test]
+fn model_lists_read_standard_and_openrouter_fields() {
+    let models = parse_model_list(
+        r#"{"data":[{"id":"a","created":5,"owned_by":"me"},{"object":"model"},
+        {"id":"b","created":"2024-01-01T00:00:00Z","display_name":"B","context_length":8192,
+        "pricing":{"prompt":"0.000001","image":"n/a"}}]}"#,
+    )
+    .unwrap();
+
+    assert_eq!(models.len(), 2, "entries without an id are skipped");
+    assert_eq!(models[0].id, "a");
+    assert_eq!(models[0].created, Some(5));
+    assert_eq!(models[0].owned_by.as_deref(), Some("me"));
+    assert!(models[0].pricing.is_none());
+
+    assert_eq!(models[1].created, None, "a date string is not a timestamp");
+    assert_eq!(models[1].name.as_deref(), Some("B"));
+    assert_eq!(models[1].context_length, Some(8192));
+    let pricing = models[1].pricing.as_ref().unwrap();
+    assert_eq!(pricing["prompt"], 0.000001);
+    assert!(!pricing.contains_key("image"));
+    assert_eq!(models[1].raw["display_name"], "B");
+
+    assert!(parse_model_list("{}").unwrap().is_empty());
+    assert!(parse_model_list(r#"{"data":"nope"}"#).is_err());
+    assert!(parse_model_list("not json").is_err());
+}
+
+#[test]
+fn single_models_may_be_wrapped_in_data() {
+    let model = parse_model(r#"{"id":"a","owned_by":"me"}"#).unwrap();
+    assert_eq!(model.owned_by.as_deref(), Some("me"));
+
+    let model = parse_model(r#"{"data":{"id":"b","context_length":4096}}"#).unwrap();
+    assert_eq!((model.id.as_str(), model.context_length), ("b", Some(4096)));
+
+    let err = parse_model(r#"{"object":"model"}"#).unwrap_err();
+    assert!(format!("{:?}", err).contains("no 'id'"), "{:?}", err);
+}
+
+#[test]
+fn list_models_fetches_model_info_and_retries() {
+    let (base_url, heads) = common::serve_sequence(vec![UNAVAILABLE, MODELS]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("retry_backoff_ms", 1)
+        });
+
+        let models = provider.call_method0("list_models").unwrap();
+        assert_eq!(models.len().unwrap(), 2);
+        let first = models.get_item(0).unwrap();
+        let id: String = first.getattr("id").unwrap().extract().unwrap();
+        assert_eq!(id, "gpt-4o");
+        let created: u64 = first.getattr("created").unwrap().extract().unwrap();
+        assert_eq!(created, 1715367049);
+        assert!(first.getattr("pricing").unwrap().is_none());
+        assert_eq!(
+            first.repr().unwrap().to_string(),
+            "ModelInfo(id='gpt-4o', owned_by='system')"
+        );
+
+        let second = models.get_item(1).unwrap();
+        let context_length: u64 = second.getattr("context_length").unwrap().extract().unwrap();
+        assert_eq!(context_length, 128000);
+        let completion: f64 = second
+            .getattr("pricing")
+            .unwrap()
+            .get_item("completion")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(completion, 0.0000006);
+        let name: String = second
+            .getattr("raw")
+            .unwrap()
+            .get_item("name")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(name, "GPT-4o-mini");
+    });
+
+    let requests: Vec<String> = heads.try_iter().collect();
+    assert_eq!(requests.len(), 2, "the 503 is retried");
+    assert!(
+        requests[1].starts_with("GET /v1/models "),
+        "{}",
+        requests[1]
+    );
+    assert!(
+        requests[1].contains("authorization: Bearer test-key"),
+        "{}",
+        requests[1]
+    );
+    assert!(!requests[1].contains("idempotency-key"), "{}", requests[1]);
+}
+
+#[test]
+fn get_model_defaults_to_the_providers_model() {
+    let (base_url, heads) = common::serve_sequence(vec![MODEL, NOT_FOUND]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider(py, &base_url);
+        let model = provider.call_method0("get_model").unwrap();
+        let owned_by: String = model.getattr("owned_by").unwrap().extract().unwrap();
+        assert_eq!(owned_by, "acme");
+        let model_id: String = provider.getattr("model").unwrap().extract().unwrap();
+        assert_eq!(model_id, "test-model", "the model getter is unaffected");
+
+        let err = provider
+            .call_method1("get_model", ("missing",))
+            .expect_err("the model does not exist");
+        assert!(err.to_string().contains("does not exist"), "{}", err);
+    });
+
+    let requests: Vec<String> = heads.try_iter().collect();
+    assert!(
+        requests[0].starts_with("GET /v1/models/test-model "),
+        "{}",
+        requests[0]
+    );
+    assert!(
+        requests[1].starts_with("GET /v1/models/missing "),
+        "{}",
+        requests[1]
+    );
+}
@@ -0,0 +1,167 @@
+mod common;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rusty_agent_sdk::internal::{ConcurrencyLimit, Deadline};
+use std::time::Duration;
+
+const OK: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"choices\":[{\"message\":{\"role\":\"assistant\",\"content\":\"ok\"}}]}";
+
+const STREAM_HEAD: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n";
+
+const STREAM_BODY: &[u8] =
+    b"data: {\"choices\":[{\"delta\":{\"content\":\"ok\"}}]}\n\ndata: [DONE]\n\n";
+
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+#[test]
+fn slots_are_capped_and_returned_on_drop() {
+    let limit = ConcurrencyLimit::new(2);
+    let first = limit.try_acquire().expect("first slot");
+    let _second = limit.try_acquire().expect("second slot");
+    assert!(limit.try_acquire().is_none());
+    assert_eq!(limit.in_flight(), 2);
+
+    drop(first);
+    assert_eq!(limit.in_flight(), 1);
+    assert!(limit.try_acquire().is_some());
+}
+
+#[test]
+fn copies_share_the_count() {
+    let limit = ConcurrencyLimit::new(1);
+    let copy = limit.with_max(1);
+    let _slot = limit.try_acquire().unwrap();
+    assert!(copy.try_acquire().is_none());
+    assert!(
+        copy.with_max(2).try_acquire().is_some(),
+        "a higher limit admits one more"
+    );
+}
+
+#[test]
+fn a_zero_limit_is_off() {
+    let limit = ConcurrencyLimit::new(0);
+    let slots: Vec<_> = (0..100).map(|_| limit.try_acquire().unwrap()).collect();
+    assert_eq!(slots.len(), 100);
+    assert_eq!(limit.in_flight(), 0);
+}
+
+#[test]
+fn a_waiting_call_gets_the_released_slot() {
+    let limit = ConcurrencyLimit::new(1);
+    let slot = limit.try_acquire().unwrap();
+    let release = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(50));
+        drop(slot);
+    });
+
+    let slot = block_on(limit.acquire(None)).expect("the slot frees up");
+    release.join().unwrap();
+    assert_eq!(limit.in_flight(), 1);
+    drop(slot);
+}
+
+#[test]
+fn a_wait_past_the_deadline_times_out() {
+    let limit = ConcurrencyLimit::new(1);
+    let _slot = limit.try_acquire().unwrap();
+    let deadline = Deadline::start(Some(Duration::from_millis(50)));
+
+    let err = block_on(limit.acquire(deadline.as_ref()))
+        .err()
+        .expect("no slot frees up");
+    assert!(
+        format!("{:?}", err).contains("max_concurrency"),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+fn a_running_stream_holds_its_slot() {
+    let base_url = common::serve_once(STREAM_HEAD, Duration::from_millis(1000), STREAM_BODY);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("max_retries", 0)?;
+            kwargs.set_item("max_concurrency", 1)
+        });
+        let stream = provider.call_method1("stream_text", ("Hello",)).unwrap();
+        py.detach(|| std::thread::sleep(Duration::from_millis(200)));
+
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("total_timeout", 0.3).unwrap();
+        let err = provider
+            .call_method1("with_model", ("other-model",))
+            .unwrap()
+            .call_method("generate_text", ("Hello",), Some(&kwargs))
+            .expect_err("the stream holds the only slot");
+        assert!(err.is_instance_of::<pyo3::exceptions::PyTimeoutError>(py));
+        assert!(err.to_string().contains("max_concurrency"), "{}", err);
+
+        let text: String = stream.call_method0("text").unwrap().extract().unwrap();
+        assert_eq!(text, "ok");
+    });
+}
+
+#[test]
+fn generate_many_runs_within_the_limit() {
+    let (base_url, heads) = common::serve_sequence(vec![OK, OK, OK]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("concurrency", 3).unwrap();
+        let results = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("max_retries", 0)?;
+            kwargs.set_item("max_concurrency", 1)
+        })
+        .call_method("generate_many", (["a", "b", "c"],), Some(&kwargs))
+        .unwrap();
+        let texts: Vec<String> = results.extract().unwrap();
+        assert_eq!(texts, ["ok", "ok", "ok"]);
+    });
+    assert_eq!(heads.try_iter().count(), 3);
+}
+
+#[test]
+fn max_concurrency_is_configurable_and_validated() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider_with(py, "http://127.0.0.1:9/v1", |kwargs| {
+            kwargs.set_item("max_retries", 0)?;
+            kwargs.set_item("max_concurrency", 1)
+        });
+        let max: usize = provider
+            .getattr("max_concurrency")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(max, 1);
+
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("max_concurrency", 4).unwrap();
+        let copy = provider
+            .call_method("with_options", (), Some(&kwargs))
+            .unwrap();
+        let max: usize = copy.getattr("max_concurrency").unwrap().extract().unwrap();
+        assert_eq!(max, 4);
+
+        kwargs.set_item("max_concurrency", -1).unwrap();
+        let err = provider
+            .call_method("with_options", (), Some(&kwargs))
+            .expect_err("a negative limit is rejected");
+        assert!(err.to_string().contains("max_concurrency"));
+    });
+}
@@ -0,0 +1,308 @@
+mod common;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+const NOT_FOUND: &[u8] =
+    b"HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"error\":{\"message\":\"model not found\"}}";
+
+const UNAVAILABLE: &[u8] = b"HTTP/1.1 503 Service Unavailable\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"error\":{\"message\":\"overloaded\"}}";
+
+const BAD_REQUEST: &[u8] =
+    b"HTTP/1.1 400 Bad Request\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"error\":{\"message\":\"bad request\"}}";
+
+const OK: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"choices\":[{\"message\":{\"role\":\"assistant\",\"content\":\"ok\"}}]}";
+
+const ROUTED: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"model\":\"router-choice\",\"choices\":[{\"message\":{\"role\":\"assistant\",\"content\":\"ok\"}}]}";
+
+const STREAM_OK: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"ok\"}}]}\n\ndata: [DONE]\n\n";
+
+fn generate_full<'py>(provider: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+    let kwargs = PyDict::new(provider.py());
+    kwargs.set_item("include_usage", true).unwrap();
+    provider.call_method("generate_text", ("Hello",), Some(&kwargs))
+}
+
+fn attempted(object: &Bound<'_, PyAny>) -> Vec<String> {
+    object
+        .getattr("attempted_models")
+        .unwrap()
+        .extract()
+        .unwrap()
+}
+
+fn fallback_used(object: &Bound<'_, PyAny>) -> bool {
+    object.getattr("fallback_used").unwrap().extract().unwrap()
+}
+
+/// The `Idempotency-Key` header of a request head.
+fn sent_key(head: &str) -> Option<String> {
+    head.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.eq_ignore_ascii_case("idempotency-key")
+            .then(|| value.trim().to_string())
+    })
+}
+
+#[test]
+fn a_missing_model_falls_back_to_the_next() {
+    let (base_url, heads) = common::serve_sequence(vec![NOT_FOUND, UNAVAILABLE, OK]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("model", "primary")?;
+            kwargs.set_item("max_retries", 0)?;
+            kwargs.set_item("fallback_models", ["second", "third"])
+        });
+        let result = generate_full(&provider).expect("the third model should answer");
+        assert_eq!(attempted(&result), ["primary", "second", "third"]);
+        assert!(fallback_used(&result));
+        let model: String = result.getattr("model").unwrap().extract().unwrap();
+        assert_eq!(model, "third");
+    });
+    assert_eq!(heads.try_iter().count(), 3);
+}
+
+#[test]
+fn client_errors_do_not_fall_back() {
+    let (base_url, heads) = common::serve_sequence(vec![BAD_REQUEST, OK]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("model", "primary")?;
+            kwargs.set_item("max_retries", 0)?;
+            kwargs.set_item("fallback_models", ["second"])
+        });
+        let err = generate_full(&provider).expect_err("400 should fail");
+        assert_eq!(attempted(err.value(py)), ["primary"]);
+        assert!(!err.to_string().contains("tried models"), "{}", err);
+    });
+    assert_eq!(heads.try_iter().count(), 1);
+}
+
+#[test]
+fn exhausting_the_chain_raises_the_last_error_with_the_models_tried() {
+    let (base_url, _heads) = common::serve_sequence(vec![UNAVAILABLE, NOT_FOUND]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("model", "primary")?;
+            kwargs.set_item("max_retries", 0)?;
+            kwargs.set_item("fallback_models", ["second"])
+        });
+        let err = generate_full(&provider).expect_err("every model fails");
+        let status: u16 = err
+            .value(py)
+            .getattr("status_code")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(status, 404);
+        assert!(
+            err.to_string().contains("(tried models: primary, second)"),
+            "{}",
+            err
+        );
+        assert_eq!(attempted(err.value(py)), ["primary", "second"]);
+    });
+}
+
+#[test]
+fn streams_fall_back_before_they_start() {
+    let (base_url, _heads) = common::serve_sequence(vec![NOT_FOUND, STREAM_OK]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("model", "primary")?;
+            kwargs.set_item("max_retries", 0)?;
+            kwargs.set_item("fallback_models", ["second"])
+        })
+        .call_method1("stream_text", ("Hello",))
+        .unwrap();
+        let text: String = stream.call_method0("text").unwrap().extract().unwrap();
+        assert_eq!(text, "ok");
+        assert_eq!(attempted(&stream), ["primary", "second"]);
+        assert!(fallback_used(&stream));
+        let model: String = stream.getattr("model").unwrap().extract().unwrap();
+        assert_eq!(model, "second");
+    });
+}
+
+#[test]
+fn without_fallbacks_results_name_the_model_alone() {
+    let (base_url, _heads) = common::serve_sequence(vec![OK, NOT_FOUND]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("model", "primary")?;
+            kwargs.set_item("max_retries", 0)
+        });
+        let result = generate_full(&provider).unwrap();
+        assert_eq!(attempted(&result), ["primary"]);
+        assert!(!fallback_used(&result));
+
+        let err = generate_full(&provider).expect_err("404 should fail");
+        assert!(!err.value(py).hasattr("attempted_models").unwrap());
+    });
+}
+
+#[test]
+fn fallback_models_are_configurable_and_validated() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider_with(py, "http://127.0.0.1:9/v1", |kwargs| {
+            kwargs.set_item("model", "primary")?;
+            kwargs.set_item("max_retries", 0)?;
+            kwargs.set_item("fallback_models", ["second"])
+        });
+        let models: Vec<String> = provider
+            .getattr("fallback_models")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(models, ["second"]);
+
+        let kwargs = PyDict::new(py);
+        kwargs
+            .set_item("fallback_models", ["third", "fourth"])
+            .unwrap();
+        let copy = provider
+            .call_method("with_options", (), Some(&kwargs))
+            .unwrap();
+        let models: Vec<String> = copy.getattr("fallback_models").unwrap().extract().unwrap();
+        assert_eq!(models, ["third", "fourth"]);
+
+        kwargs.set_item("fallback_models", [""]).unwrap();
+        let err = provider
+            .call_method("with_options", (), Some(&kwargs))
+            .expect_err("empty model names are rejected");
+        assert!(err.is_instance_of::<pyo3::exceptions::PyValueError>(py));
+    });
+}
+
+#[test]
+fn a_models_list_reports_the_model_the_router_chose() {
+    let (base_url, _heads) = common::serve_sequence(vec![ROUTED]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("include_usage", true).unwrap();
+        kwargs.set_item("models", ["first", "second"]).unwrap();
+        let result = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("model", "primary")?;
+            kwargs.set_item("max_retries", 0)
+        })
+        .call_method("generate_text", ("Hello",), Some(&kwargs))
+        .unwrap();
+        let model: String = result.getattr("model").unwrap().extract().unwrap();
+        assert_eq!(model, "router-choice");
+        assert_eq!(attempted(&result), ["first"]);
+    });
+}
+
+#[test]
+fn a_models_list_is_validated() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("models", Vec::<String>::new()).unwrap();
+        let err = common::provider_with(py, "http://127.0.0.1:9/v1", |kwargs| {
+            kwargs.set_item("model", "primary")?;
+            kwargs.set_item("max_retries", 0)
+        })
+        .call_method("generate_text", ("Hello",), Some(&kwargs))
+        .expect_err("an empty list is rejected");
+        assert!(err.is_instance_of::<pyo3::exceptions::PyValueError>(py));
+
+        kwargs.set_item("models", ["first"]).unwrap();
+        let err = common::provider_with(py, "http://127.0.0.1:9/v1", |kwargs| {
+            kwargs.set_item("model", "primary")?;
+            kwargs.set_item("max_retries", 0)?;
+            kwargs.set_item("fallback_models", ["second"])
+        })
+        .call_method("stream_text", ("Hello",), Some(&kwargs))
+        .expect_err("models and fallback_models are exclusive");
+        assert!(err.to_string().contains("fallback_models"), "{}", err);
+    });
+}
+
+#[test]
+fn each_fallback_sends_its_own_idempotency_key() {
+    let (base_url, heads) = common::serve_sequence(vec![NOT_FOUND, OK]);
+    Python::initialize();
+
+    let reported = Python::attach(|py| {
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("model", "primary")?;
+            kwargs.set_item("max_retries", 0)?;
+            kwargs.set_item("fallback_models", ["second"])
+        });
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("include_usage", true).unwrap();
+        kwargs.set_item("idempotency_key", "caller-key").unwrap();
+        let result = provider
+            .call_method("generate_text", ("Hello",), Some(&kwargs))
+            .unwrap();
+        assert!(fallback_used(&result));
+        result
+            .getattr("idempotency_key")
+            .unwrap()
+            .extract::<String>()
+            .unwrap()
+    });
+
+    let keys: Vec<Option<String>> = heads.try_iter().map(|head| sent_key(&head)).collect();
+    assert_eq!(
+        keys[0].as_deref(),
+        Some("caller-key"),
+        "only the primary gets the caller's key"
+    );
+    let fallback_key = keys[1].clone().expect("the fallback sends a key");
+    assert_ne!(fallback_key, "caller-key");
+    assert_eq!(
+        reported, fallback_key,
+        "the result reports the key that answered"
+    );
+}
+
+#[test]
+fn a_stream_reports_the_key_of_the_model_that_started_it() {
+    let (base_url, heads) = common::serve_sequence(vec![NOT_FOUND, STREAM_OK]);
+    Python::initialize();
+
+    let reported = Python::attach(|py| {
+        let stream = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("model", "primary")?;
+            kwargs.set_item("max_retries", 0)?;
+            kwargs.set_item("fallback_models", ["second"])
+        })
+        .call_method1("stream_text", ("Hello",))
+        .unwrap();
+        let text: String = stream.call_method0("text").unwrap().extract().unwrap();
+        assert_eq!(text, "ok");
+        stream
+            .getattr("idempotency_key")
+            .unwrap()
+            .extract::<String>()
+            .unwrap()
+    });
+
+    let keys: Vec<Option<String>> = heads.try_iter().map(|head| sent_key(&head)).collect();
+    assert_ne!(keys[0], keys[1]);
+    assert_eq!(keys[1].as_ref(), Some(&reported));
+}
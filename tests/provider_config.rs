@@ -1,12 +1,34 @@
+mod common;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyDict};
+use rusty_agent_sdk::Provider;
 use rusty_agent_sdk::internal::{
-    build_chat_completions_url, resolve_provider_values, resolve_runtime_config,
+    AuthHeader, PRESETS, Routing, RuntimeOverrides, TlsConfig, USER_AGENT,
+    build_chat_completions_url, find_preset, mask_api_key, normalize_base_url,
+    parse_default_headers, resolve_azure_base_url, resolve_preset_values, resolve_provider_values,
+    resolve_runtime_config,
 };
+use std::ffi::CString;
 use std::time::Duration;
 
+/// Environment with every runtime variable set to `value`.
+fn env_all(value: &'static str) -> impl Fn(&str) -> Option<String> {
+    move |_| Some(value.to_string())
+}
+
 #[test]
 fn provider_uses_env_key_when_api_key_not_provided() {
-    let (api_key, base_url) = resolve_provider_values(None, None, Some("env-key".to_string()))
-        .expect("config should be valid");
+    let (_, api_key, base_url, _) = resolve_provider_values(
+        Some("model".to_string()),
+        None,
+        None,
+        None,
+        Some("env-key".to_string()),
+        None,
+    )
+    .expect("config should be valid");
 
     assert_eq!(api_key, "env-key");
     assert_eq!(base_url, "https://openrouter.ai/api/v1");
@@ -14,10 +36,13 @@ fn provider_uses_env_key_when_api_key_not_provided() {
 
 #[test]
 fn provider_prefers_explicit_api_key_over_env() {
-    let (api_key, base_url) = resolve_provider_values(
+    let (_, api_key, base_url, _) = resolve_provider_values(
+        Some("model".to_string()),
         Some("explicit-key".to_string()),
         Some("https://api.openai.com/v1/".to_string()),
+        None,
         Some("env-key".to_string()),
+        None,
     )
     .expect("config should be valid");
 
@@ -27,11 +52,68 @@ fn provider_prefers_explicit_api_key_over_env() {
 
 #[test]
 fn provider_returns_error_when_no_api_key_is_available() {
-    let err = resolve_provider_values(None, None, None).expect_err("missing api key should fail");
+    let err = resolve_provider_values(Some("model".to_string()), None, None, None, None, None)
+        .expect_err("missing api key should fail");
     let message = format!("{:?}", err);
     assert!(message.contains("OPENROUTER_API_KEY"));
 }
 
+#[test]
+fn provider_uses_env_model_and_base_url_when_not_provided() {
+    let (model, _, base_url, _) = resolve_provider_values(
+        None,
+        Some("key".to_string()),
+        None,
+        Some("env/model".to_string()),
+        None,
+        Some("https://gateway.example.com/v1/".to_string()),
+    )
+    .expect("config should be valid");
+
+    assert_eq!(model, "env/model");
+    assert_eq!(base_url, "https://gateway.example.com/v1");
+
+    let (model, _, base_url, _) = resolve_provider_values(
+        Some("explicit/model".to_string()),
+        Some("key".to_string()),
+        Some("https://api.openai.com/v1".to_string()),
+        Some("env/model".to_string()),
+        None,
+        Some("https://gateway.example.com/v1".to_string()),
+    )
+    .expect("config should be valid");
+
+    assert_eq!(model, "explicit/model");
+    assert_eq!(base_url, "https://api.openai.com/v1");
+
+    let (_, _, base_url, _) = resolve_provider_values(
+        Some("model".to_string()),
+        Some("key".to_string()),
+        None,
+        None,
+        None,
+        Some(String::new()),
+    )
+    .expect("config should be valid");
+
+    assert_eq!(base_url, "https://openrouter.ai/api/v1");
+}
+
+#[test]
+fn provider_returns_error_when_no_model_is_available() {
+    let err = resolve_provider_values(
+        None,
+        Some("key".to_string()),
+        None,
+        Some(String::new()),
+        None,
+        None,
+    )
+    .expect_err("missing model should fail");
+    let message = format!("{:?}", err);
+    assert!(message.contains("RUSTY_AGENT_DEFAULT_MODEL"));
+}
+
 #[test]
 fn chat_url_builder_normalizes_trailing_slash() {
     let url = build_chat_completions_url("https://openrouter.ai/api/v1/");
@@ -39,14 +121,248 @@ fn chat_url_builder_normalizes_trailing_slash() {
     assert_eq!(url, "https://openrouter.ai/api/v1/chat/completions");
 }
 
+#[test]
+fn base_url_is_normalized_with_a_warning_for_pasted_endpoints() {
+    let (url, warning) = normalize_base_url("https://openrouter.ai/api/v1/").unwrap();
+    assert_eq!(url, "https://openrouter.ai/api/v1");
+    assert_eq!(warning, None);
+
+    let (url, warning) =
+        normalize_base_url("https://openrouter.ai/api/v1/chat/completions").unwrap();
+    assert_eq!(url, "https://openrouter.ai/api/v1");
+    assert!(warning.unwrap().contains("'/chat/completions'"));
+
+    let (url, warning) = normalize_base_url("http://localhost:8080/v1/embeddings/").unwrap();
+    assert_eq!(url, "http://localhost:8080/v1");
+    assert!(warning.unwrap().contains("'/embeddings'"));
+
+    let (url, _) = normalize_base_url("https://gateway.example.com/v1/?team=a&tier=b").unwrap();
+    assert_eq!(url, "https://gateway.example.com/v1?team=a&tier=b");
+    assert_eq!(
+        build_chat_completions_url(&url),
+        "https://gateway.example.com/v1/chat/completions?team=a&tier=b"
+    );
+}
+
+#[test]
+fn base_url_errors_say_what_is_wrong() {
+    let message = |base_url: &str| format!("{:?}", normalize_base_url(base_url).unwrap_err());
+
+    let error = message("openrouter.ai/api/v1");
+    assert!(error.contains("no scheme"), "{}", error);
+    assert!(
+        error.contains("'https://openrouter.ai/api/v1'"),
+        "{}",
+        error
+    );
+    let error = message("ftp://files.example.com/v1");
+    assert!(error.contains("scheme 'ftp' is not supported"), "{}", error);
+    let error = message("http://:8080/v1");
+    assert!(error.contains("empty host"), "{}", error);
+    let error = message("  ");
+    assert!(error.contains("it is empty"), "{}", error);
+
+    let err = resolve_provider_values(
+        Some("model".to_string()),
+        Some("key".to_string()),
+        Some("openrouter.ai/api/v1".to_string()),
+        None,
+        None,
+        None,
+    )
+    .expect_err("a base URL without a scheme should fail");
+    assert!(format!("{:?}", err).contains("Invalid base_url"));
+}
+
+#[test]
+fn preset_table_lists_base_urls_and_env_vars() {
+    let table: Vec<_> = PRESETS
+        .iter()
+        .map(|preset| (preset.name, preset.base_url, preset.env_var))
+        .collect();
+
+    assert_eq!(
+        table,
+        [
+            ("openai", "https://api.openai.com/v1", "OPENAI_API_KEY"),
+            (
+                "anthropic",
+                "https://api.anthropic.com/v1",
+                "ANTHROPIC_API_KEY"
+            ),
+            (
+                "openrouter",
+                "https://openrouter.ai/api/v1",
+                "OPENROUTER_API_KEY"
+            ),
+            ("groq", "https://api.groq.com/openai/v1", "GROQ_API_KEY"),
+            ("mistral", "https://api.mistral.ai/v1", "MISTRAL_API_KEY"),
+            (
+                "deepseek",
+                "https://api.deepseek.com/v1",
+                "DEEPSEEK_API_KEY"
+            ),
+            (
+                "together",
+                "https://api.together.xyz/v1",
+                "TOGETHER_API_KEY"
+            ),
+            ("xai", "https://api.x.ai/v1", "XAI_API_KEY"),
+        ]
+    );
+}
+
+#[test]
+fn preset_values_name_the_preset_env_var() {
+    for preset in PRESETS {
+        let (_, api_key, base_url) = resolve_preset_values(
+            "model".to_string(),
+            None,
+            preset.base_url,
+            preset.env_var,
+            Some("env-key".to_string()),
+        )
+        .expect("config should be valid");
+        assert_eq!(api_key, "env-key");
+        assert_eq!(base_url, preset.base_url);
+
+        let err = resolve_preset_values(
+            "model".to_string(),
+            None,
+            preset.base_url,
+            preset.env_var,
+            None,
+        )
+        .expect_err("missing api key should fail");
+        let message = format!("{:?}", err);
+        assert!(
+            message.contains(&format!(
+                "No api_key provided and {} environment variable is not set.",
+                preset.env_var
+            )),
+            "{}",
+            message
+        );
+    }
+}
+
+#[test]
+fn find_preset_lists_supported_providers_for_unknown_names() {
+    assert_eq!(find_preset("groq").unwrap().env_var, "GROQ_API_KEY");
+
+    let err = find_preset("nope").expect_err("unknown name should fail");
+    let message = format!("{:?}", err);
+    assert!(
+        message.contains(
+            "Unknown provider 'nope'. Supported providers: openai, anthropic, openrouter, groq, mistral, deepseek, together, xai."
+        ),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn preset_classmethods_match_from_named() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider_type = py.get_type::<Provider>();
+        let kwargs = [("api_key", "test-key")].into_py_dict(py).unwrap();
+        for preset in PRESETS {
+            let provider = provider_type
+                .call_method(preset.name, ("model",), Some(&kwargs))
+                .unwrap();
+            let base_url: String = provider.getattr("base_url").unwrap().extract().unwrap();
+            assert_eq!(base_url, preset.base_url, "{}", preset.name);
+
+            let named = provider_type
+                .call_method("from_named", (preset.name, "model"), Some(&kwargs))
+                .unwrap();
+            assert!(named.eq(&provider).unwrap(), "{}", preset.name);
+        }
+
+        let names: Vec<String> = provider_type
+            .call_method0("known_providers")
+            .unwrap()
+            .extract()
+            .unwrap();
+        let expected: Vec<&str> = PRESETS.iter().map(|preset| preset.name).collect();
+        assert_eq!(names, expected);
+
+        let err = provider_type
+            .call_method("from_named", ("nope", "model"), Some(&kwargs))
+            .unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py));
+        assert!(err.to_string().contains("openai, anthropic"), "{}", err);
+    });
+}
+
+#[test]
+fn azure_routing_builds_deployment_urls_and_api_key_header() {
+    let routing = Routing::azure("2024-10-21");
+    let base_url = "https://my-resource.openai.azure.com/";
+
+    assert_eq!(
+        routing.endpoint_url(base_url, "gpt-4o", "chat/completions"),
+        "https://my-resource.openai.azure.com/openai/deployments/gpt-4o/chat/completions?api-version=2024-10-21"
+    );
+    assert_eq!(
+        routing.endpoint_url(base_url, "ada-002", "embeddings"),
+        "https://my-resource.openai.azure.com/openai/deployments/ada-002/embeddings?api-version=2024-10-21"
+    );
+    assert_eq!(
+        routing.auth.header("key"),
+        Some(("api-key", "key".to_string()))
+    );
+
+    let default = Routing::default();
+    assert_eq!(
+        default.endpoint_url(base_url, "gpt-4o", "chat/completions"),
+        build_chat_completions_url(base_url)
+    );
+    assert_eq!(
+        AuthHeader::Bearer.header("key"),
+        Some(("Authorization", "Bearer key".to_string()))
+    );
+    assert_eq!(AuthHeader::Bearer.header(""), None);
+    assert_eq!(AuthHeader::ApiKey.header(""), None);
+}
+
+#[test]
+fn azure_base_url_prefers_base_url_then_resource_then_env() {
+    let env = || Some("https://env.openai.azure.com/".to_string());
+
+    let url = resolve_azure_base_url(
+        Some("res".to_string()),
+        Some("https://explicit.example.com/".to_string()),
+        env(),
+    )
+    .unwrap();
+    assert_eq!(url, "https://explicit.example.com");
+
+    let url = resolve_azure_base_url(Some("res".to_string()), None, env()).unwrap();
+    assert_eq!(url, "https://res.openai.azure.com");
+
+    let url = resolve_azure_base_url(None, None, env()).unwrap();
+    assert_eq!(url, "https://env.openai.azure.com");
+
+    let err = resolve_azure_base_url(None, None, Some(String::new()))
+        .expect_err("missing endpoint should fail");
+    assert!(format!("{:?}", err).contains("AZURE_OPENAI_ENDPOINT"));
+}
+
 #[test]
 fn runtime_config_uses_defaults_when_env_is_missing() {
-    let config = resolve_runtime_config(None, None, None, None).expect("config should be valid");
+    let config = resolve_runtime_config(None, None, None, None, None, None, None)
+        .expect("config should be valid");
 
     assert_eq!(config.request_timeout, Duration::from_secs(60));
     assert_eq!(config.connect_timeout, Duration::from_secs(10));
     assert_eq!(config.max_retries, 2);
     assert_eq!(config.retry_backoff, Duration::from_millis(250));
+    assert_eq!(config.max_retry_delay, Duration::from_secs(30));
+    assert_eq!(config.stream_reconnects, 0);
+    assert_eq!(config.stream_idle_timeout, None);
 }
 
 #[test]
@@ -56,6 +372,9 @@ fn runtime_config_reads_env_values() {
         Some("5".to_string()),
         Some("4".to_string()),
         Some("500".to_string()),
+        Some("10000".to_string()),
+        Some("3".to_string()),
+        Some("15".to_string()),
     )
     .expect("config should parse");
 
@@ -63,15 +382,900 @@ fn runtime_config_reads_env_values() {
     assert_eq!(config.connect_timeout, Duration::from_secs(5));
     assert_eq!(config.max_retries, 4);
     assert_eq!(config.retry_backoff, Duration::from_millis(500));
+    assert_eq!(config.max_retry_delay, Duration::from_secs(10));
+    assert_eq!(config.stream_reconnects, 3);
+    assert_eq!(config.stream_idle_timeout, Some(Duration::from_secs(15)));
 }
 
 #[test]
 fn runtime_config_rejects_invalid_values() {
-    let err = resolve_runtime_config(Some("0".to_string()), None, None, None)
+    let err = resolve_runtime_config(Some("0".to_string()), None, None, None, None, None, None)
         .expect_err("request timeout of 0 should fail");
     assert!(format!("{:?}", err).contains("RUSTY_AGENT_REQUEST_TIMEOUT_SECS"));
 
-    let err = resolve_runtime_config(None, None, Some("bad".to_string()), None)
+    let err = resolve_runtime_config(None, None, Some("bad".to_string()), None, None, None, None)
         .expect_err("invalid retry count should fail");
     assert!(format!("{:?}", err).contains("RUSTY_AGENT_MAX_RETRIES"));
+
+    let err = resolve_runtime_config(None, None, None, None, Some("0".to_string()), None, None)
+        .expect_err("max retry delay of 0 should fail");
+    assert!(format!("{:?}", err).contains("RUSTY_AGENT_RETRY_MAX_DELAY_MS"));
+
+    let err = resolve_runtime_config(None, None, None, None, None, Some("-1".to_string()), None)
+        .expect_err("negative reconnect count should fail");
+    assert!(format!("{:?}", err).contains("RUSTY_AGENT_STREAM_RECONNECTS"));
+
+    let err = resolve_runtime_config(None, None, None, None, None, None, Some("0".to_string()))
+        .expect_err("idle timeout of 0 should fail");
+    assert!(format!("{:?}", err).contains("RUSTY_AGENT_STREAM_IDLE_TIMEOUT_SECS"));
+}
+
+#[test]
+fn runtime_overrides_take_precedence_over_env_and_defaults() {
+    let overrides = RuntimeOverrides {
+        request_timeout: Some(1.5),
+        max_retries: Some(0),
+        ..Default::default()
+    };
+
+    let config = overrides
+        .resolve(env_all("7"))
+        .expect("config should be valid");
+    assert_eq!(config.request_timeout, Duration::from_millis(1500));
+    assert_eq!(config.max_retries, 0);
+    assert_eq!(config.connect_timeout, Duration::from_secs(7));
+    assert_eq!(config.retry_backoff, Duration::from_millis(7));
+
+    let config = overrides.resolve(|_| None).expect("config should be valid");
+    assert_eq!(config.request_timeout, Duration::from_millis(1500));
+    assert_eq!(config.connect_timeout, Duration::from_secs(10));
+    assert_eq!(config.retry_backoff, Duration::from_millis(250));
+}
+
+#[test]
+fn runtime_overrides_skip_invalid_env_for_explicit_values() {
+    let overrides = RuntimeOverrides {
+        request_timeout: Some(30.0),
+        connect_timeout: Some(2.0),
+        max_retries: Some(1),
+        retry_backoff_ms: Some(100),
+        max_retry_delay_ms: Some(2000),
+        stream_idle_timeout: Some(5.0),
+    };
+    let env = |name: &str| (name != "RUSTY_AGENT_STREAM_RECONNECTS").then(|| "bad".to_string());
+
+    let config = overrides.resolve(env).expect("explicit values should win");
+    assert_eq!(config.retry_backoff, Duration::from_millis(100));
+    assert_eq!(config.max_retry_delay, Duration::from_secs(2));
+    assert_eq!(config.stream_idle_timeout, Some(Duration::from_secs(5)));
+}
+
+#[test]
+fn runtime_overrides_reject_invalid_values() {
+    let cases = [
+        (
+            RuntimeOverrides {
+                request_timeout: Some(0.0),
+                ..Default::default()
+            },
+            "request_timeout",
+        ),
+        (
+            RuntimeOverrides {
+                connect_timeout: Some(-1.0),
+                ..Default::default()
+            },
+            "connect_timeout",
+        ),
+        (
+            RuntimeOverrides {
+                max_retries: Some(-1),
+                ..Default::default()
+            },
+            "max_retries",
+        ),
+        (
+            RuntimeOverrides {
+                retry_backoff_ms: Some(0),
+                ..Default::default()
+            },
+            "retry_backoff_ms",
+        ),
+        (
+            RuntimeOverrides {
+                max_retry_delay_ms: Some(-5),
+                ..Default::default()
+            },
+            "max_retry_delay_ms",
+        ),
+    ];
+
+    for (overrides, name) in cases {
+        let err = overrides
+            .resolve(|_| None)
+            .expect_err("value should be rejected");
+        assert!(format!("{:?}", err).contains(name), "{:?}", err);
+    }
+}
+
+#[test]
+fn provider_and_presets_reject_invalid_runtime_kwargs() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        for (key, value) in [
+            (
+                "request_timeout",
+                (-2.0f64).into_pyobject(py).unwrap().into_any(),
+            ),
+            ("max_retries", (-1i64).into_pyobject(py).unwrap().into_any()),
+        ] {
+            let kwargs = PyDict::new(py);
+            kwargs.set_item("api_key", "test-key").unwrap();
+            kwargs.set_item(key, &value).unwrap();
+
+            let err = py
+                .get_type::<Provider>()
+                .call(("test-model",), Some(&kwargs))
+                .unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+            assert!(err.to_string().contains(key));
+
+            let err = py
+                .get_type::<Provider>()
+                .call_method("openai", ("test-model",), Some(&kwargs))
+                .unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+        }
+    });
+}
+
+#[test]
+fn provider_uses_explicit_request_timeout() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let base_url = common::serve_once(
+            b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n",
+            Duration::from_secs(2),
+            b"{}",
+        );
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("api_key", "test-key").unwrap();
+        kwargs.set_item("base_url", &base_url).unwrap();
+        kwargs.set_item("request_timeout", 0.2).unwrap();
+        kwargs.set_item("max_retries", 0).unwrap();
+        let provider = py
+            .get_type::<Provider>()
+            .call(("test-model",), Some(&kwargs))
+            .unwrap();
+
+        let start = std::time::Instant::now();
+        provider
+            .call_method1("generate_text", ("Hello",))
+            .unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(1));
+    });
+}
+
+const GENERATED: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"choices\":[{\"message\":{\"role\":\"assistant\",\"content\":\"Hi\"},\"finish_reason\":\"stop\"}]}";
+
+#[test]
+fn with_model_keeps_credentials_and_endpoint() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let (base_url, heads) = common::serve_sequence(vec![GENERATED]);
+        let provider = common::provider(py, &base_url);
+
+        let copy = provider
+            .call_method1("with_model", ("other-model",))
+            .unwrap();
+        let text: String = copy
+            .call_method1("generate_text", ("Hello",))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(text, "Hi");
+
+        let request = heads.recv().unwrap();
+        assert!(request.contains("Bearer test-key"));
+        assert!(request.contains("\"model\":\"other-model\""));
+        let repr = provider.repr().unwrap().to_string();
+        assert!(repr.contains("test-model"), "{}", repr);
+    });
+}
+
+#[test]
+fn azure_provider_routes_to_deployment_with_api_key_header() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let (base_url, heads) = common::serve_sequence(vec![GENERATED]);
+        let kwargs = [("api_key", "azure-key"), ("base_url", &base_url)]
+            .into_py_dict(py)
+            .unwrap();
+        let provider = py
+            .get_type::<Provider>()
+            .call_method("azure", ("my-deployment",), Some(&kwargs))
+            .unwrap();
+
+        let text: String = provider
+            .call_method1("generate_text", ("Hello",))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(text, "Hi");
+
+        let request = heads.recv().unwrap();
+        assert!(
+            request.starts_with(
+                "POST /v1/openai/deployments/my-deployment/chat/completions?api-version=2024-10-21 "
+            ),
+            "{}",
+            request
+        );
+        assert!(request.contains("api-key: azure-key"), "{}", request);
+        assert!(!request.contains("Bearer"), "{}", request);
+
+        let openai = common::provider(py, &base_url);
+        assert!(!provider.eq(&openai).unwrap());
+
+        let globals = PyDict::new(py);
+        globals
+            .set_item("Provider", py.get_type::<Provider>())
+            .unwrap();
+        globals.set_item("provider", &provider).unwrap();
+        let code = CString::new(PICKLE_CHECKS).unwrap();
+        py.run(&code, Some(&globals), None).unwrap();
+    });
+}
+
+#[test]
+fn local_providers_send_no_authorization_header() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let (base_url, heads) = common::serve_sequence(vec![GENERATED, GENERATED]);
+        let kwargs = [("base_url", format!("{}/", base_url))]
+            .into_py_dict(py)
+            .unwrap();
+        let ollama = py
+            .get_type::<Provider>()
+            .call_method("ollama", ("llama3.2",), Some(&kwargs))
+            .unwrap();
+        let kwargs = [("api_key", ""), ("base_url", &base_url)]
+            .into_py_dict(py)
+            .unwrap();
+        let keyless = py
+            .get_type::<Provider>()
+            .call(("llama3.2",), Some(&kwargs))
+            .unwrap();
+
+        for provider in [ollama, keyless] {
+            let text: String = provider
+                .call_method1("generate_text", ("Hello",))
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(text, "Hi");
+
+            let request = heads.recv().unwrap();
+            assert!(
+                request.starts_with("POST /v1/chat/completions "),
+                "{}",
+                request
+            );
+            assert!(
+                !request.to_ascii_lowercase().contains("authorization"),
+                "{}",
+                request
+            );
+        }
+    });
+}
+
+#[test]
+fn ollama_defaults_to_the_local_server() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = py
+            .get_type::<Provider>()
+            .call_method1("ollama", ("llama3.2",))
+            .unwrap();
+        let base_url: String = provider.getattr("base_url").unwrap().extract().unwrap();
+        assert_eq!(base_url, "http://localhost:11434/v1");
+    });
+}
+
+#[test]
+fn constructors_normalize_and_validate_base_url() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider(py, "http://127.0.0.1:9/v1/chat/completions");
+        let base_url: String = provider.getattr("base_url").unwrap().extract().unwrap();
+        assert_eq!(base_url, "http://127.0.0.1:9/v1");
+        let warning: String = provider
+            .getattr("base_url_warning")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert!(warning.contains("/chat/completions"));
+
+        let kwargs = [("base_url", "http://127.0.0.1:9/v1")]
+            .into_py_dict(py)
+            .unwrap();
+        let copy = provider
+            .call_method("with_options", (), Some(&kwargs))
+            .unwrap();
+        assert!(copy.getattr("base_url_warning").unwrap().is_none());
+
+        let kwargs = [("base_url", "127.0.0.1:9/v1")].into_py_dict(py).unwrap();
+        let err = provider
+            .call_method("with_options", (), Some(&kwargs))
+            .unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py));
+        let err = py
+            .get_type::<Provider>()
+            .call_method("ollama", ("llama3.2",), Some(&kwargs))
+            .unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py));
+    });
+}
+
+#[test]
+fn with_options_replaces_only_given_settings() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let base_url = common::serve_once(
+            b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n",
+            Duration::from_secs(2),
+            b"{}",
+        );
+        let provider = common::provider(py, "http://127.0.0.1:9/v1");
+        let kwargs = PyDict::new(py);
+        kwargs
+            .set_item("base_url", format!("{}/", base_url))
+            .unwrap();
+        kwargs.set_item("request_timeout", 0.2).unwrap();
+        kwargs.set_item("max_retries", 0).unwrap();
+
+        let copy = provider
+            .call_method("with_options", (), Some(&kwargs))
+            .unwrap();
+        let repr = copy.repr().unwrap().to_string();
+        assert_eq!(
+            repr,
+            format!("Provider(model='test-model', base_url='{}')", base_url)
+        );
+
+        let start = std::time::Instant::now();
+        copy.call_method1("generate_text", ("Hello",)).unwrap_err();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        let repr = provider.repr().unwrap().to_string();
+        assert!(repr.contains("127.0.0.1:9"), "{}", repr);
+    });
+}
+
+#[test]
+fn with_options_rejects_invalid_values() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider(py, "http://127.0.0.1:9/v1");
+        for (key, value) in [
+            (
+                "connect_timeout",
+                0.0f64.into_pyobject(py).unwrap().into_any(),
+            ),
+            (
+                "retry_backoff_ms",
+                (-5i64).into_pyobject(py).unwrap().into_any(),
+            ),
+            (
+                "max_retry_delay_ms",
+                0i64.into_pyobject(py).unwrap().into_any(),
+            ),
+            (
+                "total_timeout",
+                (-1.0f64).into_pyobject(py).unwrap().into_any(),
+            ),
+            (
+                "retry_on_status",
+                vec![429, 99].into_pyobject(py).unwrap().into_any(),
+            ),
+        ] {
+            let kwargs = PyDict::new(py);
+            kwargs.set_item(key, value).unwrap();
+
+            let err = provider
+                .call_method("with_options", (), Some(&kwargs))
+                .unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+            assert!(err.to_string().contains(key));
+        }
+    });
+}
+
+#[test]
+fn mask_api_key_shows_only_the_last_four_characters() {
+    assert_eq!(mask_api_key("sk-or-v1-abcdef1234"), "****1234");
+    assert_eq!(
+        mask_api_key("k\u{e9}y-\u{4e16}\u{754c}ab"),
+        "****\u{4e16}\u{754c}ab"
+    );
+    assert_eq!(mask_api_key("abcd"), "****");
+    assert_eq!(mask_api_key(""), "****");
+}
+
+#[test]
+fn getters_report_the_resolved_configuration() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("api_key", "sk-test-secret-9876").unwrap();
+        kwargs.set_item("request_timeout", 1.5).unwrap();
+        kwargs.set_item("connect_timeout", 3).unwrap();
+        kwargs.set_item("max_retries", 4).unwrap();
+        kwargs.set_item("retry_backoff_ms", 125).unwrap();
+        let provider = py
+            .get_type::<Provider>()
+            .call_method("openai", ("gpt-4o-mini",), Some(&kwargs))
+            .unwrap();
+
+        let get = |name: &str| provider.getattr(name).unwrap();
+        assert_eq!(get("model").extract::<String>().unwrap(), "gpt-4o-mini");
+        assert_eq!(
+            get("base_url").extract::<String>().unwrap(),
+            "https://api.openai.com/v1"
+        );
+        assert_eq!(
+            get("masked_api_key").extract::<String>().unwrap(),
+            "****9876"
+        );
+        assert_eq!(get("request_timeout").extract::<f64>().unwrap(), 1.5);
+        assert_eq!(get("connect_timeout").extract::<f64>().unwrap(), 3.0);
+        assert_eq!(get("max_retries").extract::<u32>().unwrap(), 4);
+        assert_eq!(get("retry_backoff").extract::<f64>().unwrap(), 0.125);
+        assert!(provider.getattr("api_key").is_err());
+
+        let copy_kwargs = PyDict::new(py);
+        copy_kwargs.set_item("connect_timeout", 0.5).unwrap();
+        let copy = provider
+            .call_method1("with_model", ("gpt-4o",))
+            .unwrap()
+            .call_method("with_options", (), Some(&copy_kwargs))
+            .unwrap();
+        assert_eq!(
+            copy.getattr("model").unwrap().extract::<String>().unwrap(),
+            "gpt-4o"
+        );
+        assert_eq!(
+            copy.getattr("connect_timeout")
+                .unwrap()
+                .extract::<f64>()
+                .unwrap(),
+            0.5
+        );
+        for name in [
+            "base_url",
+            "masked_api_key",
+            "request_timeout",
+            "max_retries",
+            "retry_backoff",
+        ] {
+            assert!(
+                copy.getattr(name).unwrap().eq(get(name)).unwrap(),
+                "{}",
+                name
+            );
+        }
+    });
+}
+
+/// Checks run against `provider`, `same`, and `other_key` providers and two
+/// `GenerateResult`s built from one response.
+const EQUALITY_CHECKS: &str = r#"
+assert provider == same and not provider != same
+assert hash(provider) == hash(same)
+assert provider == provider.with_options(request_timeout=5, max_retries=0)
+assert provider != other_key and provider != provider.with_model("other")
+assert provider != "test-model"
+assert Provider.__eq__(provider, 1) is NotImplemented
+assert len({provider: 1, same: 2, other_key: 3}) == 2
+
+assert first == second and not first != second
+assert first != "Hi"
+try:
+    hash(first)
+except TypeError:
+    pass
+else:
+    raise AssertionError("GenerateResult should not be hashable")
+"#;
+
+#[test]
+fn providers_and_results_compare_by_value() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let (base_url, _heads) = common::serve_sequence(vec![GENERATED, GENERATED]);
+        let provider = common::provider(py, &base_url);
+        let usage = [("include_usage", true)].into_py_dict(py).unwrap();
+        let first = provider
+            .call_method("generate_text", ("Hello",), Some(&usage))
+            .unwrap();
+        let second = provider
+            .call_method("generate_text", ("Hello",), Some(&usage))
+            .unwrap();
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("api_key", "other-key").unwrap();
+        kwargs.set_item("base_url", &base_url).unwrap();
+        let other_key = py
+            .get_type::<Provider>()
+            .call(("test-model",), Some(&kwargs))
+            .unwrap();
+
+        let globals = PyDict::new(py);
+        globals
+            .set_item("Provider", py.get_type::<Provider>())
+            .unwrap();
+        globals.set_item("provider", &provider).unwrap();
+        globals
+            .set_item("same", common::provider(py, &base_url))
+            .unwrap();
+        globals.set_item("other_key", other_key).unwrap();
+        globals.set_item("first", first).unwrap();
+        globals.set_item("second", second).unwrap();
+        let code = CString::new(EQUALITY_CHECKS).unwrap();
+        py.run(&code, Some(&globals), None).unwrap();
+    });
+}
+
+/// Pickles and copies `provider`. The extension module is not importable in
+/// the embedded interpreter, so a stand-in module lets pickle find the class.
+const PICKLE_CHECKS: &str = r#"
+import copy, pickle, sys, types
+
+module = types.ModuleType("rusty_agent_sdk")
+module.Provider = Provider
+sys.modules["rusty_agent_sdk"] = module
+
+fields = ["model", "fallback_models", "base_url", "masked_api_key", "request_timeout",
+          "connect_timeout", "max_retries", "retry_backoff", "max_retry_delay",
+          "retry_on_status", "total_timeout", "idempotency_keys",
+          "circuit_breaker_threshold", "circuit_breaker_cooldown",
+          "max_requests_per_minute", "max_tokens_per_minute", "max_concurrency",
+          "default_headers"]
+copies = [pickle.loads(pickle.dumps(provider, protocol))
+          for protocol in range(2, pickle.HIGHEST_PROTOCOL + 1)]
+copies += [copy.copy(provider), copy.deepcopy(provider)]
+for clone in copies:
+    assert clone is not provider
+    assert clone == provider
+    for field in fields:
+        assert getattr(clone, field) == getattr(provider, field), field
+"#;
+
+#[test]
+fn provider_survives_pickle_and_copy() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("api_key", "sk-pickled-key").unwrap();
+        kwargs
+            .set_item("fallback_models", ["fallback-model"])
+            .unwrap();
+        kwargs
+            .set_item("base_url", "http://127.0.0.1:9/v1")
+            .unwrap();
+        kwargs.set_item("request_timeout", 1.5).unwrap();
+        kwargs.set_item("connect_timeout", 2).unwrap();
+        kwargs.set_item("max_retries", 0).unwrap();
+        kwargs.set_item("retry_backoff_ms", 30).unwrap();
+        kwargs.set_item("max_retry_delay_ms", 5000).unwrap();
+        kwargs.set_item("retry_on_status", vec![408, 429]).unwrap();
+        kwargs.set_item("total_timeout", 12.5).unwrap();
+        kwargs.set_item("idempotency_keys", false).unwrap();
+        kwargs.set_item("circuit_breaker_threshold", 3).unwrap();
+        kwargs.set_item("circuit_breaker_cooldown", 7.5).unwrap();
+        kwargs.set_item("max_requests_per_minute", 60).unwrap();
+        kwargs.set_item("max_tokens_per_minute", 90_000).unwrap();
+        kwargs.set_item("max_concurrency", 4).unwrap();
+        kwargs.set_item("stream_idle_timeout", 4.25).unwrap();
+        kwargs.set_item("ca_cert", fixture("ca.pem")).unwrap();
+        kwargs
+            .set_item("client_cert", fixture("client.pem"))
+            .unwrap();
+        kwargs
+            .set_item("client_key", fixture("client-key.pem"))
+            .unwrap();
+        kwargs.set_item("insecure_skip_tls_verify", false).unwrap();
+        kwargs
+            .set_item(
+                "default_headers",
+                [("x-tenant", "acme")].into_py_dict(py).unwrap(),
+            )
+            .unwrap();
+        let provider = py
+            .get_type::<Provider>()
+            .call(("test-model",), Some(&kwargs))
+            .unwrap();
+
+        let (args, state): ((String,), Bound<'_, PyDict>) = provider
+            .call_method0("__getnewargs_ex__")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(args.0, "test-model");
+        assert!(state.eq(&kwargs).unwrap(), "{}", state);
+
+        let globals = PyDict::new(py);
+        globals
+            .set_item("Provider", py.get_type::<Provider>())
+            .unwrap();
+        globals.set_item("provider", &provider).unwrap();
+        let code = CString::new(PICKLE_CHECKS).unwrap();
+        py.run(&code, Some(&globals), None).unwrap();
+    });
+}
+
+/// Path of a PEM file in `tests/fixtures/tls`: a test CA, and a client
+/// certificate it signed with that certificate's key.
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/tls/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn tls_config_loads_pem_files_and_names_bad_ones() {
+    let tls = TlsConfig::load(
+        Some(fixture("ca.pem")),
+        Some(fixture("client.pem")),
+        Some(fixture("client-key.pem")),
+        false,
+    )
+    .unwrap();
+    assert_eq!(tls.ca_cert, Some(fixture("ca.pem")));
+
+    let message = |tls: Result<TlsConfig, _>| format!("{:?}", tls.unwrap_err());
+
+    let error = message(TlsConfig::load(
+        Some(fixture("missing.pem")),
+        None,
+        None,
+        false,
+    ));
+    assert!(error.contains("Could not read ca_cert"), "{}", error);
+    assert!(error.contains("missing.pem"), "{}", error);
+    let error = message(TlsConfig::load(
+        Some(fixture("client-key.pem")),
+        None,
+        None,
+        false,
+    ));
+    assert!(error.contains("Invalid ca_cert"), "{}", error);
+    let error = message(TlsConfig::load(
+        None,
+        Some(fixture("client.pem")),
+        None,
+        false,
+    ));
+    assert!(error.contains("Invalid client_cert"), "{}", error);
+    assert!(error.contains("private key"), "{}", error);
+    let error = message(TlsConfig::load(
+        None,
+        None,
+        Some(fixture("client-key.pem")),
+        false,
+    ));
+    assert!(
+        error.contains("'client_key' requires 'client_cert'"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn tls_options_apply_at_construction_and_in_with_options() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("api_key", "test-key").unwrap();
+        kwargs.set_item("ca_cert", fixture("ca.pem")).unwrap();
+        kwargs.set_item("insecure_skip_tls_verify", true).unwrap();
+        let provider = py
+            .get_type::<Provider>()
+            .call(("test-model",), Some(&kwargs))
+            .unwrap();
+
+        kwargs.set_item("ca_cert", fixture("missing.pem")).unwrap();
+        let err = py
+            .get_type::<Provider>()
+            .call(("test-model",), Some(&kwargs))
+            .unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py));
+        assert!(err.to_string().contains("missing.pem"));
+
+        let kwargs = [("client_cert", fixture("ca.pem"))]
+            .into_py_dict(py)
+            .unwrap();
+        let err = provider
+            .call_method("with_options", (), Some(&kwargs))
+            .unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py));
+
+        kwargs
+            .set_item("client_key", fixture("client-key.pem"))
+            .unwrap();
+        kwargs
+            .set_item("client_cert", fixture("client.pem"))
+            .unwrap();
+        let copy = provider
+            .call_method("with_options", (), Some(&kwargs))
+            .unwrap();
+        let (_, state): ((String,), Bound<'_, PyDict>) = copy
+            .call_method0("__getnewargs_ex__")
+            .unwrap()
+            .extract()
+            .unwrap();
+        let kept = |name: &str| state.get_item(name).unwrap().unwrap().to_string();
+        assert_eq!(kept("ca_cert"), fixture("ca.pem"));
+        assert_eq!(kept("client_cert"), fixture("client.pem"));
+        assert_eq!(kept("insecure_skip_tls_verify"), "True");
+    });
+}
+
+#[test]
+fn default_headers_are_validated() {
+    let headers = parse_default_headers([
+        ("X-Tenant".to_string(), "acme".to_string()),
+        (
+            "HTTP-Referer".to_string(),
+            "https://example.com".to_string(),
+        ),
+    ])
+    .unwrap();
+    assert_eq!(headers["x-tenant"], "acme");
+    assert_eq!(headers["http-referer"], "https://example.com");
+
+    let message = |name: &str, value: &str| {
+        let headers = [(name.to_string(), value.to_string())];
+        format!("{:?}", parse_default_headers(headers).unwrap_err())
+    };
+    let error = message("X Tenant", "acme");
+    assert!(
+        error.contains("Invalid header name 'X Tenant'"),
+        "{}",
+        error
+    );
+    let error = message("X-Tenant", "acme\r\nInjected: yes");
+    assert!(error.contains("header 'X-Tenant'"), "{}", error);
+}
+
+#[test]
+fn default_headers_are_sent_under_the_auth_header() {
+    Python::initialize();
+
+    const REPLY: &[u8] =
+        b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"choices\":[{\"message\":{\"role\":\"assistant\",\"content\":\"ok\"}}]}";
+    const STREAM: &[u8] =
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"ok\"}}]}\n\ndata: [DONE]\n\n";
+
+    Python::attach(|py| {
+        let (base_url, heads) = common::serve_sequence(vec![REPLY, STREAM]);
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("api_key", "test-key").unwrap();
+        kwargs.set_item("base_url", &base_url).unwrap();
+        let headers = [("X-Tenant", "acme"), ("Authorization", "Bearer other")]
+            .into_py_dict(py)
+            .unwrap();
+        kwargs.set_item("default_headers", headers).unwrap();
+        let provider = py
+            .get_type::<Provider>()
+            .call(("test-model",), Some(&kwargs))
+            .unwrap();
+
+        provider.call_method1("generate_text", ("Hello",)).unwrap();
+        let stream = provider.call_method1("stream_text", ("Hello",)).unwrap();
+        for chunk in stream.try_iter().unwrap() {
+            chunk.unwrap();
+        }
+
+        for request in [heads.recv().unwrap(), heads.recv().unwrap()] {
+            assert!(request.contains("x-tenant: acme"), "{}", request);
+            assert!(
+                request.contains("authorization: Bearer test-key"),
+                "{}",
+                request
+            );
+            assert!(!request.contains("Bearer other"), "{}", request);
+        }
+
+        let kwargs = [(
+            "default_headers",
+            [("X Tenant", "acme")].into_py_dict(py).unwrap(),
+        )]
+        .into_py_dict(py)
+        .unwrap();
+        let err = provider
+            .call_method("with_options", (), Some(&kwargs))
+            .unwrap_err();
+        assert!(err.is_instance_of::<PyValueError>(py));
+    });
+}
+
+#[test]
+fn openrouter_attribution_sets_default_headers() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("api_key", "test-key").unwrap();
+        kwargs.set_item("site_url", "https://example.com").unwrap();
+        kwargs.set_item("app_name", "Example App").unwrap();
+        let provider = py
+            .get_type::<Provider>()
+            .call_method("openrouter", ("openai/gpt-4o-mini",), Some(&kwargs))
+            .unwrap();
+
+        let headers: std::collections::HashMap<String, String> = provider
+            .getattr("default_headers")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(headers.len(), 2);
+        assert_eq!(headers["http-referer"], "https://example.com");
+        assert_eq!(headers["x-title"], "Example App");
+    });
+}
+
+#[test]
+fn requests_identify_the_sdk_unless_default_headers_override_it() {
+    Python::initialize();
+
+    const REPLY: &[u8] =
+        b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"choices\":[{\"message\":{\"role\":\"assistant\",\"content\":\"ok\"}}]}";
+
+    assert_eq!(
+        USER_AGENT,
+        format!("rusty-agent-sdk/{} (python)", env!("CARGO_PKG_VERSION"))
+    );
+
+    Python::attach(|py| {
+        let (base_url, heads) = common::serve_sequence(vec![REPLY, REPLY]);
+        let provider = common::provider(py, &base_url);
+        provider.call_method1("generate_text", ("Hello",)).unwrap();
+        let request = heads.recv().unwrap();
+        assert!(
+            request.contains(&format!("user-agent: {}\r\n", USER_AGENT)),
+            "{}",
+            request
+        );
+
+        let user_agent = format!("my-app/1.0 {}", USER_AGENT);
+        let headers = [("User-Agent", user_agent.as_str())]
+            .into_py_dict(py)
+            .unwrap();
+        let kwargs = [("default_headers", headers)].into_py_dict(py).unwrap();
+        let provider = provider
+            .call_method("with_options", (), Some(&kwargs))
+            .unwrap();
+        provider.call_method1("generate_text", ("Hello",)).unwrap();
+        let request = heads.recv().unwrap();
+        assert!(
+            request.contains(&format!("user-agent: {}\r\n", user_agent)),
+            "{}",
+            request
+        );
+    });
 }
@@ -0,0 +1,135 @@
+mod common;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use reqwest::header::{HeaderMap, HeaderValue};
+use rusty_agent_sdk::internal::{RateLimit, rate_limit};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const LIMITED: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+x-ratelimit-remaining-requests: 59\r\nx-ratelimit-remaining-tokens: 149000\r\n\
+x-ratelimit-reset-requests: 1s\r\nx-ratelimit-reset-tokens: 6m0s\r\nConnection: close\r\n\r\n\
+{\"choices\":[{\"message\":{\"role\":\"assistant\",\"content\":\"ok\"}}]}";
+
+const STREAM_LIMITED: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\n\
+x-ratelimit-remaining-requests: 7\r\nConnection: close\r\n\r\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"ok\"}}]}\n\ndata: [DONE]\n\n";
+
+#[test]
+fn reads_openai_headers() {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "x-ratelimit-remaining-requests",
+        HeaderValue::from_static("59"),
+    );
+    headers.insert(
+        "x-ratelimit-remaining-tokens",
+        HeaderValue::from_static("149000"),
+    );
+    headers.insert("x-ratelimit-reset-requests", HeaderValue::from_static("1s"));
+    headers.insert("x-ratelimit-reset-tokens", HeaderValue::from_static("6m0s"));
+
+    assert_eq!(
+        rate_limit(&headers, SystemTime::now()),
+        RateLimit {
+            remaining_requests: Some(59),
+            remaining_tokens: Some(149_000),
+            reset_requests: Some(Duration::from_secs(1)),
+            reset_tokens: Some(Duration::from_secs(360)),
+        }
+    );
+}
+
+#[test]
+fn reads_openrouter_headers() {
+    let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let mut headers = HeaderMap::new();
+    headers.insert("x-ratelimit-remaining", HeaderValue::from_static("19"));
+    headers.insert(
+        "x-ratelimit-reset",
+        HeaderValue::from_static("1700000002500"),
+    );
+
+    let limit = rate_limit(&headers, now);
+    assert_eq!(limit.remaining_requests, Some(19));
+    assert_eq!(limit.reset_requests, Some(Duration::from_millis(2500)));
+    assert_eq!(limit.remaining_tokens, None);
+}
+
+#[test]
+fn missing_or_malformed_headers_are_none() {
+    assert_eq!(
+        rate_limit(&HeaderMap::new(), SystemTime::now()),
+        RateLimit::default()
+    );
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "x-ratelimit-remaining-requests",
+        HeaderValue::from_static("lots"),
+    );
+    headers.insert("x-ratelimit-reset-tokens", HeaderValue::from_static("soon"));
+    assert_eq!(
+        rate_limit(&headers, SystemTime::now()),
+        RateLimit::default()
+    );
+}
+
+fn rate_limit_of<'py>(object: &Bound<'py, PyAny>) -> Bound<'py, PyDict> {
+    object
+        .getattr("rate_limit")
+        .unwrap()
+        .cast_into::<PyDict>()
+        .unwrap()
+}
+
+#[test]
+fn results_expose_the_rate_limit() {
+    let (base_url, _heads) = common::serve_sequence(vec![LIMITED]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("include_usage", true).unwrap();
+        let result = common::provider(py, &base_url)
+            .call_method("generate_text", ("Hello",), Some(&kwargs))
+            .unwrap();
+        let limit = rate_limit_of(&result);
+        let remaining: u64 = limit
+            .get_item("remaining_tokens")
+            .unwrap()
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(remaining, 149_000);
+        let reset: f64 = limit
+            .get_item("reset_tokens")
+            .unwrap()
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(reset, 360.0);
+    });
+}
+
+#[test]
+fn streams_expose_the_rate_limit_once_started() {
+    let (base_url, _heads) = common::serve_sequence(vec![STREAM_LIMITED]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = common::provider(py, &base_url)
+            .call_method1("stream_text", ("Hello",))
+            .unwrap();
+        stream.call_method0("text").unwrap();
+        let limit = rate_limit_of(&stream);
+        let remaining: u64 = limit
+            .get_item("remaining_requests")
+            .unwrap()
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(remaining, 7);
+        assert!(limit.get_item("reset_requests").unwrap().unwrap().is_none());
+    });
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rusty_agent_sdk::internal::{Clock, RateLimiter};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const OK: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"choices\":[{\"message\":{\"role\":\"assistant\",\"content\":\"ok\"}}]}";
+
+/// A clock that only moves when told to.
+struct ManualClock(Mutex<Instant>);
+
+impl ManualClock {
+    fn advance(&self, by: Duration) {
+        *self.0.lock().unwrap() += by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.0.lock().unwrap()
+    }
+}
+
+fn limiter(requests: u32, tokens: u64) -> (RateLimiter, Arc<ManualClock>) {
+    let clock = Arc::new(ManualClock(Mutex::new(Instant::now())));
+    let limiter = RateLimiter::new(requests, tokens).with_clock(clock.clone());
+    (limiter, clock)
+}
+
+fn assert_wait(wait: Duration, secs: f64) {
+    assert!((wait.as_secs_f64() - secs).abs() < 1e-6, "{:?}", wait);
+}
+
+#[test]
+fn a_full_bucket_allows_a_burst_then_waits() {
+    let (limiter, clock) = limiter(2, 0);
+    limiter.try_acquire(0).expect("first request");
+    limiter.try_acquire(0).expect("second request");
+    let wait = limiter.try_acquire(0).err().expect("the bucket is empty");
+    assert_wait(wait, 30.0);
+
+    clock.advance(Duration::from_secs(30));
+    limiter.try_acquire(0).expect("one request has refilled");
+    assert!(limiter.try_acquire(0).is_err());
+}
+
+#[test]
+fn tokens_refill_continuously() {
+    let (limiter, clock) = limiter(0, 1000);
+    limiter.try_acquire(600).unwrap();
+    let wait = limiter
+        .try_acquire(600)
+        .err()
+        .expect("only 400 tokens left");
+    assert_wait(wait, 12.0);
+
+    clock.advance(Duration::from_secs(6));
+    assert_eq!(limiter.levels(), (None, Some(500.0)));
+}
+
+#[test]
+fn reported_usage_replaces_the_estimate() {
+    let (limiter, _clock) = limiter(0, 1000);
+    limiter.try_acquire(600).unwrap().settle(Some(100));
+    assert_eq!(limiter.levels(), (None, Some(900.0)));
+
+    limiter.try_acquire(100).unwrap().settle(None);
+    assert_eq!(limiter.levels(), (None, Some(800.0)));
+}
+
+#[test]
+fn an_overrun_is_repaid_before_the_next_call() {
+    let (limiter, _clock) = limiter(0, 1000);
+    limiter.try_acquire(100).unwrap().settle(Some(1500));
+    assert_eq!(limiter.levels(), (None, Some(-500.0)));
+    let wait = limiter
+        .try_acquire(100)
+        .err()
+        .expect("the bucket is in debt");
+    assert_wait(wait, 36.0);
+}
+
+#[test]
+fn an_oversized_call_waits_for_a_full_bucket() {
+    let (limiter, clock) = limiter(0, 1000);
+    limiter.try_acquire(1).unwrap();
+    let wait = limiter
+        .try_acquire(5000)
+        .err()
+        .expect("the bucket is not full");
+    assert_wait(wait, 0.06);
+
+    clock.advance(Duration::from_millis(60));
+    limiter.try_acquire(5000).expect("a full bucket admits it");
+}
+
+#[test]
+fn copies_share_the_levels() {
+    let (limiter, _clock) = limiter(1, 0);
+    let copy = limiter.with_settings(1, 500);
+    limiter.try_acquire(0).unwrap();
+    assert!(copy.try_acquire(0).is_err());
+    assert_eq!(copy.levels(), (Some(0.0), Some(500.0)));
+}
+
+#[test]
+fn zero_limits_are_off() {
+    let (limiter, _clock) = limiter(0, 0);
+    for _ in 0..100 {
+        limiter.try_acquire(1_000_000).unwrap();
+    }
+    assert_eq!(limiter.levels(), (None, None));
+}
+
+#[test]
+fn a_wait_past_total_timeout_raises_without_a_request() {
+    let (base_url, heads) = common::serve_sequence(vec![OK, OK]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("max_retries", 0)?;
+            kwargs.set_item("max_requests_per_minute", 1)
+        });
+        provider.call_method1("generate_text", ("Hello",)).unwrap();
+
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("total_timeout", 0.5).unwrap();
+        let err = provider
+            .call_method("generate_text", ("Hello",), Some(&kwargs))
+            .expect_err("the limiter needs a minute");
+        assert!(err.is_instance_of::<pyo3::exceptions::PyTimeoutError>(py));
+        assert!(err.to_string().contains("rate limit"), "{}", err);
+
+        let copy = provider
+            .call_method1("with_model", ("other-model",))
+            .unwrap();
+        let stream = copy
+            .call_method("stream_text", ("Hello",), Some(&kwargs))
+            .unwrap();
+        let err = stream
+            .call_method0("text")
+            .expect_err("copies share the limit");
+        assert!(err.is_instance_of::<pyo3::exceptions::PyTimeoutError>(py));
+    });
+    assert_eq!(heads.try_iter().count(), 1);
+}
+
+#[test]
+fn limits_are_configurable_and_validated() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider_with(py, "http://127.0.0.1:9/v1", |kwargs| {
+            kwargs.set_item("max_retries", 0)?;
+            kwargs.set_item("max_requests_per_minute", 1)
+        });
+        let requests: u32 = provider
+            .getattr("max_requests_per_minute")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(requests, 1);
+
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("max_tokens_per_minute", 2000).unwrap();
+        let copy = provider
+            .call_method("with_options", (), Some(&kwargs))
+            .unwrap();
+        let tokens: u64 = copy
+            .getattr("max_tokens_per_minute")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(tokens, 2000);
+        let levels = copy
+            .call_method0("rate_limiter_levels")
+            .unwrap()
+            .cast_into::<PyDict>()
+            .unwrap();
+        let (requests, tokens): (f64, f64) = (
+            levels
+                .get_item("requests")
+                .unwrap()
+                .unwrap()
+                .extract()
+                .unwrap(),
+            levels
+                .get_item("tokens")
+                .unwrap()
+                .unwrap()
+                .extract()
+                .unwrap(),
+        );
+        assert_eq!((requests, tokens), (1.0, 2000.0));
+
+        kwargs.set_item("max_requests_per_minute", -1).unwrap();
+        let err = provider
+            .call_method("with_options", (), Some(&kwargs))
+            .expect_err("negative limits are rejected");
+        assert!(err.to_string().contains("max_requests_per_minute"));
+    });
+}
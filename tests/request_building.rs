@@ -1,4 +1,6 @@
-use rusty_agent_sdk::internal::{ChatMessage, GenerationParams};
+use rusty_agent_sdk::internal::{
+    ChatMessage, GenerationParams, MessageContent, validate_content_parts, validate_message_roles,
+};
 
 #[test]
 fn build_messages_from_prompt_only() {
@@ -26,14 +28,17 @@ fn build_messages_from_messages_list() {
         ChatMessage {
             role: "user".into(),
             content: "Hi".into(),
+            ..Default::default()
         },
         ChatMessage {
             role: "assistant".into(),
             content: "Hello".into(),
+            ..Default::default()
         },
         ChatMessage {
             role: "user".into(),
             content: "How are you?".into(),
+            ..Default::default()
         },
     ];
     let msgs =
@@ -48,6 +53,7 @@ fn build_messages_with_system_prompt_and_messages_list() {
     let input = vec![ChatMessage {
         role: "user".into(),
         content: "Hi".into(),
+        ..Default::default()
     }];
     let msgs = GenerationParams::build_messages(None, Some("Be concise"), Some(input))
         .expect("should prepend system_prompt");
@@ -62,6 +68,7 @@ fn build_messages_prefers_messages_over_prompt() {
     let input = vec![ChatMessage {
         role: "user".into(),
         content: "From messages".into(),
+        ..Default::default()
     }];
     let msgs = GenerationParams::build_messages(Some("From prompt"), None, Some(input))
         .expect("should prefer messages");
@@ -82,15 +89,9 @@ fn chat_request_serialization_omits_none_fields() {
         messages: vec![ChatMessage {
             role: "user".into(),
             content: "Hi".into(),
+            ..Default::default()
         }],
-        temperature: None,
-        max_tokens: None,
-        top_p: None,
-        stop: None,
-        frequency_penalty: None,
-        presence_penalty: None,
-        seed: None,
-        response_format: None,
+        ..Default::default()
     };
     let req = params.into_chat_request("gpt-4".into(), None, None);
     let json = serde_json::to_string(&req).expect("should serialise");
@@ -113,6 +114,7 @@ fn chat_request_serialization_includes_set_fields() {
         messages: vec![ChatMessage {
             role: "user".into(),
             content: "Hi".into(),
+            ..Default::default()
         }],
         temperature: Some(0.7),
         max_tokens: Some(100),
@@ -122,6 +124,7 @@ fn chat_request_serialization_includes_set_fields() {
         presence_penalty: None,
         seed: Some(42),
         response_format: Some(serde_json::json!({"type": "json_object"})),
+        ..Default::default()
     };
     let req = params.into_chat_request("gpt-4".into(), Some(true), None);
     let json: serde_json::Value = serde_json::to_value(&req).expect("should serialise");
@@ -142,15 +145,9 @@ fn chat_request_includes_stream_options_when_set() {
         messages: vec![ChatMessage {
             role: "user".into(),
             content: "Hi".into(),
+            ..Default::default()
         }],
-        temperature: None,
-        max_tokens: None,
-        top_p: None,
-        stop: None,
-        frequency_penalty: None,
-        presence_penalty: None,
-        seed: None,
-        response_format: None,
+        ..Default::default()
     };
     let stream_opts = serde_json::json!({"include_usage": true});
     let req = params.into_chat_request("gpt-4".into(), Some(true), Some(stream_opts));
@@ -166,18 +163,493 @@ fn chat_request_omits_stream_options_when_none() {
         messages: vec![ChatMessage {
             role: "user".into(),
             content: "Hi".into(),
+            ..Default::default()
         }],
-        temperature: None,
-        max_tokens: None,
-        top_p: None,
-        stop: None,
-        frequency_penalty: None,
-        presence_penalty: None,
-        seed: None,
-        response_format: None,
+        ..Default::default()
     };
     let req = params.into_chat_request("gpt-4".into(), Some(true), None);
     let json = serde_json::to_string(&req).expect("should serialise");
 
     assert!(!json.contains("stream_options"));
 }
+
+#[test]
+fn chat_request_includes_reasoning_effort_when_set() {
+    let params = GenerationParams {
+        messages: vec![ChatMessage {
+            role: "user".into(),
+            content: "Hi".into(),
+            ..Default::default()
+        }],
+        reasoning_effort: Some("high".into()),
+        ..Default::default()
+    };
+    params.validate().expect("'high' should be accepted");
+    let req = params.into_chat_request("o3-mini".into(), None, None);
+    let json: serde_json::Value = serde_json::to_value(&req).expect("should serialise");
+
+    assert_eq!(json["reasoning_effort"], "high");
+}
+
+#[test]
+fn chat_request_omits_reasoning_effort_when_none() {
+    let params = GenerationParams {
+        messages: vec![ChatMessage {
+            role: "user".into(),
+            content: "Hi".into(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let req = params.into_chat_request("gpt-4".into(), None, None);
+    let json = serde_json::to_string(&req).expect("should serialise");
+
+    assert!(!json.contains("reasoning_effort"));
+}
+
+#[test]
+fn validate_rejects_unknown_reasoning_effort() {
+    let params = GenerationParams {
+        reasoning_effort: Some("extreme".into()),
+        ..Default::default()
+    };
+
+    let err = params.validate().expect_err("unknown effort should fail");
+    let msg = format!("{:?}", err);
+    assert!(msg.contains("reasoning_effort"));
+    assert!(msg.contains("extreme"));
+}
+
+#[test]
+fn chat_request_includes_prediction_when_set() {
+    let params = GenerationParams {
+        messages: vec![ChatMessage {
+            role: "user".into(),
+            content: "Hi".into(),
+            ..Default::default()
+        }],
+        prediction: Some(serde_json::json!({"type": "content", "content": "fn main() {}"})),
+        ..Default::default()
+    };
+    let req = params.into_chat_request("gpt-4o".into(), None, None);
+    let json: serde_json::Value = serde_json::to_value(&req).expect("should serialise");
+
+    assert_eq!(json["prediction"]["type"], "content");
+    assert_eq!(json["prediction"]["content"], "fn main() {}");
+}
+
+#[test]
+fn chat_request_includes_service_tier_when_set() {
+    let params = GenerationParams {
+        messages: vec![ChatMessage {
+            role: "user".into(),
+            content: "Hi".into(),
+            ..Default::default()
+        }],
+        service_tier: Some("flex".into()),
+        ..Default::default()
+    };
+    let req = params.into_chat_request("gpt-4o".into(), None, None);
+    let json: serde_json::Value = serde_json::to_value(&req).expect("should serialise");
+
+    assert_eq!(json["service_tier"], "flex");
+}
+
+#[test]
+fn chat_request_omits_service_tier_when_none() {
+    let params = GenerationParams {
+        messages: vec![ChatMessage {
+            role: "user".into(),
+            content: "Hi".into(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let req = params.into_chat_request("gpt-4o".into(), None, None);
+    let json = serde_json::to_string(&req).expect("should serialise");
+
+    assert!(!json.contains("service_tier"));
+}
+
+#[test]
+fn chat_request_sends_models_instead_of_model() {
+    let params = GenerationParams {
+        messages: vec![ChatMessage {
+            role: "user".into(),
+            content: "Hi".into(),
+            ..Default::default()
+        }],
+        models: Some(vec!["openai/gpt-4o".into(), "openai/gpt-4o-mini".into()]),
+        ..Default::default()
+    };
+    params.validate().expect("a models list should be accepted");
+    let req = params.into_chat_request("ignored".into(), None, None);
+    assert_eq!(req.primary_model(), "openai/gpt-4o");
+    let json: serde_json::Value = serde_json::to_value(&req).expect("should serialise");
+
+    assert_eq!(
+        json["models"],
+        serde_json::json!(["openai/gpt-4o", "openai/gpt-4o-mini"])
+    );
+    assert!(json.get("model").is_none());
+}
+
+#[test]
+fn validate_rejects_empty_models() {
+    let params = GenerationParams {
+        models: Some(Vec::new()),
+        ..Default::default()
+    };
+    let err = params.validate().expect_err("an empty list should fail");
+    assert!(format!("{:?}", err).contains("'models' must not be empty"));
+
+    let params = GenerationParams {
+        models: Some(vec!["openai/gpt-4o".into(), String::new()]),
+        ..Default::default()
+    };
+    let err = params.validate().expect_err("an empty name should fail");
+    assert!(format!("{:?}", err).contains("entry 1"));
+}
+
+#[test]
+fn validate_accepts_up_to_four_stop_sequences() {
+    let params = GenerationParams {
+        stop: Some(serde_json::json!(["a", "b", "c", "d"])),
+        ..Default::default()
+    };
+
+    params
+        .validate()
+        .expect("four stop sequences should be accepted");
+}
+
+#[test]
+fn validate_rejects_more_than_four_stop_sequences() {
+    let params = GenerationParams {
+        stop: Some(serde_json::json!(["a", "b", "c", "d", "e"])),
+        ..Default::default()
+    };
+
+    let err = params
+        .validate()
+        .expect_err("five stop sequences should fail");
+    let msg = format!("{:?}", err);
+    assert!(msg.contains("at most 4"));
+}
+
+#[test]
+fn validate_rejects_empty_stop_sequence() {
+    let params = GenerationParams {
+        stop: Some(serde_json::json!(["END", ""])),
+        ..Default::default()
+    };
+
+    let err = params
+        .validate()
+        .expect_err("empty stop sequence should fail");
+    assert!(format!("{:?}", err).contains("non-empty"));
+
+    let params = GenerationParams {
+        stop: Some(serde_json::json!("")),
+        ..Default::default()
+    };
+    assert!(params.validate().is_err());
+}
+
+#[test]
+fn validate_accepts_in_range_sampling_parameters() {
+    let params = GenerationParams {
+        temperature: Some(2.0),
+        max_tokens: Some(1),
+        top_p: Some(1.0),
+        frequency_penalty: Some(-2.0),
+        presence_penalty: Some(2.0),
+        ..Default::default()
+    };
+
+    params
+        .validate()
+        .expect("boundary values should be accepted");
+}
+
+#[test]
+fn validate_rejects_out_of_range_temperature() {
+    let params = GenerationParams {
+        temperature: Some(5.0),
+        ..Default::default()
+    };
+
+    let err = params.validate().expect_err("temperature 5.0 should fail");
+    let msg = format!("{:?}", err);
+    assert!(msg.contains("'temperature' must be in [0, 2]"));
+}
+
+#[test]
+fn validate_rejects_out_of_range_top_p() {
+    for top_p in [0.0, 1.5] {
+        let params = GenerationParams {
+            top_p: Some(top_p),
+            ..Default::default()
+        };
+
+        let err = params
+            .validate()
+            .expect_err("top_p outside (0, 1] should fail");
+        assert!(format!("{:?}", err).contains("'top_p' must be in (0, 1]"));
+    }
+}
+
+#[test]
+fn validate_rejects_out_of_range_penalties() {
+    let params = GenerationParams {
+        frequency_penalty: Some(-2.5),
+        ..Default::default()
+    };
+    let err = params
+        .validate()
+        .expect_err("frequency_penalty -2.5 should fail");
+    assert!(format!("{:?}", err).contains("frequency_penalty"));
+
+    let params = GenerationParams {
+        presence_penalty: Some(3.0),
+        ..Default::default()
+    };
+    let err = params
+        .validate()
+        .expect_err("presence_penalty 3.0 should fail");
+    assert!(format!("{:?}", err).contains("presence_penalty"));
+}
+
+#[test]
+fn validate_rejects_zero_max_tokens() {
+    let params = GenerationParams {
+        max_tokens: Some(0),
+        ..Default::default()
+    };
+
+    let err = params.validate().expect_err("max_tokens 0 should fail");
+    assert!(format!("{:?}", err).contains("'max_tokens' must be greater than zero"));
+}
+
+#[test]
+fn validate_rejects_zero_n() {
+    let params = GenerationParams {
+        n: Some(0),
+        ..Default::default()
+    };
+
+    let err = params.validate().expect_err("n 0 should fail");
+    assert!(format!("{:?}", err).contains("'n' must be greater than zero"));
+}
+
+#[test]
+fn chat_request_serializes_n_when_set() {
+    let params = GenerationParams {
+        n: Some(2),
+        ..Default::default()
+    };
+    let req = params.into_chat_request("gpt-4".into(), Some(true), None);
+    let json = serde_json::to_value(&req).expect("should serialise");
+    assert_eq!(json["n"], 2);
+
+    let req = GenerationParams::default().into_chat_request("gpt-4".into(), None, None);
+    let json = serde_json::to_value(&req).expect("should serialise");
+    assert!(json.get("n").is_none());
+}
+
+#[test]
+fn validate_rejects_nan_temperature() {
+    let params = GenerationParams {
+        temperature: Some(f64::NAN),
+        ..Default::default()
+    };
+
+    assert!(params.validate().is_err());
+}
+
+#[test]
+fn validate_message_roles_accepts_known_roles() {
+    let msgs: Vec<ChatMessage> = ["system", "user", "assistant", "tool", "developer"]
+        .into_iter()
+        .map(|role| ChatMessage {
+            role: role.into(),
+            content: "x".into(),
+            ..Default::default()
+        })
+        .collect();
+
+    validate_message_roles(&msgs).expect("known roles should be accepted");
+}
+
+#[test]
+fn validate_message_roles_reports_index_of_unknown_role() {
+    let msgs = vec![
+        ChatMessage {
+            role: "user".into(),
+            content: "Hi".into(),
+            ..Default::default()
+        },
+        ChatMessage {
+            role: "narrator".into(),
+            content: "Meanwhile...".into(),
+            ..Default::default()
+        },
+    ];
+
+    let err = validate_message_roles(&msgs).expect_err("unknown role should fail");
+    let msg = format!("{:?}", err);
+    assert!(msg.contains("messages[1]"));
+    assert!(msg.contains("narrator"));
+}
+
+#[test]
+fn tool_message_serializes_tool_call_id() {
+    let msg = ChatMessage {
+        role: "tool".into(),
+        content: "{\"temp\": 21}".into(),
+        tool_call_id: Some("call_123".into()),
+        ..Default::default()
+    };
+    let json = serde_json::to_value(&msg).expect("should serialise");
+
+    assert_eq!(json["tool_call_id"], "call_123");
+
+    let plain = ChatMessage {
+        role: "user".into(),
+        content: "Hi".into(),
+        ..Default::default()
+    };
+    let json = serde_json::to_string(&plain).expect("should serialise");
+    assert!(!json.contains("tool_call_id"));
+}
+
+#[test]
+fn message_name_is_serialized_and_preserved_by_build_messages() {
+    let input = vec![ChatMessage {
+        role: "user".into(),
+        content: "Hi".into(),
+        name: Some("alice".into()),
+        ..Default::default()
+    }];
+    let msgs = GenerationParams::build_messages(None, Some("Be brief"), Some(input))
+        .expect("should build messages");
+    let params = GenerationParams {
+        messages: msgs,
+        ..Default::default()
+    };
+    let req = params.into_chat_request("gpt-4".into(), None, None);
+    let json: serde_json::Value = serde_json::to_value(&req).expect("should serialise");
+
+    assert_eq!(json["messages"][1]["name"], "alice");
+    assert!(json["messages"][0].get("name").is_none());
+}
+
+#[test]
+fn text_content_serializes_as_string_and_round_trips() {
+    let msg = ChatMessage {
+        role: "user".into(),
+        content: "Describe this".into(),
+        ..Default::default()
+    };
+    let json = serde_json::to_value(&msg).expect("should serialise");
+
+    assert_eq!(json["content"], "Describe this");
+
+    let back: MessageContent =
+        serde_json::from_value(json["content"].clone()).expect("should deserialise");
+    assert_eq!(back, msg.content);
+}
+
+#[test]
+fn parts_content_serializes_as_array_and_round_trips() {
+    let parts = vec![
+        serde_json::json!({"type": "text", "text": "What is in this image?"}),
+        serde_json::json!({"type": "image_url", "image_url": {"url": "https://example.com/cat.png"}}),
+    ];
+    let msg = ChatMessage {
+        role: "user".into(),
+        content: MessageContent::Parts(parts.clone()),
+        ..Default::default()
+    };
+    let json = serde_json::to_value(&msg).expect("should serialise");
+
+    assert_eq!(json["content"][0]["type"], "text");
+    assert_eq!(
+        json["content"][1]["image_url"]["url"],
+        "https://example.com/cat.png"
+    );
+
+    let back: MessageContent =
+        serde_json::from_value(json["content"].clone()).expect("should deserialise");
+    assert_eq!(back, MessageContent::Parts(parts));
+}
+
+#[test]
+fn validate_content_parts_rejects_unknown_type_and_missing_payload() {
+    let parts = vec![serde_json::json!({"type": "video", "video": {}})];
+    let err = validate_content_parts("content", &parts).expect_err("unknown type should fail");
+    assert!(format!("{:?}", err).contains("unsupported type 'video'"));
+
+    let parts = vec![serde_json::json!({"type": "image_url"})];
+    let err = validate_content_parts("content", &parts).expect_err("missing payload should fail");
+    assert!(format!("{:?}", err).contains("missing the 'image_url' key"));
+}
+
+#[test]
+fn chat_request_includes_modalities_and_audio_when_set() {
+    let params = GenerationParams {
+        messages: vec![ChatMessage {
+            role: "user".into(),
+            content: "Say hi".into(),
+            ..Default::default()
+        }],
+        modalities: Some(vec!["text".into(), "audio".into()]),
+        audio: Some(serde_json::json!({"voice": "alloy", "format": "wav"})),
+        ..Default::default()
+    };
+    params.validate().expect("text+audio should be accepted");
+    let req = params.into_chat_request("gpt-4o-audio-preview".into(), None, None);
+    let json: serde_json::Value = serde_json::to_value(&req).expect("should serialise");
+
+    assert_eq!(json["modalities"], serde_json::json!(["text", "audio"]));
+    assert_eq!(json["audio"]["voice"], "alloy");
+    assert_eq!(json["audio"]["format"], "wav");
+}
+
+#[test]
+fn validate_rejects_bad_modalities_and_audio() {
+    let params = GenerationParams {
+        modalities: Some(vec!["video".into()]),
+        ..Default::default()
+    };
+    let err = params.validate().expect_err("unknown modality should fail");
+    assert!(format!("{:?}", err).contains("'video'"));
+
+    let params = GenerationParams {
+        modalities: Some(vec!["text".into(), "audio".into()]),
+        ..Default::default()
+    };
+    let err = params
+        .validate()
+        .expect_err("audio modality needs audio settings");
+    assert!(format!("{:?}", err).contains("'audio'"));
+
+    let params = GenerationParams {
+        audio: Some(serde_json::json!({"voice": "alloy"})),
+        ..Default::default()
+    };
+    let err = params
+        .validate()
+        .expect_err("audio without format should fail");
+    assert!(format!("{:?}", err).contains("'format'"));
+}
+
+#[test]
+fn validate_content_parts_accepts_input_audio() {
+    let parts = vec![serde_json::json!({
+        "type": "input_audio",
+        "input_audio": {"data": "UklGRg==", "format": "wav"}
+    })];
+
+    validate_content_parts("content", &parts).expect("input_audio should be accepted");
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::time::Duration;
+
+const GENERATE_BODY: &[u8] =
+    b"{\"choices\":[{\"message\":{\"role\":\"assistant\",\"content\":\"Hi\"},\
+\"finish_reason\":\"stop\"}]}";
+
+const STREAM_BODY: &[u8] = b"data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n\
+data: [DONE]\n\n";
+
+fn generate_full<'py>(py: Python<'py>, base_url: &str) -> PyResult<Bound<'py, PyAny>> {
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("include_usage", true).unwrap();
+    common::provider(py, base_url).call_method("generate_text", ("Hello",), Some(&kwargs))
+}
+
+fn request_id(object: &Bound<'_, PyAny>) -> Option<String> {
+    object.getattr("request_id").unwrap().extract().unwrap()
+}
+
+#[test]
+fn generate_result_exposes_the_request_id_header() {
+    let base_url = common::serve_once(
+        b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nx-request-id: req_123\r\nConnection: close\r\n\r\n",
+        Duration::ZERO,
+        GENERATE_BODY,
+    );
+    Python::initialize();
+
+    Python::attach(|py| {
+        let result = generate_full(py, &base_url).expect("generation should succeed");
+        assert_eq!(request_id(&result).as_deref(), Some("req_123"));
+    });
+}
+
+#[test]
+fn openrouter_id_is_used_when_x_request_id_is_absent() {
+    let base_url = common::serve_once(
+        b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nopenrouter-id: gen-abc\r\nConnection: close\r\n\r\n",
+        Duration::ZERO,
+        GENERATE_BODY,
+    );
+    Python::initialize();
+
+    Python::attach(|py| {
+        let result = generate_full(py, &base_url).expect("generation should succeed");
+        assert_eq!(request_id(&result).as_deref(), Some("gen-abc"));
+    });
+}
+
+#[test]
+fn request_id_is_none_without_the_header() {
+    let base_url = common::serve_once(
+        b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n",
+        Duration::ZERO,
+        GENERATE_BODY,
+    );
+    Python::initialize();
+
+    Python::attach(|py| {
+        let result = generate_full(py, &base_url).expect("generation should succeed");
+        assert_eq!(request_id(&result), None);
+    });
+}
+
+#[test]
+fn api_errors_carry_the_request_id() {
+    let base_url = common::serve_once(
+        b"HTTP/1.1 400 Bad Request\r\nContent-Type: application/json\r\nx-request-id: req_err\r\nConnection: close\r\n\r\n",
+        Duration::ZERO,
+        b"{\"error\":{\"message\":\"bad request\"}}",
+    );
+    Python::initialize();
+
+    Python::attach(|py| {
+        let err = generate_full(py, &base_url).expect_err("400 should fail");
+        assert_eq!(request_id(err.value(py)).as_deref(), Some("req_err"));
+    });
+}
+
+#[test]
+fn text_stream_exposes_the_request_id_header() {
+    let base_url = common::serve_once(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nx-request-id: req_stream\r\nConnection: close\r\n\r\n",
+        Duration::ZERO,
+        STREAM_BODY,
+    );
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = common::provider(py, &base_url)
+            .call_method1("stream_text", ("Hello",))
+            .expect("stream should start");
+        let text: String = stream.call_method0("text").unwrap().extract().unwrap();
+        assert_eq!(text, "Hi");
+        assert_eq!(request_id(&stream).as_deref(), Some("req_stream"));
+    });
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyInt, PyList};
+
+const UNAVAILABLE: &[u8] =
+    b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+const OK: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"choices\":[{\"message\":{\"role\":\"assistant\",\"content\":\"ok\"}}]}";
+
+const STREAM_OK: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"ok\"}}]}\n\ndata: [DONE]\n\n";
+
+/// A callback that appends its arguments to `calls`.
+fn recorder<'py>(py: Python<'py>, calls: &Bound<'py, PyList>) -> Bound<'py, PyAny> {
+    let globals = PyDict::new(py);
+    globals.set_item("calls", calls).unwrap();
+    py.eval(c"lambda *args: calls.append(args)", Some(&globals), None)
+        .unwrap()
+}
+
+fn count(provider: &Bound<'_, PyAny>, name: &str) -> u64 {
+    provider.getattr(name).unwrap().extract().unwrap()
+}
+
+#[test]
+fn on_retry_sees_the_attempt_status_and_delay() {
+    let (base_url, _heads) = common::serve_sequence(vec![UNAVAILABLE, OK]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let calls = PyList::empty(py);
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("on_retry", recorder(py, &calls))
+        });
+        let text: String = provider
+            .call_method1("generate_text", ("Hello",))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(text, "ok");
+
+        let calls: Vec<(u32, u16, f64)> = calls.extract().unwrap();
+        assert_eq!(calls.len(), 1);
+        let (attempt, status, delay) = calls[0];
+        assert_eq!((attempt, status), (1, 503));
+        assert!((0.0..=0.25).contains(&delay), "{}", delay);
+        assert_eq!(count(&provider, "retry_count"), 1);
+        assert_eq!(count(&provider, "on_retry_errors"), 0);
+    });
+}
+
+#[test]
+fn on_retry_errors_are_counted_without_failing_the_request() {
+    let (base_url, _heads) = common::serve_sequence(vec![UNAVAILABLE, OK]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        // int() rejects three arguments with a TypeError.
+        let failing = py.get_type::<PyInt>().into_any();
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("on_retry", &failing)
+        });
+        let text: String = provider
+            .call_method1("generate_text", ("Hello",))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(text, "ok");
+        assert_eq!(count(&provider, "on_retry_errors"), 1);
+    });
+}
+
+#[test]
+fn per_call_on_retry_replaces_the_providers() {
+    let (base_url, _heads) = common::serve_sequence(vec![UNAVAILABLE, OK]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider_calls = PyList::empty(py);
+        let call_calls = PyList::empty(py);
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("on_retry", recorder(py, &provider_calls))
+        });
+        let kwargs = PyDict::new(py);
+        kwargs
+            .set_item("on_retry", recorder(py, &call_calls))
+            .unwrap();
+        provider
+            .call_method("generate_text", ("Hello",), Some(&kwargs))
+            .unwrap();
+
+        assert_eq!(provider_calls.len(), 0);
+        assert_eq!(call_calls.len(), 1);
+        // The call's copy shares the provider's counts.
+        assert_eq!(count(&provider, "retry_count"), 1);
+    });
+}
+
+#[test]
+fn stream_retries_are_reported() {
+    let (base_url, _heads) = common::serve_sequence(vec![UNAVAILABLE, STREAM_OK]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let calls = PyList::empty(py);
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("on_retry", recorder(py, &calls))
+        });
+        let stream = provider.call_method1("stream_text", ("Hello",)).unwrap();
+        let text: String = stream.call_method0("text").unwrap().extract().unwrap();
+        assert_eq!(text, "ok");
+
+        let calls: Vec<(u32, u16, f64)> = calls.extract().unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!((calls[0].0, calls[0].1), (1, 503));
+    });
+}
+
+#[test]
+fn on_retry_must_be_callable() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("on_retry", "not callable").unwrap();
+        let err = common::provider(py, "http://127.0.0.1:9/v1")
+            .call_method("with_options", (), Some(&kwargs))
+            .unwrap_err();
+        assert!(err.is_instance_of::<PyTypeError>(py));
+        assert!(err.to_string().contains("on_retry"), "{}", err);
+    });
+}
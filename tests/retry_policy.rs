@@ -0,0 +1,320 @@
+mod common;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, HeaderValue};
+use rusty_agent_sdk::internal::{
+    DEFAULT_RETRY_STATUSES, is_retryable_status, jitter, parse_reset_duration, parse_retry_after,
+    retry_after, retry_delay, retry_wait,
+};
+use std::time::{Duration, Instant, SystemTime};
+
+/// 2015-10-21T07:28:00Z, the example date of RFC 9110.
+fn rfc_example_time() -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(1_445_412_480)
+}
+
+#[test]
+fn retry_after_reads_delay_seconds() {
+    let now = SystemTime::now();
+
+    assert_eq!(
+        parse_retry_after("120", now),
+        Some(Duration::from_secs(120))
+    );
+    assert_eq!(parse_retry_after(" 0 ", now), Some(Duration::ZERO));
+    assert_eq!(parse_retry_after("-1", now), None);
+    assert_eq!(parse_retry_after("soon", now), None);
+}
+
+#[test]
+fn retry_after_reads_http_dates() {
+    let now = rfc_example_time() - Duration::from_secs(90);
+
+    assert_eq!(
+        parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now),
+        Some(Duration::from_secs(90))
+    );
+    assert_eq!(
+        parse_retry_after("Thu, 29 Feb 2024 23:59:59 GMT", SystemTime::UNIX_EPOCH),
+        Some(Duration::from_secs(1_709_251_199))
+    );
+}
+
+#[test]
+fn retry_after_treats_past_dates_as_no_wait() {
+    let now = rfc_example_time() + Duration::from_secs(5);
+
+    assert_eq!(
+        parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now),
+        Some(Duration::ZERO)
+    );
+}
+
+#[test]
+fn retry_after_rejects_malformed_dates() {
+    let now = SystemTime::UNIX_EPOCH;
+
+    for value in [
+        "Wed, 21 Oct 2015 07:28:00 UTC",
+        "Wed, 21 Foo 2015 07:28:00 GMT",
+        "Wed, 32 Oct 2015 07:28:00 GMT",
+        "Wed, 21 Oct 2015 24:00:00 GMT",
+        "Wed, 21 Oct 2015 07:28 GMT",
+        "2015-10-21T07:28:00Z",
+    ] {
+        assert_eq!(parse_retry_after(value, now), None, "{}", value);
+    }
+}
+
+#[test]
+fn reset_durations_use_go_style_units() {
+    assert_eq!(parse_reset_duration("1s"), Some(Duration::from_secs(1)));
+    assert_eq!(parse_reset_duration("6m0s"), Some(Duration::from_secs(360)));
+    assert_eq!(
+        parse_reset_duration("20ms"),
+        Some(Duration::from_millis(20))
+    );
+    assert_eq!(
+        parse_reset_duration("1h2m3.5s"),
+        Some(Duration::from_millis(3_723_500))
+    );
+    assert_eq!(parse_reset_duration(""), None);
+    assert_eq!(parse_reset_duration("5"), None);
+    assert_eq!(parse_reset_duration("5d"), None);
+}
+
+#[test]
+fn retry_after_prefers_the_retry_after_header() {
+    let mut headers = HeaderMap::new();
+    headers.insert("x-ratelimit-reset-requests", HeaderValue::from_static("2s"));
+    headers.insert("x-ratelimit-reset-tokens", HeaderValue::from_static("6m0s"));
+    assert_eq!(
+        retry_after(&headers, SystemTime::now()),
+        Some(Duration::from_secs(360))
+    );
+
+    headers.insert("retry-after", HeaderValue::from_static("3"));
+    assert_eq!(
+        retry_after(&headers, SystemTime::now()),
+        Some(Duration::from_secs(3))
+    );
+
+    assert_eq!(retry_after(&HeaderMap::new(), SystemTime::now()), None);
+}
+
+#[test]
+fn retry_delay_doubles_per_attempt_up_to_two_hundred_fifty_six_times() {
+    let base = Duration::from_millis(100);
+
+    assert_eq!(retry_delay(base, 0), Duration::from_millis(100));
+    assert_eq!(retry_delay(base, 3), Duration::from_millis(800));
+    assert_eq!(retry_delay(base, 8), Duration::from_millis(25_600));
+    assert_eq!(retry_delay(base, 20), Duration::from_millis(25_600));
+}
+
+#[test]
+fn retry_wait_scales_the_backoff_by_the_jitter_sample() {
+    let base = Duration::from_millis(100);
+    let cap = Duration::from_secs(10);
+
+    assert_eq!(retry_wait(base, 2, None, cap, 0.0), Duration::ZERO);
+    assert_eq!(
+        retry_wait(base, 2, None, cap, 0.5),
+        Duration::from_millis(200)
+    );
+    assert_eq!(
+        retry_wait(base, 2, None, cap, 1.0),
+        Duration::from_millis(400)
+    );
+}
+
+#[test]
+fn retry_wait_jitters_below_the_cap() {
+    let cap = Duration::from_secs(30);
+
+    assert_eq!(
+        retry_wait(Duration::from_secs(5), 8, None, cap, 0.5),
+        Duration::from_secs(15)
+    );
+    assert_eq!(retry_wait(Duration::from_secs(5), 8, None, cap, 1.0), cap);
+}
+
+#[test]
+fn retry_wait_honors_longer_hints_up_to_the_cap() {
+    let base = Duration::from_millis(100);
+    let cap = Duration::from_secs(10);
+
+    assert_eq!(
+        retry_wait(base, 2, Some(Duration::from_millis(50)), cap, 1.0),
+        Duration::from_millis(400)
+    );
+    assert_eq!(
+        retry_wait(base, 2, Some(Duration::from_secs(3)), cap, 1.0),
+        Duration::from_secs(3)
+    );
+    assert_eq!(
+        retry_wait(base, 0, Some(Duration::from_secs(60)), cap, 0.0),
+        cap
+    );
+}
+
+#[test]
+fn jitter_samples_spread_over_the_unit_interval() {
+    let samples: Vec<f64> = (0..1000).map(|_| jitter()).collect();
+
+    assert!(samples.iter().all(|sample| (0.0..1.0).contains(sample)));
+    assert!(samples.iter().any(|&sample| sample < 0.25));
+    assert!(samples.iter().any(|&sample| sample > 0.75));
+}
+
+#[test]
+fn generate_waits_as_long_as_retry_after_asks() {
+    let (base_url, _heads) = common::serve_sequence(vec![
+        b"HTTP/1.1 429 Too Many Requests\r\nRetry-After: 1\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"choices\":[{\"message\":{\"role\":\"assistant\",\"content\":\"ok\"}}]}",
+    ]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let started = Instant::now();
+        let text: String = common::provider(py, &base_url)
+            .call_method1("generate_text", ("Hello",))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(text, "ok");
+        assert!(started.elapsed() >= Duration::from_secs(1));
+    });
+}
+
+#[test]
+fn exhausted_retries_report_the_time_spent_waiting() {
+    const RATE_LIMITED: &[u8] =
+        b"HTTP/1.1 429 Too Many Requests\r\nContent-Type: application/json\r\n\
+Content-Length: 34\r\nConnection: close\r\n\r\n{\"error\":{\"message\":\"slow down\"}}\n";
+    let (base_url, _heads) = common::serve_sequence(vec![RATE_LIMITED; 3]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let err = common::provider(py, &base_url)
+            .call_method1("generate_text", ("Hello",))
+            .expect_err("429s should fail");
+        let message = err.to_string();
+        assert!(message.contains("slow down"), "{}", message);
+        assert!(
+            message.contains("gave up after 2 retry attempt(s)"),
+            "{}",
+            message
+        );
+        assert!(message.contains("s spent waiting"), "{}", message);
+    });
+}
+
+const OK: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"choices\":[{\"message\":{\"role\":\"assistant\",\"content\":\"ok\"}}]}";
+
+#[test]
+fn retryable_statuses_come_from_the_configured_set() {
+    assert!(is_retryable_status(
+        StatusCode::TOO_MANY_REQUESTS,
+        &DEFAULT_RETRY_STATUSES
+    ));
+    assert!(!is_retryable_status(
+        StatusCode::REQUEST_TIMEOUT,
+        &DEFAULT_RETRY_STATUSES
+    ));
+
+    assert!(is_retryable_status(
+        StatusCode::REQUEST_TIMEOUT,
+        &[408, 529]
+    ));
+    assert!(!is_retryable_status(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        &[408, 529]
+    ));
+    assert!(!is_retryable_status(StatusCode::TOO_MANY_REQUESTS, &[]));
+}
+
+#[test]
+fn custom_retry_statuses_are_retried() {
+    let (base_url, _heads) = common::serve_sequence(vec![
+        b"HTTP/1.1 408 Request Timeout\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        OK,
+    ]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let text: String = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("retry_backoff_ms", 1)?;
+            kwargs.set_item("retry_on_status", [408])
+        })
+        .call_method1("generate_text", ("Hello",))
+        .unwrap()
+        .extract()
+        .unwrap();
+        assert_eq!(text, "ok");
+    });
+}
+
+#[test]
+fn statuses_outside_the_configured_set_fail_at_once() {
+    let (base_url, heads) = common::serve_sequence(vec![
+        b"HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        OK,
+    ]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("retry_backoff_ms", 1)?;
+            kwargs.set_item("retry_on_status", [429, 503])
+        });
+        let statuses: Vec<u16> = provider
+            .getattr("retry_on_status")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(statuses, [429, 503]);
+
+        let err = provider
+            .call_method1("generate_text", ("Hello",))
+            .expect_err("500 should not be retried");
+        let status: u16 = err
+            .value(py)
+            .getattr("status_code")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(status, 500);
+    });
+    assert_eq!(heads.try_iter().count(), 1);
+}
+
+#[test]
+fn retry_false_makes_a_single_attempt() {
+    let (base_url, heads) = common::serve_sequence(vec![
+        b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        OK,
+    ]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("retry", false).unwrap();
+        let err = common::provider(py, &base_url)
+            .call_method("generate_text", ("Hello",), Some(&kwargs))
+            .expect_err("503 should fail without retrying");
+        let status: u16 = err
+            .value(py)
+            .getattr("status_code")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(status, 503);
+        assert!(!err.to_string().contains("gave up"), "{}", err);
+    });
+    assert_eq!(heads.try_iter().count(), 1);
+}
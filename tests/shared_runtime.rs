@@ -0,0 +1,82 @@
+mod common;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rusty_agent_sdk::internal::runtimes_built;
+
+const GENERATED: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"choices\":[{\"message\":{\"role\":\"assistant\",\"content\":\"Hi\"},\"finish_reason\":\"stop\"}]}";
+
+const STREAMED: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n\
+data: [DONE]\n\n";
+
+#[test]
+fn sequential_calls_reuse_one_runtime() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let (base_url, _heads) = common::serve_sequence(vec![GENERATED, GENERATED, STREAMED]);
+        let provider = common::provider(py, &base_url);
+
+        for _ in 0..2 {
+            let text: String = provider
+                .call_method1("generate_text", ("Hello",))
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(text, "Hi");
+        }
+        let stream = provider.call_method1("stream_text", ("Hello",)).unwrap();
+        let text: String = stream.call_method0("text").unwrap().extract().unwrap();
+        assert_eq!(text, "Hi");
+
+        assert_eq!(runtimes_built(), 1);
+    });
+}
+
+#[test]
+fn a_forked_child_generates_with_its_own_runtime() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let (base_url, heads) = common::serve_sequence(vec![GENERATED, GENERATED]);
+        let provider = common::provider(py, &base_url);
+        let text: String = provider
+            .call_method1("generate_text", ("Hello",))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(text, "Hi");
+
+        // The child exits 0 once it has the reply. The alarm kills a child
+        // stuck on the parent's runtime instead of hanging the test.
+        let globals = PyDict::new(py);
+        globals.set_item("provider", &provider).unwrap();
+        py.run(
+            cr#"
+import os, signal
+pid = os.fork()
+if pid == 0:
+    signal.alarm(10)
+    try:
+        os._exit(0 if provider.generate_text("Hello") == "Hi" else 1)
+    except BaseException:
+        os._exit(2)
+exit_code = os.waitstatus_to_exitcode(os.waitpid(pid, 0)[1])
+"#,
+            Some(&globals),
+            None,
+        )
+        .unwrap();
+        let exit_code: i32 = globals
+            .get_item("exit_code")
+            .unwrap()
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(exit_code, 0, "the child's generate_text should succeed");
+        assert_eq!(heads.try_iter().count(), 2);
+    });
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::time::Duration;
+
+const HEAD: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n";
+
+/// One event per character of "Hello, w\u{f6}rld! \u{1f600}", then a finish.
+const BODY: &[u8] = "data: {\"choices\":[{\"delta\":{\"content\":\"H\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"e\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"l\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"l\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"o\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\",\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\" \"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"w\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"\u{f6}\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"r\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"l\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"d\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"!\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\" \"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"\u{1f600}\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n\
+data: [DONE]\n\n"
+    .as_bytes();
+
+const TEXT: &str = "Hello, w\u{f6}rld! \u{1f600}";
+
+fn collect_chunks(min_chunk_chars: Option<usize>) -> Vec<String> {
+    collect_chunks_with(min_chunk_chars, None)
+}
+
+fn collect_chunks_with(min_chunk_chars: Option<usize>, split: Option<&str>) -> Vec<String> {
+    let base_url = common::serve_once(HEAD, Duration::ZERO, BODY);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        if let Some(min_chunk_chars) = min_chunk_chars {
+            kwargs.set_item("min_chunk_chars", min_chunk_chars).unwrap();
+        }
+        if let Some(split) = split {
+            kwargs.set_item("split", split).unwrap();
+        }
+        let stream = common::provider(py, &base_url)
+            .call_method("stream_text", ("Hello",), Some(&kwargs))
+            .expect("stream should start");
+        stream
+            .try_iter()
+            .unwrap()
+            .map(|chunk| chunk.and_then(|chunk| chunk.extract()).unwrap())
+            .collect()
+    })
+}
+
+#[test]
+fn default_stream_yields_one_chunk_per_event() {
+    let chunks = collect_chunks(None);
+
+    assert_eq!(chunks.len(), TEXT.chars().count());
+    assert_eq!(chunks.concat(), TEXT);
+}
+
+#[test]
+fn coalesced_stream_merges_chunks_without_losing_text() {
+    let chunks = collect_chunks(Some(4));
+
+    assert_eq!(chunks.concat(), TEXT);
+    let (last, rest) = chunks.split_last().expect("should yield chunks");
+    assert!(!last.is_empty());
+    for chunk in rest {
+        assert!(
+            chunk.chars().count() >= 4,
+            "chunk {:?} is shorter than min_chunk_chars",
+            chunk
+        );
+    }
+}
+
+#[test]
+fn coalesced_stream_flushes_short_text_at_the_end() {
+    let chunks = collect_chunks(Some(1000));
+
+    assert_eq!(chunks, vec![TEXT.to_string()]);
+}
+
+#[test]
+fn sentence_split_stream_yields_sentences_and_flushes_the_tail() {
+    let chunks = collect_chunks_with(None, Some("sentence"));
+
+    assert_eq!(chunks, vec!["Hello, w\u{f6}rld! ", "\u{1f600}"]);
+}
+
+#[test]
+fn min_chunk_chars_rejects_detailed_streams() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("min_chunk_chars", 4).unwrap();
+        kwargs.set_item("detailed", true).unwrap();
+        let err = common::provider(py, "http://127.0.0.1:9/v1")
+            .call_method("stream_text", ("Hello",), Some(&kwargs))
+            .expect_err("detailed chunks cannot be coalesced");
+        assert!(err.to_string().contains("min_chunk_chars"));
+    });
+}
+
+#[test]
+fn client_stop_truncates_across_chunks_and_sets_finish_reason() {
+    let base_url = common::serve_once(HEAD, Duration::ZERO, BODY);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("client_stop", vec!["w\u{f6}r"]).unwrap();
+        let stream = common::provider(py, &base_url)
+            .call_method("stream_text", ("Hello",), Some(&kwargs))
+            .expect("stream should start");
+
+        let chunks: Vec<String> = stream
+            .try_iter()
+            .unwrap()
+            .map(|chunk| chunk.and_then(|chunk| chunk.extract()).unwrap())
+            .collect();
+
+        assert_eq!(chunks.concat(), "Hello, ");
+        assert!(chunks.iter().all(|chunk| !chunk.is_empty()));
+        let finish_reason: String = stream.getattr("finish_reason").unwrap().extract().unwrap();
+        assert_eq!(finish_reason, "client_stop");
+    });
+}
+
+#[test]
+fn stop_regex_with_include_match_yields_through_the_match() {
+    let base_url = common::serve_once(HEAD, Duration::ZERO, BODY);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("stop_regex", r"w\S+d").unwrap();
+        kwargs.set_item("include_match", true).unwrap();
+        let stream = common::provider(py, &base_url)
+            .call_method("stream_text", ("Hello",), Some(&kwargs))
+            .expect("stream should start");
+
+        let text: String = stream.call_method0("text").unwrap().extract().unwrap();
+        assert_eq!(text, "Hello, w\u{f6}rld");
+        let finish_reason: String = stream.getattr("finish_reason").unwrap().extract().unwrap();
+        assert_eq!(finish_reason, "client_stop");
+    });
+}
+
+#[test]
+fn stop_regex_rejects_invalid_patterns_before_connecting() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider(py, "http://127.0.0.1:9/v1");
+
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("stop_regex", "(unclosed").unwrap();
+        let err = provider
+            .call_method("stream_text", ("Hello",), Some(&kwargs))
+            .expect_err("invalid pattern should fail");
+        assert!(err.is_instance_of::<PyValueError>(py));
+        assert!(
+            err.to_string()
+                .contains("'stop_regex' is not a valid pattern")
+        );
+
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("include_match", true).unwrap();
+        let err = provider
+            .call_method("stream_text", ("Hello",), Some(&kwargs))
+            .expect_err("include_match needs a stop");
+        assert!(err.to_string().contains("include_match"));
+    });
+}
+
+#[test]
+fn client_stop_rejects_empty_sequences_and_detailed_streams() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider(py, "http://127.0.0.1:9/v1");
+
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("client_stop", vec![""]).unwrap();
+        let err = provider
+            .call_method("stream_text", ("Hello",), Some(&kwargs))
+            .expect_err("empty stop sequences should fail");
+        assert!(err.to_string().contains("must not be empty"));
+
+        kwargs.set_item("client_stop", vec!["END"]).unwrap();
+        kwargs.set_item("detailed", true).unwrap();
+        let err = provider
+            .call_method("stream_text", ("Hello",), Some(&kwargs))
+            .expect_err("detailed chunks cannot be stopped client-side");
+        assert!(err.to_string().contains("client_stop"));
+    });
+}
@@ -0,0 +1,193 @@
+mod common;
+
+use pyo3::exceptions::{PyRuntimeError, PyStopIteration};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rusty_agent_sdk::Provider;
+use std::time::{Duration, Instant};
+
+#[test]
+fn clean_stream_ends_with_stop_iteration_and_no_error() {
+    let base_url = common::serve_once(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n",
+        Duration::ZERO,
+        b"data: {\"choices\":[{\"delta\":{\"content\":\"hi\"}}]}\n\ndata: [DONE]\n\n",
+    );
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = common::provider(py, &base_url)
+            .call_method1("stream_text", ("Hello",))
+            .expect("stream should start");
+
+        let chunk: String = stream
+            .call_method0("__next__")
+            .and_then(|chunk| chunk.extract())
+            .expect("first chunk should arrive");
+        assert_eq!(chunk, "hi");
+
+        for _ in 0..2 {
+            let err = stream
+                .call_method0("__next__")
+                .expect_err("finished stream should stop");
+            assert!(err.is_instance_of::<PyStopIteration>(py));
+        }
+        assert!(stream.getattr("error").unwrap().is_none());
+    });
+}
+
+#[test]
+fn failed_stream_keeps_raising_its_error() {
+    let base_url = common::serve_once(
+        b"HTTP/1.1 401 Unauthorized\r\nContent-Type: application/json\r\nContent-Length: 36\r\nConnection: close\r\n\r\n",
+        Duration::ZERO,
+        b"{\"error\":{\"message\":\"bad api key\"}}\n",
+    );
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = common::provider(py, &base_url)
+            .call_method1("stream_text", ("Hello",))
+            .expect("stream should start");
+
+        let first = stream
+            .call_method0("__next__")
+            .expect_err("failed stream should raise");
+        assert!(first.is_instance_of::<PyRuntimeError>(py));
+        assert!(first.to_string().contains("bad api key"));
+
+        let second = stream
+            .call_method0("__next__")
+            .expect_err("failed stream should keep raising");
+        assert!(second.value(py).is(first.value(py)));
+
+        let error = stream.getattr("error").unwrap();
+        assert!(error.is(first.value(py)));
+    });
+}
+
+#[test]
+fn api_errors_carry_status_code_and_body() {
+    const BODY: &[u8] =
+        b"{\"error\":{\"message\":\"bad api key\",\"type\":\"auth\",\"code\":\"invalid_api_key\"}}";
+    let base_url = common::serve_once(
+        b"HTTP/1.1 401 Unauthorized\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n",
+        Duration::ZERO,
+        BODY,
+    );
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = common::provider(py, &base_url)
+            .call_method1("stream_text", ("Hello",))
+            .expect("stream should start");
+
+        let err = stream
+            .call_method0("__next__")
+            .expect_err("failed stream should raise");
+        let value = err.value(py);
+        let status_code: u16 = value.getattr("status_code").unwrap().extract().unwrap();
+        let code: Option<String> = value.getattr("code").unwrap().extract().unwrap();
+        let error_type: Option<String> = value.getattr("error_type").unwrap().extract().unwrap();
+        let body: String = value.getattr("body").unwrap().extract().unwrap();
+        assert_eq!(status_code, 401);
+        assert_eq!(code.as_deref(), Some("invalid_api_key"));
+        assert_eq!(error_type.as_deref(), Some("auth"));
+        assert_eq!(body.as_bytes(), BODY);
+    });
+}
+
+#[test]
+fn mid_stream_failure_keeps_partial_text() {
+    let base_url = common::serve_once(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n",
+        Duration::ZERO,
+        b"data: {\"choices\":[{\"delta\":{\"content\":\"hi\"}}]}\n\ndata: {not json\n\n",
+    );
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = common::provider(py, &base_url)
+            .call_method1("stream_text", ("Hello",))
+            .expect("stream should start");
+
+        let chunk: String = stream
+            .call_method0("__next__")
+            .and_then(|chunk| chunk.extract())
+            .expect("first chunk should arrive");
+        assert_eq!(chunk, "hi");
+
+        let err = stream
+            .call_method0("__next__")
+            .expect_err("malformed chunk should fail the stream");
+        assert!(err.to_string().contains("2 characters received"));
+
+        let partial: String = stream.getattr("partial_text").unwrap().extract().unwrap();
+        assert_eq!(partial, "hi");
+    });
+}
+
+#[test]
+fn stalled_stream_fails_after_the_idle_timeout() {
+    let base_url = common::serve_once(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n",
+        Duration::from_secs(2),
+        b"data: [DONE]\n\n",
+    );
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("stream_idle_timeout", 0.3)
+        });
+        let stream = provider
+            .call_method1("stream_text", ("Hello",))
+            .expect("stream should start");
+
+        let started = Instant::now();
+        let err = stream
+            .call_method0("__next__")
+            .expect_err("stalled stream should time out");
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(err.is_instance_of::<PyRuntimeError>(py));
+        assert!(err.to_string().contains("300ms of inactivity"));
+        assert!(err.to_string().contains("stream_idle_timeout"));
+    });
+}
+
+#[test]
+fn provider_rejects_a_non_positive_idle_timeout() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("api_key", "test-key").unwrap();
+        kwargs.set_item("stream_idle_timeout", 0).unwrap();
+        let err = py
+            .get_type::<Provider>()
+            .call(("test-model",), Some(&kwargs))
+            .expect_err("zero idle timeout should fail");
+        assert!(err.to_string().contains("stream_idle_timeout"));
+    });
+}
+
+#[test]
+fn cancelling_during_a_retry_wait_sends_no_more_requests() {
+    let (base_url, heads) = common::serve_sequence(vec![
+        b"HTTP/1.1 503 Service Unavailable\r\nRetry-After: 1\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n\
+data: [DONE]\n\n",
+    ]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = common::provider(py, &base_url)
+            .call_method1("stream_text", ("Hello",))
+            .unwrap();
+        py.detach(|| std::thread::sleep(Duration::from_millis(300)));
+        stream.call_method0("cancel").unwrap();
+        py.detach(|| std::thread::sleep(Duration::from_millis(1500)));
+    });
+
+    assert_eq!(heads.try_iter().count(), 1, "the retry is not sent");
+}
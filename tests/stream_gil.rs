@@ -0,0 +1,41 @@
+mod common;
+
+use pyo3::prelude::*;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+#[test]
+fn stream_next_releases_gil_while_waiting() {
+    let base_url = common::serve_once(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n",
+        Duration::from_millis(500),
+        b"data: {\"choices\":[{\"delta\":{\"content\":\"hi\"}}]}\n\ndata: [DONE]\n\n",
+    );
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = common::provider(py, &base_url)
+            .call_method1("stream_text", ("Hello",))
+            .expect("stream should start");
+
+        // Another thread that needs the GIL while the stream is stalled.
+        let (tx, rx) = mpsc::channel();
+        let other = std::thread::spawn(move || {
+            Python::attach(|_| tx.send(Instant::now()).unwrap());
+        });
+
+        let chunk: String = stream
+            .call_method0("__next__")
+            .and_then(|chunk| chunk.extract())
+            .expect("first chunk should arrive");
+        let returned_at = Instant::now();
+        assert_eq!(chunk, "hi");
+
+        py.detach(|| other.join().unwrap());
+        let other_ran_at = rx.recv().unwrap();
+        assert!(
+            other_ran_at < returned_at,
+            "another thread should acquire the GIL while __next__ waits"
+        );
+    });
+}
@@ -0,0 +1,126 @@
+mod common;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::time::Duration;
+
+const HEAD: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n";
+
+const BODY: &[u8] = b"data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n\
+data: {\"id\":\"gen-1\",\"created\":1700000000,\"choices\":[{\"delta\":{\"content\":\"Hello\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\" there\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"stop\"}]}\n\n\
+data: {\"choices\":[],\"usage\":{\"prompt_tokens\":3,\"completion_tokens\":2,\"total_tokens\":5}}\n\n\
+data: [DONE]\n\n";
+
+fn stream<'py>(py: Python<'py>, include_usage: bool) -> Bound<'py, PyAny> {
+    let base_url = common::serve_once(HEAD, Duration::from_millis(300), BODY);
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("include_usage", include_usage).unwrap();
+    common::provider(py, &base_url)
+        .call_method("stream_text", ("Hello",), Some(&kwargs))
+        .expect("stream should start")
+}
+
+fn getter<T: for<'a, 'py> FromPyObject<'a, 'py>>(stream: &Bound<'_, PyAny>, name: &str) -> T {
+    stream
+        .getattr(name)
+        .unwrap()
+        .extract()
+        .unwrap_or_else(|_| panic!("{} should extract", name))
+}
+
+#[test]
+fn timings_are_none_until_the_stream_finishes() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = stream(py, false);
+
+        for name in [
+            "time_to_first_chunk_ms",
+            "total_duration_ms",
+            "chunk_count",
+            "completion_tokens_per_second",
+        ] {
+            assert!(stream.getattr(name).unwrap().is_none(), "{}", name);
+        }
+        stream.call_method0("text").unwrap();
+
+        let first: f64 = getter(&stream, "time_to_first_chunk_ms");
+        let total: f64 = getter(&stream, "total_duration_ms");
+        assert!(first >= 300.0, "first chunk after {}ms", first);
+        assert!(total >= first);
+        assert_eq!(getter::<u64>(&stream, "chunk_count"), 2);
+        assert_eq!(getter::<String>(&stream, "finish_reason"), "stop");
+        assert_eq!(getter::<String>(&stream, "response_id"), "gen-1");
+        assert_eq!(getter::<u64>(&stream, "created"), 1700000000);
+    });
+}
+
+#[test]
+fn tokens_per_second_uses_reported_usage() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = stream(py, true);
+        stream.call_method0("text").unwrap();
+
+        let rate: f64 = getter(&stream, "completion_tokens_per_second");
+        assert!(rate > 0.0);
+        assert_eq!(getter::<u64>(&stream, "completion_tokens"), 2);
+        assert_eq!(getter::<String>(&stream, "finish_reason"), "stop");
+    });
+}
+
+#[test]
+fn token_details_come_from_the_usage_chunk() {
+    const DETAILED: &[u8] = b"data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"},\"finish_reason\":\"stop\"}]}\n\n\
+data: {\"choices\":[],\"usage\":{\"prompt_tokens\":1200,\"completion_tokens\":90,\
+\"prompt_tokens_details\":{\"cached_tokens\":1024},\"completion_tokens_details\":{\"reasoning_tokens\":64}}}\n\n\
+data: [DONE]\n\n";
+    let base_url = common::serve_once(HEAD, Duration::ZERO, DETAILED);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("include_usage", true).unwrap();
+        let stream = common::provider(py, &base_url)
+            .call_method("stream_text", ("Hello",), Some(&kwargs))
+            .unwrap();
+        stream.call_method0("text").unwrap();
+
+        assert_eq!(getter::<u64>(&stream, "cached_prompt_tokens"), 1024);
+        assert_eq!(getter::<u64>(&stream, "reasoning_tokens"), 64);
+    });
+}
+
+#[test]
+fn annotations_are_collected_from_the_deltas() {
+    const CITED: &[u8] = b"data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"annotations\":[{\"type\":\"url_citation\",\
+\"url_citation\":{\"url\":\"https://example.com\",\"title\":\"Example\"}}]},\"finish_reason\":\"stop\"}]}\n\n\
+data: [DONE]\n\n";
+    let base_url = common::serve_once(HEAD, Duration::ZERO, CITED);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = common::provider(py, &base_url)
+            .call_method1("stream_text", ("Hello",))
+            .unwrap();
+        assert_eq!(stream.getattr("annotations").unwrap().len().unwrap(), 0);
+        stream.call_method0("text").unwrap();
+
+        let annotations = stream.getattr("annotations").unwrap();
+        assert_eq!(annotations.len().unwrap(), 1);
+        let title: String = annotations
+            .get_item(0)
+            .unwrap()
+            .get_item("title")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(title, "Example");
+    });
+}
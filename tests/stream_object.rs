@@ -0,0 +1,81 @@
+mod common;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::time::Duration;
+
+const HEAD: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n";
+
+fn collect_objects(py: Python<'_>, base_url: &str) -> (Vec<String>, Option<PyErr>) {
+    let stream = common::provider(py, base_url)
+        .call_method1("stream_object", ("Describe Ada as JSON.",))
+        .expect("stream should start");
+    let json = py.import("json").unwrap();
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("sort_keys", true).unwrap();
+
+    let mut objects = Vec::new();
+    for item in stream.try_iter().unwrap() {
+        match item {
+            Ok(item) => objects.push(
+                json.call_method("dumps", (item,), Some(&kwargs))
+                    .unwrap()
+                    .extract()
+                    .unwrap(),
+            ),
+            Err(err) => return (objects, Some(err)),
+        }
+    }
+    (objects, None)
+}
+
+#[test]
+fn stream_object_yields_changed_snapshots() {
+    let base_url = common::serve_once(
+        HEAD,
+        Duration::ZERO,
+        b"data: {\"choices\":[{\"delta\":{\"content\":\"{\\\"name\\\": \\\"A\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"da\\\", \\\"age\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"\\\"\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\": 36}\"}}]}\n\n\
+data: [DONE]\n\n",
+    );
+    Python::initialize();
+
+    Python::attach(|py| {
+        let (objects, err) = collect_objects(py, &base_url);
+
+        assert!(err.is_none());
+        // The half-written "age" key changes nothing, so no snapshot repeats.
+        assert_eq!(
+            objects,
+            vec![
+                r#"{"name": "A"}"#,
+                r#"{"name": "Ada"}"#,
+                r#"{"age": 36, "name": "Ada"}"#,
+            ]
+        );
+    });
+}
+
+#[test]
+fn stream_object_raises_with_raw_text_on_invalid_json() {
+    let base_url = common::serve_once(
+        HEAD,
+        Duration::ZERO,
+        b"data: {\"choices\":[{\"delta\":{\"content\":\"{\\\"a\\\": 1, oops\"}}]}\n\n\
+data: [DONE]\n\n",
+    );
+    Python::initialize();
+
+    Python::attach(|py| {
+        let (objects, err) = collect_objects(py, &base_url);
+
+        assert_eq!(objects, vec![r#"{"a": 1}"#]);
+        let err = err.expect("invalid JSON should raise");
+        assert!(err.is_instance_of::<PyValueError>(py));
+        assert!(err.to_string().contains(r#"Raw output: {"a": 1, oops"#));
+    });
+}
@@ -1,4 +1,8 @@
-use rusty_agent_sdk::internal::{StreamEvent, parse_sse_event, parse_sse_line};
+use rusty_agent_sdk::internal::{
+    Annotation, SseLineBuffer, StreamEvent, StreamMetadata, StreamTimings, is_sse_comment,
+    parse_sse_chunks, parse_sse_event, parse_sse_line, sse_event_id,
+};
+use std::time::Duration;
 
 #[test]
 fn parse_sse_line_extracts_content_chunk() {
@@ -38,6 +42,22 @@ fn parse_sse_line_returns_error_for_malformed_data_payload() {
     let message = format!("{:?}", err);
 
     assert!(message.contains("Failed to parse streaming response chunk"));
+    assert!(message.contains("Payload: {not-json}"));
+}
+
+#[test]
+fn parse_sse_line_truncates_long_malformed_payloads() {
+    let line = format!("data: {{{}", "é".repeat(3000));
+
+    let err = parse_sse_line(&line).expect_err("malformed payload should fail");
+    let message = format!("{:?}", err);
+
+    assert!(message.contains("Payload: {é"));
+    assert!(message.contains("... (6001 bytes total)"));
+    // The error also keeps a longer copy of the body; only the quote in the
+    // message is cut to 2 KB.
+    let quoted = &message[..message.find("bytes total)").unwrap()];
+    assert!(quoted.len() < 2200);
 }
 
 #[test]
@@ -53,3 +73,434 @@ fn parse_sse_event_ignores_events_without_data_lines() {
     let parsed = parse_sse_event(event).expect("event without data should be ignored");
     assert_eq!(parsed, vec![StreamEvent::Ignore]);
 }
+
+#[test]
+fn parse_sse_line_extracts_reasoning_delta() {
+    let line = r#"data: {"choices":[{"delta":{"reasoning_content":"Hmm","content":""}}]}"#;
+
+    let events = parse_sse_line(line).expect("line should parse");
+
+    assert_eq!(events, vec![StreamEvent::Reasoning("Hmm".to_string())]);
+}
+
+#[test]
+fn parse_sse_line_emits_reasoning_before_content() {
+    let line = r#"data: {"choices":[{"delta":{"reasoning":"Think","content":"Answer"}}]}"#;
+
+    let events = parse_sse_line(line).expect("line should parse");
+
+    assert_eq!(
+        events,
+        vec![
+            StreamEvent::Reasoning("Think".to_string()),
+            StreamEvent::Content("Answer".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn parse_sse_line_captures_service_tier_in_metadata() {
+    let line = r#"data: {"choices":[{"delta":{},"finish_reason":"stop"}],"model":"gpt-4o","service_tier":"default"}"#;
+
+    let events = parse_sse_line(line).expect("line should parse");
+
+    assert_eq!(
+        events,
+        vec![StreamEvent::Metadata(Box::new(StreamMetadata {
+            id: None,
+            created: None,
+            usage: None,
+            finish_reason: Some("stop".to_string()),
+            model: Some("gpt-4o".to_string()),
+            service_tier: Some("default".to_string()),
+            system_fingerprint: None,
+            refusal: None,
+            annotations: Vec::new(),
+            request_id: None,
+            idempotency_key: None,
+            timings: None,
+            attempted_models: Vec::new(),
+            rate_limit: None,
+        }))]
+    );
+}
+
+#[test]
+fn parse_sse_line_captures_system_fingerprint_in_metadata() {
+    let line = r#"data: {"choices":[{"delta":{},"finish_reason":"stop"}],"system_fingerprint":"fp_44709d6fcb"}"#;
+
+    let events = parse_sse_line(line).expect("line should parse");
+
+    assert_eq!(
+        events,
+        vec![StreamEvent::Metadata(Box::new(StreamMetadata {
+            id: None,
+            created: None,
+            usage: None,
+            finish_reason: Some("stop".to_string()),
+            model: None,
+            service_tier: None,
+            system_fingerprint: Some("fp_44709d6fcb".to_string()),
+            refusal: None,
+            annotations: Vec::new(),
+            request_id: None,
+            idempotency_key: None,
+            timings: None,
+            attempted_models: Vec::new(),
+            rate_limit: None,
+        }))]
+    );
+}
+
+#[test]
+fn parse_sse_chunks_carries_text_index_and_raw_payload() {
+    let event = r#"data: {"choices":[{"index":0,"delta":{"content":"Hi"},"finish_reason":null}]}"#;
+
+    let chunks = parse_sse_chunks(event).expect("chunk should parse");
+
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].text, "Hi");
+    assert_eq!(chunks[0].index, 0);
+    assert_eq!(chunks[0].finish_reason, None);
+    assert_eq!(chunks[0].raw["choices"][0]["delta"]["content"], "Hi");
+}
+
+#[test]
+fn parse_sse_chunks_keeps_payloads_without_text() {
+    let event = r#"data: {"choices":[{"index":1,"delta":{},"finish_reason":"stop"}]}"#;
+    let chunks = parse_sse_chunks(event).expect("chunk should parse");
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].text, "");
+    assert_eq!(chunks[0].index, 1);
+    assert_eq!(chunks[0].finish_reason.as_deref(), Some("stop"));
+
+    let event = r#"data: {"choices":[],"usage":{"prompt_tokens":1,"completion_tokens":2,"total_tokens":3}}"#;
+    let chunks = parse_sse_chunks(event).expect("usage-only payload should parse");
+    assert_eq!(chunks.len(), 1);
+    assert_eq!(chunks[0].index, 0);
+    assert_eq!(chunks[0].raw["usage"]["total_tokens"], 3);
+}
+
+#[test]
+fn parse_sse_chunks_splits_multiple_choices_by_index() {
+    let event = r#"data: {"choices":[{"index":0,"delta":{"content":"A"}},{"index":1,"delta":{"content":"B"}}]}"#;
+
+    let chunks = parse_sse_chunks(event).expect("chunk should parse");
+
+    let parts: Vec<_> = chunks.iter().map(|c| (c.index, c.text.as_str())).collect();
+    assert_eq!(parts, vec![(0, "A"), (1, "B")]);
+}
+
+#[test]
+fn parse_sse_chunks_skips_done_and_non_data_events() {
+    assert!(
+        parse_sse_chunks("data: [DONE]")
+            .expect("should parse")
+            .is_empty()
+    );
+    assert!(
+        parse_sse_chunks("event: ping")
+            .expect("should parse")
+            .is_empty()
+    );
+    assert!(parse_sse_chunks("data: {not-json}").is_err());
+}
+
+#[test]
+fn parse_sse_line_reports_error_object_with_code() {
+    let line = r#"data: {"error":{"message":"Upstream overloaded","code":502}}"#;
+
+    let err = parse_sse_line(line).expect_err("error payload should fail");
+    let message = format!("{:?}", err);
+
+    assert!(message.contains("Upstream overloaded"));
+    assert!(message.contains("code 502"));
+    assert!(!message.contains("Failed to parse streaming response chunk"));
+}
+
+#[test]
+fn parse_sse_line_reports_error_object_without_code() {
+    let line = r#"data: {"error":{"message":"Rate limited","code":null}}"#;
+
+    let err = parse_sse_line(line).expect_err("error payload should fail");
+    let message = format!("{:?}", err);
+
+    assert!(message.contains("Stream error from provider: Rate limited"));
+}
+
+#[test]
+fn parse_sse_line_ignores_null_error_field() {
+    let line = r#"data: {"error":null,"choices":[{"delta":{"content":"Hi"}}]}"#;
+
+    let events = parse_sse_line(line).expect("a null error is not a failure");
+
+    assert_eq!(events, vec![StreamEvent::Content("Hi".to_string())]);
+}
+
+#[test]
+fn parse_sse_event_reports_error_events() {
+    let event =
+        "event: error\ndata: {\"error\":{\"message\":\"Model crashed\",\"code\":\"server_error\"}}";
+    let err = parse_sse_event(event).expect_err("error event should fail");
+    let message = format!("{:?}", err);
+    assert!(message.contains("Model crashed"));
+    assert!(message.contains("code server_error"));
+
+    let event = "event: error\ndata: connection dropped upstream";
+    let err = parse_sse_event(event).expect_err("plain-text error event should fail");
+    assert!(format!("{:?}", err).contains("connection dropped upstream"));
+}
+
+#[test]
+fn is_sse_comment_matches_colon_prefixed_lines() {
+    assert!(is_sse_comment(": keep-alive"));
+    assert!(is_sse_comment(":"));
+    assert!(!is_sse_comment("data: :not a comment"));
+    assert!(!is_sse_comment(""));
+}
+
+#[test]
+fn parse_sse_event_skips_comments_between_data_lines() {
+    let event =
+        "data: {\"choices\":[{\"delta\":\n: keep-alive\ndata: {\"content\":\"Hi\"}}]}\n: ping";
+    let parsed = parse_sse_event(event).expect("comments should be skipped");
+    assert_eq!(parsed, vec![StreamEvent::Content("Hi".to_string())]);
+}
+
+#[test]
+fn parse_sse_event_ignores_comment_only_events() {
+    let parsed = parse_sse_event(": keep-alive\n: ping").expect("comments should be ignored");
+    assert_eq!(parsed, vec![StreamEvent::Ignore]);
+
+    // A comment mentioning `event: error` must not be read as an error event.
+    let event = ": event: error\ndata: {\"choices\":[{\"delta\":{\"content\":\"ok\"}}]}";
+    let parsed = parse_sse_event(event).expect("comment should not mark an error event");
+    assert_eq!(parsed, vec![StreamEvent::Content("ok".to_string())]);
+}
+
+#[test]
+fn sse_event_id_reads_last_id_field() {
+    assert_eq!(sse_event_id("id: 7\ndata: {}"), Some("7"));
+    assert_eq!(sse_event_id("id:a\nid: b\ndata: {}"), Some("b"));
+    assert_eq!(sse_event_id("id:\ndata: {}"), Some(""));
+    assert_eq!(sse_event_id("data: {}"), None);
+}
+
+#[test]
+fn sse_event_id_ignores_comments_and_nul_ids() {
+    assert_eq!(sse_event_id(": id: 3\ndata: {}"), None);
+    assert_eq!(sse_event_id("id: 1\nid: bad\0id\ndata: {}"), Some("1"));
+}
+
+#[test]
+fn sse_line_buffer_keeps_multibyte_characters_split_across_reads() {
+    let input = "data: h\u{e9}llo \u{4e16}\u{754c} \u{1f600}\r\n\ndata: [DONE]\n".as_bytes();
+
+    for split in 0..=input.len() {
+        let mut buffer = SseLineBuffer::default();
+        let mut lines = buffer.push(&input[..split]);
+        lines.extend(buffer.push(&input[split..]));
+
+        assert_eq!(
+            lines,
+            vec![
+                "data: h\u{e9}llo \u{4e16}\u{754c} \u{1f600}",
+                "",
+                "data: [DONE]"
+            ],
+            "split at byte {}",
+            split
+        );
+        assert_eq!(buffer.finish(), None);
+    }
+}
+
+#[test]
+fn sse_line_buffer_handles_one_byte_reads_and_trailing_line() {
+    let input = "data: \u{1f600}\ndata: \u{4e16}".as_bytes();
+    let mut buffer = SseLineBuffer::default();
+
+    let lines: Vec<String> = input.iter().flat_map(|b| buffer.push(&[*b])).collect();
+
+    assert_eq!(lines, vec!["data: \u{1f600}"]);
+    assert_eq!(buffer.finish().as_deref(), Some("data: \u{4e16}"));
+}
+
+#[test]
+fn sse_line_buffer_next_line_reads_lines_completed_by_later_reads() {
+    let mut buffer = SseLineBuffer::default();
+
+    buffer.extend(b"data: one\r\ndata: t");
+    assert_eq!(buffer.next_line().as_deref(), Some("data: one"));
+    assert_eq!(buffer.next_line(), None);
+
+    buffer.extend(b"wo\n\n\xff\n");
+    assert_eq!(buffer.next_line().as_deref(), Some("data: two"));
+    assert_eq!(buffer.next_line().as_deref(), Some(""));
+    assert_eq!(buffer.next_line().as_deref(), Some("\u{fffd}"));
+    assert_eq!(buffer.next_line(), None);
+    assert_eq!(buffer.finish(), None);
+}
+
+#[test]
+fn sse_line_buffer_clear_drops_partial_line() {
+    let mut buffer = SseLineBuffer::default();
+
+    buffer.extend(b"data: stale\ndata: half");
+    assert_eq!(buffer.next_line().as_deref(), Some("data: stale"));
+    buffer.clear();
+    buffer.extend(b"data: fresh\n");
+
+    assert_eq!(buffer.next_line().as_deref(), Some("data: fresh"));
+    assert_eq!(buffer.finish(), None);
+}
+
+#[test]
+fn stream_timings_rate_excludes_time_to_first_chunk() {
+    let timings = StreamTimings {
+        time_to_first_chunk: Some(Duration::from_millis(500)),
+        total_duration: Duration::from_millis(2500),
+        chunk_count: 40,
+    };
+
+    assert_eq!(timings.tokens_per_second(100), Some(50.0));
+    assert_eq!(
+        StreamTimings {
+            time_to_first_chunk: None,
+            ..timings
+        }
+        .tokens_per_second(100),
+        None
+    );
+    assert_eq!(
+        StreamTimings {
+            total_duration: Duration::from_millis(500),
+            ..timings
+        }
+        .tokens_per_second(100),
+        None
+    );
+}
+
+#[test]
+fn finish_reason_only_chunk_is_kept_after_a_usage_only_chunk() {
+    let finish = r#"data: {"choices":[{"delta":{},"finish_reason":"length"}],"model":"gpt-4o"}"#;
+    let usage = r#"data: {"choices":[],"usage":{"prompt_tokens":3,"completion_tokens":2,"total_tokens":5}}"#;
+
+    let mut stored = StreamMetadata::default();
+    for line in [finish, usage] {
+        for event in parse_sse_line(line).expect("line should parse") {
+            let StreamEvent::Metadata(meta) = event else {
+                panic!("expected a metadata event");
+            };
+            stored.merge(*meta);
+        }
+    }
+
+    assert_eq!(stored.finish_reason.as_deref(), Some("length"));
+    assert_eq!(stored.model.as_deref(), Some("gpt-4o"));
+    assert_eq!(
+        stored.usage.and_then(|usage| usage.completion_tokens),
+        Some(2)
+    );
+}
+
+#[test]
+fn a_malformed_usage_chunk_keeps_the_stream_going() {
+    let line =
+        r#"data: {"choices":[{"delta":{"content":"Hi"}}],"usage":{"prompt_tokens":"three"}}"#;
+
+    let events = parse_sse_line(line).expect("bad usage should not fail the chunk");
+
+    assert!(
+        events
+            .iter()
+            .any(|event| matches!(event, StreamEvent::Content(text) if text == "Hi")),
+        "{:?}",
+        events
+    );
+    assert!(
+        events
+            .iter()
+            .all(|event| !matches!(event, StreamEvent::Metadata(meta) if meta.usage.is_some()))
+    );
+}
+
+#[test]
+fn stream_metadata_keeps_the_first_id_and_created() {
+    let first =
+        r#"data: {"id":"gen-1","created":1700000000,"choices":[{"delta":{"content":"Hi"}}]}"#;
+    let last = r#"data: {"id":"gen-2","created":1700000001,"choices":[{"delta":{},"finish_reason":"stop"}]}"#;
+
+    let mut stored = StreamMetadata::default();
+    for line in [first, last] {
+        for event in parse_sse_line(line).expect("line should parse") {
+            if let StreamEvent::Metadata(meta) = event {
+                stored.merge(*meta);
+            }
+        }
+    }
+
+    assert_eq!(stored.id.as_deref(), Some("gen-1"));
+    assert_eq!(stored.created, Some(1700000000));
+    assert_eq!(stored.finish_reason.as_deref(), Some("stop"));
+}
+
+#[test]
+fn stream_metadata_joins_refusal_pieces() {
+    let lines = [
+        r#"data: {"choices":[{"delta":{"refusal":"I can't "}}]}"#,
+        r#"data: {"choices":[{"delta":{"refusal":"help with that."}}]}"#,
+        r#"data: {"choices":[{"delta":{},"finish_reason":"stop"}]}"#,
+    ];
+
+    let mut stored = StreamMetadata::default();
+    for line in lines {
+        for event in parse_sse_line(line).expect("line should parse") {
+            if let StreamEvent::Metadata(meta) = event {
+                stored.merge(*meta);
+            }
+        }
+    }
+
+    assert_eq!(stored.refusal.as_deref(), Some("I can't help with that."));
+    assert_eq!(stored.finish_reason.as_deref(), Some("stop"));
+}
+
+#[test]
+fn stream_metadata_collects_annotations() {
+    let lines = [
+        r#"data: {"choices":[{"delta":{"content":"See"}}]}"#,
+        r#"data: {"choices":[{"delta":{"annotations":[{"type":"url_citation","url_citation":{"url":"https://a.example","title":"A","start_index":0,"end_index":3}}]}}]}"#,
+        r#"data: {"choices":[{"delta":{"annotations":[{"type":"url_citation","url_citation":{"url":"https://b.example"}}]},"finish_reason":"stop"}]}"#,
+    ];
+
+    let mut stored = StreamMetadata::default();
+    for line in lines {
+        for event in parse_sse_line(line).expect("line should parse") {
+            if let StreamEvent::Metadata(meta) = event {
+                stored.merge(*meta);
+            }
+        }
+    }
+
+    assert_eq!(
+        stored.annotations,
+        vec![
+            Annotation {
+                annotation_type: "url_citation".to_string(),
+                url: Some("https://a.example".to_string()),
+                title: Some("A".to_string()),
+                start_index: Some(0),
+                end_index: Some(3),
+            },
+            Annotation {
+                annotation_type: "url_citation".to_string(),
+                url: Some("https://b.example".to_string()),
+                title: None,
+                start_index: None,
+                end_index: None,
+            },
+        ]
+    );
+}
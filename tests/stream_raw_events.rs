@@ -0,0 +1,91 @@
+mod common;
+
+use pyo3::exceptions::PyTypeError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use std::time::Duration;
+
+const HEAD: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n";
+
+const BODY: &[u8] = b": keep-alive\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"Hello\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\" there\"}}]}\n\n\
+data: [DONE]\n\n";
+
+fn stream<'py>(py: Python<'py>, callback: &Bound<'py, PyAny>) -> Bound<'py, PyAny> {
+    let base_url = common::serve_once(HEAD, Duration::ZERO, BODY);
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("raw_event_callback", callback).unwrap();
+    common::provider(py, &base_url)
+        .call_method("stream_text", ("Hello",), Some(&kwargs))
+        .expect("stream should start")
+}
+
+#[test]
+fn raw_event_callback_sees_each_event_but_not_comments() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let events = PyList::empty(py);
+        let stream = stream(py, &events.getattr("append").unwrap());
+        let text: String = stream.call_method0("text").unwrap().extract().unwrap();
+
+        assert_eq!(text, "Hello there");
+        let events: Vec<String> = events.extract().unwrap();
+        assert_eq!(
+            events,
+            vec![
+                "data: {\"choices\":[{\"delta\":{\"content\":\"Hello\"}}]}",
+                "data: {\"choices\":[{\"delta\":{\"content\":\" there\"}}]}",
+                "data: [DONE]",
+            ]
+        );
+        let errors: usize = stream
+            .getattr("raw_event_callback_errors")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(errors, 0);
+    });
+}
+
+#[test]
+fn raw_event_callback_errors_are_counted_without_breaking_the_stream() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        // int() raises ValueError for every event.
+        let failing = py.get_type::<pyo3::types::PyInt>().into_any();
+        let stream = stream(py, &failing);
+        let text: String = stream.call_method0("text").unwrap().extract().unwrap();
+
+        assert_eq!(text, "Hello there");
+        let errors: usize = stream
+            .getattr("raw_event_callback_errors")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(errors, 3);
+    });
+}
+
+#[test]
+fn raw_event_callback_must_be_callable() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider(py, "http://127.0.0.1:9/v1");
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("raw_event_callback", 42).unwrap();
+
+        let err = provider
+            .call_method("stream_text", ("Hello",), Some(&kwargs))
+            .expect_err("non-callable should fail");
+        assert!(err.is_instance_of::<PyTypeError>(py));
+        assert!(
+            err.to_string()
+                .contains("'raw_event_callback' must be callable")
+        );
+    });
+}
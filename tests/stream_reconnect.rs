@@ -0,0 +1,184 @@
+mod common;
+
+use pyo3::prelude::*;
+use pyo3::types::{IntoPyDict, PyDict, PyList};
+
+/// A chunked stream that sends one event with `id: 1`, then drops the
+/// connection before the terminating chunk.
+const INTERRUPTED: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nTransfer-Encoding: chunked\r\n\r\n\
+3b\r\nid: 1\ndata: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n\r\n";
+
+/// The same cut-off stream from a server that sends no `id:` fields.
+const INTERRUPTED_WITHOUT_ID: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nTransfer-Encoding: chunked\r\n\r\n\
+31\r\ndata: {\"choices\":[{\"delta\":{\"content\":\"Hel\"}}]}\n\n\r\n";
+
+const UNAUTHORIZED: &[u8] =
+    b"HTTP/1.1 401 Unauthorized\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"error\":{\"message\":\"Invalid API key\"}}";
+
+const TOO_MANY_REQUESTS: &[u8] =
+    b"HTTP/1.1 429 Too Many Requests\r\nRetry-After: 1\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"error\":{\"message\":\"slow down\"}}";
+
+const RESUMED: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n\
+id: 2\ndata: {\"choices\":[{\"delta\":{\"content\":\"lo\"}}]}\n\ndata: [DONE]\n\n";
+
+fn stream_text<'py>(py: Python<'py>, base_url: &str, reconnect: Option<u32>) -> Bound<'py, PyAny> {
+    let kwargs = PyDict::new(py);
+    if let Some(reconnect) = reconnect {
+        kwargs.set_item("reconnect", reconnect).unwrap();
+    }
+    common::provider(py, base_url)
+        .call_method("stream_text", ("Hello",), Some(&kwargs))
+        .expect("stream should start")
+}
+
+#[test]
+fn stream_reconnects_with_last_event_id() {
+    let (base_url, heads) = common::serve_sequence(vec![INTERRUPTED, RESUMED]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = stream_text(py, &base_url, Some(1));
+        let text: String = stream
+            .call_method0("text")
+            .and_then(|text| text.extract())
+            .expect("stream should resume after reconnecting");
+        assert_eq!(text, "Hello");
+
+        let last_id: String = stream.getattr("last_event_id").unwrap().extract().unwrap();
+        assert_eq!(last_id, "2");
+    });
+
+    let first = heads.recv().unwrap().to_ascii_lowercase();
+    let second = heads.recv().unwrap().to_ascii_lowercase();
+    assert!(!first.contains("last-event-id"));
+    assert!(second.contains("last-event-id: 1"));
+}
+
+#[test]
+fn stream_without_reconnect_fails_on_interruption() {
+    let (base_url, _heads) = common::serve_sequence(vec![INTERRUPTED, RESUMED]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = stream_text(py, &base_url, None);
+        let err = stream
+            .call_method0("text")
+            .expect_err("interrupted stream should fail");
+        assert!(!err.to_string().contains("reconnect"));
+        assert!(err.to_string().contains("3 characters received"));
+    });
+}
+
+#[test]
+fn stream_without_event_ids_is_not_reopened() {
+    let (base_url, heads) = common::serve_sequence(vec![INTERRUPTED_WITHOUT_ID, RESUMED]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = stream_text(py, &base_url, Some(2));
+        let err = stream
+            .call_method0("text")
+            .expect_err("a new completion must not be appended to the old one");
+        assert!(!err.to_string().contains("reconnect"), "{}", err);
+        assert!(err.to_string().contains("3 characters received"), "{}", err);
+        let partial: String = stream.getattr("partial_text").unwrap().extract().unwrap();
+        assert_eq!(partial, "Hel");
+    });
+
+    assert!(heads.recv().is_ok());
+    assert!(heads.try_recv().is_err(), "the request is sent once");
+}
+
+#[test]
+fn a_refused_reconnect_ends_the_stream() {
+    let (base_url, heads) = common::serve_sequence(vec![INTERRUPTED, UNAUTHORIZED, RESUMED]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = stream_text(py, &base_url, Some(3));
+        let err = stream
+            .call_method0("text")
+            .expect_err("a 401 on reopen is not retried");
+        let message = err.to_string();
+        assert!(message.contains("Invalid API key"), "{}", message);
+        assert!(message.contains("reconnect attempt 1"), "{}", message);
+        let status: u16 = err
+            .value(py)
+            .getattr("status_code")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(status, 401);
+    });
+
+    assert_eq!(heads.try_iter().count(), 2, "no request after the 401");
+}
+
+#[test]
+fn failed_reconnects_are_reported_to_on_retry() {
+    let (base_url, heads) = common::serve_sequence(vec![INTERRUPTED, TOO_MANY_REQUESTS, RESUMED]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let calls = PyList::empty(py);
+        let globals = PyDict::new(py);
+        globals.set_item("calls", &calls).unwrap();
+        let on_retry = py
+            .eval(c"lambda *args: calls.append(args)", Some(&globals), None)
+            .unwrap();
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("on_retry", on_retry)
+        });
+
+        let text: String = provider
+            .call_method(
+                "stream_text",
+                ("Hello",),
+                Some(&[("reconnect", 2)].into_py_dict(py).unwrap()),
+            )
+            .and_then(|stream| stream.call_method0("text"))
+            .and_then(|text| text.extract())
+            .expect("the second reconnect resumes the stream");
+        assert_eq!(text, "Hello");
+
+        let (attempt, cause, _): (u32, String, f64) = calls.get_item(0).unwrap().extract().unwrap();
+        assert_eq!(attempt, 1);
+        assert!(cause.contains("error decoding response body"), "{}", cause);
+
+        let (attempt, status, delay): (u32, u16, f64) =
+            calls.get_item(1).unwrap().extract().unwrap();
+        assert_eq!((attempt, status), (2, 429));
+        assert!(delay >= 1.0, "Retry-After is honored: {}", delay);
+        assert_eq!(calls.len(), 2);
+
+        let retries: u64 = provider.getattr("retry_count").unwrap().extract().unwrap();
+        assert_eq!(retries, 2);
+    });
+
+    assert_eq!(heads.try_iter().count(), 3);
+}
+
+#[test]
+fn exhausted_reconnects_name_the_last_failure() {
+    let (base_url, _heads) = common::serve_sequence(vec![INTERRUPTED, TOO_MANY_REQUESTS]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = stream_text(py, &base_url, Some(1));
+        let err = stream
+            .call_method0("text")
+            .expect_err("the only reconnect is rate limited");
+        let message = err.to_string();
+        assert!(
+            message
+                .contains("gave up after 1 reconnect attempt(s), the last failing with: API error (429 Too Many Requests): slow down"),
+            "{}",
+            message
+        );
+    });
+}
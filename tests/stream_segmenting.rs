@@ -0,0 +1,213 @@
+use rusty_agent_sdk::internal::{Segmenter, SplitMode, StopPattern, StopScanner};
+
+/// Feed `chunks` through a segmenter and return every segment, including
+/// the tail flushed at the end.
+fn segment(mode: SplitMode, chunks: &[&str]) -> Vec<String> {
+    let mut segmenter = Segmenter::new(mode);
+    let mut segments: Vec<String> = chunks
+        .iter()
+        .flat_map(|chunk| segmenter.push(chunk))
+        .collect();
+    segments.extend(segmenter.finish());
+    segments
+}
+
+#[test]
+fn split_mode_parses_known_values() {
+    assert_eq!(SplitMode::parse("none").unwrap(), SplitMode::None);
+    assert_eq!(SplitMode::parse("line").unwrap(), SplitMode::Line);
+    assert_eq!(SplitMode::parse("sentence").unwrap(), SplitMode::Sentence);
+
+    let err = SplitMode::parse("word").expect_err("unknown mode should fail");
+    let msg = format!("{:?}", err);
+    assert!(msg.contains("'split' must be one of"));
+    assert!(msg.contains("word"));
+}
+
+#[test]
+fn none_mode_passes_chunks_through() {
+    assert_eq!(
+        segment(SplitMode::None, &["Hel", "lo. ", "Bye"]),
+        vec!["Hel", "lo. ", "Bye"]
+    );
+}
+
+#[test]
+fn line_mode_yields_complete_lines() {
+    let segments = segment(SplitMode::Line, &["first li", "ne\nsecond\n\nthi", "rd"]);
+
+    assert_eq!(segments, vec!["first line\n", "second\n", "\n", "third"]);
+}
+
+#[test]
+fn sentence_mode_holds_incomplete_tails() {
+    let mut segmenter = Segmenter::new(SplitMode::Sentence);
+
+    assert!(segmenter.push("Hello there").is_empty());
+    // A period at the end of the buffer may still be followed by more text.
+    assert!(segmenter.push(".").is_empty());
+    assert_eq!(segmenter.push(" How are"), vec!["Hello there. "]);
+    assert_eq!(segmenter.push(" you?\n"), vec!["How are you?\n"]);
+    assert_eq!(segmenter.finish(), None);
+}
+
+#[test]
+fn sentence_mode_handles_terminators_quotes_and_newlines() {
+    let segments = segment(
+        SplitMode::Sentence,
+        &["Really?! Yes. \"Stop.\" She left.\nNew line without stop\nEnd"],
+    );
+
+    assert_eq!(
+        segments,
+        vec![
+            "Really?! ",
+            "Yes. ",
+            "\"Stop.\" ",
+            "She left.\n",
+            "New line without stop\n",
+            "End",
+        ]
+    );
+}
+
+#[test]
+fn sentence_mode_skips_abbreviations_initials_and_numbers() {
+    let segments = segment(
+        SplitMode::Sentence,
+        &["Dr. Smith met J. R. Doe, e.g. at 3.5 p.m. on Main St. today. Steps:\n1. Go. Done."],
+    );
+
+    assert_eq!(
+        segments,
+        vec![
+            "Dr. Smith met J. R. Doe, e.g. at 3.5 p.m. on Main St. today. ",
+            "Steps:\n",
+            "1. Go. ",
+            "Done.",
+        ]
+    );
+}
+
+#[test]
+fn segments_reassemble_the_input_for_every_split_point() {
+    let text = "One. Two? Three!\nFour \u{1f600}. Mr. Five.";
+
+    for mode in [SplitMode::Line, SplitMode::Sentence] {
+        for (split, _) in text.char_indices() {
+            let segments = segment(mode, &[&text[..split], &text[split..]]);
+            assert_eq!(
+                segments.concat(),
+                text,
+                "{:?} split at byte {}",
+                mode,
+                split
+            );
+        }
+    }
+}
+
+/// Feed `chunks` through a stop scanner and return the emitted text, the
+/// pieces emitted per chunk, and whether a stop sequence matched.
+fn scan(stops: &[&str], chunks: &[&str]) -> (String, Vec<String>, bool) {
+    let stops = stops.iter().map(|stop| stop.to_string()).collect();
+    run_scanner(StopScanner::new(stops, None, false), chunks)
+}
+
+/// Like `scan`, with a `stop_regex` pattern instead of sequences.
+fn scan_regex(pattern: &str, include_match: bool, chunks: &[&str]) -> (String, Vec<String>, bool) {
+    let pattern = StopPattern::new(pattern).expect("pattern should compile");
+    run_scanner(
+        StopScanner::new(Vec::new(), Some(pattern), include_match),
+        chunks,
+    )
+}
+
+fn run_scanner(mut scanner: StopScanner, chunks: &[&str]) -> (String, Vec<String>, bool) {
+    let pieces: Vec<String> = chunks.iter().map(|chunk| scanner.push(chunk)).collect();
+    let stopped = scanner.stopped();
+    let mut text = pieces.concat();
+    text.extend(scanner.finish());
+    (text, pieces, stopped)
+}
+
+#[test]
+fn stop_scanner_truncates_at_a_sequence_split_across_chunks() {
+    let (text, pieces, stopped) = scan(&["END"], &["Hello E", "N", "D and more", "later"]);
+
+    assert!(stopped);
+    assert_eq!(text, "Hello ");
+    assert_eq!(pieces, vec!["Hello", " ", "", ""]);
+}
+
+#[test]
+fn stop_scanner_passes_text_through_without_a_match() {
+    let (text, pieces, stopped) = scan(&["###"], &["a#", "b##", "c"]);
+
+    assert!(!stopped);
+    assert_eq!(text, "a#b##c");
+    assert_eq!(pieces, vec!["", "a#b", "#"]);
+}
+
+#[test]
+fn stop_scanner_uses_the_earliest_match_and_keeps_characters_whole() {
+    let (text, _, stopped) = scan(
+        &["\u{1f600}\u{1f600}", "\u{f6}r"],
+        &["w\u{f6}", "rld \u{1f600}\u{1f600}"],
+    );
+
+    assert!(stopped);
+    assert_eq!(text, "w");
+}
+
+#[test]
+fn stop_scanner_with_single_character_stops_holds_nothing_back() {
+    let (text, pieces, stopped) = scan(&["\n"], &["one", " two\nthree"]);
+
+    assert!(stopped);
+    assert_eq!(text, "one two");
+    assert_eq!(pieces, vec!["one", " two"]);
+}
+
+#[test]
+fn stop_pattern_rejects_invalid_and_empty_matching_patterns() {
+    let err = StopPattern::new("(unclosed").expect_err("invalid pattern should fail");
+    assert!(format!("{:?}", err).contains("'stop_regex' is not a valid pattern"));
+
+    let err = StopPattern::new("x*").expect_err("empty match should fail");
+    assert!(format!("{:?}", err).contains("must not match an empty string"));
+}
+
+#[test]
+fn stop_regex_holds_back_a_match_completing_across_chunks() {
+    let (text, pieces, stopped) = scan_regex(r"\n```\s*\n", false, &["x = 1\n`", "``", "\nafter"]);
+
+    assert!(stopped);
+    assert_eq!(text, "x = 1");
+    assert_eq!(pieces, vec!["x = 1", "", ""]);
+}
+
+#[test]
+fn stop_regex_emits_text_that_cannot_start_a_match() {
+    let (text, pieces, stopped) = scan_regex(r"END\d", false, &["abc", " EN", "Dx", " done"]);
+
+    assert!(!stopped);
+    assert_eq!(text, "abc ENDx done");
+    assert_eq!(pieces, vec!["abc", " ", "ENDx", " done"]);
+}
+
+#[test]
+fn stop_regex_with_include_match_emits_the_match() {
+    let (text, _, stopped) = scan_regex(r"\}\s*$", true, &["{\"a\": ", "1}", " ignored"]);
+
+    assert!(stopped);
+    assert_eq!(text, "{\"a\": 1}");
+}
+
+#[test]
+fn stop_regex_word_boundaries_see_emitted_text() {
+    let (text, _, stopped) = scan_regex(r"\bstop\b", false, &["nonstop ", "then stop", " now"]);
+
+    assert!(stopped);
+    assert_eq!(text, "nonstop then ");
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::time::Duration;
+
+const HEAD: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n";
+
+const BODY: &[u8] = b"data: {\"choices\":[{\"delta\":{\"content\":\"a\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"b\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"c\"}}]}\n\n\
+data: [DONE]\n\n";
+
+fn tee<'py>(py: Python<'py>, body: &'static [u8], max_buffer: usize) -> Vec<Bound<'py, PyAny>> {
+    let base_url = common::serve_once(HEAD, Duration::ZERO, body);
+    let stream = common::provider(py, &base_url)
+        .call_method1("stream_text", ("Hello",))
+        .expect("stream should start");
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("max_buffer", max_buffer).unwrap();
+    let branches = stream
+        .call_method("tee", (2,), Some(&kwargs))
+        .expect("tee should succeed");
+
+    let err = stream
+        .call_method0("__next__")
+        .expect_err("the original stream is unusable after tee");
+    assert!(err.is_instance_of::<PyRuntimeError>(py));
+    assert!(err.to_string().contains("tee()"));
+
+    branches.try_iter().unwrap().map(Result::unwrap).collect()
+}
+
+fn next_chunk(branch: &Bound<'_, PyAny>) -> String {
+    branch
+        .call_method0("__next__")
+        .and_then(|chunk| chunk.extract())
+        .expect("branch should yield a chunk")
+}
+
+#[test]
+fn tee_feeds_every_branch_the_whole_stream() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let branches = tee(py, BODY, 1024);
+
+        for branch in &branches {
+            let text: String = branch.call_method0("text").unwrap().extract().unwrap();
+            assert_eq!(text, "abc");
+        }
+    });
+}
+
+#[test]
+fn tee_branches_interleave_with_a_small_buffer() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let branches = tee(py, BODY, 1);
+
+        for expected in ["a", "b", "c"] {
+            assert_eq!(next_chunk(&branches[0]), expected);
+            assert_eq!(next_chunk(&branches[1]), expected);
+        }
+    });
+}
+
+#[test]
+fn tee_cancelled_branch_stops_holding_the_others_back() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let branches = tee(py, BODY, 1);
+        assert_eq!(next_chunk(&branches[1]), "a");
+        branches[1].call_method0("cancel").unwrap();
+
+        let text: String = branches[0].call_method0("text").unwrap().extract().unwrap();
+        assert_eq!(text, "abc");
+        assert!(branches[1].call_method0("__next__").is_err());
+    });
+}
+
+#[test]
+fn tee_raises_stream_errors_in_every_branch() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let branches = tee(
+            py,
+            b"data: {\"choices\":[{\"delta\":{\"content\":\"a\"}}]}\n\n\
+data: {\"error\":{\"message\":\"Upstream overloaded\"}}\n\n",
+            1024,
+        );
+
+        for branch in &branches {
+            assert_eq!(next_chunk(branch), "a");
+            for _ in 0..2 {
+                let err = branch
+                    .call_method0("__next__")
+                    .expect_err("stream error should reach every branch");
+                assert!(err.is_instance_of::<PyRuntimeError>(py));
+                assert!(err.to_string().contains("Upstream overloaded"));
+            }
+            assert!(!branch.getattr("error").unwrap().is_none());
+        }
+    });
+}
+
+#[test]
+fn tee_rejects_zero_branches_and_consumed_streams() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let base_url = common::serve_once(HEAD, Duration::ZERO, BODY);
+        let stream = common::provider(py, &base_url)
+            .call_method1("stream_text", ("Hello",))
+            .expect("stream should start");
+
+        let err = stream
+            .call_method1("tee", (0,))
+            .expect_err("n=0 should fail");
+        assert!(err.is_instance_of::<PyValueError>(py));
+
+        stream.call_method0("text").unwrap();
+        let err = stream
+            .call_method0("tee")
+            .expect_err("a drained stream cannot be split");
+        assert!(err.to_string().contains("already been consumed"));
+    });
+}
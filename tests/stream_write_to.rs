@@ -0,0 +1,112 @@
+mod common;
+
+use pyo3::exceptions::{PyOSError, PyRuntimeError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::ffi::CString;
+use std::time::Duration;
+
+const HEAD: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n";
+
+const BODY: &[u8] = "data: {\"choices\":[{\"delta\":{\"content\":\"H\u{e9}\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\"llo\"}}]}\n\n\
+data: {\"choices\":[{\"delta\":{\"content\":\" world\"}}]}\n\n\
+data: [DONE]\n\n"
+    .as_bytes();
+
+/// A writer that records each write and counts flushes, optionally failing
+/// on the second write.
+const WRITER: &str = r#"
+class Writer:
+    def __init__(self, fail=False):
+        self.parts = []
+        self.flushes = 0
+        self.fail = fail
+
+    def write(self, text):
+        if self.fail and self.parts:
+            raise OSError("disk full")
+        self.parts.append(text)
+
+    def flush(self):
+        self.flushes += 1
+"#;
+
+fn writer<'py>(py: Python<'py>, fail: bool) -> Bound<'py, PyAny> {
+    let code = CString::new(WRITER).unwrap();
+    let globals = PyDict::new(py);
+    py.run(&code, Some(&globals), None).unwrap();
+    globals
+        .get_item("Writer")
+        .unwrap()
+        .unwrap()
+        .call1((fail,))
+        .unwrap()
+}
+
+fn stream<'py>(py: Python<'py>) -> Bound<'py, PyAny> {
+    let base_url = common::serve_once(HEAD, Duration::ZERO, BODY);
+    common::provider(py, &base_url)
+        .call_method1("stream_text", ("Hello",))
+        .expect("stream should start")
+}
+
+#[test]
+fn write_to_writes_each_chunk_and_returns_char_count() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = stream(py);
+        let writer = writer(py, false);
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("flush_every", 2).unwrap();
+
+        let written: usize = stream
+            .call_method("write_to", (&writer,), Some(&kwargs))
+            .and_then(|count| count.extract())
+            .expect("write_to should succeed");
+
+        assert_eq!(written, "H\u{e9}llo world".chars().count());
+        let parts: Vec<String> = writer.getattr("parts").unwrap().extract().unwrap();
+        assert_eq!(parts, vec!["H\u{e9}", "llo", " world"]);
+        // Once after the second chunk, once at the end.
+        let flushes: u32 = writer.getattr("flushes").unwrap().extract().unwrap();
+        assert_eq!(flushes, 2);
+    });
+}
+
+#[test]
+fn write_to_cancels_the_stream_when_the_writer_fails() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = stream(py);
+
+        let err = stream
+            .call_method1("write_to", (writer(py, true),))
+            .expect_err("writer error should propagate");
+        assert!(err.is_instance_of::<PyOSError>(py));
+        assert!(err.to_string().contains("disk full"));
+
+        // The stream was cancelled, so iteration ends.
+        assert!(stream.call_method0("__next__").is_err());
+        assert!(stream.getattr("error").unwrap().is_none());
+    });
+}
+
+#[test]
+fn write_to_refuses_a_consumed_stream() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = stream(py);
+        stream.call_method0("text").expect("text() should drain");
+
+        let err = stream
+            .call_method1("write_to", (writer(py, false),))
+            .expect_err("consumed stream should be refused");
+        assert!(err.is_instance_of::<PyRuntimeError>(py));
+        assert!(err.to_string().contains("already been consumed"));
+    });
+}
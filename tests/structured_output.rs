@@ -0,0 +1,189 @@
+use rusty_agent_sdk::internal::{
+    GenerationParams, build_json_schema_format, parse_json_output, parse_partial_json, schema_name,
+    validate_response_format,
+};
+use serde_json::json;
+
+fn person_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "object",
+        "properties": {"name": {"type": "string"}},
+        "required": ["name"],
+        "additionalProperties": false
+    })
+}
+
+#[test]
+fn build_json_schema_format_wraps_schema_in_envelope() {
+    let format = build_json_schema_format("person", person_schema(), true)
+        .expect("valid schema should build");
+
+    assert_eq!(
+        format,
+        serde_json::json!({
+            "type": "json_schema",
+            "json_schema": {"name": "person", "schema": person_schema(), "strict": true},
+        })
+    );
+    validate_response_format(&format).expect("built format should validate");
+}
+
+#[test]
+fn build_json_schema_format_rejects_bad_name_and_schema() {
+    let err = build_json_schema_format("has space", person_schema(), true)
+        .expect_err("invalid name should fail");
+    assert!(format!("{:?}", err).contains("'name'"));
+
+    let err = build_json_schema_format("person", serde_json::json!({"properties": {}}), true)
+        .expect_err("schema without type should fail");
+    assert!(format!("{:?}", err).contains("'type' key"));
+
+    let err = build_json_schema_format("person", serde_json::json!("object"), false)
+        .expect_err("non-object schema should fail");
+    assert!(format!("{:?}", err).contains("'schema'"));
+}
+
+#[test]
+fn validate_response_format_accepts_known_types() {
+    for format in [
+        serde_json::json!({"type": "text"}),
+        serde_json::json!({"type": "json_object"}),
+    ] {
+        validate_response_format(&format).expect("known type should be accepted");
+    }
+}
+
+#[test]
+fn validate_response_format_rejects_unknown_or_malformed_formats() {
+    let err = validate_response_format(&serde_json::json!({"type": "xml"}))
+        .expect_err("unknown type should fail");
+    assert!(format!("{:?}", err).contains("'xml'"));
+
+    let err = validate_response_format(&serde_json::json!({"json_schema": {}}))
+        .expect_err("missing type should fail");
+    assert!(format!("{:?}", err).contains("string 'type'"));
+
+    let err = validate_response_format(&serde_json::json!({"type": "json_schema"}))
+        .expect_err("json_schema without envelope should fail");
+    assert!(format!("{:?}", err).contains("missing the 'json_schema' dict"));
+}
+
+#[test]
+fn generation_params_validate_checks_response_format() {
+    let params = GenerationParams {
+        response_format: Some(serde_json::json!({"type": "yaml"})),
+        ..Default::default()
+    };
+
+    let err = params.validate().expect_err("unknown format should fail");
+    assert!(format!("{:?}", err).contains("response_format"));
+}
+
+#[test]
+fn parse_json_output_accepts_objects_arrays_and_fenced_json() {
+    assert_eq!(
+        parse_json_output(r#" {"name": "Ada"} "#).expect("object should parse"),
+        serde_json::json!({"name": "Ada"})
+    );
+    assert_eq!(
+        parse_json_output("[1, 2]").expect("array should parse"),
+        serde_json::json!([1, 2])
+    );
+    assert_eq!(
+        parse_json_output("```json\n{\"ok\": true}\n```").expect("fenced json should parse"),
+        serde_json::json!({"ok": true})
+    );
+}
+
+#[test]
+fn parse_json_output_error_includes_raw_text() {
+    let err = parse_json_output("Sure! Here you go: {name: Ada}").expect_err("should fail");
+    let msg = format!("{:?}", err);
+
+    assert!(msg.contains("invalid JSON"));
+    assert!(msg.contains("Sure! Here you go: {name: Ada}"));
+}
+
+#[test]
+fn schema_name_sanitises_class_names() {
+    assert_eq!(schema_name("Person"), "Person");
+    assert_eq!(schema_name("Page[Item]"), "Page_Item_");
+    assert_eq!(schema_name(&"A".repeat(80)).len(), 64);
+    assert_eq!(schema_name(""), "response");
+
+    build_json_schema_format(
+        &schema_name("Page[Item]"),
+        serde_json::json!({"type": "object"}),
+        false,
+    )
+    .expect("sanitised name should be accepted");
+}
+
+#[test]
+fn parse_partial_json_closes_unterminated_values() {
+    assert_eq!(parse_partial_json(""), None);
+    assert_eq!(parse_partial_json("Sure, here"), None);
+    assert_eq!(parse_partial_json("{"), Some(json!({})));
+    assert_eq!(
+        parse_partial_json("```json\n{\"name\": \"Al"),
+        Some(json!({"name": "Al"}))
+    );
+    assert_eq!(
+        parse_partial_json(r#"{"tags": ["a", "b"#),
+        Some(json!({"tags": ["a", "b"]}))
+    );
+    assert_eq!(
+        parse_partial_json(r#"{"a": {"b": [1, 2,"#),
+        Some(json!({"a": {"b": [1, 2]}}))
+    );
+    assert_eq!(
+        parse_partial_json(r#"[{"x": 1}, {"#),
+        Some(json!([{"x": 1}, {}]))
+    );
+}
+
+#[test]
+fn parse_partial_json_drops_members_that_cannot_be_completed() {
+    assert_eq!(parse_partial_json(r#"{"a": 1, "b"#), Some(json!({"a": 1})));
+    assert_eq!(
+        parse_partial_json(r#"{"a": 1, "b":"#),
+        Some(json!({"a": 1}))
+    );
+    assert_eq!(
+        parse_partial_json(r#"{"a": 1, "b": tr"#),
+        Some(json!({"a": 1}))
+    );
+    assert_eq!(parse_partial_json(r#"{"a": "x\"#), Some(json!({"a": "x"})));
+    assert_eq!(
+        parse_partial_json(r#"{"a": "x\u00"#),
+        Some(json!({"a": "x"}))
+    );
+}
+
+#[test]
+fn parse_partial_json_handles_escapes_and_complete_values() {
+    assert_eq!(
+        parse_partial_json(r#"{"q": "say \"hi\", {ok}"#),
+        Some(json!({"q": "say \"hi\", {ok}"}))
+    );
+    assert_eq!(
+        parse_partial_json("{\"done\": true}\n```"),
+        Some(json!({"done": true}))
+    );
+}
+
+#[test]
+fn parse_partial_json_is_stable_for_every_prefix() {
+    let text = r#"{"name": "Ada", "langs": ["en", "fr"], "age": 36, "meta": {"ok": true}}"#;
+    let full: serde_json::Value = serde_json::from_str(text).unwrap();
+
+    for (end, _) in text.char_indices().skip(1) {
+        let partial = parse_partial_json(&text[..end]);
+        assert!(
+            partial.as_ref().is_some_and(serde_json::Value::is_object),
+            "prefix {:?} should parse to an object",
+            &text[..end]
+        );
+    }
+    assert_eq!(parse_partial_json(text), Some(full));
+}
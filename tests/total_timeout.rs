@@ -0,0 +1,127 @@
+mod common;
+
+use pyo3::exceptions::PyTimeoutError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rusty_agent_sdk::internal::{Deadline, attempt_timeout};
+use std::time::{Duration, Instant};
+
+const STALLED_HEAD: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n";
+
+const GENERATE_BODY: &[u8] =
+    b"{\"choices\":[{\"message\":{\"role\":\"assistant\",\"content\":\"late\"}}]}";
+
+fn kwargs<'py>(py: Python<'py>, total_timeout: f64) -> Bound<'py, PyDict> {
+    let kwargs = PyDict::new(py);
+    kwargs.set_item("total_timeout", total_timeout).unwrap();
+    kwargs
+}
+
+#[test]
+fn attempt_timeout_shrinks_to_the_remaining_budget() {
+    let request_timeout = Duration::from_secs(60);
+
+    assert_eq!(
+        attempt_timeout(request_timeout, None, 3),
+        Ok(request_timeout)
+    );
+
+    let deadline = Deadline::start(Some(Duration::from_secs(2)));
+    let timeout = attempt_timeout(request_timeout, deadline.as_ref(), 0).unwrap();
+    assert!(timeout <= Duration::from_secs(2));
+    assert!(timeout > Duration::from_secs(1));
+
+    let deadline = Deadline::start(Some(Duration::from_nanos(1)));
+    std::thread::sleep(Duration::from_millis(1));
+    let error = attempt_timeout(request_timeout, deadline.as_ref(), 2).unwrap_err();
+    assert!(
+        format!("{:?}", error).contains("after 2 attempt(s)"),
+        "{:?}",
+        error
+    );
+}
+
+#[test]
+fn provider_total_timeout_cuts_a_stalled_response_short() {
+    let base_url = common::serve_once(STALLED_HEAD, Duration::from_secs(5), GENERATE_BODY);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider_with(py, &base_url, |kwargs| {
+            kwargs.set_item("total_timeout", 0.3)
+        });
+        let total_timeout: Option<f64> = provider
+            .getattr("total_timeout")
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(total_timeout, Some(0.3));
+
+        let started = Instant::now();
+        let err = provider
+            .call_method1("generate_text", ("Hello",))
+            .expect_err("the stalled response should time out");
+        assert!(err.is_instance_of::<PyTimeoutError>(py), "{}", err);
+        let message = err.to_string();
+        assert!(message.contains("total_timeout of 0.3s"), "{}", message);
+        assert!(message.contains("after 1 attempt(s)"), "{}", message);
+        assert!(started.elapsed() < Duration::from_secs(3));
+    });
+}
+
+#[test]
+fn per_call_total_timeout_overrides_the_provider() {
+    let base_url = common::serve_once(STALLED_HEAD, Duration::from_secs(5), GENERATE_BODY);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider = common::provider(py, &base_url);
+        assert!(provider.getattr("total_timeout").unwrap().is_none());
+
+        let started = Instant::now();
+        let err = provider
+            .call_method("generate_text", ("Hello",), Some(&kwargs(py, 0.3)))
+            .expect_err("the stalled response should time out");
+        assert!(err.is_instance_of::<PyTimeoutError>(py), "{}", err);
+        assert!(started.elapsed() < Duration::from_secs(3));
+    });
+}
+
+#[test]
+fn total_timeout_bounds_waits_the_server_asks_for() {
+    const UNAVAILABLE: &[u8] =
+        b"HTTP/1.1 503 Service Unavailable\r\nRetry-After: 10\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    let (base_url, _heads) = common::serve_sequence(vec![UNAVAILABLE; 3]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let started = Instant::now();
+        let err = common::provider(py, &base_url)
+            .call_method("generate_text", ("Hello",), Some(&kwargs(py, 0.5)))
+            .expect_err("the deadline should pass before the retry");
+        assert!(err.is_instance_of::<PyTimeoutError>(py), "{}", err);
+        assert!(err.to_string().contains("after 1 attempt(s)"), "{}", err);
+        assert!(started.elapsed() < Duration::from_secs(3));
+    });
+}
+
+#[test]
+fn stream_total_timeout_raises_timeout_error() {
+    let base_url = common::serve_once(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n",
+        Duration::from_secs(5),
+        b"data: [DONE]\n\n",
+    );
+    Python::initialize();
+
+    Python::attach(|py| {
+        let stream = common::provider(py, &base_url)
+            .call_method("stream_text", ("Hello",), Some(&kwargs(py, 0.3)))
+            .expect("stream should start");
+        let err = stream
+            .call_method0("text")
+            .expect_err("the stalled stream should time out");
+        assert!(err.is_instance_of::<PyTimeoutError>(py), "{}", err);
+    });
+}
@@ -0,0 +1,189 @@
+mod common;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use rusty_agent_sdk::UsageTracker;
+use rusty_agent_sdk::internal::{CompletionTokensDetails, Usage, UsageTotals};
+
+const MINI: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"model\":\"mini\",\"choices\":[{\"message\":{\"content\":\"ok\"}}],\
+\"usage\":{\"prompt_tokens\":10,\"completion_tokens\":5,\"cost\":0.25}}";
+
+const LARGE: &[u8] =
+    b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"model\":\"large\",\"choices\":[{\"message\":{\"content\":\"ok\"}}],\
+\"usage\":{\"prompt_tokens\":20,\"completion_tokens\":1}}";
+
+const STREAM: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n\
+data: {\"model\":\"mini\",\"choices\":[{\"delta\":{\"content\":\"ok\"},\"finish_reason\":\"stop\"}]}\n\n\
+data: {\"choices\":[],\"usage\":{\"prompt_tokens\":3,\"completion_tokens\":2}}\n\n\
+data: [DONE]\n\n";
+
+const FAILED: &[u8] =
+    b"HTTP/1.1 400 Bad Request\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n\
+{\"error\":{\"message\":\"bad\"}}";
+
+fn usage(prompt: u64, completion: u64, cost: Option<f64>) -> Usage {
+    Usage {
+        prompt_tokens: Some(prompt),
+        completion_tokens: Some(completion),
+        total_tokens: Some(prompt + completion),
+        completion_tokens_details: Some(CompletionTokensDetails {
+            reasoning_tokens: Some(1),
+            ..Default::default()
+        }),
+        cost,
+        ..Default::default()
+    }
+}
+
+fn get<'py, T: for<'a> FromPyObject<'a, 'py>>(object: &Bound<'py, PyAny>, name: &str) -> T {
+    object
+        .getattr(name)
+        .unwrap()
+        .extract()
+        .unwrap_or_else(|_| panic!("{} has the wrong type", name))
+}
+
+#[test]
+fn calls_are_summed_by_model() {
+    let tracker = UsageTracker::default();
+    tracker.record("mini", Some(&usage(10, 5, None)));
+    tracker.record("mini", Some(&usage(1, 1, Some(0.5))));
+    tracker.record("large", None);
+
+    let models = tracker.model_totals();
+    assert_eq!(
+        models["mini"],
+        UsageTotals {
+            calls: 2,
+            prompt_tokens: 11,
+            completion_tokens: 6,
+            total_tokens: 17,
+            cached_prompt_tokens: 0,
+            reasoning_tokens: 2,
+            cost_usd: Some(0.5),
+        }
+    );
+    assert_eq!(
+        models["large"],
+        UsageTotals {
+            calls: 1,
+            ..Default::default()
+        },
+        "a call without usage is still counted"
+    );
+
+    let totals = tracker.totals();
+    assert_eq!((totals.calls, totals.total_tokens), (3, 17));
+    assert_eq!(totals.cost_usd, Some(0.5));
+}
+
+#[test]
+fn a_tracked_provider_counts_generations_and_streams() {
+    let (base_url, heads) = common::serve_sequence(vec![MINI, LARGE, STREAM, FAILED]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let tracker = py.get_type::<UsageTracker>().call0().unwrap();
+        let provider = common::provider(py, &base_url);
+        provider.call_method1("track_usage", (&tracker,)).unwrap();
+
+        provider.call_method1("generate_text", ("Hello",)).unwrap();
+        let copy = provider.call_method1("with_model", ("large",)).unwrap();
+        copy.call_method1("generate_text", ("Hello",)).unwrap();
+        let stream = provider.call_method1("stream_text", ("Hello",)).unwrap();
+        stream.call_method0("text").unwrap();
+        provider
+            .call_method1("generate_text", ("Hello",))
+            .expect_err("the last response is an error");
+
+        assert_eq!(
+            get::<u64>(&tracker, "calls"),
+            3,
+            "failed calls are left out"
+        );
+        assert_eq!(get::<u64>(&tracker, "prompt_tokens"), 33);
+        assert_eq!(get::<u64>(&tracker, "completion_tokens"), 8);
+        assert_eq!(get::<u64>(&tracker, "total_tokens"), 41);
+        assert_eq!(get::<f64>(&tracker, "cost_usd"), 0.25);
+
+        let models = tracker.call_method0("by_model").unwrap();
+        let mini = models.get_item("mini").unwrap();
+        assert_eq!(mini.get_item("calls").unwrap().extract::<u64>().unwrap(), 2);
+        let large = models.get_item("large").unwrap();
+        assert!(large.get_item("cost_usd").unwrap().is_none());
+    });
+    let requests: Vec<String> = heads.try_iter().collect();
+    assert!(
+        requests[2].contains("\"include_usage\":true"),
+        "a tracked stream asks for usage: {}",
+        requests[2]
+    );
+}
+
+#[test]
+fn a_call_can_use_its_own_tracker() {
+    let (base_url, _heads) = common::serve_sequence(vec![MINI, MINI]);
+    Python::initialize();
+
+    Python::attach(|py| {
+        let provider_tracker = py.get_type::<UsageTracker>().call0().unwrap();
+        let call_tracker = py.get_type::<UsageTracker>().call0().unwrap();
+        let provider = common::provider(py, &base_url);
+        provider
+            .call_method1("track_usage", (&provider_tracker,))
+            .unwrap();
+
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("usage_tracker", &call_tracker).unwrap();
+        provider
+            .call_method("generate_text", ("Hello",), Some(&kwargs))
+            .unwrap();
+        assert_eq!(get::<u64>(&call_tracker, "calls"), 1);
+        assert_eq!(get::<u64>(&provider_tracker, "calls"), 0);
+
+        provider.call_method1("track_usage", (py.None(),)).unwrap();
+        assert!(provider.getattr("usage_tracker").unwrap().is_none());
+        provider.call_method1("generate_text", ("Hello",)).unwrap();
+        assert_eq!(
+            get::<u64>(&provider_tracker, "calls"),
+            0,
+            "the tracker was removed"
+        );
+    });
+}
+
+#[test]
+fn to_dict_exports_totals_and_models_until_reset() {
+    let tracker = UsageTracker::default();
+    tracker.record("mini", Some(&usage(10, 5, Some(0.25))));
+    Python::initialize();
+
+    Python::attach(|py| {
+        let tracker = Bound::new(py, tracker).unwrap().into_any();
+        let dict = tracker.call_method0("to_dict").unwrap();
+        assert_eq!(
+            dict.get_item("total_tokens")
+                .unwrap()
+                .extract::<u64>()
+                .unwrap(),
+            15
+        );
+        let mini = dict.get_item("models").unwrap().get_item("mini").unwrap();
+        assert_eq!(
+            mini.get_item("reasoning_tokens")
+                .unwrap()
+                .extract::<u64>()
+                .unwrap(),
+            1
+        );
+
+        tracker.call_method0("reset").unwrap();
+        assert_eq!(get::<u64>(&tracker, "calls"), 0);
+        assert!(tracker.getattr("cost_usd").unwrap().is_none());
+        let models = tracker.call_method0("by_model").unwrap();
+        assert_eq!(models.len().unwrap(), 0);
+    });
+}
@@ -0,0 +1,181 @@
+use pyo3::prelude::*;
+use pyo3::types::PyList;
+use rusty_agent_sdk::internal::{Matrix, Metric, cosine, l2_normalize};
+
+fn norm(vector: &[f64]) -> f64 {
+    vector.iter().map(|x| x * x).sum::<f64>().sqrt()
+}
+
+#[test]
+fn vectors_are_scaled_to_unit_length() {
+    let mut vector = vec![3.0, 4.0];
+    assert!(l2_normalize(&mut vector));
+    assert_eq!(vector, [0.6, 0.8]);
+
+    let mut vector: Vec<f64> = (1..=1536).map(|i| (i as f64).sin() * 7.0).collect();
+    l2_normalize(&mut vector);
+    assert!((norm(&vector) - 1.0).abs() < 1e-12, "{}", norm(&vector));
+}
+
+#[test]
+fn vectors_without_a_direction_are_left_alone() {
+    for vector in [
+        vec![0.0, 0.0],
+        vec![1.0, f64::NAN],
+        vec![f64::INFINITY, 1.0],
+    ] {
+        let mut normalized = vector.clone();
+        assert!(!l2_normalize(&mut normalized), "{:?}", vector);
+        assert_eq!(format!("{:?}", normalized), format!("{:?}", vector));
+    }
+}
+
+#[test]
+fn normalize_is_exposed_to_python() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let normalize = wrap_pyfunction!(rusty_agent_sdk::normalize, py).unwrap();
+        let vectors = PyList::new(py, [vec![3.0, 4.0], vec![0.0, 0.0]]).unwrap();
+        let normalized: Vec<Vec<f64>> = normalize.call1((vectors,)).unwrap().extract().unwrap();
+        assert_eq!(normalized, [[0.6, 0.8], [0.0, 0.0]]);
+
+        let err = normalize
+            .call1(("not vectors",))
+            .expect_err("a string is not a list of vectors");
+        assert!(err.is_instance_of::<pyo3::exceptions::PyTypeError>(py));
+    });
+}
+
+fn matrix() -> Matrix {
+    Matrix::from_rows(&[
+        vec![1.0, 0.0],
+        vec![0.0, 1.0],
+        vec![2.0, 2.0],
+        vec![-1.0, 0.0],
+    ])
+    .unwrap()
+}
+
+#[test]
+fn cosine_compares_directions() {
+    assert!((cosine(&[1.0, 0.0], &[5.0, 0.0]) - 1.0).abs() < 1e-12);
+    assert!((cosine(&[1.0, 1.0], &[-1.0, -1.0]) + 1.0).abs() < 1e-12);
+    assert_eq!(cosine(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+    assert_eq!(
+        cosine(&[0.0, 0.0], &[1.0, 1.0]),
+        0.0,
+        "zero vectors score 0"
+    );
+}
+
+#[test]
+fn search_ranks_rows_by_each_metric() {
+    let matrix = matrix();
+    let query = [1.0, 0.1];
+
+    let cosine: Vec<usize> = matrix
+        .search(&query, 3, Metric::Cosine)
+        .unwrap()
+        .into_iter()
+        .map(|(index, _)| index)
+        .collect();
+    assert_eq!(cosine, [0, 2, 1]);
+
+    let dot = matrix.search(&query, 1, Metric::Dot).unwrap();
+    assert_eq!(dot[0].0, 2, "dot favours the longer row");
+    assert!((dot[0].1 - 2.2).abs() < 1e-12);
+
+    let euclidean = matrix.search(&query, 4, Metric::Euclidean).unwrap();
+    let order: Vec<usize> = euclidean.iter().map(|(index, _)| *index).collect();
+    assert_eq!(order, [0, 1, 3, 2], "nearest first");
+    assert!(euclidean.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+}
+
+#[test]
+fn search_returns_at_most_the_rows_there_are() {
+    let matrix = matrix();
+    assert_eq!(
+        matrix
+            .search(&[1.0, 0.0], 10, Metric::Cosine)
+            .unwrap()
+            .len(),
+        4
+    );
+    assert!(
+        matrix
+            .search(&[1.0, 0.0], 0, Metric::Cosine)
+            .unwrap()
+            .is_empty()
+    );
+    let empty = Matrix::from_rows(&[]).unwrap();
+    assert!(empty.search(&[1.0], 5, Metric::Cosine).unwrap().is_empty());
+}
+
+#[test]
+fn mismatched_dimensions_are_rejected() {
+    let err = Matrix::from_rows(&[vec![1.0, 0.0], vec![1.0]]).unwrap_err();
+    assert!(format!("{:?}", err).contains("row 1 has 1"), "{:?}", err);
+
+    let err = matrix()
+        .search(&[1.0, 0.0, 0.0], 5, Metric::Cosine)
+        .unwrap_err();
+    assert!(
+        format!("{:?}", err).contains("'query' has 3 dimensions"),
+        "{:?}",
+        err
+    );
+
+    let err = Metric::parse("manhattan").unwrap_err();
+    assert!(
+        format!("{:?}", err).contains("cosine, dot, euclidean"),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+fn similarity_helpers_are_exposed_to_python() {
+    Python::initialize();
+
+    Python::attach(|py| {
+        let search = wrap_pyfunction!(rusty_agent_sdk::similarity_search, py).unwrap();
+        let rows = vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![0.7, 0.7]];
+        let kwargs = pyo3::types::PyDict::new(py);
+        kwargs.set_item("top_k", 2).unwrap();
+        let hits: Vec<(usize, f64)> = search
+            .call(((0.0, 1.0), rows.clone()), Some(&kwargs))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(hits.iter().map(|hit| hit.0).collect::<Vec<_>>(), [1, 2]);
+        assert!((hits[0].1 - 1.0).abs() < 1e-12);
+
+        let err = search
+            .call1(((0.0, 1.0, 2.0), rows.clone()))
+            .expect_err("the query is too long");
+        assert!(err.is_instance_of::<pyo3::exceptions::PyValueError>(py));
+
+        let many = wrap_pyfunction!(rusty_agent_sdk::similarity_search_many, py).unwrap();
+        let queries = vec![vec![1.0, 0.0], vec![0.0, 1.0]];
+        let results: Vec<Vec<(usize, f64)>> = many
+            .call((queries, rows), Some(&kwargs))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!((results[0][0].0, results[1][0].0), (0, 1));
+
+        let similarity = wrap_pyfunction!(rusty_agent_sdk::cosine_similarity, py).unwrap();
+        let score: f64 = similarity
+            .call1((vec![1.0, 0.0], vec![0.0, 2.0]))
+            .unwrap()
+            .extract()
+            .unwrap();
+        assert_eq!(score, 0.0);
+        let err = similarity
+            .call1((vec![1.0], vec![1.0, 2.0]))
+            .expect_err("lengths differ");
+        assert!(err.is_instance_of::<pyo3::exceptions::PyValueError>(py));
+    });
+}